@@ -0,0 +1,49 @@
+#!/usr/bin/env bash
+# Runs the solana CLI commands people use against a mirror blockchain, failing on the
+# first one that doesn't work.
+#
+#   scripts/solana-cli.sh http://localhost:8899/rpc/<blockchain id>
+#
+# Needs the solana CLI (2.x) on the path. Deploys go over RPC with --use-rpc, since the
+# engine has no TPU to send transactions to.
+set -euo pipefail
+
+URL=${1:?Usage: scripts/solana-cli.sh <rpc url>}
+ROOT=$(cd "$(dirname "$0")/.." && pwd)
+PROGRAM_SO="$ROOT/src/engine/spl/programs/spl_memo-3.0.0.so"
+
+WORK=$(mktemp -d)
+trap 'rm -rf "$WORK"' EXIT
+
+solana() {
+    command solana --url "$URL" --commitment confirmed --keypair "$WORK/payer.json" "$@"
+}
+
+step() {
+    echo "--- $*"
+}
+
+solana-keygen new --no-bip39-passphrase --silent --outfile "$WORK/payer.json"
+solana-keygen new --no-bip39-passphrase --silent --outfile "$WORK/recipient.json"
+solana-keygen new --no-bip39-passphrase --silent --outfile "$WORK/program.json"
+PAYER=$(solana-keygen pubkey "$WORK/payer.json")
+RECIPIENT=$(solana-keygen pubkey "$WORK/recipient.json")
+PROGRAM=$(solana-keygen pubkey "$WORK/program.json")
+
+step "solana airdrop"
+solana airdrop 10 "$PAYER"
+[ "$(solana balance "$PAYER" --lamports)" = "10000000000 lamports" ]
+
+step "solana transfer"
+solana transfer --allow-unfunded-recipient "$RECIPIENT" 1
+[ "$(solana balance "$RECIPIENT" --lamports)" = "1000000000 lamports" ]
+
+step "solana program deploy"
+solana program deploy --use-rpc --program-id "$WORK/program.json" "$PROGRAM_SO"
+solana program show "$PROGRAM"
+
+step "solana account"
+solana account "$PROGRAM" --output json | grep -q '"executable": true'
+solana account "$RECIPIENT"
+
+echo "All solana CLI commands succeeded"
@@ -0,0 +1,275 @@
+use actix_web::rt::{self, time};
+use base64::prelude::*;
+use hmac::{Hmac, Mac, NewMac};
+use ring::signature::{
+    RsaPublicKeyComponents, UnparsedPublicKey, ECDSA_P256_SHA256_FIXED, RSA_PKCS1_2048_8192_SHA256,
+};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use sha2::Sha256;
+use std::{
+    str::FromStr,
+    sync::{OnceLock, RwLock},
+    time::{SystemTime, UNIX_EPOCH},
+};
+use uuid::Uuid;
+
+use super::Role;
+use crate::settings::{self, JwtSettings};
+
+static JWKS: OnceLock<RwLock<JwkSet>> = OnceLock::new();
+
+/// Public key published in a JWKS.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Jwk {
+    Rsa { n: Vec<u8>, e: Vec<u8> },
+    // Uncompressed P-256 point
+    Ec { point: Vec<u8> },
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct JwkSet {
+    keys: Vec<(Option<String>, Jwk)>,
+}
+
+#[derive(Deserialize)]
+struct RawJwks {
+    keys: Vec<RawJwk>,
+}
+
+#[derive(Deserialize)]
+struct RawJwk {
+    kty: String,
+    kid: Option<String>,
+    crv: Option<String>,
+    n: Option<String>,
+    e: Option<String>,
+    x: Option<String>,
+    y: Option<String>,
+}
+
+impl JwkSet {
+    /// Parses a JWKS document, keys of other types or curves are skipped.
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        let raw: RawJwks = serde_json::from_str(json).map_err(|e| e.to_string())?;
+        let decode = |field: &Option<String>| {
+            field
+                .as_ref()
+                .and_then(|value| BASE64_URL_SAFE_NO_PAD.decode(value).ok())
+        };
+        let keys = raw
+            .keys
+            .iter()
+            .filter_map(|key| {
+                let jwk = match (key.kty.as_str(), key.crv.as_deref()) {
+                    ("RSA", _) => Jwk::Rsa {
+                        n: decode(&key.n)?,
+                        e: decode(&key.e)?,
+                    },
+                    ("EC", Some("P-256")) => {
+                        let mut point = vec![0x04];
+                        point.extend(decode(&key.x)?);
+                        point.extend(decode(&key.y)?);
+                        Jwk::Ec { point }
+                    }
+                    _ => return None,
+                };
+                Some((key.kid.clone(), jwk))
+            })
+            .collect();
+        Ok(JwkSet { keys })
+    }
+
+    /// The key named by `kid`, or the only key of the right type when the token has no kid.
+    fn find(&self, kid: Option<&str>, alg: &str) -> Option<&Jwk> {
+        let mut candidates = self.keys.iter().filter(|(_, jwk)| {
+            matches!(
+                (alg, jwk),
+                ("RS256", Jwk::Rsa { .. }) | ("ES256", Jwk::Ec { .. })
+            )
+        });
+        match kid {
+            Some(kid) => candidates
+                .find(|(key_id, _)| key_id.as_deref() == Some(kid))
+                .map(|(_, jwk)| jwk),
+            None => match (candidates.next(), candidates.next()) {
+                (Some((_, jwk)), None) => Some(jwk),
+                _ => None,
+            },
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct Header {
+    alg: String,
+    kid: Option<String>,
+}
+
+fn decode_segment(segment: &str) -> Result<Vec<u8>, String> {
+    BASE64_URL_SAFE_NO_PAD
+        .decode(segment)
+        .map_err(|_| "Malformed token".to_string())
+}
+
+/// Checks the signature, issuer, audience and lifetime of a token, returns its claims.
+pub fn verify(
+    token: &str,
+    settings: &JwtSettings,
+    jwks: &JwkSet,
+    now: u64,
+) -> Result<Map<String, Value>, String> {
+    let mut segments = token.split('.');
+    let (header, payload, signature) = match (
+        segments.next(),
+        segments.next(),
+        segments.next(),
+        segments.next(),
+    ) {
+        (Some(header), Some(payload), Some(signature), None) => (header, payload, signature),
+        _ => return Err("Malformed token".to_string()),
+    };
+    let signed = &token[..header.len() + 1 + payload.len()];
+    let header: Header =
+        serde_json::from_slice(&decode_segment(header)?).map_err(|_| "Malformed token")?;
+    let signature = decode_segment(signature)?;
+
+    // The algorithm comes from the configuration, never only from the token
+    let valid = match (&settings.secret, header.alg.as_str()) {
+        (Some(secret), "HS256") => {
+            let mut mac =
+                Hmac::<Sha256>::new_varkey(secret.as_bytes()).expect("HMAC accepts any key");
+            mac.update(signed.as_bytes());
+            mac.verify(&signature).is_ok()
+        }
+        (None, alg @ ("RS256" | "ES256")) => match jwks.find(header.kid.as_deref(), alg) {
+            Some(Jwk::Rsa { n, e }) => RsaPublicKeyComponents { n, e }
+                .verify(&RSA_PKCS1_2048_8192_SHA256, signed.as_bytes(), &signature)
+                .is_ok(),
+            Some(Jwk::Ec { point }) => UnparsedPublicKey::new(&ECDSA_P256_SHA256_FIXED, point)
+                .verify(signed.as_bytes(), &signature)
+                .is_ok(),
+            None => return Err("Unknown token signing key".to_string()),
+        },
+        (_, alg) => return Err(format!("Unsupported token algorithm {}", alg)),
+    };
+    if !valid {
+        return Err("Invalid token signature".to_string());
+    }
+
+    let claims: Map<String, Value> =
+        serde_json::from_slice(&decode_segment(payload)?).map_err(|_| "Malformed token")?;
+    let leeway = settings.leeway.as_secs();
+    match claims.get("exp").and_then(Value::as_u64) {
+        Some(exp) if now <= exp.saturating_add(leeway) => {}
+        Some(_) => return Err("Token expired".to_string()),
+        None => return Err("Token has no expiry".to_string()),
+    }
+    if let Some(nbf) = claims.get("nbf").and_then(Value::as_u64) {
+        if now.saturating_add(leeway) < nbf {
+            return Err("Token not yet valid".to_string());
+        }
+    }
+    if claims.get("iss").and_then(Value::as_str) != settings.issuer.as_deref() {
+        return Err("Invalid token issuer".to_string());
+    }
+    if let Some(audience) = &settings.audience {
+        let matches = match claims.get("aud") {
+            Some(Value::String(aud)) => aud == audience,
+            Some(Value::Array(auds)) => auds.iter().any(|aud| aud.as_str() == Some(audience)),
+            _ => false,
+        };
+        if !matches {
+            return Err("Invalid token audience".to_string());
+        }
+    }
+    Ok(claims)
+}
+
+/// Team and role granted by a token's claims.
+pub fn team_and_role(
+    claims: &Map<String, Value>,
+    settings: &JwtSettings,
+) -> Result<(Uuid, Role), String> {
+    let team_id = claims
+        .get(&settings.team_claim)
+        .and_then(Value::as_str)
+        .and_then(|team_id| Uuid::parse_str(team_id).ok())
+        .ok_or_else(|| format!("Token has no valid {} claim", settings.team_claim))?;
+    let role = match claims.get(&settings.role_claim).and_then(Value::as_str) {
+        Some(role) => Role::from_str(role)?,
+        None => Role::ReadOnly,
+    };
+    Ok((team_id, role))
+}
+
+/// Verifies a bearer token against the configured issuer.
+pub fn authenticate(token: &str) -> Result<(Uuid, Role), String> {
+    let settings = &settings::get().jwt;
+    if !settings.enabled() {
+        return Err("Bearer tokens are not accepted".to_string());
+    }
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|now| now.as_secs())
+        .unwrap_or(0);
+    let jwks = JWKS.get_or_init(Default::default).read().unwrap();
+    let claims = verify(token, settings, &jwks, now)?;
+    team_and_role(&claims, settings)
+}
+
+async fn fetch_jwks(client: &reqwest::Client, settings: &JwtSettings) -> Result<JwkSet, String> {
+    let jwks_url = match (&settings.jwks_url, &settings.issuer) {
+        (Some(jwks_url), _) => jwks_url.clone(),
+        (None, Some(issuer)) => {
+            let discovery = format!(
+                "{}/.well-known/openid-configuration",
+                issuer.trim_end_matches('/')
+            );
+            let document: Value = client
+                .get(discovery)
+                .send()
+                .await
+                .and_then(|res| res.error_for_status())
+                .map_err(|e| e.to_string())?
+                .json()
+                .await
+                .map_err(|e| e.to_string())?;
+            document
+                .get("jwks_uri")
+                .and_then(Value::as_str)
+                .ok_or("OIDC discovery document has no jwks_uri")?
+                .to_string()
+        }
+        (None, None) => return Err("No issuer configured".to_string()),
+    };
+    let body = client
+        .get(jwks_url)
+        .send()
+        .await
+        .and_then(|res| res.error_for_status())
+        .map_err(|e| e.to_string())?
+        .text()
+        .await
+        .map_err(|e| e.to_string())?;
+    JwkSet::from_json(&body)
+}
+
+/// Keeps the issuer's JWKS cached, so requests verify tokens without a round trip.
+/// Nothing to fetch when tokens are signed with a shared secret.
+pub fn start(settings: JwtSettings) {
+    if !settings.enabled() || settings.secret.is_some() {
+        return;
+    }
+    rt::spawn(async move {
+        let client = reqwest::Client::new();
+        let mut interval = time::interval(settings.jwks_refresh);
+        loop {
+            interval.tick().await;
+            match fetch_jwks(&client, &settings).await {
+                Ok(jwks) => *JWKS.get_or_init(Default::default).write().unwrap() = jwks,
+                Err(e) => println!("Failed to refresh JWKS: {}", e),
+            }
+        }
+    });
+}
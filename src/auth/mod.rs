@@ -0,0 +1,184 @@
+use actix_web::{
+    http::{Method, StatusCode},
+    HttpRequest,
+};
+use std::str::FromStr;
+use uuid::Uuid;
+
+pub mod jwt;
+
+use crate::{
+    engine::blocks::Blockchain,
+    settings,
+    storage::{
+        teams::{ApiKey, Team, SCOPE_READ_ONLY},
+        Storage,
+    },
+};
+
+/// What an API key may do, ordered from least to most privileged.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Role {
+    ReadOnly,
+    Developer,
+    Owner,
+}
+
+impl Role {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::ReadOnly => "read_only",
+            Role::Developer => "developer",
+            Role::Owner => "owner",
+        }
+    }
+
+    /// Least privileged role allowed to make the request, unless the
+    /// endpoint asks for more.
+    pub fn required_for(http_req: &HttpRequest) -> Self {
+        if http_req.method() == Method::GET {
+            Role::ReadOnly
+        } else {
+            Role::Developer
+        }
+    }
+}
+
+impl FromStr for Role {
+    type Err = String;
+
+    fn from_str(role: &str) -> Result<Self, Self::Err> {
+        match role {
+            "read_only" => Ok(Role::ReadOnly),
+            "developer" => Ok(Role::Developer),
+            "owner" => Ok(Role::Owner),
+            _ => Err(format!("Invalid role {}", role)),
+        }
+    }
+}
+
+/// Effective role of a key, read-only scoped keys never exceed `ReadOnly`.
+pub fn api_key_role(api_key: &ApiKey) -> Role {
+    if api_key.scope == SCOPE_READ_ONLY {
+        return Role::ReadOnly;
+    }
+    // Unknown roles get the least access
+    Role::from_str(&api_key.role).unwrap_or(Role::ReadOnly)
+}
+
+fn bearer_token(http_req: &HttpRequest) -> Option<&str> {
+    http_req
+        .headers()
+        .get("authorization")
+        .and_then(|header_value| header_value.to_str().ok())
+        .and_then(|authorization| authorization.strip_prefix("Bearer "))
+        .map(str::trim)
+}
+
+/// Resolves the `api_key` header, or a bearer JWT when there's no API key, and checks
+/// the caller holds at least `required`. Token callers have no API key.
+pub fn authenticate<T: Storage>(
+    storage: &T,
+    http_req: &HttpRequest,
+    required: Role,
+) -> Result<(Option<ApiKey>, Team), String> {
+    let api_key = http_req
+        .headers()
+        .get("api_key")
+        .and_then(|header_value| header_value.to_str().ok());
+    if let (None, Some(token)) = (api_key, bearer_token(http_req)) {
+        let (team_id, role) = jwt::authenticate(token)?;
+        let team = match storage.get_team(team_id) {
+            Ok(Some(team)) => team,
+            _ => return Err("Invalid token".to_string()),
+        };
+        if role < required {
+            return Err(format!("Token requires the {} role", required.as_str()));
+        }
+        return Ok((None, team));
+    }
+
+    let api_key =
+        Uuid::parse_str(api_key.unwrap_or("")).map_err(|_| "Invalid API key".to_string())?;
+    let (api_key, team) = storage
+        .get_api_key_team(api_key)
+        .map_err(|_| "Invalid API key".to_string())?;
+    if api_key_role(&api_key) < required {
+        return Err(format!("API key requires the {} role", required.as_str()));
+    }
+    Ok((Some(api_key), team))
+}
+
+/// Rejects a mutating RPC call on a blockchain of a team with `rpc_write_guard`
+/// when the caller authenticates as another team. Calls without credentials
+/// only need the blockchain id, unless the blockchain is read only.
+pub fn check_rpc_write<T: Storage>(
+    storage: &T,
+    http_req: &HttpRequest,
+    id: Uuid,
+) -> Result<(), String> {
+    if !http_req.headers().contains_key("api_key") && bearer_token(http_req).is_none() {
+        // Unknown blockchains are left for the RPC call to report
+        if storage
+            .get_blockchain(id)
+            .is_ok_and(|blockchain| blockchain.read_only)
+        {
+            return Err("Blockchain is read only without an API key".to_string());
+        }
+        return Ok(());
+    }
+    let blockchain = storage.get_blockchain(id)?;
+    if !blockchain.read_only {
+        match storage.get_team(blockchain.team_id)? {
+            Some(team) if team.rpc_write_guard => {}
+            _ => return Ok(()),
+        }
+    }
+    let (_, team) = authenticate(storage, http_req, Role::Developer)?;
+    if team.id != blockchain.team_id {
+        return Err("API key does not own this blockchain".to_string());
+    }
+    Ok(())
+}
+
+/// Checks the caller is a developer of the team owning the blockchain, for
+/// routes writing its accounts or programs directly. Read-only blockchains
+/// need nothing more, their writes are left to the owning team as over RPC.
+/// Fails with the status to answer: 401 without valid credentials, 403 for
+/// another team.
+pub fn check_blockchain_write<T: Storage>(
+    storage: &T,
+    http_req: &HttpRequest,
+    blockchain: &Blockchain,
+) -> Result<Option<ApiKey>, (StatusCode, String)> {
+    let (api_key, team) = authenticate(storage, http_req, Role::Developer)
+        .map_err(|e| (StatusCode::UNAUTHORIZED, e))?;
+    if team.id != blockchain.team_id {
+        return Err((
+            StatusCode::FORBIDDEN,
+            "API key does not own this blockchain".to_string(),
+        ));
+    }
+    Ok(api_key)
+}
+
+/// Checks the `admin_token` header against the configured admin token, admin
+/// routes are disabled when it isn't set.
+pub fn valid_admin_token(http_req: &HttpRequest) -> bool {
+    let expected = match &settings::get().admin_token {
+        Some(token) if !token.is_empty() => token,
+        _ => return false,
+    };
+    let token = http_req
+        .headers()
+        .get("admin_token")
+        .and_then(|header_value| header_value.to_str().ok())
+        .unwrap_or("");
+    // Constant time so the token can't be guessed byte by byte
+    token.len() == expected.len()
+        && token
+            .bytes()
+            .zip(expected.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
@@ -0,0 +1,26 @@
+use actix_web::rt::{self, time};
+use std::{sync::Arc, time::Duration};
+
+use crate::{engine::SvmEngine, reporting, storage::Storage};
+
+/// Periodically pauses blockchains idle for longer than their team allows, see
+/// `Team::auto_pause_hours`. Each instance pauses the blockchains it served.
+pub fn start<T: Storage + Clone + 'static>(svm: Arc<SvmEngine<T>>, interval: Duration) {
+    rt::spawn(async move {
+        let mut interval = time::interval(interval);
+        loop {
+            interval.tick().await;
+            match svm.pause_idle_blockchains() {
+                Ok(0) => {}
+                Ok(paused) => println!("Paused {} idle blockchains", paused),
+                Err(e) => {
+                    println!("Failed to pause idle blockchains: {}", e);
+                    reporting::capture_error(
+                        &format!("Failed to pause idle blockchains: {}", e),
+                        &[],
+                    );
+                }
+            }
+        }
+    });
+}
@@ -0,0 +1,444 @@
+//! Drives an engine's RPC and websocket endpoints with a weighted mix of operations and
+//! reports throughput and latency percentiles per operation.
+//!
+//! cargo run --release --bin bench -- --rpc http://localhost:8899/rpc/<id> \
+//!     --duration 30 --concurrency 32 --mix send=1,read=8,ws=1
+
+use actix_web::rt::{self, time};
+use base64::prelude::*;
+use rand::Rng;
+use serde_json::{json, Value};
+use solana_sdk::{
+    hash::Hash,
+    native_token::LAMPORTS_PER_SOL,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    system_instruction,
+    transaction::Transaction,
+};
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    env,
+    rc::Rc,
+    str::FromStr,
+    time::{Duration, Instant},
+};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpStream,
+};
+
+const BLOCKHASH_REFRESH: Duration = Duration::from_secs(20);
+// Enough for each transfer to create a rent exempt account
+const TRANSFER_LAMPORTS: u64 = 1_000_000;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+enum Op {
+    Send,
+    Read,
+    Ws,
+}
+
+impl FromStr for Op {
+    type Err = String;
+
+    fn from_str(op: &str) -> Result<Self, Self::Err> {
+        match op {
+            "send" => Ok(Op::Send),
+            "read" => Ok(Op::Read),
+            "ws" => Ok(Op::Ws),
+            _ => Err(format!("Unknown operation {}", op)),
+        }
+    }
+}
+
+impl Op {
+    fn name(&self) -> &'static str {
+        match self {
+            Op::Send => "sendTransaction",
+            Op::Read => "getAccountInfo",
+            Op::Ws => "slotSubscribe",
+        }
+    }
+}
+
+struct Args {
+    rpc: String,
+    ws: String,
+    duration: Duration,
+    concurrency: usize,
+    mix: Vec<(Op, u32)>,
+    airdrop: u64,
+}
+
+const USAGE: &str = "Usage: bench --rpc <url> [--ws <url>] [--duration <secs>] \
+[--concurrency <n>] [--mix send=1,read=8,ws=1] [--airdrop <sol>]";
+
+fn parse_args() -> Result<Args, String> {
+    let mut flags: HashMap<String, String> = HashMap::new();
+    let mut args = env::args().skip(1);
+    while let Some(flag) = args.next() {
+        let name = flag
+            .strip_prefix("--")
+            .ok_or_else(|| format!("Unexpected argument {}", flag))?;
+        let value = args
+            .next()
+            .ok_or_else(|| format!("--{} needs a value", name))?;
+        flags.insert(name.to_string(), value);
+    }
+    let number = |name: &str, default: u64| -> Result<u64, String> {
+        match flags.get(name) {
+            Some(value) => value
+                .parse()
+                .map_err(|_| format!("--{} must be a number", name)),
+            None => Ok(default),
+        }
+    };
+
+    let rpc = flags.get("rpc").ok_or("--rpc is required")?.clone();
+    let ws = match flags.get("ws") {
+        Some(ws) => ws.clone(),
+        None => rpc.replacen("http", "ws", 1),
+    };
+    let mix = flags
+        .get("mix")
+        .map(String::as_str)
+        .unwrap_or("send=1,read=8,ws=1")
+        .split(',')
+        .map(|entry| {
+            let (op, weight) = entry.split_once('=').unwrap_or((entry, "1"));
+            let weight = weight
+                .parse::<u32>()
+                .map_err(|_| format!("Invalid weight in {}", entry))?;
+            Ok((Op::from_str(op)?, weight))
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+    if mix.iter().all(|(_, weight)| *weight == 0) {
+        return Err("--mix needs a positive weight".to_string());
+    }
+    if mix.iter().any(|(op, weight)| *op == Op::Ws && *weight > 0) && !ws.starts_with("ws://") {
+        return Err("Websocket load needs a ws:// URL".to_string());
+    }
+    Ok(Args {
+        rpc,
+        ws,
+        duration: Duration::from_secs(number("duration", 30)?),
+        concurrency: number("concurrency", 16)?.max(1) as usize,
+        mix,
+        airdrop: number("airdrop", 1000)? * LAMPORTS_PER_SOL,
+    })
+}
+
+#[derive(Default)]
+struct Samples {
+    latencies: Vec<Duration>,
+    errors: u64,
+}
+
+struct Target {
+    client: reqwest::Client,
+    rpc: String,
+    ws: String,
+    payer: Keypair,
+    blockhash: RefCell<Hash>,
+}
+
+impl Target {
+    async fn call(&self, method: &str, params: Value) -> Result<Value, String> {
+        let res: Value = self
+            .client
+            .post(&self.rpc)
+            .json(&json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params }))
+            .send()
+            .await
+            .map_err(|e| e.to_string())?
+            .json()
+            .await
+            .map_err(|e| e.to_string())?;
+        match res.get("error") {
+            Some(error) => Err(error.to_string()),
+            None => Ok(res["result"].clone()),
+        }
+    }
+
+    async fn refresh_blockhash(&self) -> Result<(), String> {
+        let res = self.call("getLatestBlockhash", json!([])).await?;
+        let blockhash = res["value"]["blockhash"]
+            .as_str()
+            .ok_or("getLatestBlockhash returned no blockhash")?;
+        *self.blockhash.borrow_mut() = Hash::from_str(blockhash).map_err(|e| e.to_string())?;
+        Ok(())
+    }
+
+    async fn run(&self, op: Op) -> Result<(), String> {
+        match op {
+            Op::Send => {
+                let recipient = Pubkey::new_unique();
+                let ix = system_instruction::transfer(
+                    &self.payer.pubkey(),
+                    &recipient,
+                    TRANSFER_LAMPORTS,
+                );
+                let tx = Transaction::new_signed_with_payer(
+                    &[ix],
+                    Some(&self.payer.pubkey()),
+                    &[&self.payer],
+                    *self.blockhash.borrow(),
+                );
+                let wire = bincode::serialize(&tx).map_err(|e| e.to_string())?;
+                self.call(
+                    "sendTransaction",
+                    json!([BASE64_STANDARD.encode(wire), { "encoding": "base64" }]),
+                )
+                .await?;
+            }
+            Op::Read => {
+                self.call(
+                    "getAccountInfo",
+                    json!([self.payer.pubkey().to_string(), { "encoding": "base64" }]),
+                )
+                .await?;
+            }
+            Op::Ws => {
+                let mut ws = WsClient::connect(&self.ws).await?;
+                ws.send(&json!({ "jsonrpc": "2.0", "id": 1, "method": "slotSubscribe" }))
+                    .await?;
+                // The subscription id, notifications after it aren't waited for
+                let res = ws.receive().await?;
+                if res.get("error").is_some() {
+                    return Err(res.to_string());
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Just enough of a websocket client to subscribe: plain `ws://`, text frames only.
+struct WsClient {
+    stream: TcpStream,
+}
+
+impl WsClient {
+    async fn connect(url: &str) -> Result<Self, String> {
+        let url = reqwest::Url::parse(url).map_err(|e| e.to_string())?;
+        let host = url.host_str().ok_or("Websocket URL has no host")?;
+        let port = url.port().unwrap_or(80);
+        let mut stream = TcpStream::connect((host, port))
+            .await
+            .map_err(|e| e.to_string())?;
+        let key = BASE64_STANDARD.encode(rand::thread_rng().gen::<[u8; 16]>());
+        let request = format!(
+            "GET {} HTTP/1.1\r\nHost: {}:{}\r\nUpgrade: websocket\r\nConnection: Upgrade\r\n\
+             Sec-WebSocket-Key: {}\r\nSec-WebSocket-Version: 13\r\n\r\n",
+            url.path(),
+            host,
+            port,
+            key
+        );
+        stream
+            .write_all(request.as_bytes())
+            .await
+            .map_err(|e| e.to_string())?;
+
+        // Read the handshake response byte by byte so no frame data is consumed
+        let mut response = Vec::new();
+        while !response.ends_with(b"\r\n\r\n") {
+            let byte = stream.read_u8().await.map_err(|e| e.to_string())?;
+            response.push(byte);
+        }
+        if !response.starts_with(b"HTTP/1.1 101") {
+            return Err(format!(
+                "Websocket upgrade failed: {}",
+                String::from_utf8_lossy(&response)
+                    .lines()
+                    .next()
+                    .unwrap_or("")
+            ));
+        }
+        Ok(WsClient { stream })
+    }
+
+    async fn send(&mut self, message: &Value) -> Result<(), String> {
+        let payload = message.to_string().into_bytes();
+        let mut frame = vec![0x81]; // FIN + text
+        match payload.len() {
+            len @ 0..=125 => frame.push(0x80 | len as u8),
+            len @ 126..=0xffff => {
+                frame.push(0x80 | 126);
+                frame.extend_from_slice(&(len as u16).to_be_bytes());
+            }
+            len => {
+                frame.push(0x80 | 127);
+                frame.extend_from_slice(&(len as u64).to_be_bytes());
+            }
+        }
+        // Client frames are always masked
+        let mask: [u8; 4] = rand::thread_rng().gen();
+        frame.extend_from_slice(&mask);
+        frame.extend(payload.iter().enumerate().map(|(i, b)| b ^ mask[i % 4]));
+        self.stream
+            .write_all(&frame)
+            .await
+            .map_err(|e| e.to_string())
+    }
+
+    /// Next text message, control frames are skipped.
+    async fn receive(&mut self) -> Result<Value, String> {
+        loop {
+            let mut header = [0u8; 2];
+            self.stream
+                .read_exact(&mut header)
+                .await
+                .map_err(|e| e.to_string())?;
+            let opcode = header[0] & 0x0f;
+            let len = match header[1] & 0x7f {
+                126 => self.stream.read_u16().await.map_err(|e| e.to_string())? as usize,
+                127 => self.stream.read_u64().await.map_err(|e| e.to_string())? as usize,
+                len => len as usize,
+            };
+            let mut payload = vec![0u8; len];
+            self.stream
+                .read_exact(&mut payload)
+                .await
+                .map_err(|e| e.to_string())?;
+            match opcode {
+                0x1 => return serde_json::from_slice(&payload).map_err(|e| e.to_string()),
+                0x8 => return Err("Websocket closed".to_string()),
+                _ => continue,
+            }
+        }
+    }
+}
+
+fn percentile(sorted: &[Duration], percentile: f64) -> Duration {
+    if sorted.is_empty() {
+        return Duration::ZERO;
+    }
+    let rank = (percentile / 100.0 * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+fn pick(mix: &[(Op, u32)]) -> Op {
+    let total: u32 = mix.iter().map(|(_, weight)| weight).sum();
+    let mut roll = rand::thread_rng().gen_range(0..total);
+    for (op, weight) in mix {
+        if roll < *weight {
+            return *op;
+        }
+        roll -= weight;
+    }
+    mix[0].0
+}
+
+fn report(samples: &HashMap<Op, Samples>, elapsed: Duration) {
+    println!(
+        "{:<16} {:>8} {:>7} {:>9} {:>9} {:>9} {:>9} {:>9}",
+        "operation", "ok", "errors", "ops/s", "p50 ms", "p90 ms", "p99 ms", "max ms"
+    );
+    let mut ops: Vec<_> = samples.iter().collect();
+    ops.sort_by_key(|(op, _)| **op);
+    for (op, samples) in ops {
+        let mut latencies = samples.latencies.clone();
+        latencies.sort();
+        let ms = |duration: Duration| duration.as_secs_f64() * 1000.0;
+        println!(
+            "{:<16} {:>8} {:>7} {:>9.1} {:>9.2} {:>9.2} {:>9.2} {:>9.2}",
+            op.name(),
+            latencies.len(),
+            samples.errors,
+            latencies.len() as f64 / elapsed.as_secs_f64(),
+            ms(percentile(&latencies, 50.0)),
+            ms(percentile(&latencies, 90.0)),
+            ms(percentile(&latencies, 99.0)),
+            ms(latencies.last().copied().unwrap_or_default()),
+        );
+    }
+}
+
+#[actix_web::main]
+async fn main() {
+    let args = match parse_args() {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("{}\n{}", e, USAGE);
+            std::process::exit(2);
+        }
+    };
+
+    let target = Rc::new(Target {
+        client: reqwest::Client::new(),
+        rpc: args.rpc.clone(),
+        ws: args.ws.clone(),
+        payer: Keypair::new(),
+        blockhash: RefCell::new(Hash::default()),
+    });
+    if args
+        .mix
+        .iter()
+        .any(|(op, weight)| *op == Op::Send && *weight > 0)
+    {
+        if let Err(e) = target
+            .call(
+                "requestAirdrop",
+                json!([target.payer.pubkey().to_string(), args.airdrop]),
+            )
+            .await
+        {
+            eprintln!("Failed to fund the payer: {}", e);
+            std::process::exit(1);
+        }
+    }
+    if let Err(e) = target.refresh_blockhash().await {
+        eprintln!("Failed to fetch a blockhash: {}", e);
+        std::process::exit(1);
+    }
+    println!(
+        "Running {:?} against {} for {}s with {} workers",
+        args.mix,
+        args.rpc,
+        args.duration.as_secs(),
+        args.concurrency
+    );
+
+    let refresher = target.clone();
+    rt::spawn(async move {
+        let mut interval = time::interval(BLOCKHASH_REFRESH);
+        loop {
+            interval.tick().await;
+            if let Err(e) = refresher.refresh_blockhash().await {
+                eprintln!("Failed to refresh the blockhash: {}", e);
+            }
+        }
+    });
+
+    let samples: Rc<RefCell<HashMap<Op, Samples>>> = Default::default();
+    let started = Instant::now();
+    let deadline = started + args.duration;
+    let workers: Vec<_> = (0..args.concurrency)
+        .map(|_| {
+            let target = target.clone();
+            let samples = samples.clone();
+            let mix = args.mix.clone();
+            rt::spawn(async move {
+                while Instant::now() < deadline {
+                    let op = pick(&mix);
+                    let op_started = Instant::now();
+                    let result = target.run(op).await;
+                    let mut samples = samples.borrow_mut();
+                    let op_samples = samples.entry(op).or_default();
+                    match result {
+                        Ok(_) => op_samples.latencies.push(op_started.elapsed()),
+                        Err(_) => op_samples.errors += 1,
+                    }
+                }
+            })
+        })
+        .collect();
+    for worker in workers {
+        let _ = worker.await;
+    }
+
+    report(&samples.borrow(), started.elapsed());
+}
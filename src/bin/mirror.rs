@@ -0,0 +1,222 @@
+//! Manages blockchains through the HTTP API, for scripts and CI.
+//!
+//! cargo run --bin mirror -- --url http://localhost:8899 --api-key <key> create
+//!
+//! The url and API key default to the MIRROR_URL and MIRROR_API_KEY environment
+//! variables. Blockchains are given by id or by RPC url.
+
+use mockchain_engine::client::{blockchain_id, Client};
+use solana_sdk::{native_token::sol_to_lamports, pubkey::Pubkey};
+use std::{collections::HashMap, env, fs, path::Path, str::FromStr};
+use uuid::Uuid;
+
+const DEFAULT_URL: &str = "http://localhost:8899";
+
+const USAGE: &str = "Usage: mirror [--url <url>] [--api-key <key>] <command>
+
+Commands:
+  create [--config <id>]                          Create a blockchain, prints its RPC url
+  list [--label <label>]                          List blockchains
+  delete <blockchain>                             Delete a blockchain
+  reset <blockchain>                              Replace a blockchain with a fresh one from
+                                                  the same config, prints the new RPC url
+  load-program <blockchain> <program id> <file>   Deploy a program from a .so file
+      [--upgradeable]
+  load-accounts <blockchain> <file>               Load accounts from a JSON array, or NDJSON
+                                                  when the file ends in .ndjson or .jsonl
+  airdrop <blockchain> <address> <sol>            Fund an address
+  export-accounts <blockchain> [--owner <id>]     Export accounts in the solana CLI JSON
+      [--out <file>]                              format, loadable with load-accounts
+  snapshot <blockchain> [--out <file>]            Download an account snapshot";
+
+// Flags that don't take a value
+const SWITCHES: [&str; 1] = ["upgradeable"];
+
+struct Args {
+    positional: Vec<String>,
+    flags: HashMap<String, String>,
+}
+
+impl Args {
+    fn parse() -> Result<Self, String> {
+        let mut positional = vec![];
+        let mut flags = HashMap::new();
+        let mut args = env::args().skip(1);
+        while let Some(arg) = args.next() {
+            match arg.strip_prefix("--") {
+                Some(name) if SWITCHES.contains(&name) => {
+                    flags.insert(name.to_string(), "true".to_string());
+                }
+                Some(name) => {
+                    let value = args
+                        .next()
+                        .ok_or_else(|| format!("--{} needs a value", name))?;
+                    flags.insert(name.to_string(), value);
+                }
+                None => positional.push(arg),
+            }
+        }
+        Ok(Args { positional, flags })
+    }
+
+    fn flag(&self, name: &str) -> Option<&str> {
+        self.flags.get(name).map(String::as_str)
+    }
+
+    /// The positional arguments of the command, which has to have exactly `count`.
+    fn operands(&self, count: usize) -> Result<&[String], String> {
+        let operands = &self.positional[1..];
+        if operands.len() != count {
+            return Err(format!(
+                "{} takes {} argument(s), got {}",
+                self.positional[0],
+                count,
+                operands.len()
+            ));
+        }
+        Ok(operands)
+    }
+}
+
+async fn run(args: Args) -> Result<(), String> {
+    let url = args
+        .flag("url")
+        .map(str::to_string)
+        .or_else(|| env::var("MIRROR_URL").ok())
+        .unwrap_or_else(|| DEFAULT_URL.to_string());
+    let api_key = args
+        .flag("api-key")
+        .map(str::to_string)
+        .or_else(|| env::var("MIRROR_API_KEY").ok());
+    let client = Client::new(&url, api_key);
+
+    let command = args.positional.first().ok_or("Missing command")?;
+    match command.as_str() {
+        "create" => {
+            args.operands(0)?;
+            let config = args
+                .flag("config")
+                .map(|config| Uuid::parse_str(config).map_err(|_| "Invalid config".to_string()))
+                .transpose()?;
+            println!("{}", client.create_blockchain(config).await?);
+        }
+        "list" => {
+            args.operands(0)?;
+            for blockchain in client.list_blockchains(args.flag("label")).await? {
+                println!(
+                    "{}\t{}\t{}\t{}",
+                    blockchain.id,
+                    blockchain.url,
+                    blockchain.label.unwrap_or_default(),
+                    blockchain
+                        .expiry
+                        .map(|expiry| expiry.to_string())
+                        .unwrap_or_default()
+                );
+            }
+        }
+        "delete" => {
+            let operands = args.operands(1)?;
+            client
+                .delete_blockchain(blockchain_id(&operands[0])?)
+                .await?;
+        }
+        "reset" => {
+            let operands = args.operands(1)?;
+            let url = client
+                .reset_blockchain(blockchain_id(&operands[0])?)
+                .await?;
+            println!("{}", url);
+        }
+        "load-program" => {
+            let operands = args.operands(3)?;
+            let id = blockchain_id(&operands[0])?;
+            let program_id = Pubkey::from_str(&operands[1])
+                .map_err(|_| format!("Invalid program id {}", operands[1]))?;
+            let program = fs::read(&operands[2])
+                .map_err(|e| format!("Failed to read {}: {}", operands[2], e))?;
+            let upgradeable = args.flag("upgradeable").is_some();
+            let programdata = client
+                .load_program(id, &program_id, program, upgradeable)
+                .await?;
+            if let Some(programdata) = programdata {
+                println!("{}", programdata);
+            }
+        }
+        "load-accounts" => {
+            let operands = args.operands(2)?;
+            let id = blockchain_id(&operands[0])?;
+            let path = Path::new(&operands[1]);
+            let body =
+                fs::read(path).map_err(|e| format!("Failed to read {}: {}", operands[1], e))?;
+            let ndjson = matches!(
+                path.extension().and_then(|extension| extension.to_str()),
+                Some("ndjson" | "jsonl")
+            );
+            let res = client.load_accounts(id, body, ndjson).await?;
+            println!("{}", res);
+        }
+        "airdrop" => {
+            let operands = args.operands(3)?;
+            let id = blockchain_id(&operands[0])?;
+            let address = Pubkey::from_str(&operands[1])
+                .map_err(|_| format!("Invalid address {}", operands[1]))?;
+            let sol = operands[2]
+                .parse::<f64>()
+                .map_err(|_| format!("Invalid amount {}", operands[2]))?;
+            let signature = client
+                .airdrop(id, &[(address, sol_to_lamports(sol))])
+                .await?;
+            println!("{}", signature);
+        }
+        "export-accounts" => {
+            let operands = args.operands(1)?;
+            let id = blockchain_id(&operands[0])?;
+            let owner = match args.flag("owner") {
+                Some(owner) => {
+                    Some(Pubkey::from_str(owner).map_err(|_| format!("Invalid owner {}", owner))?)
+                }
+                None => None,
+            };
+            let out = args
+                .flag("out")
+                .map(str::to_string)
+                .unwrap_or_else(|| format!("{}.accounts.json", id));
+            let accounts = client.export_accounts(id, owner.as_ref()).await?;
+            fs::write(&out, accounts).map_err(|e| format!("Failed to write {}: {}", out, e))?;
+            println!("{}", out);
+        }
+        "snapshot" => {
+            let operands = args.operands(1)?;
+            let id = blockchain_id(&operands[0])?;
+            let out = args
+                .flag("out")
+                .map(str::to_string)
+                .unwrap_or_else(|| format!("{}.snapshot.gz", id));
+            let snapshot = client.download_snapshot(id).await?;
+            fs::write(&out, snapshot).map_err(|e| format!("Failed to write {}: {}", out, e))?;
+            println!("{}", out);
+        }
+        _ => return Err(format!("Unknown command {}", command)),
+    }
+    Ok(())
+}
+
+#[actix_web::main]
+async fn main() {
+    let args = match Args::parse() {
+        Ok(args) if !args.positional.is_empty() => args,
+        Ok(_) => {
+            eprintln!("{}", USAGE);
+            std::process::exit(2);
+        }
+        Err(e) => {
+            eprintln!("{}\n{}", e, USAGE);
+            std::process::exit(2);
+        }
+    };
+    if let Err(e) = run(args).await {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,223 @@
+use reqwest::{multipart, RequestBuilder, Response};
+use serde::{de::DeserializeOwned, Deserialize};
+use serde_json::{json, Value};
+use solana_sdk::pubkey::Pubkey;
+use uuid::Uuid;
+
+/// A blockchain as listed by the management API.
+#[derive(Deserialize, Debug, Clone)]
+pub struct BlockchainInfo {
+    pub id: Uuid,
+    pub url: String,
+    pub label: Option<String>,
+    pub created_at: chrono::NaiveDateTime,
+    pub expiry: Option<chrono::NaiveDateTime>,
+    pub jit: bool,
+    pub config: Option<Uuid>,
+}
+
+#[derive(Deserialize)]
+struct BlockchainList {
+    details: Vec<BlockchainInfo>,
+}
+
+#[derive(Deserialize)]
+struct CreatedBlockchain {
+    url: String,
+}
+
+/// Client for the management HTTP API, authenticated with a team API key.
+#[derive(Clone)]
+pub struct Client {
+    http: reqwest::Client,
+    base_url: String,
+    api_key: Option<String>,
+}
+
+impl Client {
+    /// `base_url` is the server root, e.g. `http://localhost:8899`.
+    pub fn new(base_url: &str, api_key: Option<String>) -> Self {
+        Client {
+            http: reqwest::Client::new(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+            api_key,
+        }
+    }
+
+    /// Creates a blockchain and returns its RPC url.
+    pub async fn create_blockchain(&self, config: Option<Uuid>) -> Result<String, String> {
+        let req = self
+            .request(reqwest::Method::POST, "/blockchains")
+            .json(&json!({ "config": config }));
+        let created: CreatedBlockchain = send(req).await?;
+        Ok(created.url)
+    }
+
+    pub async fn list_blockchains(
+        &self,
+        label: Option<&str>,
+    ) -> Result<Vec<BlockchainInfo>, String> {
+        let mut req = self.request(reqwest::Method::GET, "/blockchains");
+        if let Some(label) = label {
+            req = req.query(&[("label", label)]);
+        }
+        let list: BlockchainList = send(req).await?;
+        Ok(list.details)
+    }
+
+    pub async fn get_blockchain(&self, id: Uuid) -> Result<BlockchainInfo, String> {
+        send(self.request(reqwest::Method::GET, &format!("/blockchains/{}", id))).await
+    }
+
+    pub async fn delete_blockchain(&self, id: Uuid) -> Result<(), String> {
+        send::<Value>(self.request(reqwest::Method::DELETE, &format!("/rpc/{}", id))).await?;
+        Ok(())
+    }
+
+    /// Replaces a blockchain with a fresh one from the same config, keeping its label
+    /// and expiry. The id changes, the new RPC url is returned.
+    pub async fn reset_blockchain(&self, id: Uuid) -> Result<String, String> {
+        let blockchain = self.get_blockchain(id).await?;
+        let url = self.create_blockchain(blockchain.config).await?;
+        if blockchain.label.is_some() || blockchain.expiry.is_some() {
+            let new_id = blockchain_id(&url)?;
+            let req = self
+                .request(reqwest::Method::PATCH, &format!("/blockchains/{}", new_id))
+                .json(&json!({
+                    "label": blockchain.label,
+                    "expiry": blockchain.expiry,
+                }));
+            send::<Value>(req).await?;
+        }
+        self.delete_blockchain(id).await?;
+        Ok(url)
+    }
+
+    /// Deploys a program, returns its programdata address when upgradeable.
+    pub async fn load_program(
+        &self,
+        id: Uuid,
+        program_id: &Pubkey,
+        program: Vec<u8>,
+        upgradeable: bool,
+    ) -> Result<Option<String>, String> {
+        let form = multipart::Form::new()
+            .text("program_id", program_id.to_string())
+            .text("upgradeable", upgradeable.to_string())
+            .part(
+                "program",
+                multipart::Part::bytes(program).file_name("program.so"),
+            );
+        let req = self
+            .request(reqwest::Method::POST, &format!("/programs/{}", id))
+            .multipart(form);
+        let res: Value = send(req).await?;
+        Ok(res["programdata_address"].as_str().map(str::to_string))
+    }
+
+    /// Loads accounts from a JSON array, or from NDJSON with one account per line.
+    pub async fn load_accounts(
+        &self,
+        id: Uuid,
+        body: Vec<u8>,
+        ndjson: bool,
+    ) -> Result<Value, String> {
+        let content_type = match ndjson {
+            true => "application/x-ndjson",
+            false => "application/json",
+        };
+        let req = self
+            .request(reqwest::Method::PUT, &format!("/accounts/{}", id))
+            .header("content-type", content_type)
+            .body(body);
+        send(req).await
+    }
+
+    /// Funds the recipients in a single transaction, returns its signature.
+    pub async fn airdrop(&self, id: Uuid, recipients: &[(Pubkey, u64)]) -> Result<String, String> {
+        let body: Vec<Value> = recipients
+            .iter()
+            .map(|(address, lamports)| json!({ "address": address.to_string(), "lamports": lamports }))
+            .collect();
+        let req = self
+            .request(reqwest::Method::POST, &format!("/airdrop/{}", id))
+            .json(&body);
+        let res: Value = send(req).await?;
+        res["signature"]
+            .as_str()
+            .map(str::to_string)
+            .ok_or_else(|| "Missing signature in response".to_string())
+    }
+
+    /// Exports accounts, optionally only those owned by `owner`, as a JSON array in the
+    /// format `solana account --output json-compact` prints and `load_accounts` takes.
+    pub async fn export_accounts(
+        &self,
+        id: Uuid,
+        owner: Option<&Pubkey>,
+    ) -> Result<Vec<u8>, String> {
+        let mut req = self.request(reqwest::Method::GET, &format!("/accounts/{}/export", id));
+        if let Some(owner) = owner {
+            req = req.query(&[("owner", owner.to_string())]);
+        }
+        let res = check(req.send().await.map_err(|e| e.to_string())?).await?;
+        let bytes = res.bytes().await.map_err(|e| e.to_string())?;
+        Ok(bytes.to_vec())
+    }
+
+    /// Downloads the gzipped account snapshot of a blockchain.
+    pub async fn download_snapshot(&self, id: Uuid) -> Result<Vec<u8>, String> {
+        let req = self.request(
+            reqwest::Method::GET,
+            &format!("/blockchains/{}/snapshot", id),
+        );
+        let res = check(req.send().await.map_err(|e| e.to_string())?).await?;
+        let bytes = res.bytes().await.map_err(|e| e.to_string())?;
+        Ok(bytes.to_vec())
+    }
+
+    fn request(&self, method: reqwest::Method, path: &str) -> RequestBuilder {
+        let req = self
+            .http
+            .request(method, format!("{}{}", self.base_url, path));
+        match &self.api_key {
+            Some(api_key) => req.header("api_key", api_key),
+            None => req,
+        }
+    }
+}
+
+/// Reads the blockchain id from an RPC url, or takes the id as is.
+pub fn blockchain_id(url_or_id: &str) -> Result<Uuid, String> {
+    let id = url_or_id
+        .trim_end_matches('/')
+        .rsplit('/')
+        .next()
+        .unwrap_or("");
+    Uuid::parse_str(id).map_err(|_| format!("Invalid blockchain {}", url_or_id))
+}
+
+async fn send<T: DeserializeOwned>(req: RequestBuilder) -> Result<T, String> {
+    let res = check(req.send().await.map_err(|e| e.to_string())?).await?;
+    res.json().await.map_err(|e| e.to_string())
+}
+
+/// Turns error statuses into the server's message.
+async fn check(res: Response) -> Result<Response, String> {
+    let status = res.status();
+    if status.is_success() {
+        return Ok(res);
+    }
+    let body = res.text().await.unwrap_or_default();
+    let message = match serde_json::from_str::<Value>(&body) {
+        Ok(Value::String(message)) => message,
+        Ok(json) => json
+            .get("message")
+            .or_else(|| json.get("error"))
+            .and_then(Value::as_str)
+            .map(str::to_string)
+            .unwrap_or(body),
+        Err(_) => body,
+    };
+    Err(format!("{}: {}", status, message))
+}
@@ -0,0 +1,189 @@
+use std::{
+    io,
+    net::{SocketAddr, TcpListener},
+    sync::Arc,
+};
+
+use actix_web::{dev::ServerHandle, rt, web, App, HttpServer};
+use serde_json::{json, Value};
+use solana_sdk::{pubkey::Pubkey, signature::Keypair};
+use uuid::Uuid;
+
+use crate::{
+    endpoints::{rpc_reqest, rpc_ws},
+    engine::{SvmEngine, SVM},
+    rpc::rpc::{handle_request, RpcRequest},
+    shutdown::Shutdown,
+    storage::{memory::MemoryStorage, Storage},
+};
+
+const PORT_PAIR_ATTEMPTS: usize = 20;
+
+struct Listener {
+    addr: SocketAddr,
+    handle: ServerHandle,
+    shutdown: Shutdown,
+}
+
+/// Runs the engine inside the current process on in-memory storage, for integration
+/// tests that should not need Postgres, Redis or Docker. Programs can be deployed over
+/// RPC, as with `anchor_compat`.
+///
+/// The engine spawns onto the actix runtime, so tests have to run inside one, e.g.
+/// with `#[actix_web::test]`:
+///
+/// ```no_run
+/// # use mockchain_engine::embedded::EmbeddedEngine;
+/// #[actix_web::test]
+/// async fn test_balance() {
+///     let mut engine = EmbeddedEngine::new().unwrap();
+///     let res = engine.rpc("getBalance", serde_json::json!([engine.payer().to_string()])).await;
+///     assert!(res.is_ok());
+///
+///     // Or point a solana RpcClient at it
+///     engine.listen().unwrap();
+///     let url = engine.rpc_url().unwrap();
+/// }
+/// ```
+pub struct EmbeddedEngine {
+    svm: Arc<SvmEngine<MemoryStorage>>,
+    blockchain: Uuid,
+    listener: Option<Listener>,
+}
+
+impl EmbeddedEngine {
+    /// Creates an engine with a single blockchain, funded payer included.
+    pub fn new() -> Result<Self, String> {
+        Self::with_storage(MemoryStorage::new())
+    }
+
+    /// Same as `new`, with storage set up beforehand, e.g. with an upstream RPC.
+    pub fn with_storage(storage: MemoryStorage) -> Result<Self, String> {
+        let svm = Arc::new(SvmEngine::new(storage).with_anchor_compat(true));
+        let blockchain = svm.create_blockchain(Uuid::new_v4(), None, None, None, None, false)?;
+        Ok(EmbeddedEngine {
+            svm,
+            blockchain,
+            listener: None,
+        })
+    }
+
+    pub fn svm(&self) -> &Arc<SvmEngine<MemoryStorage>> {
+        &self.svm
+    }
+
+    /// Id of the blockchain the RPC methods run against.
+    pub fn blockchain(&self) -> Uuid {
+        self.blockchain
+    }
+
+    /// Keypair of the account funded at genesis, which also pays for airdrops.
+    pub fn payer(&self) -> Keypair {
+        self.svm
+            .storage
+            .get_blockchain(self.blockchain)
+            .expect("Embedded blockchain is missing")
+            .airdrop_keypair
+    }
+
+    pub async fn airdrop(&self, pubkey: &Pubkey, lamports: u64) -> Result<String, String> {
+        self.svm.airdrop(self.blockchain, pubkey, lamports).await
+    }
+
+    /// Moves to a new blockhash, what the block ticker does in the server.
+    pub fn produce_blocks(&self) -> Result<usize, String> {
+        self.svm.produce_blocks()
+    }
+
+    /// Calls a JSON-RPC method without going through HTTP.
+    pub async fn rpc(&self, method: &str, params: Value) -> Result<Value, Value> {
+        let req: RpcRequest = serde_json::from_value(json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": method,
+            "params": params,
+        }))
+        .map_err(|e| json!({"code": -32601, "message": e.to_string()}))?;
+        let res = handle_request(self.blockchain, req, &self.svm).await;
+        match res.error {
+            Some(error) => Err(error),
+            None => Ok(res.result.unwrap_or(Value::Null)),
+        }
+    }
+
+    /// Serves HTTP and websocket RPC on a free local port, until `stop` or the end of
+    /// the runtime. Returns the address it listens on. The next port serves the same,
+    /// since web3.js and the solana CLI derive the websocket url as the RPC port + 1.
+    pub fn listen(&mut self) -> io::Result<SocketAddr> {
+        if let Some(listener) = &self.listener {
+            return Ok(listener.addr);
+        }
+        let (rpc, ws) = bind_port_pair()?;
+        let addr = rpc.local_addr()?;
+        let svm = self.svm.clone();
+        let shutdown = Shutdown::default();
+        let app_shutdown = shutdown.clone();
+        let server = HttpServer::new(move || {
+            App::new()
+                .app_data(web::Data::new(svm.clone()))
+                .app_data(web::Data::new(app_shutdown.clone()))
+                .service(
+                    web::resource("/rpc/{id}")
+                        .route(web::get().to(rpc_ws::<MemoryStorage>))
+                        .route(web::post().to(rpc_reqest::<MemoryStorage>)),
+                )
+        })
+        .workers(1)
+        .disable_signals()
+        .listen(rpc)?
+        .listen(ws)?
+        .run();
+        let handle = server.handle();
+        rt::spawn(server);
+        self.listener = Some(Listener {
+            addr,
+            handle,
+            shutdown,
+        });
+        Ok(addr)
+    }
+
+    /// HTTP RPC url of the blockchain, once listening.
+    pub fn rpc_url(&self) -> Option<String> {
+        self.listener
+            .as_ref()
+            .map(|l| format!("http://{}/rpc/{}", l.addr, self.blockchain))
+    }
+
+    /// Websocket RPC url of the blockchain, once listening.
+    pub fn ws_url(&self) -> Option<String> {
+        self.listener
+            .as_ref()
+            .map(|l| format!("ws://{}/rpc/{}", l.addr, self.blockchain))
+    }
+
+    /// Closes websocket sessions and stops the listener, if any.
+    pub async fn stop(&mut self) {
+        if let Some(listener) = self.listener.take() {
+            listener.shutdown.trigger();
+            listener.handle.stop(true).await;
+        }
+    }
+}
+
+/// Binds two consecutive local ports.
+fn bind_port_pair() -> io::Result<(TcpListener, TcpListener)> {
+    let mut last_error = None;
+    for _ in 0..PORT_PAIR_ATTEMPTS {
+        let rpc = TcpListener::bind(("127.0.0.1", 0))?;
+        let port = rpc.local_addr()?.port();
+        if port == u16::MAX {
+            continue;
+        }
+        match TcpListener::bind(("127.0.0.1", port + 1)) {
+            Ok(ws) => return Ok((rpc, ws)),
+            Err(e) => last_error = Some(e),
+        }
+    }
+    Err(last_error.unwrap_or_else(|| io::Error::other("No free port pair")))
+}
@@ -1,44 +1,114 @@
 use actix_multipart::Multipart;
-use actix_web::{delete, get, post, put, rt, web, Error, HttpRequest, HttpResponse, Responder};
-use actix_ws::AggregatedMessage;
+use actix_web::{
+    delete, get, guard::GuardContext, patch, post, put, rt, web, Error, HttpRequest, HttpResponse,
+    Responder,
+};
+use actix_ws::{AggregatedMessage, CloseCode, CloseReason};
 use base64::prelude::*;
+use bigdecimal::ToPrimitive;
 use futures::StreamExt as _;
 use serde::Deserialize;
-use solana_sdk::{account::Account, program_option::COption, program_pack::Pack, pubkey::Pubkey};
-use spl_token::state::Mint;
-use std::{env, str::FromStr, sync::Arc};
+use solana_sdk::{
+    account::Account,
+    bpf_loader, bpf_loader_deprecated,
+    bpf_loader_upgradeable::{self, UpgradeableLoaderState},
+    bs58,
+    pubkey::Pubkey,
+    signer::Signer,
+    system_instruction::MAX_PERMITTED_DATA_LENGTH,
+};
+use std::{
+    collections::BTreeMap,
+    io::{self, BufWriter, Write},
+    str::FromStr,
+    sync::Arc,
+    time::Duration,
+};
+use tokio::sync::mpsc;
 
 use serde_json::json;
+use sha2::{Digest, Sha256};
 use uuid::Uuid;
 
 use crate::{
-    engine::{builtins::BUILTINS, SvmEngine, SVM},
+    auth::{self, Role},
+    engine::{
+        self,
+        builtins::BUILTINS,
+        idl,
+        spl::{bundle_programs, set_mint_authorities},
+        sysvars::SysvarOverrides,
+        timeline::MAX_TIMELINE_SIZE,
+        SvmEngine, MAX_AIRDROP_BATCH, SVM,
+    },
+    openapi, reporting,
     rpc::{
-        rpc::{handle_request, RpcMethod, RpcRequest},
+        rpc::{handle_request, RpcRequest, RpcResponse},
         ws::handle_ws_request,
     },
-    storage::{teams::Team, PgStorage, Storage},
+    rpc_log, settings,
+    shutdown::Shutdown,
+    storage::{
+        accounts::AccountListFilter,
+        audit::{self, AuditLogFilter, DbAuditLog},
+        blocks::{BlockchainFilter, DBBlockchainConfig, DbBlockchainUpdate},
+        health::ComponentStatus,
+        idempotency::{IdempotencyRecord, MAX_IDEMPOTENCY_KEY_LEN},
+        numeric,
+        pubsub::GeyserEvent,
+        snapshot::{Snapshot, SnapshotWriter, MAX_SNAPSHOT_BYTES},
+        teams::{ApiKey, Team, TeamQuotas, API_KEY_SCOPES, SCOPE_FULL},
+        top_ups::DbTopUp,
+        trace,
+        transactions::{DbTransactionObject, TransactionFilter, TRANSACTION_FEED_SIZE},
+        webhooks::DbWebhook,
+        PgStorage, Storage,
+    },
+    warm_pool::WarmPool,
+    webhooks,
 };
 
-pub async fn rpc_reqest(
+pub async fn rpc_reqest<T: Storage + Clone + 'static>(
+    http_req: HttpRequest,
     req: web::Json<RpcRequest>,
-    svm: web::Data<Arc<SvmEngine<PgStorage>>>,
+    svm: web::Data<Arc<SvmEngine<T>>>,
     path: web::Path<Uuid>,
 ) -> impl Responder {
     let id = path.into_inner();
-
-    let res = handle_request(id, req.clone(), &svm).await;
-    // println!("{:?}", req.method);
-    if req.method != RpcMethod::GetAccountInfo {
-        // println!("{:?}", res);
+    if req.method.is_mutating() {
+        if let Err(e) = auth::check_rpc_write(&svm.storage, &http_req, id) {
+            let req = req.into_inner();
+            return HttpResponse::Forbidden().json(RpcResponse {
+                jsonrpc: req.jsonrpc,
+                id: req.id,
+                result: None,
+                error: Some(json!({"code": -32003, "message": e})),
+                mirror_debug: None,
+            });
+        }
     }
+
+    let context = vec![
+        ("blockchain", id.to_string()),
+        ("method", req.method.to_string()),
+    ];
+    let handled = reporting::scope(context, handle_request(id, req.into_inner(), &svm));
+    // Any value asks for the operation log, it's cheap to collect
+    let res = if http_req.headers().contains_key("Mirror-Debug") {
+        let (mut res, log) = trace::traced(handled).await;
+        res.mirror_debug = Some(log);
+        res
+    } else {
+        handled.await
+    };
     HttpResponse::Ok().json(res)
 }
 
-pub async fn rpc_ws(
+pub async fn rpc_ws<T: Storage + Clone + 'static>(
     req: HttpRequest,
     path: web::Path<Uuid>,
-    svm: web::Data<Arc<SvmEngine<PgStorage>>>,
+    svm: web::Data<Arc<SvmEngine<T>>>,
+    shutdown: web::Data<Shutdown>,
     stream: web::Payload,
 ) -> Result<HttpResponse, Error> {
     let (res, mut session, stream) = actix_ws::handle(&req, stream)?;
@@ -46,35 +116,47 @@ pub async fn rpc_ws(
         .aggregate_continuations()
         .max_continuation_size(2_usize.pow(20));
     let id = path.into_inner();
+    let shutdown = shutdown.get_ref().clone();
     rt::spawn(async move {
-        while let Some(msg) = stream.next().await {
+        // Cancels the connection's pending requests once it ends
+        let closed = Shutdown::default();
+        loop {
+            let msg = tokio::select! {
+                msg = stream.next() => match msg {
+                    Some(msg) => msg,
+                    None => break,
+                },
+                _ = shutdown.wait() => {
+                    // Going away lets clients reconnect to another instance
+                    let reason = CloseReason::from(CloseCode::Away);
+                    if let Err(e) = session.close(Some(reason)).await {
+                        println!("{:?}", e);
+                    }
+                    break;
+                }
+            };
             match msg {
                 Ok(AggregatedMessage::Text(text)) => {
-                    let res = handle_ws_request(id, &text.to_string(), session.clone(), &svm).await;
-                    match res {
-                        Ok(_) => {}
-                        Err(e) => {
-                            match session
-                                .text(
-                                    serde_json::json!({
-                                        "jsonrpc": "2.0",
-                                        "id": serde_json::Value::Null,
-                                        "error": {
-                                            "code": -32603,
-                                            "message": e
-                                        }
-                                    })
-                                    .to_string(),
-                                )
-                                .await
-                            {
-                                Ok(_) => {}
-                                Err(e) => {
-                                    println!("{:?}", e);
-                                }
-                            }
+                    // Subscriptions stream until cancelled, so requests can't wait on
+                    // each other. web3.js sends all of them over one connection.
+                    let session = session.clone();
+                    let svm = svm.clone();
+                    let closed = closed.clone();
+                    rt::spawn(async move {
+                        let context = vec![("blockchain", id.to_string())];
+                        let text = text.to_string();
+                        let request = reporting::scope(
+                            context,
+                            handle_ws_request(id, &text, session.clone(), &svm),
+                        );
+                        let res = tokio::select! {
+                            res = request => res,
+                            _ = closed.wait() => return,
+                        };
+                        if let Err(e) = res {
+                            ws_error(session, e).await;
                         }
-                    }
+                    });
                 }
                 Ok(AggregatedMessage::Binary(bin)) => match session.binary(bin).await {
                     Ok(_) => {}
@@ -101,19 +183,147 @@ pub async fn rpc_ws(
                 _ => {}
             }
         }
+        closed.trigger();
     });
     Ok(res)
 }
 
+async fn ws_error(mut session: actix_ws::Session, message: String) {
+    let res = session
+        .text(
+            serde_json::json!({
+                "jsonrpc": "2.0",
+                "id": serde_json::Value::Null,
+                "error": {
+                    "code": -32603,
+                    "message": message
+                }
+            })
+            .to_string(),
+        )
+        .await;
+    if let Err(e) = res {
+        println!("{:?}", e);
+    }
+}
+
+const READINESS_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Liveness probe, only checks the process is serving requests.
+#[get("/healthz")]
+pub async fn healthz() -> impl Responder {
+    HttpResponse::Ok().json(json!({
+        "status": "ok"
+    }))
+}
+
+/// Readiness probe, fails while a dependency is unreachable or the engine is
+/// shutting down so traffic moves to other instances.
+#[get("/readyz")]
+pub async fn readyz(
+    svm: web::Data<Arc<SvmEngine<PgStorage>>>,
+    shutdown: web::Data<Shutdown>,
+) -> impl Responder {
+    let storage = svm.storage.clone();
+    let mut checks = match web::block(move || storage.readiness(READINESS_TIMEOUT)).await {
+        Ok(checks) => checks,
+        Err(e) => return HttpResponse::InternalServerError().json(e.to_string()),
+    };
+    checks.push(ComponentStatus::new(
+        "engine",
+        match shutdown.is_triggered() {
+            true => Err("Shutting down".to_string()),
+            false => Ok(()),
+        },
+    ));
+
+    let ready = checks.iter().all(|check| check.ok);
+    let mut res = match ready {
+        true => HttpResponse::Ok(),
+        false => HttpResponse::ServiceUnavailable(),
+    };
+    res.json(json!({
+        "status": if ready { "ready" } else { "unavailable" },
+        "checks": checks,
+    }))
+}
+
+/// Prometheus metrics, scraped with the admin token.
+#[get("/metrics")]
+pub async fn metrics(
+    svm: web::Data<Arc<SvmEngine<PgStorage>>>,
+    http_req: HttpRequest,
+) -> impl Responder {
+    if !auth::valid_admin_token(&http_req) {
+        return HttpResponse::Unauthorized().json(json!({
+            "message": "Invalid admin token"
+        }));
+    }
+    let mut body = crate::metrics::render();
+    body.push_str(&crate::metrics::render_queues(&svm.engine_stats().queues));
+    HttpResponse::Ok()
+        .content_type("text/plain; version=0.0.4")
+        .body(body)
+}
+
+/// OpenAPI document of this API.
+#[get("/openapi.json")]
+pub async fn openapi_spec() -> impl Responder {
+    HttpResponse::Ok().json(openapi::spec())
+}
+
+/// Swagger UI for the OpenAPI document.
+#[get("/docs")]
+pub async fn api_docs() -> impl Responder {
+    HttpResponse::Ok()
+        .content_type("text/html; charset=utf-8")
+        .body(openapi::SWAGGER_UI)
+}
+
+// Room for the text fields and multipart boundaries/headers
+const MULTIPART_OVERHEAD: usize = 64 * 1024;
+const MAX_TEXT_FIELD: usize = 64;
+
+fn program_too_large(max_program_size: usize) -> HttpResponse {
+    HttpResponse::PayloadTooLarge().json(json!({
+        "message": format!("Program is larger than {} bytes", max_program_size)
+    }))
+}
+
 #[post("/programs/{id}")]
 pub async fn load_program(
     mut payload: Multipart,
     svm: web::Data<Arc<SvmEngine<PgStorage>>>,
     path: web::Path<Uuid>,
+    http_req: HttpRequest,
 ) -> impl Responder {
     let id = path.into_inner();
+    let blockchain = match svm.storage.get_blockchain(id) {
+        Ok(blockchain) => blockchain,
+        Err(e) => {
+            return HttpResponse::InternalServerError().json(e.to_string());
+        }
+    };
+    let api_key = match auth::check_blockchain_write(&svm.storage, &http_req, &blockchain) {
+        Ok(api_key) => api_key.map(|api_key| api_key.id),
+        Err((status, e)) => return HttpResponse::build(status).json(json!({ "message": e })),
+    };
+    let max_program_size = settings::get().limits.max_program_size;
+    // Reject early when the client announces an oversized body
+    let content_length = http_req
+        .headers()
+        .get("content-length")
+        .and_then(|header_value| header_value.to_str().ok())
+        .and_then(|content_length| content_length.parse::<usize>().ok());
+    if content_length.is_some_and(|length| length > max_program_size + MULTIPART_OVERHEAD) {
+        return program_too_large(max_program_size);
+    }
+
     let mut program_data = Vec::new();
     let mut program_id_str = String::new();
+    let mut upgradeable_str = String::new();
+    let mut upgrade_authority_str = String::new();
+    let mut other_bytes = 0;
 
     // Parse the file from the request
     while let Some(item) = payload.next().await {
@@ -125,19 +335,51 @@ pub async fn load_program(
                 }));
             }
         };
-        if field.name() == Some("program") {
-            while let Some(chunk) = field.next().await {
-                let data = chunk.unwrap();
-                program_data.extend_from_slice(&data);
-            }
-        }
-        if field.name() == Some("program_id") {
-            while let Some(chunk) = field.next().await {
-                let data = chunk.unwrap();
-                program_id_str.push_str(&String::from_utf8_lossy(&data));
+        let name = field.name().map(|name| name.to_string());
+        while let Some(chunk) = field.next().await {
+            let data = match chunk {
+                Ok(data) => data,
+                Err(e) => {
+                    return HttpResponse::BadRequest().json(json!({
+                        "message": e.to_string()
+                    }));
+                }
+            };
+            match name.as_deref() {
+                Some("program") => {
+                    if program_data.len() + data.len() > max_program_size {
+                        return program_too_large(max_program_size);
+                    }
+                    program_data.extend_from_slice(&data);
+                }
+                Some(field_name @ ("program_id" | "upgradeable" | "upgrade_authority")) => {
+                    let value = match field_name {
+                        "program_id" => &mut program_id_str,
+                        "upgradeable" => &mut upgradeable_str,
+                        _ => &mut upgrade_authority_str,
+                    };
+                    if value.len() + data.len() > MAX_TEXT_FIELD {
+                        return HttpResponse::BadRequest().json(json!({
+                            "message": format!("Invalid {}", field_name.replace('_', " "))
+                        }));
+                    }
+                    value.push_str(&String::from_utf8_lossy(&data));
+                }
+                // Unknown fields are drained, but still count against the limit
+                _ => {
+                    other_bytes += data.len();
+                    if other_bytes > MULTIPART_OVERHEAD {
+                        return program_too_large(max_program_size);
+                    }
+                }
             }
         }
     }
+    if program_data.is_empty() {
+        return HttpResponse::BadRequest().json(json!({
+            "message": "Missing program"
+        }));
+    }
 
     let program_id = match program_id_str.parse() {
         Ok(program_id) => program_id,
@@ -147,6 +389,31 @@ pub async fn load_program(
             }));
         }
     };
+    let upgradeable = match upgradeable_str.trim() {
+        "" | "false" => false,
+        "true" => true,
+        _ => {
+            return HttpResponse::BadRequest().json(json!({
+                "message": "upgradeable must be true or false"
+            }));
+        }
+    };
+    let upgrade_authority = match upgrade_authority_str.trim() {
+        "" => None,
+        _ if !upgradeable => {
+            return HttpResponse::BadRequest().json(json!({
+                "message": "upgrade_authority requires upgradeable to be true"
+            }));
+        }
+        authority => match authority.parse::<Pubkey>() {
+            Ok(authority) => Some(authority),
+            Err(_) => {
+                return HttpResponse::BadRequest().json(json!({
+                    "message": "Invalid upgrade authority"
+                }));
+            }
+        },
+    };
 
     BUILTINS
         .iter()
@@ -157,259 +424,4296 @@ pub async fn load_program(
             }));
         });
 
-    let (pubkey, account) = svm.add_program(program_id, &program_data);
-    match svm.storage.set_account(id, &pubkey, account, None) {
-        Ok(_) => HttpResponse::Ok().json(json!({
-            "message": "Program loaded successfully"
-        })),
+    let accounts = if upgradeable {
+        match svm.add_upgradeable_program(program_id, &program_data, upgrade_authority) {
+            Ok(accounts) => accounts,
+            Err(e) => return HttpResponse::InternalServerError().json(e.to_string()),
+        }
+    } else {
+        vec![svm.add_program(program_id, &program_data)]
+    };
+    if let Err(res) = check_account_quota(&svm, blockchain.team_id, id, &accounts) {
+        return res;
+    }
+    // Only upgradeable deployments have a programdata account
+    let programdata_address = accounts.get(1).map(|(address, _)| address.to_string());
+    match svm.storage.set_accounts(id, accounts) {
+        Ok(_) => {
+            audit(
+                &svm,
+                api_key,
+                blockchain.team_id,
+                Some(id),
+                audit::PROGRAM_LOAD,
+                json!({
+                    "program_id": program_id.to_string(),
+                    "size": program_data.len(),
+                    "upgradeable": upgradeable,
+                    "upgrade_authority": upgrade_authority.map(|authority| authority.to_string()),
+                }),
+            );
+            HttpResponse::Ok().json(json!({
+                "message": "Program loaded successfully",
+                "programdata_address": programdata_address,
+            }))
+        }
         Err(e) => HttpResponse::InternalServerError().json(e.to_string()),
     }
 }
 
-#[derive(Deserialize)]
-pub struct AccountReq {
-    address: String,
-    lamports: u64,
-    data: String,
-    owner: String,
-    rent_epoch: u64,
-    executable: bool,
-    token_mint_auth: Option<String>,
+#[derive(Deserialize, Debug, Clone)]
+pub struct CloneProgramReq {
+    pub program_id: String,
 }
 
-#[put("/accounts/{id}")]
-pub async fn load_account(
-    accounts_req: web::Json<Vec<AccountReq>>,
+#[post("/programs/{id}/clone")]
+pub async fn clone_program(
     svm: web::Data<Arc<SvmEngine<PgStorage>>>,
     path: web::Path<Uuid>,
+    http_req: HttpRequest,
+    req: web::Json<CloneProgramReq>,
 ) -> impl Responder {
     let id = path.into_inner();
-
-    let accounts = accounts_req.iter().map(|account| {
-        let mut data = match BASE64_STANDARD.decode(&account.data) {
-            Ok(data) => data,
-            Err(_) => {
-                return Err("Invalid base64 data".to_string());
-            }
-        };
-
-        if account.token_mint_auth.is_some() {
-            let token_mint_signer =
-                match Pubkey::from_str(&account.token_mint_auth.as_ref().unwrap()) {
-                    Ok(token_mint_signer) => token_mint_signer,
-                    Err(_) => {
-                        return Err("Invalid token mint signer".to_string());
-                    }
-                };
-            let mut mint_data = match Mint::unpack(&data) {
-                Ok(mint_data) => mint_data,
-                Err(_) => {
-                    return Err("Invalid mint data".to_string());
-                }
-            };
-            mint_data.mint_authority = COption::Some(token_mint_signer);
-            match Mint::pack(mint_data, &mut data) {
-                Ok(data) => data,
-                Err(_) => {
-                    return Err("Invalid mint data".to_string());
-                }
-            };
-        }
-
-        let owner = match Pubkey::from_str(&account.owner) {
-            Ok(owner) => owner,
-            Err(_) => {
-                return Err("Invalid owner".to_string());
-            }
-        };
-        let address = match Pubkey::from_str(&account.address) {
-            Ok(address) => address,
-            Err(_) => {
-                return Err("Invalid address".to_string());
-            }
-        };
-        Ok((
-            address,
-            Account {
-                lamports: account.lamports,
-                data: data,
-                owner: owner,
-                rent_epoch: account.rent_epoch,
-                executable: account.executable,
-            },
-        ))
-    });
-
-    let accounts: Vec<(Pubkey, Account)> = match accounts.collect() {
-        Ok(accounts) => accounts,
+    let blockchain = match svm.storage.get_blockchain(id) {
+        Ok(blockchain) => blockchain,
         Err(e) => {
+            return HttpResponse::InternalServerError().json(e.to_string());
+        }
+    };
+    let api_key = match team_api_key(blockchain.team_id, &svm, &http_req) {
+        Some(api_key) => api_key,
+        None => {
+            return HttpResponse::Unauthorized().json(json!({
+                "message": "Invalid API key"
+            }));
+        }
+    };
+    let program_id = match Pubkey::from_str(&req.program_id) {
+        Ok(program_id) => program_id,
+        Err(_) => {
             return HttpResponse::BadRequest().json(json!({
-                "message": e
+                "message": "Invalid program id"
             }));
         }
     };
 
-    match svm.storage.set_accounts(id, accounts) {
-        Ok(_) => HttpResponse::Ok().json(json!({
-            "message": "Account loaded successfully"
+    match svm.clone_program(id, &program_id).await {
+        Ok(accounts) => {
+            let accounts = accounts
+                .iter()
+                .map(|a| a.to_string())
+                .collect::<Vec<String>>();
+            audit(
+                &svm,
+                api_key,
+                blockchain.team_id,
+                Some(id),
+                audit::PROGRAM_CLONE,
+                json!({ "program_id": program_id.to_string(), "accounts": accounts }),
+            );
+            HttpResponse::Ok().json(json!({
+                "message": "Program cloned successfully",
+                "accounts": accounts,
+            }))
+        }
+        Err(e) => HttpResponse::BadRequest().json(json!({
+            "message": e
         })),
-        Err(e) => HttpResponse::InternalServerError().json(e.to_string()),
     }
 }
 
 #[derive(Deserialize, Debug, Clone)]
-pub struct CreateBlockchainReq {
-    pub config: Option<Uuid>,
-    pub defer_account_initailization: Option<bool>,
+pub struct SetProgramIdlReq {
+    pub idl: serde_json::Value,
+    pub authority: Option<String>,
+    pub on_chain: Option<bool>, // Also write Anchor's IDL account, defaults to true
 }
 
-#[post("/blockchains")]
-pub async fn create_blockchain(
+#[put("/programs/{id}/{program_id}/idl")]
+pub async fn set_program_idl(
     svm: web::Data<Arc<SvmEngine<PgStorage>>>,
+    path: web::Path<(Uuid, String)>,
     http_req: HttpRequest,
-    req: Option<web::Json<CreateBlockchainReq>>,
+    req: web::Json<SetProgramIdlReq>,
 ) -> impl Responder {
-    let team = match get_team(svm.clone(), http_req.clone()) {
-        Ok(team_id) => team_id,
+    let (id, program_id) = path.into_inner();
+    let blockchain = match svm.storage.get_blockchain(id) {
+        Ok(blockchain) => blockchain,
         Err(e) => {
+            return HttpResponse::InternalServerError().json(e.to_string());
+        }
+    };
+    let api_key = match team_api_key(blockchain.team_id, &svm, &http_req) {
+        Some(api_key) => api_key,
+        None => {
             return HttpResponse::Unauthorized().json(json!({
-                "message": e
-            }))
+                "message": "Invalid API key"
+            }));
         }
     };
-
-    let existing_blockchains = match svm.get_blockchains(team.id) {
-        Ok(blockchains) => blockchains,
-        Err(e) => return HttpResponse::InternalServerError().json(e.to_string()),
+    let program_id = match Pubkey::from_str(&program_id) {
+        Ok(program_id) => program_id,
+        Err(_) => {
+            return HttpResponse::BadRequest().json(json!({
+                "message": "Invalid program id"
+            }));
+        }
     };
-
-    if team.default_expiry == None && existing_blockchains.len() >= 10 {
+    if !req.idl.is_object() {
         return HttpResponse::BadRequest().json(json!({
-            "message": "You can only create 10 blockchains per team"
+            "message": "IDL must be a JSON object"
         }));
     }
-
-    let mut label = None;
-    if team.default_expiry.is_some() {
-        let user_id = match http_req.headers().get("user_id") {
-            Some(user_id) => match user_id.to_str() {
-                Ok(user_id) => user_id.to_string(),
-                Err(_) => {
-                    return HttpResponse::BadRequest().json(json!({
-                        "message": "Invalid user_id header"
-                    }))
-                }
-            },
-            None => {
+    let authority = match &req.authority {
+        Some(authority) => match Pubkey::from_str(authority) {
+            Ok(authority) => authority,
+            Err(_) => {
                 return HttpResponse::BadRequest().json(json!({
-                    "message": "Missing user_id header"
-                }))
+                    "message": "Invalid authority"
+                }));
             }
-        };
-        if user_id == "" {
-            return HttpResponse::BadRequest().json(json!({
-                "message": "user_id header cannot be empty"
-            }));
-        }
-        label = Some(user_id);
+        },
+        None => blockchain.airdrop_keypair.pubkey(),
+    };
+
+    let idl = req.idl.to_string();
+    if let Err(e) = svm.storage.set_program_idl(id, &program_id, idl.clone()) {
+        return HttpResponse::InternalServerError().json(e.to_string());
     }
-    let expiry = match team.default_expiry {
-        Some(expiry) => {
-            Some(chrono::Utc::now().naive_utc() + chrono::Duration::seconds(expiry as i64))
-        }
-        None => None,
+    audit(
+        &svm,
+        api_key,
+        blockchain.team_id,
+        Some(id),
+        audit::PROGRAM_IDL_SET,
+        json!({ "program_id": program_id.to_string(), "size": idl.len() }),
+    );
+    if !req.on_chain.unwrap_or(true) {
+        return HttpResponse::Ok().json(json!({
+            "message": "IDL saved successfully"
+        }));
+    }
+
+    let address = match idl::idl_address(&program_id) {
+        Ok(address) => address,
+        Err(e) => return HttpResponse::InternalServerError().json(e.to_string()),
     };
-    let config = match &req {
-        Some(req) => req.config,
-        None => None,
+    let data = match idl::encode_idl_account(&authority, idl.as_bytes()) {
+        Ok(data) => data,
+        Err(e) => return HttpResponse::InternalServerError().json(e.to_string()),
     };
-    let id = svm.create_blockchain(team.id, None, label, expiry, config);
-    match id {
-        Ok(id) => {
-            let mut base_url = "https://rpc.mirror.ad/rpc/";
-            if env::var("ENV").unwrap_or_else(|_| "prod".to_string()) == "dev" {
-                base_url = "http://localhost:8899/rpc/";
-            }
-            HttpResponse::Ok().json(json!({
-                "url": format!("{}{}",base_url, id.to_string())
-            }))
-        }
+    let account = Account {
+        lamports: svm.minimum_balance_for_rent_exemption(data.len()),
+        data,
+        owner: program_id,
+        executable: false,
+        rent_epoch: 0,
+    };
+    let accounts = [(address, account)];
+    if let Err(res) = check_account_quota(&svm, blockchain.team_id, id, &accounts) {
+        return res;
+    }
+    let [(address, account)] = accounts;
+    match svm.storage.set_account(id, &address, account, None) {
+        Ok(_) => HttpResponse::Ok().json(json!({
+            "message": "IDL saved successfully",
+            "address": address.to_string(),
+        })),
         Err(e) => HttpResponse::InternalServerError().json(e.to_string()),
     }
 }
 
 #[derive(Deserialize, Debug, Clone)]
-pub struct ConvertAccountToConfigReq {
-    pub account: String,
-    pub blockchain: Uuid,
-    pub config: Uuid,
+pub struct SetUpgradeAuthorityReq {
+    pub authority: String,
 }
 
-#[post("/accounts/convert")]
-pub async fn convert_account_to_config(
+#[put("/programs/{id}/{program_id}/upgrade_authority")]
+pub async fn set_upgrade_authority(
     svm: web::Data<Arc<SvmEngine<PgStorage>>>,
-    req: web::Json<ConvertAccountToConfigReq>,
+    path: web::Path<(Uuid, String)>,
+    http_req: HttpRequest,
+    req: web::Json<SetUpgradeAuthorityReq>,
 ) -> impl Responder {
-    let pubkey = match Pubkey::from_str(&req.account) {
-        Ok(pubkey) => pubkey,
+    let (id, program_id) = path.into_inner();
+    let blockchain = match svm.storage.get_blockchain(id) {
+        Ok(blockchain) => blockchain,
+        Err(e) => {
+            return HttpResponse::InternalServerError().json(e.to_string());
+        }
+    };
+    let api_key = match team_api_key(blockchain.team_id, &svm, &http_req) {
+        Some(api_key) => api_key,
+        None => {
+            return HttpResponse::Unauthorized().json(json!({
+                "message": "Invalid API key"
+            }));
+        }
+    };
+    let program_id = match Pubkey::from_str(&program_id) {
+        Ok(program_id) => program_id,
         Err(_) => {
             return HttpResponse::BadRequest().json(json!({
-                "message": "Invalid account address"
+                "message": "Invalid program id"
             }));
         }
     };
-
+    let authority = match Pubkey::from_str(&req.authority) {
+        Ok(authority) => authority,
+        Err(_) => {
+            return HttpResponse::BadRequest().json(json!({
+                "message": "Invalid authority"
+            }));
+        }
+    };
+
+    match svm.set_upgrade_authority(id, &program_id, &authority).await {
+        Ok(programdata) => {
+            audit(
+                &svm,
+                api_key,
+                blockchain.team_id,
+                Some(id),
+                audit::PROGRAM_AUTHORITY_SET,
+                json!({
+                    "program_id": program_id.to_string(),
+                    "authority": authority.to_string(),
+                }),
+            );
+            HttpResponse::Ok().json(json!({
+                "message": "Upgrade authority set successfully",
+                "program_data": programdata.to_string(),
+            }))
+        }
+        Err(e) => HttpResponse::BadRequest().json(json!({
+            "message": e
+        })),
+    }
+}
+
+#[get("/programs/{id}/{program_id}/idl")]
+pub async fn get_program_idl(
+    svm: web::Data<Arc<SvmEngine<PgStorage>>>,
+    path: web::Path<(Uuid, String)>,
+    http_req: HttpRequest,
+) -> impl Responder {
+    let (id, program_id) = path.into_inner();
+    let blockchain = match svm.storage.get_blockchain(id) {
+        Ok(blockchain) => blockchain,
+        Err(e) => {
+            return HttpResponse::InternalServerError().json(e.to_string());
+        }
+    };
+    if !valid_api_key(blockchain.team_id, svm.clone(), http_req) {
+        return HttpResponse::Unauthorized().json(json!({
+            "message": "Invalid API key"
+        }));
+    }
+    let program_id = match Pubkey::from_str(&program_id) {
+        Ok(program_id) => program_id,
+        Err(_) => {
+            return HttpResponse::BadRequest().json(json!({
+                "message": "Invalid program id"
+            }));
+        }
+    };
+
+    let idl = match svm.storage.get_program_idl(id, &program_id) {
+        Ok(Some(idl)) => idl.into_bytes(),
+        Ok(None) => {
+            // Programs cloned from mainnet may carry their IDL account instead
+            let address = match idl::idl_address(&program_id) {
+                Ok(address) => address,
+                Err(e) => return HttpResponse::InternalServerError().json(e.to_string()),
+            };
+            match svm.storage.get_account(id, &address) {
+                Ok(Some(account)) => match idl::decode_idl_account(&account.data) {
+                    Ok((_, idl)) => idl,
+                    Err(e) => return HttpResponse::InternalServerError().json(e.to_string()),
+                },
+                Ok(None) => {
+                    return HttpResponse::NotFound().json(json!({
+                        "message": "IDL not found"
+                    }))
+                }
+                Err(e) => return HttpResponse::InternalServerError().json(e.to_string()),
+            }
+        }
+        Err(e) => return HttpResponse::InternalServerError().json(e.to_string()),
+    };
+
+    HttpResponse::Ok()
+        .content_type("application/json")
+        .body(idl)
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct ProgramLogsReq {
+    pub start: Option<chrono::NaiveDateTime>,
+    pub end: Option<chrono::NaiveDateTime>,
+    pub limit: Option<i64>,
+}
+
+#[get("/programs/{id}/{program_id}/logs")]
+pub async fn get_program_logs(
+    svm: web::Data<Arc<SvmEngine<PgStorage>>>,
+    path: web::Path<(Uuid, String)>,
+    http_req: HttpRequest,
+    req: web::Query<ProgramLogsReq>,
+) -> impl Responder {
+    let (id, program_id) = path.into_inner();
+    let blockchain = match svm.storage.get_blockchain(id) {
+        Ok(blockchain) => blockchain,
+        Err(e) => {
+            return HttpResponse::InternalServerError().json(e.to_string());
+        }
+    };
+    if !valid_api_key(blockchain.team_id, svm.clone(), http_req) {
+        return HttpResponse::Unauthorized().json(json!({
+            "message": "Invalid API key"
+        }));
+    }
+    let program_id = match Pubkey::from_str(&program_id) {
+        Ok(program_id) => program_id,
+        Err(_) => {
+            return HttpResponse::BadRequest().json(json!({
+                "message": "Invalid program id"
+            }));
+        }
+    };
+    let limit = req.limit.unwrap_or(DEFAULT_TRANSACTION_PAGE_SIZE);
+    if !(1..=MAX_TRANSACTION_PAGE_SIZE).contains(&limit) {
+        return HttpResponse::BadRequest().json(json!({
+            "message": format!("limit must be between 1 and {}", MAX_TRANSACTION_PAGE_SIZE)
+        }));
+    }
+    let start = req.start.unwrap_or(blockchain.created_at);
+    let end = req.end.unwrap_or_else(|| chrono::Utc::now().naive_utc());
+
+    match svm.get_program_logs(id, &program_id, start, end, limit) {
+        Ok(logs) => HttpResponse::Ok().json(json!({
+            "logs": logs.iter().map(|log| json!({
+                "signature": log.transaction_signature,
+                "created_at": log.created_at,
+                "index": log.index,
+                "log": log.log,
+            })).collect::<Vec<_>>(),
+        })),
+        Err(e) => HttpResponse::InternalServerError().json(e.to_string()),
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct AirdropReq {
+    pub address: String,
+    pub lamports: u64,
+}
+
+#[post("/airdrop/{id}")]
+pub async fn airdrop(
+    svm: web::Data<Arc<SvmEngine<PgStorage>>>,
+    path: web::Path<Uuid>,
+    http_req: HttpRequest,
+    req: web::Json<Vec<AirdropReq>>,
+) -> impl Responder {
+    let id = path.into_inner();
+    let blockchain = match svm.storage.get_blockchain(id) {
+        Ok(blockchain) => blockchain,
+        Err(e) => {
+            return HttpResponse::InternalServerError().json(e.to_string());
+        }
+    };
+    let api_key = match team_api_key(blockchain.team_id, &svm, &http_req) {
+        Some(api_key) => api_key,
+        None => {
+            return HttpResponse::Unauthorized().json(json!({
+                "message": "Invalid API key"
+            }));
+        }
+    };
+
+    let recipients = match req
+        .iter()
+        .map(|recipient| {
+            Pubkey::from_str(&recipient.address)
+                .map(|address| (address, recipient.lamports))
+                .map_err(|_| format!("Invalid address {}", recipient.address))
+        })
+        .collect::<Result<Vec<_>, String>>()
+    {
+        Ok(recipients) => recipients,
+        Err(e) => {
+            return HttpResponse::BadRequest().json(json!({
+                "message": e
+            }));
+        }
+    };
+    if recipients.is_empty() || recipients.len() > MAX_AIRDROP_BATCH {
+        return HttpResponse::BadRequest().json(json!({
+            "message": format!("Between 1 and {} recipients are required", MAX_AIRDROP_BATCH)
+        }));
+    }
+
+    match svm.airdrop_batch(id, &recipients).await {
+        Ok(signature) => {
+            audit(
+                &svm,
+                api_key,
+                blockchain.team_id,
+                Some(id),
+                audit::AIRDROP,
+                json!({
+                    "signature": signature,
+                    "recipients": recipients.len(),
+                    "lamports": recipients.iter().map(|(_, lamports)| *lamports as u128).sum::<u128>().to_string(),
+                }),
+            );
+            HttpResponse::Ok().json(json!({
+                "message": "Airdrop successful",
+                "signature": signature,
+            }))
+        }
+        Err(e) => HttpResponse::InternalServerError().json(e.to_string()),
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct MintTokensReq {
+    pub owner: String,
+    pub amount: u64,
+    pub mint: Option<String>, // Existing mint, a new one is created when omitted
+    pub decimals: Option<u8>,
+}
+
+#[post("/tokens/{id}/mint")]
+pub async fn mint_tokens(
+    svm: web::Data<Arc<SvmEngine<PgStorage>>>,
+    path: web::Path<Uuid>,
+    http_req: HttpRequest,
+    req: web::Json<MintTokensReq>,
+) -> impl Responder {
+    let id = path.into_inner();
+    let blockchain = match svm.storage.get_blockchain(id) {
+        Ok(blockchain) => blockchain,
+        Err(e) => {
+            return HttpResponse::InternalServerError().json(e.to_string());
+        }
+    };
+    let api_key = match team_api_key(blockchain.team_id, &svm, &http_req) {
+        Some(api_key) => api_key,
+        None => {
+            return HttpResponse::Unauthorized().json(json!({
+                "message": "Invalid API key"
+            }));
+        }
+    };
+    let owner = match Pubkey::from_str(&req.owner) {
+        Ok(owner) => owner,
+        Err(_) => {
+            return HttpResponse::BadRequest().json(json!({
+                "message": "Invalid owner"
+            }));
+        }
+    };
+    let mint = match &req.mint {
+        Some(mint) => match Pubkey::from_str(mint) {
+            Ok(mint) => Some(mint),
+            Err(_) => {
+                return HttpResponse::BadRequest().json(json!({
+                    "message": "Invalid mint"
+                }));
+            }
+        },
+        None => None,
+    };
+    if mint.is_some() && req.decimals.is_some() {
+        return HttpResponse::BadRequest().json(json!({
+            "message": "decimals can only be set when creating a mint"
+        }));
+    }
+
+    match svm
+        .mint_tokens(id, mint, req.decimals.unwrap_or(9), &owner, req.amount)
+        .await
+    {
+        Ok((mint, token_account)) => {
+            audit(
+                &svm,
+                api_key,
+                blockchain.team_id,
+                Some(id),
+                audit::TOKEN_MINT,
+                json!({
+                    "mint": mint.to_string(),
+                    "owner": owner.to_string(),
+                    "amount": req.amount.to_string(),
+                }),
+            );
+            HttpResponse::Ok().json(json!({
+                "message": "Tokens minted successfully",
+                "mint": mint.to_string(),
+                "token_account": token_account.to_string(),
+            }))
+        }
+        Err(e) => HttpResponse::BadRequest().json(json!({
+            "message": e
+        })),
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct SetMintAuthorityReq {
+    pub mint_authority: Option<String>,
+    pub freeze_authority: Option<String>,
+}
+
+#[put("/tokens/{id}/{mint}/authority")]
+pub async fn set_mint_authority(
+    svm: web::Data<Arc<SvmEngine<PgStorage>>>,
+    path: web::Path<(Uuid, String)>,
+    http_req: HttpRequest,
+    req: web::Json<SetMintAuthorityReq>,
+) -> impl Responder {
+    let (id, mint) = path.into_inner();
+    let blockchain = match svm.storage.get_blockchain(id) {
+        Ok(blockchain) => blockchain,
+        Err(e) => {
+            return HttpResponse::InternalServerError().json(e.to_string());
+        }
+    };
+    let api_key = match team_api_key(blockchain.team_id, &svm, &http_req) {
+        Some(api_key) => api_key,
+        None => {
+            return HttpResponse::Unauthorized().json(json!({
+                "message": "Invalid API key"
+            }));
+        }
+    };
+    let mint = match Pubkey::from_str(&mint) {
+        Ok(mint) => mint,
+        Err(_) => {
+            return HttpResponse::BadRequest().json(json!({
+                "message": "Invalid mint"
+            }));
+        }
+    };
+    let mut authorities = [None, None];
+    for (authority, value) in authorities
+        .iter_mut()
+        .zip([&req.mint_authority, &req.freeze_authority])
+    {
+        if let Some(value) = value {
+            match Pubkey::from_str(value) {
+                Ok(pubkey) => *authority = Some(pubkey),
+                Err(_) => {
+                    return HttpResponse::BadRequest().json(json!({
+                        "message": "Invalid authority"
+                    }));
+                }
+            }
+        }
+    }
+    let [mint_authority, freeze_authority] = authorities;
+
+    match svm
+        .set_mint_authorities(id, &mint, mint_authority, freeze_authority)
+        .await
+    {
+        Ok(()) => {
+            audit(
+                &svm,
+                api_key,
+                blockchain.team_id,
+                Some(id),
+                audit::TOKEN_AUTHORITY_SET,
+                json!({
+                    "mint": mint.to_string(),
+                    "mint_authority": req.mint_authority,
+                    "freeze_authority": req.freeze_authority,
+                }),
+            );
+            HttpResponse::Ok().json(json!({
+                "message": "Mint authority set successfully",
+                "mint": mint.to_string(),
+            }))
+        }
+        Err(e) => HttpResponse::BadRequest().json(json!({
+            "message": e
+        })),
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct CreateWebhookReq {
+    pub url: String,
+    pub events: Vec<String>,
+    pub accounts: Option<Vec<String>>,
+    pub labels: Option<Vec<String>>,
+}
+
+#[post("/webhooks/{id}")]
+pub async fn create_webhook(
+    svm: web::Data<Arc<SvmEngine<PgStorage>>>,
+    path: web::Path<Uuid>,
+    http_req: HttpRequest,
+    req: web::Json<CreateWebhookReq>,
+) -> impl Responder {
+    let id = path.into_inner();
+    let blockchain = match svm.storage.get_blockchain(id) {
+        Ok(blockchain) => blockchain,
+        Err(e) => {
+            return HttpResponse::InternalServerError().json(e.to_string());
+        }
+    };
+    let api_key = match team_api_key(blockchain.team_id, &svm, &http_req) {
+        Some(api_key) => api_key,
+        None => {
+            return HttpResponse::Unauthorized().json(json!({
+                "message": "Invalid API key"
+            }));
+        }
+    };
+    if let Err(e) = webhooks::validate_url(&req.url).await {
+        return HttpResponse::BadRequest().json(json!({
+            "message": e
+        }));
+    }
+    if req.events.is_empty()
+        || req
+            .events
+            .iter()
+            .any(|event| !webhooks::WEBHOOK_EVENTS.contains(&event.as_str()))
+    {
+        return HttpResponse::BadRequest().json(json!({
+            "message": format!("events must be a subset of {:?}", webhooks::WEBHOOK_EVENTS)
+        }));
+    }
+    let accounts = req.accounts.clone().unwrap_or_default();
+    if accounts
+        .iter()
+        .any(|address| Pubkey::from_str(address).is_err())
+    {
+        return HttpResponse::BadRequest().json(json!({
+            "message": "Invalid account address"
+        }));
+    }
+    let labels = req.labels.clone().unwrap_or_default();
+    if labels.iter().any(|label| label.trim().is_empty()) {
+        return HttpResponse::BadRequest().json(json!({
+            "message": "labels cannot be empty"
+        }));
+    }
+
+    let webhook = DbWebhook {
+        id: Uuid::new_v4(),
+        created_at: chrono::Utc::now().naive_utc(),
+        team_id: blockchain.team_id,
+        blockchain: id,
+        url: req.url.clone(),
+        secret: hex::encode(rand::random::<[u8; 32]>()),
+        events: req.events.clone(),
+        accounts,
+        labels,
+    };
+    match svm.storage.create_webhook(&webhook) {
+        Ok(_) => {
+            audit(
+                &svm,
+                api_key,
+                blockchain.team_id,
+                Some(id),
+                audit::WEBHOOK_CREATE,
+                json!({ "webhook": webhook.id, "url": webhook.url, "events": webhook.events }),
+            );
+            // The secret is only returned once, receivers use it to verify signatures
+            HttpResponse::Ok().json(json!({
+                "id": webhook.id,
+                "url": webhook.url,
+                "events": webhook.events,
+                "accounts": webhook.accounts,
+                "labels": webhook.labels,
+                "secret": webhook.secret,
+            }))
+        }
+        Err(e) => HttpResponse::InternalServerError().json(e.to_string()),
+    }
+}
+
+#[get("/webhooks/{id}")]
+pub async fn get_webhooks(
+    svm: web::Data<Arc<SvmEngine<PgStorage>>>,
+    path: web::Path<Uuid>,
+    http_req: HttpRequest,
+) -> impl Responder {
+    let id = path.into_inner();
+    let blockchain = match svm.storage.get_blockchain(id) {
+        Ok(blockchain) => blockchain,
+        Err(e) => {
+            return HttpResponse::InternalServerError().json(e.to_string());
+        }
+    };
+    if !valid_api_key(blockchain.team_id, svm.clone(), http_req) {
+        return HttpResponse::Unauthorized().json(json!({
+            "message": "Invalid API key"
+        }));
+    }
+
+    match svm.storage.get_webhooks(id) {
+        Ok(webhooks) => HttpResponse::Ok().json(
+            webhooks
+                .into_iter()
+                .map(|webhook| {
+                    json!({
+                        "id": webhook.id,
+                        "created_at": webhook.created_at,
+                        "url": webhook.url,
+                        "events": webhook.events,
+                        "accounts": webhook.accounts,
+                        "labels": webhook.labels,
+                    })
+                })
+                .collect::<Vec<_>>(),
+        ),
+        Err(e) => HttpResponse::InternalServerError().json(e.to_string()),
+    }
+}
+
+#[delete("/webhooks/{id}/{webhook_id}")]
+pub async fn delete_webhook(
+    svm: web::Data<Arc<SvmEngine<PgStorage>>>,
+    path: web::Path<(Uuid, Uuid)>,
+    http_req: HttpRequest,
+) -> impl Responder {
+    let (id, webhook_id) = path.into_inner();
+    let blockchain = match svm.storage.get_blockchain(id) {
+        Ok(blockchain) => blockchain,
+        Err(e) => {
+            return HttpResponse::InternalServerError().json(e.to_string());
+        }
+    };
+    let api_key = match team_api_key(blockchain.team_id, &svm, &http_req) {
+        Some(api_key) => api_key,
+        None => {
+            return HttpResponse::Unauthorized().json(json!({
+                "message": "Invalid API key"
+            }));
+        }
+    };
+
+    match svm.storage.delete_webhook(id, webhook_id) {
+        Ok(true) => {
+            audit(
+                &svm,
+                api_key,
+                blockchain.team_id,
+                Some(id),
+                audit::WEBHOOK_DELETE,
+                json!({ "webhook": webhook_id }),
+            );
+            HttpResponse::Ok().json(json!({
+                "message": "Webhook deleted successfully"
+            }))
+        }
+        Ok(false) => HttpResponse::NotFound().json(json!({
+            "message": "Webhook not found"
+        })),
+        Err(e) => HttpResponse::InternalServerError().json(e.to_string()),
+    }
+}
+
+// Top-ups are run by a job, more often would only be rounded up to its interval
+const MIN_TOP_UP_INTERVAL_SECONDS: i64 = 10;
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct CreateTopUpReq {
+    pub address: String,
+    /// Tops up the address's associated token account of the mint instead of lamports
+    pub mint: Option<String>,
+    /// Lamports, or base units of the mint
+    pub target: u64,
+    pub interval_seconds: i64,
+}
+
+fn top_up_json(top_up: &DbTopUp) -> serde_json::Value {
+    json!({
+        "id": top_up.id,
+        "created_at": top_up.created_at,
+        "address": top_up.address,
+        "mint": top_up.mint,
+        "target": top_up.target.to_u64(),
+        "interval_seconds": top_up.interval_seconds,
+        "last_run": top_up.last_run,
+    })
+}
+
+/// Keeps an address's balance at the target, checking it every `interval_seconds`.
+#[post("/top_ups/{id}")]
+pub async fn create_top_up(
+    svm: web::Data<Arc<SvmEngine<PgStorage>>>,
+    path: web::Path<Uuid>,
+    http_req: HttpRequest,
+    req: web::Json<CreateTopUpReq>,
+) -> impl Responder {
+    let id = path.into_inner();
+    let blockchain = match svm.storage.get_blockchain(id) {
+        Ok(blockchain) => blockchain,
+        Err(e) => {
+            return HttpResponse::InternalServerError().json(e.to_string());
+        }
+    };
+    let api_key = match team_api_key(blockchain.team_id, &svm, &http_req) {
+        Some(api_key) => api_key,
+        None => {
+            return HttpResponse::Unauthorized().json(json!({
+                "message": "Invalid API key"
+            }));
+        }
+    };
+    if Pubkey::from_str(&req.address).is_err() {
+        return HttpResponse::BadRequest().json(json!({
+            "message": "Invalid account address"
+        }));
+    }
+    if let Some(mint) = &req.mint {
+        let mint = match Pubkey::from_str(mint) {
+            Ok(mint) => mint,
+            Err(_) => {
+                return HttpResponse::BadRequest().json(json!({
+                    "message": "Invalid mint"
+                }));
+            }
+        };
+        if let Err(e) = svm.get_mint_data_sync(id, &mint) {
+            return HttpResponse::BadRequest().json(json!({
+                "message": e
+            }));
+        }
+    }
+    if req.target == 0 {
+        return HttpResponse::BadRequest().json(json!({
+            "message": "target must be positive"
+        }));
+    }
+    if req.interval_seconds < MIN_TOP_UP_INTERVAL_SECONDS {
+        return HttpResponse::BadRequest().json(json!({
+            "message": format!("interval_seconds must be at least {}", MIN_TOP_UP_INTERVAL_SECONDS)
+        }));
+    }
+
+    let top_up = DbTopUp {
+        id: Uuid::new_v4(),
+        created_at: chrono::Utc::now().naive_utc(),
+        blockchain: id,
+        address: req.address.clone(),
+        mint: req.mint.clone(),
+        target: req.target.into(),
+        interval_seconds: req.interval_seconds,
+        last_run: None,
+    };
+    match svm.storage.create_top_up(&top_up) {
+        Ok(_) => {
+            audit(
+                &svm,
+                api_key,
+                blockchain.team_id,
+                Some(id),
+                audit::TOP_UP_CREATE,
+                top_up_json(&top_up),
+            );
+            HttpResponse::Ok().json(top_up_json(&top_up))
+        }
+        Err(e) => HttpResponse::InternalServerError().json(e.to_string()),
+    }
+}
+
+#[get("/top_ups/{id}")]
+pub async fn get_top_ups(
+    svm: web::Data<Arc<SvmEngine<PgStorage>>>,
+    path: web::Path<Uuid>,
+    http_req: HttpRequest,
+) -> impl Responder {
+    let id = path.into_inner();
+    let blockchain = match svm.storage.get_blockchain(id) {
+        Ok(blockchain) => blockchain,
+        Err(e) => {
+            return HttpResponse::InternalServerError().json(e.to_string());
+        }
+    };
+    if !valid_api_key(blockchain.team_id, svm.clone(), http_req) {
+        return HttpResponse::Unauthorized().json(json!({
+            "message": "Invalid API key"
+        }));
+    }
+
+    match svm.storage.get_top_ups(id) {
+        Ok(top_ups) => HttpResponse::Ok().json(top_ups.iter().map(top_up_json).collect::<Vec<_>>()),
+        Err(e) => HttpResponse::InternalServerError().json(e.to_string()),
+    }
+}
+
+#[delete("/top_ups/{id}/{top_up_id}")]
+pub async fn delete_top_up(
+    svm: web::Data<Arc<SvmEngine<PgStorage>>>,
+    path: web::Path<(Uuid, Uuid)>,
+    http_req: HttpRequest,
+) -> impl Responder {
+    let (id, top_up_id) = path.into_inner();
+    let blockchain = match svm.storage.get_blockchain(id) {
+        Ok(blockchain) => blockchain,
+        Err(e) => {
+            return HttpResponse::InternalServerError().json(e.to_string());
+        }
+    };
+    let api_key = match team_api_key(blockchain.team_id, &svm, &http_req) {
+        Some(api_key) => api_key,
+        None => {
+            return HttpResponse::Unauthorized().json(json!({
+                "message": "Invalid API key"
+            }));
+        }
+    };
+
+    match svm.storage.delete_top_up(id, top_up_id) {
+        Ok(true) => {
+            audit(
+                &svm,
+                api_key,
+                blockchain.team_id,
+                Some(id),
+                audit::TOP_UP_DELETE,
+                json!({ "top_up": top_up_id }),
+            );
+            HttpResponse::Ok().json(json!({
+                "message": "Top-up deleted successfully"
+            }))
+        }
+        Ok(false) => HttpResponse::NotFound().json(json!({
+            "message": "Top-up not found"
+        })),
+        Err(e) => HttpResponse::InternalServerError().json(e.to_string()),
+    }
+}
+
+/// Account data, either a base64 string or a `[data, encoding]` pair as returned by
+/// `getAccountInfo` and `solana account --output json`.
+#[derive(Deserialize)]
+#[serde(untagged)]
+pub enum AccountData {
+    Base64(String),
+    Encoded(String, String),
+}
+
+impl Default for AccountData {
+    fn default() -> Self {
+        AccountData::Base64(String::new())
+    }
+}
+
+pub struct AccountReq {
+    address: String,
+    lamports: u64,
+    data: AccountData,
+    encoding: Option<String>,
+    owner: String,
+    rent_epoch: u64,
+    executable: bool,
+    token_mint_auth: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct FlatAccountReq {
+    address: String,
+    lamports: u64,
+    // Left out when the data is uploaded as a file
+    #[serde(default)]
+    data: AccountData,
+    encoding: Option<String>,
+    owner: String,
+    rent_epoch: u64,
+    executable: bool,
+    token_mint_auth: Option<String>,
+}
+
+/// Output of `solana account --output json`.
+#[derive(Deserialize)]
+struct SolanaCliAccountReq {
+    pubkey: String,
+    account: SolanaCliAccount,
+    token_mint_auth: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SolanaCliAccount {
+    lamports: u64,
+    data: AccountData,
+    owner: String,
+    executable: bool,
+    rent_epoch: u64,
+}
+
+impl<'de> Deserialize<'de> for AccountReq {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        use serde::de::Error as _;
+
+        // Dispatch on the shape, so errors name the field that's wrong
+        let value = serde_json::Value::deserialize(deserializer)?;
+        if value.get("pubkey").is_some() && value.get("account").is_some() {
+            let req: SolanaCliAccountReq =
+                serde_json::from_value(value).map_err(D::Error::custom)?;
+            return Ok(AccountReq {
+                address: req.pubkey,
+                lamports: req.account.lamports,
+                data: req.account.data,
+                encoding: None,
+                owner: req.account.owner,
+                rent_epoch: req.account.rent_epoch,
+                executable: req.account.executable,
+                token_mint_auth: req.token_mint_auth,
+            });
+        }
+        let req: FlatAccountReq = serde_json::from_value(value).map_err(D::Error::custom)?;
+        Ok(AccountReq {
+            address: req.address,
+            lamports: req.lamports,
+            data: req.data,
+            encoding: req.encoding,
+            owner: req.owner,
+            rent_epoch: req.rent_epoch,
+            executable: req.executable,
+            token_mint_auth: req.token_mint_auth,
+        })
+    }
+}
+
+fn decode_account_data(account: &AccountReq) -> Result<Vec<u8>, String> {
+    let (data, encoding) = match &account.data {
+        AccountData::Base64(data) => (data, account.encoding.as_deref().unwrap_or("base64")),
+        AccountData::Encoded(data, encoding) => (data, encoding.as_str()),
+    };
+    match encoding {
+        "base64" => BASE64_STANDARD
+            .decode(data)
+            .map_err(|_| "Invalid base64 data".to_string()),
+        "base58" => bs58::decode(data)
+            .into_vec()
+            .map_err(|_| "Invalid base58 data".to_string()),
+        _ => Err(format!(
+            "Unsupported encoding {}, expected base64 or base58",
+            encoding
+        )),
+    }
+}
+
+/// Rejects accounts the runtime could never have produced, so they fail on load rather
+/// than on first use.
+fn validate_account(address: &Pubkey, account: &Account) -> Result<(), String> {
+    if account.data.len() > MAX_PERMITTED_DATA_LENGTH as usize {
+        return Err(format!(
+            "Account {} data is larger than {} bytes",
+            address, MAX_PERMITTED_DATA_LENGTH
+        ));
+    }
+    if !account.executable {
+        return Ok(());
+    }
+    if bpf_loader_upgradeable::check_id(&account.owner) {
+        return match bincode::deserialize(&account.data) {
+            Ok(UpgradeableLoaderState::Program { .. }) => Ok(()),
+            _ => Err(format!(
+                "Executable account {} owned by the upgradeable loader must be a Program account",
+                address
+            )),
+        };
+    }
+    if !bpf_loader::check_id(&account.owner) && !bpf_loader_deprecated::check_id(&account.owner) {
+        return Err(format!(
+            "Executable account {} must be owned by a BPF loader, not {}",
+            address, account.owner
+        ));
+    }
+    Ok(())
+}
+
+fn parse_account_req(account: &AccountReq) -> Result<(Pubkey, Account), String> {
+    let data = decode_account_data(account)?;
+    account_from_req(account, data)
+}
+
+fn account_from_req(account: &AccountReq, mut data: Vec<u8>) -> Result<(Pubkey, Account), String> {
+    if let Some(auth) = &account.token_mint_auth {
+        let token_mint_signer = match Pubkey::from_str(auth) {
+            Ok(token_mint_signer) => token_mint_signer,
+            Err(_) => {
+                return Err("Invalid token mint signer".to_string());
+            }
+        };
+        set_mint_authorities(&mut data, Some(token_mint_signer), None)?;
+    }
+
+    let owner = match Pubkey::from_str(&account.owner) {
+        Ok(owner) => owner,
+        Err(_) => {
+            return Err("Invalid owner".to_string());
+        }
+    };
+    let address = match Pubkey::from_str(&account.address) {
+        Ok(address) => address,
+        Err(_) => {
+            return Err("Invalid address".to_string());
+        }
+    };
+    let account = Account {
+        lamports: account.lamports,
+        data,
+        owner,
+        rent_epoch: account.rent_epoch,
+        executable: account.executable,
+    };
+    validate_account(&address, &account)?;
+    Ok((address, account))
+}
+
+#[put("/accounts/{id}")]
+pub async fn load_account(
+    accounts_req: web::Json<Vec<AccountReq>>,
+    svm: web::Data<Arc<SvmEngine<PgStorage>>>,
+    path: web::Path<Uuid>,
+    http_req: HttpRequest,
+) -> impl Responder {
+    let id = path.into_inner();
+    let blockchain = match svm.storage.get_blockchain(id) {
+        Ok(blockchain) => blockchain,
+        Err(e) => {
+            return HttpResponse::InternalServerError().json(e.to_string());
+        }
+    };
+    let api_key = match auth::check_blockchain_write(&svm.storage, &http_req, &blockchain) {
+        Ok(api_key) => api_key.map(|api_key| api_key.id),
+        Err((status, e)) => return HttpResponse::build(status).json(json!({ "message": e })),
+    };
+
+    let accounts = accounts_req.iter().map(parse_account_req);
+
+    let accounts: Vec<(Pubkey, Account)> = match accounts.collect() {
+        Ok(accounts) => accounts,
+        Err(e) => {
+            return HttpResponse::BadRequest().json(json!({
+                "message": e
+            }));
+        }
+    };
+
+    if let Err(res) = check_account_quota(&svm, blockchain.team_id, id, &accounts) {
+        return res;
+    }
+    let addresses = accounts
+        .iter()
+        .map(|(address, _)| address.to_string())
+        .collect::<Vec<_>>();
+    match svm.storage.set_accounts(id, accounts) {
+        Ok(_) => {
+            audit(
+                &svm,
+                api_key,
+                blockchain.team_id,
+                Some(id),
+                audit::ACCOUNT_LOAD,
+                json!({ "addresses": addresses }),
+            );
+            HttpResponse::Ok().json(json!({
+                "message": "Account loaded successfully"
+            }))
+        }
+        Err(e) => HttpResponse::InternalServerError().json(e.to_string()),
+    }
+}
+
+fn is_multipart(ctx: &GuardContext) -> bool {
+    ctx.head()
+        .headers()
+        .get("content-type")
+        .and_then(|content_type| content_type.to_str().ok())
+        .map(|content_type| content_type.starts_with("multipart/form-data"))
+        .unwrap_or(false)
+}
+
+const MAX_ACCOUNT_FIELD: usize = 64 * 1024;
+
+/// Loads one account whose data is uploaded as a file, the `account` field holds the
+/// rest of the account as JSON.
+#[put("/accounts/{id}", guard = "is_multipart")]
+pub async fn upload_account(
+    mut payload: Multipart,
+    svm: web::Data<Arc<SvmEngine<PgStorage>>>,
+    path: web::Path<Uuid>,
+    http_req: HttpRequest,
+) -> impl Responder {
+    let id = path.into_inner();
+    let blockchain = match svm.storage.get_blockchain(id) {
+        Ok(blockchain) => blockchain,
+        Err(e) => {
+            return HttpResponse::InternalServerError().json(e.to_string());
+        }
+    };
+    let api_key = match team_api_key(blockchain.team_id, &svm, &http_req) {
+        Some(api_key) => api_key,
+        None => {
+            return HttpResponse::Unauthorized().json(json!({
+                "message": "Invalid API key"
+            }));
+        }
+    };
+
+    let mut account_json = Vec::new();
+    let mut data = None;
+    while let Some(item) = payload.next().await {
+        let mut field = match item {
+            Ok(i) => i,
+            Err(e) => {
+                return HttpResponse::BadRequest().json(json!({
+                    "message": e.to_string()
+                }));
+            }
+        };
+        let name = field.name().map(|name| name.to_string());
+        while let Some(chunk) = field.next().await {
+            let chunk = match chunk {
+                Ok(chunk) => chunk,
+                Err(e) => {
+                    return HttpResponse::BadRequest().json(json!({
+                        "message": e.to_string()
+                    }));
+                }
+            };
+            let (buffer, limit) = match name.as_deref() {
+                Some("account") => (&mut account_json, MAX_ACCOUNT_FIELD),
+                Some("data") => (
+                    data.get_or_insert_with(Vec::new),
+                    MAX_PERMITTED_DATA_LENGTH as usize,
+                ),
+                _ => continue,
+            };
+            if buffer.len() + chunk.len() > limit {
+                return HttpResponse::PayloadTooLarge().json(json!({
+                    "message": format!("{} is larger than {} bytes", name.unwrap_or_default(), limit)
+                }));
+            }
+            buffer.extend_from_slice(&chunk);
+        }
+    }
+
+    let account_req: AccountReq = match serde_json::from_slice(&account_json) {
+        Ok(account_req) => account_req,
+        Err(e) => {
+            return HttpResponse::BadRequest().json(json!({
+                "message": format!("Invalid account: {}", e)
+            }));
+        }
+    };
+    let data = match data {
+        Some(data) => match &account_req.data {
+            AccountData::Base64(encoded) if encoded.is_empty() => data,
+            _ => {
+                return HttpResponse::BadRequest().json(json!({
+                    "message": "Account data must be either uploaded or inline, not both"
+                }));
+            }
+        },
+        None => match decode_account_data(&account_req) {
+            Ok(data) => data,
+            Err(e) => {
+                return HttpResponse::BadRequest().json(json!({
+                    "message": e
+                }));
+            }
+        },
+    };
+    let (address, account) = match account_from_req(&account_req, data) {
+        Ok(account) => account,
+        Err(e) => {
+            return HttpResponse::BadRequest().json(json!({
+                "message": e
+            }));
+        }
+    };
+
+    let accounts = vec![(address, account)];
+    if let Err(res) = check_account_quota(&svm, blockchain.team_id, id, &accounts) {
+        return res;
+    }
+    let size = accounts[0].1.data.len();
+    match svm.storage.set_accounts(id, accounts) {
+        Ok(_) => {
+            audit(
+                &svm,
+                api_key,
+                blockchain.team_id,
+                Some(id),
+                audit::ACCOUNT_LOAD,
+                json!({ "addresses": [address.to_string()], "size": size }),
+            );
+            HttpResponse::Ok().json(json!({
+                "message": "Account loaded successfully"
+            }))
+        }
+        Err(e) => HttpResponse::InternalServerError().json(e.to_string()),
+    }
+}
+
+// Accounts written per set_accounts call, or fewer once the batch holds this many bytes
+const IMPORT_BATCH_SIZE: usize = 1000;
+const IMPORT_BATCH_BYTES: usize = 8 * 1024 * 1024;
+// A 10 MiB account is roughly 14 MiB once base64 encoded
+const MAX_IMPORT_LINE: usize = 16 * 1024 * 1024;
+const MAX_IMPORT_ERRORS: usize = 100;
+const IMPORT_FLUSH_TIMEOUT: Duration = Duration::from_secs(30);
+
+fn is_ndjson(ctx: &GuardContext) -> bool {
+    ctx.head()
+        .headers()
+        .get("content-type")
+        .and_then(|content_type| content_type.to_str().ok())
+        .map(|content_type| content_type.starts_with("application/x-ndjson"))
+        .unwrap_or(false)
+}
+
+/// Outcome of an NDJSON import, invalid lines are skipped and reported.
+#[derive(Default)]
+pub struct AccountImport {
+    pub lines: usize,
+    pub imported: usize,
+    pub failed: usize,
+    pub errors: Vec<serde_json::Value>,
+    /// Parsed accounts not written yet.
+    pub batch: Vec<(Pubkey, Account)>,
+    batch_bytes: usize,
+    // The start of a line that's still being received
+    pending: Vec<u8>,
+}
+
+impl AccountImport {
+    /// Parses the complete lines the chunk ends, keeping the rest for the next one.
+    pub fn push_chunk(&mut self, chunk: &[u8]) -> Result<(), String> {
+        let mut pending = std::mem::take(&mut self.pending);
+        // What's pending has no newline, only the chunk needs searching
+        let mut scanned = pending.len();
+        pending.extend_from_slice(chunk);
+        let mut start = 0;
+        while let Some(end) = pending[scanned..].iter().position(|b| *b == b'\n') {
+            self.push_line(&pending[start..scanned + end]);
+            start = scanned + end + 1;
+            scanned = start;
+        }
+        pending.drain(..start);
+        self.pending = pending;
+        if self.pending.len() > MAX_IMPORT_LINE {
+            return Err(format!(
+                "Line {} is longer than {} bytes",
+                self.lines + 1,
+                MAX_IMPORT_LINE
+            ));
+        }
+        Ok(())
+    }
+
+    /// Parses the last line, which doesn't need a trailing newline.
+    pub fn finish(&mut self) {
+        let line = std::mem::take(&mut self.pending);
+        if !line.is_empty() {
+            self.push_line(&line);
+        }
+    }
+
+    fn push_line(&mut self, line: &[u8]) {
+        self.lines += 1;
+        if line.iter().all(|b| b.is_ascii_whitespace()) {
+            return;
+        }
+        let account = serde_json::from_slice::<AccountReq>(line)
+            .map_err(|e| e.to_string())
+            .and_then(|account| parse_account_req(&account));
+        match account {
+            Ok(account) => {
+                self.batch_bytes += account.1.data.len();
+                self.batch.push(account);
+            }
+            Err(e) => {
+                self.failed += 1;
+                if self.errors.len() < MAX_IMPORT_ERRORS {
+                    self.errors
+                        .push(json!({ "line": self.lines, "message": e }));
+                }
+            }
+        }
+    }
+
+    fn batch_full(&self) -> bool {
+        self.batch.len() >= IMPORT_BATCH_SIZE || self.batch_bytes >= IMPORT_BATCH_BYTES
+    }
+
+    async fn write_batch(&mut self, svm: &SvmEngine<PgStorage>, id: Uuid) -> Result<(), String> {
+        if self.batch.is_empty() {
+            return Ok(());
+        }
+        let batch = std::mem::take(&mut self.batch);
+        let count = batch.len();
+        self.batch_bytes = 0;
+        svm.storage.set_accounts(id, batch)?;
+        // Keep at most one batch of write-behind inserts in memory
+        svm.storage.flush(IMPORT_FLUSH_TIMEOUT).await;
+        self.imported += count;
+        Ok(())
+    }
+
+    fn summary(&self) -> serde_json::Value {
+        json!({
+            "lines": self.lines,
+            "imported": self.imported,
+            "failed": self.failed,
+            "errors": self.errors,
+        })
+    }
+}
+
+/// Streams newline delimited accounts, in the same format as `PUT /accounts/{id}`, into
+/// storage in batches.
+#[put("/accounts/{id}", guard = "is_ndjson")]
+pub async fn import_accounts(
+    mut payload: web::Payload,
+    svm: web::Data<Arc<SvmEngine<PgStorage>>>,
+    path: web::Path<Uuid>,
+    http_req: HttpRequest,
+) -> impl Responder {
+    let id = path.into_inner();
+    let blockchain = match svm.storage.get_blockchain(id) {
+        Ok(blockchain) => blockchain,
+        Err(e) => {
+            return HttpResponse::InternalServerError().json(e.to_string());
+        }
+    };
+    let api_key = match team_api_key(blockchain.team_id, &svm, &http_req) {
+        Some(api_key) => api_key,
+        None => {
+            return HttpResponse::Unauthorized().json(json!({
+                "message": "Invalid API key"
+            }));
+        }
+    };
+
+    let mut import = AccountImport::default();
+    let mut stream_error = None;
+    let mut storage_error = None;
+    loop {
+        let chunk = match payload.next().await {
+            Some(Ok(chunk)) => chunk,
+            Some(Err(e)) => {
+                stream_error = Some(e.to_string());
+                break;
+            }
+            None => break,
+        };
+        if let Err(e) = import.push_chunk(&chunk) {
+            stream_error = Some(e);
+            break;
+        }
+        if import.batch_full() {
+            match account_quota_exceeded(&svm, blockchain.team_id, id, &import.batch) {
+                Ok(None) => {}
+                Ok(Some(e)) => {
+                    stream_error = Some(e);
+                    break;
+                }
+                Err(e) => {
+                    storage_error = Some(e);
+                    break;
+                }
+            }
+            if let Err(e) = import.write_batch(&svm, id).await {
+                storage_error = Some(e);
+                break;
+            }
+        }
+    }
+    if stream_error.is_none() && storage_error.is_none() {
+        import.finish();
+        match account_quota_exceeded(&svm, blockchain.team_id, id, &import.batch) {
+            Ok(None) => {
+                if let Err(e) = import.write_batch(&svm, id).await {
+                    storage_error = Some(e);
+                }
+            }
+            Ok(Some(e)) => stream_error = Some(e),
+            Err(e) => storage_error = Some(e),
+        }
+    }
+
+    if import.imported > 0 {
+        audit(
+            &svm,
+            api_key,
+            blockchain.team_id,
+            Some(id),
+            audit::ACCOUNT_LOAD,
+            json!({ "format": "ndjson", "imported": import.imported }),
+        );
+    }
+    // Batches written before a failure stay imported
+    let mut summary = import.summary();
+    if let Some(e) = storage_error {
+        summary["message"] = json!(e);
+        return HttpResponse::InternalServerError().json(summary);
+    }
+    if let Some(e) = stream_error {
+        summary["message"] = json!(e);
+        return HttpResponse::BadRequest().json(summary);
+    }
+    HttpResponse::Ok().json(summary)
+}
+
+const DEFAULT_ACCOUNT_PAGE_SIZE: i64 = 100;
+const MAX_ACCOUNT_PAGE_SIZE: i64 = 1000;
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct ListAccountsReq {
+    pub owner: Option<String>,
+    pub executable: Option<bool>,
+    pub label: Option<String>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+#[get("/accounts/{id}")]
+pub async fn list_accounts(
+    svm: web::Data<Arc<SvmEngine<PgStorage>>>,
+    path: web::Path<Uuid>,
+    http_req: HttpRequest,
+    req: web::Query<ListAccountsReq>,
+) -> impl Responder {
+    let id = path.into_inner();
+    let blockchain = match svm.storage.get_blockchain(id) {
+        Ok(blockchain) => blockchain,
+        Err(e) => {
+            return HttpResponse::InternalServerError().json(e.to_string());
+        }
+    };
+    if !valid_api_key(blockchain.team_id, svm.clone(), http_req) {
+        return HttpResponse::Unauthorized().json(json!({
+            "message": "Invalid API key"
+        }));
+    }
+
+    let owner = match &req.owner {
+        Some(owner) => match Pubkey::from_str(owner) {
+            Ok(owner) => Some(owner),
+            Err(_) => {
+                return HttpResponse::BadRequest().json(json!({
+                    "message": "Invalid owner"
+                }));
+            }
+        },
+        None => None,
+    };
+    let limit = req.limit.unwrap_or(DEFAULT_ACCOUNT_PAGE_SIZE);
+    if !(1..=MAX_ACCOUNT_PAGE_SIZE).contains(&limit) {
+        return HttpResponse::BadRequest().json(json!({
+            "message": format!("limit must be between 1 and {}", MAX_ACCOUNT_PAGE_SIZE)
+        }));
+    }
+    let offset = req.offset.unwrap_or(0);
+    if offset < 0 {
+        return HttpResponse::BadRequest().json(json!({
+            "message": "offset cannot be negative"
+        }));
+    }
+    let filter = AccountListFilter {
+        owner,
+        executable: req.executable,
+        label: req.label.clone().filter(|label| !label.is_empty()),
+        limit,
+        offset,
+    };
+
+    match svm.storage.list_accounts(id, &filter) {
+        Ok((accounts, total)) => HttpResponse::Ok().json(json!({
+            "accounts": accounts
+                .into_iter()
+                .map(|account| {
+                    json!({
+                        "address": account.address,
+                        "lamports": numeric::saturating_u64(&account.lamports),
+                        "owner": account.owner,
+                        "executable": account.executable,
+                        "rent_epoch": numeric::saturating_u64(&account.rent_epoch),
+                        "label": account.label,
+                        "data_len": account.data_len,
+                    })
+                })
+                .collect::<Vec<_>>(),
+            "total": total,
+            "limit": limit,
+            "offset": offset,
+        })),
+        Err(e) => HttpResponse::InternalServerError().json(e.to_string()),
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct ExportAccountsReq {
+    pub owner: Option<String>,
+}
+
+/// Writes accounts as a JSON array of `solana account --output json-compact` entries.
+struct AccountsJsonWriter<W: Write> {
+    writer: W,
+    written: usize,
+}
+
+impl<W: Write> AccountsJsonWriter<W> {
+    fn new(writer: W) -> Self {
+        AccountsJsonWriter { writer, written: 0 }
+    }
+
+    fn write_account(&mut self, address: &Pubkey, account: &Account) -> Result<(), String> {
+        let separator: &[u8] = if self.written == 0 { b"[" } else { b"," };
+        self.writer
+            .write_all(separator)
+            .map_err(|e| e.to_string())?;
+        let entry = json!({
+            "pubkey": address.to_string(),
+            "account": {
+                "lamports": account.lamports,
+                "data": [BASE64_STANDARD.encode(&account.data), "base64"],
+                "owner": account.owner.to_string(),
+                "executable": account.executable,
+                "rentEpoch": account.rent_epoch,
+                "space": account.data.len(),
+            },
+        });
+        serde_json::to_writer(&mut self.writer, &entry).map_err(|e| e.to_string())?;
+        self.written += 1;
+        Ok(())
+    }
+
+    fn finish(mut self) -> Result<(), String> {
+        let end: &[u8] = if self.written == 0 { b"[]" } else { b"]" };
+        self.writer.write_all(end).map_err(|e| e.to_string())?;
+        self.writer.flush().map_err(|e| e.to_string())
+    }
+}
+
+/// Dumps accounts as `solana account --output json-compact` does. Each entry loads back
+/// through `PUT /accounts/{id}`. Accounts of an owner are sorted by address, a whole
+/// chain is streamed in storage order without loading it at once.
+#[get("/accounts/{id}/export")]
+pub async fn export_accounts(
+    svm: web::Data<Arc<SvmEngine<PgStorage>>>,
+    path: web::Path<Uuid>,
+    http_req: HttpRequest,
+    req: web::Query<ExportAccountsReq>,
+) -> impl Responder {
+    let id = path.into_inner();
+    let blockchain = match svm.storage.get_blockchain(id) {
+        Ok(blockchain) => blockchain,
+        Err(e) => {
+            return HttpResponse::InternalServerError().json(e.to_string());
+        }
+    };
+    if !valid_api_key(blockchain.team_id, svm.clone(), http_req) {
+        return HttpResponse::Unauthorized().json(json!({
+            "message": "Invalid API key"
+        }));
+    }
+
+    let owner = match &req.owner {
+        Some(owner) => match Pubkey::from_str(owner) {
+            Ok(owner) => Some(owner),
+            Err(_) => {
+                return HttpResponse::BadRequest().json(json!({
+                    "message": "Invalid owner"
+                }));
+            }
+        },
+        None => None,
+    };
+
+    // Read and encode accounts on a blocking thread while the response streams the output
+    let (tx, rx) = mpsc::channel(16);
+    let svm = svm.clone();
+    rt::task::spawn_blocking(move || {
+        let errors = tx.clone();
+        let mut export =
+            AccountsJsonWriter::new(BufWriter::with_capacity(64 * 1024, ChannelWriter(tx)));
+        let written = match owner {
+            Some(owner) => svm
+                .storage
+                .get_program_accounts(id, &owner)
+                .and_then(|mut accounts| {
+                    accounts.sort_by_key(|(address, _)| *address);
+                    accounts
+                        .iter()
+                        .try_for_each(|(address, account)| export.write_account(address, account))
+                }),
+            None => svm.storage.stream_accounts(id, &mut |address, account| {
+                export.write_account(&address, &account)
+            }),
+        }
+        .and_then(|()| export.finish());
+        if let Err(e) = written {
+            println!("Failed to export accounts: {}", e);
+            // Aborts the response rather than ending it as truncated JSON
+            let _ = errors.blocking_send(Err(io::Error::other(e)));
+        }
+    });
+    let body = futures::stream::unfold(rx, |mut rx| async move {
+        rx.recv().await.map(|chunk| (chunk, rx))
+    });
+
+    HttpResponse::Ok()
+        .content_type("application/json")
+        .insert_header((
+            "Content-Disposition",
+            format!("attachment; filename=\"{}.accounts.json\"", id),
+        ))
+        .streaming(body)
+}
+
+#[get("/accounts/{id}/{pubkey}")]
+pub async fn get_account(
+    svm: web::Data<Arc<SvmEngine<PgStorage>>>,
+    path: web::Path<(Uuid, String)>,
+    http_req: HttpRequest,
+) -> impl Responder {
+    let (id, pubkey) = path.into_inner();
+    let blockchain = match svm.storage.get_blockchain(id) {
+        Ok(blockchain) => blockchain,
+        Err(e) => {
+            return HttpResponse::InternalServerError().json(e.to_string());
+        }
+    };
+    if !valid_api_key(blockchain.team_id, svm.clone(), http_req) {
+        return HttpResponse::Unauthorized().json(json!({
+            "message": "Invalid API key"
+        }));
+    }
+    let address = match Pubkey::from_str(&pubkey) {
+        Ok(address) => address,
+        Err(_) => {
+            return HttpResponse::BadRequest().json(json!({
+                "message": "Invalid account address"
+            }));
+        }
+    };
+
+    match svm.storage.get_account(id, &address) {
+        Ok(Some(account)) => HttpResponse::Ok().json(json!({
+            "address": address.to_string(),
+            "lamports": account.lamports,
+            "data": BASE64_STANDARD.encode(&account.data),
+            "data_len": account.data.len(),
+            "owner": account.owner.to_string(),
+            "executable": account.executable,
+            "rent_epoch": account.rent_epoch,
+        })),
+        Ok(None) => HttpResponse::NotFound().json(json!({
+            "message": "Account not found"
+        })),
+        Err(e) => HttpResponse::InternalServerError().json(e.to_string()),
+    }
+}
+
+#[delete("/accounts/{id}/{pubkey}")]
+pub async fn delete_account(
+    svm: web::Data<Arc<SvmEngine<PgStorage>>>,
+    path: web::Path<(Uuid, String)>,
+    http_req: HttpRequest,
+) -> impl Responder {
+    let (id, pubkey) = path.into_inner();
+    let blockchain = match svm.storage.get_blockchain(id) {
+        Ok(blockchain) => blockchain,
+        Err(e) => {
+            return HttpResponse::InternalServerError().json(e.to_string());
+        }
+    };
+    let api_key = match team_api_key(blockchain.team_id, &svm, &http_req) {
+        Some(api_key) => api_key,
+        None => {
+            return HttpResponse::Unauthorized().json(json!({
+                "message": "Invalid API key"
+            }));
+        }
+    };
+    let address = match Pubkey::from_str(&pubkey) {
+        Ok(address) => address,
+        Err(_) => {
+            return HttpResponse::BadRequest().json(json!({
+                "message": "Invalid account address"
+            }));
+        }
+    };
+
+    match svm.storage.delete_account(id, &address) {
+        Ok(true) => {
+            audit(
+                &svm,
+                api_key,
+                blockchain.team_id,
+                Some(id),
+                audit::ACCOUNT_DELETE,
+                json!({ "address": address.to_string() }),
+            );
+            HttpResponse::Ok().json(json!({
+                "message": "Account deleted successfully"
+            }))
+        }
+        Ok(false) => HttpResponse::NotFound().json(json!({
+            "message": "Account not found"
+        })),
+        Err(e) => HttpResponse::InternalServerError().json(e.to_string()),
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct SetAccountLabelReq {
+    pub label: String,
+}
+
+/// Tags an account so fixtures can find it by name, replacing any label it had.
+#[put("/accounts/{id}/{pubkey}/label")]
+pub async fn set_account_label(
+    svm: web::Data<Arc<SvmEngine<PgStorage>>>,
+    path: web::Path<(Uuid, String)>,
+    http_req: HttpRequest,
+    req: web::Json<SetAccountLabelReq>,
+) -> impl Responder {
+    if req.label.trim().is_empty() {
+        return HttpResponse::BadRequest().json(json!({
+            "message": "label cannot be empty"
+        }));
+    }
+    update_account_label(svm, path, http_req, Some(req.label.clone()))
+}
+
+#[delete("/accounts/{id}/{pubkey}/label")]
+pub async fn clear_account_label(
+    svm: web::Data<Arc<SvmEngine<PgStorage>>>,
+    path: web::Path<(Uuid, String)>,
+    http_req: HttpRequest,
+) -> impl Responder {
+    update_account_label(svm, path, http_req, None)
+}
+
+fn update_account_label(
+    svm: web::Data<Arc<SvmEngine<PgStorage>>>,
+    path: web::Path<(Uuid, String)>,
+    http_req: HttpRequest,
+    label: Option<String>,
+) -> HttpResponse {
+    let (id, pubkey) = path.into_inner();
+    let blockchain = match svm.storage.get_blockchain(id) {
+        Ok(blockchain) => blockchain,
+        Err(e) => {
+            return HttpResponse::InternalServerError().json(e.to_string());
+        }
+    };
+    let api_key = match team_api_key(blockchain.team_id, &svm, &http_req) {
+        Some(api_key) => api_key,
+        None => {
+            return HttpResponse::Unauthorized().json(json!({
+                "message": "Invalid API key"
+            }));
+        }
+    };
+    let address = match Pubkey::from_str(&pubkey) {
+        Ok(address) => address,
+        Err(_) => {
+            return HttpResponse::BadRequest().json(json!({
+                "message": "Invalid account address"
+            }));
+        }
+    };
+
+    match svm.storage.set_account_label(id, &address, label.clone()) {
+        Ok(true) => {
+            audit(
+                &svm,
+                api_key,
+                blockchain.team_id,
+                Some(id),
+                audit::ACCOUNT_LABEL,
+                json!({ "address": address.to_string(), "label": label }),
+            );
+            HttpResponse::Ok().json(json!({
+                "address": address.to_string(),
+                "label": label,
+            }))
+        }
+        Ok(false) => HttpResponse::NotFound().json(json!({
+            "message": "Account not found"
+        })),
+        Err(e) => HttpResponse::InternalServerError().json(e.to_string()),
+    }
+}
+
+const DEFAULT_TRANSACTION_PAGE_SIZE: i64 = 100;
+const MAX_TRANSACTION_PAGE_SIZE: i64 = 1000;
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct SearchTransactionsReq {
+    pub address: Option<String>,
+    pub program_id: Option<String>,
+    /// `success` or `failed`
+    pub status: Option<String>,
+    pub start: Option<chrono::NaiveDateTime>,
+    pub end: Option<chrono::NaiveDateTime>,
+    pub signature_prefix: Option<String>,
+    pub fee_payer: Option<String>,
+    pub is_vote: Option<bool>,
+    /// Micro-lamports per compute unit
+    pub min_compute_unit_price: Option<u64>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+/// Transaction with its meta resolved into addresses, in the shape dashboards display.
+fn transaction_json(tx: &DbTransactionObject) -> serde_json::Value {
+    let addresses: Vec<&str> = tx
+        .account_keys
+        .iter()
+        .map(|key| key.account.as_str())
+        .collect();
+    json!({
+        "signature": tx.transaction.signature,
+        "slot": numeric::saturating_u64(&tx.transaction.slot),
+        "created_at": tx.transaction.created_at,
+        "version": tx.transaction.version,
+        "status": if tx.meta.err.is_some() { "failed" } else { "success" },
+        "err": tx.meta.err,
+        "fee": tx.meta.fee.to_u64(),
+        "compute_units_consumed": tx.meta.compute_units_consumed.to_u64(),
+        "compute_unit_price": tx.transaction.compute_unit_price.as_ref().and_then(|price| price.to_u64()),
+        "fee_payer": tx.transaction.fee_payer,
+        "is_vote": tx.transaction.is_vote,
+        "signatures": tx.signatures.iter().map(|s| s.signature.as_str()).collect::<Vec<_>>(),
+        "account_keys": tx.account_keys.iter().map(|key| json!({
+            "address": key.account,
+            "signer": key.signer,
+            "writable": key.writable,
+        })).collect::<Vec<_>>(),
+        "instructions": tx.instructions.iter().map(|ix| json!({
+            "program_id": ix.program_id,
+            "accounts": ix
+                .accounts
+                .iter()
+                .filter_map(|index| addresses.get(*index as usize))
+                .collect::<Vec<_>>(),
+            "data": bs58::encode(&ix.data).into_string(),
+            "inner": ix.inner,
+            "stack_height": ix.stack_height,
+        })).collect::<Vec<_>>(),
+        "log_messages": tx.log_messages.iter().map(|log| log.log.as_str()).collect::<Vec<_>>(),
+        "pre_balances": tx.meta.pre_balances,
+        "post_balances": tx.meta.post_balances,
+    })
+}
+
+fn parse_pubkey_param(name: &str, value: &Option<String>) -> Result<Option<String>, HttpResponse> {
+    match value {
+        Some(value) => match Pubkey::from_str(value) {
+            Ok(pubkey) => Ok(Some(pubkey.to_string())),
+            Err(_) => Err(HttpResponse::BadRequest().json(json!({
+                "message": format!("Invalid {}", name)
+            }))),
+        },
+        None => Ok(None),
+    }
+}
+
+#[get("/transactions/{id}")]
+pub async fn search_transactions(
+    svm: web::Data<Arc<SvmEngine<PgStorage>>>,
+    path: web::Path<Uuid>,
+    http_req: HttpRequest,
+    req: web::Query<SearchTransactionsReq>,
+) -> impl Responder {
+    let id = path.into_inner();
+    let blockchain = match svm.storage.get_blockchain(id) {
+        Ok(blockchain) => blockchain,
+        Err(e) => {
+            return HttpResponse::InternalServerError().json(e.to_string());
+        }
+    };
+    if !valid_api_key(blockchain.team_id, svm.clone(), http_req) {
+        return HttpResponse::Unauthorized().json(json!({
+            "message": "Invalid API key"
+        }));
+    }
+
+    let address = match parse_pubkey_param("address", &req.address) {
+        Ok(address) => address,
+        Err(res) => return res,
+    };
+    let program_id = match parse_pubkey_param("program id", &req.program_id) {
+        Ok(program_id) => program_id,
+        Err(res) => return res,
+    };
+    let fee_payer = match parse_pubkey_param("fee payer", &req.fee_payer) {
+        Ok(fee_payer) => fee_payer,
+        Err(res) => return res,
+    };
+    let failed = match req.status.as_deref() {
+        None => None,
+        Some("success") => Some(false),
+        Some("failed") => Some(true),
+        Some(_) => {
+            return HttpResponse::BadRequest().json(json!({
+                "message": "status must be success or failed"
+            }));
+        }
+    };
+    if let Some(prefix) = &req.signature_prefix {
+        // Also keeps LIKE wildcards out of the prefix
+        if prefix.len() > 88 || bs58::decode(prefix).into_vec().is_err() {
+            return HttpResponse::BadRequest().json(json!({
+                "message": "Invalid signature prefix"
+            }));
+        }
+    }
+    let limit = req.limit.unwrap_or(DEFAULT_TRANSACTION_PAGE_SIZE);
+    if !(1..=MAX_TRANSACTION_PAGE_SIZE).contains(&limit) {
+        return HttpResponse::BadRequest().json(json!({
+            "message": format!("limit must be between 1 and {}", MAX_TRANSACTION_PAGE_SIZE)
+        }));
+    }
+    let offset = req.offset.unwrap_or(0);
+    if offset < 0 {
+        return HttpResponse::BadRequest().json(json!({
+            "message": "offset cannot be negative"
+        }));
+    }
+
+    let filter = TransactionFilter {
+        address,
+        program_id,
+        failed,
+        start: req.start,
+        end: req.end,
+        signature_prefix: req.signature_prefix.clone(),
+        fee_payer,
+        is_vote: req.is_vote,
+        min_compute_unit_price: req.min_compute_unit_price,
+        limit,
+        offset,
+    };
+    match svm.storage.search_transactions(id, &filter) {
+        Ok((transactions, total)) => HttpResponse::Ok().json(json!({
+            "transactions": transactions.iter().map(transaction_json).collect::<Vec<_>>(),
+            "total": total,
+            "limit": limit,
+            "offset": offset,
+        })),
+        Err(e) => HttpResponse::InternalServerError().json(e.to_string()),
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct CreateBlockchainReq {
+    pub config: Option<Uuid>,
+    pub defer_account_initailization: Option<bool>,
+    /// Genesis program builds to load from `programs.directory` instead, by program id
+    pub programs: Option<BTreeMap<String, String>>,
+    /// Accounts cloned from the upstream RPC at genesis, over the config's accounts
+    pub mainnet_accounts: Option<Vec<String>>,
+}
+
+const MAX_MAINNET_ACCOUNTS: usize = 1000;
+
+#[post("/blockchains")]
+pub async fn create_blockchain(
+    svm: web::Data<Arc<SvmEngine<PgStorage>>>,
+    warm_pool: web::Data<WarmPool>,
+    http_req: HttpRequest,
+    req: Option<web::Json<CreateBlockchainReq>>,
+) -> impl Responder {
+    let (api_key, team) = match get_caller(&svm, &http_req) {
+        Ok(caller) => caller,
+        Err(e) => {
+            return HttpResponse::Unauthorized().json(json!({
+                "message": e
+            }))
+        }
+    };
+    let idempotency_key = match idempotency_key(&http_req) {
+        Ok(key) => key,
+        Err(res) => return res,
+    };
+    let (label, expiry) = match team_blockchain_defaults(&team, &http_req) {
+        Ok(defaults) => defaults,
+        Err(res) => return res,
+    };
+    let mut programs = vec![];
+    for (program_id, file) in req
+        .as_ref()
+        .and_then(|req| req.programs.clone())
+        .unwrap_or_default()
+    {
+        match Pubkey::from_str(&program_id) {
+            Ok(program_id) => programs.push((program_id, file)),
+            Err(_) => {
+                return HttpResponse::BadRequest().json(json!({
+                    "message": format!("Invalid program id {}", program_id)
+                }))
+            }
+        }
+    }
+    let mut mainnet_accounts = vec![];
+    for address in req
+        .as_ref()
+        .and_then(|req| req.mainnet_accounts.clone())
+        .unwrap_or_default()
+    {
+        match Pubkey::from_str(&address) {
+            Ok(address) => mainnet_accounts.push(address),
+            Err(_) => {
+                return HttpResponse::BadRequest().json(json!({
+                    "message": format!("Invalid account address {}", address)
+                }))
+            }
+        }
+    }
+    mainnet_accounts.sort();
+    mainnet_accounts.dedup();
+    if mainnet_accounts.len() > MAX_MAINNET_ACCOUNTS {
+        return HttpResponse::BadRequest().json(json!({
+            "message": format!("At most {} mainnet_accounts can be cloned", MAX_MAINNET_ACCOUNTS)
+        }));
+    }
+    let new = NewBlockchain {
+        label,
+        expiry,
+        config: req.as_ref().and_then(|req| req.config),
+        defer_accounts: req
+            .as_ref()
+            .and_then(|req| req.defer_account_initailization)
+            .unwrap_or(false),
+        programs,
+        mainnet_accounts,
+    };
+    if new.defer_accounts && !new.programs.is_empty() {
+        return HttpResponse::BadRequest().json(json!({
+            "message": "programs can't be combined with defer_account_initailization"
+        }));
+    }
+    if new.defer_accounts && !new.mainnet_accounts.is_empty() {
+        return HttpResponse::BadRequest().json(json!({
+            "message": "mainnet_accounts can't be combined with defer_account_initailization"
+        }));
+    }
+
+    let Some(key) = idempotency_key else {
+        return match create_team_blockchain(&svm, &warm_pool, api_key, &team, new).await {
+            Ok(id) => HttpResponse::Ok().json(json!({
+                "url": blockchain_url(id)
+            })),
+            Err(res) => res,
+        };
+    };
+    // Retries have to repeat the request, expiry moves with the clock so it's left out
+    let fingerprint = json!({
+        "label": new.label,
+        "config": new.config,
+        "defer_account_initailization": new.defer_accounts,
+        "programs": req.as_ref().and_then(|req| req.programs.clone()),
+        "mainnet_accounts": new
+            .mainnet_accounts
+            .iter()
+            .map(|address| address.to_string())
+            .collect::<Vec<_>>(),
+    })
+    .to_string();
+    if let Err(res) = reserve_idempotency_key(&svm, team.id, &key, &fingerprint) {
+        return res;
+    }
+    let created = create_team_blockchain(&svm, &warm_pool, api_key, &team, new).await;
+    complete_idempotency_key(&svm, team.id, &key, fingerprint, created)
+}
+
+/// Claims an `Idempotency-Key` for a blockchain creating request, or answers
+/// the request from the first one that used it.
+fn reserve_idempotency_key(
+    svm: &web::Data<Arc<SvmEngine<PgStorage>>>,
+    team_id: Uuid,
+    key: &str,
+    fingerprint: &str,
+) -> Result<(), HttpResponse> {
+    match svm
+        .storage
+        .reserve_idempotency_key(team_id, key, fingerprint)
+    {
+        Ok(None) => Ok(()),
+        Ok(Some(record)) if record.fingerprint != fingerprint => {
+            Err(HttpResponse::UnprocessableEntity().json(json!({
+                "message": "Idempotency-Key was already used for a different request"
+            })))
+        }
+        Ok(Some(IdempotencyRecord {
+            blockchain: Some(id),
+            ..
+        })) => Err(HttpResponse::Ok()
+            .insert_header(("Idempotent-Replayed", "true"))
+            .json(json!({
+                "url": blockchain_url(id)
+            }))),
+        Ok(Some(_)) => Err(HttpResponse::Conflict().json(json!({
+            "message": "A request with this Idempotency-Key is in progress"
+        }))),
+        Err(e) => Err(HttpResponse::InternalServerError().json(e.to_string())),
+    }
+}
+
+/// Records the blockchain a reserved request created, or frees the key for a
+/// retry when it failed.
+fn complete_idempotency_key(
+    svm: &web::Data<Arc<SvmEngine<PgStorage>>>,
+    team_id: Uuid,
+    key: &str,
+    fingerprint: String,
+    created: Result<Uuid, HttpResponse>,
+) -> HttpResponse {
+    match created {
+        Ok(id) => {
+            let record = IdempotencyRecord {
+                fingerprint,
+                blockchain: Some(id),
+            };
+            if let Err(e) = svm.storage.complete_idempotency_key(team_id, key, &record) {
+                println!("Failed to complete idempotency key: {}", e);
+            }
+            HttpResponse::Ok().json(json!({
+                "url": blockchain_url(id)
+            }))
+        }
+        Err(res) => {
+            if let Err(e) = svm.storage.release_idempotency_key(team_id, key) {
+                println!("Failed to release idempotency key: {}", e);
+            }
+            res
+        }
+    }
+}
+
+/// The `Idempotency-Key` header, if any.
+fn idempotency_key(http_req: &HttpRequest) -> Result<Option<String>, HttpResponse> {
+    let Some(key) = http_req.headers().get("Idempotency-Key") else {
+        return Ok(None);
+    };
+    match key.to_str() {
+        Ok(key) if !key.is_empty() && key.len() <= MAX_IDEMPOTENCY_KEY_LEN => {
+            Ok(Some(key.to_string()))
+        }
+        _ => Err(HttpResponse::BadRequest().json(json!({
+            "message": format!(
+                "Idempotency-Key must be 1 to {} visible ASCII characters",
+                MAX_IDEMPOTENCY_KEY_LEN
+            )
+        }))),
+    }
+}
+
+/// What `POST /blockchains` asks for, once the team's defaults are applied.
+struct NewBlockchain {
+    label: Option<String>,
+    expiry: Option<chrono::NaiveDateTime>,
+    config: Option<Uuid>,
+    defer_accounts: bool,
+    programs: Vec<(Pubkey, String)>,
+    mainnet_accounts: Vec<Pubkey>,
+}
+
+/// Claims a warm pool blockchain or provisions one, within the team's limit.
+async fn create_team_blockchain(
+    svm: &web::Data<Arc<SvmEngine<PgStorage>>>,
+    warm_pool: &WarmPool,
+    api_key: Option<Uuid>,
+    team: &Team,
+    new: NewBlockchain,
+) -> Result<Uuid, HttpResponse> {
+    let NewBlockchain {
+        label,
+        expiry,
+        config,
+        defer_accounts,
+        programs,
+        mainnet_accounts,
+    } = new;
+    check_blockchain_limit(svm, team)?;
+    let mut bundles = vec![];
+    if let Some(config_id) = config {
+        match svm.storage.get_config(config_id) {
+            Ok(Some(config)) if config.is_visible_to(team.id) => bundles = config.bundles,
+            Ok(_) => {
+                return Err(HttpResponse::NotFound().json(json!({
+                    "message": "Config not found"
+                })))
+            }
+            Err(e) => return Err(HttpResponse::InternalServerError().json(e.to_string())),
+        }
+    }
+    // Fetched first, so an upstream failure doesn't leave a blockchain to clean up
+    let mut cloned = vec![];
+    if !mainnet_accounts.is_empty() {
+        cloned = svm
+            .fetch_remote_accounts(&mainnet_accounts)
+            .await
+            .map_err(|e| {
+                HttpResponse::BadGateway().json(json!({
+                    "message": format!("Failed to clone mainnet accounts: {}", e)
+                }))
+            })?;
+    }
+    let claimed = match svm
+        .storage
+        .claim_pooled_blockchain(team.id, config, label.clone(), expiry)
+    {
+        Ok(claimed) => claimed.map(|blockchain| blockchain.id),
+        Err(e) => {
+            // The pool is only a shortcut, fall back to creating one
+            println!("Failed to claim a warm pool blockchain: {}", e);
+            None
+        }
+    };
+    warm_pool.refill(team.id, config);
+    let id = match claimed {
+        Some(id) => Ok(id),
+        None => svm.create_blockchain(team.id, None, label.clone(), expiry, config, defer_accounts),
+    };
+    let id = match id {
+        Ok(id) => id,
+        Err(e) => return Err(HttpResponse::InternalServerError().json(e.to_string())),
+    };
+    if !programs.is_empty() {
+        if let Err(e) = svm.set_genesis_programs(id, &programs) {
+            if let Err(e) = svm.delete_blockchain(id) {
+                println!("Failed to delete blockchain {}: {}", id, e);
+            }
+            return Err(HttpResponse::BadRequest().json(json!({
+                "message": e
+            })));
+        }
+    }
+    if let Err(e) = svm.clone_bundles(id, &bundles).await {
+        if let Err(e) = svm.delete_blockchain(id) {
+            println!("Failed to delete blockchain {}: {}", id, e);
+        }
+        return Err(HttpResponse::BadGateway().json(json!({
+            "message": format!("Failed to clone bundled programs: {}", e)
+        })));
+    }
+    let cloned_count = cloned.len();
+    if !cloned.is_empty() {
+        let written = match check_account_quota(svm, team.id, id, &cloned) {
+            Ok(()) => svm
+                .storage
+                .set_accounts(id, cloned)
+                .map_err(|e| HttpResponse::InternalServerError().json(e)),
+            Err(res) => Err(res),
+        };
+        if let Err(res) = written {
+            if let Err(e) = svm.delete_blockchain(id) {
+                println!("Failed to delete blockchain {}: {}", id, e);
+            }
+            return Err(res);
+        }
+    }
+    audit(
+        svm,
+        api_key,
+        team.id,
+        Some(id),
+        audit::BLOCKCHAIN_CREATE,
+        json!({
+            "label": label,
+            "expiry": expiry,
+            "config": config,
+            "warm_pool": claimed.is_some(),
+            "bundles": bundles,
+            "mainnet_accounts": cloned_count,
+            "programs": programs
+                .iter()
+                .map(|(program_id, file)| (program_id.to_string(), file.clone()))
+                .collect::<BTreeMap<_, _>>(),
+        }),
+    );
+    Ok(id)
+}
+
+fn is_snapshot_upload(ctx: &GuardContext) -> bool {
+    ctx.head()
+        .headers()
+        .get("content-type")
+        .and_then(|content_type| content_type.to_str().ok())
+        .map(|content_type| {
+            content_type == "application/gzip" || content_type == "application/x-gzip"
+        })
+        .unwrap_or(false)
+}
+
+#[post("/blockchains", guard = "is_snapshot_upload")]
+pub async fn create_blockchain_from_snapshot(
+    svm: web::Data<Arc<SvmEngine<PgStorage>>>,
+    http_req: HttpRequest,
+    mut payload: web::Payload,
+) -> impl Responder {
+    let (api_key, team) = match get_caller(&svm, &http_req) {
+        Ok(caller) => caller,
+        Err(e) => {
+            return HttpResponse::Unauthorized().json(json!({
+                "message": e
+            }))
+        }
+    };
+    let idempotency_key = match idempotency_key(&http_req) {
+        Ok(key) => key,
+        Err(res) => return res,
+    };
+    let (label, expiry) = match team_blockchain_defaults(&team, &http_req) {
+        Ok(defaults) => defaults,
+        Err(res) => return res,
+    };
+
+    let max_snapshot_size = settings::get().limits.max_snapshot_size;
+    let mut body = Vec::new();
+    while let Some(chunk) = payload.next().await {
+        let chunk = match chunk {
+            Ok(chunk) => chunk,
+            Err(e) => {
+                return HttpResponse::BadRequest().json(json!({
+                    "message": e.to_string()
+                }));
+            }
+        };
+        if body.len() + chunk.len() > max_snapshot_size {
+            return HttpResponse::PayloadTooLarge().json(json!({
+                "message": "Snapshot is too large"
+            }));
+        }
+        body.extend_from_slice(&chunk);
+    }
+
+    let Some(key) = idempotency_key else {
+        return match create_snapshot_blockchain(&svm, api_key, &team, label, expiry, body).await {
+            Ok(id) => HttpResponse::Ok().json(json!({
+                "url": blockchain_url(id)
+            })),
+            Err(res) => res,
+        };
+    };
+    // Retries have to upload the same snapshot, expiry is left out like for `POST /blockchains`
+    let fingerprint = json!({
+        "label": label,
+        "snapshot": hex::encode(Sha256::digest(&body)),
+    })
+    .to_string();
+    if let Err(res) = reserve_idempotency_key(&svm, team.id, &key, &fingerprint) {
+        return res;
+    }
+    let created = create_snapshot_blockchain(&svm, api_key, &team, label, expiry, body).await;
+    complete_idempotency_key(&svm, team.id, &key, fingerprint, created)
+}
+
+/// Restores an uploaded snapshot into a new blockchain, within the team's limit.
+async fn create_snapshot_blockchain(
+    svm: &web::Data<Arc<SvmEngine<PgStorage>>>,
+    api_key: Option<Uuid>,
+    team: &Team,
+    label: Option<String>,
+    expiry: Option<chrono::NaiveDateTime>,
+    body: Vec<u8>,
+) -> Result<Uuid, HttpResponse> {
+    check_blockchain_limit(svm, team)?;
+    // Read twice, to validate and count the accounts before anything is created,
+    // then to write them, so they're never in memory together
+    let counted = web::block(move || {
+        let mut count = 0;
+        Snapshot::read_accounts(body.as_slice(), MAX_SNAPSHOT_BYTES, &mut |_, _| {
+            count += 1;
+            Ok(())
+        })
+        .map(|_| (body, count))
+    })
+    .await;
+    let (body, account_count) = match counted {
+        Ok(Ok(counted)) => counted,
+        Ok(Err(e)) => {
+            return Err(HttpResponse::BadRequest().json(json!({
+                "message": e
+            })));
+        }
+        Err(e) => return Err(HttpResponse::InternalServerError().json(e.to_string())),
+    };
+
+    // Plus the airdrop account
+    check_new_blockchain_accounts(svm, team.id, account_count + 1)?;
+    // Here rather than on a blocking thread, the writes queue their Postgres
+    // upserts on this one
+    let id = svm
+        .create_blockchain_from_accounts(
+            team.id,
+            label.clone(),
+            expiry,
+            account_count,
+            &mut |each| {
+                Snapshot::read_accounts(body.as_slice(), MAX_SNAPSHOT_BYTES, each).map(|_| ())
+            },
+        )
+        .map_err(|e| HttpResponse::InternalServerError().json(e))?;
+    audit(
+        svm,
+        api_key,
+        team.id,
+        Some(id),
+        audit::BLOCKCHAIN_CREATE,
+        json!({ "label": label, "expiry": expiry, "snapshot_accounts": account_count }),
+    );
+    Ok(id)
+}
+
+/// Forwards written bytes to a streaming response body.
+struct ChannelWriter(mpsc::Sender<Result<web::Bytes, io::Error>>);
+
+impl Write for ChannelWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0
+            .blocking_send(Ok(web::Bytes::copy_from_slice(buf)))
+            .map_err(|_| io::Error::from(io::ErrorKind::BrokenPipe))?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[get("/blockchains/{id}/snapshot")]
+pub async fn download_snapshot(
+    svm: web::Data<Arc<SvmEngine<PgStorage>>>,
+    path: web::Path<Uuid>,
+    http_req: HttpRequest,
+) -> impl Responder {
+    let id = path.into_inner();
+    let blockchain = match svm.storage.get_blockchain(id) {
+        Ok(blockchain) => blockchain,
+        Err(e) => {
+            return HttpResponse::InternalServerError().json(e.to_string());
+        }
+    };
+    if !valid_api_key(blockchain.team_id, svm.clone(), http_req) {
+        return HttpResponse::Unauthorized().json(json!({
+            "message": "Invalid API key"
+        }));
+    }
+
+    let slot = match svm.get_latest_block(id) {
+        Ok(block) => block.block_height,
+        Err(e) => return HttpResponse::InternalServerError().json(e.to_string()),
+    };
+
+    // Read and compress accounts on a blocking thread while the response streams the output
+    let (tx, rx) = mpsc::channel(16);
+    let svm = svm.clone();
+    rt::task::spawn_blocking(move || {
+        let errors = tx.clone();
+        let writer = BufWriter::with_capacity(64 * 1024, ChannelWriter(tx));
+        let written = SnapshotWriter::new(writer, slot).and_then(|mut snapshot| {
+            svm.storage.stream_accounts(id, &mut |address, account| {
+                snapshot.write_account(&address, &account)
+            })?;
+            snapshot.finish()
+        });
+        if let Err(e) = written {
+            println!("Failed to write snapshot: {}", e);
+            // Aborts the response rather than ending it as a truncated archive
+            let _ = errors.blocking_send(Err(io::Error::other(e)));
+        }
+    });
+    let body = futures::stream::unfold(rx, |mut rx| async move {
+        rx.recv().await.map(|chunk| (chunk, rx))
+    });
+
+    HttpResponse::Ok()
+        .content_type("application/gzip")
+        .insert_header((
+            "Content-Disposition",
+            format!("attachment; filename=\"{}.snapshot.gz\"", id),
+        ))
+        .streaming(body)
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct CloneBlockchainReq {
+    pub label: Option<String>,
+}
+
+#[get("/blockchains/{id}")]
+pub async fn get_blockchain(
+    svm: web::Data<Arc<SvmEngine<PgStorage>>>,
+    path: web::Path<Uuid>,
+    http_req: HttpRequest,
+) -> impl Responder {
+    let id = path.into_inner();
+    let blockchain = match svm.storage.get_blockchain(id) {
+        Ok(blockchain) => blockchain,
+        Err(e) => {
+            return HttpResponse::InternalServerError().json(e.to_string());
+        }
+    };
+    if !valid_api_key(blockchain.team_id, svm.clone(), http_req) {
+        return HttpResponse::Unauthorized().json(json!({
+            "message": "Invalid API key"
+        }));
+    }
+
+    let slot = match svm.get_latest_block(id) {
+        Ok(block) => block.block_height,
+        Err(e) => return HttpResponse::InternalServerError().json(e.to_string()),
+    };
+    let transaction_count = match svm.get_transaction_count(id) {
+        Ok(count) => count,
+        Err(e) => return HttpResponse::InternalServerError().json(e.to_string()),
+    };
+
+    HttpResponse::Ok().json(json!({
+        "id": blockchain.id,
+        "url": blockchain_url(blockchain.id),
+        "label": blockchain.label,
+        "created_at": blockchain.created_at,
+        "expiry": blockchain.expiry,
+        "jit": blockchain.jit,
+        "config": blockchain.config,
+        "log_bytes_limit": blockchain.log_bytes_limit,
+        "read_only": blockchain.read_only,
+        "sponsor_fees": blockchain.sponsor_fees,
+        "parity_check": blockchain.parity_check,
+        "accounts_status": blockchain.accounts_status,
+        "slot": slot,
+        "transaction_count": transaction_count,
+        "identity": blockchain.airdrop_keypair.pubkey().to_string(),
+    }))
+}
+
+const DEFAULT_FEED_SIZE: usize = 25;
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct FeedReq {
+    pub limit: Option<usize>,
+}
+
+impl FeedReq {
+    fn limit(&self) -> Result<usize, HttpResponse> {
+        match self.limit.unwrap_or(DEFAULT_FEED_SIZE) {
+            limit @ 1..=TRANSACTION_FEED_SIZE => Ok(limit),
+            _ => Err(HttpResponse::BadRequest().json(json!({
+                "message": format!("limit must be between 1 and {}", TRANSACTION_FEED_SIZE)
+            }))),
+        }
+    }
+}
+
+#[get("/blockchains/{id}/blocks/latest")]
+pub async fn latest_blocks(
+    svm: web::Data<Arc<SvmEngine<PgStorage>>>,
+    path: web::Path<Uuid>,
+    http_req: HttpRequest,
+    req: web::Query<FeedReq>,
+) -> impl Responder {
+    let id = path.into_inner();
+    let blockchain = match svm.storage.get_blockchain(id) {
+        Ok(blockchain) => blockchain,
+        Err(e) => {
+            return HttpResponse::InternalServerError().json(e.to_string());
+        }
+    };
+    if !valid_api_key(blockchain.team_id, svm.clone(), http_req) {
+        return HttpResponse::Unauthorized().json(json!({
+            "message": "Invalid API key"
+        }));
+    }
+    let limit = match req.limit() {
+        Ok(limit) => limit,
+        Err(res) => return res,
+    };
+
+    match svm.storage.get_recent_blocks(id, limit) {
+        Ok(blocks) => HttpResponse::Ok().json(json!({
+            "blocks": blocks.iter().map(|block| json!({
+                "slot": block.block_height,
+                "blockhash": block.blockhash.to_string(),
+                "previous_blockhash": block.previous_blockhash.to_string(),
+                "parent_slot": block.parent_slot,
+                "block_time": block.block_time,
+            })).collect::<Vec<_>>(),
+        })),
+        Err(e) => HttpResponse::InternalServerError().json(e.to_string()),
+    }
+}
+
+#[get("/blockchains/{id}/transactions/latest")]
+pub async fn latest_transactions(
+    svm: web::Data<Arc<SvmEngine<PgStorage>>>,
+    path: web::Path<Uuid>,
+    http_req: HttpRequest,
+    req: web::Query<FeedReq>,
+) -> impl Responder {
+    let id = path.into_inner();
+    let blockchain = match svm.storage.get_blockchain(id) {
+        Ok(blockchain) => blockchain,
+        Err(e) => {
+            return HttpResponse::InternalServerError().json(e.to_string());
+        }
+    };
+    if !valid_api_key(blockchain.team_id, svm.clone(), http_req) {
+        return HttpResponse::Unauthorized().json(json!({
+            "message": "Invalid API key"
+        }));
+    }
+    let limit = match req.limit() {
+        Ok(limit) => limit,
+        Err(res) => return res,
+    };
+
+    match svm.storage.get_recent_transactions(id, limit) {
+        Ok(transactions) => HttpResponse::Ok().json(json!({
+            "transactions": transactions,
+        })),
+        Err(e) => HttpResponse::InternalServerError().json(e.to_string()),
+    }
+}
+
+#[get("/blockchains/{id}/write_locks")]
+pub async fn write_lock_report(
+    svm: web::Data<Arc<SvmEngine<PgStorage>>>,
+    path: web::Path<Uuid>,
+    http_req: HttpRequest,
+    req: web::Query<FeedReq>,
+) -> impl Responder {
+    let id = path.into_inner();
+    let blockchain = match svm.storage.get_blockchain(id) {
+        Ok(blockchain) => blockchain,
+        Err(e) => {
+            return HttpResponse::InternalServerError().json(e.to_string());
+        }
+    };
+    if !valid_api_key(blockchain.team_id, svm.clone(), http_req) {
+        return HttpResponse::Unauthorized().json(json!({
+            "message": "Invalid API key"
+        }));
+    }
+    let limit = match req.limit() {
+        Ok(limit) => limit,
+        Err(res) => return res,
+    };
+
+    HttpResponse::Ok().json(json!({
+        "accounts": svm.write_lock_report(id, limit).iter().map(|(address, stats)| json!({
+            "address": address.to_string(),
+            "write_locks": stats.write_locks,
+            "conflicts": stats.conflicts,
+        })).collect::<Vec<_>>(),
+    }))
+}
+
+const DEFAULT_TIMELINE_SIZE: usize = 100;
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct TimelineReq {
+    pub since: Option<chrono::NaiveDateTime>,
+    pub limit: Option<usize>,
+}
+
+#[get("/blockchains/{id}/timeline")]
+pub async fn blockchain_timeline(
+    svm: web::Data<Arc<SvmEngine<PgStorage>>>,
+    path: web::Path<Uuid>,
+    http_req: HttpRequest,
+    req: web::Query<TimelineReq>,
+) -> impl Responder {
+    let id = path.into_inner();
+    let blockchain = match svm.storage.get_blockchain(id) {
+        Ok(blockchain) => blockchain,
+        Err(e) => {
+            return HttpResponse::InternalServerError().json(e.to_string());
+        }
+    };
+    if !valid_api_key(blockchain.team_id, svm.clone(), http_req) {
+        return HttpResponse::Unauthorized().json(json!({
+            "message": "Invalid API key"
+        }));
+    }
+    let limit = req.limit.unwrap_or(DEFAULT_TIMELINE_SIZE);
+    if !(1..=MAX_TIMELINE_SIZE).contains(&limit) {
+        return HttpResponse::BadRequest().json(json!({
+            "message": format!("limit must be between 1 and {}", MAX_TIMELINE_SIZE)
+        }));
+    }
+
+    match svm.timeline(id, req.since, limit) {
+        Ok(events) => HttpResponse::Ok().json(json!({
+            "events": events,
+        })),
+        Err(e) => HttpResponse::InternalServerError().json(e.to_string()),
+    }
+}
+
+#[get("/blockchains/{id}/sysvars")]
+pub async fn get_sysvar_overrides(
+    svm: web::Data<Arc<SvmEngine<PgStorage>>>,
+    path: web::Path<Uuid>,
+    http_req: HttpRequest,
+) -> impl Responder {
+    let id = path.into_inner();
+    let blockchain = match svm.storage.get_blockchain(id) {
+        Ok(blockchain) => blockchain,
+        Err(e) => {
+            return HttpResponse::InternalServerError().json(e.to_string());
+        }
+    };
+    if !valid_api_key(blockchain.team_id, svm.clone(), http_req) {
+        return HttpResponse::Unauthorized().json(json!({
+            "message": "Invalid API key"
+        }));
+    }
+
+    match svm.get_sysvar_overrides(id) {
+        Ok(overrides) => HttpResponse::Ok().json(overrides),
+        Err(e) => HttpResponse::InternalServerError().json(e.to_string()),
+    }
+}
+
+#[put("/blockchains/{id}/sysvars")]
+pub async fn set_sysvar_overrides(
+    svm: web::Data<Arc<SvmEngine<PgStorage>>>,
+    path: web::Path<Uuid>,
+    http_req: HttpRequest,
+    req: web::Json<SysvarOverrides>,
+) -> impl Responder {
+    let id = path.into_inner();
+    let blockchain = match svm.storage.get_blockchain(id) {
+        Ok(blockchain) => blockchain,
+        Err(e) => {
+            return HttpResponse::InternalServerError().json(e.to_string());
+        }
+    };
+    let api_key = match team_api_key(blockchain.team_id, &svm, &http_req) {
+        Some(api_key) => api_key,
+        None => {
+            return HttpResponse::Unauthorized().json(json!({
+                "message": "Invalid API key"
+            }));
+        }
+    };
+
+    let overrides = req.into_inner();
+    if let Err(e) = overrides.validate() {
+        return HttpResponse::BadRequest().json(json!({
+            "message": e
+        }));
+    }
+    match svm.set_sysvar_overrides(id, overrides.clone()) {
+        Ok(_) => {
+            audit(
+                &svm,
+                api_key,
+                blockchain.team_id,
+                Some(id),
+                audit::BLOCKCHAIN_SYSVARS_SET,
+                json!(overrides),
+            );
+            HttpResponse::Ok().json(overrides)
+        }
+        Err(e) => HttpResponse::InternalServerError().json(e.to_string()),
+    }
+}
+
+#[get("/blockchains/{id}/jit")]
+pub async fn jit_report(
+    svm: web::Data<Arc<SvmEngine<PgStorage>>>,
+    path: web::Path<Uuid>,
+    http_req: HttpRequest,
+) -> impl Responder {
+    let id = path.into_inner();
+    let blockchain = match svm.storage.get_blockchain(id) {
+        Ok(blockchain) => blockchain,
+        Err(e) => {
+            return HttpResponse::InternalServerError().json(e.to_string());
+        }
+    };
+    if !valid_api_key(blockchain.team_id, svm.clone(), http_req) {
+        return HttpResponse::Unauthorized().json(json!({
+            "message": "Invalid API key"
+        }));
+    }
+
+    // Only what this instance fetched, each one keeps its own
+    let stats = crate::metrics::jit_stats(id).unwrap_or_default();
+    let upstream_ms = stats.upstream_latency.sum().as_millis() as u64;
+    let requests = stats.upstream_latency.count();
+    HttpResponse::Ok().json(json!({
+        "jit": blockchain.jit,
+        "hits": stats.hits,
+        "misses": stats.misses,
+        "found": stats.found,
+        "not_found": stats.misses - stats.found,
+        "bytes": stats.bytes,
+        "errors": stats.errors,
+        "upstream": {
+            "requests": requests,
+            "total_ms": upstream_ms,
+            "avg_ms": upstream_ms.checked_div(requests).unwrap_or(0),
+        },
+        "recent": stats.recent.iter().rev().collect::<Vec<_>>(),
+    }))
+}
+
+#[post("/blockchains/{id}/clone")]
+pub async fn clone_blockchain(
+    svm: web::Data<Arc<SvmEngine<PgStorage>>>,
+    path: web::Path<Uuid>,
+    http_req: HttpRequest,
+    req: Option<web::Json<CloneBlockchainReq>>,
+) -> impl Responder {
+    let source = path.into_inner();
+    let (api_key, team) = match get_caller(&svm, &http_req) {
+        Ok(caller) => caller,
+        Err(e) => {
+            return HttpResponse::Unauthorized().json(json!({
+                "message": e
+            }))
+        }
+    };
+    let blockchain = match svm.storage.get_blockchain(source) {
+        Ok(blockchain) => blockchain,
+        Err(e) => {
+            return HttpResponse::InternalServerError().json(e.to_string());
+        }
+    };
+    if blockchain.team_id != team.id {
+        return HttpResponse::Unauthorized().json(json!({
+            "message": "Invalid API key"
+        }));
+    }
+
+    if let Err(res) = check_blockchain_limit(&svm, &team) {
+        return res;
+    }
+    // The clone gets every account of the source, as counted by its usage
+    let source_accounts = match svm.storage.get_storage_usage(source) {
+        Ok(usage) => usage.accounts as usize,
+        Err(e) => return HttpResponse::InternalServerError().json(e.to_string()),
+    };
+    if let Err(res) = check_new_blockchain_accounts(&svm, team.id, source_accounts) {
+        return res;
+    }
+    let (mut label, expiry) = match team_blockchain_defaults(&team, &http_req) {
+        Ok(defaults) => defaults,
+        Err(res) => return res,
+    };
+    if let Some(req_label) = req.and_then(|req| req.label.clone()) {
+        label = Some(req_label);
+    }
+
+    match svm.clone_blockchain(source, team.id, label.clone(), expiry) {
+        Ok(id) => {
+            audit(
+                &svm,
+                api_key,
+                team.id,
+                Some(id),
+                audit::BLOCKCHAIN_CLONE,
+                json!({ "source": source, "label": label, "expiry": expiry }),
+            );
+            HttpResponse::Ok().json(json!({
+                "url": blockchain_url(id)
+            }))
+        }
+        Err(e) => HttpResponse::InternalServerError().json(e.to_string()),
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct UpdateBlockchainReq {
+    pub label: Option<String>,
+    pub expiry: Option<chrono::NaiveDateTime>,
+    pub extend_seconds: Option<i64>,
+    // 0 keeps every log, null goes back to Solana's limit
+    #[serde(default, deserialize_with = "nullable")]
+    pub log_bytes_limit: Option<Option<i32>>,
+    pub read_only: Option<bool>,
+    pub sponsor_fees: Option<bool>,
+    pub parity_check: Option<bool>,
+}
+
+/// Tells an explicit `null`, `Some(None)`, apart from a missing field, `None`.
+fn nullable<'de, D: serde::Deserializer<'de>, T: Deserialize<'de>>(
+    deserializer: D,
+) -> Result<Option<Option<T>>, D::Error> {
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+#[patch("/blockchains/{id}")]
+pub async fn update_blockchain(
+    svm: web::Data<Arc<SvmEngine<PgStorage>>>,
+    path: web::Path<Uuid>,
+    http_req: HttpRequest,
+    req: web::Json<UpdateBlockchainReq>,
+) -> impl Responder {
+    let id = path.into_inner();
+    let blockchain = match svm.storage.get_blockchain(id) {
+        Ok(blockchain) => blockchain,
+        Err(e) => {
+            return HttpResponse::InternalServerError().json(e.to_string());
+        }
+    };
+    let api_key = match team_api_key(blockchain.team_id, &svm, &http_req) {
+        Some(api_key) => api_key,
+        None => {
+            return HttpResponse::Unauthorized().json(json!({
+                "message": "Invalid API key"
+            }));
+        }
+    };
+
+    let now = chrono::Utc::now().naive_utc();
+    let expiry = match (req.expiry, req.extend_seconds) {
+        (Some(_), Some(_)) => {
+            return HttpResponse::BadRequest().json(json!({
+                "message": "Only one of expiry and extend_seconds can be set"
+            }));
+        }
+        (Some(expiry), None) => {
+            if expiry <= now {
+                return HttpResponse::BadRequest().json(json!({
+                    "message": "Expiry must be in the future"
+                }));
+            }
+            Some(expiry)
+        }
+        (None, Some(seconds)) => {
+            if seconds <= 0 {
+                return HttpResponse::BadRequest().json(json!({
+                    "message": "extend_seconds must be positive"
+                }));
+            }
+            let current = match blockchain.expiry {
+                Some(expiry) => expiry.max(now),
+                None => {
+                    return HttpResponse::BadRequest().json(json!({
+                        "message": "Blockchain does not expire"
+                    }));
+                }
+            };
+            Some(current + chrono::Duration::seconds(seconds))
+        }
+        (None, None) => None,
+    };
+    if req.log_bytes_limit.flatten().is_some_and(|limit| limit < 0) {
+        return HttpResponse::BadRequest().json(json!({
+            "message": "log_bytes_limit cannot be negative"
+        }));
+    }
+    if req.label.is_none()
+        && expiry.is_none()
+        && req.log_bytes_limit.is_none()
+        && req.read_only.is_none()
+        && req.sponsor_fees.is_none()
+        && req.parity_check.is_none()
+    {
+        return HttpResponse::BadRequest().json(json!({
+            "message": "Nothing to update"
+        }));
+    }
+
+    let update = DbBlockchainUpdate {
+        label: req.label.clone(),
+        expiry,
+        log_bytes_limit: req.log_bytes_limit,
+        read_only: req.read_only,
+        sponsor_fees: req.sponsor_fees,
+        parity_check: req.parity_check,
+        accounts_status: None,
+    };
+    match svm.storage.update_blockchain(id, &update) {
+        Ok(blockchain) => {
+            audit(
+                &svm,
+                api_key,
+                blockchain.team_id,
+                Some(id),
+                audit::BLOCKCHAIN_UPDATE,
+                json!({
+                    "label": update.label,
+                    "expiry": update.expiry,
+                    "log_bytes_limit": update.log_bytes_limit,
+                    "read_only": update.read_only,
+                    "sponsor_fees": update.sponsor_fees,
+                    "parity_check": update.parity_check,
+                }),
+            );
+            HttpResponse::Ok().json(json!({
+                "url": blockchain_url(blockchain.id),
+                "label": blockchain.label,
+                "expiry": blockchain.expiry,
+                "log_bytes_limit": blockchain.log_bytes_limit,
+                "read_only": blockchain.read_only,
+                "sponsor_fees": blockchain.sponsor_fees,
+                "parity_check": blockchain.parity_check,
+            }))
+        }
+        Err(e) => HttpResponse::InternalServerError().json(e.to_string()),
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct ConvertAccountToConfigReq {
+    pub account: String,
+    pub blockchain: Uuid,
+    pub config: Uuid,
+}
+
+#[post("/accounts/convert")]
+pub async fn convert_account_to_config(
+    svm: web::Data<Arc<SvmEngine<PgStorage>>>,
+    req: web::Json<ConvertAccountToConfigReq>,
+) -> impl Responder {
+    let pubkey = match Pubkey::from_str(&req.account) {
+        Ok(pubkey) => pubkey,
+        Err(_) => {
+            return HttpResponse::BadRequest().json(json!({
+                "message": "Invalid account address"
+            }));
+        }
+    };
+
     let account = match svm.storage.get_account(req.blockchain, &pubkey) {
         Ok(account) => match account {
             Some(account) => account,
             None => {
                 return HttpResponse::BadRequest().json(json!({
-                    "message": "Account not found"
+                    "message": "Account not found"
+                }));
+            }
+        },
+        Err(e) => {
+            return HttpResponse::InternalServerError().json(e.to_string());
+        }
+    };
+
+    // let mut mint = Mint::unpack(&account.data).unwrap();
+    // println!("{:?}", mint.mint_authority);
+    // let authority = pubkey!("5YNmS1R9nNSCDzb5a7mMJ1dwK9uHeAAF4CmPEwKgVWr8");
+    // mint.mint_authority = COption::Some(authority);
+
+    // mint.pack_into_slice(&mut account.data);
+
+    match svm.storage.set_config_account(req.config, &pubkey, account) {
+        Ok(_) => HttpResponse::Ok().json(json!({
+            "message": "Account converted to config account successfully"
+        })),
+        Err(e) => HttpResponse::InternalServerError().json(e.to_string()),
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct CreateConfigReq {
+    pub label: String,
+    /// Program bundles cloned from mainnet into the config's blockchains, e.g. "metaplex"
+    pub bundles: Option<Vec<String>>,
+}
+
+#[post("/configs")]
+pub async fn create_config(
+    svm: web::Data<Arc<SvmEngine<PgStorage>>>,
+    http_req: HttpRequest,
+    req: web::Json<CreateConfigReq>,
+) -> impl Responder {
+    let (api_key, team_id) = match get_caller(&svm, &http_req) {
+        Ok((api_key, team)) => (api_key, team.id),
+        Err(e) => {
+            return HttpResponse::Unauthorized().json(json!({
+                "message": e
+            }))
+        }
+    };
+    if req.label.trim().is_empty() {
+        return HttpResponse::BadRequest().json(json!({
+            "message": "Label cannot be empty"
+        }));
+    }
+    let mut bundles = req.bundles.clone().unwrap_or_default();
+    bundles.sort();
+    bundles.dedup();
+    if let Some(e) = bundles
+        .iter()
+        .find_map(|bundle| bundle_programs(bundle).err())
+    {
+        return HttpResponse::BadRequest().json(json!({
+            "message": e
+        }));
+    }
+
+    match svm
+        .storage
+        .create_config(Some(team_id), req.label.clone(), bundles)
+    {
+        Ok(config) => {
+            audit(
+                &svm,
+                api_key,
+                team_id,
+                None,
+                audit::CONFIG_CREATE,
+                json!({ "config": config.id, "label": config.label, "bundles": config.bundles }),
+            );
+            HttpResponse::Ok().json(config)
+        }
+        Err(e) => HttpResponse::InternalServerError().json(e.to_string()),
+    }
+}
+
+#[get("/configs")]
+pub async fn get_configs(
+    svm: web::Data<Arc<SvmEngine<PgStorage>>>,
+    http_req: HttpRequest,
+) -> impl Responder {
+    let team_id = match get_team_id(svm.clone(), http_req) {
+        Ok(team_id) => team_id,
+        Err(e) => {
+            return HttpResponse::Unauthorized().json(json!({
+                "message": e
+            }))
+        }
+    };
+
+    match svm.storage.get_configs(team_id) {
+        Ok(configs) => HttpResponse::Ok().json(configs),
+        Err(e) => HttpResponse::InternalServerError().json(e.to_string()),
+    }
+}
+
+#[delete("/configs/{id}")]
+pub async fn delete_config(
+    svm: web::Data<Arc<SvmEngine<PgStorage>>>,
+    http_req: HttpRequest,
+    path: web::Path<Uuid>,
+) -> impl Responder {
+    let id = path.into_inner();
+    let (api_key, team_id, config) = match get_owned_config(&svm, http_req.clone(), id) {
+        Ok(owned) => owned,
+        Err(res) => return res,
+    };
+
+    drop_pooled_blockchains(&svm, id);
+    match svm.storage.delete_config(id) {
+        Ok(_) => {
+            audit(
+                &svm,
+                api_key,
+                team_id,
+                None,
+                audit::CONFIG_DELETE,
+                json!({ "config": id, "label": config.label }),
+            );
+            HttpResponse::Ok().json(json!({
+                "message": "Config deleted successfully"
+            }))
+        }
+        Err(e) => HttpResponse::InternalServerError().json(e.to_string()),
+    }
+}
+
+#[put("/configs/{id}/accounts")]
+pub async fn set_config_accounts(
+    svm: web::Data<Arc<SvmEngine<PgStorage>>>,
+    http_req: HttpRequest,
+    path: web::Path<Uuid>,
+    accounts_req: web::Json<Vec<AccountReq>>,
+) -> impl Responder {
+    let id = path.into_inner();
+    let (api_key, team_id, _) = match get_owned_config(&svm, http_req.clone(), id) {
+        Ok(owned) => owned,
+        Err(res) => return res,
+    };
+
+    let accounts: Vec<(Pubkey, Account)> =
+        match accounts_req.iter().map(parse_account_req).collect() {
+            Ok(accounts) => accounts,
+            Err(e) => {
+                return HttpResponse::BadRequest().json(json!({
+                    "message": e
+                }));
+            }
+        };
+    let addresses = accounts
+        .iter()
+        .map(|(address, _)| address.to_string())
+        .collect::<Vec<_>>();
+    let count = accounts.len();
+
+    match svm.storage.set_config_accounts(id, accounts) {
+        Ok(_) => {
+            drop_pooled_blockchains(&svm, id);
+            audit(
+                &svm,
+                api_key,
+                team_id,
+                None,
+                audit::CONFIG_ACCOUNTS_SET,
+                json!({ "config": id, "addresses": addresses }),
+            );
+            HttpResponse::Ok().json(json!({
+                "message": "Config accounts saved successfully",
+                "count": count,
+            }))
+        }
+        Err(e) => HttpResponse::InternalServerError().json(e.to_string()),
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct DeleteConfigAccountsReq {
+    pub addresses: Vec<String>,
+}
+
+#[delete("/configs/{id}/accounts")]
+pub async fn delete_config_accounts(
+    svm: web::Data<Arc<SvmEngine<PgStorage>>>,
+    http_req: HttpRequest,
+    path: web::Path<Uuid>,
+    req: web::Json<DeleteConfigAccountsReq>,
+) -> impl Responder {
+    let id = path.into_inner();
+    let (api_key, team_id, _) = match get_owned_config(&svm, http_req.clone(), id) {
+        Ok(owned) => owned,
+        Err(res) => return res,
+    };
+
+    let addresses: Vec<Pubkey> = match req
+        .addresses
+        .iter()
+        .map(|address| Pubkey::from_str(address))
+        .collect()
+    {
+        Ok(addresses) => addresses,
+        Err(_) => {
+            return HttpResponse::BadRequest().json(json!({
+                "message": "Invalid address"
+            }));
+        }
+    };
+
+    match svm.storage.delete_config_accounts(id, &addresses) {
+        Ok(count) => {
+            drop_pooled_blockchains(&svm, id);
+            audit(
+                &svm,
+                api_key,
+                team_id,
+                None,
+                audit::CONFIG_ACCOUNTS_DELETE,
+                json!({ "config": id, "addresses": req.addresses }),
+            );
+            HttpResponse::Ok().json(json!({
+                "message": "Config accounts deleted successfully",
+                "count": count,
+            }))
+        }
+        Err(e) => HttpResponse::InternalServerError().json(e.to_string()),
+    }
+}
+
+#[get("/configs/{id}/blockchains")]
+pub async fn get_config_blockchains(
+    svm: web::Data<Arc<SvmEngine<PgStorage>>>,
+    http_req: HttpRequest,
+    path: web::Path<Uuid>,
+) -> impl Responder {
+    let id = path.into_inner();
+    let team_id = match get_team_id(svm.clone(), http_req) {
+        Ok(team_id) => team_id,
+        Err(e) => {
+            return HttpResponse::Unauthorized().json(json!({
+                "message": e
+            }))
+        }
+    };
+    match svm.storage.get_config(id) {
+        Ok(Some(config)) if config.is_visible_to(team_id) => {}
+        Ok(_) => {
+            return HttpResponse::NotFound().json(json!({
+                "message": "Config not found"
+            }))
+        }
+        Err(e) => return HttpResponse::InternalServerError().json(e.to_string()),
+    }
+
+    // Only the caller's blockchains, shared configs are used by other teams too
+    match svm.storage.get_blockchains_by_config(team_id, id) {
+        Ok(blockchains) => HttpResponse::Ok().json(
+            blockchains
+                .into_iter()
+                .map(|blockchain| {
+                    json!({
+                        "url": blockchain_url(blockchain.id),
+                        "label": blockchain.label,
+                        "created_at": blockchain.created_at,
+                        "expiry": blockchain.expiry,
+                    })
+                })
+                .collect::<Vec<_>>(),
+        ),
+        Err(e) => HttpResponse::InternalServerError().json(e.to_string()),
+    }
+}
+
+fn get_owned_config(
+    svm: &web::Data<Arc<SvmEngine<PgStorage>>>,
+    http_req: HttpRequest,
+    id: Uuid,
+) -> Result<(Option<Uuid>, Uuid, DBBlockchainConfig), HttpResponse> {
+    let (api_key, team_id) = match get_caller(svm, &http_req) {
+        Ok((api_key, team)) => (api_key, team.id),
+        Err(e) => {
+            return Err(HttpResponse::Unauthorized().json(json!({
+                "message": e
+            })))
+        }
+    };
+    match svm.storage.get_config(id) {
+        Ok(Some(config)) if config.team_id == Some(team_id) => Ok((api_key, team_id, config)),
+        // Shared configs are visible but read-only
+        Ok(Some(config)) if config.team_id.is_none() => {
+            Err(HttpResponse::Forbidden().json(json!({
+                "message": "Shared configs cannot be modified"
+            })))
+        }
+        Ok(_) => Err(HttpResponse::NotFound().json(json!({
+            "message": "Config not found"
+        }))),
+        Err(e) => Err(HttpResponse::InternalServerError().json(e.to_string())),
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct CreateApiKeyReq {
+    pub label: String,
+    pub scope: Option<String>,
+    pub role: Option<String>, // Defaults to developer
+}
+
+#[post("/api_keys")]
+pub async fn create_api_key(
+    svm: web::Data<Arc<SvmEngine<PgStorage>>>,
+    http_req: HttpRequest,
+    req: web::Json<CreateApiKeyReq>,
+) -> impl Responder {
+    let (caller, team_id) = match auth::authenticate(&svm.storage, &http_req, Role::Owner) {
+        Ok((caller, team)) => (caller.map(|caller| caller.id), team.id),
+        Err(e) => {
+            return HttpResponse::Unauthorized().json(json!({
+                "message": e
+            }))
+        }
+    };
+    let scope = req.scope.clone().unwrap_or(SCOPE_FULL.to_string());
+    if !API_KEY_SCOPES.contains(&scope.as_str()) {
+        return HttpResponse::BadRequest().json(json!({
+            "message": format!("scope must be one of {:?}", API_KEY_SCOPES)
+        }));
+    }
+    let role = match &req.role {
+        Some(role) => match Role::from_str(role) {
+            Ok(role) => role,
+            Err(e) => {
+                return HttpResponse::BadRequest().json(json!({
+                    "message": e
                 }));
             }
         },
+        None => Role::Developer,
+    };
+
+    let api_key = ApiKey {
+        id: Uuid::new_v4(),
+        created_at: chrono::Utc::now().naive_utc(),
+        team_id,
+        label: req.label.clone(),
+        scope,
+        revoked_at: None,
+        role: role.as_str().to_string(),
+    };
+    match svm.storage.create_api_key(&api_key) {
+        Ok(_) => {
+            audit(
+                &svm,
+                caller,
+                team_id,
+                None,
+                audit::API_KEY_CREATE,
+                json!({
+                    "key": api_key.id,
+                    "label": api_key.label,
+                    "scope": api_key.scope,
+                    "role": api_key.role,
+                }),
+            );
+            HttpResponse::Ok().json(api_key_json(&api_key))
+        }
+        Err(e) => HttpResponse::InternalServerError().json(e.to_string()),
+    }
+}
+
+#[get("/api_keys")]
+pub async fn get_api_keys(
+    svm: web::Data<Arc<SvmEngine<PgStorage>>>,
+    http_req: HttpRequest,
+) -> impl Responder {
+    // Listing reveals the keys themselves
+    let team_id = match auth::authenticate(&svm.storage, &http_req, Role::Owner) {
+        Ok((_, team)) => team.id,
+        Err(e) => {
+            return HttpResponse::Unauthorized().json(json!({
+                "message": e
+            }))
+        }
+    };
+
+    match svm.storage.get_api_keys(team_id) {
+        Ok(api_keys) => {
+            HttpResponse::Ok().json(api_keys.iter().map(api_key_json).collect::<Vec<_>>())
+        }
+        Err(e) => HttpResponse::InternalServerError().json(e.to_string()),
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct UpdateApiKeyReq {
+    pub label: String,
+}
+
+#[patch("/api_keys/{key}")]
+pub async fn update_api_key(
+    svm: web::Data<Arc<SvmEngine<PgStorage>>>,
+    path: web::Path<Uuid>,
+    http_req: HttpRequest,
+    req: web::Json<UpdateApiKeyReq>,
+) -> impl Responder {
+    let key = path.into_inner();
+    let (caller, team_id) = match auth::authenticate(&svm.storage, &http_req, Role::Owner) {
+        Ok((caller, team)) => (caller.map(|caller| caller.id), team.id),
+        Err(e) => {
+            return HttpResponse::Unauthorized().json(json!({
+                "message": e
+            }))
+        }
+    };
+
+    match svm
+        .storage
+        .set_api_key_label(team_id, key, req.label.clone())
+    {
+        Ok(true) => {
+            audit(
+                &svm,
+                caller,
+                team_id,
+                None,
+                audit::API_KEY_UPDATE,
+                json!({ "key": key, "label": req.label }),
+            );
+            HttpResponse::Ok().json(json!({
+                "message": "API key updated successfully"
+            }))
+        }
+        Ok(false) => HttpResponse::NotFound().json(json!({
+            "message": "API key not found"
+        })),
+        Err(e) => HttpResponse::InternalServerError().json(e.to_string()),
+    }
+}
+
+#[delete("/api_keys/{key}")]
+pub async fn revoke_api_key(
+    svm: web::Data<Arc<SvmEngine<PgStorage>>>,
+    path: web::Path<Uuid>,
+    http_req: HttpRequest,
+) -> impl Responder {
+    let key = path.into_inner();
+    let (caller, team_id) = match auth::authenticate(&svm.storage, &http_req, Role::Owner) {
+        Ok((caller, team)) => (caller.map(|caller| caller.id), team.id),
+        Err(e) => {
+            return HttpResponse::Unauthorized().json(json!({
+                "message": e
+            }))
+        }
+    };
+
+    match svm.storage.revoke_api_key(team_id, key) {
+        Ok(true) => {
+            audit(
+                &svm,
+                caller,
+                team_id,
+                None,
+                audit::API_KEY_REVOKE,
+                json!({ "key": key }),
+            );
+            HttpResponse::Ok().json(json!({
+                "message": "API key revoked successfully"
+            }))
+        }
+        Ok(false) => HttpResponse::NotFound().json(json!({
+            "message": "API key not found"
+        })),
+        Err(e) => HttpResponse::InternalServerError().json(e.to_string()),
+    }
+}
+
+fn api_key_json(api_key: &ApiKey) -> serde_json::Value {
+    json!({
+        "key": api_key.id,
+        "created_at": api_key.created_at,
+        "label": api_key.label,
+        "scope": api_key.scope,
+        "role": api_key.role,
+        "revoked_at": api_key.revoked_at,
+    })
+}
+
+const DEFAULT_AUDIT_LOG_PAGE_SIZE: i64 = 100;
+const MAX_AUDIT_LOG_PAGE_SIZE: i64 = 1000;
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct GetAuditLogsReq {
+    pub blockchain: Option<Uuid>,
+    pub action: Option<String>,
+    pub since: Option<chrono::NaiveDateTime>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+#[get("/audit_logs")]
+pub async fn get_audit_logs(
+    svm: web::Data<Arc<SvmEngine<PgStorage>>>,
+    http_req: HttpRequest,
+    req: web::Query<GetAuditLogsReq>,
+) -> impl Responder {
+    // Entries name the API key that made each change
+    let team_id = match auth::authenticate(&svm.storage, &http_req, Role::Owner) {
+        Ok((_, team)) => team.id,
+        Err(e) => {
+            return HttpResponse::Unauthorized().json(json!({
+                "message": e
+            }))
+        }
+    };
+    let limit = req.limit.unwrap_or(DEFAULT_AUDIT_LOG_PAGE_SIZE);
+    if !(1..=MAX_AUDIT_LOG_PAGE_SIZE).contains(&limit) {
+        return HttpResponse::BadRequest().json(json!({
+            "message": format!("limit must be between 1 and {}", MAX_AUDIT_LOG_PAGE_SIZE)
+        }));
+    }
+    let offset = req.offset.unwrap_or(0);
+    if offset < 0 {
+        return HttpResponse::BadRequest().json(json!({
+            "message": "offset cannot be negative"
+        }));
+    }
+
+    let filter = AuditLogFilter {
+        blockchain: req.blockchain,
+        action: req.action.clone(),
+        actions: vec![],
+        since: req.since,
+        limit,
+        offset,
+    };
+    match svm.storage.get_audit_logs(team_id, &filter) {
+        Ok((logs, total)) => HttpResponse::Ok().json(json!({
+            "audit_logs": logs
+                .into_iter()
+                .map(|log| {
+                    json!({
+                        "id": log.id,
+                        "created_at": log.created_at,
+                        "api_key": log.api_key,
+                        "action": log.action,
+                        "blockchain": log.blockchain,
+                        "summary": serde_json::from_str::<serde_json::Value>(&log.summary)
+                            .unwrap_or(serde_json::Value::String(log.summary)),
+                    })
+                })
+                .collect::<Vec<_>>(),
+            "total": total,
+            "limit": limit,
+            "offset": offset,
+        })),
+        Err(e) => HttpResponse::InternalServerError().json(e.to_string()),
+    }
+}
+
+/// Manual trigger for the expiry job, kept for operators.
+#[post("/blockchains/expire")]
+pub async fn expire_blockchains(
+    svm: web::Data<Arc<SvmEngine<PgStorage>>>,
+    http_req: HttpRequest,
+) -> impl Responder {
+    if !auth::valid_admin_token(&http_req) {
+        return HttpResponse::Unauthorized().json(json!({
+            "message": "Invalid admin token"
+        }));
+    }
+
+    match svm.expire_blockchains() {
+        Ok(deleted) => HttpResponse::Ok().json(json!({
+            "message": "Expired blockchains deleted successfully",
+            "deleted": deleted,
+        })),
+        Err(e) => HttpResponse::InternalServerError().json(e.to_string()),
+    }
+}
+
+const DEFAULT_ADMIN_PAGE_SIZE: i64 = 100;
+const MAX_ADMIN_PAGE_SIZE: i64 = 1000;
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct AdminListBlockchainsReq {
+    pub team_id: Option<Uuid>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+#[get("/admin/blockchains")]
+pub async fn admin_list_blockchains(
+    svm: web::Data<Arc<SvmEngine<PgStorage>>>,
+    http_req: HttpRequest,
+    req: web::Query<AdminListBlockchainsReq>,
+) -> impl Responder {
+    if !auth::valid_admin_token(&http_req) {
+        return HttpResponse::Unauthorized().json(json!({
+            "message": "Invalid admin token"
+        }));
+    }
+    let limit = req.limit.unwrap_or(DEFAULT_ADMIN_PAGE_SIZE);
+    if !(1..=MAX_ADMIN_PAGE_SIZE).contains(&limit) {
+        return HttpResponse::BadRequest().json(json!({
+            "message": format!("limit must be between 1 and {}", MAX_ADMIN_PAGE_SIZE)
+        }));
+    }
+    let offset = req.offset.unwrap_or(0);
+    if offset < 0 {
+        return HttpResponse::BadRequest().json(json!({
+            "message": "offset cannot be negative"
+        }));
+    }
+
+    let filter = BlockchainFilter {
+        team_id: req.team_id,
+        limit: Some(limit),
+        offset,
+        ..Default::default()
+    };
+    let (blockchains, total) = match svm.storage.get_all_blockchains(&filter) {
+        Ok(blockchains) => blockchains,
+        Err(e) => return HttpResponse::InternalServerError().json(e.to_string()),
+    };
+    let queues = svm.engine_stats().queues;
+    let mut results = Vec::with_capacity(blockchains.len());
+    for blockchain in blockchains {
+        // Usage counters live in the cache, so this stays cheap for a full page
+        let usage = match svm.storage.get_storage_usage(blockchain.id) {
+            Ok(usage) => usage,
+            Err(e) => return HttpResponse::InternalServerError().json(e.to_string()),
+        };
+        let queued = queues
+            .iter()
+            .find(|queue| queue.blockchain == blockchain.id)
+            .map(|queue| queue.queued)
+            .unwrap_or(0);
+        results.push(json!({
+            "id": blockchain.id,
+            "team_id": blockchain.team_id,
+            "label": blockchain.label,
+            "created_at": blockchain.created_at,
+            "expiry": blockchain.expiry,
+            "jit": blockchain.jit,
+            "config": blockchain.config,
+            "usage": usage,
+            "queued_transactions": queued,
+        }));
+    }
+
+    HttpResponse::Ok().json(json!({
+        "blockchains": results,
+        "total": total,
+        "limit": limit,
+        "offset": offset,
+    }))
+}
+
+#[delete("/admin/blockchains/{id}")]
+pub async fn admin_delete_blockchain(
+    svm: web::Data<Arc<SvmEngine<PgStorage>>>,
+    path: web::Path<Uuid>,
+    http_req: HttpRequest,
+) -> impl Responder {
+    if !auth::valid_admin_token(&http_req) {
+        return HttpResponse::Unauthorized().json(json!({
+            "message": "Invalid admin token"
+        }));
+    }
+    let id = path.into_inner();
+    let blockchain = match svm.storage.get_blockchain(id) {
+        Ok(blockchain) => blockchain,
+        Err(e) => {
+            return HttpResponse::InternalServerError().json(e.to_string());
+        }
+    };
+
+    match svm.delete_blockchain(id) {
+        Ok(_) => {
+            audit(
+                &svm,
+                None,
+                blockchain.team_id,
+                Some(id),
+                audit::BLOCKCHAIN_DELETE,
+                json!({ "label": blockchain.label, "admin": true }),
+            );
+            HttpResponse::Ok().json(json!({
+                "message": "Blockchain deleted successfully"
+            }))
+        }
+        Err(e) => HttpResponse::InternalServerError().json(e.to_string()),
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct TransferBlockchainReq {
+    pub team_id: Uuid,
+}
+
+#[post("/admin/blockchains/{id}/transfer")]
+pub async fn admin_transfer_blockchain(
+    svm: web::Data<Arc<SvmEngine<PgStorage>>>,
+    path: web::Path<Uuid>,
+    http_req: HttpRequest,
+    req: web::Json<TransferBlockchainReq>,
+) -> impl Responder {
+    if !auth::valid_admin_token(&http_req) {
+        return HttpResponse::Unauthorized().json(json!({
+            "message": "Invalid admin token"
+        }));
+    }
+    let id = path.into_inner();
+    let blockchain = match svm.storage.get_blockchain(id) {
+        Ok(blockchain) => blockchain,
         Err(e) => {
             return HttpResponse::InternalServerError().json(e.to_string());
         }
     };
+    match svm.storage.get_team(req.team_id) {
+        Ok(Some(_)) => {}
+        Ok(None) => {
+            return HttpResponse::NotFound().json(json!({
+                "message": "Team not found"
+            }))
+        }
+        Err(e) => return HttpResponse::InternalServerError().json(e.to_string()),
+    }
+    if blockchain.team_id == req.team_id {
+        return HttpResponse::BadRequest().json(json!({
+            "message": "Blockchain already belongs to the team"
+        }));
+    }
 
-    // let mut mint = Mint::unpack(&account.data).unwrap();
-    // println!("{:?}", mint.mint_authority);
-    // let authority = pubkey!("5YNmS1R9nNSCDzb5a7mMJ1dwK9uHeAAF4CmPEwKgVWr8");
-    // mint.mint_authority = COption::Some(authority);
+    match svm.storage.transfer_blockchain(id, req.team_id) {
+        Ok(_) => {
+            // Both teams see the transfer in their audit log
+            let summary = json!({ "from": blockchain.team_id, "to": req.team_id });
+            for team_id in [blockchain.team_id, req.team_id] {
+                audit(
+                    &svm,
+                    None,
+                    team_id,
+                    Some(id),
+                    audit::BLOCKCHAIN_TRANSFER,
+                    summary.clone(),
+                );
+            }
+            HttpResponse::Ok().json(json!({
+                "message": "Blockchain transferred successfully",
+                "team_id": req.team_id,
+            }))
+        }
+        Err(e) => HttpResponse::InternalServerError().json(e.to_string()),
+    }
+}
 
-    // mint.pack_into_slice(&mut account.data);
+/// Slots replayed per request unless it asks for fewer.
+const DEFAULT_REPLAY_SLOTS: i64 = 100;
+const MAX_REPLAY_SLOTS: i64 = 1000;
+/// Blocks plus transactions per page, busy slots end a page before `limit` does.
+const MAX_REPLAY_EVENTS: usize = 10_000;
 
-    match svm.storage.set_config_account(req.config, &pubkey, account) {
-        Ok(_) => HttpResponse::Ok().json(json!({
-            "message": "Account converted to config account successfully"
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ReplayTarget {
+    /// Publishes the events to the geyser topic again
+    #[default]
+    Kafka,
+    /// Returns the events to the caller instead
+    Response,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct GeyserReplayReq {
+    pub from_slot: u64,
+    pub limit: Option<i64>,
+    pub target: Option<ReplayTarget>,
+}
+
+/// Replays a blockchain's block and transaction events from `from_slot` on, so
+/// indexers joining late can backfill. Only slots that have closed are replayed,
+/// `next_slot` is where the next page starts and null once the replay reaches
+/// the slot still being produced, which the live topic covers.
+#[post("/admin/blockchains/{id}/geyser/replay")]
+pub async fn admin_replay_geyser_events(
+    svm: web::Data<Arc<SvmEngine<PgStorage>>>,
+    path: web::Path<Uuid>,
+    http_req: HttpRequest,
+    req: web::Json<GeyserReplayReq>,
+) -> impl Responder {
+    if !auth::valid_admin_token(&http_req) {
+        return HttpResponse::Unauthorized().json(json!({
+            "message": "Invalid admin token"
+        }));
+    }
+    let id = path.into_inner();
+    let limit = req.limit.unwrap_or(DEFAULT_REPLAY_SLOTS);
+    if !(1..=MAX_REPLAY_SLOTS).contains(&limit) {
+        return HttpResponse::BadRequest().json(json!({
+            "message": format!("limit must be between 1 and {}", MAX_REPLAY_SLOTS)
+        }));
+    }
+    let blockchain = match svm.storage.get_blockchain(id) {
+        Ok(blockchain) => blockchain,
+        Err(e) => return HttpResponse::InternalServerError().json(e.to_string()),
+    };
+    let current_slot = match svm.storage.get_latest_block(id) {
+        Ok(block) => block.block_height,
+        Err(e) => return HttpResponse::InternalServerError().json(e.to_string()),
+    };
+    let storage = svm.storage.clone();
+    let from_slot = req.from_slot;
+    let events = match web::block(move || {
+        storage.get_slot_history(id, from_slot, current_slot, limit, MAX_REPLAY_EVENTS)
+    })
+    .await
+    {
+        Ok(Ok(events)) => events,
+        Ok(Err(e)) => return HttpResponse::InternalServerError().json(e.to_string()),
+        Err(e) => return HttpResponse::InternalServerError().json(e.to_string()),
+    };
+
+    let slots: Vec<u64> = events
+        .iter()
+        .filter_map(|event| match event {
+            GeyserEvent::Block(block) => Some(block.slot as u64),
+            GeyserEvent::Transaction(_) => None,
+        })
+        .collect();
+    // The page may have ended before `limit` slots on MAX_REPLAY_EVENTS
+    let next_slot = match slots.last() {
+        Some(last) if last + 1 < current_slot => Some(last + 1),
+        _ => None,
+    };
+
+    match req.target.unwrap_or_default() {
+        ReplayTarget::Response => HttpResponse::Ok().json(json!({
+            "slots": slots.len(),
+            "next_slot": next_slot,
+            "events": events,
+        })),
+        ReplayTarget::Kafka => {
+            // Waits for delivery, which can take up to the replay timeout
+            let storage = svm.storage.clone();
+            let published = events.len();
+            match web::block(move || storage.replay_geyser_events(&events)).await {
+                Ok(Ok(())) => {}
+                Ok(Err(e)) => return HttpResponse::InternalServerError().json(e.to_string()),
+                Err(e) => return HttpResponse::InternalServerError().json(e.to_string()),
+            }
+            audit(
+                &svm,
+                None,
+                blockchain.team_id,
+                Some(id),
+                audit::BLOCKCHAIN_GEYSER_REPLAY,
+                json!({ "from_slot": req.from_slot, "slots": slots.len() }),
+            );
+            HttpResponse::Ok().json(json!({
+                "slots": slots.len(),
+                "next_slot": next_slot,
+                "published": published,
+            }))
+        }
+    }
+}
+
+#[put("/admin/teams/{id}/quotas")]
+pub async fn admin_set_team_quotas(
+    svm: web::Data<Arc<SvmEngine<PgStorage>>>,
+    path: web::Path<Uuid>,
+    http_req: HttpRequest,
+    req: web::Json<TeamQuotas>,
+) -> impl Responder {
+    if !auth::valid_admin_token(&http_req) {
+        return HttpResponse::Unauthorized().json(json!({
+            "message": "Invalid admin token"
+        }));
+    }
+    let id = path.into_inner();
+    if let Err(e) = req.validate() {
+        return HttpResponse::BadRequest().json(json!({
+            "message": e
+        }));
+    }
+
+    match svm.storage.set_team_quotas(id, &req) {
+        Ok(Some(team)) => {
+            audit(
+                &svm,
+                None,
+                id,
+                None,
+                audit::TEAM_QUOTAS_SET,
+                json!(team.quotas()),
+            );
+            HttpResponse::Ok().json(json!({
+                "team_id": id,
+                "quotas": team.quotas(),
+            }))
+        }
+        Ok(None) => HttpResponse::NotFound().json(json!({
+            "message": "Team not found"
         })),
         Err(e) => HttpResponse::InternalServerError().json(e.to_string()),
     }
 }
 
-#[post("/blockchains/expire")]
-pub async fn expire_blockchains(svm: web::Data<Arc<SvmEngine<PgStorage>>>) -> impl Responder {
-    let expired_blockchains = match svm.storage.get_expired_blockchains() {
-        Ok(blockchains) => blockchains,
-        Err(e) => return HttpResponse::InternalServerError().json(e.to_string()),
-    };
+#[derive(Deserialize, Debug, Clone)]
+pub struct TeamAutoPauseReq {
+    pub idle_hours: Option<i32>, // Unset never pauses
+}
+
+#[put("/admin/teams/{id}/auto_pause")]
+pub async fn admin_set_team_auto_pause(
+    svm: web::Data<Arc<SvmEngine<PgStorage>>>,
+    path: web::Path<Uuid>,
+    http_req: HttpRequest,
+    req: web::Json<TeamAutoPauseReq>,
+) -> impl Responder {
+    if !auth::valid_admin_token(&http_req) {
+        return HttpResponse::Unauthorized().json(json!({
+            "message": "Invalid admin token"
+        }));
+    }
+    let id = path.into_inner();
+    if req.idle_hours.is_some_and(|hours| hours < 0) {
+        return HttpResponse::BadRequest().json(json!({
+            "message": "idle_hours cannot be negative"
+        }));
+    }
+
+    match svm.storage.set_team_auto_pause(id, req.idle_hours) {
+        Ok(Some(_)) => {
+            audit(
+                &svm,
+                None,
+                id,
+                None,
+                audit::TEAM_AUTO_PAUSE_SET,
+                json!({ "idle_hours": req.idle_hours }),
+            );
+            HttpResponse::Ok().json(json!({
+                "team_id": id,
+                "idle_hours": req.idle_hours,
+            }))
+        }
+        Ok(None) => HttpResponse::NotFound().json(json!({
+            "message": "Team not found"
+        })),
+        Err(e) => HttpResponse::InternalServerError().json(e.to_string()),
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct TeamRpcWriteGuardReq {
+    pub enabled: bool,
+}
+
+#[put("/admin/teams/{id}/rpc_write_guard")]
+pub async fn admin_set_team_rpc_write_guard(
+    svm: web::Data<Arc<SvmEngine<PgStorage>>>,
+    path: web::Path<Uuid>,
+    http_req: HttpRequest,
+    req: web::Json<TeamRpcWriteGuardReq>,
+) -> impl Responder {
+    if !auth::valid_admin_token(&http_req) {
+        return HttpResponse::Unauthorized().json(json!({
+            "message": "Invalid admin token"
+        }));
+    }
+    let id = path.into_inner();
 
-    for blockchain in expired_blockchains {
-        if let Err(e) = svm.delete_blockchain(blockchain.id) {
-            println!("Error deleting blockchain {}: {}", blockchain.id, e);
+    match svm.storage.set_team_rpc_write_guard(id, req.enabled) {
+        Ok(Some(_)) => {
+            audit(
+                &svm,
+                None,
+                id,
+                None,
+                audit::TEAM_RPC_WRITE_GUARD_SET,
+                json!({ "enabled": req.enabled }),
+            );
+            HttpResponse::Ok().json(json!({
+                "team_id": id,
+                "enabled": req.enabled,
+            }))
         }
+        Ok(None) => HttpResponse::NotFound().json(json!({
+            "message": "Team not found"
+        })),
+        Err(e) => HttpResponse::InternalServerError().json(e.to_string()),
     }
+}
 
-    HttpResponse::Ok().json(json!({
-        "message": "Expired blockchains deleted successfully"
-    }))
+#[get("/admin/stats")]
+pub async fn admin_stats(
+    svm: web::Data<Arc<SvmEngine<PgStorage>>>,
+    http_req: HttpRequest,
+) -> impl Responder {
+    if !auth::valid_admin_token(&http_req) {
+        return HttpResponse::Unauthorized().json(json!({
+            "message": "Invalid admin token"
+        }));
+    }
+
+    HttpResponse::Ok().json(svm.engine_stats())
+}
+
+/// Each transaction queue with the transaction it's executing and where time goes per stage,
+/// for when sends appear to hang.
+#[get("/admin/pipeline")]
+pub async fn admin_pipeline(
+    svm: web::Data<Arc<SvmEngine<PgStorage>>>,
+    http_req: HttpRequest,
+) -> impl Responder {
+    if !auth::valid_admin_token(&http_req) {
+        return HttpResponse::Unauthorized().json(json!({
+            "message": "Invalid admin token"
+        }));
+    }
+
+    let queues: Vec<_> = svm
+        .engine_stats()
+        .queues
+        .into_iter()
+        .map(|queue| {
+            let stages: serde_json::Map<String, serde_json::Value> =
+                crate::metrics::stage_stats(queue.blockchain)
+                    .into_iter()
+                    .map(|(stage, histogram)| {
+                        let mean_ms = histogram.sum().as_secs_f64() * 1000.0
+                            / histogram.count().max(1) as f64;
+                        (
+                            stage.to_string(),
+                            json!({ "count": histogram.count(), "mean_ms": mean_ms }),
+                        )
+                    })
+                    .collect();
+            json!({
+                "blockchain": queue.blockchain,
+                "queued": queue.queued,
+                "oldest_queued_ms": queue.oldest_queued_ms,
+                "processing": queue.processing,
+                "processed": queue.processed,
+                "stages": stages,
+            })
+        })
+        .collect();
+    HttpResponse::Ok().json(json!({ "queues": queues }))
+}
+
+/// Blockchains whose RPC requests and responses are all logged on this instance.
+#[get("/admin/debug")]
+pub async fn admin_debug_blockchains(http_req: HttpRequest) -> impl Responder {
+    if !auth::valid_admin_token(&http_req) {
+        return HttpResponse::Unauthorized().json(json!({
+            "message": "Invalid admin token"
+        }));
+    }
+
+    HttpResponse::Ok().json(json!({ "blockchains": rpc_log::debug_list() }))
+}
+
+#[put("/admin/debug/{id}")]
+pub async fn admin_enable_debug(path: web::Path<Uuid>, http_req: HttpRequest) -> impl Responder {
+    if !auth::valid_admin_token(&http_req) {
+        return HttpResponse::Unauthorized().json(json!({
+            "message": "Invalid admin token"
+        }));
+    }
+
+    let id = path.into_inner();
+    rpc_log::set_debug(id, true);
+    HttpResponse::Ok().json(json!({ "blockchain": id, "debug": true }))
+}
+
+#[delete("/admin/debug/{id}")]
+pub async fn admin_disable_debug(path: web::Path<Uuid>, http_req: HttpRequest) -> impl Responder {
+    if !auth::valid_admin_token(&http_req) {
+        return HttpResponse::Unauthorized().json(json!({
+            "message": "Invalid admin token"
+        }));
+    }
+
+    let id = path.into_inner();
+    if !rpc_log::set_debug(id, false) {
+        return HttpResponse::NotFound().json(json!({
+            "message": "Debug logging is not enabled for this blockchain"
+        }));
+    }
+    HttpResponse::Ok().json(json!({ "blockchain": id, "debug": false }))
+}
+
+const MAX_BLOCKCHAIN_PAGE_SIZE: i64 = 1000;
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct ListBlockchainsReq {
+    pub label: Option<String>,
+    /// Same as `label`, for blockchains created with the `user_id` header.
+    pub user_id: Option<String>,
+    pub created_after: Option<chrono::NaiveDateTime>,
+    pub expiring_before: Option<chrono::NaiveDateTime>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
 }
 
 #[get("/blockchains")]
 pub async fn get_blockchains(
     svm: web::Data<Arc<SvmEngine<PgStorage>>>,
     http_req: HttpRequest,
+    req: web::Query<ListBlockchainsReq>,
 ) -> impl Responder {
     let team_id = match get_team_id(svm.clone(), http_req) {
         Ok(team_id) => team_id,
@@ -419,10 +4723,51 @@ pub async fn get_blockchains(
             }))
         }
     };
-    let res = svm.get_blockchains(team_id);
-    match res {
-        Ok(blockchains) => HttpResponse::Ok().json(json!({
-            "blockchains": blockchains.iter().map(|b| format!("https://rpc.mirror.ad/rpc/{}", b.id.to_string())).collect::<Vec<String>>()
+    let req = req.into_inner();
+    let label = match (req.label, req.user_id) {
+        (Some(label), Some(user_id)) if label != user_id => {
+            return HttpResponse::BadRequest().json(json!({
+                "message": "label and user_id must match when both are set"
+            }));
+        }
+        (label, user_id) => label.or(user_id),
+    };
+    // Unpaginated by default, so existing callers still get every blockchain
+    if let Some(limit) = req.limit {
+        if !(1..=MAX_BLOCKCHAIN_PAGE_SIZE).contains(&limit) {
+            return HttpResponse::BadRequest().json(json!({
+                "message": format!("limit must be between 1 and {}", MAX_BLOCKCHAIN_PAGE_SIZE)
+            }));
+        }
+    }
+    let offset = req.offset.unwrap_or(0);
+    if offset < 0 {
+        return HttpResponse::BadRequest().json(json!({
+            "message": "offset cannot be negative"
+        }));
+    }
+
+    let filter = BlockchainFilter {
+        team_id: Some(team_id),
+        label,
+        created_after: req.created_after,
+        expiring_before: req.expiring_before,
+        limit: req.limit,
+        offset,
+    };
+    match svm.storage.get_all_blockchains(&filter) {
+        Ok((blockchains, total)) => HttpResponse::Ok().json(json!({
+            "blockchains": blockchains.iter().map(|b| blockchain_url(b.id)).collect::<Vec<String>>(),
+            "details": blockchains.iter().map(|b| json!({
+                "id": b.id,
+                "url": blockchain_url(b.id),
+                "label": b.label,
+                "created_at": b.created_at,
+                "expiry": b.expiry,
+                "jit": b.jit,
+                "config": b.config,
+            })).collect::<Vec<_>>(),
+            "total": total,
         })),
         Err(e) => HttpResponse::InternalServerError().json(e.to_string()),
     }
@@ -433,8 +4778,8 @@ pub async fn delete_blockchains(
     svm: web::Data<Arc<SvmEngine<PgStorage>>>,
     http_req: HttpRequest,
 ) -> impl Responder {
-    let team_id = match get_team_id(svm.clone(), http_req) {
-        Ok(team_id) => team_id,
+    let (api_key, team_id) = match auth::authenticate(&svm.storage, &http_req, Role::Developer) {
+        Ok((api_key, team)) => (api_key.map(|api_key| api_key.id), team.id),
         Err(e) => {
             return HttpResponse::Unauthorized().json(json!({
                 "message": e
@@ -448,6 +4793,14 @@ pub async fn delete_blockchains(
 
     for blockchain in blockchains {
         svm.delete_blockchain(blockchain.id).unwrap();
+        audit(
+            &svm,
+            api_key,
+            team_id,
+            Some(blockchain.id),
+            audit::BLOCKCHAIN_DELETE,
+            json!({ "label": blockchain.label }),
+        );
     }
 
     HttpResponse::Ok().json(json!({
@@ -467,82 +4820,204 @@ pub async fn delete_blockchain(
             return HttpResponse::InternalServerError().json(e.to_string());
         }
     };
-    if !valid_api_key(blockchain.team_id, svm.clone(), http_req) {
-        return HttpResponse::Unauthorized().json(json!({
-            "message": "Invalid API key"
-        }));
-    }
+    let api_key = match team_api_key(blockchain.team_id, &svm, &http_req) {
+        Some(api_key) => api_key,
+        None => {
+            return HttpResponse::Unauthorized().json(json!({
+                "message": "Invalid API key"
+            }));
+        }
+    };
     let res = svm.delete_blockchain(id);
     match res {
-        Ok(_) => HttpResponse::Ok().json(json!({
-            "message": "Blockchain deleted successfully"
-        })),
+        Ok(_) => {
+            audit(
+                &svm,
+                api_key,
+                blockchain.team_id,
+                Some(id),
+                audit::BLOCKCHAIN_DELETE,
+                json!({ "label": blockchain.label }),
+            );
+            HttpResponse::Ok().json(json!({
+                "message": "Blockchain deleted successfully"
+            }))
+        }
         Err(e) => HttpResponse::InternalServerError().json(e.to_string()),
     }
 }
 
+/// Warm pool blockchains copied the config's accounts when they were built, they're
+/// dropped on changes and rebuilt by the warm pool job.
+fn drop_pooled_blockchains(svm: &web::Data<Arc<SvmEngine<PgStorage>>>, config: Uuid) {
+    if let Err(e) = svm.storage.delete_pooled_blockchains(config) {
+        println!(
+            "Failed to drop warm pool blockchains of config {}: {}",
+            config, e
+        );
+    }
+}
+
+fn check_blockchain_limit(
+    svm: &web::Data<Arc<SvmEngine<PgStorage>>>,
+    team: &Team,
+) -> Result<(), HttpResponse> {
+    let Some(max_blockchains) = team.blockchain_limit() else {
+        return Ok(());
+    };
+    let existing_blockchains = match svm.get_blockchains(team.id) {
+        Ok(blockchains) => blockchains,
+        Err(e) => return Err(HttpResponse::InternalServerError().json(e.to_string())),
+    };
+
+    if existing_blockchains.len() >= max_blockchains.max(0) as usize {
+        return Err(HttpResponse::BadRequest().json(json!({
+            "message": format!(
+                "Your team's quota is {} blockchains, delete one to create another",
+                max_blockchains
+            )
+        })));
+    }
+    Ok(())
+}
+
+/// Message for writing accounts that would take a blockchain past its team's account
+/// quota, see `engine::account_quota_exceeded`.
+fn account_quota_exceeded(
+    svm: &SvmEngine<PgStorage>,
+    team_id: Uuid,
+    id: Uuid,
+    accounts: &[(Pubkey, Account)],
+) -> Result<Option<String>, String> {
+    let addresses: Vec<Pubkey> = accounts.iter().map(|(address, _)| *address).collect();
+    engine::account_quota_exceeded(&svm.storage, team_id, id, &addresses)
+}
+
+fn check_account_quota(
+    svm: &SvmEngine<PgStorage>,
+    team_id: Uuid,
+    id: Uuid,
+    accounts: &[(Pubkey, Account)],
+) -> Result<(), HttpResponse> {
+    match account_quota_exceeded(svm, team_id, id, accounts) {
+        Ok(None) => Ok(()),
+        Ok(Some(message)) => Err(HttpResponse::BadRequest().json(json!({
+            "message": message
+        }))),
+        Err(e) => Err(HttpResponse::InternalServerError().json(e.to_string())),
+    }
+}
+
+fn check_new_blockchain_accounts(
+    svm: &SvmEngine<PgStorage>,
+    team_id: Uuid,
+    accounts: usize,
+) -> Result<(), HttpResponse> {
+    match engine::new_blockchain_quota_exceeded(&svm.storage, team_id, accounts) {
+        Ok(None) => Ok(()),
+        Ok(Some(message)) => Err(HttpResponse::BadRequest().json(json!({
+            "message": message
+        }))),
+        Err(e) => Err(HttpResponse::InternalServerError().json(e.to_string())),
+    }
+}
+
+/// Label and expiry of a new blockchain. Teams with a default expiry create
+/// blockchains on behalf of their users, identified by the user_id header.
+fn team_blockchain_defaults(
+    team: &Team,
+    http_req: &HttpRequest,
+) -> Result<(Option<String>, Option<chrono::NaiveDateTime>), HttpResponse> {
+    let mut label = None;
+    if team.default_expiry.is_some() {
+        let user_id = match http_req.headers().get("user_id") {
+            Some(user_id) => match user_id.to_str() {
+                Ok(user_id) => user_id.to_string(),
+                Err(_) => {
+                    return Err(HttpResponse::BadRequest().json(json!({
+                        "message": "Invalid user_id header"
+                    })))
+                }
+            },
+            None => {
+                return Err(HttpResponse::BadRequest().json(json!({
+                    "message": "Missing user_id header"
+                })))
+            }
+        };
+        if user_id.is_empty() {
+            return Err(HttpResponse::BadRequest().json(json!({
+                "message": "user_id header cannot be empty"
+            })));
+        }
+        label = Some(user_id);
+    }
+    let expiry = team
+        .default_expiry
+        .map(|expiry| chrono::Utc::now().naive_utc() + chrono::Duration::seconds(expiry as i64));
+    Ok((label, expiry))
+}
+
+fn blockchain_url(id: Uuid) -> String {
+    settings::get().blockchain_url(id)
+}
+
+/// Records a management change in the team's audit log. The change already
+/// happened, so a failed write is logged rather than failing the request.
+fn audit(
+    svm: &web::Data<Arc<SvmEngine<PgStorage>>>,
+    api_key: Option<Uuid>,
+    team_id: Uuid,
+    blockchain: Option<Uuid>,
+    action: &str,
+    summary: serde_json::Value,
+) {
+    let log = DbAuditLog::new(team_id, api_key, action, blockchain, summary);
+    if let Err(e) = svm.storage.create_audit_log(&log) {
+        println!("Failed to record {} for team {}: {}", action, team_id, e);
+    }
+}
+
 fn valid_api_key(
     id: Uuid,
     svm: web::Data<Arc<SvmEngine<PgStorage>>>,
     http_req: HttpRequest,
 ) -> bool {
-    let api_key = http_req
-        .headers()
-        .get("api_key")
-        .and_then(|header_value| header_value.to_str().ok())
-        .unwrap_or("");
-    let api_key = match Uuid::parse_str(api_key) {
-        Ok(api_key) => api_key,
-        Err(_) => {
-            return false;
-        }
-    };
-    let team = match svm.storage.get_team_from_api_key(api_key) {
-        Ok(team) => team,
-        Err(_) => {
-            return false;
-        }
-    };
-    if team.id != id {
-        return false;
+    team_api_key(id, &svm, &http_req).is_some()
+}
+
+/// The verified API key a caller of team `id` authenticated with, `Some(None)`
+/// for dashboard tokens, or `None` when the caller can't act for the team.
+fn team_api_key(
+    id: Uuid,
+    svm: &web::Data<Arc<SvmEngine<PgStorage>>>,
+    http_req: &HttpRequest,
+) -> Option<Option<Uuid>> {
+    match auth::authenticate(&svm.storage, http_req, Role::required_for(http_req)) {
+        Ok((api_key, team)) if team.id == id => Some(api_key.map(|api_key| api_key.id)),
+        _ => None,
     }
-    true
 }
 
 fn get_team_id(
     svm: web::Data<Arc<SvmEngine<PgStorage>>>,
     http_req: HttpRequest,
 ) -> Result<Uuid, String> {
-    let api_key = http_req
-        .headers()
-        .get("api_key")
-        .and_then(|header_value| header_value.to_str().ok())
-        .unwrap_or("");
-
-    match Uuid::parse_str(api_key) {
-        Ok(api_key) => match svm.storage.get_team_from_api_key(api_key) {
-            Ok(team) => Ok(team.id),
-            Err(_) => Err("Invalid API key".to_string()),
-        },
-        Err(_) => Err("Invalid API key".to_string()),
-    }
+    get_team(svm, http_req).map(|team| team.id)
 }
 
 fn get_team(
     svm: web::Data<Arc<SvmEngine<PgStorage>>>,
     http_req: HttpRequest,
 ) -> Result<Team, String> {
-    let api_key = http_req
-        .headers()
-        .get("api_key")
-        .and_then(|header_value| header_value.to_str().ok())
-        .unwrap_or("");
+    get_caller(&svm, &http_req).map(|(_, team)| team)
+}
 
-    match Uuid::parse_str(api_key) {
-        Ok(api_key) => match svm.storage.get_team_from_api_key(api_key) {
-            Ok(team) => Ok(team),
-            Err(_) => Err("Invalid API key".to_string()),
-        },
-        Err(_) => Err("Invalid API key".to_string()),
-    }
+/// The caller's team with the verified API key it authenticated with, if any.
+fn get_caller(
+    svm: &web::Data<Arc<SvmEngine<PgStorage>>>,
+    http_req: &HttpRequest,
+) -> Result<(Option<Uuid>, Team), String> {
+    auth::authenticate(&svm.storage, http_req, Role::required_for(http_req))
+        .map(|(api_key, team)| (api_key.map(|api_key| api_key.id), team))
 }
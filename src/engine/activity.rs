@@ -0,0 +1,53 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+use uuid::Uuid;
+
+/// When this instance last served each blockchain, and which ones it paused for
+/// being idle. Paused blockchains have no queue worker here and resume on their
+/// next request.
+#[derive(Default)]
+pub struct Activity {
+    used: Mutex<HashMap<Uuid, Instant>>,
+    paused: Mutex<HashSet<Uuid>>,
+}
+
+impl Activity {
+    /// Records a request, returns true if it resumed a paused blockchain.
+    pub fn touch(&self, id: Uuid) -> bool {
+        self.used.lock().unwrap().insert(id, Instant::now());
+        self.paused.lock().unwrap().remove(&id)
+    }
+
+    /// Blockchains unused for at least `min`, with how long they've been idle.
+    pub fn idle(&self, min: Duration) -> Vec<(Uuid, Duration)> {
+        let now = Instant::now();
+        self.used
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(id, used)| (*id, now - *used))
+            .filter(|(_, idle)| *idle >= min)
+            .collect()
+    }
+
+    pub fn pause(&self, id: Uuid) {
+        self.used.lock().unwrap().remove(&id);
+        self.paused.lock().unwrap().insert(id);
+    }
+
+    pub fn is_paused(&self, id: Uuid) -> bool {
+        self.paused.lock().unwrap().contains(&id)
+    }
+
+    pub fn paused(&self) -> usize {
+        self.paused.lock().unwrap().len()
+    }
+
+    pub fn forget(&self, id: Uuid) {
+        self.used.lock().unwrap().remove(&id);
+        self.paused.lock().unwrap().remove(&id);
+    }
+}
@@ -1,5 +1,7 @@
+use diesel::{expression::AsExpression, sql_types::Text};
 use serde::Serialize;
 use solana_sdk::{hash::Hash, signature::Keypair, transaction::VersionedTransaction};
+use std::str::FromStr;
 use uuid::Uuid;
 
 #[derive(Serialize)]
@@ -20,4 +22,52 @@ pub struct Blockchain {
     pub label: Option<String>,
     pub expiry: Option<chrono::NaiveDateTime>,
     pub jit: bool,
+    pub config: Option<Uuid>,
+    /// Provisioned ahead of time for the warm pool, hidden until claimed.
+    pub pooled: bool,
+    /// Log bytes kept per transaction, Solana's limit when unset and no limit when 0.
+    pub log_bytes_limit: Option<i32>,
+    /// RPC calls that mutate state need the owning team's API key.
+    pub read_only: bool,
+    /// The airdrop identity pays the fee of transactions whose payer can't.
+    pub sponsor_fees: bool,
+    /// Executed transactions are simulated again on the same state, and any
+    /// difference between the two is logged.
+    pub parity_check: bool,
+    pub accounts_status: AccountsStatus,
+}
+
+/// Whether a blockchain's genesis accounts (builtins, SPL programs, sysvars and
+/// config accounts) are uploaded. Creation can defer the upload to a background
+/// task, the blockchain is usable once they're `Ready`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, AsExpression)]
+#[serde(rename_all = "snake_case")]
+#[diesel(sql_type = Text)]
+pub enum AccountsStatus {
+    Initializing,
+    Ready,
+    Failed,
+}
+
+impl AccountsStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AccountsStatus::Initializing => "initializing",
+            AccountsStatus::Ready => "ready",
+            AccountsStatus::Failed => "failed",
+        }
+    }
+}
+
+impl FromStr for AccountsStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "initializing" => Ok(AccountsStatus::Initializing),
+            "ready" => Ok(AccountsStatus::Ready),
+            "failed" => Ok(AccountsStatus::Failed),
+            _ => Err(format!("Unknown accounts status {}", s)),
+        }
+    }
 }
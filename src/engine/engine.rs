@@ -1,5 +1,6 @@
 use actix_web::rt;
 use chrono::{DateTime, Utc};
+use serde::Serialize;
 use solana_bpf_loader_program::syscalls::{
     create_program_runtime_environment_v1, create_program_runtime_environment_v2,
 };
@@ -13,40 +14,240 @@ use solana_program_runtime::{
 };
 use solana_sdk::{
     account::{Account, AccountSharedData, ReadableAccount, WritableAccount},
+    account_utils::StateMut,
+    bpf_loader_upgradeable::{self, UpgradeableLoaderState},
     clock::Clock,
     epoch_rewards::EpochRewards,
     epoch_schedule::EpochSchedule,
     feature_set::{remove_rounding_in_fee_calculation, FeatureSet},
     fee::FeeStructure,
-    hash::Hash,
+    hash::{hash, Hash},
+    inner_instruction::InnerInstructionsList,
+    instruction::InstructionError,
+    message::SanitizedMessage,
     native_loader,
     pubkey::Pubkey,
     rent::Rent,
     reserved_account_keys::ReservedAccountKeys,
+    signature::Signature,
+    signer::Signer,
     slot_hashes::SlotHashes,
     stake_history::StakeHistory,
+    system_program,
     sysvar::{Sysvar, SysvarId},
     transaction::{MessageHash, SanitizedTransaction, TransactionError, VersionedTransaction},
-    transaction_context::{IndexOfAccount, TransactionContext},
+    transaction_context::{IndexOfAccount, TransactionContext, TransactionReturnData},
 };
 use solana_svm::message_processor::MessageProcessor;
 use solana_timings::ExecuteTimings;
 use std::{
     cell::RefCell,
-    collections::HashMap,
+    collections::{HashMap, HashSet, VecDeque},
+    future::Future,
     rc::Rc,
-    sync::{Arc, Mutex},
+    sync::{
+        atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+use tokio::{
+    sync::mpsc::{self, error::TrySendError},
+    time,
 };
-use tokio::sync::mpsc::{self};
 use uuid::Uuid;
 
-use crate::{engine::tokens::collect_token_balances, storage::Storage};
+use crate::{
+    engine::tokens::collect_token_balances,
+    metrics, reporting, settings,
+    storage::{accounts::is_empty_account, audit::DbAuditLog, Storage},
+};
 
 use super::{
-    blocks::Block, builtins::BUILTINS, construct_instructions_account, execute_tx_helper,
-    transactions::TransactionMetadata, validate_fee_payer, AccountsDB, Loader, RentState,
+    account_quota_exceeded,
+    blocks::{Block, Blockchain},
+    builtins::BUILTINS,
+    construct_instructions_account, execute_tx_helper,
+    parity::ExecutionOutcome,
+    preflight::{LoadedPrograms, Preflight, PreflightCache},
+    status_cache::{CachedStatus, StatusCache},
+    sysvars::{is_engine_sysvar, sysvar_account, OverridesCache, SysvarOverrides},
+    timeline,
+    trace::{self, InstructionTrace},
+    transactions::TransactionMetadata,
+    validate_fee_payer,
+    write_locks::WriteLocks,
+    AccountsDB, Loader, ProcessingStats, QueueStats, RentState, TransactionAccounts,
 };
 
+/// Steps a queued transaction goes through, each one is timed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Stage {
+    Queued,
+    Sanitize,
+    LoadAccounts,
+    Execute,
+    TokenBalances,
+    Save,
+}
+
+impl Stage {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Stage::Queued => "queued",
+            Stage::Sanitize => "sanitize",
+            Stage::LoadAccounts => "load_accounts",
+            Stage::Execute => "execute",
+            Stage::TokenBalances => "token_balances",
+            Stage::Save => "save",
+        }
+    }
+}
+
+#[derive(Default)]
+struct QueueState {
+    // When each queued transaction was sent, oldest first
+    enqueued: VecDeque<Instant>,
+    // Signature, stage and when the stage started
+    current: Option<(String, Stage, Instant)>,
+    processed: u64,
+    // Sends turned away because the queue was full
+    rejected: u64,
+}
+
+#[derive(Clone)]
+struct Queue {
+    sender: mpsc::Sender<(VersionedTransaction, bool)>,
+    state: Arc<Mutex<QueueState>>,
+}
+
+/// What's left of an execution once its context, which can't leave the thread
+/// that executed it, is taken apart.
+struct Execution {
+    result: Result<(), TransactionError>,
+    compute_units: u64,
+    fee: u64,
+    payer_key: Option<Pubkey>,
+    logs: Vec<String>,
+    // None when the transaction failed before it had a context
+    outputs: Option<ExecutionOutputs>,
+    programs: LoadedPrograms,
+    trace: Option<Vec<InstructionTrace>>,
+    timed_out: bool,
+}
+
+type ExecutionOutputs = (
+    Signature,
+    TransactionReturnData,
+    InnerInstructionsList,
+    TransactionAccounts,
+    TransactionAccounts,
+);
+
+impl Execution {
+    /// A transaction failed for running past the execution timeout, without writes.
+    fn timed_out(signature: Signature, timeout: Duration) -> Self {
+        Execution {
+            result: Err(TransactionError::InstructionError(
+                0,
+                InstructionError::ProgramFailedToComplete,
+            )),
+            compute_units: 0,
+            fee: 0,
+            payer_key: None,
+            logs: vec![timeout_message(timeout)],
+            outputs: Some((
+                signature,
+                TransactionReturnData::default(),
+                Vec::new(),
+                Vec::new(),
+                Vec::new(),
+            )),
+            programs: LoadedPrograms::default(),
+            trace: None,
+            timed_out: true,
+        }
+    }
+}
+
+fn timeout_message(timeout: Duration) -> String {
+    format!(
+        "Transaction exceeded the {} ms execution timeout",
+        timeout.as_millis()
+    )
+}
+
+/// Why a transaction wasn't saved. Errors of the engine or its storage are
+/// internal, `?` on them converts.
+enum RunError {
+    /// The transaction couldn't be sanitized or loaded, like a validator rejects it.
+    Rejected(String),
+    Internal(String),
+}
+
+impl From<String> for RunError {
+    fn from(e: String) -> Self {
+        RunError::Internal(e)
+    }
+}
+
+impl std::fmt::Display for RunError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            RunError::Rejected(e) | RunError::Internal(e) => f.write_str(e),
+        }
+    }
+}
+
+/// Returned when a blockchain's queue has no room for another transaction.
+pub const QUEUE_FULL: &str = "Transaction queue is full, try again shortly";
+
+/// Times the stages of one transaction, the last stage ends when it's dropped.
+struct StageTimer {
+    blockchain: Uuid,
+    state: Arc<Mutex<QueueState>>,
+    stage: Stage,
+    started: Instant,
+}
+
+impl StageTimer {
+    fn start(blockchain: Uuid, state: Arc<Mutex<QueueState>>, signature: String) -> Self {
+        let started = {
+            let mut state = state.lock().unwrap();
+            let started = state.enqueued.pop_front().unwrap_or_else(Instant::now);
+            state.current = Some((signature, Stage::Queued, started));
+            started
+        };
+        StageTimer {
+            blockchain,
+            state,
+            stage: Stage::Queued,
+            started,
+        }
+    }
+
+    fn enter(&mut self, stage: Stage) {
+        let now = Instant::now();
+        metrics::record_stage(self.blockchain, self.stage.as_str(), now - self.started);
+        self.stage = stage;
+        self.started = now;
+        if let Some(current) = &mut self.state.lock().unwrap().current {
+            current.1 = stage;
+            current.2 = now;
+        }
+    }
+}
+
+impl Drop for StageTimer {
+    fn drop(&mut self) {
+        metrics::record_stage(self.blockchain, self.stage.as_str(), self.started.elapsed());
+        let mut state = self.state.lock().unwrap();
+        state.current = None;
+        state.processed += 1;
+    }
+}
+
 #[derive(Clone)]
 pub struct TransactionProcessor<T: Storage + Clone + 'static> {
     rent: Rent,
@@ -54,7 +255,22 @@ pub struct TransactionProcessor<T: Storage + Clone + 'static> {
     feature_set: FeatureSet,
     sysvar_cache: SysvarCache,
     storage: T,
-    queue_senders: Arc<Mutex<HashMap<Uuid, mpsc::Sender<(Uuid, VersionedTransaction)>>>>,
+    queues: Arc<Mutex<HashMap<Uuid, Queue>>>,
+    // Queued plus executing transactions, across every blockchain
+    in_flight: Arc<AtomicUsize>,
+    draining: Arc<AtomicBool>,
+    // What simulations loaded, for sending the same transactions
+    preflights: Arc<PreflightCache>,
+    write_locks: Arc<WriteLocks>,
+    statuses: Arc<StatusCache>,
+    sysvar_overrides: Arc<OverridesCache>,
+    // Executions parity checks compared with a simulation, and how many disagreed
+    parity_checks: Arc<AtomicU64>,
+    parity_divergences: Arc<AtomicU64>,
+    // Of each blockchain's queue, see `QueueSettings`
+    queue_capacity: usize,
+    queue_idle_timeout: Option<Duration>,
+    execution_timeout: Option<Duration>,
 }
 
 impl<T: Storage + Clone + 'static> TransactionProcessor<T> {
@@ -66,7 +282,18 @@ impl<T: Storage + Clone + 'static> TransactionProcessor<T> {
         storage: T,
     ) -> Arc<Self> {
         let mut raw_engine = Self {
-            queue_senders: Arc::new(Mutex::new(HashMap::new())),
+            queues: Arc::new(Mutex::new(HashMap::new())),
+            in_flight: Arc::new(AtomicUsize::new(0)),
+            draining: Arc::new(AtomicBool::new(false)),
+            preflights: Arc::default(),
+            write_locks: Arc::default(),
+            statuses: Arc::default(),
+            sysvar_overrides: Arc::default(),
+            parity_checks: Arc::default(),
+            parity_divergences: Arc::default(),
+            queue_capacity: settings::get().queue.capacity,
+            queue_idle_timeout: settings::get().queue.idle_timeout,
+            execution_timeout: settings::get().limits.execution_timeout,
             rent,
             fee_structure,
             feature_set,
@@ -79,32 +306,299 @@ impl<T: Storage + Clone + 'static> TransactionProcessor<T> {
         engine
     }
 
+    /// Reserves a slot for a transaction about to be queued, synchronously so
+    /// a drain can't miss it. Fails once the engine is shutting down.
+    pub fn accept_transaction(&self) -> Result<(), String> {
+        if self.is_draining() {
+            return Err("Engine is shutting down".to_string());
+        }
+        self.in_flight.fetch_add(1, Ordering::AcqRel);
+        Ok(())
+    }
+
+    /// Overrides `queue.capacity` and `queue.idle_timeout_ms` for queues started
+    /// from now on.
+    pub fn set_queue_limits(&mut self, capacity: usize, idle_timeout: Option<Duration>) {
+        self.queue_capacity = capacity;
+        self.queue_idle_timeout = idle_timeout;
+    }
+
+    /// Overrides `limits.execution_timeout_ms`.
+    pub fn set_execution_timeout(&mut self, timeout: Option<Duration>) {
+        self.execution_timeout = timeout;
+    }
+
+    /// Queues a transaction reserved with `accept_transaction`, waiting for room
+    /// when the blockchain's queue is full.
     pub async fn queue_transaction(&self, id: Uuid, raw_tx: VersionedTransaction, jit: bool) {
-        let mut queue_senders = self.queue_senders.lock().unwrap();
-        match queue_senders.get(&id) {
-            Some(sender) => {
-                if let Err(e) = sender.send((id, raw_tx)).await {
-                    println!("Failed to queue transaction: {}", e);
+        let mut raw_tx = raw_tx;
+        loop {
+            let queue = self.queue(id);
+            queue
+                .state
+                .lock()
+                .unwrap()
+                .enqueued
+                .push_back(Instant::now());
+            match queue.sender.send((raw_tx, jit)).await {
+                Ok(()) => return,
+                // Its worker stopped for being idle meanwhile, the next one takes it
+                Err(mpsc::error::SendError((tx, _))) => {
+                    queue.state.lock().unwrap().enqueued.pop_back();
+                    raw_tx = tx;
                 }
             }
-            None => {
-                let (sender, mut receiver) = mpsc::channel(100);
-                queue_senders.insert(id, sender.clone());
+        }
+    }
 
-                if let Err(e) = sender.send((id, raw_tx)).await {
-                    println!("Failed to queue transaction: {}", e);
+    /// Queues a transaction reserved with `accept_transaction`, or releases the
+    /// reservation and fails with `QUEUE_FULL` when the blockchain's queue is full.
+    pub fn try_queue_transaction(
+        &self,
+        id: Uuid,
+        raw_tx: VersionedTransaction,
+        jit: bool,
+    ) -> Result<(), String> {
+        let mut raw_tx = raw_tx;
+        loop {
+            let queue = self.queue(id);
+            queue
+                .state
+                .lock()
+                .unwrap()
+                .enqueued
+                .push_back(Instant::now());
+            match queue.sender.try_send((raw_tx, jit)) {
+                Ok(()) => return Ok(()),
+                Err(TrySendError::Full(_)) => {
+                    let mut state = queue.state.lock().unwrap();
+                    state.enqueued.pop_back();
+                    state.rejected += 1;
+                    self.in_flight.fetch_sub(1, Ordering::AcqRel);
+                    return Err(QUEUE_FULL.to_string());
+                }
+                Err(TrySendError::Closed((tx, _))) => {
+                    queue.state.lock().unwrap().enqueued.pop_back();
+                    raw_tx = tx;
                 }
+            }
+        }
+    }
 
-                let engine = self.clone();
-                rt::spawn(async move {
-                    while let Some((id, raw_tx)) = receiver.recv().await {
-                        if let Err(e) = engine.process_and_save_transaction(id, raw_tx, jit).await {
-                            println!("Failed to process transaction: {}", e);
+    /// The blockchain's queue, started with its worker if it has none.
+    fn queue(&self, id: Uuid) -> Queue {
+        let mut queues = self.queues.lock().unwrap();
+        if let Some(queue) = queues.get(&id) {
+            return queue.clone();
+        }
+        let (sender, mut receiver) = mpsc::channel(self.queue_capacity);
+        let queue = Queue {
+            sender,
+            state: Default::default(),
+        };
+        queues.insert(id, queue.clone());
+
+        let engine = self.clone();
+        let state = queue.state.clone();
+        rt::spawn(async move {
+            loop {
+                let next = match engine.queue_idle_timeout {
+                    Some(idle_timeout) => time::timeout(idle_timeout, receiver.recv()).await,
+                    None => Ok(receiver.recv().await),
+                };
+                match next {
+                    Ok(Some((raw_tx, jit))) => engine.run(id, raw_tx, jit, state.clone()).await,
+                    Ok(None) => break,
+                    Err(_) => {
+                        let next = {
+                            let mut queues = engine.queues.lock().unwrap();
+                            // Senders holding the queue fail from now on and look
+                            // it up again, which waits for the lock
+                            receiver.close();
+                            match queues.get(&id) {
+                                Some(queue) if Arc::ptr_eq(&queue.state, &state) => {
+                                    // Free capacity also counts sends in flight
+                                    if queue.sender.capacity() == queue.sender.max_capacity() {
+                                        queues.remove(&id);
+                                        None
+                                    } else {
+                                        // Some were sent meanwhile, this worker takes
+                                        // the next queue too so two never run at once
+                                        let (sender, next) = mpsc::channel(engine.queue_capacity);
+                                        queues.insert(
+                                            id,
+                                            Queue {
+                                                sender,
+                                                state: state.clone(),
+                                            },
+                                        );
+                                        Some(next)
+                                    }
+                                }
+                                _ => None,
+                            }
+                        };
+                        while let Some((raw_tx, jit)) = receiver.recv().await {
+                            engine.run(id, raw_tx, jit, state.clone()).await;
+                        }
+                        match next {
+                            Some(next) => receiver = next,
+                            None => break,
                         }
                     }
-                });
+                }
             }
+        });
+        queue
+    }
+
+    /// Processes a transaction taken off an external queue, e.g. Kafka, counting it
+    /// as in flight meanwhile. Fails without processing it once draining.
+    pub async fn process_external(
+        &self,
+        id: Uuid,
+        raw_tx: VersionedTransaction,
+        jit: bool,
+    ) -> Result<(), String> {
+        self.accept_transaction()?;
+        self.run(id, raw_tx, jit, Default::default()).await;
+        Ok(())
+    }
+
+    /// Processes and saves a reserved transaction, releasing its reservation.
+    async fn run(
+        &self,
+        id: Uuid,
+        raw_tx: VersionedTransaction,
+        jit: bool,
+        state: Arc<Mutex<QueueState>>,
+    ) {
+        let signature = raw_tx
+            .signatures
+            .first()
+            .map(|signature| signature.to_string())
+            .unwrap_or_default();
+        let timer = StageTimer::start(id, state, signature.clone());
+        let context = vec![
+            ("blockchain", id.to_string()),
+            ("signature", signature.clone()),
+        ];
+        let processed = reporting::scope(
+            context,
+            self.process_and_save_transaction(id, raw_tx, jit, timer),
+        );
+        if let Err(e) = processed.await {
+            println!("Failed to process transaction: {}", e);
+            // A transaction failing on its own is the client's concern
+            if let RunError::Internal(e) = &e {
+                reporting::capture_warning(&format!("Failed to process transaction: {}", e), &[]);
+            }
+            // Saved transactions are removed with the write
+            if let Err(e) = self.storage.delete_pending_transaction(id, &signature) {
+                println!("Failed to delete pending transaction: {}", e);
+            }
+        }
+        self.in_flight.fetch_sub(1, Ordering::AcqRel);
+    }
+
+    /// Drops a blockchain's queue, which ends its worker, returns false if the queue
+    /// still has work. The next transaction starts a new queue.
+    pub fn close_queue(&self, id: Uuid) -> bool {
+        let mut queues = self.queues.lock().unwrap();
+        let busy = queues.get(&id).is_some_and(|queue| {
+            let state = queue.state.lock().unwrap();
+            // Transactions leave `enqueued` once they start, so this covers ones
+            // received but not yet started too
+            !state.enqueued.is_empty() || state.current.is_some()
+        });
+        if !busy {
+            queues.remove(&id);
         }
+        !busy
+    }
+
+    /// Stops new transactions from being accepted, queued ones still run.
+    pub fn start_draining(&self) {
+        self.draining.store(true, Ordering::Release);
+    }
+
+    pub fn is_draining(&self) -> bool {
+        self.draining.load(Ordering::Acquire)
+    }
+
+    pub fn in_flight(&self) -> usize {
+        self.in_flight.load(Ordering::Acquire)
+    }
+
+    pub fn cached_preflights(&self) -> usize {
+        self.preflights.len()
+    }
+
+    /// Executions parity checks compared with a simulation, and how many of them
+    /// the simulation disagreed with.
+    pub fn parity_checks(&self) -> (u64, u64) {
+        (
+            self.parity_checks.load(Ordering::Relaxed),
+            self.parity_divergences.load(Ordering::Relaxed),
+        )
+    }
+
+    pub fn write_locks(&self) -> &WriteLocks {
+        &self.write_locks
+    }
+
+    pub fn statuses(&self) -> &StatusCache {
+        &self.statuses
+    }
+
+    /// The blockchain's sysvar overrides, none set reads as the defaults.
+    pub fn sysvar_overrides(&self, id: Uuid) -> Result<SysvarOverrides, String> {
+        if let Some(overrides) = self.sysvar_overrides.get(id) {
+            return Ok(overrides);
+        }
+        let overrides = self.storage.get_sysvar_overrides(id)?.unwrap_or_default();
+        self.sysvar_overrides.insert(id, overrides.clone());
+        Ok(overrides)
+    }
+
+    pub fn set_sysvar_overrides(&self, id: Uuid, overrides: SysvarOverrides) -> Result<(), String> {
+        self.storage.set_sysvar_overrides(id, &overrides)?;
+        self.sysvar_overrides.insert(id, overrides);
+        Ok(())
+    }
+
+    pub fn forget_sysvar_overrides(&self, id: Uuid) {
+        self.sysvar_overrides.forget(id);
+    }
+
+    /// Waiting and executing transactions of each live blockchain queue.
+    pub fn queue_stats(&self) -> Vec<QueueStats> {
+        let queues = self.queues.lock().unwrap();
+        queues
+            .iter()
+            .filter(|(_, queue)| !queue.sender.is_closed())
+            .map(|(id, queue)| {
+                let state = queue.state.lock().unwrap();
+                QueueStats {
+                    blockchain: *id,
+                    queued: queue.sender.max_capacity() - queue.sender.capacity(),
+                    oldest_queued_ms: state
+                        .enqueued
+                        .front()
+                        .map(|enqueued| enqueued.elapsed().as_millis() as u64),
+                    processing: state.current.as_ref().map(|(signature, stage, started)| {
+                        ProcessingStats {
+                            signature: signature.clone(),
+                            stage: *stage,
+                            stage_ms: started.elapsed().as_millis() as u64,
+                        }
+                    }),
+                    processed: state.processed,
+                    capacity: queue.sender.max_capacity(),
+                    rejected: state.rejected,
+                }
+            })
+            .collect()
     }
 
     fn set_sysvar<S>(&mut self, sysvar: &S)
@@ -128,6 +622,90 @@ impl<T: Storage + Clone + 'static> TransactionProcessor<T> {
         self.set_sysvar(&StakeHistory::default());
     }
 
+    /// The sysvars of a blockchain at its latest blocks, with its overrides over
+    /// the engine's defaults.
+    pub fn sysvars(&self, id: Uuid) -> Result<SysvarCache, String> {
+        let mut sysvar_cache = self.sysvar_cache.clone();
+        let overrides = self.sysvar_overrides(id)?;
+        if let Some(epoch_schedule) = overrides.epoch_schedule() {
+            sysvar_cache.set_sysvar_for_tests(&epoch_schedule);
+        }
+        let recent_blocks = self.storage.get_recent_blocks(id, 100)?;
+        let slot_hashes = recent_blocks
+            .iter()
+            .map(|block| (block.block_height, block.blockhash))
+            .collect::<Vec<_>>();
+        sysvar_cache.set_sysvar_for_tests(&SlotHashes::new(&slot_hashes));
+        // Still read by the system program to initialize and advance nonce accounts
+        let mut newest_first: Vec<_> = recent_blocks.iter().collect();
+        newest_first.sort_by_key(|block| std::cmp::Reverse(block.block_height));
+        #[allow(deprecated)]
+        {
+            use solana_sdk::sysvar::recent_blockhashes::{IterItem, RecentBlockhashes};
+            let recent_blockhashes: RecentBlockhashes = newest_first
+                .iter()
+                .enumerate()
+                .map(|(i, block)| {
+                    IterItem(
+                        i as u64,
+                        &block.blockhash,
+                        self.fee_structure.lamports_per_signature,
+                    )
+                })
+                .collect();
+            sysvar_cache.set_sysvar_for_tests(&recent_blockhashes);
+        }
+        // The clock is at the latest block, with the time that block was produced at
+        let mut clock = Clock::default();
+        if let Some(latest) = recent_blocks.iter().max_by_key(|block| block.block_height) {
+            clock.slot = latest.block_height;
+            clock.unix_timestamp = latest.block_time as i64;
+        }
+        sysvar_cache.set_sysvar_for_tests(&overrides.clock(clock));
+        Ok(sysvar_cache)
+    }
+
+    /// Reads accounts from storage, except the sysvars the engine keeps, which
+    /// come from `sysvars` so they match what programs read through syscalls.
+    pub async fn load_accounts(
+        &self,
+        id: Uuid,
+        addresses: &Vec<&Pubkey>,
+        jit: bool,
+        sysvars: &SysvarCache,
+    ) -> Result<Vec<Option<Account>>, String> {
+        let stored: Vec<&Pubkey> = addresses
+            .iter()
+            .filter(|address| !is_engine_sysvar(address))
+            .copied()
+            .collect();
+        if stored.len() == addresses.len() {
+            return self.storage.get_accounts_jit(id, addresses, jit).await;
+        }
+        let mut stored_accounts = if stored.is_empty() {
+            vec![]
+        } else {
+            self.storage.get_accounts_jit(id, &stored, jit).await?
+        }
+        .into_iter();
+        Ok(addresses
+            .iter()
+            .map(|address| match is_engine_sysvar(address) {
+                true => sysvar_account(sysvars, address),
+                false => stored_accounts.next().flatten(),
+            })
+            .collect())
+    }
+
+    /// Collects logs up to a blockchain's `log_bytes_limit`.
+    fn log_collector(limit: Option<i32>) -> Rc<RefCell<LogCollector>> {
+        match limit {
+            None => LogCollector::new_ref(),
+            Some(0) => LogCollector::new_ref_with_limit(None),
+            Some(limit) => LogCollector::new_ref_with_limit(Some(limit as usize)),
+        }
+    }
+
     pub fn new_loader(&self, id: Uuid) -> Loader<T> {
         Loader::new(self.storage.clone(), id, self.sysvar_cache.clone())
     }
@@ -137,7 +715,16 @@ impl<T: Storage + Clone + 'static> TransactionProcessor<T> {
         id: Uuid,
         raw_tx: VersionedTransaction,
         jit: bool,
-    ) -> Result<(), String> {
+        mut timer: StageTimer,
+    ) -> Result<(), RunError> {
+        let started = Instant::now();
+        timer.enter(Stage::Sanitize);
+        let preflight = raw_tx
+            .signatures
+            .first()
+            .and_then(|signature| self.preflights.take(id, signature));
+        // Read once for the whole run, every step below needs some of its settings
+        let blockchain = self.storage.get_blockchain(id)?;
         let address_loader = Loader::new(self.storage.clone(), id, self.sysvar_cache.clone());
 
         let tx = match SanitizedTransaction::try_create(
@@ -148,7 +735,7 @@ impl<T: Storage + Clone + 'static> TransactionProcessor<T> {
             &ReservedAccountKeys::empty_key_set(),
         ) {
             Ok(tx) => tx,
-            Err(e) => return Err(e.to_string()),
+            Err(e) => return Err(RunError::Rejected(e.to_string())),
         };
 
         let (current_block, _valid_blockhash) =
@@ -158,23 +745,83 @@ impl<T: Storage + Clone + 'static> TransactionProcessor<T> {
         // };
         let message = tx.message();
         let account_keys = message.account_keys();
+        self.write_locks.record(
+            id,
+            current_block.block_height,
+            account_keys
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| message.is_writable(*i))
+                .map(|(_, key)| *key),
+        );
         let addresses: Vec<&Pubkey> = account_keys.iter().collect();
-        let accounts_vec = self.storage.get_accounts_jit(id, &addresses, jit).await?;
+        timer.enter(Stage::LoadAccounts);
+        // Accounts are read again since they may have changed, but the preflight
+        // already fetched the missing ones upstream
+        let fetch_upstream = jit && !preflight.as_ref().is_some_and(|preflight| preflight.jit);
+        let sysvars = self.sysvars(id)?;
+        let accounts_vec = self
+            .before_deadline(
+                started,
+                self.load_accounts(id, &addresses, fetch_upstream, &sysvars),
+            )
+            .await?;
 
-        let accounts_map: HashMap<&Pubkey, Option<Account>> = addresses
+        let mut accounts_map: HashMap<&Pubkey, Option<Account>> = addresses
             .iter()
             .cloned()
             .zip(accounts_vec.into_iter())
             .collect();
-        let accounts_db = AccountsDB::new(accounts_map.clone());
-        let log_collector = LogCollector::new_ref();
-        let (tx_result, accumulated_consume_units, context, fee, payer_key) =
-            self.process_transaction(id, &tx, log_collector.clone(), &accounts_db);
-        if context == None {
+        let sponsored_fee = self.sponsor_fee(&blockchain, &tx, &mut accounts_map)?;
+        let accounts_db = AccountsDB::new(accounts_map);
+        let programs = preflight
+            .map(|preflight| preflight.programs)
+            .unwrap_or_default();
+        timer.enter(Stage::Execute);
+        let Execution {
+            result: tx_result,
+            compute_units: accumulated_consume_units,
+            fee,
+            payer_key,
+            logs,
+            outputs,
+            timed_out,
+            ..
+        } = self
+            .execute_before_deadline(
+                started,
+                &blockchain,
+                &tx,
+                &accounts_db,
+                &sysvars,
+                programs,
+                None,
+            )
+            .await?;
+        let Some((signature, return_data, inner_instructions, post_accounts, tx_accounts)) =
+            outputs
+        else {
             if let Err(err) = tx_result {
-                return Err(err.to_string());
+                return Err(RunError::Rejected(err.to_string()));
             } else {
-                return Err("Context is None".to_string());
+                return Err("Context is None".to_string().into());
+            }
+        };
+        // Accounts the transaction creates count against its team's quota, like
+        // accounts written any other way
+        let created: Vec<Pubkey> = post_accounts
+            .iter()
+            .filter(|(pubkey, account)| {
+                accounts_db.get_account(pubkey).is_none()
+                    && (account.lamports() > 0 || !account.data().is_empty())
+            })
+            .map(|(pubkey, _)| *pubkey)
+            .collect();
+        if tx_result.is_ok() && !created.is_empty() {
+            if let Some(message) =
+                account_quota_exceeded(&self.storage, blockchain.team_id, id, &created)?
+            {
+                return Err(RunError::Rejected(message));
             }
         }
         //Decrement account if tx failed and payer is not None
@@ -185,20 +832,17 @@ impl<T: Storage + Clone + 'static> TransactionProcessor<T> {
             self.storage
                 .set_account_lamports(id, &payer_key, payer_account.lamports())?;
         }
-        let context = context.unwrap();
-        let (signature, return_data, inner_instructions, post_accounts) =
-            execute_tx_helper(tx.clone(), context);
-        let Ok(logs) = Rc::try_unwrap(log_collector).map(|lc| lc.into_inner().messages) else {
-            unreachable!("Log collector should not be used after send_transaction returns")
-        };
+        // Writes of timed out transactions are dropped, there's nothing to compare
+        if !timed_out && blockchain.parity_check {
+            let executed =
+                ExecutionOutcome::new(accumulated_consume_units, logs.clone(), &tx_accounts);
+            self.check_parity(&blockchain, &tx, &accounts_db, &sysvars, &executed)
+                .await;
+        }
 
-        let account_balances = collect_token_balances(
-            id,
-            tx.clone(),
-            &accounts_db,
-            self.storage.clone(),
-            post_accounts.clone(),
-        );
+        timer.enter(Stage::TokenBalances);
+        let account_balances =
+            collect_token_balances(id, &tx, &accounts_db, self.storage.clone(), &tx_accounts);
         let (pre_token_balances, post_token_balances) = match account_balances {
             Some(balances) => (
                 Some(balances.pre_token_balances),
@@ -244,27 +888,222 @@ impl<T: Storage + Clone + 'static> TransactionProcessor<T> {
                 .collect(),
             pre_token_balances,
             post_token_balances,
+            trace: None,
+            sponsored_fee,
         };
 
+        timer.enter(Stage::Save);
         self.storage.save_transaction(id, &meta)?;
-
-        self.storage.set_accounts(
+        if meta.err.is_none() {
+            self.record_deployments(&blockchain, &tx);
+        }
+        self.statuses.insert(
             id,
-            post_accounts
-                .into_iter()
-                .map(|(pubkey, account_shared_data)| (pubkey, Account::from(account_shared_data)))
-                .collect(),
-        )?;
+            meta.signature,
+            CachedStatus {
+                slot: meta.current_block.block_height,
+                err: meta.err.clone(),
+                created_at: Utc::now(),
+            },
+        );
+
+        let (empty, post_accounts): (Vec<_>, Vec<_>) = post_accounts
+            .into_iter()
+            .map(|(pubkey, account_shared_data)| (pubkey, Account::from(account_shared_data)))
+            .partition(|(_, account)| is_empty_account(account));
+        self.storage.set_accounts(id, post_accounts)?;
+        if !empty.is_empty() {
+            self.sweep_drained_accounts(&blockchain, empty)?;
+        }
+        if sponsored_fee > 0 {
+            // Charged last since the identity can be one of the transaction's accounts
+            let sponsor = blockchain.airdrop_keypair.pubkey();
+            let lamports = self
+                .storage
+                .get_account(id, &sponsor)?
+                .map_or(0, |account| account.lamports);
+            self.storage.set_account_lamports(
+                id,
+                &sponsor,
+                lamports.saturating_sub(sponsored_fee),
+            )?;
+        }
 
         Ok(())
     }
 
+    /// Audit entries for the programs the transaction deployed or upgraded, where
+    /// the blockchain's timeline finds them.
+    fn record_deployments(&self, blockchain: &Blockchain, tx: &SanitizedTransaction) {
+        for (action, program_id) in timeline::program_deployments(tx) {
+            let log = DbAuditLog::new(
+                blockchain.team_id,
+                None,
+                action,
+                Some(blockchain.id),
+                serde_json::json!({
+                    "signature": tx.signature().to_string(),
+                    "program_id": program_id.to_string(),
+                }),
+            );
+            if let Err(e) = self.storage.create_audit_log(&log) {
+                println!(
+                    "Failed to record {} of {} on {}: {}",
+                    action, program_id, blockchain.id, e
+                );
+            }
+        }
+    }
+
+    /// Simulates an executed transaction on the state it executed on, without the
+    /// programs its preflight compiled, and logs where the two disagree. The
+    /// simulation has its own execution deadline.
+    async fn check_parity(
+        &self,
+        blockchain: &Blockchain,
+        tx: &SanitizedTransaction,
+        accounts_db: &AccountsDB,
+        sysvars: &SysvarCache,
+        executed: &ExecutionOutcome,
+    ) {
+        let id = blockchain.id;
+        let simulation = self
+            .execute_before_deadline(
+                Instant::now(),
+                blockchain,
+                tx,
+                accounts_db,
+                sysvars,
+                LoadedPrograms::default(),
+                None,
+            )
+            .await;
+        let (compute_units, logs, accounts) = match simulation {
+            Ok(Execution {
+                compute_units,
+                logs,
+                outputs: Some((_, _, _, _, accounts)),
+                timed_out: false,
+                ..
+            }) => (compute_units, logs, accounts),
+            Ok(Execution {
+                timed_out: true, ..
+            }) => {
+                println!(
+                    "Parity check of {} on {} failed, the simulation timed out",
+                    tx.signature(),
+                    id
+                );
+                return;
+            }
+            Ok(_) => {
+                println!(
+                    "Parity check of {} on {} failed, the simulation didn't run",
+                    tx.signature(),
+                    id
+                );
+                return;
+            }
+            Err(e) => {
+                println!("Parity check of {} on {} failed: {}", tx.signature(), id, e);
+                return;
+            }
+        };
+        let simulated = ExecutionOutcome::new(compute_units, logs, &accounts);
+        let divergences = executed.divergences(&simulated);
+        self.parity_checks.fetch_add(1, Ordering::Relaxed);
+        if !divergences.is_empty() {
+            self.parity_divergences.fetch_add(1, Ordering::Relaxed);
+        }
+        for divergence in divergences {
+            println!(
+                "Parity divergence of {} on {}: {}",
+                tx.signature(),
+                id,
+                divergence
+            );
+        }
+    }
+
+    /// Deletes the accounts a transaction drained, like a validator stops storing
+    /// them. JIT blockchains keep them, deleted they'd be fetched upstream again.
+    fn sweep_drained_accounts(
+        &self,
+        blockchain: &Blockchain,
+        accounts: Vec<(Pubkey, Account)>,
+    ) -> Result<(), String> {
+        if blockchain.jit {
+            return self.storage.set_accounts(blockchain.id, accounts);
+        }
+        let addresses: Vec<Pubkey> = accounts.into_iter().map(|(pubkey, _)| pubkey).collect();
+        self.storage.delete_accounts(blockchain.id, &addresses)?;
+        Ok(())
+    }
+
+    /// Lends the fee payer its fee when the blockchain sponsors fees and the payer
+    /// can't pay, returning the lamports the airdrop identity owes for it.
+    fn sponsor_fee(
+        &self,
+        blockchain: &Blockchain,
+        tx: &SanitizedTransaction,
+        accounts: &mut HashMap<&Pubkey, Option<Account>>,
+    ) -> Result<u64, String> {
+        if !blockchain.sponsor_fees {
+            return Ok(0);
+        }
+        let payer = tx.message().fee_payer();
+        let sponsor = blockchain.airdrop_keypair.pubkey();
+        let fee = self.fee(tx.message());
+        let Some(payer_account) = accounts.get_mut(payer) else {
+            return Ok(0);
+        };
+        // Nonce and program owned payers keep failing like they would on mainnet
+        let balance = match payer_account {
+            Some(account)
+                if !system_program::check_id(&account.owner) || !account.data.is_empty() =>
+            {
+                return Ok(0)
+            }
+            Some(account) => account.lamports,
+            None => 0,
+        };
+        if *payer == sponsor || balance >= fee {
+            return Ok(0);
+        }
+        let sponsor_balance = self
+            .storage
+            .get_account(blockchain.id, &sponsor)?
+            .map_or(0, |account| account.lamports);
+        if sponsor_balance < fee {
+            return Ok(0);
+        }
+        payer_account
+            .get_or_insert_with(|| Account::new(0, 0, &system_program::id()))
+            .lamports += fee;
+        Ok(fee)
+    }
+
+    fn fee(&self, message: &SanitizedMessage) -> u64 {
+        solana_fee::calculate_fee(
+            message,
+            false,
+            self.fee_structure.lamports_per_signature,
+            0,
+            self.feature_set
+                .is_active(&remove_rounding_in_fee_calculation::id()),
+        )
+    }
+
+    /// Executes without saving anything, `trace` records each instruction.
     pub async fn simulate_transaction(
         &self,
         id: Uuid,
         raw_tx: VersionedTransaction,
         jit: bool,
+        trace: bool,
     ) -> Result<TransactionMetadata, String> {
+        let started = Instant::now();
+        let blockchain = self.storage.get_blockchain(id)?;
         // For v0 transactions, we need to use the native loader to load the program
         let sysvar_cache = self.sysvar_cache.clone();
 
@@ -288,32 +1127,49 @@ impl<T: Storage + Clone + 'static> TransactionProcessor<T> {
         let message = tx.message();
         let account_keys = message.account_keys();
         let addresses: Vec<&Pubkey> = account_keys.iter().collect();
-        let accounts_vec = self.storage.get_accounts_jit(id, &addresses, jit).await?;
-        let accounts_map: HashMap<&Pubkey, Option<Account>> = addresses
+        let sysvars = self.sysvars(id)?;
+        let accounts_vec = self
+            .before_deadline(started, self.load_accounts(id, &addresses, jit, &sysvars))
+            .await?;
+        let mut accounts_map: HashMap<&Pubkey, Option<Account>> = addresses
             .iter()
             .cloned()
             .zip(accounts_vec.into_iter())
             .collect();
-        let accounts_db = AccountsDB::new(accounts_map.clone());
-        let log_collector = LogCollector::new_ref();
-        let (tx_result, accumulated_consume_units, context, _, _) =
-            self.process_transaction(id, &tx, log_collector.clone(), &accounts_db);
-        if context == None {
+        let sponsored_fee = self.sponsor_fee(&blockchain, &tx, &mut accounts_map)?;
+        let accounts_db = AccountsDB::new(accounts_map);
+        let Execution {
+            result: tx_result,
+            compute_units: accumulated_consume_units,
+            logs,
+            outputs,
+            programs,
+            trace,
+            ..
+        } = self
+            .execute_before_deadline(
+                started,
+                &blockchain,
+                &tx,
+                &accounts_db,
+                &sysvars,
+                LoadedPrograms::default(),
+                trace.then(Vec::new),
+            )
+            .await?;
+        // Failed executions are results too, with their logs and units consumed
+        let Some((signature, return_data, inner_instructions, post_accounts, _)) = outputs else {
             if let Err(err) = tx_result {
                 return Err(err.to_string());
             } else {
                 return Err("Context is None".to_string());
             }
-        }
-        if tx_result.is_err() {
-            return Err(tx_result.unwrap_err().to_string());
-        }
-        let context = context.unwrap();
-        let (signature, return_data, inner_instructions, post_accounts) =
-            execute_tx_helper(tx.clone(), context);
-        let Ok(logs) = Rc::try_unwrap(log_collector).map(|lc| lc.into_inner().messages) else {
-            unreachable!("Log collector should not be used after send_transaction returns")
         };
+        // Failed transactions aren't sent after their preflight
+        if tx_result.is_ok() {
+            self.preflights
+                .insert(id, *tx.signature(), Preflight { jit, programs });
+        }
 
         let meta = TransactionMetadata {
             signature,
@@ -328,31 +1184,124 @@ impl<T: Storage + Clone + 'static> TransactionProcessor<T> {
             pre_accounts: accounts_db
                 .accounts
                 .iter()
-                .map(|(k, v)| {
-                    if let Some(account) = v {
-                        (
-                            k.to_owned().to_owned(),
-                            AccountSharedData::from(account.to_owned()),
-                        )
-                    } else {
-                        (k.to_owned().to_owned(), AccountSharedData::default())
-                    }
-                })
+                .map(|(k, v)| (k.to_owned().to_owned(), v.clone().unwrap_or_default()))
                 .collect(),
             post_accounts: post_accounts.clone(),
             pre_token_balances: None,  //TODO: Implement pre_token_balances
             post_token_balances: None, //TODO: Implement post_token_balances
+            trace,
+            sponsored_fee,
         };
 
         Ok(meta)
     }
 
+    /// `future`, failing once `limits.execution_timeout_ms` has passed since `started`.
+    async fn before_deadline<V>(
+        &self,
+        started: Instant,
+        future: impl Future<Output = Result<V, String>>,
+    ) -> Result<V, String> {
+        let Some(timeout) = self.execution_timeout else {
+            return future.await;
+        };
+        time::timeout(timeout.saturating_sub(started.elapsed()), future)
+            .await
+            .map_err(|_| timeout_message(timeout))?
+    }
+
+    /// Executes on a blocking thread, failing once `limits.execution_timeout_ms` has
+    /// passed since `started`. Execution can't be interrupted, past the deadline it
+    /// finishes in the background and is dropped, so its writes never land.
+    #[allow(clippy::too_many_arguments)]
+    async fn execute_before_deadline(
+        &self,
+        started: Instant,
+        blockchain: &Blockchain,
+        tx: &SanitizedTransaction,
+        accounts_db: &AccountsDB,
+        sysvars: &SysvarCache,
+        programs: LoadedPrograms,
+        trace: Option<Vec<InstructionTrace>>,
+    ) -> Result<Execution, String> {
+        let engine = self.clone();
+        let (id, log_bytes_limit) = (blockchain.id, blockchain.log_bytes_limit);
+        let (tx, accounts_db, sysvars) = (tx.clone(), accounts_db.clone(), sysvars.clone());
+        let signature = *tx.signature();
+        let execution = rt::task::spawn_blocking(move || {
+            let log_collector = Self::log_collector(log_bytes_limit);
+            engine.execute(
+                id,
+                &tx,
+                log_collector,
+                &accounts_db,
+                &sysvars,
+                programs,
+                trace,
+            )
+        });
+        let Some(timeout) = self.execution_timeout else {
+            return execution
+                .await
+                .map_err(|e| format!("Failed to execute transaction: {}", e));
+        };
+        match time::timeout(timeout.saturating_sub(started.elapsed()), execution).await {
+            // It may have finished past the deadline before this got to run
+            Ok(Ok(_)) if started.elapsed() > timeout => {
+                Ok(Execution::timed_out(signature, timeout))
+            }
+            Ok(execution) => execution.map_err(|e| format!("Failed to execute transaction: {}", e)),
+            Err(_) => Ok(Execution::timed_out(signature, timeout)),
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn execute(
+        &self,
+        id: Uuid,
+        tx: &SanitizedTransaction,
+        log_collector: Rc<RefCell<LogCollector>>,
+        accounts_db: &AccountsDB,
+        sysvars: &SysvarCache,
+        mut programs: LoadedPrograms,
+        mut trace: Option<Vec<InstructionTrace>>,
+    ) -> Execution {
+        let (result, compute_units, context, fee, payer_key) = self.process_transaction(
+            id,
+            tx,
+            log_collector.clone(),
+            accounts_db,
+            sysvars,
+            &mut programs,
+            trace.as_mut(),
+        );
+        let outputs = context.map(|context| execute_tx_helper(tx.clone(), context));
+        let Ok(logs) = Rc::try_unwrap(log_collector).map(|lc| lc.into_inner().messages) else {
+            unreachable!("Log collector should not be used after process_transaction returns")
+        };
+        Execution {
+            result,
+            compute_units,
+            fee,
+            payer_key,
+            logs,
+            outputs,
+            programs,
+            trace,
+            timed_out: false,
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
     fn process_transaction(
         &self,
         id: Uuid,
         tx: &SanitizedTransaction,
         log_collector: Rc<RefCell<LogCollector>>,
         accounts_db: &AccountsDB,
+        sysvars: &SysvarCache,
+        programs: &mut LoadedPrograms,
+        trace: Option<&mut Vec<InstructionTrace>>,
     ) -> (
         Result<(), TransactionError>,
         u64,
@@ -363,21 +1312,6 @@ impl<T: Storage + Clone + 'static> TransactionProcessor<T> {
         let compute_budget = ComputeBudget::default();
         let blockhash = tx.message().recent_blockhash();
         let mut program_cache_for_tx_batch = ProgramCacheForTxBatch::default();
-        let mut sysvar_cache = self.sysvar_cache.clone();
-        let recent_blocks = self.storage.get_recent_blocks(id, 100).unwrap();
-        let slot_hashes = recent_blocks
-            .iter()
-            .map(|block| (block.block_height, block.blockhash))
-            .collect::<Vec<_>>();
-        sysvar_cache.set_sysvar_for_tests(&SlotHashes::new(&slot_hashes));
-        let mut clock = Clock::default();
-        clock.unix_timestamp = Utc::now().timestamp();
-        clock.slot = recent_blocks
-            .iter()
-            .map(|block| block.block_height)
-            .max()
-            .unwrap_or(0);
-        sysvar_cache.set_sysvar_for_tests(&clock);
 
         BUILTINS.iter().for_each(|builtint| {
             let loaded_program =
@@ -402,14 +1336,7 @@ impl<T: Storage + Clone + 'static> TransactionProcessor<T> {
         let mut accumulated_consume_units = 0;
         let message = tx.message();
         let account_keys = message.account_keys();
-        let fee = solana_fee::calculate_fee(
-            message,
-            false,
-            self.fee_structure.lamports_per_signature,
-            0,
-            self.feature_set
-                .is_active(&remove_rounding_in_fee_calculation::id()),
-        );
+        let fee = self.fee(message);
         let mut validated_fee_payer = false;
         let mut payer_key = None;
         let maybe_accounts = account_keys
@@ -451,25 +1378,25 @@ impl<T: Storage + Clone + 'static> TransactionProcessor<T> {
                 return (Err(e), accumulated_consume_units, None, fee, payer_key);
             }
         };
-        accounts.iter().for_each(|(pubkey, account)| {
-            if !account.executable() {
+        let program_accounts = self.prefetch_program_accounts(id, accounts_db, &accounts);
+        accounts.iter().for_each(|(pubkey, program_account)| {
+            if !program_account.executable() {
                 return;
             }
             let pubkey = pubkey.to_owned();
             if BUILTINS.iter().any(|b| b.program_id == pubkey) {
                 return;
             }
-            let program_account = match accounts_db.get_account(&pubkey) {
-                Some(account) => account,
-                // Don't need to worry about jit since those accounts should already have been pulled
-                None => match mut_self.storage.get_account(id, &pubkey) {
-                    Ok(account) => match account {
-                        Some(account) => account.into(),
-                        None => return,
-                    },
-                    Err(_) => return,
-                },
+
+            let elf = match mut_self.program_elf(&program_accounts, program_account) {
+                Some(elf) => elf,
+                None => return,
             };
+            let elf_hash = hash(&elf);
+            if let Some(entry) = programs.get(&pubkey, &elf_hash) {
+                program_cache_for_tx_batch.replenish(pubkey, entry);
+                return;
+            }
 
             let program_runtime_v1 = create_program_runtime_environment_v1(
                 &self.feature_set,
@@ -483,13 +1410,15 @@ impl<T: Storage + Clone + 'static> TransactionProcessor<T> {
                 Arc::new(program_runtime_v1),
                 0,
                 0,
-                program_account.data(),
-                program_account.data().len(),
+                &elf,
+                elf.len(),
                 &mut LoadProgramMetrics::default(),
             )
             .unwrap(); //TODO: This may panic
 
-            program_cache_for_tx_batch.replenish(pubkey, Arc::new(entry));
+            let entry = Arc::new(entry);
+            program_cache_for_tx_batch.replenish(pubkey, entry.clone());
+            programs.insert(pubkey, elf_hash, entry);
         });
         if !validated_fee_payer {
             return (
@@ -529,18 +1458,12 @@ impl<T: Storage + Clone + 'static> TransactionProcessor<T> {
                     .iter()
                     .any(|(key, _)| key == owner_id)
                 {
-                    let owner_account = match accounts_db.get_account(owner_id) {
-                        Some(account) => account,
-                        None => match self.storage.get_account(id, owner_id) {
-                            Ok(account) => match account {
-                                Some(account) => account.into(),
-                                None => return Err(TransactionError::ProgramAccountNotFound),
-                            },
-                            Err(_) => {
-                                println!("Owner account not found for program {}", owner_id);
-                                return Err(TransactionError::ProgramAccountNotFound);
-                            }
-                        },
+                    let owner_account = match program_accounts.get(owner_id) {
+                        Some(account) => account.clone(),
+                        None => {
+                            println!("Owner account not found for program {}", owner_id);
+                            return Err(TransactionError::ProgramAccountNotFound);
+                        }
                     };
                     if !native_loader::check_id(owner_account.owner()) {
                         return Err(TransactionError::InvalidProgramForExecution);
@@ -556,27 +1479,37 @@ impl<T: Storage + Clone + 'static> TransactionProcessor<T> {
         match maybe_program_indices {
             Ok(program_indices) => {
                 let mut context = self.create_transaction_context(compute_budget, accounts);
-                let mut tx_result = MessageProcessor::process_message(
-                    tx.message(),
-                    &program_indices,
-                    &mut InvokeContext::new(
-                        &mut context,
-                        &mut program_cache_for_tx_batch,
-                        EnvironmentConfig::new(
-                            *blockhash,
-                            None,
-                            None,
-                            Arc::new(self.feature_set.clone().into()),
-                            0,
-                            &sysvar_cache,
-                        ),
-                        Some(log_collector),
-                        compute_budget,
+                let mut invoke_context = InvokeContext::new(
+                    &mut context,
+                    &mut program_cache_for_tx_batch,
+                    EnvironmentConfig::new(
+                        *blockhash,
+                        None,
+                        None,
+                        Arc::new(self.feature_set.clone()),
+                        0,
+                        sysvars,
                     ),
-                    &mut ExecuteTimings::default(),
-                    &mut accumulated_consume_units,
-                )
-                .map(|_| ());
+                    Some(log_collector),
+                    compute_budget,
+                );
+                let mut tx_result = match trace {
+                    Some(trace) => trace::process_message(
+                        tx.message(),
+                        &program_indices,
+                        &mut invoke_context,
+                        &mut accumulated_consume_units,
+                        trace,
+                    ),
+                    None => MessageProcessor::process_message(
+                        tx.message(),
+                        &program_indices,
+                        &mut invoke_context,
+                        &mut ExecuteTimings::default(),
+                        &mut accumulated_consume_units,
+                    ),
+                };
+                drop(invoke_context);
                 if let Err(err) = self.check_accounts_rent(tx, &context, accounts_db) {
                     tx_result = Err(err);
                 };
@@ -593,6 +1526,80 @@ impl<T: Storage + Clone + 'static> TransactionProcessor<T> {
         }
     }
 
+    /// The owners and program data accounts of the transaction's programs, the ones
+    /// it doesn't reference itself read from storage in one go. Those should already
+    /// exist locally, so jit isn't needed.
+    fn prefetch_program_accounts(
+        &self,
+        id: Uuid,
+        accounts_db: &AccountsDB,
+        accounts: &[(Pubkey, AccountSharedData)],
+    ) -> HashMap<Pubkey, AccountSharedData> {
+        let mut program_accounts = HashMap::new();
+        let mut missing = HashSet::new();
+        let programs = accounts.iter().filter(|(pubkey, account)| {
+            account.executable() && !BUILTINS.iter().any(|b| b.program_id == *pubkey)
+        });
+        for (_, account) in programs {
+            let mut related = vec![*account.owner()];
+            if bpf_loader_upgradeable::check_id(account.owner()) {
+                if let Ok(UpgradeableLoaderState::Program {
+                    programdata_address,
+                }) = account.state()
+                {
+                    related.push(programdata_address);
+                }
+            }
+            for address in related {
+                match accounts_db.get_account(&address) {
+                    Some(account) => {
+                        program_accounts.insert(address, account);
+                    }
+                    None => {
+                        missing.insert(address);
+                    }
+                }
+            }
+        }
+        if missing.is_empty() {
+            return program_accounts;
+        }
+        let missing: Vec<&Pubkey> = missing.iter().collect();
+        match self.storage.get_accounts(id, &missing) {
+            Ok(found) => {
+                for (address, account) in missing.iter().zip(found) {
+                    if let Some(account) = account {
+                        program_accounts.insert(**address, account.into());
+                    }
+                }
+            }
+            Err(e) => println!("Failed to load program accounts: {}", e),
+        }
+        program_accounts
+    }
+
+    /// Executable bytes of a program, upgradeable programs keep them in their programdata account.
+    fn program_elf(
+        &self,
+        program_accounts: &HashMap<Pubkey, AccountSharedData>,
+        program_account: &AccountSharedData,
+    ) -> Option<Vec<u8>> {
+        if !bpf_loader_upgradeable::check_id(program_account.owner()) {
+            return Some(program_account.data().to_vec());
+        }
+        let programdata_address = match program_account.state() {
+            Ok(UpgradeableLoaderState::Program {
+                programdata_address,
+            }) => programdata_address,
+            _ => return None,
+        };
+        program_accounts
+            .get(&programdata_address)?
+            .data()
+            .get(UpgradeableLoaderState::size_of_programdata_metadata()..)
+            .map(|elf| elf.to_vec())
+    }
+
     fn create_transaction_context(
         &self,
         compute_budget: ComputeBudget,
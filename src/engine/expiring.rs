@@ -0,0 +1,116 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    hash::Hash,
+    time::{Duration, Instant},
+};
+
+/// A map whose entries expire `ttl` after they're inserted, holding at most
+/// `capacity` of them. Entries are kept in insertion order, so a full map drops
+/// its oldest entry instead of scanning for expired ones.
+pub struct ExpiringMap<K, V> {
+    entries: HashMap<K, (V, Instant)>,
+    // Replaced and removed entries stay here until they reach the front
+    order: VecDeque<(K, Instant)>,
+    ttl: Duration,
+    capacity: usize,
+    weigh: fn(&V) -> usize,
+    weight: usize,
+    max_weight: usize,
+}
+
+impl<K: Eq + Hash + Clone, V> ExpiringMap<K, V> {
+    pub fn new(ttl: Duration, capacity: usize) -> Self {
+        Self::with_max_weight(ttl, capacity, usize::MAX, |_| 0)
+    }
+
+    /// Also drops the oldest entries while the entries `weigh` more than
+    /// `max_weight` together. Heavier entries aren't inserted at all.
+    pub fn with_max_weight(
+        ttl: Duration,
+        capacity: usize,
+        max_weight: usize,
+        weigh: fn(&V) -> usize,
+    ) -> Self {
+        ExpiringMap {
+            entries: HashMap::new(),
+            order: VecDeque::new(),
+            ttl,
+            capacity,
+            weigh,
+            weight: 0,
+            max_weight,
+        }
+    }
+
+    pub fn insert(&mut self, key: K, value: V) {
+        let weight = (self.weigh)(&value);
+        if weight > self.max_weight {
+            return;
+        }
+        self.remove(&key);
+        let now = Instant::now();
+        while let Some((_, inserted)) = self.order.front() {
+            if self.order.len() < self.capacity
+                && self.weight + weight <= self.max_weight
+                && now.duration_since(*inserted) < self.ttl
+            {
+                break;
+            }
+            self.pop_front();
+        }
+        self.order.push_back((key.clone(), now));
+        self.entries.insert(key, (value, now));
+        self.weight += weight;
+    }
+
+    pub fn get(&self, key: &K) -> Option<&V> {
+        let (value, inserted) = self.entries.get(key)?;
+        (inserted.elapsed() < self.ttl).then_some(value)
+    }
+
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let (value, inserted) = self.entries.remove(key)?;
+        self.weight -= (self.weigh)(&value);
+        (inserted.elapsed() < self.ttl).then_some(value)
+    }
+
+    pub fn retain(&mut self, mut keep: impl FnMut(&K, &V) -> bool) {
+        let (weigh, weight) = (self.weigh, &mut self.weight);
+        self.entries.retain(|key, (value, _)| {
+            let kept = keep(key, value);
+            if !kept {
+                *weight -= weigh(value);
+            }
+            kept
+        });
+        let entries = &self.entries;
+        self.order.retain(|(key, _)| entries.contains_key(key));
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// What the entries weigh together.
+    pub fn weight(&self) -> usize {
+        self.weight
+    }
+
+    fn pop_front(&mut self) {
+        let Some((key, inserted)) = self.order.pop_front() else {
+            return;
+        };
+        // Only if the entry wasn't inserted again since
+        if self
+            .entries
+            .get(&key)
+            .is_some_and(|(_, current)| *current == inserted)
+        {
+            self.remove(&key);
+        }
+    }
+}
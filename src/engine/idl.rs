@@ -0,0 +1,53 @@
+use flate2::{read::ZlibDecoder, write::ZlibEncoder, Compression};
+use sha2::{Digest, Sha256};
+use solana_sdk::pubkey::Pubkey;
+use std::io::{Read, Write};
+
+const IDL_SEED: &str = "anchor:idl";
+// discriminator + authority + data_len
+const IDL_HEADER_LEN: usize = 8 + 32 + 4;
+
+/// Address Anchor derives for a program's on-chain IDL account.
+pub fn idl_address(program_id: &Pubkey) -> Result<Pubkey, String> {
+    let (base, _) = Pubkey::find_program_address(&[], program_id);
+    Pubkey::create_with_seed(&base, IDL_SEED, program_id).map_err(|e| e.to_string())
+}
+
+fn idl_discriminator() -> [u8; 8] {
+    let hash = Sha256::digest(b"account:IdlAccount");
+    let mut discriminator = [0u8; 8];
+    discriminator.copy_from_slice(&hash[..8]);
+    discriminator
+}
+
+/// Serializes an IDL into the layout of Anchor's `IdlAccount`, the JSON is zlib compressed.
+pub fn encode_idl_account(authority: &Pubkey, idl: &[u8]) -> Result<Vec<u8>, String> {
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(idl).map_err(|e| e.to_string())?;
+    let compressed = encoder.finish().map_err(|e| e.to_string())?;
+
+    let mut data = Vec::with_capacity(IDL_HEADER_LEN + compressed.len());
+    data.extend_from_slice(&idl_discriminator());
+    data.extend_from_slice(authority.as_ref());
+    data.extend_from_slice(&(compressed.len() as u32).to_le_bytes());
+    data.extend_from_slice(&compressed);
+    Ok(data)
+}
+
+/// Returns the authority and uncompressed IDL JSON of an `IdlAccount`.
+pub fn decode_idl_account(data: &[u8]) -> Result<(Pubkey, Vec<u8>), String> {
+    if data.len() < IDL_HEADER_LEN || data[..8] != idl_discriminator() {
+        return Err("Not an IDL account".to_string());
+    }
+    let authority = Pubkey::try_from(&data[8..40]).map_err(|e| e.to_string())?;
+    let data_len = u32::from_le_bytes(data[40..44].try_into().unwrap()) as usize;
+    let compressed = data
+        .get(IDL_HEADER_LEN..IDL_HEADER_LEN + data_len)
+        .ok_or("IDL account data is truncated".to_string())?;
+
+    let mut idl = Vec::new();
+    ZlibDecoder::new(compressed)
+        .read_to_end(&mut idl)
+        .map_err(|e| e.to_string())?;
+    Ok((authority, idl))
+}
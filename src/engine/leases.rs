@@ -0,0 +1,140 @@
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+use uuid::Uuid;
+
+use crate::storage::Storage;
+
+struct Lease {
+    renewed: Instant,
+    used: Instant,
+}
+
+/// Leases this instance holds on blockchains, see `ClusterSettings`. Holding one
+/// means running the blockchain's queue and producing its blocks here.
+pub struct Leases {
+    instance: Uuid,
+    ttl: Duration,
+    held: Mutex<HashMap<Uuid, Lease>>,
+}
+
+impl Leases {
+    pub fn new(instance: Uuid, ttl: Duration) -> Self {
+        Leases {
+            instance,
+            ttl,
+            held: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn instance(&self) -> Uuid {
+        self.instance
+    }
+
+    /// Whether this instance holds the lease, taking it when nobody does. Leases
+    /// renewed within half their TTL are trusted without asking storage.
+    pub fn hold<T: Storage>(&self, storage: &T, id: Uuid) -> Result<bool, String> {
+        let now = Instant::now();
+        if let Some(lease) = self.held.lock().unwrap().get_mut(&id) {
+            lease.used = now;
+            if now - lease.renewed < self.ttl / 2 {
+                return Ok(true);
+            }
+        }
+        let holder = storage.acquire_lease(id, self.instance, self.ttl)?;
+        let mut held = self.held.lock().unwrap();
+        if holder != self.instance {
+            held.remove(&id);
+            return Ok(false);
+        }
+        held.insert(
+            id,
+            Lease {
+                renewed: now,
+                used: now,
+            },
+        );
+        Ok(true)
+    }
+
+    pub fn held(&self) -> Vec<Uuid> {
+        self.held.lock().unwrap().keys().copied().collect()
+    }
+
+    /// Marks a held lease as used, so it isn't released as idle.
+    pub fn touch(&self, id: Uuid) {
+        if let Some(lease) = self.held.lock().unwrap().get_mut(&id) {
+            lease.used = Instant::now();
+        }
+    }
+
+    /// Renews every held lease, releasing the ones unused for a TTL unless `busy`
+    /// says their queue still has work. Returns how many are still held.
+    pub fn renew<T: Storage>(&self, storage: &T, busy: impl Fn(Uuid) -> bool) -> usize {
+        let now = Instant::now();
+        let leases: Vec<(Uuid, Instant)> = self
+            .held
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(id, lease)| (*id, lease.used))
+            .collect();
+        for (id, used) in leases {
+            if now - used >= self.ttl && !busy(id) {
+                match storage.release_lease(id, self.instance) {
+                    Ok(true) => {
+                        self.held.lock().unwrap().remove(&id);
+                        continue;
+                    }
+                    // Work was forwarded meanwhile, keep holding
+                    Ok(false) => {}
+                    Err(e) => println!("Failed to release lease on {}: {}", id, e),
+                }
+            }
+            match storage.acquire_lease(id, self.instance, self.ttl) {
+                Ok(holder) if holder == self.instance => {
+                    if let Some(lease) = self.held.lock().unwrap().get_mut(&id) {
+                        lease.renewed = now;
+                    }
+                }
+                Ok(holder) => {
+                    println!("Lost lease on {} to {}", id, holder);
+                    self.held.lock().unwrap().remove(&id);
+                }
+                // Still held until it expires, the next renewal retries
+                Err(e) => println!("Failed to renew lease on {}: {}", id, e),
+            }
+        }
+        self.held.lock().unwrap().len()
+    }
+
+    /// Gives up a held lease, unless work was forwarded for it meanwhile.
+    pub fn release<T: Storage>(&self, storage: &T, id: Uuid) -> Result<bool, String> {
+        if !self.held.lock().unwrap().contains_key(&id) {
+            return Ok(true);
+        }
+        let released = storage.release_lease(id, self.instance)?;
+        if released {
+            self.held.lock().unwrap().remove(&id);
+        }
+        Ok(released)
+    }
+
+    /// Gives up every held lease, e.g. on shutdown.
+    pub fn release_all<T: Storage>(&self, storage: &T) {
+        let held: Vec<Uuid> = self
+            .held
+            .lock()
+            .unwrap()
+            .drain()
+            .map(|(id, _)| id)
+            .collect();
+        for id in held {
+            if let Err(e) = storage.release_lease(id, self.instance) {
+                println!("Failed to release lease on {}: {}", id, e);
+            }
+        }
+    }
+}
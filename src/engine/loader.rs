@@ -0,0 +1,70 @@
+use solana_sdk::{
+    account::Account,
+    bpf_loader_upgradeable::{self, get_program_data_address, UpgradeableLoaderState},
+    pubkey::Pubkey,
+    rent::Rent,
+};
+
+/// Program and ProgramData accounts of a program deployed through `bpf_loader_upgradeable`,
+/// the ELF lives in the ProgramData account after its metadata.
+pub fn upgradeable_program_accounts(
+    program_id: &Pubkey,
+    program_bytes: &[u8],
+    upgrade_authority: Option<Pubkey>,
+    rent: &Rent,
+) -> Result<[(Pubkey, Account); 2], String> {
+    let programdata_address = get_program_data_address(program_id);
+
+    let program_state = UpgradeableLoaderState::Program {
+        programdata_address,
+    };
+    let program_data = bincode::serialize(&program_state).map_err(|e| e.to_string())?;
+    let program = Account {
+        lamports: rent.minimum_balance(program_data.len()),
+        data: program_data,
+        owner: bpf_loader_upgradeable::id(),
+        executable: true,
+        rent_epoch: 0,
+    };
+
+    let programdata_state = UpgradeableLoaderState::ProgramData {
+        slot: 0,
+        upgrade_authority_address: upgrade_authority,
+    };
+    let mut programdata_data = bincode::serialize(&programdata_state).map_err(|e| e.to_string())?;
+    // Metadata is padded to a fixed size whether or not there's an authority
+    programdata_data.resize(UpgradeableLoaderState::size_of_programdata_metadata(), 0);
+    programdata_data.extend_from_slice(program_bytes);
+    let programdata = Account {
+        lamports: rent.minimum_balance(programdata_data.len()),
+        data: programdata_data,
+        owner: bpf_loader_upgradeable::id(),
+        executable: false,
+        rent_epoch: 0,
+    };
+
+    Ok([(*program_id, program), (programdata_address, programdata)])
+}
+
+/// Rewrites the upgrade authority in a ProgramData account's metadata, keeping the ELF.
+pub fn set_upgrade_authority(
+    programdata: &mut Account,
+    authority: Option<Pubkey>,
+) -> Result<(), String> {
+    let slot = match bincode::deserialize(&programdata.data) {
+        Ok(UpgradeableLoaderState::ProgramData { slot, .. }) => slot,
+        _ => return Err("Account is not a ProgramData account".to_string()),
+    };
+    let metadata_len = UpgradeableLoaderState::size_of_programdata_metadata();
+    if programdata.data.len() < metadata_len {
+        return Err("Account is not a ProgramData account".to_string());
+    }
+    let metadata = bincode::serialize(&UpgradeableLoaderState::ProgramData {
+        slot,
+        upgrade_authority_address: authority,
+    })
+    .map_err(|e| e.to_string())?;
+    programdata.data[..metadata_len].fill(0);
+    programdata.data[..metadata.len()].copy_from_slice(&metadata);
+    Ok(())
+}
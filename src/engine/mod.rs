@@ -1,9 +1,13 @@
+use activity::Activity;
 use actix_web::rt::{self, time};
-use blocks::{Block, Blockchain};
+use blocks::{AccountsStatus, Block, Blockchain};
 use builtins::BUILTINS;
 use chrono::{DateTime, Utc};
-use engine::TransactionProcessor;
-use itertools::Itertools;
+use engine::{Stage, TransactionProcessor};
+use leases::Leases;
+use queue::KafkaQueue;
+use serde::Serialize;
+use serde_json::json;
 use sha2::{Digest, Sha256};
 use solana_account_decoder::parse_token::is_known_spl_token_id;
 use solana_banks_interface::{TransactionConfirmationStatus, TransactionStatus};
@@ -13,7 +17,8 @@ use solana_sdk::{
     account::{Account, AccountSharedData, ReadableAccount, WritableAccount},
     account_utils::StateMut,
     address_lookup_table::{self, error::AddressLookupError, state::AddressLookupTable},
-    bpf_loader,
+    bpf_loader, bpf_loader_deprecated,
+    bpf_loader_upgradeable::{self, UpgradeableLoaderState},
     clock::Clock,
     epoch_rewards::EpochRewards,
     epoch_schedule::EpochSchedule,
@@ -29,6 +34,7 @@ use solana_sdk::{
     native_loader,
     native_token::LAMPORTS_PER_SOL,
     nonce,
+    program_option::COption,
     program_pack::Pack,
     pubkey::Pubkey,
     rent::Rent,
@@ -47,35 +53,130 @@ use solana_sdk::{
     },
 };
 
-use spl::generate_spl_programs;
-use spl_token::state::Account as SplAccount;
+use simulations::SimulationPools;
+use spl::{
+    associated_token_address, bundle_programs, generate_spl_programs, read_program,
+    set_mint_authorities,
+};
 use spl_token::state::Mint;
+use spl_token::state::{Account as SplAccount, AccountState};
+use spl_token_2022::extension::{
+    immutable_owner::ImmutableOwner, BaseStateWithExtensionsMut, ExtensionType,
+    StateWithExtensionsMut,
+};
+use status_cache::CachedStatus;
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
+    path::PathBuf,
     str::FromStr,
-    sync::{Arc, RwLock},
+    sync::{Arc, Mutex},
     time::Duration,
     vec,
 }; // Add this import at the top of your file
+use subscriptions::{LogsFilter, Subscriptions};
+use sysvars::{is_engine_sysvar, sysvar_account, SysvarOverrides};
+use timeline::{TimelineEvent, TIMELINE_ACTIONS};
 use tokens::TokenAmount;
 use tokio::sync::mpsc;
-use transactions::{TransactionMeta, TransactionMetadata};
+use transactions::{is_vote_transaction, TransactionMeta, TransactionMetadata};
 use uuid::Uuid;
+use write_locks::WriteLockStats;
+
+use crate::metrics;
+use crate::settings;
+use crate::storage::{
+    audit::{self, AuditLogFilter, DbAuditLog},
+    blocks::DbBlockchainUpdate,
+    events::BusEvent,
+    leases::Forwarded,
+    pubsub::PubSubHeartbeat,
+    top_ups::DbTopUp,
+    transactions::{
+        DbPendingTransaction, DbTransaction, DbTransactionLogMessage, TokenBalanceChange,
+    },
+    Storage,
+};
 
-use crate::storage::{transactions::DbTransaction, Storage};
-
+pub mod activity;
 pub mod blocks;
 pub mod builtins;
 pub mod engine;
+pub mod expiring;
+pub mod idl;
+pub mod leases;
+pub mod loader;
+pub mod parity;
+pub mod preflight;
+pub mod queue;
+pub mod simulations;
 pub mod spl;
+pub mod status_cache;
+pub mod subscriptions;
+pub mod sysvars;
+pub mod timeline;
 pub mod tokens;
+pub mod trace;
 pub mod transactions;
+pub mod write_locks;
+
+/// Airdrops are recorded as a single legacy transaction, which can address at most 256 accounts.
+pub const MAX_AIRDROP_BATCH: usize = 250;
+/// Slots a commitment trails the processed slot by, as on mainnet.
+pub const CONFIRMATION_DEPTH: u64 = 1;
+pub const FINALIZATION_DEPTH: u64 = 32;
+/// Forwarded items a lease holder takes per blockchain at a time.
+const FORWARDED_BATCH: usize = 100;
+/// Empty accounts deleted per run of the sweep.
+const EMPTY_ACCOUNT_SWEEP_SIZE: i64 = 1000;
+
+/// In-memory work of this engine instance, for operators.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct EngineStats {
+    pub queues: Vec<QueueStats>,
+    pub queued_transactions: usize,
+    pub in_flight_transactions: usize, // Queued plus executing
+    pub subscriptions: usize,
+    pub draining: bool,
+    /// Idle blockchains whose queue worker this instance stopped, see `Team::auto_pause_hours`.
+    pub paused_blockchains: usize,
+    /// Simulations kept for when their transaction is sent.
+    pub cached_preflights: usize,
+    /// Recent transaction statuses kept for signature polling.
+    pub cached_statuses: usize,
+    /// Executions of blockchains with `parity_check` on compared with a simulation.
+    pub parity_checks: u64,
+    /// Of those, the ones the simulation disagreed with.
+    pub parity_divergences: u64,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct QueueStats {
+    pub blockchain: Uuid,
+    pub queued: usize,
+    /// How long the next transaction has been waiting.
+    pub oldest_queued_ms: Option<u64>,
+    pub processing: Option<ProcessingStats>,
+    pub processed: u64,
+    pub capacity: usize,
+    /// Sends turned away with `QUEUE_FULL` since the queue started.
+    pub rejected: u64,
+}
+
+/// The transaction a queue is executing, and how long it's been in its current stage.
+#[derive(Clone, Debug, Serialize)]
+pub struct ProcessingStats {
+    pub signature: String,
+    pub stage: Stage,
+    pub stage_ms: u64,
+}
 
 pub trait SVM<T: Storage + Clone + 'static> {
     fn new(storage: T) -> Self;
 
     fn new_loader(&self, id: Uuid) -> Loader<T>;
 
+    /// With `defer_accounts` the blockchain is returned once its genesis block exists, and
+    /// the builtin, SPL, sysvar and config accounts are written in the background.
     fn create_blockchain(
         &self,
         team_id: Uuid,
@@ -83,9 +184,64 @@ pub trait SVM<T: Storage + Clone + 'static> {
         label: Option<String>,
         expiry: Option<chrono::NaiveDateTime>,
         config: Option<Uuid>,
+        defer_accounts: bool,
+    ) -> Result<Uuid, String>;
+    /// Provisions `count` hidden blockchains for the team and config, to be claimed later.
+    fn fill_warm_pool(
+        &self,
+        team_id: Uuid,
+        config: Option<Uuid>,
+        count: usize,
+    ) -> Result<(), String>;
+    fn clone_blockchain(
+        &self,
+        source: Uuid,
+        team_id: Uuid,
+        label: Option<String>,
+        expiry: Option<chrono::NaiveDateTime>,
+    ) -> Result<Uuid, String>;
+    /// A blockchain with `count` accounts, plus a funded airdrop account. `read`
+    /// hands the accounts to its argument one at a time, they're written in
+    /// chunks as they come. The blockchain is deleted again if `read` fails.
+    fn create_blockchain_from_accounts(
+        &self,
+        team_id: Uuid,
+        label: Option<String>,
+        expiry: Option<chrono::NaiveDateTime>,
+        count: usize,
+        read: &mut dyn FnMut(
+            &mut dyn FnMut(Pubkey, Account) -> Result<(), String>,
+        ) -> Result<(), String>,
     ) -> Result<Uuid, String>;
     fn get_blockchains(&self, team_id: Uuid) -> Result<Vec<Blockchain>, String>;
     fn delete_blockchain(&self, id: Uuid) -> Result<(), String>;
+    /// Replaces genesis programs with builds from `programs.directory`, keyed by
+    /// program id with the file name to load.
+    fn set_genesis_programs(&self, id: Uuid, programs: &[(Pubkey, String)]) -> Result<(), String>;
+    fn get_sysvar_overrides(&self, id: Uuid) -> Result<SysvarOverrides, String>;
+    /// Replaces the blockchain's sysvar overrides, read by the transactions after it.
+    fn set_sysvar_overrides(&self, id: Uuid, overrides: SysvarOverrides) -> Result<(), String>;
+    /// Deletes every blockchain past its expiry, returns how many were deleted.
+    fn expire_blockchains(&self) -> Result<usize, String>;
+    /// Deletes zero-lamport accounts without data left by writes that bypass
+    /// transactions, returns how many were deleted.
+    fn sweep_empty_accounts(&self) -> Result<usize, String>;
+    fn engine_stats(&self) -> EngineStats;
+    /// The blockchain's most write-locked accounts, as executed by this instance.
+    fn write_lock_report(&self, id: Uuid, limit: usize) -> Vec<(Pubkey, WriteLockStats)>;
+    /// Significant events of the blockchain newest first, merged from its audit log,
+    /// which also holds the program deployments its transactions made, its
+    /// creation and its expiry.
+    fn timeline(
+        &self,
+        id: Uuid,
+        since: Option<chrono::NaiveDateTime>,
+        limit: usize,
+    ) -> Result<Vec<TimelineEvent>, String>;
+    /// Stops accepting transactions, then waits up to `timeout` for the queued
+    /// ones and their database writes to finish.
+    #[allow(async_fn_in_trait)]
+    async fn drain(&self, timeout: Duration);
 
     #[allow(async_fn_in_trait)]
     async fn get_account(
@@ -103,6 +259,23 @@ pub trait SVM<T: Storage + Clone + 'static> {
         pubkey: &Pubkey,
         limit: Option<usize>,
     ) -> Result<Vec<DbTransaction>, String>;
+    /// Log lines the program emitted between `start` and `end`, oldest first.
+    fn get_program_logs(
+        &self,
+        id: Uuid,
+        program_id: &Pubkey,
+        start: chrono::NaiveDateTime,
+        end: chrono::NaiveDateTime,
+        limit: i64,
+    ) -> Result<Vec<DbTransactionLogMessage>, String>;
+    /// Balance changes of the owner's token accounts, newest first.
+    fn get_token_balance_changes(
+        &self,
+        id: Uuid,
+        owner: &Pubkey,
+        before: Option<&Signature>,
+        limit: i64,
+    ) -> Result<Vec<TokenBalanceChange>, String>;
     #[allow(async_fn_in_trait)]
     async fn get_balance(&self, id: Uuid, pubkey: &Pubkey) -> Result<Option<u64>, String>;
     fn get_block(&self, id: Uuid, slot_number: &u64) -> Result<Option<Block>, String>;
@@ -123,6 +296,8 @@ pub trait SVM<T: Storage + Clone + 'static> {
         jit: bool,
     ) -> Result<Vec<Option<Account>>, String>;
     fn latest_blockhash(&self, id: Uuid) -> Result<Block, String>;
+    /// Appends a block to every blockchain whose blockhash was read since the last call.
+    fn produce_blocks(&self) -> Result<usize, String>;
     fn current_block(&self, id: Uuid) -> Result<Block, String>;
     fn minimum_balance_for_rent_exemption(&self, data_len: usize) -> u64;
     fn is_blockhash_valid(&self, id: Uuid, blockhash: &Hash) -> Result<(Block, bool), String>;
@@ -137,6 +312,12 @@ pub trait SVM<T: Storage + Clone + 'static> {
         id: Uuid,
         pubkey: &Pubkey,
     ) -> Result<Vec<(Pubkey, Account)>, String>;
+    /// Initialized nonce accounts the authority can advance.
+    fn get_nonce_accounts(
+        &self,
+        id: Uuid,
+        authority: &Pubkey,
+    ) -> Result<Vec<(Pubkey, Account)>, String>;
     fn get_largest_accounts(&self, id: Uuid) -> Result<Vec<(Pubkey, u64)>, String>;
     #[allow(async_fn_in_trait)]
     async fn get_token_supply(
@@ -156,7 +337,21 @@ pub trait SVM<T: Storage + Clone + 'static> {
         &self,
         id: Uuid,
         signature: &Signature,
-    ) -> Result<Option<(Transaction, TransactionMeta, TransactionStatus)>, String>;
+    ) -> Result<Option<(VersionedTransaction, TransactionMeta, TransactionStatus)>, String>;
+    /// The transaction's status, without loading the transaction when it was
+    /// processed recently.
+    fn get_signature_status(
+        &self,
+        id: Uuid,
+        signature: &Signature,
+    ) -> Result<Option<TransactionStatus>, String>;
+    /// The statuses of `signatures` like `get_signature_status`, with the latest
+    /// slot they're confirmed against.
+    fn get_signature_statuses(
+        &self,
+        id: Uuid,
+        signatures: &[Signature],
+    ) -> Result<(u64, Vec<Option<TransactionStatus>>), String>;
     fn get_transaction_count(&self, id: Uuid) -> Result<u64, String>;
     fn send_transaction(
         &self,
@@ -171,17 +366,99 @@ pub trait SVM<T: Storage + Clone + 'static> {
         tx: VersionedTransaction,
         jit: bool,
     ) -> Result<TransactionMetadata, String>;
+    /// Simulates, recording each instruction's program, units and account writes
+    /// in the result's `trace`.
+    #[allow(async_fn_in_trait)]
+    async fn trace_transaction(
+        &self,
+        id: Uuid,
+        tx: VersionedTransaction,
+        jit: bool,
+    ) -> Result<TransactionMetadata, String>;
     #[allow(async_fn_in_trait)]
     async fn airdrop(&self, id: Uuid, pubkey: &Pubkey, lamports: u64) -> Result<String, String>;
+    /// Mints tokens to the owner's associated token account, creating the mint
+    /// (with the blockchain identity as authority) and the account when missing.
+    #[allow(async_fn_in_trait)]
+    async fn mint_tokens(
+        &self,
+        id: Uuid,
+        mint: Option<Pubkey>,
+        decimals: u8,
+        owner: &Pubkey,
+        amount: u64,
+    ) -> Result<(Pubkey, Pubkey), String>;
+    /// Writes the owner's associated token account with exactly `amount`, creating
+    /// it when missing, and moves the mint's supply by the difference. Returns the
+    /// token account.
+    #[allow(async_fn_in_trait)]
+    async fn set_token_balance(
+        &self,
+        id: Uuid,
+        mint: &Pubkey,
+        owner: &Pubkey,
+        amount: u64,
+    ) -> Result<Pubkey, String>;
+    /// Funds several accounts with one synthetic transaction.
+    #[allow(async_fn_in_trait)]
+    async fn airdrop_batch(&self, id: Uuid, recipients: &[(Pubkey, u64)])
+        -> Result<String, String>;
     fn add_program(&self, program_id: Pubkey, program_bytes: &[u8]) -> (Pubkey, Account);
+    /// Program and ProgramData accounts for a `bpf_loader_upgradeable` deployment.
+    fn add_upgradeable_program(
+        &self,
+        program_id: Pubkey,
+        program_bytes: &[u8],
+        upgrade_authority: Option<Pubkey>,
+    ) -> Result<Vec<(Pubkey, Account)>, String>;
+    /// Copies a deployed program from the upstream RPC, returns the addresses written.
+    #[allow(async_fn_in_trait)]
+    async fn clone_program(&self, id: Uuid, program_id: &Pubkey) -> Result<Vec<Pubkey>, String>;
+    #[allow(async_fn_in_trait)]
+    /// Clones the programs of each bundle from mainnet, returns the accounts written.
+    async fn clone_bundles(&self, id: Uuid, bundles: &[String]) -> Result<Vec<Pubkey>, String>;
+    /// Rewrites a mint's mint and freeze authorities, `None` leaves one as is.
+    #[allow(async_fn_in_trait)]
+    async fn set_mint_authorities(
+        &self,
+        id: Uuid,
+        mint: &Pubkey,
+        mint_authority: Option<Pubkey>,
+        freeze_authority: Option<Pubkey>,
+    ) -> Result<(), String>;
+    /// Rewrites an upgradeable program's upgrade authority, returns its ProgramData account.
+    #[allow(async_fn_in_trait)]
+    async fn set_upgrade_authority(
+        &self,
+        id: Uuid,
+        program_id: &Pubkey,
+        authority: &Pubkey,
+    ) -> Result<Pubkey, String>;
+    /// Airdrops or mints the difference between the top-up's target and the current
+    /// balance, returns how much was added.
+    #[allow(async_fn_in_trait)]
+    async fn top_up(&self, top_up: &DbTopUp) -> Result<u64, String>;
+    /// Runs the top-ups that are due, returns how many added to a balance.
+    #[allow(async_fn_in_trait)]
+    async fn run_top_ups(&self) -> Result<usize, String>;
+    /// Fetches accounts from the upstream RPC, plus the program data of upgradeable
+    /// programs among them, failing if any is missing.
+    #[allow(async_fn_in_trait)]
+    async fn fetch_remote_accounts(
+        &self,
+        addresses: &[Pubkey],
+    ) -> Result<Vec<(Pubkey, Account)>, String>;
 
     #[allow(async_fn_in_trait)]
+    /// Waits for the transaction to reach the commitment, `None` once unsubscribed.
     async fn signature_subscribe(
         &self,
         id: Uuid,
+        req_id: u32,
         signature: &Signature,
         commitment: TransactionConfirmationStatus,
-    ) -> Result<u64, String>;
+    ) -> Result<Option<TransactionStatus>, String>;
+    fn signature_unsubscribe(&self, req_id: u32) -> Result<(), String>;
     fn slot_subscribe(
         &self,
         id: Uuid,
@@ -193,9 +470,16 @@ pub trait SVM<T: Storage + Clone + 'static> {
         &self,
         id: Uuid,
         req_id: u32,
-        pubkey: &Pubkey,
+        filter: &LogsFilter,
     ) -> Result<
-        mpsc::Receiver<Option<(Signature, Transaction, TransactionMeta, TransactionStatus)>>,
+        mpsc::Receiver<
+            Option<(
+                Signature,
+                VersionedTransaction,
+                TransactionMeta,
+                TransactionStatus,
+            )>,
+        >,
         String,
     >;
     fn logs_unsubscribe(&self, req_id: u32) -> Result<(), String>;
@@ -209,7 +493,22 @@ pub struct SvmEngine<T: Storage + Clone + 'static> {
     sysvar_cache: SysvarCache,
     pub storage: T,
     transaction_processor: Arc<TransactionProcessor<T>>,
-    subscribed_slots: Arc<RwLock<Vec<u32>>>,
+    subscriptions: Subscriptions,
+    // Blockchains handed a blockhash since blocks were last produced
+    pending_blocks: Arc<Mutex<HashSet<Uuid>>>,
+    // Whether a ticker produces blocks, without one nothing would ever finalize
+    produces_blocks: bool,
+    anchor_compat: bool,
+    // Only set when instances coordinate through leases
+    leases: Option<Arc<Leases>>,
+    // Sent transactions go to Kafka instead of the in-process queues when set
+    queue: Option<KafkaQueue>,
+    // Instance that queued transactions are persisted under and resumed by, set
+    // when `features.persist_queue` is on
+    persist_queue: Option<Uuid>,
+    activity: Arc<Activity>,
+    programs_directory: Option<PathBuf>,
+    simulations: Arc<SimulationPools>,
 }
 
 impl<T: Storage + Clone + 'static> SVM<T> for SvmEngine<T> {
@@ -228,7 +527,22 @@ impl<T: Storage + Clone + 'static> SVM<T> for SvmEngine<T> {
             sysvar_cache: SysvarCache::default(),
             storage,
             transaction_processor: tx_processor,
-            subscribed_slots: Arc::new(RwLock::new(Vec::new())),
+            subscriptions: Subscriptions::default(),
+            pending_blocks: Arc::new(Mutex::new(HashSet::new())),
+            produces_blocks: settings::get().jobs.block_interval.is_some(),
+            anchor_compat: settings::get().features.anchor_compat,
+            leases: None,
+            queue: None,
+            persist_queue: settings::get()
+                .cluster
+                .instance_id
+                .filter(|_| settings::get().features.persist_queue),
+            activity: Arc::default(),
+            programs_directory: settings::get().programs.directory.clone(),
+            simulations: Arc::new(SimulationPools::new(
+                settings::get().limits.simulation_workers,
+                settings::get().limits.simulation_queue_depth,
+            )),
         };
         engine.set_sysvars();
 
@@ -247,28 +561,34 @@ impl<T: Storage + Clone + 'static> SVM<T> for SvmEngine<T> {
     async fn signature_subscribe(
         &self,
         id: Uuid,
+        req_id: u32,
         signature: &Signature,
         commitment: TransactionConfirmationStatus,
-    ) -> Result<u64, String> {
+    ) -> Result<Option<TransactionStatus>, String> {
+        // Dropped with this future, when the connection goes away mid-wait
+        let _registration = self.subscriptions.register(req_id);
         let mut interval = time::interval(Duration::from_millis(50));
         loop {
-            let tx = self.get_transaction(id, signature)?;
-            if tx == None {
-                continue;
+            interval.tick().await;
+            if !self.subscriptions.contains(req_id) {
+                return Ok(None);
             }
-            if let Some((_, _, status)) = tx {
-                if status.confirmation_status == None {
-                    continue;
-                }
-                let confirmation_status = status.confirmation_status.unwrap();
-                if status_is_greater(&commitment, &confirmation_status) {
-                    return Ok(status.slot);
+            if let Some((_, _, status)) = self.get_transaction(id, signature)? {
+                let reached = status
+                    .confirmation_status
+                    .as_ref()
+                    .is_some_and(|status| status_is_greater(&commitment, status));
+                if reached {
+                    return Ok(Some(status));
                 }
             }
-            interval.tick().await;
         }
     }
 
+    fn signature_unsubscribe(&self, req_id: u32) -> Result<(), String> {
+        self.subscriptions.remove(req_id)
+    }
+
     fn slot_subscribe(
         &self,
         _id: Uuid,
@@ -340,34 +660,104 @@ impl<T: Storage + Clone + 'static> SVM<T> for SvmEngine<T> {
         Ok(rx)
     }
     fn slot_unsubscribe(&self, req_id: u32) -> Result<(), String> {
-        let mut sub_slots = self.subscribed_slots.try_write().unwrap();
-        let (idx, _) = match sub_slots.iter().find_position(|val| **val == req_id) {
-            Some(val) => val,
-            None => return Err("Subscription ID not found".to_string()),
-        };
-
-        sub_slots.remove(idx);
-        Ok(())
+        self.subscriptions.remove(req_id)
     }
     fn logs_subscribe(
         &self,
         id: Uuid,
         req_id: u32,
-        pubkey: &Pubkey,
+        filter: &LogsFilter,
     ) -> Result<
-        mpsc::Receiver<Option<(Signature, Transaction, TransactionMeta, TransactionStatus)>>,
+        mpsc::Receiver<
+            Option<(
+                Signature,
+                VersionedTransaction,
+                TransactionMeta,
+                TransactionStatus,
+            )>,
+        >,
         String,
     > {
+        let events = self.storage.subscribe_events(id)?;
+        // Without events, logs are polled by the transactions mentioning the account
+        let pubkey_clone = match (filter, &events) {
+            (LogsFilter::Mentions(pubkey), _) => *pubkey,
+            (_, Some(_)) => Pubkey::default(), // Unused
+            (_, None) => return Err("Subscribing to all logs requires the event bus".to_string()),
+        };
         let (tx, rx) = mpsc::channel(100); // Create a channel with a buffer size of 100
         let mut interval = time::interval(Duration::from_millis(50));
         let self_clone = self.clone();
-        let pubkey_clone = pubkey.clone();
-        self.subscribed_slots.try_write().unwrap().push(req_id);
-        let sub_slots = self.subscribed_slots.clone();
+        self.subscriptions.add(req_id);
+        let subscriptions = self.subscriptions.clone();
+
+        if let Some(mut events) = events {
+            let filter = filter.clone();
+            rt::spawn(async move {
+                loop {
+                    let event = tokio::select! {
+                        event = events.recv() => event,
+                        _ = interval.tick() => {
+                            if !subscriptions.contains(req_id) {
+                                let _ = tx.send(None).await;
+                                break;
+                            }
+                            continue;
+                        }
+                    };
+                    let (signature, accounts) = match event {
+                        Some(BusEvent::Transaction {
+                            signature,
+                            accounts,
+                        }) => (signature, accounts),
+                        Some(_) => continue,
+                        None => {
+                            let _ = tx.send(None).await;
+                            break;
+                        }
+                    };
+                    if !filter.mentioned(&accounts) {
+                        continue;
+                    }
+                    let signature = match Signature::from_str(&signature) {
+                        Ok(signature) => signature,
+                        Err(_) => continue,
+                    };
+                    let (transaction, transaction_meta, transaction_status) =
+                        match self_clone.get_transaction(id, &signature) {
+                            Ok(Some(transaction)) => transaction,
+                            Ok(None) => continue,
+                            Err(_) => {
+                                let _ = tx.send(None).await;
+                                break;
+                            }
+                        };
+                    if !filter.matches(&accounts, is_vote_transaction(&transaction)) {
+                        continue;
+                    }
+                    if tx
+                        .send(Some((
+                            signature,
+                            transaction,
+                            transaction_meta,
+                            transaction_status,
+                        )))
+                        .await
+                        .is_err()
+                    {
+                        break;
+                    }
+                }
+            });
+            return Ok(rx);
+        }
+
         rt::spawn(async move {
+            // Each tick reads from where the last one stopped, so nothing is skipped
+            let mut since = Utc::now().naive_utc();
             loop {
                 interval.tick().await;
-                if !sub_slots.try_read().unwrap().contains(&req_id) {
+                if !subscriptions.contains(req_id) {
                     match tx.send(None).await {
                         Ok(_) => {}
                         Err(_) => {}
@@ -375,15 +765,19 @@ impl<T: Storage + Clone + 'static> SVM<T> for SvmEngine<T> {
                     break;
                 }
                 let now = Utc::now().naive_utc();
-                let start = now - Duration::from_millis(50);
                 let transactions = self_clone.storage.get_transactions_for_address_created_at(
                     id,
                     &pubkey_clone,
-                    start,
+                    since,
                     now,
                 );
-                let transactions = match transactions {
-                    Ok(transactions) => transactions,
+                let signatures = match transactions {
+                    // Both ends are inclusive, the last tick already sent those at `since`
+                    Ok(transactions) => transactions
+                        .into_iter()
+                        .filter(|transaction| transaction.created_at > since)
+                        .map(|transaction| transaction.signature)
+                        .collect::<Vec<_>>(),
                     Err(_) => {
                         match tx.send(None).await {
                             Ok(_) => {}
@@ -392,9 +786,10 @@ impl<T: Storage + Clone + 'static> SVM<T> for SvmEngine<T> {
                         break;
                     }
                 };
+                since = now;
 
-                for db_transaction in transactions {
-                    let signature = match Signature::from_str(&db_transaction.signature) {
+                for signature in signatures {
+                    let signature = match Signature::from_str(&signature) {
                         Ok(signature) => signature,
                         Err(_) => {
                             match tx.send(None).await {
@@ -437,14 +832,7 @@ impl<T: Storage + Clone + 'static> SVM<T> for SvmEngine<T> {
         Ok(rx)
     }
     fn logs_unsubscribe(&self, req_id: u32) -> Result<(), String> {
-        let mut sub_slots = self.subscribed_slots.try_write().unwrap();
-        let (idx, _) = match sub_slots.iter().find_position(|val| **val == req_id) {
-            Some(val) => val,
-            None => return Err("Subscription ID not found".to_string()),
-        };
-
-        sub_slots.remove(idx);
-        Ok(())
+        self.subscriptions.remove(req_id)
     }
 
     fn create_blockchain(
@@ -454,88 +842,338 @@ impl<T: Storage + Clone + 'static> SVM<T> for SvmEngine<T> {
         label: Option<String>,
         expiry: Option<chrono::NaiveDateTime>,
         config: Option<Uuid>,
+        defer_accounts: bool,
+    ) -> Result<Uuid, String> {
+        let blockchain = Blockchain {
+            id: Uuid::new_v4(),
+            created_at: Utc::now().naive_utc(),
+            airdrop_keypair: airdrop_keypair.unwrap_or_else(Keypair::new),
+            team_id,
+            label,
+            expiry,
+            jit: false,
+            config,
+            pooled: false,
+            log_bytes_limit: None,
+            read_only: false,
+            sponsor_fees: false,
+            parity_check: false,
+            accounts_status: match defer_accounts {
+                true => AccountsStatus::Initializing,
+                false => AccountsStatus::Ready,
+            },
+        };
+        self.provision_blockchain(blockchain, defer_accounts)
+    }
+
+    fn fill_warm_pool(
+        &self,
+        team_id: Uuid,
+        config: Option<Uuid>,
+        count: usize,
+    ) -> Result<(), String> {
+        for _ in 0..count {
+            let blockchain = Blockchain {
+                id: Uuid::new_v4(),
+                created_at: Utc::now().naive_utc(),
+                airdrop_keypair: Keypair::new(),
+                team_id,
+                label: None,
+                expiry: None,
+                jit: false,
+                config,
+                pooled: true,
+                log_bytes_limit: None,
+                read_only: false,
+                sponsor_fees: false,
+                parity_check: false,
+                accounts_status: AccountsStatus::Ready,
+            };
+            self.provision_blockchain(blockchain, false)?;
+        }
+        Ok(())
+    }
+
+    fn clone_blockchain(
+        &self,
+        source: Uuid,
+        team_id: Uuid,
+        label: Option<String>,
+        expiry: Option<chrono::NaiveDateTime>,
     ) -> Result<Uuid, String> {
-        let keypair = match airdrop_keypair {
-            Some(k) => k,
-            None => Keypair::new(),
+        let source = self.storage.get_blockchain(source)?;
+        let accounts = self.storage.snapshot_accounts(source.id)?;
+        if let Some(message) =
+            new_blockchain_quota_exceeded(&self.storage, team_id, accounts.len())?
+        {
+            return Err(message);
+        }
+
+        // The clone keeps the source's identity so the funded airdrop account
+        // carried over with the accounts remains usable
+        let blockchain = Blockchain {
+            id: Uuid::new_v4(),
+            created_at: Utc::now().naive_utc(),
+            airdrop_keypair: source.airdrop_keypair.insecure_clone(),
+            team_id,
+            label,
+            expiry,
+            jit: source.jit,
+            config: source.config,
+            pooled: false,
+            log_bytes_limit: source.log_bytes_limit,
+            read_only: source.read_only,
+            sponsor_fees: source.sponsor_fees,
+            parity_check: source.parity_check,
+            accounts_status: AccountsStatus::Ready,
         };
+        let id = self.storage.set_blockchain(&blockchain)?;
+        self.set_genesis_block(id)?;
+        if let Some(overrides) = self.storage.get_sysvar_overrides(source.id)? {
+            self.storage.set_sysvar_overrides(id, &overrides)?;
+        }
+
+        for chunk in accounts.chunks(1000) {
+            self.storage.set_accounts(id, chunk.to_vec())?;
+        }
+
+        Ok(id)
+    }
 
+    fn create_blockchain_from_accounts(
+        &self,
+        team_id: Uuid,
+        label: Option<String>,
+        expiry: Option<chrono::NaiveDateTime>,
+        count: usize,
+        read: &mut dyn FnMut(
+            &mut dyn FnMut(Pubkey, Account) -> Result<(), String>,
+        ) -> Result<(), String>,
+    ) -> Result<Uuid, String> {
+        // Plus the airdrop account
+        if let Some(message) = new_blockchain_quota_exceeded(&self.storage, team_id, count + 1)? {
+            return Err(message);
+        }
+        let keypair = Keypair::new();
         let blockchain = Blockchain {
             id: Uuid::new_v4(),
             created_at: Utc::now().naive_utc(),
             airdrop_keypair: keypair.insecure_clone(),
             team_id,
-            label: label,
-            expiry: expiry,
+            label,
+            expiry,
             jit: false,
+            config: None,
+            pooled: false,
+            log_bytes_limit: None,
+            read_only: false,
+            sponsor_fees: false,
+            parity_check: false,
+            accounts_status: AccountsStatus::Ready,
         };
-
         let id = self.storage.set_blockchain(&blockchain)?;
-        let mut hasher = Sha256::new();
-        hasher.update(id.as_bytes());
-        let hash_array = hasher.finalize();
-        let hash = Hash::new_from_array(hash_array.into());
-        match self.storage.set_block(
-            id,
-            &Block {
-                blockhash: hash,
-                block_time: 0,
-                previous_blockhash: Hash::default(),
-                block_height: 0,
-                parent_slot: 0,
-                transactions: vec![],
-            },
-        ) {
-            Ok(_) => {}
-            Err(e) => {
-                println!("Error setting genesis block: {:?}", e);
-                return Err(e);
+        self.set_genesis_block(id)?;
+
+        let mut chunk = Vec::with_capacity(1000);
+        let mut read_count = 0;
+        let written = read(&mut |address, account| {
+            read_count += 1;
+            if read_count > count {
+                return Err(format!("More than the {} accounts expected", count));
             }
-        };
-        let mut accounts_to_upload: Vec<(Pubkey, Account)> = vec![];
-        if config.is_some() {
-            let config_id = config.unwrap();
-            let accounts = self
-                .storage
-                .get_config_accounts(config_id)
-                .expect("Failed to get config accounts");
-            accounts.iter().for_each(|(pubkey, account)| {
-                accounts_to_upload.push((pubkey.clone(), account.clone()));
-            });
-        }
-
-        let mut sysvars = self.get_sysvars();
-        sysvars.iter_mut().for_each(|(pubkey, account)| {
-            accounts_to_upload.push((pubkey.clone(), account.clone()));
-        });
-        accounts_to_upload.push((
-            keypair.pubkey(),
-            Account {
-                lamports: 1_000_000u64.wrapping_mul(LAMPORTS_PER_SOL),
-                data: vec![],
-                owner: system_program::id(),
-                executable: false,
-                rent_epoch: 100000000000,
-            },
-        ));
-        BUILTINS.iter().for_each(|builtint| {
-            let mut account: Account =
-                native_loader::create_loadable_account_for_test(builtint.name).into();
-            account.rent_epoch = 1000000;
-            accounts_to_upload.push((builtint.program_id, account));
-        });
-        let program_accounts = generate_spl_programs(self);
-        program_accounts.iter().for_each(|(pubkey, account)| {
-            accounts_to_upload.push((pubkey.clone(), account.clone()));
+            chunk.push((address, account));
+            if chunk.len() == 1000 {
+                self.storage.set_accounts(id, std::mem::take(&mut chunk))?;
+            }
+            Ok(())
+        })
+        .and_then(|_| {
+            chunk.push((
+                keypair.pubkey(),
+                Account {
+                    lamports: 1_000_000u64.wrapping_mul(LAMPORTS_PER_SOL),
+                    data: vec![],
+                    owner: system_program::id(),
+                    executable: false,
+                    rent_epoch: 100000000000,
+                },
+            ));
+            self.storage.set_accounts(id, std::mem::take(&mut chunk))
         });
-
-        self.storage.set_accounts(id, accounts_to_upload)?;
+        if let Err(e) = written {
+            if let Err(e) = self.delete_blockchain(id) {
+                println!("Failed to delete partly restored blockchain {}: {}", id, e);
+            }
+            return Err(e);
+        }
 
         Ok(id)
     }
 
     fn delete_blockchain(&self, id: Uuid) -> Result<(), String> {
-        self.storage.delete_blockchain(id)
+        self.storage.delete_blockchain(id)?;
+        metrics::forget_blockchain(id);
+        self.activity.forget(id);
+        self.transaction_processor.write_locks().forget(id);
+        self.transaction_processor.statuses().forget(id);
+        self.transaction_processor.forget_sysvar_overrides(id);
+        Ok(())
+    }
+
+    fn set_genesis_programs(&self, id: Uuid, programs: &[(Pubkey, String)]) -> Result<(), String> {
+        let directory = self
+            .programs_directory
+            .as_deref()
+            .ok_or("No programs directory is configured")?;
+        let mut accounts = vec![];
+        for (program_id, file) in programs {
+            let bytes = read_program(directory, file)?;
+            accounts.push(self.add_program(*program_id, &bytes));
+        }
+        self.storage.set_accounts(id, accounts)
+    }
+
+    fn get_sysvar_overrides(&self, id: Uuid) -> Result<SysvarOverrides, String> {
+        self.transaction_processor.sysvar_overrides(id)
+    }
+
+    fn set_sysvar_overrides(&self, id: Uuid, overrides: SysvarOverrides) -> Result<(), String> {
+        overrides.validate()?;
+        self.transaction_processor
+            .set_sysvar_overrides(id, overrides)
+    }
+
+    fn expire_blockchains(&self) -> Result<usize, String> {
+        let mut deleted = 0;
+        for blockchain in self.storage.get_expired_blockchains()? {
+            match self.delete_blockchain(blockchain.id) {
+                Ok(_) => {
+                    deleted += 1;
+                    let log = DbAuditLog::new(
+                        blockchain.team_id,
+                        None,
+                        audit::BLOCKCHAIN_EXPIRE,
+                        Some(blockchain.id),
+                        json!({ "expiry": blockchain.expiry }),
+                    );
+                    if let Err(e) = self.storage.create_audit_log(&log) {
+                        println!("Failed to record expiry of {}: {}", blockchain.id, e);
+                    }
+                }
+                Err(e) => println!("Error deleting blockchain {}: {}", blockchain.id, e),
+            }
+        }
+        Ok(deleted)
+    }
+
+    fn sweep_empty_accounts(&self) -> Result<usize, String> {
+        let mut by_blockchain: HashMap<Uuid, Vec<Pubkey>> = HashMap::new();
+        for (id, address) in self.storage.get_empty_accounts(EMPTY_ACCOUNT_SWEEP_SIZE)? {
+            by_blockchain.entry(id).or_default().push(address);
+        }
+        let mut deleted = 0;
+        for (id, addresses) in by_blockchain {
+            // The database lags the cache, each account is checked again as it's deleted
+            deleted += self.storage.delete_empty_accounts(id, &addresses)?;
+        }
+        Ok(deleted)
+    }
+
+    fn write_lock_report(&self, id: Uuid, limit: usize) -> Vec<(Pubkey, WriteLockStats)> {
+        self.transaction_processor.write_locks().report(id, limit)
+    }
+
+    fn timeline(
+        &self,
+        id: Uuid,
+        since: Option<chrono::NaiveDateTime>,
+        limit: usize,
+    ) -> Result<Vec<TimelineEvent>, String> {
+        let blockchain = self.storage.get_blockchain(id)?;
+        let (logs, _) = self.storage.get_audit_logs(
+            blockchain.team_id,
+            &AuditLogFilter {
+                blockchain: Some(id),
+                actions: TIMELINE_ACTIONS.iter().map(|a| a.to_string()).collect(),
+                since,
+                limit: limit as i64,
+                ..Default::default()
+            },
+        )?;
+
+        let mut events: Vec<TimelineEvent> = logs
+            .into_iter()
+            .map(TimelineEvent::from_audit_log)
+            .collect();
+        // Warm pool blockchains and ones made before the audit log have no create entry
+        let created = events
+            .iter()
+            .any(|e| e.kind == audit::BLOCKCHAIN_CREATE || e.kind == audit::BLOCKCHAIN_CLONE);
+        if !created && since.is_none_or(|since| blockchain.created_at >= since) {
+            events.push(TimelineEvent {
+                timestamp: blockchain.created_at,
+                kind: audit::BLOCKCHAIN_CREATE.to_string(),
+                source: "blockchain",
+                signature: None,
+                details: json!({ "config": blockchain.config }),
+            });
+        }
+        if let Some(expiry) = blockchain.expiry {
+            if since.is_none_or(|since| expiry >= since) {
+                events.push(TimelineEvent {
+                    timestamp: expiry,
+                    kind: timeline::BLOCKCHAIN_EXPIRY.to_string(),
+                    source: "blockchain",
+                    signature: None,
+                    details: json!({ "expiry": expiry }),
+                });
+            }
+        }
+        Ok(timeline::merge(events, limit))
+    }
+
+    fn engine_stats(&self) -> EngineStats {
+        let queues = self.transaction_processor.queue_stats();
+        let (parity_checks, parity_divergences) = self.transaction_processor.parity_checks();
+        EngineStats {
+            queued_transactions: queues.iter().map(|queue| queue.queued).sum(),
+            queues,
+            in_flight_transactions: self.transaction_processor.in_flight(),
+            subscriptions: self.subscriptions.len(),
+            draining: self.transaction_processor.is_draining(),
+            paused_blockchains: self.activity.paused(),
+            cached_preflights: self.transaction_processor.cached_preflights(),
+            cached_statuses: self.transaction_processor.statuses().len(),
+            parity_checks,
+            parity_divergences,
+        }
+    }
+
+    async fn drain(&self, timeout: Duration) {
+        self.transaction_processor.start_draining();
+        let deadline = time::Instant::now() + timeout;
+        let mut interval = time::interval(Duration::from_millis(50));
+        while self.transaction_processor.in_flight() > 0 {
+            if time::Instant::now() >= deadline {
+                println!(
+                    "Gave up on {} queued transactions",
+                    self.transaction_processor.in_flight()
+                );
+                break;
+            }
+            interval.tick().await;
+        }
+        if let Some(queue) = &self.queue {
+            queue.flush(deadline.saturating_duration_since(time::Instant::now()));
+        }
+        // Work forwarded from now on waits for the next holder
+        if let Some(leases) = &self.leases {
+            leases.release_all(&self.storage);
+        }
+        // Transactions write behind, so flush after the queues are empty
+        self.storage
+            .flush(deadline.saturating_duration_since(time::Instant::now()))
+            .await;
     }
 
     fn get_blockchains(&self, team_id: Uuid) -> Result<Vec<Blockchain>, String> {
@@ -548,6 +1186,10 @@ impl<T: Storage + Clone + 'static> SVM<T> for SvmEngine<T> {
         pubkey: &Pubkey,
         jit: bool,
     ) -> Result<Option<Account>, String> {
+        if is_engine_sysvar(pubkey) {
+            let sysvars = self.transaction_processor.sysvars(id)?;
+            return Ok(sysvar_account(&sysvars, pubkey));
+        }
         self.storage.get_account_jit(id, pubkey, jit).await
     }
 
@@ -585,6 +1227,29 @@ impl<T: Storage + Clone + 'static> SVM<T> for SvmEngine<T> {
         self.storage.get_transactions_for_address(id, pubkey, limit)
     }
 
+    fn get_program_logs(
+        &self,
+        id: Uuid,
+        program_id: &Pubkey,
+        start: chrono::NaiveDateTime,
+        end: chrono::NaiveDateTime,
+        limit: i64,
+    ) -> Result<Vec<DbTransactionLogMessage>, String> {
+        self.storage
+            .get_program_logs(id, program_id, start, end, limit)
+    }
+
+    fn get_token_balance_changes(
+        &self,
+        id: Uuid,
+        owner: &Pubkey,
+        before: Option<&Signature>,
+        limit: i64,
+    ) -> Result<Vec<TokenBalanceChange>, String> {
+        self.storage
+            .get_token_balance_changes(id, owner, before, limit)
+    }
+
     async fn get_balance(&self, id: Uuid, pubkey: &Pubkey) -> Result<Option<u64>, String> {
         match self.get_account(id, pubkey, false).await? {
             Some(account) => Ok(Some(account.lamports)),
@@ -605,13 +1270,14 @@ impl<T: Storage + Clone + 'static> SVM<T> for SvmEngine<T> {
         id: Uuid,
         slot_number: &u64,
     ) -> Result<Option<TransactionConfirmationStatus>, String> {
-        match self
-            .storage
-            .get_block_created_at(id, slot_number.to_owned())
-        {
-            Ok(created_at) => Ok(Some(tx_confirmation_status(created_at))),
-            Err(e) => Err(e),
-        }
+        self.storage
+            .get_block_created_at(id, slot_number.to_owned())?;
+        let current_slot = self.storage.get_latest_block(id)?.block_height;
+        Ok(Some(self.confirmation_status(
+            id,
+            *slot_number,
+            current_slot,
+        )))
     }
 
     fn get_latest_block(&self, id: Uuid) -> Result<Block, String> {
@@ -648,32 +1314,54 @@ impl<T: Storage + Clone + 'static> SVM<T> for SvmEngine<T> {
         pubkeys: &Vec<&Pubkey>,
         jit: bool,
     ) -> Result<Vec<Option<Account>>, String> {
-        self.storage.get_accounts_jit(id, pubkeys, jit).await
+        if !pubkeys.iter().any(|pubkey| is_engine_sysvar(pubkey)) {
+            return self.storage.get_accounts_jit(id, pubkeys, jit).await;
+        }
+        let sysvars = self.transaction_processor.sysvars(id)?;
+        self.transaction_processor
+            .load_accounts(id, pubkeys, jit, &sysvars)
+            .await
     }
 
     fn latest_blockhash(&self, id: Uuid) -> Result<Block, String> {
         let block = self.storage.get_latest_block(id)?;
+        self.pending_blocks.lock().unwrap().insert(id);
+        Ok(block)
+    }
 
-        // if self.is_blockhash_valid(id, &block.blockhash)? {
-        //     return Ok(block);
-        // }
-
-        let mut hasher = Sha256::new();
-        hasher.update(block.blockhash.as_ref());
-        let hash_array = hasher.finalize();
-        let current_blockhash = Hash::new_from_array(hash_array.into());
-        let next_block = Block {
-            blockhash: current_blockhash,
-            block_time: block.block_time + 60,
-            previous_blockhash: block.blockhash,
-            block_height: block.block_height + 1,
-            parent_slot: block.block_height,
-            transactions: vec![],
-        };
-        let self_clone = self.clone();
-        self_clone.storage.set_block(id, &next_block).unwrap();
-
-        Ok(block)
+    fn produce_blocks(&self) -> Result<usize, String> {
+        let pending: Vec<Uuid> = self.pending_blocks.lock().unwrap().drain().collect();
+        let mut produced = 0;
+        for id in pending {
+            if let Some(leases) = &self.leases {
+                if !leases.hold(&self.storage, id)? {
+                    self.storage.forward(id, &Forwarded::Block)?;
+                    continue;
+                }
+            }
+            let block = match self.storage.get_latest_block(id) {
+                Ok(block) => block,
+                // Deleted since its blockhash was read
+                Err(_) => continue,
+            };
+            // The next hash only depends on the previous one, so instances racing
+            // on the same blockchain write the same block
+            let mut hasher = Sha256::new();
+            hasher.update(block.blockhash.as_ref());
+            let hash_array = hasher.finalize();
+            let next_block = Block {
+                blockhash: Hash::new_from_array(hash_array.into()),
+                // Never before its parent, so the slot-time mapping stays ordered
+                block_time: (Utc::now().timestamp() as u64).max(block.block_time),
+                previous_blockhash: block.blockhash,
+                block_height: block.block_height + 1,
+                parent_slot: block.block_height,
+                transactions: vec![],
+            };
+            self.storage.set_block(id, &next_block)?;
+            produced += 1;
+        }
+        Ok(produced)
     }
 
     fn current_block(&self, id: Uuid) -> Result<Block, String> {
@@ -743,6 +1431,14 @@ impl<T: Storage + Clone + 'static> SVM<T> for SvmEngine<T> {
         self.storage.get_program_accounts(id, pubkey)
     }
 
+    fn get_nonce_accounts(
+        &self,
+        id: Uuid,
+        authority: &Pubkey,
+    ) -> Result<Vec<(Pubkey, Account)>, String> {
+        self.storage.get_nonce_accounts(id, authority)
+    }
+
     async fn get_token_supply(
         &self,
         id: Uuid,
@@ -773,7 +1469,7 @@ impl<T: Storage + Clone + 'static> SVM<T> for SvmEngine<T> {
         &self,
         id: Uuid,
         signature: &Signature,
-    ) -> Result<Option<(Transaction, TransactionMeta, TransactionStatus)>, String> {
+    ) -> Result<Option<(VersionedTransaction, TransactionMeta, TransactionStatus)>, String> {
         let res = match self.storage.get_transaction(id, signature) {
             Ok(res) => res,
             Err(e) => {
@@ -784,7 +1480,8 @@ impl<T: Storage + Clone + 'static> SVM<T> for SvmEngine<T> {
         if res == None {
             return Ok(None);
         }
-        let (tx, slot, tx_meta, tx_res, created_at) = res.unwrap();
+        let (tx, slot, tx_meta, tx_res, _) = res.unwrap();
+        let current_slot = self.storage.get_latest_block(id)?.block_height;
 
         Ok(Some((
             tx,
@@ -793,11 +1490,57 @@ impl<T: Storage + Clone + 'static> SVM<T> for SvmEngine<T> {
                 slot,
                 confirmations: None,
                 err: tx_res,
-                confirmation_status: Some(tx_confirmation_status(created_at.and_utc())),
+                confirmation_status: Some(self.confirmation_status(id, slot, current_slot)),
             },
         )))
     }
 
+    fn get_signature_status(
+        &self,
+        id: Uuid,
+        signature: &Signature,
+    ) -> Result<Option<TransactionStatus>, String> {
+        let (_, mut statuses) = self.get_signature_statuses(id, &[*signature])?;
+        Ok(statuses.pop().flatten())
+    }
+
+    fn get_signature_statuses(
+        &self,
+        id: Uuid,
+        signatures: &[Signature],
+    ) -> Result<(u64, Vec<Option<TransactionStatus>>), String> {
+        let current_slot = self.storage.get_latest_block(id)?.block_height;
+        let statuses = self.transaction_processor.statuses();
+        let mut found = Vec::with_capacity(signatures.len());
+        for signature in signatures {
+            let status = match statuses.get(id, signature) {
+                Some(status) => status,
+                None => {
+                    let Some((_, slot, _, err, created_at)) =
+                        self.storage.get_transaction(id, signature)?
+                    else {
+                        found.push(None);
+                        continue;
+                    };
+                    let status = CachedStatus {
+                        slot,
+                        err,
+                        created_at: created_at.and_utc(),
+                    };
+                    statuses.insert(id, *signature, status.clone());
+                    status
+                }
+            };
+            found.push(Some(TransactionStatus {
+                slot: status.slot,
+                confirmations: None,
+                err: status.err,
+                confirmation_status: Some(self.confirmation_status(id, status.slot, current_slot)),
+            }));
+        }
+        Ok((current_slot, found))
+    }
+
     fn get_transaction_count(&self, id: Uuid) -> Result<u64, String> {
         self.storage.get_transaction_count(id)
     }
@@ -809,7 +1552,6 @@ impl<T: Storage + Clone + 'static> SVM<T> for SvmEngine<T> {
         jit: bool,
     ) -> Result<String, String> {
         let tx_processor = self.transaction_processor.clone();
-        let tx_clone = raw_tx.clone();
         if raw_tx.signatures.len() < 1 {
             return Err("Transaction must include signatures".to_string());
         }
@@ -821,11 +1563,42 @@ impl<T: Storage + Clone + 'static> SVM<T> for SvmEngine<T> {
         //     return Err("Transaction cannot be replayed".to_string());
         // };
 
-        rt::spawn(async move {
-            tx_processor.queue_transaction(id, tx_clone, jit).await;
-        });
+        // Consumer groups decide which instance processes it
+        if let Some(queue) = &self.queue {
+            if tx_processor.is_draining() {
+                return Err("Engine is shutting down".to_string());
+            }
+            queue.produce(id, &raw_tx, jit)?;
+            return Ok(raw_tx.signatures[0].to_string());
+        }
+        if let Some(leases) = &self.leases {
+            if !leases.hold(&self.storage, id)? {
+                if tx_processor.is_draining() {
+                    return Err("Engine is shutting down".to_string());
+                }
+                self.storage
+                    .forward(id, &Forwarded::transaction(&raw_tx, jit)?)?;
+                return Ok(raw_tx.signatures[0].to_string());
+            }
+        }
+        if tx_processor.is_draining() {
+            return Err("Engine is shutting down".to_string());
+        }
+        self.persist_pending(id, &raw_tx, jit)?;
+        let signature = raw_tx.signatures[0].to_string();
+        let queued = tx_processor
+            .accept_transaction()
+            .and_then(|_| tx_processor.try_queue_transaction(id, raw_tx, jit));
+        if let Err(e) = queued {
+            if self.persist_queue.is_some() {
+                if let Err(e) = self.storage.delete_pending_transaction(id, &signature) {
+                    println!("Failed to delete pending transaction: {}", e);
+                }
+            }
+            return Err(e);
+        }
 
-        Ok(raw_tx.signatures[0].to_string())
+        Ok(signature)
     }
 
     async fn simulate_transaction(
@@ -834,39 +1607,230 @@ impl<T: Storage + Clone + 'static> SVM<T> for SvmEngine<T> {
         raw_tx: VersionedTransaction,
         jit: bool,
     ) -> Result<TransactionMetadata, String> {
-        self.transaction_processor
-            .simulate_transaction(id, raw_tx, jit)
-            .await
+        let simulation = self
+            .transaction_processor
+            .simulate_transaction(id, raw_tx, jit, false);
+        self.simulations.run(id, simulation).await?
+    }
+
+    async fn trace_transaction(
+        &self,
+        id: Uuid,
+        raw_tx: VersionedTransaction,
+        jit: bool,
+    ) -> Result<TransactionMetadata, String> {
+        let simulation = self
+            .transaction_processor
+            .simulate_transaction(id, raw_tx, jit, true);
+        self.simulations.run(id, simulation).await?
     }
 
     async fn airdrop(&self, id: Uuid, pubkey: &Pubkey, lamports: u64) -> Result<String, String> {
-        let mut pre_balance = 0;
-        let existing_account = self.get_account(id, pubkey, false).await?;
-        let mut account = match existing_account {
-            Some(account) => {
-                pre_balance = account.lamports;
-                account
+        self.airdrop_batch(id, &[(*pubkey, lamports)]).await
+    }
+
+    async fn mint_tokens(
+        &self,
+        id: Uuid,
+        mint: Option<Pubkey>,
+        decimals: u8,
+        owner: &Pubkey,
+        amount: u64,
+    ) -> Result<(Pubkey, Pubkey), String> {
+        let (mint_address, mint_account) = match mint {
+            Some(mint_address) => {
+                let mut mint_account = match self.get_account(id, &mint_address, false).await? {
+                    Some(account) => account,
+                    None => return Err(format!("Mint {} not found", mint_address)),
+                };
+                if !is_known_spl_token_id(&mint_account.owner) {
+                    return Err(format!("Account {} is not a token mint", mint_address));
+                }
+                let mut state = StateWithExtensionsMut::<spl_token_2022::state::Mint>::unpack(
+                    &mut mint_account.data,
+                )
+                .map_err(|_| format!("Account {} is not a token mint", mint_address))?;
+                state.base.supply = state
+                    .base
+                    .supply
+                    .checked_add(amount)
+                    .ok_or("Mint supply overflow".to_string())?;
+                state.pack_base();
+                (mint_address, mint_account)
+            }
+            None => {
+                let authority = self.get_identity(id)?;
+                let mut data = vec![0; Mint::LEN];
+                Mint::pack(
+                    Mint {
+                        mint_authority: COption::Some(authority),
+                        supply: amount,
+                        decimals,
+                        is_initialized: true,
+                        freeze_authority: COption::None,
+                    },
+                    &mut data,
+                )
+                .map_err(|e| e.to_string())?;
+                let mint_account = Account {
+                    lamports: self.minimum_balance_for_rent_exemption(Mint::LEN),
+                    data,
+                    owner: spl_token::id(),
+                    executable: false,
+                    rent_epoch: 0,
+                };
+                (Keypair::new().pubkey(), mint_account)
+            }
+        };
+
+        let token_program = mint_account.owner;
+        let token_address = associated_token_address(owner, &mint_address, &token_program);
+        let token_account = match self.get_account(id, &token_address, false).await? {
+            Some(mut token_account) => {
+                let mut state = StateWithExtensionsMut::<spl_token_2022::state::Account>::unpack(
+                    &mut token_account.data,
+                )
+                .map_err(|_| format!("Account {} is not a token account", token_address))?;
+                if state.base.mint != mint_address || state.base.owner != *owner {
+                    return Err(format!(
+                        "Token account {} belongs to a different mint or owner",
+                        token_address
+                    ));
+                }
+                state.base.amount = state
+                    .base
+                    .amount
+                    .checked_add(amount)
+                    .ok_or("Token amount overflow".to_string())?;
+                state.pack_base();
+                token_account
+            }
+            None => self.new_token_account(&mint_address, owner, amount, &token_program)?,
+        };
+
+        self.set_accounts_within_quota(
+            id,
+            vec![(mint_address, mint_account), (token_address, token_account)],
+        )?;
+        Ok((mint_address, token_address))
+    }
+
+    async fn set_token_balance(
+        &self,
+        id: Uuid,
+        mint: &Pubkey,
+        owner: &Pubkey,
+        amount: u64,
+    ) -> Result<Pubkey, String> {
+        let mut mint_account = match self.get_account(id, mint, false).await? {
+            Some(account) => account,
+            None => return Err(format!("Mint {} not found", mint)),
+        };
+        if !is_known_spl_token_id(&mint_account.owner) {
+            return Err(format!("Account {} is not a token mint", mint));
+        }
+        let token_program = mint_account.owner;
+        let token_address = associated_token_address(owner, mint, &token_program);
+        let (previous, token_account) = match self.get_account(id, &token_address, false).await? {
+            Some(mut token_account) => {
+                let mut state = StateWithExtensionsMut::<spl_token_2022::state::Account>::unpack(
+                    &mut token_account.data,
+                )
+                .map_err(|_| format!("Account {} is not a token account", token_address))?;
+                if state.base.mint != *mint || state.base.owner != *owner {
+                    return Err(format!(
+                        "Token account {} belongs to a different mint or owner",
+                        token_address
+                    ));
+                }
+                let previous = state.base.amount;
+                state.base.amount = amount;
+                state.pack_base();
+                (previous, token_account)
             }
-            None => Account {
+            None => (
+                0,
+                self.new_token_account(mint, owner, amount, &token_program)?,
+            ),
+        };
+
+        let mut state =
+            StateWithExtensionsMut::<spl_token_2022::state::Mint>::unpack(&mut mint_account.data)
+                .map_err(|_| format!("Account {} is not a token mint", mint))?;
+        // The account's old balance leaves the supply as the new one joins it
+        state.base.supply = state
+            .base
+            .supply
+            .checked_sub(previous)
+            .and_then(|supply| supply.checked_add(amount))
+            .ok_or("Mint supply overflow".to_string())?;
+        state.pack_base();
+
+        self.set_accounts_within_quota(
+            id,
+            vec![(*mint, mint_account), (token_address, token_account)],
+        )?;
+        Ok(token_address)
+    }
+
+    async fn airdrop_batch(
+        &self,
+        id: Uuid,
+        recipients: &[(Pubkey, u64)],
+    ) -> Result<String, String> {
+        if recipients.is_empty() {
+            return Err("No airdrop recipients".to_string());
+        }
+        if recipients.len() > MAX_AIRDROP_BATCH {
+            return Err(format!(
+                "At most {} recipients can be airdropped at once",
+                MAX_AIRDROP_BATCH
+            ));
+        }
+        // Repeated addresses are funded once with the sum of their amounts
+        let mut totals: Vec<(Pubkey, u64)> = vec![];
+        for (pubkey, lamports) in recipients {
+            match totals.iter_mut().find(|(address, _)| address == pubkey) {
+                Some((_, total)) => {
+                    *total = total
+                        .checked_add(*lamports)
+                        .ok_or("Airdrop amount overflow".to_string())?
+                }
+                None => totals.push((*pubkey, *lamports)),
+            }
+        }
+
+        let pubkeys = totals.iter().map(|(pubkey, _)| pubkey).collect::<Vec<_>>();
+        let existing_accounts = self.get_multiple_accounts(id, &pubkeys, false).await?;
+        let mut pre_balances = vec![];
+        let mut accounts = vec![];
+        for ((pubkey, lamports), existing_account) in totals.iter().zip(existing_accounts) {
+            let mut account = existing_account.unwrap_or(Account {
                 lamports: 0,
                 data: vec![],
                 owner: system_program::id(),
                 executable: false,
                 rent_epoch: 100000000,
-            },
-        };
-        account.lamports = account.lamports + lamports;
-        self.storage.set_account(id, pubkey, account, None)?;
+            });
+            pre_balances.push(account.lamports);
+            account.lamports = account
+                .lamports
+                .checked_add(*lamports)
+                .ok_or("Airdrop amount overflow".to_string())?;
+            accounts.push((*pubkey, account));
+        }
+        self.set_accounts_within_quota(id, accounts)?;
 
         let current_block = self.get_latest_block(id)?;
         let signer_pubkey = Pubkey::new_unique();
         let signature = Signature::new_unique();
         let raw_tx = Transaction::new_with_payer(
-            &[system_instruction::transfer(
-                &signer_pubkey,
-                pubkey,
-                lamports,
-            )],
+            &totals
+                .iter()
+                .map(|(pubkey, lamports)| {
+                    system_instruction::transfer(&signer_pubkey, pubkey, *lamports)
+                })
+                .collect::<Vec<_>>(),
             Some(&signer_pubkey),
         );
         let versioned_message = VersionedMessage::Legacy(raw_tx.message);
@@ -883,56 +1847,64 @@ impl<T: Storage + Clone + 'static> SVM<T> for SvmEngine<T> {
             Loader::new(self.storage.clone(), id, self.sysvar_cache.clone()),
             &ReservedAccountKeys::empty_key_set(),
         )
-        .unwrap();
+        .map_err(|e| e.to_string())?;
         let mut return_data = TransactionReturnData::default();
         return_data.program_id = system_program::id();
 
+        let total_lamports: u64 = totals.iter().map(|(_, lamports)| lamports).sum();
+        let signer_balance = 100_000_000_000_000_000;
+        let mut pre_accounts = vec![(
+            signer_pubkey,
+            AccountSharedData::new(signer_balance, 0, &system_program::id()),
+        )];
+        let mut post_accounts = vec![(
+            signer_pubkey,
+            AccountSharedData::new(
+                signer_balance.saturating_sub(total_lamports),
+                0,
+                &system_program::id(),
+            ),
+        )];
+        for ((pubkey, lamports), pre_balance) in totals.iter().zip(pre_balances) {
+            pre_accounts.push((
+                *pubkey,
+                AccountSharedData::new(pre_balance, 0, &system_program::id()),
+            ));
+            post_accounts.push((
+                *pubkey,
+                AccountSharedData::new(pre_balance + lamports, 0, &system_program::id()),
+            ));
+        }
+        for accounts in [&mut pre_accounts, &mut post_accounts] {
+            accounts.push((
+                system_program::id(),
+                AccountSharedData::new(1_000_000_000, 0, &system_program::id()),
+            ));
+        }
+
         let tx = TransactionMetadata {
             signature,
             err: None,
-            logs: vec![
-                "Program 11111111111111111111111111111111 invoke [1]".to_string(),
-                "Program 11111111111111111111111111111111 success".to_string(),
-            ],
+            logs: totals
+                .iter()
+                .flat_map(|_| {
+                    [
+                        "Program 11111111111111111111111111111111 invoke [1]".to_string(),
+                        "Program 11111111111111111111111111111111 success".to_string(),
+                    ]
+                })
+                .collect(),
             inner_instructions: vec![],
             compute_units_consumed: 0,
             return_data: return_data,
             tx: sanitized_tx,
             current_block,
-            pre_accounts: vec![
-                (
-                    signer_pubkey.clone(),
-                    AccountSharedData::new(100_000_000_000_000_000, 0, &system_program::id()),
-                ),
-                (
-                    pubkey.clone(),
-                    AccountSharedData::new(pre_balance, 0, &system_program::id()),
-                ),
-                (
-                    system_program::id(),
-                    AccountSharedData::new(1_000_000_000, 0, &system_program::id()),
-                ),
-            ],
-            post_accounts: vec![
-                (
-                    signer_pubkey.clone(),
-                    AccountSharedData::new(
-                        100_000_000_000_000_000 - lamports,
-                        0,
-                        &system_program::id(),
-                    ),
-                ),
-                (
-                    pubkey.clone(),
-                    AccountSharedData::new(pre_balance + lamports, 0, &system_program::id()),
-                ),
-                (
-                    system_program::id(),
-                    AccountSharedData::new(1_000_000_000, 0, &system_program::id()),
-                ),
-            ],
+            pre_accounts,
+            post_accounts,
             pre_token_balances: None,
             post_token_balances: None,
+            trace: None,
+            sponsored_fee: 0,
         };
 
         self.storage.save_transaction(id, &tx)?;
@@ -951,9 +1923,763 @@ impl<T: Storage + Clone + 'static> SVM<T> for SvmEngine<T> {
         };
         (program_id, account)
     }
+
+    fn add_upgradeable_program(
+        &self,
+        program_id: Pubkey,
+        program_bytes: &[u8],
+        upgrade_authority: Option<Pubkey>,
+    ) -> Result<Vec<(Pubkey, Account)>, String> {
+        let accounts = loader::upgradeable_program_accounts(
+            &program_id,
+            program_bytes,
+            upgrade_authority,
+            &self.rent,
+        )?;
+        Ok(accounts.into())
+    }
+
+    async fn clone_program(&self, id: Uuid, program_id: &Pubkey) -> Result<Vec<Pubkey>, String> {
+        if BUILTINS
+            .iter()
+            .any(|builtin| builtin.program_id == *program_id)
+        {
+            return Err(format!(
+                "Program id {} is a builtin program, and can't be overwritten",
+                program_id
+            ));
+        }
+        let program = match self
+            .storage
+            .get_remote_accounts(&[*program_id])
+            .await?
+            .pop()
+        {
+            Some(Some(program)) => program,
+            _ => return Err(format!("Program {} not found", program_id)),
+        };
+        if !program.executable {
+            return Err(format!("Account {} is not a program", program_id));
+        }
+
+        let mut accounts = vec![];
+        if bpf_loader_upgradeable::check_id(&program.owner) {
+            let programdata_address = match program.state() {
+                Ok(UpgradeableLoaderState::Program {
+                    programdata_address,
+                }) => programdata_address,
+                _ => return Err(format!("Invalid upgradeable program {}", program_id)),
+            };
+            let programdata = match self
+                .storage
+                .get_remote_accounts(&[programdata_address])
+                .await?
+                .pop()
+            {
+                Some(Some(programdata)) => programdata,
+                _ => {
+                    return Err(format!(
+                        "Program data {} of {} not found",
+                        programdata_address, program_id
+                    ))
+                }
+            };
+            accounts.push((programdata_address, programdata));
+        } else if !bpf_loader::check_id(&program.owner)
+            && !bpf_loader_deprecated::check_id(&program.owner)
+        {
+            return Err(format!(
+                "Program {} is owned by unsupported loader {}",
+                program_id, program.owner
+            ));
+        }
+        accounts.insert(0, (*program_id, program));
+
+        let addresses = accounts.iter().map(|(address, _)| *address).collect();
+        self.set_accounts_within_quota(id, accounts)?;
+        Ok(addresses)
+    }
+
+    async fn clone_bundles(&self, id: Uuid, bundles: &[String]) -> Result<Vec<Pubkey>, String> {
+        let mut addresses = vec![];
+        for bundle in bundles {
+            for program_id in bundle_programs(bundle)? {
+                addresses.extend(self.clone_program(id, program_id).await?);
+            }
+        }
+        Ok(addresses)
+    }
+
+    async fn set_mint_authorities(
+        &self,
+        id: Uuid,
+        mint: &Pubkey,
+        mint_authority: Option<Pubkey>,
+        freeze_authority: Option<Pubkey>,
+    ) -> Result<(), String> {
+        if mint_authority.is_none() && freeze_authority.is_none() {
+            return Err("No authority to set".to_string());
+        }
+        // Cloned mainnet mints may not be local yet
+        let jit = self.storage.get_blockchain(id)?.jit;
+        let mut account = match self.get_account(id, mint, jit).await? {
+            Some(account) => account,
+            None => return Err(format!("Mint {} not found", mint)),
+        };
+        if !is_known_spl_token_id(&account.owner) {
+            return Err(format!("Account {} is not a token mint", mint));
+        }
+        set_mint_authorities(&mut account.data, mint_authority, freeze_authority)
+            .map_err(|_| format!("Account {} is not a token mint", mint))?;
+        self.storage.set_accounts(id, vec![(*mint, account)])
+    }
+
+    async fn set_upgrade_authority(
+        &self,
+        id: Uuid,
+        program_id: &Pubkey,
+        authority: &Pubkey,
+    ) -> Result<Pubkey, String> {
+        let jit = self.storage.get_blockchain(id)?.jit;
+        let program = match self.get_account(id, program_id, jit).await? {
+            Some(program) => program,
+            None => return Err(format!("Program {} not found", program_id)),
+        };
+        let programdata_address =
+            match bpf_loader_upgradeable::check_id(&program.owner).then(|| program.state()) {
+                Some(Ok(UpgradeableLoaderState::Program {
+                    programdata_address,
+                })) => programdata_address,
+                _ => return Err(format!("Program {} is not upgradeable", program_id)),
+            };
+        let mut programdata = match self.get_account(id, &programdata_address, jit).await? {
+            Some(programdata) => programdata,
+            None => {
+                return Err(format!(
+                    "Program data {} of {} not found",
+                    programdata_address, program_id
+                ))
+            }
+        };
+        loader::set_upgrade_authority(&mut programdata, Some(*authority))?;
+        self.storage
+            .set_accounts(id, vec![(programdata_address, programdata)])?;
+        Ok(programdata_address)
+    }
+
+    async fn top_up(&self, top_up: &DbTopUp) -> Result<u64, String> {
+        let id = top_up.blockchain;
+        let address = top_up.address()?;
+        let target = top_up.target()?;
+        let Some(mint) = top_up.mint()? else {
+            let balance = self
+                .get_account(id, &address, false)
+                .await?
+                .map(|account| account.lamports)
+                .unwrap_or(0);
+            if balance >= target {
+                return Ok(0);
+            }
+            self.airdrop(id, &address, target - balance).await?;
+            return Ok(target - balance);
+        };
+
+        let token_program = match self.get_account(id, &mint, false).await? {
+            Some(mint_account) => mint_account.owner,
+            None => return Err(format!("Mint {} not found", mint)),
+        };
+        let token_address = associated_token_address(&address, &mint, &token_program);
+        let balance = match self.get_account(id, &token_address, false).await? {
+            Some(mut token_account) => {
+                StateWithExtensionsMut::<spl_token_2022::state::Account>::unpack(
+                    &mut token_account.data,
+                )
+                .map_err(|_| format!("Account {} is not a token account", token_address))?
+                .base
+                .amount
+            }
+            None => 0,
+        };
+        if balance >= target {
+            return Ok(0);
+        }
+        self.mint_tokens(id, Some(mint), 0, &address, target - balance)
+            .await?;
+        Ok(target - balance)
+    }
+
+    async fn run_top_ups(&self) -> Result<usize, String> {
+        let now = Utc::now().naive_utc();
+        let mut topped_up = 0;
+        for top_up in self.storage.get_all_top_ups()? {
+            if !top_up.is_due(now) || !self.storage.claim_top_up(top_up.id, top_up.last_run, now)? {
+                continue;
+            }
+            match self.top_up(&top_up).await {
+                Ok(0) => {}
+                Ok(_) => topped_up += 1,
+                // One broken top-up shouldn't hold up the others
+                Err(e) => println!("Failed to run top-up {}: {}", top_up.id, e),
+            }
+        }
+        Ok(topped_up)
+    }
+
+    async fn fetch_remote_accounts(
+        &self,
+        addresses: &[Pubkey],
+    ) -> Result<Vec<(Pubkey, Account)>, String> {
+        if let Some(address) = addresses.iter().find(|address| {
+            is_engine_sysvar(address)
+                || BUILTINS
+                    .iter()
+                    .any(|builtin| builtin.program_id == **address)
+        }) {
+            return Err(format!(
+                "Account {} is provided by the engine and can't be cloned",
+                address
+            ));
+        }
+
+        let mut accounts = Vec::with_capacity(addresses.len());
+        let mut programdata_addresses = vec![];
+        for (address, account) in addresses
+            .iter()
+            .zip(self.storage.get_remote_accounts(addresses).await?)
+        {
+            let Some(account) = account else {
+                return Err(format!("Account {} not found", address));
+            };
+            if account.executable && bpf_loader_upgradeable::check_id(&account.owner) {
+                if let Ok(UpgradeableLoaderState::Program {
+                    programdata_address,
+                }) = account.state()
+                {
+                    programdata_addresses.push(programdata_address);
+                }
+            }
+            accounts.push((*address, account));
+        }
+
+        // Upgradeable programs don't run without their program data
+        programdata_addresses.retain(|address| !addresses.contains(address));
+        programdata_addresses.sort();
+        programdata_addresses.dedup();
+        if programdata_addresses.is_empty() {
+            return Ok(accounts);
+        }
+        for (address, programdata) in programdata_addresses.iter().zip(
+            self.storage
+                .get_remote_accounts(&programdata_addresses)
+                .await?,
+        ) {
+            match programdata {
+                Some(programdata) => accounts.push((*address, programdata)),
+                None => return Err(format!("Program data {} not found", address)),
+            }
+        }
+        Ok(accounts)
+    }
 }
 
 impl<T: Storage + Clone + 'static> SvmEngine<T> {
+    /// Overrides `features.anchor_compat`, e.g. for an embedded engine.
+    pub fn with_anchor_compat(mut self, enabled: bool) -> Self {
+        self.anchor_compat = enabled;
+        self
+    }
+
+    /// Overrides `queue.capacity` and `queue.idle_timeout_ms`, before any
+    /// transaction is sent.
+    pub fn with_queue_limits(mut self, capacity: usize, idle_timeout: Option<Duration>) -> Self {
+        Arc::make_mut(&mut self.transaction_processor).set_queue_limits(capacity, idle_timeout);
+        self
+    }
+
+    /// Overrides `limits.execution_timeout_ms`, before any transaction is sent.
+    pub fn with_execution_timeout(mut self, timeout: Option<Duration>) -> Self {
+        Arc::make_mut(&mut self.transaction_processor).set_execution_timeout(timeout);
+        self
+    }
+
+    /// Overrides `limits.simulation_workers` and `limits.simulation_queue_depth`.
+    pub fn with_simulation_limits(mut self, workers: usize, depth: usize) -> Self {
+        self.simulations = Arc::new(SimulationPools::new(workers, depth));
+        self
+    }
+
+    /// Overrides whether `jobs.block_interval_ms` is set, i.e. whether something
+    /// calls `produce_blocks` so transactions finalize. Without it every
+    /// transaction reads as finalized.
+    pub fn with_block_production(mut self, enabled: bool) -> Self {
+        self.produces_blocks = enabled;
+        self
+    }
+
+    /// `Storage::set_accounts`, failing instead when the accounts it creates would
+    /// take the blockchain past its team's account quota.
+    fn set_accounts_within_quota(
+        &self,
+        id: Uuid,
+        accounts: Vec<(Pubkey, Account)>,
+    ) -> Result<(), String> {
+        let team_id = self.storage.get_blockchain(id)?.team_id;
+        let addresses: Vec<Pubkey> = accounts.iter().map(|(address, _)| *address).collect();
+        if let Some(message) = account_quota_exceeded(&self.storage, team_id, id, &addresses)? {
+            return Err(message);
+        }
+        self.storage.set_accounts(id, accounts)
+    }
+
+    /// Overrides `programs.directory`, where genesis program builds are loaded from.
+    pub fn with_programs_directory(mut self, directory: Option<PathBuf>) -> Self {
+        self.programs_directory = directory;
+        self
+    }
+
+    /// Whether programs can be deployed with loader transactions sent over RPC.
+    pub fn anchor_compat(&self) -> bool {
+        self.anchor_compat
+    }
+
+    /// Coordinates with other instances through blockchain leases, as `instance`.
+    pub fn with_leases(mut self, instance: Uuid, ttl: Duration) -> Self {
+        self.leases = Some(Arc::new(Leases::new(instance, ttl)));
+        self
+    }
+
+    pub fn leases(&self) -> Option<&Leases> {
+        self.leases.as_deref()
+    }
+
+    /// Queues sent transactions on Kafka, see `QueueSettings`. Consumers are
+    /// started separately, with `KafkaQueue::start`.
+    pub fn with_kafka_queue(mut self, queue: KafkaQueue) -> Self {
+        self.queue = Some(queue);
+        self
+    }
+
+    pub fn is_draining(&self) -> bool {
+        self.transaction_processor.is_draining()
+    }
+
+    /// Records a request for the blockchain, resuming it if it was paused.
+    pub fn touch(&self, id: Uuid) {
+        if self.activity.touch(id) {
+            println!("Resumed blockchain {}", id);
+        }
+    }
+
+    pub fn is_paused(&self, id: Uuid) -> bool {
+        self.activity.is_paused(id)
+    }
+
+    /// Pauses the blockchains idle here for longer than their team's
+    /// `auto_pause_hours`: their queue worker stops, their lease is given up and
+    /// no blocks are produced until a request resumes them. Returns how many
+    /// were paused.
+    pub fn pause_idle_blockchains(&self) -> Result<usize, String> {
+        let mut policies: HashMap<Uuid, Option<Duration>> = HashMap::new();
+        let mut paused = 0;
+        for (id, idle) in self.activity.idle(Duration::ZERO) {
+            let blockchain = match self.storage.get_blockchain(id) {
+                Ok(blockchain) => blockchain,
+                // Deleted elsewhere, tracked again if it's still used
+                Err(_) => {
+                    self.activity.forget(id);
+                    continue;
+                }
+            };
+            let pause_after = match policies.get(&blockchain.team_id) {
+                Some(pause_after) => *pause_after,
+                None => {
+                    let pause_after = self
+                        .storage
+                        .get_team(blockchain.team_id)?
+                        .and_then(|team| team.auto_pause_hours)
+                        .map(|hours| Duration::from_secs(hours.max(0) as u64 * 60 * 60));
+                    policies.insert(blockchain.team_id, pause_after);
+                    pause_after
+                }
+            };
+            if pause_after.is_none_or(|pause_after| idle < pause_after) {
+                continue;
+            }
+            if !self.transaction_processor.close_queue(id) {
+                continue;
+            }
+            if let Some(leases) = &self.leases {
+                // Work was forwarded meanwhile, so it isn't idle
+                if !leases.release(&self.storage, id)? {
+                    continue;
+                }
+            }
+            self.pending_blocks.lock().unwrap().remove(&id);
+            self.activity.pause(id);
+            paused += 1;
+        }
+        Ok(paused)
+    }
+
+    /// Heartbeats of the blockchains this instance serves: the ones it holds the
+    /// lease of when coordinating through leases, otherwise the ones it served
+    /// and didn't pause.
+    pub fn heartbeats(&self) -> Vec<PubSubHeartbeat> {
+        let ids: Vec<Uuid> = match &self.leases {
+            Some(leases) => leases.held(),
+            None => self
+                .activity
+                .idle(Duration::ZERO)
+                .into_iter()
+                .map(|(id, _)| id)
+                .collect(),
+        };
+        let timestamp = Utc::now().timestamp_millis();
+        ids.into_iter()
+            .filter_map(|id| {
+                // Deleted since it was served
+                let block = self.storage.get_latest_block(id).ok()?;
+                Some(PubSubHeartbeat {
+                    blockchain: id,
+                    slot: block.block_height.into(),
+                    block_height: block.block_height.into(),
+                    block_time: block.block_time,
+                    timestamp,
+                })
+            })
+            .collect()
+    }
+
+    /// Publishes `heartbeats` to the geyser topic, returns how many were sent.
+    pub fn publish_heartbeats(&self) -> usize {
+        let heartbeats = self.heartbeats();
+        self.storage.publish_heartbeats(&heartbeats);
+        heartbeats.len()
+    }
+
+    /// The commitment `slot` reached while `current_slot` is being processed.
+    /// Blocks keep coming until it's finalized, like they do while clients read
+    /// blockhashes, so confirmation loops make progress. Without a ticker no block
+    /// would come, so everything reads as finalized.
+    fn confirmation_status(
+        &self,
+        id: Uuid,
+        slot: u64,
+        current_slot: u64,
+    ) -> TransactionConfirmationStatus {
+        if !self.produces_blocks {
+            return TransactionConfirmationStatus::Finalized;
+        }
+        let status = tx_confirmation_status(slot, current_slot);
+        if !matches!(status, TransactionConfirmationStatus::Finalized) {
+            self.pending_blocks.lock().unwrap().insert(id);
+        }
+        status
+    }
+
+    /// Overrides `features.persist_queue` and `cluster.instance_id`, queued
+    /// transactions are persisted under `instance` when set.
+    pub fn with_persist_queue(mut self, instance: Option<Uuid>) -> Self {
+        self.persist_queue = instance;
+        self
+    }
+
+    fn persist_pending(
+        &self,
+        id: Uuid,
+        raw_tx: &VersionedTransaction,
+        jit: bool,
+    ) -> Result<(), String> {
+        let Some(instance) = self.persist_queue else {
+            return Ok(());
+        };
+        let pending = DbPendingTransaction::new(id, Some(instance), raw_tx, jit)?;
+        self.storage.save_pending_transaction(&pending)
+    }
+
+    /// Queues the transactions this instance accepted but never saved, e.g. before
+    /// a crash, in the order they were sent. Returns how many were queued.
+    /// Rows without an instance are never resumed, every instance would run them.
+    pub async fn resume_pending_transactions(&self) -> Result<usize, String> {
+        let Some(instance) = self.persist_queue else {
+            return Ok(0);
+        };
+        let mut resumed = 0;
+        for pending in self.storage.get_pending_transactions(instance)? {
+            let id = pending.blockchain;
+            let raw_tx = match pending.decode() {
+                Ok(raw_tx) => raw_tx,
+                Err(e) => {
+                    println!("Dropping pending transaction {}: {}", pending.signature, e);
+                    self.storage
+                        .delete_pending_transaction(id, &pending.signature)?;
+                    continue;
+                }
+            };
+            // Written before the crash, only the pending row was left behind
+            let signature = Signature::from_str(&pending.signature).map_err(|e| e.to_string())?;
+            if self.storage.get_transaction(id, &signature)?.is_some() {
+                self.storage
+                    .delete_pending_transaction(id, &pending.signature)?;
+                continue;
+            }
+            if let Some(leases) = &self.leases {
+                if !leases.hold(&self.storage, id)? {
+                    // The holder records it again when it takes it
+                    self.storage
+                        .forward(id, &Forwarded::transaction(&raw_tx, pending.jit)?)?;
+                    self.storage
+                        .delete_pending_transaction(id, &pending.signature)?;
+                    continue;
+                }
+            }
+            self.transaction_processor.accept_transaction()?;
+            self.transaction_processor
+                .queue_transaction(id, raw_tx, pending.jit)
+                .await;
+            resumed += 1;
+        }
+        Ok(resumed)
+    }
+
+    /// Processes a transaction taken off the Kafka queue, failing once draining.
+    pub async fn process_queued(
+        &self,
+        id: Uuid,
+        raw_tx: VersionedTransaction,
+        jit: bool,
+    ) -> Result<(), String> {
+        self.transaction_processor
+            .process_external(id, raw_tx, jit)
+            .await
+    }
+
+    /// Queues the transactions and blocks other instances forwarded for the
+    /// blockchains this one holds, returns how many were taken.
+    pub async fn run_forwarded(&self) -> Result<usize, String> {
+        let leases = match &self.leases {
+            Some(leases) => leases,
+            None => return Ok(0),
+        };
+        // Left for the next holder once leases are released
+        if self.transaction_processor.is_draining() {
+            return Ok(0);
+        }
+        let work = self
+            .storage
+            .take_forwarded(&leases.held(), FORWARDED_BATCH)?;
+        let taken = work.len();
+        let mut work = work.into_iter();
+        while let Some((id, forwarded)) = work.next() {
+            leases.touch(id);
+            self.touch(id);
+            if let Err(e) = self.run_forwarded_item(id, &forwarded).await {
+                // Already off the list, the senders were told it's accepted
+                self.return_forwarded(std::iter::once((id, forwarded)).chain(work));
+                return Err(e);
+            }
+        }
+        Ok(taken)
+    }
+
+    async fn run_forwarded_item(&self, id: Uuid, forwarded: &Forwarded) -> Result<(), String> {
+        match forwarded {
+            Forwarded::Transaction { transaction, jit } => {
+                let raw_tx = match Forwarded::decode_transaction(transaction) {
+                    Ok(raw_tx) => raw_tx,
+                    Err(e) => {
+                        println!("Dropping forwarded transaction: {}", e);
+                        return Ok(());
+                    }
+                };
+                self.persist_pending(id, &raw_tx, *jit)?;
+                if let Err(e) = self.transaction_processor.accept_transaction() {
+                    if self.persist_queue.is_some() {
+                        let signature = raw_tx.signatures[0].to_string();
+                        if let Err(e) = self.storage.delete_pending_transaction(id, &signature) {
+                            println!("Failed to delete pending transaction: {}", e);
+                        }
+                    }
+                    return Err(e);
+                }
+                self.transaction_processor
+                    .queue_transaction(id, raw_tx, *jit)
+                    .await;
+            }
+            Forwarded::Block => {
+                self.pending_blocks.lock().unwrap().insert(id);
+            }
+        }
+        Ok(())
+    }
+
+    /// Puts forwarded work back on each blockchain's list, in the order it was taken.
+    fn return_forwarded(&self, work: impl Iterator<Item = (Uuid, Forwarded)>) {
+        let mut by_blockchain: Vec<(Uuid, Vec<Forwarded>)> = vec![];
+        for (id, forwarded) in work {
+            match by_blockchain.iter_mut().find(|(other, _)| *other == id) {
+                Some((_, items)) => items.push(forwarded),
+                None => by_blockchain.push((id, vec![forwarded])),
+            }
+        }
+        for (id, items) in by_blockchain {
+            if let Err(e) = self.storage.return_forwarded(id, &items) {
+                println!("Dropping {} forwarded items of {}: {}", items.len(), id, e);
+            }
+        }
+    }
+
+    /// Renews held leases and gives up idle ones, returns how many are held.
+    pub fn renew_leases(&self) -> usize {
+        let leases = match &self.leases {
+            Some(leases) => leases,
+            None => return 0,
+        };
+        let busy: HashSet<Uuid> = self
+            .transaction_processor
+            .queue_stats()
+            .into_iter()
+            .filter(|queue| queue.queued > 0 || queue.processing.is_some())
+            .map(|queue| queue.blockchain)
+            .collect();
+        let pending = self.pending_blocks.lock().unwrap().clone();
+        leases.renew(&self.storage, |id| {
+            busy.contains(&id) || pending.contains(&id)
+        })
+    }
+
+    /// An initialized associated token account. Token-2022 ones get `ImmutableOwner`
+    /// like the associated token program gives them, but not the extensions their
+    /// mint may ask for.
+    fn new_token_account(
+        &self,
+        mint: &Pubkey,
+        owner: &Pubkey,
+        amount: u64,
+        token_program: &Pubkey,
+    ) -> Result<Account, String> {
+        let data = if *token_program == spl_token_2022::id() {
+            let len =
+                ExtensionType::try_calculate_account_len::<spl_token_2022::state::Account>(&[
+                    ExtensionType::ImmutableOwner,
+                ])
+                .map_err(|e| e.to_string())?;
+            let mut data = vec![0; len];
+            let mut state =
+                StateWithExtensionsMut::<spl_token_2022::state::Account>::unpack_uninitialized(
+                    &mut data,
+                )
+                .map_err(|e| e.to_string())?;
+            state
+                .init_extension::<ImmutableOwner>(true)
+                .map_err(|e| e.to_string())?;
+            state.base = spl_token_2022::state::Account {
+                mint: *mint,
+                owner: *owner,
+                amount,
+                state: spl_token_2022::state::AccountState::Initialized,
+                ..spl_token_2022::state::Account::default()
+            };
+            state.pack_base();
+            state.init_account_type().map_err(|e| e.to_string())?;
+            data
+        } else {
+            let mut data = vec![0; SplAccount::LEN];
+            SplAccount::pack(
+                SplAccount {
+                    mint: *mint,
+                    owner: *owner,
+                    amount,
+                    state: AccountState::Initialized,
+                    ..SplAccount::default()
+                },
+                &mut data,
+            )
+            .map_err(|e| e.to_string())?;
+            data
+        };
+        Ok(Account {
+            lamports: self.minimum_balance_for_rent_exemption(data.len()),
+            data,
+            owner: *token_program,
+            executable: false,
+            rent_epoch: 0,
+        })
+    }
+
+    /// Stores the blockchain with its genesis block, then writes the accounts every
+    /// blockchain starts with.
+    fn provision_blockchain(
+        &self,
+        blockchain: Blockchain,
+        defer_accounts: bool,
+    ) -> Result<Uuid, String> {
+        let id = self.storage.set_blockchain(&blockchain)?;
+        self.set_genesis_block(id)?;
+        if !defer_accounts {
+            self.storage
+                .set_accounts(id, self.genesis_accounts(&blockchain)?)?;
+            return Ok(id);
+        }
+
+        // Building the SPL program accounts reads their builds, so it's deferred too
+        let svm = self.clone();
+        rt::task::spawn_blocking(move || {
+            let uploaded = svm.genesis_accounts(&blockchain).and_then(|accounts| {
+                // Deleted meanwhile, its accounts would outlive it
+                svm.storage.get_blockchain(id)?;
+                svm.storage.set_accounts(id, accounts)
+            });
+            let status = match uploaded {
+                Ok(()) => AccountsStatus::Ready,
+                Err(e) => {
+                    println!("Failed to initialize accounts of blockchain {}: {}", id, e);
+                    AccountsStatus::Failed
+                }
+            };
+            let update = DbBlockchainUpdate {
+                accounts_status: Some(status),
+                ..Default::default()
+            };
+            if let Err(e) = svm.storage.update_blockchain(id, &update) {
+                println!("Failed to set accounts status of blockchain {}: {}", id, e);
+            }
+        });
+        Ok(id)
+    }
+
+    /// Accounts every new blockchain starts with: its config's accounts, sysvars,
+    /// the funded airdrop identity, builtins and SPL programs.
+    fn genesis_accounts(&self, blockchain: &Blockchain) -> Result<Vec<(Pubkey, Account)>, String> {
+        let mut accounts_to_upload: Vec<(Pubkey, Account)> = vec![];
+        if let Some(config_id) = blockchain.config {
+            accounts_to_upload.extend(self.storage.get_config_accounts(config_id)?);
+        }
+
+        accounts_to_upload.extend(self.get_sysvars());
+        accounts_to_upload.push((
+            blockchain.airdrop_keypair.pubkey(),
+            Account {
+                lamports: 1_000_000u64.wrapping_mul(LAMPORTS_PER_SOL),
+                data: vec![],
+                owner: system_program::id(),
+                executable: false,
+                rent_epoch: 100000000000,
+            },
+        ));
+        BUILTINS.iter().for_each(|builtint| {
+            let mut account: Account =
+                native_loader::create_loadable_account_for_test(builtint.name).into();
+            account.rent_epoch = 1000000;
+            accounts_to_upload.push((builtint.program_id, account));
+        });
+        accounts_to_upload.extend(generate_spl_programs(
+            self,
+            self.programs_directory.as_deref(),
+        ));
+        Ok(accounts_to_upload)
+    }
+
     /// Sets the sysvar to the test environment.
     pub fn set_sysvar<S>(&mut self, sysvar: &S)
     where
@@ -994,6 +2720,31 @@ impl<T: Storage + Clone + 'static> SvmEngine<T> {
         sysvars.push(self.get_sysvar(&StakeHistory::default()));
         sysvars
     }
+
+    /// Writes the genesis block, its hash is derived from the blockchain id.
+    fn set_genesis_block(&self, id: Uuid) -> Result<(), String> {
+        let mut hasher = Sha256::new();
+        hasher.update(id.as_bytes());
+        let hash_array = hasher.finalize();
+        let hash = Hash::new_from_array(hash_array.into());
+        match self.storage.set_block(
+            id,
+            &Block {
+                blockhash: hash,
+                block_time: Utc::now().timestamp() as u64,
+                previous_blockhash: Hash::default(),
+                block_height: 0,
+                parent_slot: 0,
+                transactions: vec![],
+            },
+        ) {
+            Ok(_) => Ok(()),
+            Err(e) => {
+                println!("Error setting genesis block: {:?}", e);
+                Err(e)
+            }
+        }
+    }
 }
 
 pub fn construct_instructions_account(message: &SanitizedMessage) -> AccountSharedData {
@@ -1143,26 +2894,29 @@ pub fn get_system_account_kind(account: &AccountSharedData) -> Option<SystemAcco
     }
 }
 
-pub struct AccountsDB<'a> {
-    accounts: HashMap<&'a Pubkey, Option<Account>>,
+#[derive(Clone)]
+pub struct AccountsDB {
+    // Account data sits behind an `Arc`, lookups during execution share it
+    accounts: HashMap<Pubkey, Option<AccountSharedData>>,
 }
 
-impl<'a> AccountsDB<'a> {
-    fn new(accounts: HashMap<&'a Pubkey, Option<Account>>) -> Self {
-        AccountsDB { accounts }
+impl AccountsDB {
+    fn new(accounts: HashMap<&Pubkey, Option<Account>>) -> Self {
+        AccountsDB {
+            accounts: accounts
+                .into_iter()
+                .map(|(pubkey, account)| (*pubkey, account.map(AccountSharedData::from)))
+                .collect(),
+        }
     }
 
     fn get_account(&self, pubkey: &Pubkey) -> Option<AccountSharedData> {
-        match self.accounts.get(pubkey) {
-            Some(account) => match account {
-                Some(account) => Some(AccountSharedData::from(account.to_owned())),
-                None => None,
-            },
-            None => None,
-        }
+        self.accounts.get(pubkey).cloned().flatten()
     }
 }
 
+type TransactionAccounts = Vec<(Pubkey, AccountSharedData)>;
+
 fn execute_tx_helper(
     sanitized_tx: SanitizedTransaction,
     ctx: TransactionContext,
@@ -1170,23 +2924,33 @@ fn execute_tx_helper(
     Signature,
     solana_sdk::transaction_context::TransactionReturnData,
     InnerInstructionsList,
-    Vec<(Pubkey, AccountSharedData)>,
+    TransactionAccounts,
+    TransactionAccounts,
 ) {
     let signature = sanitized_tx.signature().to_owned();
     let inner_instructions = inner_instructions_list_from_instruction_trace(&ctx);
     let ExecutionRecord {
-        accounts,
+        mut accounts,
         return_data,
         touched_account_count: _,
         accounts_resize_delta: _,
     } = ctx.into();
     let msg = sanitized_tx.message();
+    // Loader accounts the runtime added come after the transaction's own
+    accounts.truncate(msg.account_keys().len());
     let post_accounts = accounts
-        .into_iter()
+        .iter()
         .enumerate()
-        .filter_map(|(idx, pair)| msg.is_writable(idx).then_some(pair))
+        .filter(|(idx, _)| msg.is_writable(*idx))
+        .map(|(_, pair)| pair.clone())
         .collect();
-    (signature, return_data, inner_instructions, post_accounts)
+    (
+        signature,
+        return_data,
+        inner_instructions,
+        post_accounts,
+        accounts,
+    )
 }
 
 /// Pulled verbatim from `solana-svm` crate, `transaction_processor.rs`
@@ -1311,8 +3075,83 @@ impl<T: Storage + Clone + 'static> Loader<T> {
     }
 }
 
-pub fn tx_confirmation_status(_time: chrono::DateTime<Utc>) -> TransactionConfirmationStatus {
-    return TransactionConfirmationStatus::Finalized;
+/// Message for creating `addresses` when that would take the blockchain past its
+/// team's account quota. Accounts that already exist are only overwritten, so they
+/// don't count.
+pub fn account_quota_exceeded<T: Storage>(
+    storage: &T,
+    team_id: Uuid,
+    id: Uuid,
+    addresses: &[Pubkey],
+) -> Result<Option<String>, String> {
+    let Some(max_accounts) = storage
+        .get_team(team_id)?
+        .and_then(|team| team.max_accounts)
+    else {
+        return Ok(None);
+    };
+    let max_accounts = max_accounts.max(0) as u64;
+    let existing = storage.get_storage_usage(id)?.accounts;
+    let mut created = addresses.len() as u64;
+    if existing + created > max_accounts {
+        created = storage
+            .get_accounts(id, &addresses.iter().collect())?
+            .iter()
+            .filter(|account| account.is_none())
+            .count() as u64;
+    }
+    if existing + created > max_accounts {
+        return Ok(Some(format!(
+            "Your team's quota is {} accounts per blockchain, this blockchain has {} and the request adds {}",
+            max_accounts, existing, created
+        )));
+    }
+    Ok(None)
+}
+
+/// Message for creating a blockchain that starts with `accounts` accounts when
+/// that's more than its team's account quota allows.
+pub fn new_blockchain_quota_exceeded<T: Storage>(
+    storage: &T,
+    team_id: Uuid,
+    accounts: usize,
+) -> Result<Option<String>, String> {
+    let Some(max_accounts) = storage
+        .get_team(team_id)?
+        .and_then(|team| team.max_accounts)
+    else {
+        return Ok(None);
+    };
+    let max_accounts = max_accounts.max(0) as u64;
+    if accounts as u64 > max_accounts {
+        return Ok(Some(format!(
+            "Your team's quota is {} accounts per blockchain, the new blockchain would have {}",
+            max_accounts, accounts
+        )));
+    }
+    Ok(None)
+}
+
+/// How far a transaction landing in `slot` is confirmed while `current_slot` is
+/// being processed, so statuses agree with `commitment_slot`.
+pub fn tx_confirmation_status(slot: u64, current_slot: u64) -> TransactionConfirmationStatus {
+    let commitment = |commitment| slot <= commitment_slot(current_slot, &commitment);
+    if commitment(TransactionConfirmationStatus::Finalized) {
+        TransactionConfirmationStatus::Finalized
+    } else if commitment(TransactionConfirmationStatus::Confirmed) {
+        TransactionConfirmationStatus::Confirmed
+    } else {
+        TransactionConfirmationStatus::Processed
+    }
+}
+
+/// The highest slot reaching `commitment` while `slot` is being processed.
+pub fn commitment_slot(slot: u64, commitment: &TransactionConfirmationStatus) -> u64 {
+    match commitment {
+        TransactionConfirmationStatus::Processed => slot,
+        TransactionConfirmationStatus::Confirmed => slot.saturating_sub(CONFIRMATION_DEPTH),
+        TransactionConfirmationStatus::Finalized => slot.saturating_sub(FINALIZATION_DEPTH),
+    }
 }
 
 pub fn status_is_greater(
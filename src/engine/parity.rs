@@ -0,0 +1,79 @@
+use solana_sdk::{
+    account::{AccountSharedData, ReadableAccount},
+    hash::{hashv, Hash},
+    pubkey::Pubkey,
+};
+
+/// What executing a transaction produced, compared between its execution and
+/// a simulation of it on blockchains that check parity.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ExecutionOutcome {
+    pub compute_units: u64,
+    pub logs: Vec<String>,
+    /// Hashes of the transaction's accounts after it ran, in account key order.
+    pub accounts: Vec<(Pubkey, Hash)>,
+}
+
+impl ExecutionOutcome {
+    pub fn new(
+        compute_units: u64,
+        logs: Vec<String>,
+        accounts: &[(Pubkey, AccountSharedData)],
+    ) -> Self {
+        ExecutionOutcome {
+            compute_units,
+            logs,
+            accounts: accounts
+                .iter()
+                .map(|(pubkey, account)| (*pubkey, account_hash(account)))
+                .collect(),
+        }
+    }
+
+    /// Where `simulated` disagrees with this execution, empty if nowhere.
+    pub fn divergences(&self, simulated: &ExecutionOutcome) -> Vec<String> {
+        let mut divergences = vec![];
+        if self.compute_units != simulated.compute_units {
+            divergences.push(format!(
+                "executed with {} compute units, simulated with {}",
+                self.compute_units, simulated.compute_units
+            ));
+        }
+        let logs = self.logs.len().max(simulated.logs.len());
+        if let Some(line) = (0..logs).find(|&i| self.logs.get(i) != simulated.logs.get(i)) {
+            divergences.push(format!(
+                "log line {} executed as {:?}, simulated as {:?}",
+                line,
+                self.logs.get(line),
+                simulated.logs.get(line)
+            ));
+        }
+        if self.accounts.len() != simulated.accounts.len() {
+            divergences.push(format!(
+                "executed with {} accounts, simulated with {}",
+                self.accounts.len(),
+                simulated.accounts.len()
+            ));
+        }
+        for ((pubkey, executed), (_, simulated)) in self.accounts.iter().zip(&simulated.accounts) {
+            if executed != simulated {
+                divergences.push(format!(
+                    "account {} executed as {}, simulated as {}",
+                    pubkey, executed, simulated
+                ));
+            }
+        }
+        divergences
+    }
+}
+
+/// Hash of everything about an account a transaction can change.
+pub fn account_hash(account: &AccountSharedData) -> Hash {
+    hashv(&[
+        &account.lamports().to_le_bytes(),
+        account.owner().as_ref(),
+        &[account.executable() as u8],
+        &account.rent_epoch().to_le_bytes(),
+        account.data(),
+    ])
+}
@@ -0,0 +1,92 @@
+use solana_program_runtime::loaded_programs::ProgramCacheEntry;
+use solana_sdk::{hash::Hash, pubkey::Pubkey, signature::Signature};
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use uuid::Uuid;
+
+use super::expiring::ExpiringMap;
+
+// Clients send right after their preflight, later sends just compile again
+const PREFLIGHT_TTL: Duration = Duration::from_secs(30);
+const PREFLIGHT_CAPACITY: usize = 1024;
+// Entries hold compiled programs of up to 10 MiB each
+const PREFLIGHT_BYTES: usize = 256 * 1024 * 1024;
+
+/// Programs compiled for a transaction, by the hash of the ELF each one was
+/// compiled from.
+#[derive(Default)]
+pub struct LoadedPrograms(Vec<(Pubkey, Hash, Arc<ProgramCacheEntry>)>);
+
+impl LoadedPrograms {
+    /// The compiled program, unless it was deployed again since.
+    pub fn get(&self, program_id: &Pubkey, elf_hash: &Hash) -> Option<Arc<ProgramCacheEntry>> {
+        self.0
+            .iter()
+            .find(|(id, hash, _)| id == program_id && hash == elf_hash)
+            .map(|(_, _, entry)| entry.clone())
+    }
+
+    pub fn insert(&mut self, program_id: Pubkey, elf_hash: Hash, entry: Arc<ProgramCacheEntry>) {
+        self.0.retain(|(id, _, _)| *id != program_id);
+        self.0.push((program_id, elf_hash, entry));
+    }
+
+    /// The size of the accounts the programs were compiled from.
+    pub fn bytes(&self) -> usize {
+        self.0.iter().map(|(_, _, entry)| entry.account_size).sum()
+    }
+}
+
+/// What simulating a transaction loaded, reused when the queue executes the same
+/// transaction after it's sent.
+pub struct Preflight {
+    /// Accounts missing locally were fetched upstream, and saved if they existed.
+    pub jit: bool,
+    pub programs: LoadedPrograms,
+}
+
+/// Preflights by blockchain and signature, each one is taken at most once.
+pub struct PreflightCache {
+    entries: Mutex<ExpiringMap<(Uuid, Signature), Preflight>>,
+}
+
+impl Default for PreflightCache {
+    fn default() -> Self {
+        PreflightCache {
+            entries: Mutex::new(ExpiringMap::with_max_weight(
+                PREFLIGHT_TTL,
+                PREFLIGHT_CAPACITY,
+                PREFLIGHT_BYTES,
+                |preflight| preflight.programs.bytes(),
+            )),
+        }
+    }
+}
+
+impl PreflightCache {
+    pub fn insert(&self, id: Uuid, signature: Signature, preflight: Preflight) {
+        self.entries
+            .lock()
+            .unwrap()
+            .insert((id, signature), preflight);
+    }
+
+    pub fn take(&self, id: Uuid, signature: &Signature) -> Option<Preflight> {
+        self.entries.lock().unwrap().remove(&(id, *signature))
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The size of the programs the cached preflights compiled.
+    pub fn bytes(&self) -> usize {
+        self.entries.lock().unwrap().weight()
+    }
+}
@@ -0,0 +1,203 @@
+use actix_web::rt::{self, time};
+use base64::prelude::*;
+use rdkafka::{
+    admin::{AdminClient, AdminOptions, NewTopic, TopicReplication},
+    config::ClientConfig,
+    consumer::{CommitMode, Consumer, StreamConsumer},
+    message::Message,
+    producer::{BaseRecord, DeliveryResult, Producer, ProducerContext, ThreadedProducer},
+    ClientContext,
+};
+use serde::{Deserialize, Serialize};
+use solana_sdk::transaction::VersionedTransaction;
+use std::{sync::Arc, time::Duration};
+use uuid::Uuid;
+
+use super::SvmEngine;
+use crate::{reporting, settings::QueueSettings, storage::Storage};
+
+// How often idle consumers check whether the engine is draining
+const DRAIN_CHECK_INTERVAL: Duration = Duration::from_millis(500);
+
+/// A sent transaction on the Kafka queue, keyed by blockchain so each one stays
+/// on a single partition, in order.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct QueuedTransaction {
+    pub blockchain: Uuid,
+    /// Base64 bincode transaction, as sent.
+    pub transaction: String,
+    pub jit: bool,
+}
+
+impl QueuedTransaction {
+    pub fn new(blockchain: Uuid, tx: &VersionedTransaction, jit: bool) -> Result<Self, String> {
+        let bytes = bincode::serialize(tx).map_err(|e| e.to_string())?;
+        Ok(QueuedTransaction {
+            blockchain,
+            transaction: BASE64_STANDARD.encode(bytes),
+            jit,
+        })
+    }
+
+    pub fn decode(&self) -> Result<VersionedTransaction, String> {
+        let bytes = BASE64_STANDARD
+            .decode(&self.transaction)
+            .map_err(|e| e.to_string())?;
+        bincode::deserialize(&bytes).map_err(|e| e.to_string())
+    }
+}
+
+/// Reports transactions Kafka failed to take, which are lost.
+struct DeliveryReports;
+
+impl ClientContext for DeliveryReports {}
+
+impl ProducerContext for DeliveryReports {
+    type DeliveryOpaque = ();
+
+    fn delivery(&self, delivery_result: &DeliveryResult<'_>, _: Self::DeliveryOpaque) {
+        if let Err((e, _)) = delivery_result {
+            println!("Failed to queue transaction on Kafka: {:?}", e);
+            reporting::capture_error(
+                &format!("Failed to queue transaction on Kafka: {:?}", e),
+                &[],
+            );
+        }
+    }
+}
+
+/// Transaction queue on Kafka, see `QueueSettings`.
+#[derive(Clone)]
+pub struct KafkaQueue {
+    brokers: String,
+    settings: QueueSettings,
+    producer: Arc<ThreadedProducer<DeliveryReports>>,
+}
+
+impl KafkaQueue {
+    pub fn new(brokers: &str, settings: &QueueSettings) -> Result<Self, String> {
+        let producer = ClientConfig::new()
+            .set("bootstrap.servers", brokers)
+            .set("acks", "all")
+            .set("enable.idempotence", "true")
+            .create_with_context(DeliveryReports)
+            .map_err(|e| format!("Failed to create queue producer: {}", e))?;
+        Ok(KafkaQueue {
+            brokers: brokers.to_string(),
+            settings: settings.clone(),
+            producer: Arc::new(producer),
+        })
+    }
+
+    pub fn produce(&self, id: Uuid, tx: &VersionedTransaction, jit: bool) -> Result<(), String> {
+        let payload = serde_json::to_string(&QueuedTransaction::new(id, tx, jit)?)
+            .map_err(|e| e.to_string())?;
+        let key = id.to_string();
+        self.producer
+            .send(
+                BaseRecord::to(&self.settings.topic)
+                    .key(&key)
+                    .payload(&payload),
+            )
+            .map_err(|(e, _)| format!("Failed to queue transaction: {}", e))
+    }
+
+    /// Blocks until produced transactions are delivered or the timeout passes.
+    pub fn flush(&self, timeout: Duration) {
+        if let Err(e) = self.producer.flush(timeout) {
+            println!("Failed to flush transaction queue: {:?}", e);
+        }
+    }
+
+    /// Creates the topic if needed, then runs `settings.consumers` consumers in the
+    /// group. Offsets are committed once a transaction is processed, so transactions
+    /// an instance was processing when it died run again elsewhere. They stop
+    /// consuming once the engine drains.
+    pub fn start<T: Storage + Clone + 'static>(&self, svm: Arc<SvmEngine<T>>) {
+        let queue = self.clone();
+        rt::spawn(async move {
+            if let Err(e) = queue.create_topic().await {
+                println!("{}", e);
+            }
+            for _ in 0..queue.settings.consumers {
+                match queue.consumer() {
+                    Ok(consumer) => {
+                        rt::spawn(consume(consumer, svm.clone()));
+                    }
+                    Err(e) => {
+                        println!("{}", e);
+                        reporting::capture_error(&e, &[]);
+                    }
+                }
+            }
+        });
+    }
+
+    async fn create_topic(&self) -> Result<(), String> {
+        let admin = ClientConfig::new()
+            .set("bootstrap.servers", &self.brokers)
+            .create::<AdminClient<_>>()
+            .map_err(|e| format!("Failed to create queue admin client: {}", e))?;
+        let topic = NewTopic::new(
+            &self.settings.topic,
+            self.settings.partitions,
+            TopicReplication::Fixed(1),
+        );
+        // Fails per topic, e.g. when it already exists
+        admin
+            .create_topics(&[topic], &AdminOptions::new())
+            .await
+            .map(|_| ())
+            .map_err(|e| format!("Failed to create queue topic: {}", e))
+    }
+
+    fn consumer(&self) -> Result<StreamConsumer, String> {
+        let consumer: StreamConsumer = ClientConfig::new()
+            .set("bootstrap.servers", &self.brokers)
+            .set("group.id", &self.settings.group_id)
+            .set("enable.auto.commit", "true")
+            .set("enable.auto.offset.store", "false")
+            .set("auto.offset.reset", "earliest")
+            .create()
+            .map_err(|e| format!("Failed to create queue consumer: {}", e))?;
+        consumer
+            .subscribe(&[&self.settings.topic])
+            .map_err(|e| format!("Failed to subscribe to the transaction queue: {}", e))?;
+        Ok(consumer)
+    }
+}
+
+async fn consume<T: Storage + Clone + 'static>(consumer: StreamConsumer, svm: Arc<SvmEngine<T>>) {
+    while !svm.is_draining() {
+        let message = match time::timeout(DRAIN_CHECK_INTERVAL, consumer.recv()).await {
+            Ok(Ok(message)) => message,
+            Ok(Err(e)) => {
+                println!("Failed to read the transaction queue: {}", e);
+                continue;
+            }
+            Err(_) => continue,
+        };
+        let queued = message
+            .payload()
+            .ok_or_else(|| "Empty message".to_string())
+            .and_then(|payload| {
+                serde_json::from_slice::<QueuedTransaction>(payload).map_err(|e| e.to_string())
+            })
+            .and_then(|queued| Ok((queued.blockchain, queued.decode()?, queued.jit)));
+        match queued {
+            Ok((id, raw_tx, jit)) => {
+                // Draining, left for whichever consumer takes the partition over
+                if svm.process_queued(id, raw_tx, jit).await.is_err() {
+                    break;
+                }
+            }
+            Err(e) => println!("Dropping invalid queued transaction: {}", e),
+        }
+        if let Err(e) = consumer.store_offset_from_message(&message) {
+            println!("Failed to store queue offset: {}", e);
+        }
+    }
+    if let Err(e) = consumer.commit_consumer_state(CommitMode::Sync) {
+        println!("Failed to commit queue offsets: {}", e);
+    }
+}
@@ -0,0 +1,102 @@
+use std::{
+    collections::HashMap,
+    future::Future,
+    sync::{Arc, Mutex},
+};
+use tokio::sync::Semaphore;
+use uuid::Uuid;
+
+pub const SIMULATIONS_BUSY: &str = "Too many simulations queued for this blockchain";
+
+struct Pool {
+    // Tokio semaphores hand out permits in arrival order
+    workers: Semaphore,
+    // Simulations running or waiting for a worker
+    admitted: Mutex<usize>,
+}
+
+/// Bounds simulations per blockchain, so one blockchain spamming
+/// `simulateTransaction` waits on its own workers instead of everyone's.
+/// Beyond `workers` running and `depth` waiting, simulations are rejected.
+pub struct SimulationPools {
+    pools: Mutex<HashMap<Uuid, Arc<Pool>>>,
+    workers: usize,
+    depth: usize,
+}
+
+impl SimulationPools {
+    pub fn new(workers: usize, depth: usize) -> Self {
+        SimulationPools {
+            pools: Mutex::new(HashMap::new()),
+            workers: workers.max(1),
+            depth,
+        }
+    }
+
+    pub async fn run<F: Future>(&self, id: Uuid, simulation: F) -> Result<F::Output, String> {
+        let admission = self.admit(id)?;
+        let _permit = admission
+            .pool
+            .workers
+            .acquire()
+            .await
+            .map_err(|_| SIMULATIONS_BUSY.to_string())?;
+        Ok(simulation.await)
+    }
+
+    /// Simulations running or waiting on the blockchain's workers.
+    pub fn admitted(&self, id: Uuid) -> usize {
+        let pools = self.pools.lock().unwrap();
+        pools
+            .get(&id)
+            .map(|pool| *pool.admitted.lock().unwrap())
+            .unwrap_or(0)
+    }
+
+    fn admit(&self, id: Uuid) -> Result<Admission<'_>, String> {
+        let mut pools = self.pools.lock().unwrap();
+        let pool = pools
+            .entry(id)
+            .or_insert_with(|| {
+                Arc::new(Pool {
+                    workers: Semaphore::new(self.workers),
+                    admitted: Mutex::new(0),
+                })
+            })
+            .clone();
+        let mut admitted = pool.admitted.lock().unwrap();
+        if *admitted >= self.workers + self.depth {
+            return Err(SIMULATIONS_BUSY.to_string());
+        }
+        *admitted += 1;
+        drop(admitted);
+        Ok(Admission {
+            pools: self,
+            id,
+            pool,
+        })
+    }
+}
+
+// Released when the simulation finishes or its request is dropped
+struct Admission<'a> {
+    pools: &'a SimulationPools,
+    id: Uuid,
+    pool: Arc<Pool>,
+}
+
+impl Drop for Admission<'_> {
+    fn drop(&mut self) {
+        let mut pools = self.pools.pools.lock().unwrap();
+        let mut admitted = self.pool.admitted.lock().unwrap();
+        *admitted -= 1;
+        // Idle blockchains don't keep a pool around
+        if *admitted == 0
+            && pools
+                .get(&self.id)
+                .is_some_and(|pool| Arc::ptr_eq(pool, &self.pool))
+        {
+            pools.remove(&self.id);
+        }
+    }
+}
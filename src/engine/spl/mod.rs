@@ -1,37 +1,157 @@
-use solana_program::pubkey;
+use solana_program::{program_option::COption, pubkey};
 use solana_sdk::{account::Account, pubkey::Pubkey};
+use spl_token_2022::extension::StateWithExtensionsMut;
+use std::{fs, path::Path, str::FromStr};
 
 use crate::storage::Storage;
 
 use super::{SvmEngine, SVM};
 
+pub const ASSOCIATED_TOKEN_PROGRAM_ID: Pubkey =
+    pubkey!("ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL");
+
+pub fn associated_token_address(owner: &Pubkey, mint: &Pubkey, token_program: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[owner.as_ref(), token_program.as_ref(), mint.as_ref()],
+        &ASSOCIATED_TOKEN_PROGRAM_ID,
+    )
+    .0
+}
+
+/// Rewrites the authorities of an SPL Token or Token-2022 mint, `None` leaves one as is.
+pub fn set_mint_authorities(
+    data: &mut [u8],
+    mint_authority: Option<Pubkey>,
+    freeze_authority: Option<Pubkey>,
+) -> Result<(), String> {
+    let mut state = StateWithExtensionsMut::<spl_token_2022::state::Mint>::unpack(data)
+        .map_err(|_| "Invalid mint data".to_string())?;
+    if let Some(authority) = mint_authority {
+        state.base.mint_authority = COption::Some(authority);
+    }
+    if let Some(authority) = freeze_authority {
+        state.base.freeze_authority = COption::Some(authority);
+    }
+    state.pack_base();
+    Ok(())
+}
+
+/// Programs every blockchain starts with, unless `programs.directory` has another
+/// build of them.
+const GENESIS_PROGRAMS: [(Pubkey, &[u8]); 6] = [
+    (
+        pubkey!("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA"),
+        include_bytes!("programs/spl_token-3.5.0.so"),
+    ),
+    (
+        pubkey!("TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb"),
+        include_bytes!("programs/spl_token_2022.so"),
+    ),
+    (
+        pubkey!("Memo1UhkJRfHyvLMcVucJwxXeuD728EqVDDwQDxFMNo"),
+        include_bytes!("programs/spl_memo-1.0.0.so"),
+    ),
+    (
+        pubkey!("MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcHr"),
+        include_bytes!("programs/spl_memo-3.0.0.so"),
+    ),
+    (
+        ASSOCIATED_TOKEN_PROGRAM_ID,
+        include_bytes!("programs/spl_associated_token_account-1.1.1.so"),
+    ),
+    (
+        pubkey!("metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s"),
+        include_bytes!("programs/metaplex_metadata_program.so"),
+    ),
+];
+
+/// Programs cloned from mainnet into blockchains whose config lists the bundle,
+/// on top of the genesis programs.
+const BUNDLES: [(&str, &[Pubkey]); 1] = [(
+    // Token Metadata is a genesis program already
+    "metaplex",
+    &[
+        pubkey!("CndyV3LdqHUfDLmE5naZjVN8rBZz4tqhdefbAnjHG3JR"), // Candy Machine
+        pubkey!("Guard1JwRhJkVH6XZhzoYxeBVQe872VH6QggF4BWmS9g"), // Candy Guard
+        pubkey!("hausS13jsjafwWwGqZTUQRmWyvyxn9EQpqMwV1PBBmk"),  // Auction House
+        pubkey!("BGUMAp9Gq7iTEuizy4pqaxsTyUCBK68MDfK752saRPUY"), // Bubblegum
+        pubkey!("cmtDvXumGCrqC1Age74AVPhSRVXJMd8PJS91L8KbNCK"),  // Account Compression
+        pubkey!("noopb9bkMVfRPU8AsbpTUg8AQkHtKwMYZiFUjNRtMmV"),  // Noop
+        pubkey!("auth9SigNpDKz4sJJ1DfCTuZrZNSAgh9sFD3rboVmgg"),  // Token Auth Rules
+    ],
+)];
+
+pub fn bundle_programs(bundle: &str) -> Result<&'static [Pubkey], String> {
+    BUNDLES
+        .iter()
+        .find(|(name, _)| *name == bundle)
+        .map(|(_, programs)| *programs)
+        .ok_or_else(|| format!("Unknown bundle {}", bundle))
+}
+
+/// The embedded genesis programs, with the `<program id>.so` builds found in
+/// `directory` replacing or adding to them. Unreadable builds are skipped.
 pub fn generate_spl_programs<T: Storage + Clone + 'static>(
     svm: &SvmEngine<T>,
+    directory: Option<&Path>,
 ) -> Vec<(Pubkey, Account)> {
-    vec![
-        svm.add_program(
-            pubkey!("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA"),
-            include_bytes!("programs/spl_token-3.5.0.so"),
-        ),
-        svm.add_program(
-            pubkey!("TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb"),
-            include_bytes!("programs/spl_token_2022.so"),
-        ),
-        svm.add_program(
-            pubkey!("Memo1UhkJRfHyvLMcVucJwxXeuD728EqVDDwQDxFMNo"),
-            include_bytes!("programs/spl_memo-1.0.0.so"),
-        ),
-        svm.add_program(
-            pubkey!("MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcHr"),
-            include_bytes!("programs/spl_memo-3.0.0.so"),
-        ),
-        svm.add_program(
-            pubkey!("ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL"),
-            include_bytes!("programs/spl_associated_token_account-1.1.1.so"),
-        ),
-        svm.add_program(
-            pubkey!("metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s"),
-            include_bytes!("programs/metaplex_metadata_program.so"),
-        ),
-    ]
+    let mut programs: Vec<(Pubkey, Vec<u8>)> = GENESIS_PROGRAMS
+        .iter()
+        .map(|(program_id, bytes)| (*program_id, bytes.to_vec()))
+        .collect();
+    if let Some(directory) = directory {
+        for (program_id, bytes) in directory_programs(directory) {
+            match programs.iter_mut().find(|(id, _)| *id == program_id) {
+                Some((_, embedded)) => *embedded = bytes,
+                None => programs.push((program_id, bytes)),
+            }
+        }
+    }
+    programs
+        .iter()
+        .map(|(program_id, bytes)| svm.add_program(*program_id, bytes))
+        .collect()
+}
+
+fn directory_programs(directory: &Path) -> Vec<(Pubkey, Vec<u8>)> {
+    let entries = match fs::read_dir(directory) {
+        Ok(entries) => entries,
+        Err(e) => {
+            println!(
+                "Failed to read programs from {}: {}",
+                directory.display(),
+                e
+            );
+            return vec![];
+        }
+    };
+    let mut programs = vec![];
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.extension().and_then(|ext| ext.to_str()) != Some("so") {
+            continue;
+        }
+        let program_id = match path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .and_then(|stem| Pubkey::from_str(stem).ok())
+        {
+            Some(program_id) => program_id,
+            None => continue,
+        };
+        match fs::read(&path) {
+            Ok(bytes) => programs.push((program_id, bytes)),
+            Err(e) => println!("Failed to read program {}: {}", path.display(), e),
+        }
+    }
+    programs
+}
+
+/// A program build from `directory`, which `file` has to name directly.
+pub fn read_program(directory: &Path, file: &str) -> Result<Vec<u8>, String> {
+    let name = Path::new(file);
+    if file.is_empty() || name.file_name() != Some(name.as_os_str()) {
+        return Err(format!("Invalid program file {}", file));
+    }
+    fs::read(directory.join(name)).map_err(|e| format!("Failed to read program {}: {}", file, e))
 }
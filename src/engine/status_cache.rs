@@ -0,0 +1,59 @@
+use chrono::Utc;
+use solana_sdk::{signature::Signature, transaction::TransactionError};
+use std::{sync::Mutex, time::Duration};
+use uuid::Uuid;
+
+use super::expiring::ExpiringMap;
+
+// Clients poll until their transaction confirms, seconds after sending it
+const STATUS_TTL: Duration = Duration::from_secs(60);
+// Entries are small, a busy instance's last minute of transactions fits
+const STATUS_CAPACITY: usize = 100_000;
+
+/// Where and how a processed transaction landed.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CachedStatus {
+    pub slot: u64,
+    pub err: Option<TransactionError>,
+    pub created_at: chrono::DateTime<Utc>,
+}
+
+/// Statuses of recent transactions by blockchain and signature, so polling
+/// `getSignatureStatuses` doesn't load whole transactions from storage. Only
+/// processed transactions are cached, unknown signatures are looked up each time.
+pub struct StatusCache {
+    entries: Mutex<ExpiringMap<(Uuid, Signature), CachedStatus>>,
+}
+
+impl Default for StatusCache {
+    fn default() -> Self {
+        StatusCache {
+            entries: Mutex::new(ExpiringMap::new(STATUS_TTL, STATUS_CAPACITY)),
+        }
+    }
+}
+
+impl StatusCache {
+    pub fn insert(&self, id: Uuid, signature: Signature, status: CachedStatus) {
+        self.entries.lock().unwrap().insert((id, signature), status);
+    }
+
+    pub fn get(&self, id: Uuid, signature: &Signature) -> Option<CachedStatus> {
+        self.entries.lock().unwrap().get(&(id, *signature)).cloned()
+    }
+
+    pub fn forget(&self, id: Uuid) {
+        self.entries
+            .lock()
+            .unwrap()
+            .retain(|(blockchain, _), _| *blockchain != id);
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
@@ -0,0 +1,95 @@
+use solana_sdk::pubkey::Pubkey;
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+};
+
+/// Transactions a `logsSubscribe` is notified of. Like Solana, `All` leaves out
+/// vote transactions while `AllWithVotes` and `Mentions` keep them.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LogsFilter {
+    All,
+    AllWithVotes,
+    Mentions(Pubkey),
+}
+
+impl LogsFilter {
+    /// Whether a transaction touching `accounts` can match, before it's loaded.
+    pub fn mentioned(&self, accounts: &[String]) -> bool {
+        match self {
+            LogsFilter::All | LogsFilter::AllWithVotes => true,
+            LogsFilter::Mentions(pubkey) => accounts.contains(&pubkey.to_string()),
+        }
+    }
+
+    pub fn matches(&self, accounts: &[String], is_vote: bool) -> bool {
+        self.mentioned(accounts) && !(is_vote && *self == LogsFilter::All)
+    }
+}
+
+/// Ids of the live slot, logs and signature subscriptions, polling loops stop once
+/// their id is removed. The lock only guards map lookups and is never held across an await,
+/// so it can't block the runtime or fail under concurrent subscribe/unsubscribe.
+#[derive(Clone, Default)]
+pub struct Subscriptions {
+    // Connections pick their own request ids, so the same id can be live twice
+    ids: Arc<Mutex<HashMap<u32, usize>>>,
+}
+
+impl Subscriptions {
+    pub fn add(&self, id: u32) {
+        *self.lock().entry(id).or_default() += 1;
+    }
+
+    /// Adds the id until the returned registration is dropped, for subscribers that
+    /// can be cancelled mid-wait.
+    pub fn register(&self, id: u32) -> Registration {
+        self.add(id);
+        Registration {
+            subscriptions: self.clone(),
+            id,
+        }
+    }
+
+    pub fn remove(&self, id: u32) -> Result<(), String> {
+        let mut ids = self.lock();
+        match ids.get_mut(&id) {
+            Some(1) => {
+                ids.remove(&id);
+            }
+            Some(count) => *count -= 1,
+            None => return Err("Subscription ID not found".to_string()),
+        }
+        Ok(())
+    }
+
+    pub fn contains(&self, id: u32) -> bool {
+        self.lock().contains_key(&id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.lock().values().sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+
+    // The map is updated in single statements, a panic elsewhere can't leave it
+    // half written
+    fn lock(&self) -> MutexGuard<'_, HashMap<u32, usize>> {
+        self.ids.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+pub struct Registration {
+    subscriptions: Subscriptions,
+    id: u32,
+}
+
+impl Drop for Registration {
+    fn drop(&mut self) {
+        // Already gone when the subscriber unsubscribed
+        let _ = self.subscriptions.remove(self.id);
+    }
+}
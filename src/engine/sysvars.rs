@@ -0,0 +1,165 @@
+use serde::{Deserialize, Serialize};
+use solana_program_runtime::sysvar_cache::SysvarCache;
+use solana_sdk::{
+    account::{Account, AccountSharedData},
+    clock::Clock,
+    epoch_schedule::{EpochSchedule, MINIMUM_SLOTS_PER_EPOCH},
+    pubkey::Pubkey,
+    sysvar::{self, Sysvar},
+};
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use uuid::Uuid;
+
+use super::expiring::ExpiringMap;
+
+// Overrides rarely change, other instances see a change this late
+const OVERRIDES_TTL: Duration = Duration::from_secs(5);
+const OVERRIDES_CAPACITY: usize = 10_000;
+
+/// Sysvars the engine keeps in its `SysvarCache`, their accounts are served from
+/// there rather than storage. The slot history isn't kept, so it's still stored.
+#[allow(deprecated)]
+const ENGINE_SYSVARS: [Pubkey; 8] = [
+    sysvar::clock::ID,
+    sysvar::epoch_rewards::ID,
+    sysvar::epoch_schedule::ID,
+    sysvar::last_restart_slot::ID,
+    sysvar::recent_blockhashes::ID,
+    sysvar::rent::ID,
+    sysvar::slot_hashes::ID,
+    sysvar::stake_history::ID,
+];
+
+pub fn is_engine_sysvar(pubkey: &Pubkey) -> bool {
+    ENGINE_SYSVARS.contains(pubkey)
+}
+
+/// The sysvar's account as transactions see it, `None` for sysvars the engine
+/// doesn't keep.
+#[allow(deprecated)]
+pub fn sysvar_account(sysvars: &SysvarCache, pubkey: &Pubkey) -> Option<Account> {
+    match *pubkey {
+        sysvar::clock::ID => account(sysvars.get_clock()),
+        sysvar::epoch_rewards::ID => account(sysvars.get_epoch_rewards()),
+        sysvar::epoch_schedule::ID => account(sysvars.get_epoch_schedule()),
+        sysvar::last_restart_slot::ID => account(sysvars.get_last_restart_slot()),
+        sysvar::recent_blockhashes::ID => account(sysvars.get_recent_blockhashes()),
+        sysvar::rent::ID => account(sysvars.get_rent()),
+        sysvar::slot_hashes::ID => account(sysvars.get_slot_hashes()),
+        sysvar::stake_history::ID => account(sysvars.get_stake_history()),
+        _ => None,
+    }
+}
+
+fn account<S: Sysvar + Serialize, E>(sysvar: Result<Arc<S>, E>) -> Option<Account> {
+    let sysvar = sysvar.ok()?;
+    AccountSharedData::new_data(1, sysvar.as_ref(), &sysvar::id())
+        .ok()
+        .map(Account::from)
+}
+
+/// Sysvar values a blockchain sets in place of the engine's defaults. The clock
+/// still follows the latest block for whatever isn't set.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct SysvarOverrides {
+    #[serde(default)]
+    pub clock: ClockOverride,
+    pub epoch_schedule: Option<EpochScheduleOverride>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct ClockOverride {
+    pub epoch: Option<u64>,
+    pub leader_schedule_epoch: Option<u64>,
+    pub epoch_start_timestamp: Option<i64>,
+    /// Fixed in place of the latest block's time
+    pub unix_timestamp: Option<i64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct EpochScheduleOverride {
+    pub slots_per_epoch: u64,
+    pub leader_schedule_slot_offset: Option<u64>, // Defaults to slots_per_epoch
+    #[serde(default)]
+    pub warmup: bool,
+}
+
+impl SysvarOverrides {
+    pub fn validate(&self) -> Result<(), String> {
+        match &self.epoch_schedule {
+            Some(schedule) if schedule.slots_per_epoch < MINIMUM_SLOTS_PER_EPOCH => Err(format!(
+                "slots_per_epoch must be at least {}",
+                MINIMUM_SLOTS_PER_EPOCH
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// `None` when the default schedule applies, or the stored one is invalid.
+    pub fn epoch_schedule(&self) -> Option<EpochSchedule> {
+        let schedule = self.epoch_schedule.as_ref()?;
+        (schedule.slots_per_epoch >= MINIMUM_SLOTS_PER_EPOCH).then(|| {
+            EpochSchedule::custom(
+                schedule.slots_per_epoch,
+                schedule
+                    .leader_schedule_slot_offset
+                    .unwrap_or(schedule.slots_per_epoch),
+                schedule.warmup,
+            )
+        })
+    }
+
+    /// The clock at the latest block with the overrides applied. A custom epoch
+    /// schedule also moves the epochs, unless they're set too.
+    pub fn clock(&self, mut clock: Clock) -> Clock {
+        if let Some(schedule) = self.epoch_schedule() {
+            clock.epoch = schedule.get_epoch(clock.slot);
+            clock.leader_schedule_epoch = schedule.get_leader_schedule_epoch(clock.slot);
+        }
+        let ClockOverride {
+            epoch,
+            leader_schedule_epoch,
+            epoch_start_timestamp,
+            unix_timestamp,
+        } = &self.clock;
+        clock.epoch = epoch.unwrap_or(clock.epoch);
+        clock.leader_schedule_epoch = leader_schedule_epoch.unwrap_or(clock.leader_schedule_epoch);
+        clock.epoch_start_timestamp = epoch_start_timestamp.unwrap_or(clock.epoch_start_timestamp);
+        clock.unix_timestamp = unix_timestamp.unwrap_or(clock.unix_timestamp);
+        clock
+    }
+}
+
+/// Each blockchain's sysvar overrides as read from storage, so transactions
+/// don't read them again.
+pub struct OverridesCache {
+    entries: Mutex<ExpiringMap<Uuid, SysvarOverrides>>,
+}
+
+impl Default for OverridesCache {
+    fn default() -> Self {
+        OverridesCache {
+            entries: Mutex::new(ExpiringMap::new(OVERRIDES_TTL, OVERRIDES_CAPACITY)),
+        }
+    }
+}
+
+impl OverridesCache {
+    pub fn insert(&self, id: Uuid, overrides: SysvarOverrides) {
+        self.entries.lock().unwrap().insert(id, overrides);
+    }
+
+    pub fn get(&self, id: Uuid) -> Option<SysvarOverrides> {
+        self.entries.lock().unwrap().get(&id).cloned()
+    }
+
+    pub fn forget(&self, id: Uuid) {
+        self.entries.lock().unwrap().remove(&id);
+    }
+}
@@ -0,0 +1,100 @@
+use serde::Serialize;
+use serde_json::Value;
+use solana_sdk::{
+    bpf_loader_upgradeable, loader_upgradeable_instruction::UpgradeableLoaderInstruction,
+    pubkey::Pubkey, transaction::SanitizedTransaction,
+};
+
+use crate::storage::audit::{self, DbAuditLog};
+
+/// Most events returned at once.
+pub const MAX_TIMELINE_SIZE: usize = 1000;
+
+/// Audit actions shown on a blockchain's timeline.
+pub const TIMELINE_ACTIONS: &[&str] = &[
+    audit::BLOCKCHAIN_CREATE,
+    audit::BLOCKCHAIN_CLONE,
+    audit::BLOCKCHAIN_UPDATE,
+    audit::BLOCKCHAIN_TRANSFER,
+    audit::BLOCKCHAIN_SYSVARS_SET,
+    audit::BLOCKCHAIN_EXPIRE,
+    audit::PROGRAM_LOAD,
+    audit::PROGRAM_CLONE,
+    audit::PROGRAM_AUTHORITY_SET,
+    audit::PROGRAM_DEPLOY,
+    audit::PROGRAM_UPGRADE,
+    audit::ACCOUNT_LOAD,
+    audit::ACCOUNT_DELETE,
+    audit::AIRDROP,
+    audit::TOKEN_MINT,
+    audit::TOKEN_AUTHORITY_SET,
+];
+
+/// When the blockchain is set to expire, in the future until the expiry job runs.
+pub const BLOCKCHAIN_EXPIRY: &str = "blockchain.expiry";
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct TimelineEvent {
+    pub timestamp: chrono::NaiveDateTime,
+    /// An audit action, or the blockchain event kind above
+    pub kind: String,
+    /// `audit`, `transaction` or `blockchain`
+    pub source: &'static str,
+    pub signature: Option<String>,
+    pub details: Value,
+}
+
+impl TimelineEvent {
+    pub fn from_audit_log(log: DbAuditLog) -> Self {
+        let details =
+            serde_json::from_str::<Value>(&log.summary).unwrap_or(Value::String(log.summary));
+        // Deployments and upgrades are recorded as their transactions execute
+        let source = match log.action.as_str() {
+            audit::PROGRAM_DEPLOY | audit::PROGRAM_UPGRADE => "transaction",
+            _ => "audit",
+        };
+        TimelineEvent {
+            timestamp: log.created_at,
+            kind: log.action,
+            source,
+            signature: details
+                .get("signature")
+                .and_then(|signature| signature.as_str())
+                .map(|signature| signature.to_string()),
+            details,
+        }
+    }
+}
+
+/// Programs deployed or upgraded by the transaction's instructions to the
+/// upgradeable loader, with the audit action of each.
+pub fn program_deployments(tx: &SanitizedTransaction) -> Vec<(&'static str, Pubkey)> {
+    let message = tx.message();
+    let account_keys = message.account_keys();
+    message
+        .instructions()
+        .iter()
+        .filter(|ix| {
+            account_keys.get(ix.program_id_index as usize) == Some(&bpf_loader_upgradeable::id())
+        })
+        .filter_map(|ix| {
+            // Position of the program account in the instruction's accounts
+            let (action, program_position) = match bincode::deserialize(&ix.data).ok()? {
+                UpgradeableLoaderInstruction::DeployWithMaxDataLen { .. } => {
+                    (audit::PROGRAM_DEPLOY, 2)
+                }
+                UpgradeableLoaderInstruction::Upgrade => (audit::PROGRAM_UPGRADE, 1),
+                _ => return None,
+            };
+            let program_index = *ix.accounts.get(program_position)?;
+            Some((action, *account_keys.get(program_index as usize)?))
+        })
+        .collect()
+}
+
+/// Newest events first, events at the same time keep their order, cut to `limit`.
+pub fn merge(mut events: Vec<TimelineEvent>, limit: usize) -> Vec<TimelineEvent> {
+    events.sort_by_key(|event| std::cmp::Reverse(event.timestamp));
+    events.truncate(limit);
+    events
+}
@@ -29,12 +29,18 @@ pub struct TokenAmount {
     pub ui_amount_string: String,
 }
 
+/// Token balances before and after the transaction of every token account it
+/// loaded, static or from lookup tables. Accounts only touched by inner
+/// instructions, like the pools of a swap through a router, have to be among
+/// them too. `tx_accounts` are the transaction's accounts after execution in
+/// account key order, as left in its `TransactionContext`, or empty when its
+/// writes were dropped.
 pub fn collect_token_balances<T: Storage + Clone + 'static>(
     id: Uuid,
-    tx: SanitizedTransaction,
+    tx: &SanitizedTransaction,
     accounts_db: &AccountsDB,
     storage: T,
-    post_accounts: Vec<(Pubkey, AccountSharedData)>,
+    tx_accounts: &[(Pubkey, AccountSharedData)],
 ) -> Option<TransactionTokenBalancesSet> {
     let account_keys = tx.message().account_keys();
     let has_token_program = account_keys.iter().any(is_known_spl_token_id);
@@ -52,41 +58,36 @@ pub fn collect_token_balances<T: Storage + Clone + 'static>(
         }
 
         let pre_account = accounts_db.get_account(account_id);
-        let post_account = post_accounts
-            .iter()
-            .find(|(pubkey, _)| pubkey == account_id)
-            .map(|(_, account)| account.clone());
-
-        match pre_account {
-            Some(pre_account) => {
-                if let Some(pre_balance) = collect_token_balance_from_account(
-                    id,
-                    pre_account,
-                    storage.clone(),
-                    post_accounts.clone(),
-                    index,
-                    &mut mint_decimals,
-                ) {
-                    pre_balances.push(pre_balance);
-                }
-            }
-            None => {}
-        };
-        match post_account {
-            Some(post_account) => {
-                if let Some(post_balance) = collect_token_balance_from_account(
-                    id,
-                    post_account,
-                    storage.clone(),
-                    post_accounts.clone(),
-                    index,
-                    &mut mint_decimals,
-                ) {
-                    post_balances.push(post_balance);
-                }
-            }
-            None => {}
+        // Read-only accounts are included, unchanged, so they don't read as closed
+        let post_account = match tx_accounts.get(index) {
+            Some((pubkey, account)) if pubkey == account_id => Some(account.clone()),
+            _ => pre_account.clone(),
         };
+
+        if let Some(pre_balance) = pre_account.and_then(|account| {
+            collect_token_balance_from_account(
+                id,
+                account,
+                storage.clone(),
+                tx_accounts,
+                index,
+                &mut mint_decimals,
+            )
+        }) {
+            pre_balances.push(pre_balance);
+        }
+        if let Some(post_balance) = post_account.and_then(|account| {
+            collect_token_balance_from_account(
+                id,
+                account,
+                storage.clone(),
+                tx_accounts,
+                index,
+                &mut mint_decimals,
+            )
+        }) {
+            post_balances.push(post_balance);
+        }
     }
 
     Some(TransactionTokenBalancesSet {
@@ -99,7 +100,7 @@ fn collect_token_balance_from_account<T: Storage + Clone + 'static>(
     id: Uuid,
     account: AccountSharedData,
     storage: T,
-    post_accounts: Vec<(Pubkey, AccountSharedData)>,
+    tx_accounts: &[(Pubkey, AccountSharedData)],
     account_idx: usize,
     mint_decimals: &mut HashMap<Pubkey, u8>,
 ) -> Option<TransactionTokenBalance> {
@@ -111,7 +112,7 @@ fn collect_token_balance_from_account<T: Storage + Clone + 'static>(
     let mint = token_account.base.mint;
 
     let decimals = mint_decimals.get(&mint).cloned().or_else(|| {
-        let decimals = get_mint_decimals(storage, post_accounts, id, &mint)?;
+        let decimals = get_mint_decimals(storage, tx_accounts, id, &mint)?;
         mint_decimals.insert(mint, decimals);
         Some(decimals)
     })?;
@@ -126,21 +127,22 @@ fn collect_token_balance_from_account<T: Storage + Clone + 'static>(
             ui_amount: Some(ui_amount),
             ui_amount_string: ui_amount.to_string(),
         },
-        owner: account.owner().to_string(),
-        program_id: token_account.base.owner.to_string(),
+        owner: token_account.base.owner.to_string(),
+        program_id: account.owner().to_string(),
     })
 }
 
 fn get_mint_decimals<T: Storage + Clone + 'static>(
     storage: T,
-    post_accounts: Vec<(Pubkey, AccountSharedData)>,
+    tx_accounts: &[(Pubkey, AccountSharedData)],
     id: Uuid,
     mint: &Pubkey,
 ) -> Option<u8> {
     if mint == &spl_token::native_mint::id() {
         Some(spl_token::native_mint::DECIMALS)
     } else {
-        let mint_account = match post_accounts.iter().find(|(pubkey, _)| pubkey == mint) {
+        // A mint the transaction created or loaded is read as the transaction left it
+        let mint_account = match tx_accounts.iter().find(|(pubkey, _)| pubkey == mint) {
             Some((_, account)) => account.clone(),
             None => match storage.get_account(id, mint).ok()? {
                 Some(account) => account.to_account_shared_data(),
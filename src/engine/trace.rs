@@ -0,0 +1,163 @@
+use solana_program_runtime::invoke_context::InvokeContext;
+use solana_sdk::{
+    account::{AccountSharedData, WritableAccount},
+    instruction::InstructionError,
+    message::SanitizedMessage,
+    precompiles::get_precompile,
+    pubkey::Pubkey,
+    sysvar::instructions,
+    transaction::TransactionError,
+    transaction_context::{IndexOfAccount, InstructionAccount},
+};
+use solana_timings::ExecuteTimings;
+
+/// A top-level instruction of a traced execution, see `process_message`.
+#[derive(Clone, Debug)]
+pub struct InstructionTrace {
+    pub program_id: Pubkey,
+    // Units consumed by the transaction before and after the instruction
+    pub compute_units_before: u64,
+    pub compute_units_after: u64,
+    pub logs: Vec<String>,
+    pub err: Option<InstructionError>,
+    /// Writable accounts the instruction changed, empty when it failed since its
+    /// changes are rolled back with the transaction.
+    pub account_writes: Vec<AccountWrite>,
+}
+
+#[derive(Clone, Debug)]
+pub struct AccountWrite {
+    pub pubkey: Pubkey,
+    pub before: AccountSharedData,
+    pub after: AccountSharedData,
+}
+
+/// `MessageProcessor::process_message`, recording each instruction it runs.
+pub fn process_message(
+    message: &SanitizedMessage,
+    program_indices: &[Vec<IndexOfAccount>],
+    invoke_context: &mut InvokeContext,
+    accumulated_consumed_units: &mut u64,
+    trace: &mut Vec<InstructionTrace>,
+) -> Result<(), TransactionError> {
+    let mut execute_timings = ExecuteTimings::default();
+    for (instruction_index, ((program_id, instruction), program_indices)) in message
+        .program_instructions_iter()
+        .zip(program_indices.iter())
+        .enumerate()
+    {
+        if let Some(account_index) = invoke_context
+            .transaction_context
+            .find_index_of_account(&instructions::id())
+        {
+            let mut account = invoke_context
+                .transaction_context
+                .get_account_at_index(account_index)
+                .map_err(|_| TransactionError::InvalidAccountIndex)?
+                .borrow_mut();
+            instructions::store_current_index(
+                account.data_as_mut_slice(),
+                instruction_index as u16,
+            );
+        }
+
+        let mut instruction_accounts = Vec::with_capacity(instruction.accounts.len());
+        for (instruction_account_index, index_in_transaction) in
+            instruction.accounts.iter().enumerate()
+        {
+            let index_in_callee = instruction.accounts[..instruction_account_index]
+                .iter()
+                .position(|account_index| account_index == index_in_transaction)
+                .unwrap_or(instruction_account_index)
+                as IndexOfAccount;
+            let index_in_transaction = *index_in_transaction as usize;
+            instruction_accounts.push(InstructionAccount {
+                index_in_transaction: index_in_transaction as IndexOfAccount,
+                index_in_caller: index_in_transaction as IndexOfAccount,
+                index_in_callee,
+                is_signer: message.is_signer(index_in_transaction),
+                is_writable: message.is_writable(index_in_transaction),
+            });
+        }
+
+        let before = writable_accounts(message, invoke_context);
+        let logs_before = logged(invoke_context);
+        let compute_units_before = *accumulated_consumed_units;
+        let mut compute_units_consumed = 0;
+        let result = match get_precompile(program_id, |feature_id| {
+            invoke_context.get_feature_set().is_active(feature_id)
+        }) {
+            Some(precompile) => invoke_context.process_precompile(
+                precompile,
+                &instruction.data,
+                &instruction_accounts,
+                program_indices,
+                message.instructions().iter().map(|ix| ix.data.as_slice()),
+            ),
+            None => invoke_context.process_instruction(
+                &instruction.data,
+                &instruction_accounts,
+                program_indices,
+                &mut compute_units_consumed,
+                &mut execute_timings,
+            ),
+        };
+        *accumulated_consumed_units =
+            accumulated_consumed_units.saturating_add(compute_units_consumed);
+
+        let account_writes = match result {
+            Ok(()) => before
+                .into_iter()
+                .zip(writable_accounts(message, invoke_context))
+                .filter(|((_, before), (_, after))| before != after)
+                .map(|((pubkey, before), (_, after))| AccountWrite {
+                    pubkey,
+                    before,
+                    after,
+                })
+                .collect(),
+            Err(_) => vec![],
+        };
+        let logs = invoke_context
+            .get_log_collector()
+            .map(|collector| collector.borrow().messages[logs_before..].to_vec())
+            .unwrap_or_default();
+        trace.push(InstructionTrace {
+            program_id: *program_id,
+            compute_units_before,
+            compute_units_after: *accumulated_consumed_units,
+            logs,
+            err: result.clone().err(),
+            account_writes,
+        });
+
+        result.map_err(|err| TransactionError::InstructionError(instruction_index as u8, err))?;
+    }
+    Ok(())
+}
+
+fn writable_accounts(
+    message: &SanitizedMessage,
+    invoke_context: &InvokeContext,
+) -> Vec<(Pubkey, AccountSharedData)> {
+    let transaction_context = &invoke_context.transaction_context;
+    message
+        .account_keys()
+        .iter()
+        .enumerate()
+        .filter(|(index, _)| message.is_writable(*index))
+        .filter_map(|(index, pubkey)| {
+            let account = transaction_context
+                .get_account_at_index(index as IndexOfAccount)
+                .ok()?;
+            Some((*pubkey, account.borrow().clone()))
+        })
+        .collect()
+}
+
+fn logged(invoke_context: &InvokeContext) -> usize {
+    invoke_context
+        .get_log_collector()
+        .map(|collector| collector.borrow().messages.len())
+        .unwrap_or(0)
+}
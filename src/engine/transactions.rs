@@ -4,13 +4,30 @@ use solana_account_decoder::parse_token::UiTokenAmount;
 use solana_sdk::{
     account::AccountSharedData,
     inner_instruction::InnerInstructionsList,
+    message::{v0::LoadedAddresses, VersionedMessage},
     pubkey::Pubkey,
     signature::Signature,
-    transaction::{SanitizedTransaction, TransactionError},
+    transaction::{
+        SanitizedTransaction, TransactionError, TransactionVersion, VersionedTransaction,
+    },
     transaction_context::TransactionReturnData,
 };
 
-use super::blocks::Block;
+use super::{blocks::Block, trace::InstructionTrace};
+
+/// Same as `SanitizedTransaction::is_simple_vote_transaction`, which is what
+/// stored transactions are flagged with.
+pub fn is_vote_transaction(tx: &VersionedTransaction) -> bool {
+    let message = &tx.message;
+    let instructions = message.instructions();
+    matches!(message, VersionedMessage::Legacy(_))
+        && tx.signatures.len() < 3
+        && instructions.len() == 1
+        && message
+            .static_account_keys()
+            .get(instructions[0].program_id_index as usize)
+            .is_some_and(solana_sdk::vote::program::check_id)
+}
 
 pub struct TransactionMetadata {
     pub signature: Signature,
@@ -25,6 +42,10 @@ pub struct TransactionMetadata {
     pub post_accounts: Vec<(Pubkey, AccountSharedData)>,
     pub pre_token_balances: Option<Vec<TransactionTokenBalance>>,
     pub post_token_balances: Option<Vec<TransactionTokenBalance>>,
+    /// Set by traced simulations only.
+    pub trace: Option<Vec<InstructionTrace>>,
+    /// Lamports of the fee paid by the blockchain's airdrop identity.
+    pub sponsored_fee: u64,
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize)]
@@ -36,12 +57,20 @@ pub struct TransactionMeta {
     pub log_messages: Vec<String>,
     pub inner_instructions: InnerInstructionsList,
     pub compute_units_consumed: u64,
+    /// Accounts the transaction loaded from address lookup tables.
+    #[serde(skip)]
+    pub loaded_addresses: LoadedAddresses,
+    /// Unix timestamp of the transaction's block.
+    pub block_time: Option<i64>,
+    pub version: TransactionVersion,
     pub pre_balances: Vec<u64>,
     pub pre_token_balances: Option<Vec<TransactionTokenBalance>>,
     pub post_token_balances: Option<Vec<TransactionTokenBalance>>,
     pub post_balances: Vec<u64>,
     pub rewards: Vec<u64>, //todo: rewards
     pub status: Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sponsored_fee: Option<u64>,
 }
 
 #[derive(Clone, Debug, PartialEq, Serialize)]
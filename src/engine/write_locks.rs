@@ -0,0 +1,62 @@
+use serde::Serialize;
+use solana_sdk::pubkey::Pubkey;
+use std::{collections::HashMap, sync::Mutex};
+use uuid::Uuid;
+
+/// Accounts tracked per blockchain, new ones are ignored past this.
+pub const MAX_WRITE_LOCKED_ACCOUNTS: usize = 10_000;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize)]
+pub struct WriteLockStats {
+    /// Executed transactions that write-locked the account
+    pub write_locks: u64,
+    /// Write locks taken in a slot another transaction already write-locked it in,
+    /// those transactions can't run in parallel
+    pub conflicts: u64,
+    #[serde(skip)]
+    last_slot: u64,
+}
+
+/// Accounts the transactions this instance executed write-locked, per blockchain.
+#[derive(Default)]
+pub struct WriteLocks {
+    blockchains: Mutex<HashMap<Uuid, HashMap<Pubkey, WriteLockStats>>>,
+}
+
+impl WriteLocks {
+    pub fn record(&self, id: Uuid, slot: u64, accounts: impl IntoIterator<Item = Pubkey>) {
+        let mut blockchains = self.blockchains.lock().unwrap();
+        let tracked = blockchains.entry(id).or_default();
+        for account in accounts {
+            let full = tracked.len() >= MAX_WRITE_LOCKED_ACCOUNTS;
+            let stats = match tracked.get_mut(&account) {
+                Some(stats) => stats,
+                None if full => continue,
+                None => tracked.entry(account).or_default(),
+            };
+            if stats.write_locks > 0 && stats.last_slot == slot {
+                stats.conflicts += 1;
+            }
+            stats.write_locks += 1;
+            stats.last_slot = slot;
+        }
+    }
+
+    /// The blockchain's most write-locked accounts first, ties by conflicts.
+    pub fn report(&self, id: Uuid, limit: usize) -> Vec<(Pubkey, WriteLockStats)> {
+        let blockchains = self.blockchains.lock().unwrap();
+        let mut accounts: Vec<(Pubkey, WriteLockStats)> = blockchains
+            .get(&id)
+            .map(|tracked| tracked.iter().map(|(k, v)| (*k, *v)).collect())
+            .unwrap_or_default();
+        accounts.sort_by(|(a_key, a), (b_key, b)| {
+            (b.write_locks, b.conflicts, a_key).cmp(&(a.write_locks, a.conflicts, b_key))
+        });
+        accounts.truncate(limit);
+        accounts
+    }
+
+    pub fn forget(&self, id: Uuid) {
+        self.blockchains.lock().unwrap().remove(&id);
+    }
+}
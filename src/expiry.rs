@@ -0,0 +1,27 @@
+use actix_web::rt::{self, time};
+use std::{sync::Arc, time::Duration};
+
+use crate::{
+    engine::{SvmEngine, SVM},
+    reporting,
+    storage::Storage,
+};
+
+/// Periodically deletes expired blockchains and records their expiry in the
+/// audit log.
+pub fn start<T: Storage + Clone + 'static>(svm: Arc<SvmEngine<T>>, interval: Duration) {
+    rt::spawn(async move {
+        let mut interval = time::interval(interval);
+        loop {
+            interval.tick().await;
+            match svm.expire_blockchains() {
+                Ok(0) => {}
+                Ok(deleted) => println!("Deleted {} expired blockchains", deleted),
+                Err(e) => {
+                    println!("Failed to expire blockchains: {}", e);
+                    reporting::capture_error(&format!("Failed to expire blockchains: {}", e), &[]);
+                }
+            }
+        }
+    });
+}
@@ -0,0 +1,17 @@
+use actix_web::rt::{self, time};
+use std::{sync::Arc, time::Duration};
+
+use crate::{engine::SvmEngine, storage::Storage};
+
+/// Periodically publishes the slot of each blockchain this instance serves to the
+/// geyser topic, see `SvmEngine::heartbeats`. Heartbeats keep going while a
+/// blockchain is idle, so consumers only stop seeing them when the engine stops.
+pub fn start<T: Storage + Clone + 'static>(svm: Arc<SvmEngine<T>>, interval: Duration) {
+    rt::spawn(async move {
+        let mut interval = time::interval(interval);
+        loop {
+            interval.tick().await;
+            svm.publish_heartbeats();
+        }
+    });
+}
@@ -0,0 +1,33 @@
+use actix_web::rt::{self, time};
+use std::{sync::Arc, time::Duration};
+
+use crate::{engine::SvmEngine, reporting, storage::Storage};
+
+/// Picks up work other instances forward for the blockchains this one holds,
+/// and renews its leases every third of their TTL. A lease whose holder dies
+/// expires after the TTL, the next instance sending a transaction or producing
+/// a block then takes it over along with the work forwarded meanwhile.
+pub fn start<T: Storage + Clone + 'static>(
+    svm: Arc<SvmEngine<T>>,
+    forward_interval: Duration,
+    lease_ttl: Duration,
+) {
+    let renewer = svm.clone();
+    rt::spawn(async move {
+        let mut interval = time::interval(lease_ttl / 3);
+        loop {
+            interval.tick().await;
+            renewer.renew_leases();
+        }
+    });
+    rt::spawn(async move {
+        let mut interval = time::interval(forward_interval);
+        loop {
+            interval.tick().await;
+            if let Err(e) = svm.run_forwarded().await {
+                println!("Failed to run forwarded work: {}", e);
+                reporting::capture_error(&format!("Failed to run forwarded work: {}", e), &[]);
+            }
+        }
+    });
+}
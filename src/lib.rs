@@ -1,7 +1,25 @@
+pub mod auth;
+pub mod auto_pause;
+pub mod client;
+pub mod embedded;
 pub mod endpoints;
 pub mod engine;
+pub mod expiry;
+pub mod heartbeat;
+pub mod leases;
+pub mod metrics;
+pub mod openapi;
+pub mod reporting;
 pub mod rpc;
+pub mod rpc_log;
+pub mod settings;
+pub mod shutdown;
 pub mod storage;
+pub mod sweep;
+pub mod ticker;
+pub mod top_ups;
+pub mod warm_pool;
+pub mod webhooks;
 
 extern crate diesel;
 
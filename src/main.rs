@@ -4,35 +4,130 @@ use actix_web::{middleware, rt, web, App, HttpServer};
 use dotenv::dotenv;
 
 use mockchain_engine::{
+    auth, auto_pause,
     endpoints::{
-        create_blockchain, delete_blockchain, delete_blockchains, expire_blockchains,
-        get_blockchains, load_account, load_program, rpc_reqest, rpc_ws,
+        admin_debug_blockchains, admin_delete_blockchain, admin_disable_debug, admin_enable_debug,
+        admin_list_blockchains, admin_pipeline, admin_replay_geyser_events,
+        admin_set_team_auto_pause, admin_set_team_quotas, admin_set_team_rpc_write_guard,
+        admin_stats, admin_transfer_blockchain, airdrop, api_docs, blockchain_timeline,
+        clear_account_label, clone_blockchain, clone_program, create_api_key, create_blockchain,
+        create_blockchain_from_snapshot, create_config, create_top_up, create_webhook,
+        delete_account, delete_blockchain, delete_blockchains, delete_config,
+        delete_config_accounts, delete_top_up, delete_webhook, download_snapshot,
+        expire_blockchains, export_accounts, get_account, get_api_keys, get_audit_logs,
+        get_blockchain, get_blockchains, get_config_blockchains, get_configs, get_program_idl,
+        get_program_logs, get_sysvar_overrides, get_top_ups, get_webhooks, healthz,
+        import_accounts, jit_report, latest_blocks, latest_transactions, list_accounts,
+        load_account, load_program, metrics, mint_tokens, openapi_spec, readyz, revoke_api_key,
+        rpc_reqest, rpc_ws, search_transactions, set_account_label, set_config_accounts,
+        set_mint_authority, set_program_idl, set_sysvar_overrides, set_upgrade_authority,
+        update_api_key, update_blockchain, upload_account, write_lock_report,
     },
-    engine::{SvmEngine, SVM},
-    storage::{self},
+    engine::{queue::KafkaQueue, SvmEngine, SVM},
+    expiry, heartbeat, leases, reporting, rpc_log,
+    settings::{self, EventBusKind, QueueKind, Settings},
+    shutdown::{self, Shutdown},
+    storage::{self, PgStorage},
+    sweep, ticker, top_ups,
+    warm_pool::WarmPool,
+    webhooks,
 };
-use std::{env, sync::Arc};
+use std::sync::Arc;
+use uuid::Uuid;
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
     dotenv().ok();
-    std::env::set_var("RUST_LOG", "debug");
-    env_logger::init();
 
-    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set");
-    let cache_url = env::var("CACHE_URL").expect("CACHE_URL must be set");
-    let rpc_url = env::var("RPC_URL").expect("RPC_URL must be set");
-    let pubsub_url = env::var("PUBSUB_URL").expect("PUBSUB_URL must be set");
-    let storage = storage::PgStorage::new(&database_url, &cache_url, &rpc_url, &pubsub_url);
-    let svm = Arc::new(SvmEngine::new(storage.clone()));
+    let settings = Settings::load().expect("Invalid settings");
+    rpc_log::init(&settings.logging);
+    settings::init(settings.clone());
+    reporting::init(&settings.reporting, &settings.env);
+    let storage_settings = settings.clone();
+    let new_storage = move || {
+        let storage = storage::PgStorage::with_settings(
+            &storage_settings.database_url,
+            &storage_settings.cache_url,
+            &storage_settings.rpc_url,
+            &storage_settings.pubsub_url,
+            &storage_settings.pool,
+        );
+        match storage_settings.event_bus {
+            EventBusKind::Redis => storage.with_event_bus(&storage_settings.cache_url),
+            EventBusKind::Kafka => storage,
+        }
+    };
+    let storage = new_storage();
+    let mut svm = SvmEngine::new(storage.clone());
+    if settings.cluster.leases {
+        let instance = settings.cluster.instance_id.unwrap_or_else(Uuid::new_v4);
+        println!("Coordinating blockchain leases as {}", instance);
+        svm = svm.with_leases(instance, settings.cluster.lease_ttl);
+    }
+    let queue = match settings.queue.kind {
+        QueueKind::Kafka => Some(
+            KafkaQueue::new(&settings.pubsub_url, &settings.queue)
+                .expect("Failed to create transaction queue"),
+        ),
+        QueueKind::Memory => None,
+    };
+    if let Some(queue) = &queue {
+        svm = svm.with_kafka_queue(queue.clone());
+    }
+    let svm = Arc::new(svm);
+    if let Some(queue) = &queue {
+        queue.start(svm.clone());
+    }
+    if settings.cluster.leases {
+        leases::start(
+            svm.clone(),
+            settings.cluster.forward_interval,
+            settings.cluster.lease_ttl,
+        );
+    }
+    match svm.resume_pending_transactions().await {
+        Ok(0) => {}
+        Ok(resumed) => println!("Resumed {} queued transactions", resumed),
+        Err(e) => println!("Failed to resume queued transactions: {}", e),
+    }
+    let shutdown = Shutdown::default();
+    if settings.features.webhooks {
+        webhooks::start(storage.clone());
+    }
+    auth::jwt::start(settings.jwt.clone());
+    if let Some(interval) = settings.jobs.expiry_interval {
+        expiry::start(svm.clone(), interval);
+    }
+    if let Some(interval) = settings.jobs.block_interval {
+        ticker::start(svm.clone(), interval);
+    }
+    if let Some(interval) = settings.jobs.auto_pause_interval {
+        auto_pause::start(svm.clone(), interval);
+    }
+    if let Some(interval) = settings.jobs.top_up_interval {
+        top_ups::start(svm.clone(), interval);
+    }
+    if let Some(interval) = settings.jobs.sweep_interval {
+        sweep::start(svm.clone(), interval);
+    }
+    if let Some(interval) = settings.jobs.heartbeat_interval {
+        heartbeat::start(svm.clone(), interval);
+    }
+    let warm_pool = match settings.jobs.warm_pool_size {
+        0 => WarmPool::default(),
+        size => WarmPool::start(svm.clone(), size, settings.jobs.warm_pool_interval),
+    };
 
-    if env::var("ENV").unwrap_or_else(|_| "prod".to_string()) == "dev" {
+    if settings.is_dev() {
+        let shutdown = shutdown.clone();
+        let dev_ws_bind = settings.server.dev_ws_bind.clone();
         rt::spawn(async move {
-            let storage = storage::PgStorage::new(&database_url, &cache_url, &rpc_url, &pubsub_url);
+            let storage = new_storage();
             let svm = Arc::new(SvmEngine::new(storage.clone()));
             HttpServer::new(move || {
                 App::new()
                     .app_data(web::Data::new(svm.clone())) // Share dependencies
+                    .app_data(web::Data::new(shutdown.clone()))
                     .wrap(middleware::Logger::default())
                     .wrap(
                         Cors::default()
@@ -41,17 +136,21 @@ async fn main() -> std::io::Result<()> {
                             .allow_any_header()
                             .supports_credentials(),
                     )
-                    .route("/rpc/{id}", web::get().to(rpc_ws))
+                    .route("/rpc/{id}", web::get().to(rpc_ws::<PgStorage>))
             })
-            .bind(("0.0.0.0", 8900))?
+            .bind(dev_ws_bind)?
             .run()
             .await
         });
     }
 
-    HttpServer::new(move || {
+    let app_svm = svm.clone();
+    let app_shutdown = shutdown.clone();
+    let mut server = HttpServer::new(move || {
         App::new()
-            .app_data(web::Data::new(svm.clone())) // Share dependencies
+            .app_data(web::Data::new(app_svm.clone())) // Share dependencies
+            .app_data(web::Data::new(app_shutdown.clone()))
+            .app_data(web::Data::new(warm_pool.clone()))
             .wrap(middleware::Logger::default())
             .wrap(
                 Cors::default()
@@ -62,20 +161,100 @@ async fn main() -> std::io::Result<()> {
             )
             .service(
                 web::resource("/rpc/{id}")
-                    .route(web::get().to(rpc_ws))
+                    .route(web::get().to(rpc_ws::<PgStorage>))
                     .route(web::delete().to(delete_blockchain))
-                    .route(web::post().to(rpc_reqest)),
+                    .route(web::post().to(rpc_reqest::<PgStorage>)),
             )
+            .service(create_blockchain_from_snapshot)
             .service(create_blockchain)
             .service(get_blockchains)
+            .service(get_blockchain)
+            .service(update_blockchain)
+            .service(clone_blockchain)
+            .service(download_snapshot)
             .service(expire_blockchains)
             .service(load_program)
+            .service(clone_program)
+            .service(set_program_idl)
+            .service(set_upgrade_authority)
+            .service(get_program_idl)
+            .service(get_program_logs)
             .service(delete_blockchains)
             .service(load_program)
+            .service(import_accounts)
+            .service(upload_account)
             .service(load_account)
+            .service(airdrop)
+            .service(mint_tokens)
+            .service(set_mint_authority)
+            .service(create_webhook)
+            .service(get_webhooks)
+            .service(delete_webhook)
+            .service(create_top_up)
+            .service(get_top_ups)
+            .service(delete_top_up)
+            .service(create_api_key)
+            .service(get_api_keys)
+            .service(update_api_key)
+            .service(revoke_api_key)
+            .service(get_audit_logs)
+            .service(list_accounts)
+            // Before get_account, which would take "export" as an address
+            .service(export_accounts)
+            .service(get_account)
+            .service(delete_account)
+            .service(set_account_label)
+            .service(clear_account_label)
+            .service(search_transactions)
+            .service(latest_blocks)
+            .service(latest_transactions)
+            .service(write_lock_report)
+            .service(blockchain_timeline)
+            .service(jit_report)
+            .service(get_sysvar_overrides)
+            .service(set_sysvar_overrides)
+            .service(create_config)
+            .service(get_configs)
+            .service(delete_config)
+            .service(set_config_accounts)
+            .service(delete_config_accounts)
+            .service(get_config_blockchains)
+            .service(admin_list_blockchains)
+            .service(admin_delete_blockchain)
+            .service(admin_transfer_blockchain)
+            .service(admin_replay_geyser_events)
+            .service(admin_set_team_quotas)
+            .service(admin_set_team_auto_pause)
+            .service(admin_set_team_rpc_write_guard)
+            .service(admin_stats)
+            .service(admin_pipeline)
+            .service(admin_debug_blockchains)
+            .service(admin_enable_debug)
+            .service(admin_disable_debug)
+            .service(healthz)
+            .service(readyz)
+            .service(metrics)
+            .service(openapi_spec)
+            .service(api_docs)
     })
-    .bind(("0.0.0.0", 8899))?
-    .bind(("::", 9001))?
-    .run()
-    .await
+    // Signals are handled below, so queues drain before the workers stop
+    .disable_signals();
+    if let Some(workers) = settings.server.workers {
+        server = server.workers(workers);
+    }
+    for address in &settings.server.bind {
+        server = server.bind(address)?;
+    }
+    let server = server.run();
+
+    let handle = server.handle();
+    rt::spawn(async move {
+        shutdown::signal().await;
+        println!("Shutting down, draining transaction queues");
+        shutdown.trigger();
+        svm.drain(settings.jobs.shutdown_timeout).await;
+        handle.stop(true).await;
+    });
+
+    server.await
 }
@@ -0,0 +1,433 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt::Write,
+    sync::{Mutex, OnceLock},
+    time::Duration,
+};
+
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::engine::QueueStats;
+
+/// Upper bounds of the latency histogram buckets, in seconds.
+pub const LATENCY_BUCKETS: [f64; 12] = [
+    0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 10.0,
+];
+
+static RPC: OnceLock<Mutex<HashMap<RpcKey, RpcStats>>> = OnceLock::new();
+// Transaction pipeline stage durations, by blockchain and stage
+static PIPELINE: OnceLock<Mutex<HashMap<(Uuid, &'static str), Histogram>>> = OnceLock::new();
+// Accounts JIT blockchains read and fetched upstream, by blockchain
+static JIT: OnceLock<Mutex<HashMap<Uuid, JitStats>>> = OnceLock::new();
+
+/// Upstream fetches kept per blockchain for its JIT report.
+pub const JIT_RECENT_FETCHES: usize = 100;
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+struct RpcKey {
+    blockchain: Uuid,
+    transport: &'static str,
+    method: String,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Histogram {
+    /// Non-cumulative, one per bucket plus one for values above the last bound.
+    buckets: [u64; LATENCY_BUCKETS.len() + 1],
+    sum: f64,
+    count: u64,
+}
+
+impl Histogram {
+    pub fn observe(&mut self, value: Duration) {
+        let seconds = value.as_secs_f64();
+        let bucket = LATENCY_BUCKETS
+            .iter()
+            .position(|bound| seconds <= *bound)
+            .unwrap_or(LATENCY_BUCKETS.len());
+        self.buckets[bucket] += 1;
+        self.sum += seconds;
+        self.count += 1;
+    }
+
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    pub fn sum(&self) -> Duration {
+        Duration::from_secs_f64(self.sum)
+    }
+
+    fn render(&self, out: &mut String, name: &str, labels: &str) {
+        let mut cumulative = 0;
+        for (bound, count) in LATENCY_BUCKETS.iter().zip(self.buckets) {
+            cumulative += count;
+            let _ = writeln!(
+                out,
+                "{}_bucket{{{},le=\"{}\"}} {}",
+                name, labels, bound, cumulative
+            );
+        }
+        let _ = writeln!(
+            out,
+            "{}_bucket{{{},le=\"+Inf\"}} {}",
+            name, labels, self.count
+        );
+        let _ = writeln!(out, "{}_sum{{{}}} {}", name, labels, self.sum);
+        let _ = writeln!(out, "{}_count{{{}}} {}", name, labels, self.count);
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct RpcStats {
+    pub latency: Histogram,
+    pub errors: u64,
+    pub request_bytes: u64,
+    pub response_bytes: u64,
+}
+
+/// Records one RPC call, `transport` is `http` or `ws`.
+pub fn record_rpc(
+    blockchain: Uuid,
+    transport: &'static str,
+    method: &str,
+    elapsed: Duration,
+    failed: bool,
+    request_bytes: usize,
+    response_bytes: usize,
+) {
+    let key = RpcKey {
+        blockchain,
+        transport,
+        method: method.to_string(),
+    };
+    let mut rpc = RPC.get_or_init(Default::default).lock().unwrap();
+    let stats = rpc.entry(key).or_default();
+    stats.latency.observe(elapsed);
+    if failed {
+        stats.errors += 1;
+    }
+    stats.request_bytes += request_bytes as u64;
+    stats.response_bytes += response_bytes as u64;
+}
+
+/// Stats recorded for a blockchain's method, mostly for tests.
+pub fn rpc_stats(blockchain: Uuid, transport: &'static str, method: &str) -> Option<RpcStats> {
+    let key = RpcKey {
+        blockchain,
+        transport,
+        method: method.to_string(),
+    };
+    RPC.get_or_init(Default::default)
+        .lock()
+        .unwrap()
+        .get(&key)
+        .cloned()
+}
+
+/// Records how long a transaction spent in a pipeline stage.
+pub fn record_stage(blockchain: Uuid, stage: &'static str, elapsed: Duration) {
+    PIPELINE
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap()
+        .entry((blockchain, stage))
+        .or_default()
+        .observe(elapsed);
+}
+
+/// Durations recorded for each stage of a blockchain's pipeline.
+pub fn stage_stats(blockchain: Uuid) -> Vec<(&'static str, Histogram)> {
+    let pipeline = PIPELINE.get_or_init(Default::default).lock().unwrap();
+    let mut stages: Vec<_> = pipeline
+        .iter()
+        .filter(|((id, _), _)| *id == blockchain)
+        .map(|((_, stage), histogram)| (*stage, histogram.clone()))
+        .collect();
+    stages.sort_by_key(|(stage, _)| *stage);
+    stages
+}
+
+/// An account a JIT blockchain didn't have, as fetched upstream.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct JitFetch {
+    pub pubkey: String,
+    /// Whether the account exists upstream, only then is it stored
+    pub found: bool,
+    pub owner: Option<String>,
+    pub bytes: usize,
+    /// Of the upstream request the account was part of
+    pub latency_ms: u64,
+    pub fetched_at: chrono::NaiveDateTime,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct JitStats {
+    /// Requested accounts already stored
+    pub hits: u64,
+    /// Requested accounts fetched upstream
+    pub misses: u64,
+    /// Misses that existed upstream and were stored
+    pub found: u64,
+    /// Account data stored from upstream
+    pub bytes: u64,
+    pub errors: u64,
+    pub upstream_latency: Histogram,
+    /// Newest last, up to `JIT_RECENT_FETCHES`
+    pub recent: VecDeque<JitFetch>,
+}
+
+/// Records a JIT read, `fetches` are the accounts it fetched upstream in `elapsed`.
+pub fn record_jit(blockchain: Uuid, hits: usize, fetches: Vec<JitFetch>, elapsed: Duration) {
+    let mut jit = JIT.get_or_init(Default::default).lock().unwrap();
+    let stats = jit.entry(blockchain).or_default();
+    stats.hits += hits as u64;
+    if fetches.is_empty() {
+        return;
+    }
+    stats.upstream_latency.observe(elapsed);
+    for fetch in fetches {
+        stats.misses += 1;
+        if fetch.found {
+            stats.found += 1;
+            stats.bytes += fetch.bytes as u64;
+        }
+        if stats.recent.len() >= JIT_RECENT_FETCHES {
+            stats.recent.pop_front();
+        }
+        stats.recent.push_back(fetch);
+    }
+}
+
+/// Records an upstream fetch that failed after `elapsed`.
+pub fn record_jit_error(blockchain: Uuid, elapsed: Duration) {
+    let mut jit = JIT.get_or_init(Default::default).lock().unwrap();
+    let stats = jit.entry(blockchain).or_default();
+    stats.upstream_latency.observe(elapsed);
+    stats.errors += 1;
+}
+
+/// What this instance fetched for a JIT blockchain.
+pub fn jit_stats(blockchain: Uuid) -> Option<JitStats> {
+    JIT.get_or_init(Default::default)
+        .lock()
+        .unwrap()
+        .get(&blockchain)
+        .cloned()
+}
+
+/// Drops a deleted blockchain's series so they don't accumulate forever.
+pub fn forget_blockchain(blockchain: Uuid) {
+    RPC.get_or_init(Default::default)
+        .lock()
+        .unwrap()
+        .retain(|key, _| key.blockchain != blockchain);
+    PIPELINE
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap()
+        .retain(|(id, _), _| *id != blockchain);
+    JIT.get_or_init(Default::default)
+        .lock()
+        .unwrap()
+        .remove(&blockchain);
+}
+
+struct ByteCounter(usize);
+
+impl std::io::Write for ByteCounter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0 += buf.len();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Serialized JSON length of `value`, without buffering it.
+pub fn json_size<T: serde::Serialize>(value: &T) -> usize {
+    let mut counter = ByteCounter(0);
+    let _ = serde_json::to_writer(&mut counter, value);
+    counter.0
+}
+
+/// Name, help text and value of each per-method counter.
+type Counter = (&'static str, &'static str, fn(&RpcStats) -> u64);
+
+/// Name, help text and value of each per-blockchain JIT counter.
+type JitCounter = (&'static str, &'static str, fn(&JitStats) -> u64);
+
+const JIT_COUNTERS: [JitCounter; 5] = [
+    (
+        "jit_account_hits_total",
+        "Accounts JIT blockchains read that were already stored.",
+        |s| s.hits,
+    ),
+    (
+        "jit_account_misses_total",
+        "Accounts JIT blockchains fetched upstream.",
+        |s| s.misses,
+    ),
+    (
+        "jit_accounts_found_total",
+        "Fetched accounts that existed upstream and were stored.",
+        |s| s.found,
+    ),
+    (
+        "jit_stored_bytes_total",
+        "Account data stored from upstream.",
+        |s| s.bytes,
+    ),
+    (
+        "jit_upstream_errors_total",
+        "Upstream fetches that failed.",
+        |s| s.errors,
+    ),
+];
+
+const RPC_COUNTERS: [Counter; 4] = [
+    ("rpc_requests_total", "RPC requests by method.", |s| {
+        s.latency.count()
+    }),
+    (
+        "rpc_errors_total",
+        "RPC requests answered with an error.",
+        |s| s.errors,
+    ),
+    (
+        "rpc_request_bytes_total",
+        "Size of RPC request payloads.",
+        |s| s.request_bytes,
+    ),
+    (
+        "rpc_response_bytes_total",
+        "Size of RPC response payloads.",
+        |s| s.response_bytes,
+    ),
+];
+
+/// Every metric in the Prometheus text exposition format.
+pub fn render() -> String {
+    let mut out = String::new();
+    let rpc = RPC.get_or_init(Default::default).lock().unwrap();
+    let mut series: Vec<_> = rpc.iter().collect();
+    series.sort_by_key(|(key, _)| *key);
+    let labels = |key: &RpcKey| {
+        format!(
+            "blockchain=\"{}\",transport=\"{}\",method=\"{}\"",
+            key.blockchain, key.transport, key.method
+        )
+    };
+
+    let _ = writeln!(
+        out,
+        "# HELP rpc_request_duration_seconds RPC request latency by method."
+    );
+    let _ = writeln!(out, "# TYPE rpc_request_duration_seconds histogram");
+    for (key, stats) in &series {
+        stats
+            .latency
+            .render(&mut out, "rpc_request_duration_seconds", &labels(key));
+    }
+    for (name, help, value) in RPC_COUNTERS {
+        let _ = writeln!(out, "# HELP {} {}", name, help);
+        let _ = writeln!(out, "# TYPE {} counter", name);
+        for (key, stats) in &series {
+            let _ = writeln!(out, "{}{{{}}} {}", name, labels(key), value(stats));
+        }
+    }
+    drop(rpc);
+
+    let pipeline = PIPELINE.get_or_init(Default::default).lock().unwrap();
+    let mut stages: Vec<_> = pipeline.iter().collect();
+    stages.sort_by_key(|(key, _)| *key);
+    let _ = writeln!(
+        out,
+        "# HELP pipeline_stage_duration_seconds Time transactions spend in each pipeline stage."
+    );
+    let _ = writeln!(out, "# TYPE pipeline_stage_duration_seconds histogram");
+    for ((blockchain, stage), histogram) in stages {
+        histogram.render(
+            &mut out,
+            "pipeline_stage_duration_seconds",
+            &format!("blockchain=\"{}\",stage=\"{}\"", blockchain, stage),
+        );
+    }
+    drop(pipeline);
+
+    let jit = JIT.get_or_init(Default::default).lock().unwrap();
+    let mut blockchains: Vec<_> = jit.iter().collect();
+    blockchains.sort_by_key(|(blockchain, _)| *blockchain);
+    let _ = writeln!(
+        out,
+        "# HELP jit_upstream_duration_seconds Latency of upstream fetches for JIT blockchains."
+    );
+    let _ = writeln!(out, "# TYPE jit_upstream_duration_seconds histogram");
+    for (blockchain, stats) in &blockchains {
+        stats.upstream_latency.render(
+            &mut out,
+            "jit_upstream_duration_seconds",
+            &format!("blockchain=\"{}\"", blockchain),
+        );
+    }
+    for (name, help, value) in JIT_COUNTERS {
+        let _ = writeln!(out, "# HELP {} {}", name, help);
+        let _ = writeln!(out, "# TYPE {} counter", name);
+        for (blockchain, stats) in &blockchains {
+            let _ = writeln!(
+                out,
+                "{}{{blockchain=\"{}\"}} {}",
+                name,
+                blockchain,
+                value(stats)
+            );
+        }
+    }
+    out
+}
+
+/// Queue gauges, read from the engine when scraped.
+pub fn render_queues(queues: &[QueueStats]) -> String {
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        "# HELP pipeline_queue_length Transactions waiting in a blockchain's queue."
+    );
+    let _ = writeln!(out, "# TYPE pipeline_queue_length gauge");
+    for queue in queues {
+        let _ = writeln!(
+            out,
+            "pipeline_queue_length{{blockchain=\"{}\"}} {}",
+            queue.blockchain, queue.queued
+        );
+    }
+    let _ = writeln!(
+        out,
+        "# HELP pipeline_oldest_queued_seconds Age of the oldest transaction waiting in a queue."
+    );
+    let _ = writeln!(out, "# TYPE pipeline_oldest_queued_seconds gauge");
+    for queue in queues {
+        let age = queue.oldest_queued_ms.unwrap_or(0) as f64 / 1000.0;
+        let _ = writeln!(
+            out,
+            "pipeline_oldest_queued_seconds{{blockchain=\"{}\"}} {}",
+            queue.blockchain, age
+        );
+    }
+    let _ = writeln!(
+        out,
+        "# HELP pipeline_queue_rejected_total Transactions turned away by a full queue."
+    );
+    let _ = writeln!(out, "# TYPE pipeline_queue_rejected_total counter");
+    for queue in queues {
+        let _ = writeln!(
+            out,
+            "pipeline_queue_rejected_total{{blockchain=\"{}\"}} {}",
+            queue.blockchain, queue.rejected
+        );
+    }
+    out
+}
@@ -0,0 +1,972 @@
+//! OpenAPI document for the management API, served at `/openapi.json` with a Swagger
+//! UI at `/docs`. Routes are listed by hand in `OPERATIONS`, a test checks them against
+//! the handlers registered in main.rs.
+
+use serde_json::{json, Map, Value};
+
+/// Credentials an operation accepts.
+#[derive(Clone, Copy)]
+pub enum Security {
+    None,
+    /// Team API key or JWT, with the role the HTTP method requires.
+    Team,
+    /// Team API key or JWT with the owner role.
+    Owner,
+    /// The `admin_token` header.
+    Admin,
+}
+
+#[derive(Clone, Copy)]
+pub enum Body {
+    None,
+    /// JSON body, by component schema name.
+    Json(&'static str),
+    /// Optional JSON body, by component schema name.
+    OptionalJson(&'static str),
+    /// JSON array of the component schema.
+    JsonArray(&'static str),
+    /// Multipart form, by component schema name.
+    Multipart(&'static str),
+}
+
+/// A query parameter, with its JSON schema type and format.
+pub type QueryParam = (&'static str, &'static str, Option<&'static str>);
+
+pub struct Operation {
+    pub method: &'static str,
+    pub path: &'static str,
+    pub tag: &'static str,
+    pub summary: &'static str,
+    pub security: Security,
+    pub body: Body,
+    pub query: &'static [QueryParam],
+}
+
+const PAGE: [QueryParam; 2] = [("limit", "integer", None), ("offset", "integer", None)];
+
+pub const OPERATIONS: &[Operation] = &[
+    Operation {
+        method: "post",
+        path: "/rpc/{id}",
+        tag: "rpc",
+        summary: "Solana JSON-RPC request",
+        security: Security::None,
+        body: Body::Json("RpcRequest"),
+        query: &[],
+    },
+    Operation {
+        method: "get",
+        path: "/rpc/{id}",
+        tag: "rpc",
+        summary: "Solana JSON-RPC websocket subscriptions",
+        security: Security::None,
+        body: Body::None,
+        query: &[],
+    },
+    Operation {
+        method: "delete",
+        path: "/rpc/{id}",
+        tag: "blockchains",
+        summary: "Delete a blockchain",
+        security: Security::Team,
+        body: Body::None,
+        query: &[],
+    },
+    Operation {
+        method: "post",
+        path: "/blockchains",
+        tag: "blockchains",
+        summary: "Create a blockchain, or restore one from a gzipped snapshot body. Retries \
+                  with the same Idempotency-Key header return the blockchain first created",
+        security: Security::Team,
+        body: Body::OptionalJson("CreateBlockchain"),
+        query: &[],
+    },
+    Operation {
+        method: "get",
+        path: "/blockchains",
+        tag: "blockchains",
+        summary: "List the team's blockchains",
+        security: Security::Team,
+        body: Body::None,
+        query: &[
+            ("label", "string", None),
+            ("user_id", "string", None),
+            ("created_after", "string", Some("date-time")),
+            ("expiring_before", "string", Some("date-time")),
+            PAGE[0],
+            PAGE[1],
+        ],
+    },
+    Operation {
+        method: "delete",
+        path: "/blockchains",
+        tag: "blockchains",
+        summary: "Delete all the team's blockchains",
+        security: Security::Team,
+        body: Body::None,
+        query: &[],
+    },
+    Operation {
+        method: "get",
+        path: "/blockchains/{id}",
+        tag: "blockchains",
+        summary: "Get a blockchain",
+        security: Security::Team,
+        body: Body::None,
+        query: &[],
+    },
+    Operation {
+        method: "patch",
+        path: "/blockchains/{id}",
+        tag: "blockchains",
+        summary: "Update a blockchain's label or expiry",
+        security: Security::Team,
+        body: Body::Json("UpdateBlockchain"),
+        query: &[],
+    },
+    Operation {
+        method: "post",
+        path: "/blockchains/{id}/clone",
+        tag: "blockchains",
+        summary: "Copy a blockchain's accounts into a new blockchain",
+        security: Security::Team,
+        body: Body::OptionalJson("CloneBlockchain"),
+        query: &[],
+    },
+    Operation {
+        method: "get",
+        path: "/blockchains/{id}/snapshot",
+        tag: "blockchains",
+        summary: "Download a gzipped snapshot of a blockchain's accounts",
+        security: Security::Team,
+        body: Body::None,
+        query: &[],
+    },
+    Operation {
+        method: "get",
+        path: "/blockchains/{id}/blocks/latest",
+        tag: "blockchains",
+        summary: "Latest blocks",
+        security: Security::Team,
+        body: Body::None,
+        query: &[("limit", "integer", None)],
+    },
+    Operation {
+        method: "get",
+        path: "/blockchains/{id}/transactions/latest",
+        tag: "blockchains",
+        summary: "Latest transactions",
+        security: Security::Team,
+        body: Body::None,
+        query: &[("limit", "integer", None)],
+    },
+    Operation {
+        method: "get",
+        path: "/blockchains/{id}/write_locks",
+        tag: "blockchains",
+        summary: "Most write-locked accounts, with how often transactions of the same slot contended for them",
+        security: Security::Team,
+        body: Body::None,
+        query: &[("limit", "integer", None)],
+    },
+    Operation {
+        method: "get",
+        path: "/blockchains/{id}/timeline",
+        tag: "blockchains",
+        summary: "Significant events of a blockchain newest first, from its audit log and transactions",
+        security: Security::Team,
+        body: Body::None,
+        query: &[
+            ("since", "string", Some("date-time")),
+            ("limit", "integer", None),
+        ],
+    },
+    Operation {
+        method: "get",
+        path: "/blockchains/{id}/jit",
+        tag: "blockchains",
+        summary: "Accounts fetched upstream for a JIT blockchain, with hit rates and upstream latency",
+        security: Security::Team,
+        body: Body::None,
+        query: &[],
+    },
+    Operation {
+        method: "get",
+        path: "/blockchains/{id}/sysvars",
+        tag: "blockchains",
+        summary: "Sysvar values the blockchain sets in place of the defaults",
+        security: Security::Team,
+        body: Body::None,
+        query: &[],
+    },
+    Operation {
+        method: "put",
+        path: "/blockchains/{id}/sysvars",
+        tag: "blockchains",
+        summary: "Replace the blockchain's clock and epoch schedule overrides",
+        security: Security::Team,
+        body: Body::Json("SysvarOverrides"),
+        query: &[],
+    },
+    Operation {
+        method: "post",
+        path: "/blockchains/expire",
+        tag: "admin",
+        summary: "Delete expired blockchains",
+        security: Security::Admin,
+        body: Body::None,
+        query: &[],
+    },
+    Operation {
+        method: "post",
+        path: "/programs/{id}",
+        tag: "programs",
+        summary: "Deploy a program from a .so file",
+        security: Security::Team,
+        body: Body::Multipart("LoadProgram"),
+        query: &[],
+    },
+    Operation {
+        method: "post",
+        path: "/programs/{id}/clone",
+        tag: "programs",
+        summary: "Copy a program from the upstream RPC",
+        security: Security::Team,
+        body: Body::Json("CloneProgram"),
+        query: &[],
+    },
+    Operation {
+        method: "put",
+        path: "/programs/{id}/{program_id}/idl",
+        tag: "programs",
+        summary: "Set a program's Anchor IDL",
+        security: Security::Team,
+        body: Body::Json("SetProgramIdl"),
+        query: &[],
+    },
+    Operation {
+        method: "put",
+        path: "/programs/{id}/{program_id}/upgrade_authority",
+        tag: "programs",
+        summary: "Make a key an upgradeable program's upgrade authority",
+        security: Security::Team,
+        body: Body::Json("SetUpgradeAuthority"),
+        query: &[],
+    },
+    Operation {
+        method: "get",
+        path: "/programs/{id}/{program_id}/idl",
+        tag: "programs",
+        summary: "Get a program's Anchor IDL",
+        security: Security::Team,
+        body: Body::None,
+        query: &[],
+    },
+    Operation {
+        method: "get",
+        path: "/programs/{id}/{program_id}/logs",
+        tag: "programs",
+        summary: "List the log lines a program emitted, oldest first",
+        security: Security::Team,
+        body: Body::None,
+        query: &[
+            ("start", "string", Some("date-time")),
+            ("end", "string", Some("date-time")),
+            PAGE[0],
+        ],
+    },
+    Operation {
+        method: "put",
+        path: "/accounts/{id}",
+        tag: "accounts",
+        summary: "Load accounts from JSON, a multipart upload or NDJSON",
+        security: Security::Team,
+        body: Body::JsonArray("Account"),
+        query: &[],
+    },
+    Operation {
+        method: "get",
+        path: "/accounts/{id}",
+        tag: "accounts",
+        summary: "List accounts",
+        security: Security::Team,
+        body: Body::None,
+        query: &[
+            ("owner", "string", None),
+            ("executable", "boolean", None),
+            ("label", "string", None),
+            PAGE[0],
+            PAGE[1],
+        ],
+    },
+    Operation {
+        method: "get",
+        path: "/accounts/{id}/export",
+        tag: "accounts",
+        summary: "Export accounts in the solana CLI JSON format",
+        security: Security::Team,
+        body: Body::None,
+        query: &[("owner", "string", None)],
+    },
+    Operation {
+        method: "get",
+        path: "/accounts/{id}/{pubkey}",
+        tag: "accounts",
+        summary: "Get an account",
+        security: Security::Team,
+        body: Body::None,
+        query: &[],
+    },
+    Operation {
+        method: "delete",
+        path: "/accounts/{id}/{pubkey}",
+        tag: "accounts",
+        summary: "Delete an account",
+        security: Security::Team,
+        body: Body::None,
+        query: &[],
+    },
+    Operation {
+        method: "put",
+        path: "/accounts/{id}/{pubkey}/label",
+        tag: "accounts",
+        summary: "Label an account",
+        security: Security::Team,
+        body: Body::Json("SetAccountLabel"),
+        query: &[],
+    },
+    Operation {
+        method: "delete",
+        path: "/accounts/{id}/{pubkey}/label",
+        tag: "accounts",
+        summary: "Clear an account's label",
+        security: Security::Team,
+        body: Body::None,
+        query: &[],
+    },
+    Operation {
+        method: "post",
+        path: "/airdrop/{id}",
+        tag: "accounts",
+        summary: "Fund accounts in a single transaction",
+        security: Security::Team,
+        body: Body::JsonArray("Airdrop"),
+        query: &[],
+    },
+    Operation {
+        method: "post",
+        path: "/tokens/{id}/mint",
+        tag: "accounts",
+        summary: "Mint tokens to an owner's associated token account",
+        security: Security::Team,
+        body: Body::Json("MintTokens"),
+        query: &[],
+    },
+    Operation {
+        method: "put",
+        path: "/tokens/{id}/{mint}/authority",
+        tag: "accounts",
+        summary: "Make keys a mint's mint or freeze authority",
+        security: Security::Team,
+        body: Body::Json("SetMintAuthority"),
+        query: &[],
+    },
+    Operation {
+        method: "get",
+        path: "/transactions/{id}",
+        tag: "transactions",
+        summary: "Search transactions",
+        security: Security::Team,
+        body: Body::None,
+        query: &[
+            ("address", "string", None),
+            ("program_id", "string", None),
+            ("status", "string", None),
+            ("start", "string", Some("date-time")),
+            ("end", "string", Some("date-time")),
+            ("signature_prefix", "string", None),
+            ("fee_payer", "string", None),
+            ("is_vote", "boolean", None),
+            ("min_compute_unit_price", "integer", Some("int64")),
+            PAGE[0],
+            PAGE[1],
+        ],
+    },
+    Operation {
+        method: "post",
+        path: "/webhooks/{id}",
+        tag: "webhooks",
+        summary: "Create a webhook",
+        security: Security::Team,
+        body: Body::Json("CreateWebhook"),
+        query: &[],
+    },
+    Operation {
+        method: "get",
+        path: "/webhooks/{id}",
+        tag: "webhooks",
+        summary: "List webhooks",
+        security: Security::Team,
+        body: Body::None,
+        query: &[],
+    },
+    Operation {
+        method: "delete",
+        path: "/webhooks/{id}/{webhook_id}",
+        tag: "webhooks",
+        summary: "Delete a webhook",
+        security: Security::Team,
+        body: Body::None,
+        query: &[],
+    },
+    Operation {
+        method: "post",
+        path: "/top_ups/{id}",
+        tag: "top_ups",
+        summary: "Keep an address topped up to a balance",
+        security: Security::Team,
+        body: Body::Json("CreateTopUp"),
+        query: &[],
+    },
+    Operation {
+        method: "get",
+        path: "/top_ups/{id}",
+        tag: "top_ups",
+        summary: "List top-ups",
+        security: Security::Team,
+        body: Body::None,
+        query: &[],
+    },
+    Operation {
+        method: "delete",
+        path: "/top_ups/{id}/{top_up_id}",
+        tag: "top_ups",
+        summary: "Delete a top-up",
+        security: Security::Team,
+        body: Body::None,
+        query: &[],
+    },
+    Operation {
+        method: "post",
+        path: "/configs",
+        tag: "configs",
+        summary: "Create a blockchain config",
+        security: Security::Team,
+        body: Body::Json("CreateConfig"),
+        query: &[],
+    },
+    Operation {
+        method: "get",
+        path: "/configs",
+        tag: "configs",
+        summary: "List configs",
+        security: Security::Team,
+        body: Body::None,
+        query: &[],
+    },
+    Operation {
+        method: "delete",
+        path: "/configs/{id}",
+        tag: "configs",
+        summary: "Delete a config",
+        security: Security::Team,
+        body: Body::None,
+        query: &[],
+    },
+    Operation {
+        method: "put",
+        path: "/configs/{id}/accounts",
+        tag: "configs",
+        summary: "Set accounts of a config",
+        security: Security::Team,
+        body: Body::JsonArray("Account"),
+        query: &[],
+    },
+    Operation {
+        method: "delete",
+        path: "/configs/{id}/accounts",
+        tag: "configs",
+        summary: "Remove accounts from a config",
+        security: Security::Team,
+        body: Body::Json("DeleteConfigAccounts"),
+        query: &[],
+    },
+    Operation {
+        method: "get",
+        path: "/configs/{id}/blockchains",
+        tag: "configs",
+        summary: "List blockchains created from a config",
+        security: Security::Team,
+        body: Body::None,
+        query: &[],
+    },
+    Operation {
+        method: "post",
+        path: "/api_keys",
+        tag: "api keys",
+        summary: "Create an API key",
+        security: Security::Owner,
+        body: Body::Json("CreateApiKey"),
+        query: &[],
+    },
+    Operation {
+        method: "get",
+        path: "/api_keys",
+        tag: "api keys",
+        summary: "List API keys",
+        security: Security::Owner,
+        body: Body::None,
+        query: &[],
+    },
+    Operation {
+        method: "patch",
+        path: "/api_keys/{key}",
+        tag: "api keys",
+        summary: "Rename an API key",
+        security: Security::Owner,
+        body: Body::Json("UpdateApiKey"),
+        query: &[],
+    },
+    Operation {
+        method: "delete",
+        path: "/api_keys/{key}",
+        tag: "api keys",
+        summary: "Revoke an API key",
+        security: Security::Owner,
+        body: Body::None,
+        query: &[],
+    },
+    Operation {
+        method: "get",
+        path: "/audit_logs",
+        tag: "api keys",
+        summary: "List audit logs",
+        security: Security::Owner,
+        body: Body::None,
+        query: &[
+            ("blockchain", "string", Some("uuid")),
+            ("action", "string", None),
+            ("since", "string", Some("date-time")),
+            PAGE[0],
+            PAGE[1],
+        ],
+    },
+    Operation {
+        method: "get",
+        path: "/admin/blockchains",
+        tag: "admin",
+        summary: "List blockchains of all teams",
+        security: Security::Admin,
+        body: Body::None,
+        query: &[("team_id", "string", Some("uuid")), PAGE[0], PAGE[1]],
+    },
+    Operation {
+        method: "delete",
+        path: "/admin/blockchains/{id}",
+        tag: "admin",
+        summary: "Delete any blockchain",
+        security: Security::Admin,
+        body: Body::None,
+        query: &[],
+    },
+    Operation {
+        method: "post",
+        path: "/admin/blockchains/{id}/transfer",
+        tag: "admin",
+        summary: "Move a blockchain to another team",
+        security: Security::Admin,
+        body: Body::Json("TransferBlockchain"),
+        query: &[],
+    },
+    Operation {
+        method: "post",
+        path: "/admin/blockchains/{id}/geyser/replay",
+        tag: "admin",
+        summary: "Replay a blockchain's geyser block and transaction events from a slot, to Kafka or in the response",
+        security: Security::Admin,
+        body: Body::Json("GeyserReplay"),
+        query: &[],
+    },
+    Operation {
+        method: "put",
+        path: "/admin/teams/{id}/quotas",
+        tag: "admin",
+        summary: "Replace a team's quotas, null removes one",
+        security: Security::Admin,
+        body: Body::Json("TeamQuotas"),
+        query: &[],
+    },
+    Operation {
+        method: "put",
+        path: "/admin/teams/{id}/auto_pause",
+        tag: "admin",
+        summary: "Pause the team's blockchains once idle this many hours, they resume on their next request",
+        security: Security::Admin,
+        body: Body::Json("TeamAutoPause"),
+        query: &[],
+    },
+    Operation {
+        method: "put",
+        path: "/admin/teams/{id}/rpc_write_guard",
+        tag: "admin",
+        summary: "Reject sendTransaction and requestAirdrop on the team's blockchains from API keys of other teams",
+        security: Security::Admin,
+        body: Body::Json("TeamRpcWriteGuard"),
+        query: &[],
+    },
+    Operation {
+        method: "get",
+        path: "/admin/stats",
+        tag: "admin",
+        summary: "Engine statistics",
+        security: Security::Admin,
+        body: Body::None,
+        query: &[],
+    },
+    Operation {
+        method: "get",
+        path: "/admin/pipeline",
+        tag: "admin",
+        summary: "Transaction queue depths",
+        security: Security::Admin,
+        body: Body::None,
+        query: &[],
+    },
+    Operation {
+        method: "get",
+        path: "/admin/debug",
+        tag: "admin",
+        summary: "Blockchains with RPC debug logging",
+        security: Security::Admin,
+        body: Body::None,
+        query: &[],
+    },
+    Operation {
+        method: "put",
+        path: "/admin/debug/{id}",
+        tag: "admin",
+        summary: "Log every RPC body of a blockchain",
+        security: Security::Admin,
+        body: Body::None,
+        query: &[],
+    },
+    Operation {
+        method: "delete",
+        path: "/admin/debug/{id}",
+        tag: "admin",
+        summary: "Stop debug logging for a blockchain",
+        security: Security::Admin,
+        body: Body::None,
+        query: &[],
+    },
+    Operation {
+        method: "get",
+        path: "/healthz",
+        tag: "health",
+        summary: "Liveness probe",
+        security: Security::None,
+        body: Body::None,
+        query: &[],
+    },
+    Operation {
+        method: "get",
+        path: "/readyz",
+        tag: "health",
+        summary: "Readiness probe",
+        security: Security::None,
+        body: Body::None,
+        query: &[],
+    },
+    Operation {
+        method: "get",
+        path: "/metrics",
+        tag: "health",
+        summary: "Prometheus metrics",
+        security: Security::Admin,
+        body: Body::None,
+        query: &[],
+    },
+    Operation {
+        method: "get",
+        path: "/openapi.json",
+        tag: "health",
+        summary: "This document",
+        security: Security::None,
+        body: Body::None,
+        query: &[],
+    },
+    Operation {
+        method: "get",
+        path: "/docs",
+        tag: "health",
+        summary: "Swagger UI for this document",
+        security: Security::None,
+        body: Body::None,
+        query: &[],
+    },
+];
+
+/// Request schemas, named after the request structs in endpoints.rs.
+fn schemas() -> Value {
+    json!({
+        "Error": object(&[("message", string())], &["message"]),
+        "RpcRequest": object(&[
+            ("jsonrpc", string()),
+            ("id", json!({})),
+            ("method", string()),
+            ("params", json!({})),
+        ], &["jsonrpc", "id", "method"]),
+        "CreateBlockchain": object(&[
+            ("config", uuid()),
+            ("defer_account_initailization", boolean()),
+            ("programs", json!({"type": "object", "additionalProperties": string()})),
+            ("mainnet_accounts", json!({"type": "array", "items": string(), "maxItems": 1000})),
+        ], &[]),
+        "UpdateBlockchain": object(&[
+            ("label", string()),
+            ("expiry", date_time()),
+            ("extend_seconds", integer()),
+            ("log_bytes_limit", json!({"type": "integer", "nullable": true})),
+            ("read_only", boolean()),
+            ("sponsor_fees", boolean()),
+            ("parity_check", boolean()),
+        ], &[]),
+        "CloneBlockchain": object(&[("label", string())], &[]),
+        "SysvarOverrides": object(&[
+            ("clock", object(&[
+                ("epoch", integer()),
+                ("leader_schedule_epoch", integer()),
+                ("epoch_start_timestamp", integer()),
+                ("unix_timestamp", integer()),
+            ], &[])),
+            ("epoch_schedule", object(&[
+                ("slots_per_epoch", integer()),
+                ("leader_schedule_slot_offset", integer()),
+                ("warmup", boolean()),
+            ], &["slots_per_epoch"])),
+        ], &[]),
+        "LoadProgram": object(&[
+            ("program_id", string()),
+            ("program", json!({"type": "string", "format": "binary"})),
+            ("upgradeable", boolean()),
+            ("upgrade_authority", string()),
+        ], &["program_id", "program"]),
+        "CloneProgram": object(&[("program_id", string())], &["program_id"]),
+        "SetProgramIdl": object(&[
+            ("idl", json!({"type": "object"})),
+            ("authority", string()),
+            ("on_chain", boolean()),
+        ], &["idl"]),
+        "SetUpgradeAuthority": object(&[("authority", string())], &["authority"]),
+        "Account": object(&[
+            ("address", string()),
+            ("lamports", integer()),
+            ("data", json!({"oneOf": [string(), {"type": "array", "items": string()}]})),
+            ("encoding", string()),
+            ("owner", string()),
+            ("rent_epoch", integer()),
+            ("executable", boolean()),
+            ("token_mint_auth", string()),
+        ], &["address", "lamports", "owner", "rent_epoch", "executable"]),
+        "Airdrop": object(&[
+            ("address", string()),
+            ("lamports", integer()),
+        ], &["address", "lamports"]),
+        "MintTokens": object(&[
+            ("owner", string()),
+            ("amount", integer()),
+            ("mint", string()),
+            ("decimals", integer()),
+        ], &["owner", "amount"]),
+        "SetMintAuthority": object(&[
+            ("mint_authority", string()),
+            ("freeze_authority", string()),
+        ], &[]),
+        "CreateWebhook": object(&[
+            ("url", string()),
+            ("events", json!({"type": "array", "items": string()})),
+            ("accounts", json!({"type": "array", "items": string()})),
+            ("labels", json!({"type": "array", "items": string()})),
+        ], &["url", "events"]),
+        "CreateTopUp": object(&[
+            ("address", string()),
+            ("mint", string()),
+            ("target", integer()),
+            ("interval_seconds", integer()),
+        ], &["address", "target", "interval_seconds"]),
+        "SetAccountLabel": object(&[
+            ("label", string()),
+        ], &["label"]),
+        "CreateConfig": object(&[
+            ("label", string()),
+            ("bundles", json!({"type": "array", "items": {"type": "string", "enum": ["metaplex"]}})),
+        ], &["label"]),
+        "DeleteConfigAccounts": object(&[
+            ("addresses", json!({"type": "array", "items": string()})),
+        ], &["addresses"]),
+        "CreateApiKey": object(&[
+            ("label", string()),
+            ("scope", string()),
+            ("role", json!({"type": "string", "enum": ["read_only", "developer", "owner"]})),
+        ], &["label"]),
+        "UpdateApiKey": object(&[("label", string())], &["label"]),
+        "TransferBlockchain": object(&[("team_id", uuid())], &["team_id"]),
+        "GeyserReplay": object(&[
+            ("from_slot", integer()),
+            ("limit", integer()),
+            ("target", json!({"type": "string", "enum": ["kafka", "response"]})),
+        ], &["from_slot"]),
+        "TeamQuotas": object(&[
+            ("max_blockchains", integer()),
+            ("max_accounts", integer()),
+            ("max_transactions_per_day", integer()),
+        ], &[]),
+        "TeamAutoPause": object(&[("idle_hours", integer())], &[]),
+        "TeamRpcWriteGuard": object(&[("enabled", boolean())], &["enabled"]),
+    })
+}
+
+fn object(properties: &[(&str, Value)], required: &[&str]) -> Value {
+    let properties: Map<String, Value> = properties
+        .iter()
+        .map(|(name, schema)| (name.to_string(), schema.clone()))
+        .collect();
+    json!({"type": "object", "properties": properties, "required": required})
+}
+
+fn string() -> Value {
+    json!({"type": "string"})
+}
+
+fn integer() -> Value {
+    json!({"type": "integer"})
+}
+
+fn boolean() -> Value {
+    json!({"type": "boolean"})
+}
+
+fn uuid() -> Value {
+    json!({"type": "string", "format": "uuid"})
+}
+
+fn date_time() -> Value {
+    json!({"type": "string", "format": "date-time"})
+}
+
+fn schema_ref(name: &str) -> Value {
+    json!({"$ref": format!("#/components/schemas/{}", name)})
+}
+
+/// Path parameters, from the `{name}` segments of the path.
+fn path_params(path: &str) -> Vec<Value> {
+    path.split('/')
+        .filter_map(|segment| segment.strip_prefix('{')?.strip_suffix('}'))
+        .map(|name| {
+            let schema = match name {
+                "program_id" | "pubkey" => string(),
+                _ => uuid(),
+            };
+            json!({"name": name, "in": "path", "required": true, "schema": schema})
+        })
+        .collect()
+}
+
+fn operation(op: &Operation) -> Value {
+    let mut parameters = path_params(op.path);
+    parameters.extend(op.query.iter().map(|(name, kind, format)| {
+        let mut schema = json!({"type": kind});
+        if let Some(format) = format {
+            schema["format"] = json!(format);
+        }
+        json!({"name": name, "in": "query", "required": false, "schema": schema})
+    }));
+
+    let error = json!({
+        "description": "Error",
+        "content": {"application/json": {"schema": schema_ref("Error")}},
+    });
+    let mut value = json!({
+        "tags": [op.tag],
+        "summary": op.summary,
+        "parameters": parameters,
+        "responses": {
+            "200": {"description": "OK"},
+            "400": error.clone(),
+            "401": error,
+        },
+    });
+    let body = match op.body {
+        Body::None => None,
+        Body::Json(name) => Some(("application/json", schema_ref(name), true)),
+        Body::OptionalJson(name) => Some(("application/json", schema_ref(name), false)),
+        Body::JsonArray(name) => Some((
+            "application/json",
+            json!({"type": "array", "items": schema_ref(name)}),
+            true,
+        )),
+        Body::Multipart(name) => Some(("multipart/form-data", schema_ref(name), true)),
+    };
+    if let Some((content_type, schema, required)) = body {
+        value["requestBody"] = json!({
+            "required": required,
+            "content": {content_type: {"schema": schema}},
+        });
+    }
+    let security = match op.security {
+        Security::None => json!([]),
+        Security::Team | Security::Owner => json!([{"api_key": []}, {"bearer": []}]),
+        Security::Admin => json!([{"admin_token": []}]),
+    };
+    value["security"] = security;
+    if let Security::Owner = op.security {
+        value["description"] = json!("Requires the owner role.");
+    }
+    value
+}
+
+/// The OpenAPI 3 document.
+pub fn spec() -> Value {
+    let mut paths = Map::new();
+    for op in OPERATIONS {
+        let path = paths
+            .entry(op.path.to_string())
+            .or_insert_with(|| json!({}));
+        path[op.method] = operation(op);
+    }
+    json!({
+        "openapi": "3.0.3",
+        "info": {
+            "title": "Mirror Engine",
+            "version": env!("CARGO_PKG_VERSION"),
+        },
+        "paths": paths,
+        "components": {
+            "schemas": schemas(),
+            "securitySchemes": {
+                "api_key": {"type": "apiKey", "in": "header", "name": "api_key"},
+                "bearer": {"type": "http", "scheme": "bearer", "bearerFormat": "JWT"},
+                "admin_token": {"type": "apiKey", "in": "header", "name": "admin_token"},
+            },
+        },
+    })
+}
+
+/// Swagger UI page loading the document from `/openapi.json`.
+pub const SWAGGER_UI: &str = r##"<!DOCTYPE html>
+<html>
+<head>
+  <meta charset="utf-8" />
+  <title>Mirror Engine API</title>
+  <link rel="stylesheet" href="https://unpkg.com/swagger-ui-dist@5/swagger-ui.css" />
+</head>
+<body>
+  <div id="swagger-ui"></div>
+  <script src="https://unpkg.com/swagger-ui-dist@5/swagger-ui-bundle.js"></script>
+  <script>
+    window.ui = SwaggerUIBundle({ url: "/openapi.json", dom_id: "#swagger-ui" });
+  </script>
+</body>
+</html>
+"##;
@@ -0,0 +1,212 @@
+use actix_web::rt;
+use serde_json::{json, Map, Value};
+use std::{
+    backtrace::Backtrace,
+    future::Future,
+    panic,
+    str::FromStr,
+    sync::OnceLock,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use tokio::sync::mpsc;
+use uuid::Uuid;
+
+use crate::settings::ReportingSettings;
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+static REPORTER: OnceLock<Reporter> = OnceLock::new();
+
+/// Tags attached to everything reported from inside a `scope`, such as the blockchain
+/// and transaction being worked on.
+pub type Context = Vec<(&'static str, String)>;
+
+tokio::task_local! {
+    static CONTEXT: Context;
+}
+
+/// Where events are sent, parsed from `https://<key>@<host>/<project>`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Dsn {
+    public_key: String,
+    // Scheme, host, port and any path before the project id
+    base: String,
+    project_id: String,
+}
+
+impl FromStr for Dsn {
+    type Err = String;
+
+    fn from_str(dsn: &str) -> Result<Self, Self::Err> {
+        let url = reqwest::Url::parse(dsn).map_err(|e| format!("Invalid DSN: {}", e))?;
+        if url.username().is_empty() {
+            return Err("DSN has no public key".to_string());
+        }
+        let host = url.host_str().ok_or("DSN has no host")?;
+        let path = url.path().trim_end_matches('/');
+        let (prefix, project_id) = path.rsplit_once('/').unwrap_or(("", path));
+        if project_id.is_empty() {
+            return Err("DSN has no project id".to_string());
+        }
+        let port = url
+            .port()
+            .map(|port| format!(":{}", port))
+            .unwrap_or_default();
+        Ok(Dsn {
+            public_key: url.username().to_string(),
+            base: format!("{}://{}{}{}", url.scheme(), host, port, prefix),
+            project_id: project_id.to_string(),
+        })
+    }
+}
+
+impl Dsn {
+    pub fn store_url(&self) -> String {
+        format!("{}/api/{}/store/", self.base, self.project_id)
+    }
+
+    pub fn auth_header(&self) -> String {
+        format!(
+            "Sentry sentry_version=7, sentry_client=mockchain-engine/{}, sentry_key={}",
+            env!("CARGO_PKG_VERSION"),
+            self.public_key
+        )
+    }
+}
+
+struct Reporter {
+    events: mpsc::UnboundedSender<Value>,
+    environment: String,
+}
+
+/// Starts sending reports and installs the panic hook, nothing happens without a DSN.
+pub fn init(settings: &ReportingSettings, env: &str) {
+    let dsn = match settings.dsn.as_deref().map(Dsn::from_str) {
+        Some(Ok(dsn)) => dsn,
+        Some(Err(e)) => {
+            println!("Error reporting disabled: {}", e);
+            return;
+        }
+        None => return,
+    };
+    let (events, mut receiver) = mpsc::unbounded_channel::<Value>();
+    let reporter = Reporter {
+        events,
+        environment: settings
+            .environment
+            .clone()
+            .unwrap_or_else(|| env.to_string()),
+    };
+    if REPORTER.set(reporter).is_err() {
+        return;
+    }
+
+    let client = reqwest::Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .expect("Failed to create reporting client");
+    rt::spawn(async move {
+        while let Some(event) = receiver.recv().await {
+            let res = client
+                .post(dsn.store_url())
+                .header("X-Sentry-Auth", dsn.auth_header())
+                .json(&event)
+                .send()
+                .await
+                .and_then(|res| res.error_for_status());
+            if let Err(e) = res {
+                println!("Failed to report error: {}", e);
+            }
+        }
+    });
+
+    let previous = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        let message = match info.payload().downcast_ref::<&str>() {
+            Some(message) => message.to_string(),
+            None => match info.payload().downcast_ref::<String>() {
+                Some(message) => message.clone(),
+                None => "Box<dyn Any>".to_string(),
+            },
+        };
+        let mut context = current_context();
+        if let Some(location) = info.location() {
+            context.push(("location", location.to_string()));
+        }
+        if let Some(thread) = std::thread::current().name() {
+            context.push(("thread", thread.to_string()));
+        }
+        send(
+            "fatal",
+            &format!("panic: {}", message),
+            context,
+            Some(Backtrace::force_capture().to_string()),
+        );
+        previous(info);
+    }));
+}
+
+/// Runs `future` with `context` attached to anything it reports or panics with.
+pub fn scope<F: Future>(context: Context, future: F) -> impl Future<Output = F::Output> {
+    CONTEXT.scope(context, future)
+}
+
+/// Context of the enclosing `scope`, for carrying it into spawned tasks.
+pub fn current_context() -> Context {
+    CONTEXT
+        .try_with(|context| context.clone())
+        .unwrap_or_default()
+}
+
+/// Reports an error that's otherwise only logged.
+pub fn capture_error(message: &str, context: &[(&'static str, String)]) {
+    capture("error", message, context);
+}
+
+/// Reports something unexpected that the engine recovered from.
+pub fn capture_warning(message: &str, context: &[(&'static str, String)]) {
+    capture("warning", message, context);
+}
+
+fn capture(level: &str, message: &str, context: &[(&'static str, String)]) {
+    let mut tags = current_context();
+    tags.extend_from_slice(context);
+    send(level, message, tags, None);
+}
+
+fn send(level: &str, message: &str, context: Context, backtrace: Option<String>) {
+    if let Some(reporter) = REPORTER.get() {
+        let event = event(level, message, &reporter.environment, &context, backtrace);
+        let _ = reporter.events.send(event);
+    }
+}
+
+/// A Sentry event, context becomes its tags.
+pub fn event(
+    level: &str,
+    message: &str,
+    environment: &str,
+    context: &[(&'static str, String)],
+    backtrace: Option<String>,
+) -> Value {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|now| now.as_secs_f64())
+        .unwrap_or(0.0);
+    let tags: Map<String, Value> = context
+        .iter()
+        .map(|(key, value)| (key.to_string(), Value::String(value.clone())))
+        .collect();
+    json!({
+        "event_id": Uuid::new_v4().simple().to_string(),
+        "timestamp": timestamp,
+        "platform": "native",
+        "level": level,
+        "logger": "mockchain-engine",
+        "release": env!("CARGO_PKG_VERSION"),
+        "environment": environment,
+        "message": { "formatted": message },
+        "tags": tags,
+        "extra": { "backtrace": backtrace },
+    })
+}
@@ -13,7 +13,7 @@ use crate::{
     storage::Storage,
 };
 
-use super::rpc::{encode_account, parse_pubkey, RpcRequest};
+use super::rpc::{encode_account, parse_pubkey, rpc_context, RpcRequest};
 
 pub async fn get_account_info<T: Storage + Clone + 'static>(
     id: Uuid,
@@ -114,12 +114,12 @@ pub async fn get_account_info<T: Storage + Clone + 'static>(
                     }
                 };
                 Ok(serde_json::json!({
-                    "context": { "slot": slot.block_height,"apiVersion":"2.1.13" },
+                    "context": rpc_context(slot.block_height),
                     "value": account_data,
                 }))
             }
             None => Ok(serde_json::json!({
-                "context": { "slot": slot.block_height,"apiVersion":"2.1.13" },
+                "context": rpc_context(slot.block_height),
                 "value": null,
             })),
         },
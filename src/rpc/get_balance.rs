@@ -6,7 +6,7 @@ use crate::{
     storage::Storage,
 };
 
-use super::rpc::{parse_pubkey, RpcRequest};
+use super::rpc::{parse_pubkey, rpc_context, RpcRequest};
 
 pub async fn get_balance<T: Storage + Clone + 'static>(
     id: Uuid,
@@ -42,11 +42,11 @@ pub async fn get_balance<T: Storage + Clone + 'static>(
     match svm.get_balance(id, &pubkey).await {
         Ok(balance) => match balance {
             Some(balance) => Ok(serde_json::json!({
-                "context": { "slot": slot.block_height,"apiVersion":"2.1.13" },
+                "context": rpc_context(slot.block_height),
                 "value": balance,
             })),
             None => Ok(serde_json::json!({
-                "context": { "slot": slot.block_height,"apiVersion":"2.1.13" },
+                "context": rpc_context(slot.block_height),
                 "value": 0,
             })),
         },
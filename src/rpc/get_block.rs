@@ -1,12 +1,13 @@
 use serde_json::Value;
+use solana_rpc_client_api::config::RpcBlockConfig;
 use uuid::Uuid;
 
 use crate::{
     engine::{SvmEngine, SVM},
-    storage::Storage,
+    storage::{transactions::string_to_version, Storage},
 };
 
-use super::rpc::RpcRequest;
+use super::rpc::{check_transaction_version, RpcRequest};
 
 pub fn get_block<T: Storage + Clone + 'static>(
     id: Uuid,
@@ -28,6 +29,32 @@ pub fn get_block<T: Storage + Clone + 'static>(
         }
     };
 
+    let config: Option<RpcBlockConfig> = req
+        .params
+        .as_ref()
+        .and_then(|params| params.get(1))
+        .and_then(|v| v.as_object())
+        .map(|map| serde_json::from_value(Value::Object(map.clone())))
+        .transpose()
+        .unwrap_or_default();
+    let max_supported_transaction_version =
+        config.and_then(|config| config.max_supported_transaction_version);
+    let versions = match svm.storage.get_slot_transaction_versions(id, block_height) {
+        Ok(versions) => versions,
+        Err(e) => {
+            return Err(serde_json::json!({
+                "code": -32002,
+                "message": e,
+            }))
+        }
+    };
+    for version in versions {
+        check_transaction_version(
+            &string_to_version(&version),
+            max_supported_transaction_version,
+        )?;
+    }
+
     match svm.get_block(id, &block_height) {
         Ok(block) => Ok(serde_json::json!({
             "value": block,
@@ -6,7 +6,7 @@ use crate::{
     storage::Storage,
 };
 
-use super::rpc::RpcRequest;
+use super::rpc::{rpc_context, RpcRequest};
 
 pub fn get_block_time<T: Storage + Clone + 'static>(
     id: Uuid,
@@ -31,7 +31,7 @@ pub fn get_block_time<T: Storage + Clone + 'static>(
     match svm.get_block(id, &block_height) {
         Ok(block) => match block {
             Some(block) => Ok(serde_json::json!({
-                "context": { "slot": block_height,"apiVersion":"2.1.13" },
+                "context": rpc_context(block_height),
                 "value": {
                     "blockTime": block.block_time,
                 }
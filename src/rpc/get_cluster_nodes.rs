@@ -0,0 +1,32 @@
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::{
+    engine::{SvmEngine, SVM},
+    storage::Storage,
+};
+
+/// The blockchain's only node. Clients such as `solana program deploy` look up the
+/// leader here, without TPU addresses they send transactions over RPC.
+pub fn get_cluster_nodes<T: Storage + Clone + 'static>(
+    id: Uuid,
+    svm: &SvmEngine<T>,
+) -> Result<Value, Value> {
+    match svm.get_identity(id) {
+        Ok(pubkey) => Ok(serde_json::json!([{
+            "pubkey": pubkey.to_string(),
+            "gossip": null,
+            "tpu": null,
+            "tpuQuic": null,
+            "rpc": null,
+            "pubsub": null,
+            "version": "2.1.13",
+            "featureSet": 2891131721u32,
+            "shredVersion": 0,
+        }])),
+        Err(e) => Err(serde_json::json!({
+            "code": -32002,
+            "message": e,
+        })),
+    }
+}
@@ -9,7 +9,7 @@ use crate::{
     storage::Storage,
 };
 
-use super::rpc::RpcRequest;
+use super::rpc::{rpc_context, RpcRequest};
 
 pub fn get_fee_for_message<T: Storage + Clone + 'static>(
     id: Uuid,
@@ -39,7 +39,7 @@ pub fn get_fee_for_message<T: Storage + Clone + 'static>(
             }));
         }
     };
-    let message: VersionedMessage = match serde_json::from_slice(&decoded_message) {
+    let message: VersionedMessage = match bincode::deserialize(&decoded_message) {
         Ok(msg) => msg,
         Err(e) => {
             return Err(serde_json::json!({
@@ -72,7 +72,17 @@ pub fn get_fee_for_message<T: Storage + Clone + 'static>(
         }
     };
 
+    let slot = match svm.get_latest_block(id) {
+        Ok(block) => block.block_height,
+        Err(_) => {
+            return Err(serde_json::json!({
+                "code": -32002,
+                "message": "Failed to get latest block",
+            }))
+        }
+    };
     Ok(serde_json::json!({
-        "value": svm.get_fee_for_message( &sanitized_message),
+        "context": rpc_context(slot),
+        "value": svm.get_fee_for_message(&sanitized_message),
     }))
 }
@@ -6,6 +6,8 @@ use crate::{
     storage::Storage,
 };
 
+use super::rpc::rpc_context;
+
 pub fn get_largest_accounts<T: Storage + Clone + 'static>(
     id: Uuid,
     svm: &SvmEngine<T>,
@@ -22,9 +24,7 @@ pub fn get_largest_accounts<T: Storage + Clone + 'static>(
 
     match svm.get_largest_accounts(id) {
         Ok(accounts) => Ok(serde_json::json!({
-            "context": {
-                "slot": current_slot.block_height
-              },
+            "context": rpc_context(current_slot.block_height),
             "accounts": accounts.iter().map(|(account, balance)|{
                 serde_json::json!({
                     "address": account.to_string(),
@@ -1,4 +1,5 @@
 use serde_json::Value;
+use solana_sdk::clock::MAX_PROCESSING_AGE;
 use uuid::Uuid;
 
 use crate::{
@@ -6,18 +7,18 @@ use crate::{
     storage::Storage,
 };
 
+use super::rpc::rpc_context;
+
 pub fn get_latest_blockhash<T: Storage + Clone + 'static>(
     id: Uuid,
     svm: &SvmEngine<T>,
 ) -> Result<Value, Value> {
     match svm.latest_blockhash(id) {
         Ok(blockhash) => Ok(serde_json::json!({
-            "context": {
-                "slot": blockhash.block_height
-              },
+            "context": rpc_context(blockhash.block_height),
               "value": {
                 "blockhash": blockhash.blockhash.to_string(),
-                "lastValidBlockHeight": blockhash.block_height+100 //hardcoded
+                "lastValidBlockHeight": blockhash.block_height + MAX_PROCESSING_AGE as u64
               }
         })),
         Err(e) => Err(serde_json::json!({
@@ -15,7 +15,7 @@ use crate::{
     storage::Storage,
 };
 
-use super::rpc::{parse_pubkey, RpcRequest};
+use super::rpc::{context_slot, parse_pubkey, rpc_context, RpcRequest};
 
 pub async fn get_multiple_accounts<T: Storage + Clone + 'static>(
     id: Uuid,
@@ -74,12 +74,13 @@ pub async fn get_multiple_accounts<T: Storage + Clone + 'static>(
         }
     };
 
+    let slot = context_slot(id, svm)?;
     match svm
         .get_multiple_accounts(id, &pubkeys, blockchain.jit)
         .await
     {
         Ok(accounts) => Ok(serde_json::json!({
-            "context": { "apiVersion":"2.1.13", "slot": 341197247 },
+            "context": rpc_context(slot),
             "value": accounts
             .iter()
             .enumerate()
@@ -10,6 +10,7 @@ use solana_rpc_client_api::{
     config::{RpcAccountInfoConfig, RpcProgramAccountsConfig},
     filter::RpcFilterType,
 };
+use solana_sdk::hash::hash;
 use spl_token_2022::{extension::StateWithExtensions, state::Account as TokenAccount};
 use uuid::Uuid;
 
@@ -67,8 +68,31 @@ pub fn get_program_accounts<T: Storage + Clone + 'static>(
         }
     };
 
-    match svm.get_program_accounts(id, &pubkey) {
-        Ok(accounts) => Ok(serde_json::Value::Array(
+    // The config shapes the result, the program's accounts are all it's read from
+    let cache_key = cache_key(req.params.as_ref().and_then(|params| params.get(1)));
+    let generation = match svm
+        .storage
+        .get_cached_program_accounts(id, &pubkey, &cache_key)
+    {
+        Ok((Some(cached), generation)) => match serde_json::from_str(&cached) {
+            Ok(result) => return Ok(result),
+            Err(e) => {
+                println!("Invalid cached program accounts of {}: {}", pubkey, e);
+                Some(generation)
+            }
+        },
+        Ok((None, generation)) => Some(generation),
+        Err(e) => {
+            println!(
+                "Failed to read cached program accounts of {}: {}",
+                pubkey, e
+            );
+            None
+        }
+    };
+
+    let result = match svm.get_program_accounts(id, &pubkey) {
+        Ok(accounts) => serde_json::Value::Array(
             accounts
                 .iter()
                 .filter(|(_, account)| {
@@ -124,10 +148,30 @@ pub fn get_program_accounts<T: Storage + Clone + 'static>(
                     })
                 })
                 .collect::<Vec<_>>(),
-        )),
-        Err(e) => Err(serde_json::json!({
-            "code": -32002,
-            "message": e,
-        })),
+        ),
+        Err(e) => {
+            return Err(serde_json::json!({
+                "code": -32002,
+                "message": e,
+            }))
+        }
+    };
+    // Without the generation there's nothing to tell a stale result by
+    if let Some(generation) = generation {
+        if let Err(e) = svm.storage.cache_program_accounts(
+            id,
+            &pubkey,
+            &cache_key,
+            &result.to_string(),
+            generation,
+        ) {
+            println!("Failed to cache program accounts of {}: {}", pubkey, e);
+        }
     }
+    Ok(result)
+}
+
+/// Hash of the request's config, equal configs read the same cached result.
+pub fn cache_key(config: Option<&Value>) -> String {
+    hash(config.unwrap_or(&Value::Null).to_string().as_bytes()).to_string()
 }
@@ -1,14 +1,13 @@
 use serde_json::Value;
-use solana_banks_interface::{TransactionConfirmationStatus, TransactionStatus};
-use solana_sdk::transaction::Transaction;
+use solana_banks_interface::TransactionConfirmationStatus;
 use uuid::Uuid;
 
 use crate::{
-    engine::{transactions::TransactionMeta, SvmEngine, SVM},
+    engine::{SvmEngine, SVM},
     storage::Storage,
 };
 
-use super::rpc::{parse_signature, RpcRequest};
+use super::rpc::{parse_signature, rpc_context, RpcRequest};
 
 pub fn get_signature_statuses<T: Storage + Clone + 'static>(
     id: Uuid,
@@ -29,45 +28,32 @@ pub fn get_signature_statuses<T: Storage + Clone + 'static>(
             }));
         }
     };
-    let sig_arr = sig_raw_arr
+    let sigs = sig_raw_arr
         .iter()
-        .map(|sig| sig.as_str().unwrap())
-        .collect::<Vec<&str>>();
-
-    let sigs = sig_arr
-        .iter()
-        .map(|sig_str| parse_signature(sig_str))
+        .map(|sig| match sig.as_str() {
+            Some(sig_str) => parse_signature(sig_str),
+            None => Err(serde_json::json!({
+                "code": -32602,
+                "message": "Invalid param: signatures must be strings"
+            })),
+        })
         .collect::<Result<Vec<solana_sdk::signature::Signature>, Value>>()?;
 
-    let txs: Vec<Option<(Transaction, _, TransactionStatus)>> = sigs
-        .iter()
-        .map(|sig| svm.get_transaction(id, &sig))
-        .collect::<Result<
-            Vec<
-                Option<(
-                    solana_sdk::transaction::Transaction,
-                    TransactionMeta,
-                    TransactionStatus,
-                )>,
-            >,
-            String,
-        >>()?;
-
-    // let slot = match svm.get_latest_block(id) {
-    //     Ok(slot) => slot,
-    //     Err(_) => {
-    //         return Err(serde_json::json!({
-    //             "code": -32002,
-    //             "message": "Failed to get latest block",
-    //         }))
-    //     }
-    // };
+    let (slot, statuses) = match svm.get_signature_statuses(id, &sigs) {
+        Ok(statuses) => statuses,
+        Err(e) => {
+            return Err(serde_json::json!({
+                "code": -32002,
+                "message": e,
+            }))
+        }
+    };
     Ok(serde_json::json!({
-        "context": { "slot": 100,"apiVersion":"2.1.13" },
-        "value": txs
+        "context": rpc_context(slot),
+        "value": statuses
         .iter()
-        .map(|tx| match tx {
-            Some((_,_, status)) => {
+        .map(|status| match status {
+            Some(status) => {
                 let status_value = match status.err.clone() {
                     Some(err) => {
                         serde_json::json!({
@@ -80,12 +66,8 @@ pub fn get_signature_statuses<T: Storage + Clone + 'static>(
                         })
                     }
                 };
-                let mut slot = status.slot;
-                if slot > 0{
-                    slot = status.slot - 1;
-                }
                 serde_json::json!({
-                    "slot": slot,
+                    "slot": status.slot.saturating_sub(1),
                     "confirmations": null,
                     "err": status.err,
                     "status": status_value,
@@ -1,10 +1,9 @@
-use bigdecimal::ToPrimitive;
 use serde_json::Value;
 use uuid::Uuid;
 
 use crate::{
     engine::{SvmEngine, SVM},
-    storage::Storage,
+    storage::{numeric, Storage},
 };
 
 use super::rpc::{parse_pubkey, RpcRequest};
@@ -38,8 +37,8 @@ pub fn get_signatures_for_address<T: Storage + Clone + 'static>(
                     "err": null,
                     "memo": null,
                     "signature": tx.signature,
-                    "slot": tx.slot.to_u64().unwrap(),
-                    "blockTime": null
+                    "slot": numeric::saturating_u64(&tx.slot),
+                    "blockTime": tx.block_time
                 })
             })
             .collect::<Value>()),
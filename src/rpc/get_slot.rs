@@ -0,0 +1,48 @@
+use serde_json::Value;
+use solana_banks_interface::TransactionConfirmationStatus;
+use uuid::Uuid;
+
+use crate::{
+    engine::{commitment_slot, SvmEngine, SVM},
+    storage::Storage,
+};
+
+use super::rpc::RpcRequest;
+
+pub fn get_slot<T: Storage + Clone + 'static>(
+    id: Uuid,
+    req: &RpcRequest,
+    svm: &SvmEngine<T>,
+) -> Result<Value, Value> {
+    // Same default as a validator
+    let commitment = req
+        .params
+        .as_ref()
+        .and_then(|params| params.get(0))
+        .and_then(|v| v.get("commitment"))
+        .and_then(|v| v.as_str())
+        .unwrap_or("finalized");
+    let commitment = match commitment {
+        "finalized" => TransactionConfirmationStatus::Finalized,
+        "confirmed" => TransactionConfirmationStatus::Confirmed,
+        "processed" => TransactionConfirmationStatus::Processed,
+        _ => {
+            return Err(serde_json::json!({
+                "code": -32602,
+                "message": "Invalid `commitment` value",
+            }))
+        }
+    };
+
+    // Reading the blockhash keeps blocks coming while a client polls for a slot
+    match svm.latest_blockhash(id) {
+        Ok(block) => Ok(serde_json::json!(commitment_slot(
+            block.block_height,
+            &commitment
+        ))),
+        Err(e) => Err(serde_json::json!({
+            "code": -32002,
+            "message": e,
+        })),
+    }
+}
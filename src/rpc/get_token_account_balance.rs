@@ -6,7 +6,7 @@ use crate::{
     storage::Storage,
 };
 
-use super::rpc::{parse_pubkey, RpcRequest};
+use super::rpc::{context_slot, parse_pubkey, rpc_context, RpcRequest};
 
 pub async fn get_token_account_balance<T: Storage + Clone + 'static>(
     id: Uuid,
@@ -47,13 +47,14 @@ pub async fn get_token_account_balance<T: Storage + Clone + 'static>(
         }
     };
 
+    let slot = context_slot(id, svm)?;
     match svm
         .get_token_account_balance(id, &pubkey, blockchain.jit)
         .await
     {
         Ok(amount) => match amount {
             Some(amount) => Ok(serde_json::json!({
-                "context": { "slot": 341197053,"apiVersion":"2.1.13" },
+                "context": rpc_context(slot),
                 "value":  amount,
             })),
             None => Err(serde_json::json!({
@@ -9,7 +9,7 @@ use crate::{
     storage::Storage,
 };
 
-use super::rpc::{parse_pubkey, RpcRequest};
+use super::rpc::{parse_pubkey, rpc_context, RpcRequest};
 
 pub async fn get_token_accounts_by_owner<T: Storage + Clone + 'static>(
     id: Uuid,
@@ -161,7 +161,7 @@ pub async fn get_token_accounts_by_owner<T: Storage + Clone + 'static>(
             };
 
             Ok(serde_json::json!({
-                "context": { "apiVersion":"2.1.13", "slot": slot.block_height },
+                "context": rpc_context(slot.block_height),
                 "value": vals}))
         }
         Err(e) => Err(serde_json::json!({
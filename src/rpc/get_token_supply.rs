@@ -6,7 +6,7 @@ use crate::{
     storage::Storage,
 };
 
-use super::rpc::{parse_pubkey, RpcRequest};
+use super::rpc::{parse_pubkey, rpc_context, RpcRequest};
 
 pub async fn get_token_supply<T: Storage + Clone + 'static>(
     id: Uuid,
@@ -60,7 +60,7 @@ pub async fn get_token_supply<T: Storage + Clone + 'static>(
     match svm.get_token_supply(id, &pubkey, blockchain.jit).await {
         Ok(amount) => match amount {
             Some(amount) => Ok(serde_json::json!({
-                "context": { "slot": slot.block_height,"apiVersion":"2.1.13" },
+                "context": rpc_context(slot.block_height),
                 "value":  amount,
             })),
             None => Err(serde_json::json!({
@@ -1,11 +1,7 @@
 use jsonrpc_core::Result as JsonResult;
 use serde_json::Value;
 use solana_rpc_client_api::{config::RpcTransactionConfig, custom_error::RpcCustomError};
-use solana_sdk::{
-    instruction::AccountMeta,
-    message::{v0::LoadedAddresses, VersionedMessage},
-    transaction::{TransactionError, VersionedTransaction},
-};
+use solana_sdk::transaction::TransactionError;
 use solana_transaction_status::{
     ConfirmedTransactionWithStatusMeta, EncodedConfirmedTransactionWithStatusMeta,
     InnerInstructions, TransactionStatusMeta, TransactionTokenBalance, TransactionWithStatusMeta,
@@ -19,7 +15,7 @@ use crate::{
     storage::Storage,
 };
 
-use super::rpc::{parse_signature, RpcRequest};
+use super::rpc::{check_transaction_version, parse_signature, rpc_context, RpcRequest};
 
 pub fn get_transaction<T: Storage + Clone + 'static>(
     id: Uuid,
@@ -63,7 +59,6 @@ pub fn get_transaction<T: Storage + Clone + 'static>(
         max_supported_transaction_version,
     } = config.unwrap_or_default();
     _ = commitment;
-    _ = max_supported_transaction_version;
     let encoding = encoding.unwrap_or(UiTransactionEncoding::Base64);
 
     let slot = match svm.get_latest_block(id) {
@@ -85,11 +80,8 @@ pub fn get_transaction<T: Storage + Clone + 'static>(
         Ok(transaction) => {
             match transaction {
                 Some((transaction, tx_meta, status)) => {
-                    let versioned_message = VersionedMessage::Legacy(transaction.message().clone());
-                    let versioned_transaction = VersionedTransaction {
-                        message: versioned_message,
-                        signatures: transaction.signatures.clone(),
-                    };
+                    check_transaction_version(&tx_meta.version, max_supported_transaction_version)?;
+                    let tx_slot = status.slot;
                     let status = match tx_meta.clone().err {
                         Some(err) => {
                             Err(TransactionError::AccountNotFound) //TODO: This is bad
@@ -115,10 +107,10 @@ pub fn get_transaction<T: Storage + Clone + 'static>(
                         .collect();
 
                     let confirmed_tx = ConfirmedTransactionWithStatusMeta {
-                        slot: slot.block_height,
+                        slot: tx_slot,
                         tx_with_meta: TransactionWithStatusMeta::Complete(
                             VersionedTransactionWithStatusMeta {
-                                transaction: versioned_transaction,
+                                transaction,
                                 meta: TransactionStatusMeta {
                                     status: status,
                                     fee: tx_meta.fee,
@@ -155,29 +147,15 @@ pub fn get_transaction<T: Storage + Clone + 'static>(
                                         },
                                     ),
                                     rewards: None,
-                                    loaded_addresses: LoadedAddresses {
-                                        writable: vec![], //TODO
-                                        readonly: vec![], //TODO
-                                    },
+                                    loaded_addresses: tx_meta.loaded_addresses.clone(),
                                     return_data: None,
                                     compute_units_consumed: Some(tx_meta.compute_units_consumed),
                                 },
                             },
                         ),
-                        block_time: None,
+                        block_time: tx_meta.block_time,
                     };
 
-                    let account_metas = transaction
-                        .message()
-                        .account_keys
-                        .iter()
-                        .enumerate()
-                        .map(|(idx, key)| AccountMeta {
-                            pubkey: key.to_owned(),
-                            is_signer: transaction.message().is_signer(idx),
-                            is_writable: transaction.message().is_maybe_writable(idx, None),
-                        })
-                        .collect::<Vec<AccountMeta>>();
                     match encode_transaction(confirmed_tx) {
                         Ok(encoded_tx) => {
                             let mut val = serde_json::json!(encoded_tx);
@@ -217,7 +195,7 @@ pub fn get_transaction<T: Storage + Clone + 'static>(
                     }
                 }
                 None => Ok(serde_json::json!({
-                    "context": { "slot": slot.block_height,"apiVersion":"2.1.13" },
+                    "context": rpc_context(slot.block_height),
                     "value": null,
                 })),
             }
@@ -6,7 +6,7 @@ use crate::{
     storage::Storage,
 };
 
-use super::rpc::{parse_hash, RpcRequest};
+use super::rpc::{parse_hash, rpc_context, RpcRequest};
 
 pub fn is_blockhash_valid<T: Storage + Clone + 'static>(
     id: Uuid,
@@ -48,12 +48,12 @@ pub fn is_blockhash_valid<T: Storage + Clone + 'static>(
     };
     if res {
         Ok(serde_json::json!({
-            "context": { "slot": block.block_height,"apiVersion":"2.1.13" },
+            "context": rpc_context(block.block_height),
             "value": true,
         }))
     } else {
         Ok(serde_json::json!({
-            "context": { "slot": block.block_height,"apiVersion":"2.1.13" },
+            "context": rpc_context(block.block_height),
             "value": false,
         }))
     }
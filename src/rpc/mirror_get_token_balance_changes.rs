@@ -0,0 +1,71 @@
+use serde::Deserialize;
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::{
+    engine::{SvmEngine, SVM},
+    storage::Storage,
+};
+
+use super::rpc::{parse_pubkey, parse_signature, RpcRequest};
+
+const DEFAULT_LIMIT: i64 = 100;
+const MAX_LIMIT: i64 = 1000;
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct TokenBalanceChangesConfig {
+    /// Transactions to read changes from
+    limit: Option<i64>,
+    /// Signature to start searching backwards from, exclusive
+    before: Option<String>,
+}
+
+/// Balance changes of an owner's token accounts, newest first, so wallets can
+/// render activity without diffing transactions themselves.
+pub fn mirror_get_token_balance_changes<T: Storage + Clone + 'static>(
+    id: Uuid,
+    req: &RpcRequest,
+    svm: &SvmEngine<T>,
+) -> Result<Value, Value> {
+    let owner_str = match req
+        .params
+        .as_ref()
+        .and_then(|params| params.get(0))
+        .and_then(|v| v.as_str())
+    {
+        Some(s) => s,
+        None => {
+            return Err(serde_json::json!({
+                "code": -32602,
+                "message": "`params` should have at least 1 argument(s)"
+            }));
+        }
+    };
+    let owner = parse_pubkey(owner_str)?;
+    let config: TokenBalanceChangesConfig = match req.params.as_ref().and_then(|p| p.get(1)) {
+        Some(config) => serde_json::from_value(config.clone()).map_err(|e| {
+            serde_json::json!({
+                "code": -32602,
+                "message": format!("Invalid params: {}", e),
+            })
+        })?,
+        None => TokenBalanceChangesConfig::default(),
+    };
+    let limit = config.limit.unwrap_or(DEFAULT_LIMIT);
+    if !(1..=MAX_LIMIT).contains(&limit) {
+        return Err(serde_json::json!({
+            "code": -32602,
+            "message": format!("Invalid params: limit must be between 1 and {}", MAX_LIMIT),
+        }));
+    }
+    let before = config.before.as_deref().map(parse_signature).transpose()?;
+
+    match svm.get_token_balance_changes(id, &owner, before.as_ref(), limit) {
+        Ok(changes) => Ok(serde_json::json!(changes)),
+        Err(e) => Err(serde_json::json!({
+            "code": -32002,
+            "message": e,
+        })),
+    }
+}
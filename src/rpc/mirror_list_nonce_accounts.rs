@@ -0,0 +1,77 @@
+use serde_json::Value;
+use solana_account_decoder_client_types::UiAccountEncoding;
+use solana_rpc_client_api::config::RpcAccountInfoConfig;
+use uuid::Uuid;
+
+use crate::{
+    engine::{SvmEngine, SVM},
+    storage::Storage,
+};
+
+use super::rpc::{encode_account, parse_pubkey, rpc_context, RpcRequest};
+
+/// Nonce accounts an authority controls, parsed by default, for debugging
+/// durable nonce transactions.
+pub fn mirror_list_nonce_accounts<T: Storage + Clone + 'static>(
+    id: Uuid,
+    req: &RpcRequest,
+    svm: &SvmEngine<T>,
+) -> Result<Value, Value> {
+    let authority_str = match req
+        .params
+        .as_ref()
+        .and_then(|params| params.get(0))
+        .and_then(|v| v.as_str())
+    {
+        Some(s) => s,
+        None => {
+            return Err(serde_json::json!({
+                "code": -32602,
+                "message": "`params` should have at least 1 argument(s)"
+            }));
+        }
+    };
+    let authority = parse_pubkey(authority_str)?;
+    let config: Option<RpcAccountInfoConfig> = req
+        .params
+        .as_ref()
+        .and_then(|params| params.get(1))
+        .and_then(|v| v.as_object())
+        .map(|map| serde_json::from_value(Value::Object(map.clone())))
+        .transpose()
+        .unwrap_or_default();
+    let encoding = config
+        .and_then(|config| config.encoding)
+        .unwrap_or(UiAccountEncoding::JsonParsed);
+
+    let slot = match svm.get_latest_block(id) {
+        Ok(slot) => slot,
+        Err(_) => {
+            return Err(serde_json::json!({
+                "code": -32002,
+                "message": "Failed to get latest block",
+            }))
+        }
+    };
+
+    match svm.get_nonce_accounts(id, &authority) {
+        Ok(accounts) => Ok(serde_json::json!({
+            "context": rpc_context(slot.block_height),
+            "value": accounts
+                .iter()
+                .map(|(pubkey, account)| {
+                    let account_data = encode_account(account, pubkey, encoding, None, None)
+                        .map_err(|e| serde_json::json!({"code": -32002, "message": e}))?;
+                    Ok(serde_json::json!({
+                        "pubkey": pubkey.to_string(),
+                        "account": account_data,
+                    }))
+                })
+                .collect::<Result<Vec<Value>, Value>>()?,
+        })),
+        Err(e) => Err(serde_json::json!({
+            "code": -32002,
+            "message": e,
+        })),
+    }
+}
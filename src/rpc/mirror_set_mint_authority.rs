@@ -0,0 +1,70 @@
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::{
+    engine::{SvmEngine, SVM},
+    storage::Storage,
+};
+
+use super::rpc::{parse_pubkey, rpc_context, RpcRequest};
+
+/// Makes a key the mint or freeze authority of a mint, so tests can mint or
+/// freeze cloned mainnet tokens.
+pub async fn mirror_set_mint_authority<T: Storage + Clone + 'static>(
+    id: Uuid,
+    req: &RpcRequest,
+    svm: &SvmEngine<T>,
+) -> Result<Value, Value> {
+    let param = |index: usize| req.params.as_ref().and_then(|params| params.get(index));
+    let (mint_str, authorities) = match (
+        param(0).and_then(|v| v.as_str()),
+        param(1).and_then(|v| v.as_object()),
+    ) {
+        (Some(mint), Some(authorities)) => (mint, authorities),
+        _ => {
+            return Err(serde_json::json!({
+                "code": -32602,
+                "message": "`params` should have at least 2 argument(s)"
+            }));
+        }
+    };
+    let mint = parse_pubkey(mint_str)?;
+    let authority = |field: &str| match authorities.get(field) {
+        Some(Value::String(authority)) => parse_pubkey(authority).map(Some),
+        Some(Value::Null) | None => Ok(None),
+        Some(_) => Err(serde_json::json!({
+            "code": -32602,
+            "message": format!("`{}` should be a string", field)
+        })),
+    };
+    let mint_authority = authority("mintAuthority")?;
+    let freeze_authority = authority("freezeAuthority")?;
+
+    let slot = match svm.get_latest_block(id) {
+        Ok(slot) => slot,
+        Err(_) => {
+            return Err(serde_json::json!({
+                "code": -32002,
+                "message": "Failed to get latest block",
+            }))
+        }
+    };
+
+    match svm
+        .set_mint_authorities(id, &mint, mint_authority, freeze_authority)
+        .await
+    {
+        Ok(()) => Ok(serde_json::json!({
+            "context": rpc_context(slot.block_height),
+            "value": {
+                "mint": mint.to_string(),
+                "mintAuthority": mint_authority.map(|authority| authority.to_string()),
+                "freezeAuthority": freeze_authority.map(|authority| authority.to_string()),
+            },
+        })),
+        Err(e) => Err(serde_json::json!({
+            "code": -32000,
+            "message": e,
+        })),
+    }
+}
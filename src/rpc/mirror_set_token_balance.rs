@@ -0,0 +1,72 @@
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::{
+    engine::{SvmEngine, SVM},
+    storage::Storage,
+};
+
+use super::rpc::{parse_pubkey, rpc_context, RpcRequest};
+
+/// Writes an owner's associated token account with an amount of a mint's tokens,
+/// for test setup without a faucet. Amounts can be strings, since JavaScript
+/// numbers can't hold every u64.
+pub async fn mirror_set_token_balance<T: Storage + Clone + 'static>(
+    id: Uuid,
+    req: &RpcRequest,
+    svm: &SvmEngine<T>,
+) -> Result<Value, Value> {
+    let param = |index: usize| req.params.as_ref().and_then(|params| params.get(index));
+    let (owner_str, mint_str) = match (
+        param(0).and_then(|v| v.as_str()),
+        param(1).and_then(|v| v.as_str()),
+    ) {
+        (Some(owner), Some(mint)) => (owner, mint),
+        _ => {
+            return Err(serde_json::json!({
+                "code": -32602,
+                "message": "`params` should have at least 3 argument(s)"
+            }));
+        }
+    };
+    let owner = parse_pubkey(owner_str)?;
+    let mint = parse_pubkey(mint_str)?;
+    let amount = match param(2) {
+        Some(Value::Number(amount)) => amount.as_u64(),
+        Some(Value::String(amount)) => amount.parse::<u64>().ok(),
+        _ => None,
+    };
+    let amount = match amount {
+        Some(amount) => amount,
+        None => {
+            return Err(serde_json::json!({
+                "code": -32602,
+                "message": "`amount` should be a u64 as a number or string"
+            }));
+        }
+    };
+
+    let slot = match svm.get_latest_block(id) {
+        Ok(slot) => slot,
+        Err(_) => {
+            return Err(serde_json::json!({
+                "code": -32002,
+                "message": "Failed to get latest block",
+            }))
+        }
+    };
+
+    match svm.set_token_balance(id, &mint, &owner, amount).await {
+        Ok(token_account) => Ok(serde_json::json!({
+            "context": rpc_context(slot.block_height),
+            "value": {
+                "tokenAccount": token_account.to_string(),
+                "amount": amount.to_string(),
+            },
+        })),
+        Err(e) => Err(serde_json::json!({
+            "code": -32000,
+            "message": e,
+        })),
+    }
+}
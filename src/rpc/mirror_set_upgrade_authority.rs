@@ -0,0 +1,57 @@
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::{
+    engine::{SvmEngine, SVM},
+    storage::Storage,
+};
+
+use super::rpc::{parse_pubkey, rpc_context, RpcRequest};
+
+/// Makes a key the upgrade authority of an upgradeable program, so tests can
+/// upgrade or close cloned mainnet programs.
+pub async fn mirror_set_upgrade_authority<T: Storage + Clone + 'static>(
+    id: Uuid,
+    req: &RpcRequest,
+    svm: &SvmEngine<T>,
+) -> Result<Value, Value> {
+    let param = |index: usize| req.params.as_ref().and_then(|params| params.get(index));
+    let (program_id_str, authority_str) = match (
+        param(0).and_then(|v| v.as_str()),
+        param(1).and_then(|v| v.as_str()),
+    ) {
+        (Some(program_id), Some(authority)) => (program_id, authority),
+        _ => {
+            return Err(serde_json::json!({
+                "code": -32602,
+                "message": "`params` should have at least 2 argument(s)"
+            }));
+        }
+    };
+    let program_id = parse_pubkey(program_id_str)?;
+    let authority = parse_pubkey(authority_str)?;
+
+    let slot = match svm.get_latest_block(id) {
+        Ok(slot) => slot,
+        Err(_) => {
+            return Err(serde_json::json!({
+                "code": -32002,
+                "message": "Failed to get latest block",
+            }))
+        }
+    };
+
+    match svm.set_upgrade_authority(id, &program_id, &authority).await {
+        Ok(programdata) => Ok(serde_json::json!({
+            "context": rpc_context(slot.block_height),
+            "value": {
+                "programData": programdata.to_string(),
+                "upgradeAuthority": authority.to_string(),
+            },
+        })),
+        Err(e) => Err(serde_json::json!({
+            "code": -32000,
+            "message": e,
+        })),
+    }
+}
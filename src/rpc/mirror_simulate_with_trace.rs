@@ -0,0 +1,92 @@
+use base64::prelude::*;
+use serde::Deserialize;
+use serde_json::Value;
+use solana_sdk::account::ReadableAccount;
+use uuid::Uuid;
+
+use crate::{
+    engine::{trace::InstructionTrace, SvmEngine},
+    storage::Storage,
+};
+
+use super::{rpc::RpcRequest, simulate_transaction::simulate};
+
+/// Shares its object with `simulateTransaction`'s config, so unknown fields are
+/// left to that one.
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct TraceConfig {
+    /// Include the data each instruction wrote, base64 encoded
+    #[serde(default)]
+    account_data: bool,
+}
+
+/// `simulateTransaction`, with the program, compute units and account writes of
+/// each instruction as it ran, which mainnet can't show.
+pub async fn mirror_simulate_with_trace<T: Storage + Clone + 'static>(
+    id: Uuid,
+    req: &RpcRequest,
+    svm: &SvmEngine<T>,
+) -> Result<Value, Value> {
+    let config: TraceConfig = match req
+        .params
+        .as_ref()
+        .and_then(|params| params.get(1))
+        .filter(|v| !v.is_null())
+    {
+        Some(config) => serde_json::from_value(config.clone()).map_err(|e| {
+            serde_json::json!({
+                "code": -32602,
+                "message": format!("Invalid params: {}", e),
+            })
+        })?,
+        None => TraceConfig::default(),
+    };
+    let (mut res, trace) = simulate(id, req, svm, true).await?;
+    res["value"]["trace"] = trace
+        .unwrap_or_default()
+        .iter()
+        .enumerate()
+        .map(|(index, instruction)| trace_json(index, instruction, config.account_data))
+        .collect();
+    Ok(res)
+}
+
+fn trace_json(index: usize, instruction: &InstructionTrace, account_data: bool) -> Value {
+    let account_writes: Vec<Value> = instruction
+        .account_writes
+        .iter()
+        .map(|write| {
+            let mut json = serde_json::json!({
+                "pubkey": write.pubkey.to_string(),
+                "lamports": {
+                    "before": write.before.lamports(),
+                    "after": write.after.lamports(),
+                },
+                "owner": {
+                    "before": write.before.owner().to_string(),
+                    "after": write.after.owner().to_string(),
+                },
+                "dataLength": {
+                    "before": write.before.data().len(),
+                    "after": write.after.data().len(),
+                },
+            });
+            if account_data {
+                json["data"] =
+                    serde_json::json!([BASE64_STANDARD.encode(write.after.data()), "base64"]);
+            }
+            json
+        })
+        .collect();
+    serde_json::json!({
+        "index": index,
+        "programId": instruction.program_id.to_string(),
+        "computeUnitsBefore": instruction.compute_units_before,
+        "computeUnitsAfter": instruction.compute_units_after,
+        "computeUnitsConsumed": instruction.compute_units_after - instruction.compute_units_before,
+        "logs": instruction.logs,
+        "err": instruction.err,
+        "accountWrites": account_writes,
+    })
+}
@@ -4,6 +4,7 @@ pub mod get_block;
 pub mod get_block_commitment;
 pub mod get_block_height;
 pub mod get_block_time;
+pub mod get_cluster_nodes;
 pub mod get_epoch_info;
 pub mod get_fee_for_message;
 pub mod get_genesis_hash;
@@ -16,6 +17,7 @@ pub mod get_multiple_accounts;
 pub mod get_program_accounts;
 pub mod get_signature_statuses;
 pub mod get_signatures_for_address;
+pub mod get_slot;
 pub mod get_slot_leaders;
 pub mod get_token_account_balance;
 pub mod get_token_accounts_by_owner;
@@ -24,6 +26,12 @@ pub mod get_transaction;
 pub mod get_transaction_count;
 pub mod get_version;
 pub mod is_blockhash_valid;
+pub mod mirror_get_token_balance_changes;
+pub mod mirror_list_nonce_accounts;
+pub mod mirror_set_mint_authority;
+pub mod mirror_set_token_balance;
+pub mod mirror_set_upgrade_authority;
+pub mod mirror_simulate_with_trace;
 pub mod request_airdrop;
 pub mod rpc;
 pub mod send_transaction;
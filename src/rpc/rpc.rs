@@ -1,4 +1,4 @@
-use std::{cmp::min, fmt, str::FromStr};
+use std::{cmp::min, fmt, str::FromStr, time::Instant};
 
 use base64::prelude::*;
 use bincode::Options;
@@ -6,32 +6,48 @@ use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use solana_account_decoder::{encode_ui_account, parse_account_data::AccountAdditionalDataV2};
 use solana_account_decoder_client_types::{UiAccount, UiAccountEncoding, UiDataSliceConfig};
+use solana_rpc_client_api::custom_error::RpcCustomError;
 use solana_sdk::{
-    account::ReadableAccount, bs58, hash::Hash, packet::PACKET_DATA_SIZE, pubkey::Pubkey,
-    signature::Signature, transaction::VersionedTransaction,
+    account::ReadableAccount,
+    bs58,
+    hash::Hash,
+    packet::PACKET_DATA_SIZE,
+    pubkey::Pubkey,
+    signature::Signature,
+    transaction::{TransactionVersion, VersionedTransaction},
 };
 use solana_transaction_status_client_types::TransactionBinaryEncoding;
 use std::any::type_name;
 use uuid::Uuid;
 
-use crate::{engine::SvmEngine, storage::Storage};
+use crate::{
+    engine::{SvmEngine, SVM},
+    metrics, rpc_log,
+    storage::{trace::OperationLog, Storage},
+};
 
 use super::{
     get_account_info::get_account_info, get_balance::get_balance, get_block::get_block,
     get_block_commitment::get_block_commitment, get_block_height::get_block_height,
-    get_block_time::get_block_time, get_epoch_info::get_epoch_info,
+    get_block_time::get_block_time, get_cluster_nodes::get_cluster_nodes,
+    get_epoch_info::get_epoch_info, get_fee_for_message::get_fee_for_message,
     get_genesis_hash::get_genesis_hash, get_health::get_health, get_identity::get_identity,
     get_largest_accounts::get_largest_accounts, get_latest_blockhash::get_latest_blockhash,
     get_minimum_balance_for_rent_exemption::get_minimum_balance_for_rent_exemption,
     get_multiple_accounts::get_multiple_accounts, get_program_accounts::get_program_accounts,
     get_signature_statuses::get_signature_statuses,
-    get_signatures_for_address::get_signatures_for_address, get_slot_leaders::get_slot_leaders,
-    get_token_account_balance::get_token_account_balance,
+    get_signatures_for_address::get_signatures_for_address, get_slot::get_slot,
+    get_slot_leaders::get_slot_leaders, get_token_account_balance::get_token_account_balance,
     get_token_accounts_by_owner::get_token_accounts_by_owner, get_token_supply::get_token_supply,
     get_transaction::get_transaction, get_transaction_count::get_transaction_count,
     get_version::get_version, is_blockhash_valid::is_blockhash_valid,
-    request_airdrop::request_airdrop, send_transaction::send_transaction,
-    simulate_transaction::simulate_transaction,
+    mirror_get_token_balance_changes::mirror_get_token_balance_changes,
+    mirror_list_nonce_accounts::mirror_list_nonce_accounts,
+    mirror_set_mint_authority::mirror_set_mint_authority,
+    mirror_set_token_balance::mirror_set_token_balance,
+    mirror_set_upgrade_authority::mirror_set_upgrade_authority,
+    mirror_simulate_with_trace::mirror_simulate_with_trace, request_airdrop::request_airdrop,
+    send_transaction::send_transaction, simulate_transaction::simulate_transaction,
 };
 
 #[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
@@ -91,6 +107,13 @@ pub enum RpcMethod {
     SimulateTransaction,
 
     GetAsset,
+
+    MirrorGetTokenBalanceChanges,
+    MirrorListNonceAccounts,
+    MirrorSetMintAuthority,
+    MirrorSetTokenBalance,
+    MirrorSetUpgradeAuthority,
+    MirrorSimulateWithTrace,
 }
 
 impl fmt::Display for RpcMethod {
@@ -149,11 +172,31 @@ impl fmt::Display for RpcMethod {
             RpcMethod::SendTransaction => "SendTransaction",
             RpcMethod::SimulateTransaction => "SimulateTransaction",
             RpcMethod::GetAsset => "GetAsset",
+            RpcMethod::MirrorGetTokenBalanceChanges => "MirrorGetTokenBalanceChanges",
+            RpcMethod::MirrorListNonceAccounts => "MirrorListNonceAccounts",
+            RpcMethod::MirrorSetMintAuthority => "MirrorSetMintAuthority",
+            RpcMethod::MirrorSetTokenBalance => "MirrorSetTokenBalance",
+            RpcMethod::MirrorSetUpgradeAuthority => "MirrorSetUpgradeAuthority",
+            RpcMethod::MirrorSimulateWithTrace => "MirrorSimulateWithTrace",
         };
         write!(f, "{}", method_str)
     }
 }
 
+impl RpcMethod {
+    /// Methods changing the blockchain's state, see `Team::rpc_write_guard`.
+    pub fn is_mutating(&self) -> bool {
+        matches!(
+            self,
+            RpcMethod::SendTransaction
+                | RpcMethod::RequestAirdrop
+                | RpcMethod::MirrorSetMintAuthority
+                | RpcMethod::MirrorSetTokenBalance
+                | RpcMethod::MirrorSetUpgradeAuthority
+        )
+    }
+}
+
 #[derive(Deserialize, Debug, Clone)]
 pub struct RpcRequest {
     pub jsonrpc: String,
@@ -170,6 +213,9 @@ pub struct RpcResponse {
     pub result: Option<Value>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub error: Option<Value>,
+    /// Storage work behind the response, when the caller sent `Mirror-Debug`.
+    #[serde(rename = "mirrorDebug", skip_serializing_if = "Option::is_none")]
+    pub mirror_debug: Option<OperationLog>,
 }
 
 pub async fn handle_request<T: Storage + Clone + 'static>(
@@ -177,30 +223,85 @@ pub async fn handle_request<T: Storage + Clone + 'static>(
     req: RpcRequest,
     svm: &SvmEngine<T>,
 ) -> RpcResponse {
-    let result = match req.method {
-        RpcMethod::GetAccountInfo => get_account_info(id, &req, svm).await,
-        RpcMethod::GetBalance => get_balance(id, &req, svm).await,
-        RpcMethod::GetBlock => get_block(id, &req, svm),
-        RpcMethod::GetBlockCommitment => get_block_commitment(id, &req, svm),
+    let started = Instant::now();
+    let result = dispatch(id, &req, svm).await;
+    metrics::record_rpc(
+        id,
+        "http",
+        &req.method.to_string(),
+        started.elapsed(),
+        result.is_err(),
+        req.params.as_ref().map(metrics::json_size).unwrap_or(0),
+        match &result {
+            Ok(r) => metrics::json_size(r),
+            Err(e) => metrics::json_size(e),
+        },
+    );
+    if rpc_log::sampled(id) {
+        rpc_log::log_exchange(
+            id,
+            "http",
+            &req.method.to_string(),
+            req.params.as_ref().unwrap_or(&Value::Null),
+            &result,
+        );
+    }
+
+    match result {
+        Ok(r) => RpcResponse {
+            jsonrpc: req.jsonrpc,
+            id: req.id,
+            result: Some(r),
+            error: None,
+            mirror_debug: None,
+        },
+        Err(e) => RpcResponse {
+            jsonrpc: req.jsonrpc,
+            id: req.id,
+            result: None,
+            error: Some(e),
+            mirror_debug: None,
+        },
+    }
+}
+
+async fn dispatch<T: Storage + Clone + 'static>(
+    id: Uuid,
+    req: &RpcRequest,
+    svm: &SvmEngine<T>,
+) -> Result<Value, Value> {
+    svm.touch(id);
+    match req.method {
+        RpcMethod::GetAccountInfo => get_account_info(id, req, svm).await,
+        RpcMethod::GetBalance => get_balance(id, req, svm).await,
+        RpcMethod::GetBlock => get_block(id, req, svm),
+        RpcMethod::GetBlockCommitment => get_block_commitment(id, req, svm),
         RpcMethod::GetBlockHeight => get_block_height(id, svm),
-        RpcMethod::GetBlockProduction => Ok(serde_json::json!({
-                "context": {
-                  "slot": 9887,"apiVersion":"2.1.13"
-                },
+        RpcMethod::GetBlockProduction => {
+            let slot = context_slot(id, svm)?;
+            let identity = svm.get_identity(id).map_err(|e| {
+                serde_json::json!({
+                    "code": -32002,
+                    "message": e,
+                })
+            })?;
+            Ok(serde_json::json!({
+                "context": rpc_context(slot),
                 "value": {
                   "byIdentity": {
-                    "85iYT5RuzRTDgjyRa3cP8SYhM2j21fj7NhfJ3peu1DPr": [9888, 9886]
+                    identity.to_string(): [slot, slot]
                   },
                   "range": {
                     "firstSlot": 0,
-                    "lastSlot": 9887
+                    "lastSlot": slot
                   }
                 }
-        })),
+            }))
+        }
         RpcMethod::GetBlocks => Ok(serde_json::json!([5, 6, 7, 8, 9, 10])),
         RpcMethod::GetBlocksWithLimit => Ok(serde_json::json!([5, 6, 7, 8, 9, 10])),
-        RpcMethod::GetBlockTime => get_block_time(id, &req, svm),
-        RpcMethod::GetClusterNodes => Ok(serde_json::json!([])),
+        RpcMethod::GetBlockTime => get_block_time(id, req, svm),
+        RpcMethod::GetClusterNodes => get_cluster_nodes(id, svm),
         RpcMethod::GetEpochInfo => get_epoch_info(id, svm),
         RpcMethod::GetEpochSchedule => Ok(serde_json::json!({
                 "firstNormalEpoch": 8,
@@ -209,9 +310,7 @@ pub async fn handle_request<T: Storage + Clone + 'static>(
                 "slotsPerEpoch": 8192,
                 "warmup": true
         })),
-        RpcMethod::GetFeeForMessage => Ok(serde_json::json!({
-            "context": { "slot": 5068,"apiVersion":"2.1.13" }, "value": 5000
-        })),
+        RpcMethod::GetFeeForMessage => get_fee_for_message(id, req, svm),
         RpcMethod::GetFirstAvailableBlock => Ok(serde_json::json!(1)),
         RpcMethod::GetGenesisHash => get_genesis_hash(id, svm),
         RpcMethod::GetHealth => get_health(),
@@ -244,37 +343,33 @@ pub async fn handle_request<T: Storage + Clone + 'static>(
         RpcMethod::GetMaxRetransmitSlot => get_block_height(id, svm),
         RpcMethod::GetMaxShredInsertSlot => get_block_height(id, svm),
         RpcMethod::GetMinimumBalanceForRentExemption => {
-            get_minimum_balance_for_rent_exemption(&req, svm)
+            get_minimum_balance_for_rent_exemption(req, svm)
         }
-        RpcMethod::GetMultipleAccounts => get_multiple_accounts(id, &req, svm).await,
-        RpcMethod::GetProgramAccounts => get_program_accounts(id, &req, svm),
+        RpcMethod::GetMultipleAccounts => get_multiple_accounts(id, req, svm).await,
+        RpcMethod::GetProgramAccounts => get_program_accounts(id, req, svm),
         RpcMethod::GetRecentPerformanceSamples => Ok(serde_json::json!([{
           "numSlots": 126,
           "numTransactions": 126,
           "numNonVoteTransactions": 1,
           "samplePeriodSecs": 60,
-          "slot": 348125
+          "slot": context_slot(id, svm)?
         }])),
         RpcMethod::GetRecentPrioritizationFees => Ok(serde_json::json!([{
-          "slot": 348125,
+          "slot": context_slot(id, svm)?,
           "prioritizationFee": 0
         }])),
-        RpcMethod::GetSignaturesForAddress => get_signatures_for_address(id, &req, svm),
-        RpcMethod::GetSignatureStatuses => get_signature_statuses(id, &req, svm),
-        RpcMethod::GetSlot => get_block_height(id, svm),
+        RpcMethod::GetSignaturesForAddress => get_signatures_for_address(id, req, svm),
+        RpcMethod::GetSignatureStatuses => get_signature_statuses(id, req, svm),
+        RpcMethod::GetSlot => get_slot(id, req, svm),
         RpcMethod::GetSlotLeader => get_identity(id, svm),
-        RpcMethod::GetSlotLeaders => get_slot_leaders(id, &req, svm),
-        RpcMethod::GetStakeMinimumDelegation => Err(serde_json::json!({
-            "context": {
-                "slot": 501,"apiVersion":"2.1.13"
-              },
-              "value": 1000000000
+        RpcMethod::GetSlotLeaders => get_slot_leaders(id, req, svm),
+        RpcMethod::GetStakeMinimumDelegation => Ok(serde_json::json!({
+            "context": rpc_context(context_slot(id, svm)?),
+            "value": 1000000000
         })),
         //TODO: fix this
         RpcMethod::GetSupply => Ok(serde_json::json!({
-            "context": {
-                "slot": 1114,"apiVersion":"2.1.13"
-              },
+            "context": rpc_context(context_slot(id, svm)?),
               "value": {
                 "circulating": 16000,
                 "nonCirculating": 1000000,
@@ -287,18 +382,18 @@ pub async fn handle_request<T: Storage + Clone + 'static>(
                 "total": 1016000
               }
         })),
-        RpcMethod::GetTokenAccountBalance => get_token_account_balance(id, &req, svm).await,
+        RpcMethod::GetTokenAccountBalance => get_token_account_balance(id, req, svm).await,
         RpcMethod::GetTokenAccountsByDelegate => Err(serde_json::json!({
             "code": -32601,
             "message": "Method not found",
         })),
-        RpcMethod::GetTokenAccountsByOwner => get_token_accounts_by_owner(id, &req, svm).await,
+        RpcMethod::GetTokenAccountsByOwner => get_token_accounts_by_owner(id, req, svm).await,
         RpcMethod::GetTokenLargestAccounts => Err(serde_json::json!({
             "code": -32601,
             "message": "Method not found",
         })),
-        RpcMethod::GetTokenSupply => get_token_supply(id, &req, svm).await,
-        RpcMethod::GetTransaction => get_transaction(id, &req, svm),
+        RpcMethod::GetTokenSupply => get_token_supply(id, req, svm).await,
+        RpcMethod::GetTransaction => get_transaction(id, req, svm),
         RpcMethod::GetTransactionCount => get_transaction_count(id, svm),
         RpcMethod::GetVersion => get_version(),
         RpcMethod::GetVoteAccounts => Ok(serde_json::json!({
@@ -318,11 +413,11 @@ pub async fn handle_request<T: Storage + Clone + 'static>(
               ],
               "delinquent": []
         })),
-        RpcMethod::IsBlockhashValid => is_blockhash_valid(id, &req, svm),
+        RpcMethod::IsBlockhashValid => is_blockhash_valid(id, req, svm),
         RpcMethod::MinimumLedgerSlot => Ok(serde_json::json!(0)),
-        RpcMethod::RequestAirdrop => request_airdrop(id, &req, svm).await,
-        RpcMethod::SendTransaction => send_transaction(id, &req, svm).await,
-        RpcMethod::SimulateTransaction => simulate_transaction(id, &req, svm).await,
+        RpcMethod::RequestAirdrop => request_airdrop(id, req, svm).await,
+        RpcMethod::SendTransaction => send_transaction(id, req, svm).await,
+        RpcMethod::SimulateTransaction => simulate_transaction(id, req, svm).await,
         RpcMethod::GetAsset => Err(serde_json::json!({
                 "jsonrpc": "2.0",
                 "error": {
@@ -331,24 +426,34 @@ pub async fn handle_request<T: Storage + Clone + 'static>(
                 },
                 "id": "A5JxZVHgXe7fn5TqJXm6Hj2zKh1ptDapae2YjtXbZJoy"
         })),
-    };
+        RpcMethod::MirrorGetTokenBalanceChanges => mirror_get_token_balance_changes(id, req, svm),
+        RpcMethod::MirrorListNonceAccounts => mirror_list_nonce_accounts(id, req, svm),
+        RpcMethod::MirrorSetMintAuthority => mirror_set_mint_authority(id, req, svm).await,
+        RpcMethod::MirrorSetTokenBalance => mirror_set_token_balance(id, req, svm).await,
+        RpcMethod::MirrorSetUpgradeAuthority => mirror_set_upgrade_authority(id, req, svm).await,
+        RpcMethod::MirrorSimulateWithTrace => mirror_simulate_with_trace(id, req, svm).await,
+    }
+}
 
-    match result {
-        Ok(r) => RpcResponse {
-            jsonrpc: req.jsonrpc,
-            id: req.id,
-            result: Some(r),
-            error: None,
-        },
-        Err(e) => RpcResponse {
-            jsonrpc: req.jsonrpc,
-            id: req.id,
-            result: None,
-            error: Some(e),
-        },
+/// The slot a request's reads are stamped with. Read once, before any data, so
+/// `context.slot` never claims state newer than the response returns.
+pub fn context_slot<T: Storage + Clone + 'static>(
+    id: Uuid,
+    svm: &SvmEngine<T>,
+) -> Result<u64, Value> {
+    match svm.get_latest_block(id) {
+        Ok(block) => Ok(block.block_height),
+        Err(_) => Err(serde_json::json!({
+            "code": -32002,
+            "message": "Failed to get latest block",
+        })),
     }
 }
 
+pub fn rpc_context(slot: u64) -> Value {
+    serde_json::json!({ "slot": slot, "apiVersion": "2.1.13" })
+}
+
 pub fn parse_pubkey(pubkey_str: &str) -> Result<Pubkey, Value> {
     match Pubkey::from_str(pubkey_str) {
         Ok(pk) => Ok(pk),
@@ -385,6 +490,30 @@ pub fn encode_account<T: ReadableAccount>(
     }
 }
 
+/// The `-32015` error for a transaction newer than the client's `maxSupportedTransactionVersion`.
+pub fn unsupported_transaction_version(version: u8) -> Value {
+    let error = jsonrpc_core::Error::from(RpcCustomError::UnsupportedTransactionVersion(version));
+    serde_json::json!({
+        "code": error.code.code(),
+        "message": error.message,
+    })
+}
+
+/// Whether a client supporting up to `max_supported` can read the transaction.
+pub fn check_transaction_version(
+    version: &TransactionVersion,
+    max_supported: Option<u8>,
+) -> Result<(), Value> {
+    match version {
+        TransactionVersion::Number(version)
+            if max_supported.is_none_or(|max_supported| *version > max_supported) =>
+        {
+            Err(unsupported_transaction_version(*version))
+        }
+        _ => Ok(()),
+    }
+}
+
 pub fn parse_signature(sig_str: &str) -> Result<Signature, Value> {
     match Signature::from_str(sig_str) {
         Ok(pk) => Ok(pk),
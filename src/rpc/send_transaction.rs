@@ -5,7 +5,7 @@ use solana_transaction_status_client_types::UiTransactionEncoding;
 use uuid::Uuid;
 
 use crate::{
-    engine::{SvmEngine, SVM},
+    engine::{engine::QUEUE_FULL, simulations::SIMULATIONS_BUSY, SvmEngine, SVM},
     storage::Storage,
 };
 
@@ -79,15 +79,15 @@ pub async fn send_transaction<T: Storage + Clone + 'static>(
         }
     };
 
-    if unsanitized_tx
-        .message
-        .instructions()
-        .iter()
-        .map(|ix| ix.program_id(unsanitized_tx.message.static_account_keys()))
-        .any(|program_id| {
-            program_id.to_owned() == bpf_loader::id()
-                || program_id.to_owned() == bpf_loader_upgradeable::id()
-        })
+    if !svm.anchor_compat()
+        && unsanitized_tx
+            .message
+            .instructions()
+            .iter()
+            .map(|ix| ix.program_id(unsanitized_tx.message.static_account_keys()))
+            .any(|program_id| {
+                *program_id == bpf_loader::id() || *program_id == bpf_loader_upgradeable::id()
+            })
     {
         return Err(serde_json::json!({
             "code": -32602,
@@ -107,42 +107,96 @@ pub async fn send_transaction<T: Storage + Clone + 'static>(
 
     let mut jit = blockchain.jit;
     if !skip_preflight {
-        match svm
-            .simulate_transaction(id, unsanitized_tx.clone(), false)
-            .await
-        {
+        match preflight(id, &unsanitized_tx, false, svm).await {
             // If the tx passed without jit, run it without jit regardless of the blockchain setting
             Ok(_) => jit = false,
-            Err(e) => {
-                // If the tx failed and the blockchain is set to jit, try it with jit
-                if jit {
-                    match svm
-                        .simulate_transaction(id, unsanitized_tx.clone(), false)
-                        .await
-                    {
-                        Ok(_) => jit = true,
-                        Err(e) => {
-                            return Err(serde_json::json!({
-                                "code": -32602,
-                                "message": e,
-                            }));
-                        }
-                    }
-                } else {
-                    // If the tx failed and the blockchain is not set to jit, return the error
-                    return Err(serde_json::json!({
-                        "code": -32602,
-                        "message": e,
-                    }));
-                }
-            }
+            // Retrying with jit would only queue another simulation
+            Err(e) if e["code"] == -32005 => return Err(e),
+            // If the tx failed and the blockchain is set to jit, try it with jit
+            Err(_) if jit => preflight(id, &unsanitized_tx, true, svm).await?,
+            // If the tx failed and the blockchain is not set to jit, return the error
+            Err(e) => return Err(e),
         }
     }
+    take_transaction_quota(blockchain.team_id, svm)?;
     match svm.send_transaction(id, unsanitized_tx, jit) {
         Ok(res) => Ok(serde_json::json!(res)),
+        Err(e) if e == QUEUE_FULL => Err(serde_json::json!({
+            "code": -32005,
+            "message": e,
+        })),
         Err(e) => Err(serde_json::json!({
             "code": -32602,
             "message": e,
         })),
     }
 }
+
+/// Counts the transaction against its team's daily quota, if the team has one.
+fn take_transaction_quota<T: Storage + Clone + 'static>(
+    team_id: Uuid,
+    svm: &SvmEngine<T>,
+) -> Result<(), Value> {
+    let internal_error = |e: String| {
+        serde_json::json!({
+            "code": -32603,
+            "message": e,
+        })
+    };
+    let Some(max_transactions) = svm
+        .storage
+        .get_team(team_id)
+        .map_err(internal_error)?
+        .and_then(|team| team.max_transactions_per_day)
+    else {
+        return Ok(());
+    };
+    if !svm
+        .storage
+        .take_daily_transaction(team_id, max_transactions.max(0) as u32)
+        .map_err(internal_error)?
+    {
+        return Err(serde_json::json!({
+            "code": -32602,
+            "message": format!(
+                "Your team's quota is {} transactions per day (UTC), try again tomorrow",
+                max_transactions
+            ),
+        }));
+    }
+    Ok(())
+}
+
+/// Simulates the transaction, failing the way the RPC does when it wouldn't succeed.
+async fn preflight<T: Storage + Clone + 'static>(
+    id: Uuid,
+    tx: &VersionedTransaction,
+    jit: bool,
+    svm: &SvmEngine<T>,
+) -> Result<(), Value> {
+    let res = svm
+        .simulate_transaction(id, tx.clone(), jit)
+        .await
+        .map_err(|e| {
+            serde_json::json!({
+                "code": if e == SIMULATIONS_BUSY { -32005 } else { -32602 },
+                "message": e,
+            })
+        })?;
+    match res.err {
+        None => Ok(()),
+        Some(err) => Err(serde_json::json!({
+            "code": -32002,
+            "message": format!("Transaction simulation failed: {}", err),
+            "data": {
+                "err": err,
+                "logs": res.logs,
+                "accounts": null,
+                "unitsConsumed": res.compute_units_consumed,
+                "returnData": null,
+                "innerInstructions": null,
+                "replacementBlockhash": null,
+            },
+        })),
+    }
+}
@@ -1,27 +1,74 @@
 use base64::prelude::*;
 use serde_json::Value;
-use solana_sdk::{account::AccountSharedData, bpf_loader, bpf_loader_upgradeable};
+use solana_account_decoder::UiAccountEncoding;
+use solana_rpc_client_api::config::RpcSimulateTransactionConfig;
+use solana_sdk::{
+    bpf_loader, bpf_loader_upgradeable, clock::MAX_PROCESSING_AGE,
+    transaction::VersionedTransaction,
+};
+use solana_transaction_status_client_types::UiTransactionEncoding;
 use uuid::Uuid;
 
 use crate::{
-    engine::{SvmEngine, SVM},
+    engine::{simulations::SIMULATIONS_BUSY, trace::InstructionTrace, SvmEngine, SVM},
     storage::Storage,
 };
 
-use super::rpc::{parse_tx, RpcRequest};
+use super::rpc::{decode_and_deserialize, encode_account, parse_pubkey, rpc_context, RpcRequest};
 
 pub async fn simulate_transaction<T: Storage + Clone + 'static>(
     id: Uuid,
     req: &RpcRequest,
     svm: &SvmEngine<T>,
 ) -> Result<Value, Value> {
-    let tx = match req
+    simulate(id, req, svm, false).await.map(|(res, _)| res)
+}
+
+/// The `simulateTransaction` response, with the instruction trace when `trace`.
+pub async fn simulate<T: Storage + Clone + 'static>(
+    id: Uuid,
+    req: &RpcRequest,
+    svm: &SvmEngine<T>,
+    trace: bool,
+) -> Result<(Value, Option<Vec<InstructionTrace>>), Value> {
+    let tx_data = match req.params.as_ref().and_then(|params| params.get(0)) {
+        Some(s) => s.as_str().ok_or_else(|| {
+            serde_json::json!({
+                "code": -32602,
+                "message": "`params[0]` should be a string"
+            })
+        })?,
+        None => {
+            return Err(serde_json::json!({
+                "code": -32602,
+                "message": "`params` should have at least 1 argument(s)"
+            }));
+        }
+    };
+    let config: RpcSimulateTransactionConfig = match req
         .params
         .as_ref()
-        .and_then(|params| params.get(0))
-        .and_then(|v| Some(v))
+        .and_then(|params| params.get(1))
+        .filter(|v| !v.is_null())
     {
-        Some(s) => match parse_tx(s.clone()) {
+        Some(config) => serde_json::from_value(config.clone()).map_err(|e| {
+            serde_json::json!({
+                "code": -32602,
+                "message": format!("Invalid params: {}", e),
+            })
+        })?,
+        None => RpcSimulateTransactionConfig::default(),
+    };
+    // Base64 when unspecified, which is what clients of this engine have always sent
+    let tx_encoding = config.encoding.unwrap_or(UiTransactionEncoding::Base64);
+    let binary_encoding = tx_encoding.into_binary_encoding().ok_or_else(|| {
+        serde_json::json!({
+            "code": -32602,
+            "message": format!("unsupported encoding: {tx_encoding}. Supported encodings: base58, base64"),
+        })
+    })?;
+    let (_, mut tx) =
+        match decode_and_deserialize::<VersionedTransaction>(tx_data.to_owned(), binary_encoding) {
             Ok(tx) => tx,
             Err(_) => {
                 return Err(serde_json::json!({
@@ -29,13 +76,26 @@ pub async fn simulate_transaction<T: Storage + Clone + 'static>(
                     "message": "Invalid params: unable to parse tx"
                 }));
             }
-        },
-        None => {
-            return Err(serde_json::json!({
-                "code": -32602,
-                "message": "`params` should have at least 1 argument(s)"
-            }));
+        };
+    let requested_accounts = match &config.accounts {
+        Some(accounts) => {
+            if accounts.encoding == Some(UiAccountEncoding::JsonParsed) {
+                return Err(serde_json::json!({
+                    "code": -32602,
+                    "message": "jsonParsed encoding is not supported for simulation accounts",
+                }));
+            }
+            let pubkeys = accounts
+                .addresses
+                .iter()
+                .map(|address| parse_pubkey(address))
+                .collect::<Result<Vec<_>, Value>>()?;
+            Some((
+                pubkeys,
+                accounts.encoding.unwrap_or(UiAccountEncoding::Base64),
+            ))
         }
+        None => None,
     };
 
     let slot = match svm.get_latest_block(id) {
@@ -48,15 +108,15 @@ pub async fn simulate_transaction<T: Storage + Clone + 'static>(
         }
     };
 
-    if tx
-        .message
-        .instructions()
-        .iter()
-        .map(|ix| ix.program_id(tx.message.static_account_keys()))
-        .any(|program_id| {
-            program_id.to_owned() == bpf_loader::id()
-                || program_id.to_owned() == bpf_loader_upgradeable::id()
-        })
+    if !svm.anchor_compat()
+        && tx
+            .message
+            .instructions()
+            .iter()
+            .map(|ix| ix.program_id(tx.message.static_account_keys()))
+            .any(|program_id| {
+                *program_id == bpf_loader::id() || *program_id == bpf_loader_upgradeable::id()
+            })
     {
         return Err(serde_json::json!({
             "code": -32602,
@@ -74,27 +134,77 @@ pub async fn simulate_transaction<T: Storage + Clone + 'static>(
         }
     };
 
-    match svm.simulate_transaction(id, tx, blockchain.jit).await {
+    let replacement_blockhash = match config.replace_recent_blockhash {
+        true => Some(serde_json::json!({
+            "blockhash": slot.blockhash.to_string(),
+            "lastValidBlockHeight": slot.block_height + MAX_PROCESSING_AGE as u64,
+        })),
+        false => None,
+    };
+    if config.replace_recent_blockhash {
+        if config.sig_verify {
+            return Err(serde_json::json!({
+                "code": -32602,
+                "message": "sigVerify may not be used with replaceRecentBlockhash",
+            }));
+        }
+        tx.message.set_recent_blockhash(slot.blockhash);
+    }
+
+    let simulated = match trace {
+        true => svm.trace_transaction(id, tx, blockchain.jit).await,
+        false => svm.simulate_transaction(id, tx, blockchain.jit).await,
+    };
+    match simulated {
         Ok(res) => {
-            let return_data_str = BASE64_STANDARD.encode(&res.return_data.data);
-            Ok(serde_json::json!({
-                "context": {
-                    "slot": slot.block_height,"apiVersion":"2.1.13"
-                  },
+            let accounts = match requested_accounts {
+                Some((pubkeys, encoding)) => Some(
+                    pubkeys
+                        .iter()
+                        .map(|pubkey| {
+                            res.post_accounts
+                                .iter()
+                                .find(|(address, _)| address == pubkey)
+                                .map(|(_, account)| {
+                                    encode_account(account, pubkey, encoding, None, None)
+                                })
+                                .transpose()
+                        })
+                        .collect::<Result<Vec<_>, String>>()
+                        .map_err(|e| {
+                            serde_json::json!({
+                                "code": -32602,
+                                "message": e,
+                            })
+                        })?,
+                ),
+                None => None,
+            };
+            let return_data = match res.return_data.data.is_empty() {
+                true => None,
+                false => Some(serde_json::json!({
+                    "data": [BASE64_STANDARD.encode(&res.return_data.data), "base64"],
+                    "programId": res.return_data.program_id.to_string(),
+                })),
+            };
+            let value = serde_json::json!({
+                "context": rpc_context(slot.block_height),
                   "value": {
                     "err": res.err,
-                    "accounts": res.post_accounts.iter().map(|(_, account)|  {
-                        account
-                    }).collect::<Vec<&AccountSharedData>>(),
+                    "accounts": accounts,
                     "logs": res.logs,
-                    "returnData": {
-                      "data": [return_data_str, "base64"],
-                      "programId": res.return_data.program_id.to_string(),
-                    },
+                    "returnData": return_data,
                     "unitsConsumed": res.compute_units_consumed,
+                    "innerInstructions": null,
+                    "replacementBlockhash": replacement_blockhash,
                   }
-            }))
+            });
+            Ok((value, res.trace))
         }
+        Err(e) if e == SIMULATIONS_BUSY => Err(serde_json::json!({
+            "code": -32005,
+            "message": e,
+        })),
         Err(e) => Err(serde_json::json!({
             "code": -32602,
             "message": e,
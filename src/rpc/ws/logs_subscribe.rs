@@ -1,9 +1,10 @@
 use actix_ws::Session;
+use serde_json::Value;
 use uuid::Uuid;
 
 use crate::{
-    engine::{SvmEngine, SVM},
-    rpc::rpc::parse_pubkey,
+    engine::{subscriptions::LogsFilter, SvmEngine, SVM},
+    rpc::rpc::{parse_pubkey, rpc_context},
     storage::Storage,
 };
 
@@ -15,37 +16,36 @@ pub async fn logs_subscribe<T: Storage + Clone + 'static>(
     mut session: Session,
     svm: &SvmEngine<T>,
 ) -> Result<(), String> {
-    let mentions = match req
-        .params
-        .as_ref()
-        .and_then(|params| params.get(0))
-        .and_then(|v| v.as_object())
-        .and_then(|obj| obj.get("mentions"))
-        .and_then(|v| v.as_array())
-    {
-        Some(arr) => arr,
-        None => {
-            println!(
-                "metions params: {:?}",
-                req.params
-                    .as_ref()
-                    .and_then(|params| params.get(0))
-                    .and_then(|v| v.as_object())
-            );
-            return Err("`params` should have an argument with a `mentions` field".to_string());
+    let filter = match req.params.as_ref().and_then(|params| params.get(0)) {
+        Some(Value::String(filter)) if filter == "all" => LogsFilter::All,
+        Some(Value::String(filter)) if filter == "allWithVotes" => LogsFilter::AllWithVotes,
+        Some(Value::Object(obj)) => {
+            let mentions = match obj.get("mentions").and_then(|v| v.as_array()) {
+                Some(arr) => arr,
+                None => {
+                    return Err(
+                        "`params` should have an argument with a `mentions` field".to_string()
+                    );
+                }
+            };
+            if mentions.len() != 1 {
+                return Err("`mentions` must have 1 argument".to_string());
+            }
+            let pubkey_str = match mentions[0].as_str() {
+                Some(s) => s,
+                None => {
+                    return Err("`mentions` should be a string".to_string());
+                }
+            };
+            LogsFilter::Mentions(parse_pubkey(pubkey_str).map_err(|e| e.to_string())?)
         }
-    };
-
-    if mentions.len() != 1 {
-        return Err("`mentions` must have 1 argument".to_string());
-    }
-    let pubkey_str = match mentions[0].as_str() {
-        Some(s) => s,
-        None => {
-            return Err("`mentions` should be a string".to_string());
+        _ => {
+            return Err(
+                "`params` should start with \"all\", \"allWithVotes\" or a `mentions` filter"
+                    .to_string(),
+            );
         }
     };
-    let pubkey = parse_pubkey(pubkey_str).map_err(|e| e.to_string())?;
 
     let sub_id = rand::random::<u32>();
     session
@@ -60,7 +60,7 @@ pub async fn logs_subscribe<T: Storage + Clone + 'static>(
         .await
         .map_err(|e| e.to_string())?;
 
-    let mut receiver = match svm.logs_subscribe(id, sub_id, &pubkey) {
+    let mut receiver = match svm.logs_subscribe(id, sub_id, &filter) {
         Ok(rec) => rec,
         Err(e) => {
             println!("Error: {:?}", e);
@@ -74,7 +74,7 @@ pub async fn logs_subscribe<T: Storage + Clone + 'static>(
             Some(res) => res,
             None => return Ok(()),
         };
-        let (signature, _, transaction_meta, _) = match res {
+        let (signature, _, transaction_meta, status) = match res {
             Some(res) => res,
             None => return Ok(()),
         };
@@ -87,9 +87,7 @@ pub async fn logs_subscribe<T: Storage + Clone + 'static>(
                   "method": "logsNotification",
                   "params": {
                     "result": {
-                        "context": {
-                          "slot": 5208469
-                        },
+                        "context": rpc_context(status.slot),
                         "value": {
                           "signature": signature.to_string(),
                           "err": transaction_meta.err,
@@ -1,16 +1,20 @@
-use crate::{engine::SvmEngine, storage::Storage};
+use crate::{engine::SvmEngine, metrics, rpc_log, storage::Storage};
 use actix_ws::Session;
 use futures::TryFutureExt;
 use logs_subscribe::logs_subscribe;
 use logs_unsubscribe::logs_unsubscribe;
 use serde::Deserialize;
+use serde_json::Value;
 use signature_subscribe::signature_subscribe;
+use signature_unsubscribe::signature_unsubscribe;
 use slot_subscribe::slot_subscribe;
 use slot_unsubscribe::slot_unsubscribe;
+use std::time::Instant;
 use uuid::Uuid;
 pub mod logs_subscribe;
 pub mod logs_unsubscribe;
 pub mod signature_subscribe;
+pub mod signature_unsubscribe;
 pub mod slot_subscribe;
 pub mod slot_unsubscribe;
 
@@ -51,8 +55,42 @@ pub async fn handle_ws_request<T: Storage + Clone + 'static>(
     session: Session,
     svm: &SvmEngine<T>,
 ) -> Result<(), String> {
-    let req: RpcRequest = serde_json::from_str(msg).map_err(|e| e.to_string())?;
+    let started = Instant::now();
+    let req: RpcRequest = match serde_json::from_str(msg) {
+        Ok(req) => req,
+        Err(e) => {
+            metrics::record_rpc(id, "ws", "invalid", started.elapsed(), true, msg.len(), 0);
+            return Err(e.to_string());
+        }
+    };
+    let result = dispatch(id, req.clone(), session, svm).await;
+    // Notifications are written to the session as they happen, so only requests are sized
+    metrics::record_rpc(
+        id,
+        "ws",
+        &format!("{:?}", req.method),
+        started.elapsed(),
+        result.is_err(),
+        msg.len(),
+        0,
+    );
+    if rpc_log::sampled(id) {
+        let response = match &result {
+            Ok(()) => Ok(Value::Null),
+            Err(e) => Err(Value::String(e.clone())),
+        };
+        rpc_log::log_exchange(id, "ws", &format!("{:?}", req.method), msg, &response);
+    }
+    result
+}
 
+async fn dispatch<T: Storage + Clone + 'static>(
+    id: Uuid,
+    req: RpcRequest,
+    session: Session,
+    svm: &SvmEngine<T>,
+) -> Result<(), String> {
+    svm.touch(id);
     match req.method {
         RpcMethod::AccountSubscribe => {
             session
@@ -129,10 +167,7 @@ pub async fn handle_ws_request<T: Storage + Clone + 'static>(
                 .await?;
         }
         RpcMethod::SignatureSubscribe => signature_subscribe(id, &req, session, svm).await?,
-        RpcMethod::SignatureUnsubscribe => {
-            println!("SignatureUnsubscribe");
-            signature_subscribe(id, &req, session.clone(), svm).await? //TODO: This should be its own function
-        }
+        RpcMethod::SignatureUnsubscribe => signature_unsubscribe(&req, session, svm).await?,
         RpcMethod::SlotSubscribe => slot_subscribe(id, &req, session, svm).await?,
         RpcMethod::SlotsUpdatesSubscribe => {
             session
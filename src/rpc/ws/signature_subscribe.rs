@@ -1,11 +1,10 @@
 use actix_ws::Session;
-use chrono::Utc;
 use solana_banks_interface::TransactionConfirmationStatus;
 use uuid::Uuid;
 
 use crate::{
     engine::{SvmEngine, SVM},
-    rpc::rpc::parse_signature,
+    rpc::rpc::{parse_signature, rpc_context},
     storage::Storage,
 };
 
@@ -28,25 +27,22 @@ pub async fn signature_subscribe<T: Storage + Clone + 'static>(
             return Err("`params` should have at least 1 argument(s)".to_string());
         }
     };
-    let commitment = match req
+    // Same default as a validator, web3.js and the CLI don't always pass one
+    let commitment = req
         .params
         .as_ref()
         .and_then(|params| params.get(1))
         .and_then(|v| v.as_object())
         .and_then(|obj| obj.get("commitment"))
         .and_then(|v| v.as_str())
-    {
-        Some(c) => c.to_string(),
-        None => {
-            return Err("`commitment` parameter is missing or invalid".to_string());
-        }
-    };
-    let confirmation = match commitment.as_str() {
+        .unwrap_or("finalized");
+    let confirmation = match commitment {
         "finalized" => TransactionConfirmationStatus::Finalized,
         "confirmed" => TransactionConfirmationStatus::Confirmed,
         "processed" => TransactionConfirmationStatus::Processed,
         _ => return Err("Invalid `commitment` value".to_string()),
     };
+    let signature = parse_signature(sig_str).map_err(|e| e.to_string())?;
 
     let sub_id = rand::random::<u32>();
     session
@@ -61,37 +57,33 @@ pub async fn signature_subscribe<T: Storage + Clone + 'static>(
         .await
         .map_err(|e| e.to_string())?;
 
-    let signature = parse_signature(sig_str).map_err(|e| e.to_string())?;
-    match svm.signature_subscribe(id, &signature, confirmation).await {
-        Ok(slot) => {
-            println!("Signature subscribed: {}", Utc::now().to_rfc3339());
-            session
-                .text(
-                    serde_json::json!({
-                      "jsonrpc": "2.0",
-                      "method": "signatureNotification",
-                      "params": {
-                        "result": {
-                          "context": {
-                            "slot": slot+10,"apiVersion":"2.1.13" //hardcoded
-                          },
-                          "value": {
-                            "err": null
-                          }
-                        },
-                        "subscription": sub_id
-                      }
-                    })
-                    .to_string(),
-                )
-                .await
-                .map_err(|e| e.to_string())?
-        }
-        Err(e) => {
-            println!("Error: {:?}", e);
-            return Err(e);
-        }
-    }
+    // Like a validator, the subscription ends with its notification
+    let status = match svm
+        .signature_subscribe(id, sub_id, &signature, confirmation)
+        .await?
+    {
+        Some(status) => status,
+        None => return Ok(()),
+    };
+    session
+        .text(
+            serde_json::json!({
+              "jsonrpc": "2.0",
+              "method": "signatureNotification",
+              "params": {
+                "result": {
+                  "context": rpc_context(status.slot),
+                  "value": {
+                    "err": status.err
+                  }
+                },
+                "subscription": sub_id
+              }
+            })
+            .to_string(),
+        )
+        .await
+        .map_err(|e| e.to_string())?;
 
     Ok(())
 }
@@ -0,0 +1,90 @@
+use std::{
+    collections::HashSet,
+    fmt::Display,
+    sync::{Mutex, OnceLock},
+};
+
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::settings::LoggingSettings;
+
+/// Target full request and response bodies are logged under, filter it like a module.
+pub const TARGET: &str = "rpc_bodies";
+
+static SAMPLE_RATE: OnceLock<f64> = OnceLock::new();
+// Per instance, like the metrics, so debug mode has to be switched on everywhere
+static DEBUG_BLOCKCHAINS: OnceLock<Mutex<HashSet<Uuid>>> = OnceLock::new();
+
+/// Installs the logger with the configured filter and loads the sampling settings.
+pub fn init(settings: &LoggingSettings) {
+    let _ = env_logger::Builder::new()
+        .parse_filters(&settings.level)
+        .try_init();
+    let _ = SAMPLE_RATE.set(settings.sample_rate);
+    debug_blockchains()
+        .lock()
+        .unwrap()
+        .extend(&settings.debug_blockchains);
+}
+
+fn debug_blockchains() -> &'static Mutex<HashSet<Uuid>> {
+    DEBUG_BLOCKCHAINS.get_or_init(Default::default)
+}
+
+/// Switches full body logging on or off for a blockchain, returns whether it changed.
+pub fn set_debug(blockchain: Uuid, enabled: bool) -> bool {
+    let mut blockchains = debug_blockchains().lock().unwrap();
+    match enabled {
+        true => blockchains.insert(blockchain),
+        false => blockchains.remove(&blockchain),
+    }
+}
+
+pub fn debug_enabled(blockchain: Uuid) -> bool {
+    debug_blockchains().lock().unwrap().contains(&blockchain)
+}
+
+pub fn debug_list() -> Vec<Uuid> {
+    let mut blockchains: Vec<Uuid> = debug_blockchains()
+        .lock()
+        .unwrap()
+        .iter()
+        .copied()
+        .collect();
+    blockchains.sort();
+    blockchains
+}
+
+/// Whether this request's bodies should be logged.
+pub fn sampled(blockchain: Uuid) -> bool {
+    if !log::log_enabled!(target: TARGET, log::Level::Info) {
+        return false;
+    }
+    let rate = SAMPLE_RATE.get().copied().unwrap_or(0.0);
+    debug_enabled(blockchain) || (rate > 0.0 && rand::random::<f64>() < rate)
+}
+
+/// Logs a request and what it was answered with, `transport` is `http` or `ws`.
+pub fn log_exchange(
+    blockchain: Uuid,
+    transport: &str,
+    method: &str,
+    request: impl Display,
+    response: &Result<Value, Value>,
+) {
+    let (status, body) = match response {
+        Ok(body) => ("ok", body),
+        Err(body) => ("error", body),
+    };
+    log::info!(
+        target: TARGET,
+        "blockchain={} transport={} method={} status={} request={} response={}",
+        blockchain,
+        transport,
+        method,
+        status,
+        request,
+        body
+    );
+}
@@ -11,10 +11,16 @@ allow_tables_to_appear_in_same_query!(
     accounts,
     blocks,
     blockchains,
+    blockchain_sysvars,
     teams,
     api_keys,
+    audit_logs,
     blockchain_configs,
-    blockchain_config_accounts
+    blockchain_config_accounts,
+    program_idls,
+    webhooks,
+    pending_transactions,
+    top_ups
 );
 
 table! {
@@ -54,6 +60,21 @@ table! {
         label -> Nullable<Text>,
         expiry -> Nullable<Timestamp>,
         jit -> Bool,
+        config -> Nullable<Uuid>,
+        pooled -> Bool,
+        log_bytes_limit -> Nullable<Integer>,
+        read_only -> Bool,
+        sponsor_fees -> Bool,
+        parity_check -> Bool,
+        accounts_status -> Text,
+    }
+}
+
+table! {
+    blockchain_sysvars (blockchain) {
+        blockchain -> Uuid,
+        updated_at -> Timestamp,
+        overrides -> Text,
     }
 }
 
@@ -66,6 +87,11 @@ table! {
         recent_blockhash -> Bytea,
         slot -> Numeric,
         blockchain -> Uuid,
+        block_time -> Nullable<Int8>,
+        message -> Nullable<Bytea>,
+        compute_unit_price -> Nullable<Numeric>,
+        fee_payer -> Nullable<Text>,
+        is_vote -> Bool,
     }
 }
 
@@ -78,6 +104,8 @@ table! {
         signer -> Bool,
         writable -> Bool,
         index -> SmallInt,
+        lookup -> Bool,
+        owner -> Nullable<Text>,
     }
 }
 
@@ -101,6 +129,7 @@ table! {
         transaction_signature -> Text,
         log -> Text,
         index -> SmallInt,
+        program_id -> Nullable<Text>,
     }
 }
 
@@ -114,6 +143,7 @@ table! {
         fee -> Numeric,
         pre_balances -> Array<BigInt>,
         post_balances -> Array<BigInt>,
+        sponsored_fee -> Nullable<Numeric>,
     }
 }
 
@@ -132,6 +162,11 @@ table! {
         created_at -> Timestamp,
         name -> Text,
         default_expiry -> Nullable<Integer>,
+        max_blockchains -> Nullable<Integer>,
+        max_accounts -> Nullable<Integer>,
+        max_transactions_per_day -> Nullable<Integer>,
+        auto_pause_hours -> Nullable<Integer>,
+        rpc_write_guard -> Bool,
     }
 }
 
@@ -141,6 +176,9 @@ table! {
         created_at -> Timestamp,
         team_id -> Uuid,
         label -> Text,
+        scope -> Text,
+        revoked_at -> Nullable<Timestamp>,
+        role -> Text,
     }
 }
 
@@ -164,6 +202,8 @@ table! {
         id -> Uuid,
         created_at -> Timestamp,
         label -> Text,
+        team_id -> Nullable<Uuid>,
+        bundles -> Array<Text>,
     }
 }
 
@@ -181,3 +221,63 @@ table! {
         config -> Uuid,
     }
 }
+
+table! {
+    program_idls (id) {
+        id -> Uuid,
+        created_at -> Timestamp,
+        blockchain -> Uuid,
+        program_id -> Varchar,
+        idl -> Text,
+    }
+}
+
+table! {
+    webhooks (id) {
+        id -> Uuid,
+        created_at -> Timestamp,
+        team_id -> Uuid,
+        blockchain -> Uuid,
+        url -> Text,
+        secret -> Text,
+        events -> Array<Text>,
+        accounts -> Array<Text>,
+        labels -> Array<Text>,
+    }
+}
+
+table! {
+    audit_logs (id) {
+        id -> Uuid,
+        created_at -> Timestamp,
+        team_id -> Uuid,
+        api_key -> Nullable<Uuid>,
+        action -> Text,
+        blockchain -> Nullable<Uuid>,
+        summary -> Text,
+    }
+}
+
+table! {
+    pending_transactions (blockchain, signature) {
+        blockchain -> Uuid,
+        signature -> Varchar,
+        created_at -> Timestamp,
+        instance -> Nullable<Uuid>,
+        transaction -> Bytea,
+        jit -> Bool,
+    }
+}
+
+table! {
+    top_ups (id) {
+        id -> Uuid,
+        created_at -> Timestamp,
+        blockchain -> Uuid,
+        address -> Text,
+        mint -> Nullable<Text>,
+        target -> Numeric,
+        interval_seconds -> Int8,
+        last_run -> Nullable<Timestamp>,
+    }
+}
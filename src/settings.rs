@@ -0,0 +1,848 @@
+use serde::{Deserialize, Deserializer};
+use std::{env, fs, path::PathBuf, str::FromStr, sync::OnceLock, time::Duration};
+use uuid::Uuid;
+
+use crate::reporting::Dsn;
+
+const DEFAULT_CONFIG_FILE: &str = "config.toml";
+
+static SETTINGS: OnceLock<Settings> = OnceLock::new();
+
+/// Engine configuration, read from a TOML file (`CONFIG_FILE`, or `config.toml`
+/// when present) with environment variables taking precedence.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Settings {
+    pub env: String, // "dev" serves local URLs and the standalone websocket server
+    pub database_url: String,
+    pub cache_url: String,
+    pub rpc_url: String,
+    pub pubsub_url: String, // Kafka, empty disables the geyser stream
+    pub event_bus: EventBusKind,
+    pub admin_token: Option<String>, // Admin routes are disabled without one
+    pub server: ServerSettings,
+    pub pool: PoolSettings,
+    pub jobs: JobSettings,
+    pub limits: LimitSettings,
+    pub jwt: JwtSettings,
+    pub reporting: ReportingSettings,
+    pub logging: LoggingSettings,
+    pub features: FeatureSettings,
+    pub cluster: ClusterSettings,
+    pub queue: QueueSettings,
+    pub programs: ProgramSettings,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EventBusKind {
+    #[default]
+    Kafka,
+    Redis,
+}
+
+impl FromStr for EventBusKind {
+    type Err = String;
+
+    fn from_str(kind: &str) -> Result<Self, Self::Err> {
+        match kind {
+            "" | "kafka" => Ok(EventBusKind::Kafka),
+            "redis" => Ok(EventBusKind::Redis),
+            _ => Err(format!("Invalid event bus {}", kind)),
+        }
+    }
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            env: "prod".to_string(),
+            database_url: String::new(),
+            cache_url: String::new(),
+            rpc_url: String::new(),
+            pubsub_url: String::new(),
+            event_bus: EventBusKind::default(),
+            admin_token: None,
+            server: ServerSettings::default(),
+            pool: PoolSettings::default(),
+            jobs: JobSettings::default(),
+            limits: LimitSettings::default(),
+            jwt: JwtSettings::default(),
+            reporting: ReportingSettings::default(),
+            logging: LoggingSettings::default(),
+            features: FeatureSettings::default(),
+            cluster: ClusterSettings::default(),
+            queue: QueueSettings::default(),
+            programs: ProgramSettings::default(),
+        }
+    }
+}
+
+impl Settings {
+    /// Reads the config file, applies environment overrides and checks the
+    /// required URLs are set.
+    pub fn load() -> Result<Self, String> {
+        let settings = match env::var("CONFIG_FILE") {
+            Ok(path) => Self::from_file(&path)?,
+            Err(_) if fs::metadata(DEFAULT_CONFIG_FILE).is_ok() => {
+                Self::from_file(DEFAULT_CONFIG_FILE)?
+            }
+            Err(_) => Settings::default(),
+        };
+        let settings = settings.with_env()?;
+        settings.validate()?;
+        Ok(settings)
+    }
+
+    pub fn from_file(path: &str) -> Result<Self, String> {
+        let contents =
+            fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+        Self::from_toml(&contents).map_err(|e| format!("Invalid config file {}: {}", path, e))
+    }
+
+    pub fn from_toml(contents: &str) -> Result<Self, String> {
+        toml::from_str(contents).map_err(|e| e.to_string())
+    }
+
+    /// Overrides with the environment variables the engine has always read.
+    pub fn with_env(self) -> Result<Self, String> {
+        Ok(Settings {
+            env: env_or("ENV", self.env)?,
+            database_url: env_or("DATABASE_URL", self.database_url)?,
+            cache_url: env_or("CACHE_URL", self.cache_url)?,
+            rpc_url: env_or("RPC_URL", self.rpc_url)?,
+            pubsub_url: env_or("PUBSUB_URL", self.pubsub_url)?,
+            event_bus: env_or("EVENT_BUS", self.event_bus)?,
+            admin_token: match env::var("ADMIN_TOKEN") {
+                Ok(token) => Some(token),
+                Err(_) => self.admin_token,
+            },
+            server: self.server.with_env()?,
+            pool: self.pool.with_env()?,
+            jobs: self.jobs.with_env()?,
+            limits: self.limits.with_env()?,
+            jwt: self.jwt.with_env()?,
+            reporting: self.reporting.with_env()?,
+            logging: self.logging.with_env()?,
+            features: self.features.with_env()?,
+            cluster: self.cluster.with_env()?,
+            queue: self.queue.with_env()?,
+            programs: self.programs.with_env()?,
+        })
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        for (key, value) in [
+            ("database_url", &self.database_url),
+            ("cache_url", &self.cache_url),
+            ("rpc_url", &self.rpc_url),
+        ] {
+            if value.is_empty() {
+                return Err(format!("{} must be set", key));
+            }
+        }
+        if self.server.bind.is_empty() {
+            return Err("server.bind must list at least one address".to_string());
+        }
+        if self.jobs.warm_pool_size > 0 && self.jobs.warm_pool_interval.is_zero() {
+            return Err("jobs.warm_pool_interval_ms must be positive".to_string());
+        }
+        if self.jwt.secret.is_some() && self.jwt.issuer.is_none() {
+            return Err("jwt.secret requires jwt.issuer".to_string());
+        }
+        if self.jwt.issuer.is_some() && self.jwt.jwks_refresh.is_zero() {
+            return Err("jwt.jwks_refresh_ms must be positive".to_string());
+        }
+        if !(0.0..=1.0).contains(&self.logging.sample_rate) {
+            return Err("logging.sample_rate must be between 0 and 1".to_string());
+        }
+        if self.cluster.leases {
+            if self.cluster.forward_interval.is_zero() {
+                return Err("cluster.forward_interval_ms must be positive".to_string());
+            }
+            if self.cluster.lease_ttl <= self.cluster.forward_interval {
+                return Err("cluster.lease_ttl_ms must exceed forward_interval_ms".to_string());
+            }
+        }
+        if self.features.persist_queue && self.cluster.instance_id.is_none() {
+            return Err("features.persist_queue requires cluster.instance_id".to_string());
+        }
+        if self.limits.simulation_workers == 0 {
+            return Err("limits.simulation_workers must be positive".to_string());
+        }
+        if self.queue.capacity == 0 {
+            return Err("queue.capacity must be positive".to_string());
+        }
+        if self.queue.kind == QueueKind::Kafka {
+            if self.pubsub_url.is_empty() {
+                return Err("queue.kind = \"kafka\" requires pubsub_url".to_string());
+            }
+            if self.queue.partitions < 1 || self.queue.consumers == 0 {
+                return Err("queue.partitions and queue.consumers must be positive".to_string());
+            }
+        }
+        if let Some(directory) = &self.programs.directory {
+            if !directory.is_dir() {
+                return Err(format!(
+                    "programs.directory {} is not a directory",
+                    directory.display()
+                ));
+            }
+        }
+        if let Some(dsn) = &self.reporting.dsn {
+            Dsn::from_str(dsn).map_err(|e| format!("reporting.dsn: {}", e))?;
+        }
+        Ok(())
+    }
+
+    pub fn is_dev(&self) -> bool {
+        self.env == "dev"
+    }
+
+    /// Base of the RPC URLs handed out for blockchains.
+    pub fn public_url(&self) -> String {
+        match &self.server.public_url {
+            Some(url) => url.clone(),
+            None if self.is_dev() => "http://localhost:8899/rpc/".to_string(),
+            None => "https://rpc.mirror.ad/rpc/".to_string(),
+        }
+    }
+
+    pub fn blockchain_url(&self, id: Uuid) -> String {
+        format!("{}{}", self.public_url(), id)
+    }
+}
+
+/// Makes the loaded settings available to `get`, only the first call wins.
+pub fn init(settings: Settings) {
+    let _ = SETTINGS.set(settings);
+}
+
+/// Settings passed to `init`, or the environment alone when the engine is
+/// embedded without loading them.
+pub fn get() -> &'static Settings {
+    SETTINGS.get_or_init(|| {
+        Settings::default()
+            .with_env()
+            .expect("Invalid settings in environment")
+    })
+}
+
+/// Listen addresses and the URL clients reach the engine on.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerSettings {
+    pub bind: Vec<String>,
+    pub dev_ws_bind: String, // Standalone websocket server, dev only
+    pub public_url: Option<String>,
+    pub workers: Option<usize>, // Defaults to one per core
+}
+
+impl Default for ServerSettings {
+    fn default() -> Self {
+        ServerSettings {
+            bind: vec!["0.0.0.0:8899".to_string(), "[::]:9001".to_string()],
+            dev_ws_bind: "0.0.0.0:8900".to_string(),
+            public_url: None,
+            workers: None,
+        }
+    }
+}
+
+impl ServerSettings {
+    fn with_env(self) -> Result<Self, String> {
+        Ok(ServerSettings {
+            bind: match env::var("BIND_ADDRESSES") {
+                Ok(bind) => bind
+                    .split(',')
+                    .map(|address| address.trim().to_string())
+                    .filter(|address| !address.is_empty())
+                    .collect(),
+                Err(_) => self.bind,
+            },
+            dev_ws_bind: env_or("DEV_WS_BIND", self.dev_ws_bind)?,
+            public_url: match env::var("PUBLIC_URL") {
+                Ok(url) => Some(url),
+                Err(_) => self.public_url,
+            },
+            workers: match env_parse::<usize>("WORKERS")? {
+                Some(workers) => Some(workers),
+                None => self.workers,
+            },
+        })
+    }
+}
+
+/// Upload sizes in bytes, larger requests are rejected with 413, and how long a
+/// transaction may take to load and execute.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LimitSettings {
+    pub max_program_size: usize,
+    pub max_snapshot_size: usize,
+    /// Transactions running past it fail and their writes are dropped.
+    #[serde(
+        rename = "execution_timeout_ms",
+        deserialize_with = "optional_duration_ms"
+    )]
+    pub execution_timeout: Option<Duration>,
+    /// Simulations each blockchain runs at once, more wait in its queue.
+    pub simulation_workers: usize,
+    /// Simulations waiting per blockchain before more are rejected as busy.
+    pub simulation_queue_depth: usize,
+}
+
+impl Default for LimitSettings {
+    fn default() -> Self {
+        LimitSettings {
+            // Largest account the runtime allows
+            max_program_size: 10 * 1024 * 1024,
+            max_snapshot_size: 512 * 1024 * 1024,
+            execution_timeout: Some(Duration::from_secs(5)),
+            simulation_workers: 4,
+            simulation_queue_depth: 32,
+        }
+    }
+}
+
+impl LimitSettings {
+    fn with_env(self) -> Result<Self, String> {
+        Ok(LimitSettings {
+            max_program_size: env_or("MAX_PROGRAM_SIZE", self.max_program_size)?,
+            max_snapshot_size: env_or("MAX_SNAPSHOT_SIZE", self.max_snapshot_size)?,
+            execution_timeout: env_optional_duration(
+                "EXECUTION_TIMEOUT_MS",
+                self.execution_timeout,
+            )?,
+            simulation_workers: env_or("SIMULATION_WORKERS", self.simulation_workers)?,
+            simulation_queue_depth: env_or("SIMULATION_QUEUE_DEPTH", self.simulation_queue_depth)?,
+        })
+    }
+}
+
+/// Bearer JWTs accepted in place of the `api_key` header, enabled by setting the issuer.
+/// Tokens are checked against `secret` (HS256) when set, otherwise against the issuer's
+/// JWKS (RS256/ES256), found through OIDC discovery unless `jwks_url` is given.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct JwtSettings {
+    pub issuer: Option<String>,
+    pub audience: Option<String>,
+    pub jwks_url: Option<String>,
+    pub secret: Option<String>,
+    pub team_claim: String, // Holds the team id
+    pub role_claim: String, // Tokens without one are read only
+    #[serde(rename = "jwks_refresh_ms", deserialize_with = "duration_ms")]
+    pub jwks_refresh: Duration,
+    #[serde(rename = "leeway_ms", deserialize_with = "duration_ms")]
+    pub leeway: Duration,
+}
+
+impl Default for JwtSettings {
+    fn default() -> Self {
+        JwtSettings {
+            issuer: None,
+            audience: None,
+            jwks_url: None,
+            secret: None,
+            team_claim: "team_id".to_string(),
+            role_claim: "role".to_string(),
+            jwks_refresh: Duration::from_secs(300),
+            leeway: Duration::from_secs(60),
+        }
+    }
+}
+
+impl JwtSettings {
+    pub fn enabled(&self) -> bool {
+        self.issuer.is_some()
+    }
+
+    fn with_env(self) -> Result<Self, String> {
+        Ok(JwtSettings {
+            issuer: env::var("JWT_ISSUER").ok().or(self.issuer),
+            audience: env::var("JWT_AUDIENCE").ok().or(self.audience),
+            jwks_url: env::var("JWT_JWKS_URL").ok().or(self.jwks_url),
+            secret: env::var("JWT_SECRET").ok().or(self.secret),
+            team_claim: env_or("JWT_TEAM_CLAIM", self.team_claim)?,
+            role_claim: env_or("JWT_ROLE_CLAIM", self.role_claim)?,
+            jwks_refresh: env_duration("JWT_JWKS_REFRESH_MS")?.unwrap_or(self.jwks_refresh),
+            leeway: env_duration("JWT_LEEWAY_MS")?.unwrap_or(self.leeway),
+        })
+    }
+}
+
+/// Panics and swallowed errors are sent to Sentry when a DSN is set.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ReportingSettings {
+    pub dsn: Option<String>,
+    pub environment: Option<String>, // Defaults to `env`
+}
+
+impl ReportingSettings {
+    fn with_env(self) -> Result<Self, String> {
+        Ok(ReportingSettings {
+            dsn: env::var("SENTRY_DSN").ok().or(self.dsn),
+            environment: env::var("SENTRY_ENVIRONMENT").ok().or(self.environment),
+        })
+    }
+}
+
+/// Log filters and which RPC traffic gets its full request and response bodies logged.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LoggingSettings {
+    /// `env_logger` filter, e.g. `info,mockchain_engine::storage=debug`.
+    pub level: String,
+    /// Fraction of RPC requests logged in full, between 0 and 1.
+    pub sample_rate: f64,
+    /// Blockchains whose RPC traffic is always logged, more can be added through the admin API.
+    pub debug_blockchains: Vec<Uuid>,
+}
+
+impl Default for LoggingSettings {
+    fn default() -> Self {
+        LoggingSettings {
+            level: "info".to_string(),
+            sample_rate: 0.0,
+            debug_blockchains: vec![],
+        }
+    }
+}
+
+impl LoggingSettings {
+    fn with_env(self) -> Result<Self, String> {
+        Ok(LoggingSettings {
+            level: env::var("LOG_LEVEL").unwrap_or(self.level),
+            sample_rate: env_or("LOG_SAMPLE_RATE", self.sample_rate)?,
+            debug_blockchains: self.debug_blockchains,
+        })
+    }
+}
+
+/// Optional subsystems that can be switched off per deployment.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FeatureSettings {
+    pub webhooks: bool,
+    /// Accepts program deployments over RPC, which `anchor test` and `solana program
+    /// deploy` rely on. Off for hosted deployments, where programs are uploaded via the API.
+    pub anchor_compat: bool,
+    /// Records queued transactions in Postgres so they are processed after a crash.
+    /// Needs `cluster.instance_id`, each instance only resumes its own.
+    pub persist_queue: bool,
+}
+
+impl Default for FeatureSettings {
+    fn default() -> Self {
+        FeatureSettings {
+            webhooks: true,
+            anchor_compat: false,
+            persist_queue: false,
+        }
+    }
+}
+
+impl FeatureSettings {
+    fn with_env(self) -> Result<Self, String> {
+        Ok(FeatureSettings {
+            webhooks: env_or("WEBHOOKS_ENABLED", self.webhooks)?,
+            anchor_compat: env_or("ANCHOR_COMPAT", self.anchor_compat)?,
+            persist_queue: env_or("PERSIST_QUEUE", self.persist_queue)?,
+        })
+    }
+}
+
+/// Coordination between engine instances sharing Postgres and Redis. With leases
+/// on, one instance at a time runs a blockchain's transaction queue and produces its
+/// blocks, the others forward work to it. Every instance has to agree on `leases`.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ClusterSettings {
+    pub leases: bool,
+    /// Identifies this instance as a lease holder, random per process when unset,
+    /// and as the owner of the queued transactions it persists.
+    pub instance_id: Option<Uuid>,
+    /// How long a lease outlives its holder, i.e. how long failover takes.
+    #[serde(rename = "lease_ttl_ms", deserialize_with = "duration_ms")]
+    pub lease_ttl: Duration,
+    /// How often holders pick up work forwarded by other instances.
+    #[serde(rename = "forward_interval_ms", deserialize_with = "duration_ms")]
+    pub forward_interval: Duration,
+}
+
+impl Default for ClusterSettings {
+    fn default() -> Self {
+        ClusterSettings {
+            leases: false,
+            instance_id: None,
+            lease_ttl: Duration::from_secs(10),
+            forward_interval: Duration::from_millis(20),
+        }
+    }
+}
+
+impl ClusterSettings {
+    fn with_env(self) -> Result<Self, String> {
+        Ok(ClusterSettings {
+            leases: env_or("CLUSTER_LEASES", self.leases)?,
+            instance_id: env_parse("INSTANCE_ID")?.or(self.instance_id),
+            lease_ttl: env_duration("LEASE_TTL_MS")?.unwrap_or(self.lease_ttl),
+            forward_interval: env_duration("FORWARD_INTERVAL_MS")?.unwrap_or(self.forward_interval),
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum QueueKind {
+    #[default]
+    Memory,
+    Kafka,
+}
+
+impl FromStr for QueueKind {
+    type Err = String;
+
+    fn from_str(kind: &str) -> Result<Self, Self::Err> {
+        match kind {
+            "" | "memory" => Ok(QueueKind::Memory),
+            "kafka" => Ok(QueueKind::Kafka),
+            _ => Err(format!("Invalid transaction queue {}", kind)),
+        }
+    }
+}
+
+/// Where sent transactions wait to be processed. In memory they are lost with the
+/// process. On Kafka (the `pubsub_url` brokers) they are partitioned by blockchain
+/// and processed by a consumer group spread over every instance.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct QueueSettings {
+    pub kind: QueueKind,
+    pub topic: String,
+    pub group_id: String,
+    /// Created with the topic, bounds how many instances process transactions.
+    pub partitions: i32,
+    /// Consumers per instance, each processing its partitions one transaction at a time.
+    pub consumers: usize,
+    /// Transactions each blockchain's in-memory queue holds, sends past it are rejected.
+    pub capacity: usize,
+    /// How long an in-memory queue's worker waits for transactions before stopping.
+    #[serde(rename = "idle_timeout_ms", deserialize_with = "optional_duration_ms")]
+    pub idle_timeout: Option<Duration>,
+}
+
+impl Default for QueueSettings {
+    fn default() -> Self {
+        QueueSettings {
+            kind: QueueKind::default(),
+            topic: "transactions".to_string(),
+            group_id: "mirror-engine".to_string(),
+            partitions: 32,
+            consumers: 4,
+            capacity: 100,
+            idle_timeout: Some(Duration::from_secs(5 * 60)),
+        }
+    }
+}
+
+impl QueueSettings {
+    fn with_env(self) -> Result<Self, String> {
+        Ok(QueueSettings {
+            kind: env_or("TRANSACTION_QUEUE", self.kind)?,
+            topic: env::var("QUEUE_TOPIC").unwrap_or(self.topic),
+            group_id: env::var("QUEUE_GROUP_ID").unwrap_or(self.group_id),
+            partitions: env_or("QUEUE_PARTITIONS", self.partitions)?,
+            consumers: env_or("QUEUE_CONSUMERS", self.consumers)?,
+            capacity: env_or("QUEUE_CAPACITY", self.capacity)?,
+            idle_timeout: env_optional_duration("QUEUE_IDLE_TIMEOUT_MS", self.idle_timeout)?,
+        })
+    }
+}
+
+/// Builds of the genesis programs loaded when blockchains are created, so newer
+/// ones can be deployed without rebuilding the engine. Files are named after the
+/// program id, `<program id>.so`, and replace the embedded build of that program
+/// or add another one. Blockchains can also pick other files from the directory.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ProgramSettings {
+    pub directory: Option<PathBuf>, // Only the embedded builds without one
+}
+
+impl ProgramSettings {
+    fn with_env(self) -> Result<Self, String> {
+        Ok(ProgramSettings {
+            directory: env::var("PROGRAMS_DIRECTORY")
+                .ok()
+                .map(PathBuf::from)
+                .or(self.directory),
+        })
+    }
+}
+
+/// Connection pool tuning for Postgres and Redis. Durations are written in
+/// milliseconds in the config file, with a `_ms` suffix.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PoolSettings {
+    pub database_max_size: u32,
+    pub database_min_idle: Option<u32>,
+    #[serde(
+        rename = "database_connect_timeout_ms",
+        deserialize_with = "duration_ms"
+    )]
+    pub database_connect_timeout: Duration,
+    #[serde(
+        rename = "database_idle_timeout_ms",
+        deserialize_with = "optional_duration_ms"
+    )]
+    pub database_idle_timeout: Option<Duration>,
+    #[serde(
+        rename = "database_max_lifetime_ms",
+        deserialize_with = "optional_duration_ms"
+    )]
+    pub database_max_lifetime: Option<Duration>,
+    #[serde(
+        rename = "database_statement_timeout_ms",
+        deserialize_with = "optional_duration_ms"
+    )]
+    pub database_statement_timeout: Option<Duration>,
+    /// How long account writes to Postgres are held, so the ones of consecutive
+    /// transactions on a blockchain are coalesced. Redis is written right away.
+    #[serde(
+        rename = "database_write_window_ms",
+        deserialize_with = "optional_duration_ms"
+    )]
+    pub database_write_window: Option<Duration>,
+    pub cache_max_size: u32,
+    #[serde(rename = "cache_connect_timeout_ms", deserialize_with = "duration_ms")]
+    pub cache_connect_timeout: Duration,
+    #[serde(
+        rename = "cache_read_timeout_ms",
+        deserialize_with = "optional_duration_ms"
+    )]
+    pub cache_read_timeout: Option<Duration>,
+    #[serde(
+        rename = "cache_max_lifetime_ms",
+        deserialize_with = "optional_duration_ms"
+    )]
+    pub cache_max_lifetime: Option<Duration>,
+    /// How long getProgramAccounts results stay cached in Redis, writes to the
+    /// program's accounts drop them before.
+    #[serde(
+        rename = "program_accounts_cache_ttl_ms",
+        deserialize_with = "optional_duration_ms"
+    )]
+    pub program_accounts_cache_ttl: Option<Duration>,
+}
+
+impl Default for PoolSettings {
+    fn default() -> Self {
+        PoolSettings {
+            database_max_size: 10,
+            database_min_idle: None,
+            database_connect_timeout: Duration::from_secs(30),
+            database_idle_timeout: Some(Duration::from_secs(10 * 60)),
+            database_max_lifetime: Some(Duration::from_secs(30 * 60)),
+            database_statement_timeout: None,
+            database_write_window: Some(Duration::from_millis(20)),
+            cache_max_size: 15,
+            cache_connect_timeout: Duration::from_secs(30),
+            cache_read_timeout: None,
+            cache_max_lifetime: Some(Duration::from_secs(30 * 60)),
+            program_accounts_cache_ttl: Some(Duration::from_secs(60)),
+        }
+    }
+}
+
+impl PoolSettings {
+    /// Reads overrides from the environment, unset variables keep their defaults.
+    /// Durations are in milliseconds, 0 disables an optional timeout.
+    pub fn from_env() -> Result<Self, String> {
+        PoolSettings::default().with_env()
+    }
+
+    /// Same as `from_env`, on top of these settings instead of the defaults.
+    pub fn with_env(self) -> Result<Self, String> {
+        let default = self;
+        Ok(PoolSettings {
+            database_max_size: env_or("DATABASE_POOL_MAX_SIZE", default.database_max_size)?,
+            database_min_idle: match env_parse::<u32>("DATABASE_POOL_MIN_IDLE")? {
+                Some(min_idle) => Some(min_idle),
+                None => default.database_min_idle,
+            },
+            database_connect_timeout: env_duration("DATABASE_CONNECT_TIMEOUT_MS")?
+                .unwrap_or(default.database_connect_timeout),
+            database_idle_timeout: env_optional_duration(
+                "DATABASE_IDLE_TIMEOUT_MS",
+                default.database_idle_timeout,
+            )?,
+            database_max_lifetime: env_optional_duration(
+                "DATABASE_MAX_LIFETIME_MS",
+                default.database_max_lifetime,
+            )?,
+            database_statement_timeout: env_optional_duration(
+                "DATABASE_STATEMENT_TIMEOUT_MS",
+                default.database_statement_timeout,
+            )?,
+            database_write_window: env_optional_duration(
+                "DATABASE_WRITE_WINDOW_MS",
+                default.database_write_window,
+            )?,
+            cache_max_size: env_or("CACHE_POOL_MAX_SIZE", default.cache_max_size)?,
+            cache_connect_timeout: env_duration("CACHE_CONNECT_TIMEOUT_MS")?
+                .unwrap_or(default.cache_connect_timeout),
+            cache_read_timeout: env_optional_duration(
+                "CACHE_READ_TIMEOUT_MS",
+                default.cache_read_timeout,
+            )?,
+            cache_max_lifetime: env_optional_duration(
+                "CACHE_MAX_LIFETIME_MS",
+                default.cache_max_lifetime,
+            )?,
+            program_accounts_cache_ttl: env_optional_duration(
+                "PROGRAM_ACCOUNTS_CACHE_TTL_MS",
+                default.program_accounts_cache_ttl,
+            )?,
+        })
+    }
+}
+
+/// Intervals of the background jobs run by every engine instance, and how
+/// long shutdown waits for queued work.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct JobSettings {
+    #[serde(
+        rename = "expiry_interval_ms",
+        deserialize_with = "optional_duration_ms"
+    )]
+    pub expiry_interval: Option<Duration>,
+    /// How often blockchains whose blockhash was read get a new block. Unset,
+    /// transactions read as finalized as soon as they are processed.
+    #[serde(
+        rename = "block_interval_ms",
+        deserialize_with = "optional_duration_ms"
+    )]
+    pub block_interval: Option<Duration>,
+    #[serde(rename = "shutdown_timeout_ms", deserialize_with = "duration_ms")]
+    pub shutdown_timeout: Duration,
+    /// Blockchains kept provisioned per team and config, 0 disables the warm pool.
+    pub warm_pool_size: usize,
+    #[serde(rename = "warm_pool_interval_ms", deserialize_with = "duration_ms")]
+    pub warm_pool_interval: Duration,
+    /// How often idle blockchains are paused, per their team's `auto_pause_hours`.
+    #[serde(
+        rename = "auto_pause_interval_ms",
+        deserialize_with = "optional_duration_ms"
+    )]
+    pub auto_pause_interval: Option<Duration>,
+    /// How often due balance top-ups are run.
+    #[serde(
+        rename = "top_up_interval_ms",
+        deserialize_with = "optional_duration_ms"
+    )]
+    pub top_up_interval: Option<Duration>,
+    /// How often zero-lamport accounts without data are deleted.
+    #[serde(
+        rename = "sweep_interval_ms",
+        deserialize_with = "optional_duration_ms"
+    )]
+    pub sweep_interval: Option<Duration>,
+    /// How often a heartbeat with the current slot is published to the geyser
+    /// topic for each blockchain this instance serves.
+    #[serde(
+        rename = "heartbeat_interval_ms",
+        deserialize_with = "optional_duration_ms"
+    )]
+    pub heartbeat_interval: Option<Duration>,
+}
+
+impl Default for JobSettings {
+    fn default() -> Self {
+        JobSettings {
+            expiry_interval: Some(Duration::from_secs(60)),
+            block_interval: Some(Duration::from_millis(400)),
+            shutdown_timeout: Duration::from_secs(30),
+            warm_pool_size: 0,
+            warm_pool_interval: Duration::from_secs(30),
+            auto_pause_interval: Some(Duration::from_secs(5 * 60)),
+            top_up_interval: Some(Duration::from_secs(10)),
+            sweep_interval: Some(Duration::from_secs(5 * 60)),
+            heartbeat_interval: Some(Duration::from_secs(10)),
+        }
+    }
+}
+
+impl JobSettings {
+    /// Same conventions as `PoolSettings::from_env`, 0 disables a job.
+    pub fn from_env() -> Result<Self, String> {
+        JobSettings::default().with_env()
+    }
+
+    pub fn with_env(self) -> Result<Self, String> {
+        let default = self;
+        Ok(JobSettings {
+            expiry_interval: env_optional_duration("EXPIRY_INTERVAL_MS", default.expiry_interval)?,
+            block_interval: env_optional_duration("BLOCK_INTERVAL_MS", default.block_interval)?,
+            shutdown_timeout: env_duration("SHUTDOWN_TIMEOUT_MS")?
+                .unwrap_or(default.shutdown_timeout),
+            warm_pool_size: env_or("WARM_POOL_SIZE", default.warm_pool_size)?,
+            warm_pool_interval: env_duration("WARM_POOL_INTERVAL_MS")?
+                .unwrap_or(default.warm_pool_interval),
+            auto_pause_interval: env_optional_duration(
+                "AUTO_PAUSE_INTERVAL_MS",
+                default.auto_pause_interval,
+            )?,
+            top_up_interval: env_optional_duration("TOP_UP_INTERVAL_MS", default.top_up_interval)?,
+            sweep_interval: env_optional_duration("SWEEP_INTERVAL_MS", default.sweep_interval)?,
+            heartbeat_interval: env_optional_duration(
+                "HEARTBEAT_INTERVAL_MS",
+                default.heartbeat_interval,
+            )?,
+        })
+    }
+}
+
+fn env_parse<V: FromStr>(key: &str) -> Result<Option<V>, String> {
+    match env::var(key) {
+        Ok(value) => value
+            .trim()
+            .parse::<V>()
+            .map(Some)
+            .map_err(|_| format!("Invalid value for {}: {}", key, value)),
+        Err(_) => Ok(None),
+    }
+}
+
+fn env_or<V: FromStr>(key: &str, default: V) -> Result<V, String> {
+    Ok(env_parse(key)?.unwrap_or(default))
+}
+
+fn env_duration(key: &str) -> Result<Option<Duration>, String> {
+    Ok(env_parse::<u64>(key)?.map(Duration::from_millis))
+}
+
+fn env_optional_duration(key: &str, default: Option<Duration>) -> Result<Option<Duration>, String> {
+    match env_parse::<u64>(key)? {
+        Some(0) => Ok(None),
+        Some(ms) => Ok(Some(Duration::from_millis(ms))),
+        None => Ok(default),
+    }
+}
+
+fn duration_ms<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+    Ok(Duration::from_millis(u64::deserialize(deserializer)?))
+}
+
+fn optional_duration_ms<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<Duration>, D::Error> {
+    match u64::deserialize(deserializer)? {
+        0 => Ok(None),
+        ms => Ok(Some(Duration::from_millis(ms))),
+    }
+}
@@ -0,0 +1,200 @@
+use bigdecimal::BigDecimal;
+use std::{
+    collections::{BTreeSet, HashMap},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use uuid::Uuid;
+
+use super::accounts::DbAccount;
+
+/// Account rows waiting for their blockchain's write window to close, see
+/// `PoolSettings::database_write_window`. A row written again meanwhile replaces
+/// the earlier one, so consecutive transactions touching the same accounts cost
+/// a single upsert.
+#[derive(Clone, Default)]
+pub struct AccountWrites {
+    window: Option<Duration>,
+    pending: Arc<Mutex<HashMap<Uuid, Pending>>>,
+    order: Arc<Mutex<WriteOrder>>,
+}
+
+/// A blockchain's queued rows.
+#[derive(Default)]
+struct Pending {
+    rows: HashMap<String, DbAccount>,
+    /// Owners the accounts moved away from, their cached program accounts are
+    /// dropped again once the rows land
+    previous_owners: BTreeSet<String>,
+}
+
+/// Orders rows on their way to Postgres against deletes of the same accounts.
+#[derive(Default)]
+struct WriteOrder {
+    sequence: u64,
+    /// Sequences of the tickets not dropped yet
+    tickets: BTreeSet<u64>,
+    /// Sequence each account was deleted at, while older tickets are around
+    deleted: HashMap<(Uuid, String), u64>,
+    /// Held while a blockchain's rows are upserted or deleted
+    locks: HashMap<Uuid, Arc<Mutex<()>>>,
+}
+
+/// Rows left `AccountWrites` for Postgres at this point. Accounts deleted after
+/// it are dropped from them, see `AccountWrites::live`.
+pub struct WriteTicket {
+    order: Arc<Mutex<WriteOrder>>,
+    sequence: u64,
+}
+
+impl Drop for WriteTicket {
+    fn drop(&mut self) {
+        self.order.lock().unwrap().tickets.remove(&self.sequence);
+    }
+}
+
+impl AccountWrites {
+    pub fn new(window: Option<Duration>) -> Self {
+        AccountWrites {
+            window,
+            pending: Arc::default(),
+            order: Arc::default(),
+        }
+    }
+
+    /// How long writes are held, `None` writes each set of accounts right away.
+    pub fn window(&self) -> Option<Duration> {
+        self.window
+    }
+
+    /// Queues rows and the owners their accounts moved away from, returns true
+    /// when this opened the blockchain's window, so the caller flushes it once
+    /// the window closes.
+    pub fn add(&self, id: Uuid, accounts: Vec<DbAccount>, previous_owners: Vec<String>) -> bool {
+        let mut pending = self.pending.lock().unwrap();
+        let opened = !pending.contains_key(&id);
+        let Pending {
+            rows,
+            previous_owners: owners,
+        } = pending.entry(id).or_default();
+        owners.extend(previous_owners);
+        for mut account in accounts {
+            // The label is only written on insert, a later write without one keeps it
+            if let Some(previous) = rows.remove(&account.address) {
+                account.label = account.label.or(previous.label);
+            }
+            rows.insert(account.address.clone(), account);
+        }
+        opened
+    }
+
+    /// Closes the blockchain's window, returning its rows and previous owners.
+    pub fn take(&self, id: Uuid) -> (Vec<DbAccount>, Vec<String>) {
+        self.pending
+            .lock()
+            .unwrap()
+            .remove(&id)
+            .map(|pending| {
+                (
+                    pending.rows.into_values().collect(),
+                    pending.previous_owners.into_iter().collect(),
+                )
+            })
+            .unwrap_or_default()
+    }
+
+    /// Keeps a queued row in line with a lamports update written on its own.
+    pub fn set_lamports(&self, id: Uuid, address: &str, lamports: u64) {
+        if let Some(account) = self
+            .pending
+            .lock()
+            .unwrap()
+            .get_mut(&id)
+            .and_then(|pending| pending.rows.get_mut(address))
+        {
+            account.lamports = BigDecimal::from(lamports);
+        }
+    }
+
+    /// Drops a queued row, so a deleted account isn't written back. Rows already
+    /// ticketed for Postgres skip it too.
+    pub fn forget(&self, id: Uuid, address: &str) {
+        if let Some(pending) = self.pending.lock().unwrap().get_mut(&id) {
+            pending.rows.remove(address);
+        }
+        let mut order = self.order.lock().unwrap();
+        order.sequence += 1;
+        let sequence = order.sequence;
+        // Deletes only matter to tickets older than them
+        match order.tickets.first().copied() {
+            Some(oldest) => {
+                order.deleted.retain(|_, deleted| *deleted > oldest);
+                order.deleted.insert((id, address.to_string()), sequence);
+            }
+            None => order.deleted.clear(),
+        }
+    }
+
+    /// Drops every queued row of a deleted blockchain.
+    pub fn forget_blockchain(&self, id: Uuid) {
+        if let Some(pending) = self.pending.lock().unwrap().get_mut(&id) {
+            pending.rows.clear();
+        }
+        self.order.lock().unwrap().locks.remove(&id);
+    }
+
+    /// Take before sending rows to Postgres, and hold until they're written.
+    pub fn ticket(&self) -> WriteTicket {
+        let mut order = self.order.lock().unwrap();
+        order.sequence += 1;
+        let sequence = order.sequence;
+        order.tickets.insert(sequence);
+        WriteTicket {
+            order: self.order.clone(),
+            sequence,
+        }
+    }
+
+    /// The rows of `ticket` whose accounts weren't deleted since.
+    pub fn live(&self, id: Uuid, ticket: &WriteTicket, rows: Vec<DbAccount>) -> Vec<DbAccount> {
+        let order = self.order.lock().unwrap();
+        rows.into_iter()
+            .filter(|row| {
+                order
+                    .deleted
+                    .get(&(id, row.address.clone()))
+                    .is_none_or(|deleted| *deleted < ticket.sequence)
+            })
+            .collect()
+    }
+
+    /// Runs Postgres writes of a blockchain's rows one at a time, so an upsert
+    /// can't land after a delete that happened later.
+    pub fn in_order<T>(&self, id: Uuid, write: impl FnOnce() -> T) -> T {
+        let lock = self
+            .order
+            .lock()
+            .unwrap()
+            .locks
+            .entry(id)
+            .or_default()
+            .clone();
+        // A write that panicked leaves nothing half done
+        let _guard = lock.lock().unwrap_or_else(|e| e.into_inner());
+        write()
+    }
+
+    /// Rows waiting across every blockchain.
+    pub fn len(&self) -> usize {
+        self.pending
+            .lock()
+            .unwrap()
+            .values()
+            .map(|pending| pending.rows.len())
+            .sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
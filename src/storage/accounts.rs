@@ -1,7 +1,13 @@
-use bigdecimal::{BigDecimal, ToPrimitive};
+use super::numeric::{self, StorageError};
+use bigdecimal::BigDecimal;
 use diesel::prelude::*;
 use serde::{Deserialize, Serialize};
-use solana_sdk::{account::Account, pubkey::Pubkey};
+use solana_sdk::{
+    account::Account,
+    nonce::{state::Versions, State as NonceState},
+    pubkey::Pubkey,
+    system_program,
+};
 use std::str::FromStr;
 use uuid::Uuid;
 
@@ -22,6 +28,22 @@ pub struct DbAccount {
 }
 
 impl DbAccount {
+    /// Takes ownership of the account so its data is moved rather than copied.
+    pub fn new(pubkey: &Pubkey, account: Account, label: Option<String>, blockchain: Uuid) -> Self {
+        DbAccount {
+            id: Uuid::new_v4(),
+            created_at: chrono::Utc::now().naive_utc(),
+            address: pubkey.to_string(),
+            lamports: account.lamports.into(),
+            data: account.data,
+            owner: account.owner.to_string(),
+            executable: account.executable,
+            rent_epoch: account.rent_epoch.into(),
+            label,
+            blockchain,
+        }
+    }
+
     pub fn from_account(
         pubkey: &Pubkey,
         account: &Account,
@@ -42,14 +64,14 @@ impl DbAccount {
         }
     }
 
-    pub fn into_account(self) -> Account {
-        Account {
-            lamports: self.lamports.to_u64().unwrap(),
+    pub fn into_account(self) -> Result<Account, StorageError> {
+        Ok(Account {
+            lamports: numeric::to_u64("lamports", &self.lamports)?,
             data: self.data,
             owner: Pubkey::from_str(&self.owner).unwrap(),
             executable: self.executable,
-            rent_epoch: self.rent_epoch.to_u64().unwrap(),
-        }
+            rent_epoch: numeric::saturating_u64(&self.rent_epoch),
+        })
     }
 }
 
@@ -90,13 +112,58 @@ impl DbConfigAccount {
         }
     }
 
-    pub fn into_account(self) -> Account {
-        Account {
-            lamports: self.lamports.to_u64().unwrap(),
+    pub fn into_account(self) -> Result<Account, StorageError> {
+        Ok(Account {
+            lamports: numeric::to_u64("lamports", &self.lamports)?,
             data: self.data,
             owner: Pubkey::from_str(&self.owner).unwrap(),
             executable: self.executable,
-            rent_epoch: self.rent_epoch.to_u64().unwrap(),
-        }
+            rent_epoch: numeric::saturating_u64(&self.rent_epoch),
+        })
+    }
+}
+
+/// Filters and paging for listing the accounts of a blockchain.
+#[derive(Clone, Debug, Default)]
+pub struct AccountListFilter {
+    pub owner: Option<Pubkey>,
+    pub executable: Option<bool>,
+    pub label: Option<String>, // case-insensitive substring match
+    pub limit: i64,
+    pub offset: i64,
+}
+
+/// Account row without its data, used for listings.
+#[derive(Queryable, Clone, Debug, Serialize)]
+pub struct AccountSummary {
+    pub address: String,
+    pub lamports: BigDecimal,
+    pub owner: String,
+    pub executable: bool,
+    pub rent_epoch: BigDecimal,
+    pub label: Option<String>,
+    pub data_len: i32,
+}
+
+/// Where an initialized nonce account's authority sits in its data, after the
+/// version and state tags.
+pub const NONCE_AUTHORITY_OFFSET: usize = 8;
+
+/// Drained accounts without data, which a validator stops storing.
+pub fn is_empty_account(account: &Account) -> bool {
+    account.lamports == 0 && account.data.is_empty()
+}
+
+/// The authority of an initialized system nonce account.
+pub fn nonce_authority(account: &Account) -> Option<Pubkey> {
+    if account.owner != system_program::id() || account.data.len() != NonceState::size() {
+        return None;
+    }
+    match bincode::deserialize::<Versions>(&account.data)
+        .ok()?
+        .state()
+    {
+        NonceState::Initialized(data) => Some(data.authority),
+        NonceState::Uninitialized => None,
     }
 }
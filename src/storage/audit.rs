@@ -0,0 +1,82 @@
+use diesel::prelude::*;
+use uuid::Uuid;
+
+pub const BLOCKCHAIN_CREATE: &str = "blockchain.create";
+pub const BLOCKCHAIN_CLONE: &str = "blockchain.clone";
+pub const BLOCKCHAIN_UPDATE: &str = "blockchain.update";
+pub const BLOCKCHAIN_DELETE: &str = "blockchain.delete";
+pub const BLOCKCHAIN_EXPIRE: &str = "blockchain.expire";
+pub const BLOCKCHAIN_TRANSFER: &str = "blockchain.transfer";
+pub const BLOCKCHAIN_SYSVARS_SET: &str = "blockchain.sysvars.set";
+pub const BLOCKCHAIN_GEYSER_REPLAY: &str = "blockchain.geyser.replay";
+pub const PROGRAM_LOAD: &str = "program.load";
+pub const PROGRAM_CLONE: &str = "program.clone";
+pub const PROGRAM_IDL_SET: &str = "program.idl.set";
+pub const PROGRAM_AUTHORITY_SET: &str = "program.authority.set";
+pub const PROGRAM_DEPLOY: &str = "program.deploy";
+pub const PROGRAM_UPGRADE: &str = "program.upgrade";
+pub const ACCOUNT_LOAD: &str = "account.load";
+pub const ACCOUNT_DELETE: &str = "account.delete";
+pub const ACCOUNT_LABEL: &str = "account.label";
+pub const AIRDROP: &str = "airdrop";
+pub const TOKEN_MINT: &str = "token.mint";
+pub const TOKEN_AUTHORITY_SET: &str = "token.authority.set";
+pub const CONFIG_CREATE: &str = "config.create";
+pub const CONFIG_DELETE: &str = "config.delete";
+pub const CONFIG_ACCOUNTS_SET: &str = "config.accounts.set";
+pub const CONFIG_ACCOUNTS_DELETE: &str = "config.accounts.delete";
+pub const WEBHOOK_CREATE: &str = "webhook.create";
+pub const WEBHOOK_DELETE: &str = "webhook.delete";
+pub const TOP_UP_CREATE: &str = "top_up.create";
+pub const TOP_UP_DELETE: &str = "top_up.delete";
+pub const API_KEY_CREATE: &str = "api_key.create";
+pub const API_KEY_UPDATE: &str = "api_key.update";
+pub const API_KEY_REVOKE: &str = "api_key.revoke";
+pub const TEAM_QUOTAS_SET: &str = "team.quotas.set";
+pub const TEAM_AUTO_PAUSE_SET: &str = "team.auto_pause.set";
+pub const TEAM_RPC_WRITE_GUARD_SET: &str = "team.rpc_write_guard.set";
+
+#[derive(Queryable, Selectable, Insertable, Clone, Debug)]
+#[diesel(table_name = crate::schema::audit_logs)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct DbAuditLog {
+    pub id: Uuid,
+    pub created_at: chrono::NaiveDateTime,
+    pub team_id: Uuid,
+    pub api_key: Option<Uuid>, // None for unauthenticated routes and background jobs
+    pub action: String,
+    pub blockchain: Option<Uuid>,
+    pub summary: String, // JSON object describing the change
+}
+
+impl DbAuditLog {
+    pub fn new(
+        team_id: Uuid,
+        api_key: Option<Uuid>,
+        action: &str,
+        blockchain: Option<Uuid>,
+        summary: serde_json::Value,
+    ) -> Self {
+        DbAuditLog {
+            id: Uuid::new_v4(),
+            created_at: chrono::Utc::now().naive_utc(),
+            team_id,
+            api_key,
+            action: action.to_string(),
+            blockchain,
+            summary: summary.to_string(),
+        }
+    }
+}
+
+/// Filters and paging for a team's audit log, newest entries first.
+#[derive(Clone, Debug, Default)]
+pub struct AuditLogFilter {
+    pub blockchain: Option<Uuid>,
+    pub action: Option<String>,
+    /// Entries with any of these actions, every action when empty.
+    pub actions: Vec<String>,
+    pub since: Option<chrono::NaiveDateTime>,
+    pub limit: i64,
+    pub offset: i64,
+}
@@ -1,12 +1,18 @@
-use bigdecimal::{BigDecimal, ToPrimitive};
-use diesel::prelude::*;
+use bigdecimal::BigDecimal;
+use diesel::{
+    pg::Pg,
+    prelude::*,
+    serialize::{self, Output, ToSql},
+    sql_types::Text,
+};
 use serde::{Deserialize, Serialize};
 use solana_sdk::{hash::Hash, signature::Keypair};
 use uuid::Uuid;
 
-use crate::engine::blocks::{Block, Blockchain};
+use super::numeric::{self, StorageError};
+use crate::engine::blocks::{AccountsStatus, Block, Blockchain};
 
-#[derive(Queryable, Selectable, Insertable, AsChangeset, Clone)]
+#[derive(Queryable, QueryableByName, Selectable, Insertable, AsChangeset, Clone)]
 #[diesel(table_name = crate::schema::blockchains)]
 #[diesel(check_for_backend(diesel::pg::Pg))]
 pub struct DbBlockchain {
@@ -17,6 +23,13 @@ pub struct DbBlockchain {
     pub label: Option<String>,
     pub expiry: Option<chrono::NaiveDateTime>,
     pub jit: bool,
+    pub config: Option<Uuid>,
+    pub pooled: bool,
+    pub log_bytes_limit: Option<i32>,
+    pub read_only: bool,
+    pub sponsor_fees: bool,
+    pub parity_check: bool,
+    pub accounts_status: String,
 }
 
 impl DbBlockchain {
@@ -29,10 +42,54 @@ impl DbBlockchain {
             label: self.label,
             expiry: self.expiry,
             jit: self.jit,
+            config: self.config,
+            pooled: self.pooled,
+            log_bytes_limit: self.log_bytes_limit,
+            read_only: self.read_only,
+            sponsor_fees: self.sponsor_fees,
+            parity_check: self.parity_check,
+            // Written by `AccountsStatus::as_str`
+            accounts_status: self
+                .accounts_status
+                .parse()
+                .unwrap_or(AccountsStatus::Ready),
         }
     }
 }
 
+/// Partial update of a blockchain, `None` fields are left untouched.
+#[derive(AsChangeset, Clone, Default)]
+#[diesel(table_name = crate::schema::blockchains)]
+pub struct DbBlockchainUpdate {
+    pub label: Option<String>,
+    pub expiry: Option<chrono::NaiveDateTime>,
+    /// `Some(None)` clears the limit.
+    pub log_bytes_limit: Option<Option<i32>>,
+    pub read_only: Option<bool>,
+    pub sponsor_fees: Option<bool>,
+    pub parity_check: Option<bool>,
+    pub accounts_status: Option<AccountsStatus>,
+}
+
+impl ToSql<Text, Pg> for AccountsStatus {
+    fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, Pg>) -> serialize::Result {
+        <str as ToSql<Text, Pg>>::to_sql(self.as_str(), out)
+    }
+}
+
+/// Narrows a blockchain listing, `None` fields match everything.
+#[derive(Clone, Debug, Default)]
+pub struct BlockchainFilter {
+    pub team_id: Option<Uuid>,
+    pub label: Option<String>,
+    pub created_after: Option<chrono::NaiveDateTime>,
+    /// Only blockchains with an expiry before this time, chains that never expire are left out.
+    pub expiring_before: Option<chrono::NaiveDateTime>,
+    /// Every match when `None`.
+    pub limit: Option<i64>,
+    pub offset: i64,
+}
+
 #[derive(Queryable, Selectable, Insertable, AsChangeset, Clone, Serialize, Deserialize)]
 #[diesel(table_name = crate::schema::blocks)]
 #[diesel(check_for_backend(diesel::pg::Pg))]
@@ -48,10 +105,13 @@ pub struct DbBlock {
 }
 
 impl DbBlock {
+    /// The block's time is kept as its creation time, which `into_block` reads back.
     pub fn from_block(block: &Block, blockchain: Uuid) -> Self {
         DbBlock {
             id: Uuid::new_v4(),
-            created_at: chrono::Utc::now().naive_utc(),
+            created_at: chrono::DateTime::from_timestamp(block.block_time as i64, 0)
+                .unwrap_or_default()
+                .naive_utc(),
             blockchain,
             blockhash: block.blockhash.to_bytes().to_vec(),
             previous_blockhash: block.previous_blockhash.to_bytes().to_vec(),
@@ -61,28 +121,36 @@ impl DbBlock {
         }
     }
 
-    pub fn into_block(self) -> (Block, Uuid) {
-        (
+    pub fn into_block(self) -> Result<(Block, Uuid), StorageError> {
+        Ok((
             Block {
                 blockhash: Hash::new_from_array(self.blockhash.as_slice().try_into().unwrap()),
                 previous_blockhash: Hash::new_from_array(
                     self.previous_blockhash.as_slice().try_into().unwrap(),
                 ),
-                block_height: self.block_height.to_u64().unwrap(),
+                block_height: numeric::to_u64("block_height", &self.block_height)?,
                 block_time: self.created_at.and_utc().timestamp() as u64,
-                parent_slot: self.parent_slot.to_u64().unwrap(),
+                parent_slot: numeric::to_u64("parent_slot", &self.parent_slot)?,
                 transactions: vec![],
             },
             self.blockchain,
-        )
+        ))
     }
 }
 
-#[derive(Queryable, Selectable, Insertable, AsChangeset, Clone)]
+#[derive(Queryable, Selectable, Insertable, AsChangeset, Clone, Serialize)]
 #[diesel(table_name = crate::schema::blockchain_configs)]
 #[diesel(check_for_backend(diesel::pg::Pg))]
 pub struct DBBlockchainConfig {
     pub id: Uuid,
     pub created_at: chrono::NaiveDateTime,
     pub label: String,
+    pub team_id: Option<Uuid>, // None for configs shared with every team
+    pub bundles: Vec<String>,  // Program bundles cloned into its blockchains, see `bundle_programs`
+}
+
+impl DBBlockchainConfig {
+    pub fn is_visible_to(&self, team_id: Uuid) -> bool {
+        self.team_id.is_none() || self.team_id == Some(team_id)
+    }
 }
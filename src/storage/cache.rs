@@ -1,14 +1,64 @@
-use super::{accounts::DbAccount, blocks::DbBlock, transactions::DbTransactionObject};
+use super::{
+    accounts::DbAccount,
+    blocks::DbBlock,
+    numeric,
+    trace::{self, Operation},
+    transactions::{DbTransactionObject, TransactionSummary, TRANSACTION_FEED_SIZE},
+    usage::StorageUsage,
+};
 use base64::prelude::*;
-use bigdecimal::ToPrimitive;
-use r2d2::Pool;
+use r2d2::{Pool, PooledConnection};
 use r2d2_redis::RedisConnectionManager;
 use redis::Commands;
+use std::{
+    collections::HashMap,
+    ops::{Deref, DerefMut},
+    time::{Duration, Instant},
+};
 use uuid::Uuid;
 
+use crate::settings::PoolSettings;
+
 #[derive(Clone)]
 pub struct Cache {
     pool: Pool<RedisConnectionManager>,
+    program_accounts_ttl: Option<Duration>,
+}
+
+#[derive(Debug)]
+struct ReadTimeout(Option<Duration>);
+
+impl r2d2::CustomizeConnection<redis::Connection, r2d2_redis::Error> for ReadTimeout {
+    fn on_acquire(&self, conn: &mut redis::Connection) -> Result<(), r2d2_redis::Error> {
+        conn.set_read_timeout(self.0)
+            .map_err(r2d2_redis::Error::Other)
+    }
+}
+
+/// A pooled Redis connection, recorded in the request trace once it's given back.
+pub struct CacheConnection {
+    con: PooledConnection<RedisConnectionManager>,
+    taken_at: Instant,
+}
+
+impl Deref for CacheConnection {
+    type Target = redis::Connection;
+
+    fn deref(&self) -> &Self::Target {
+        &self.con
+    }
+}
+
+impl DerefMut for CacheConnection {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.con
+    }
+}
+
+impl Drop for CacheConnection {
+    fn drop(&mut self) {
+        trace::record(Operation::Redis, 1, self.taken_at.elapsed());
+    }
 }
 
 // pub struct BlockchainCache {
@@ -24,18 +74,31 @@ pub struct Cache {
 
 impl Cache {
     pub fn new(url: &str) -> Self {
+        Self::with_settings(url, &PoolSettings::default())
+    }
+
+    pub fn with_settings(url: &str, settings: &PoolSettings) -> Self {
         let manager = RedisConnectionManager::new(url).unwrap();
         let pool = Pool::builder()
-            .max_size(15) // Set the maximum number of connections
+            .max_size(settings.cache_max_size)
+            .connection_timeout(settings.cache_connect_timeout)
+            .max_lifetime(settings.cache_max_lifetime)
+            .connection_customizer(Box::new(ReadTimeout(settings.cache_read_timeout)))
             .build(manager)
             .unwrap();
-        Self { pool }
+        Self {
+            pool,
+            program_accounts_ttl: settings.program_accounts_cache_ttl,
+        }
     }
 
-    pub fn get_connection(&self) -> Result<r2d2::PooledConnection<RedisConnectionManager>, String> {
-        self.pool
+    pub fn get_connection(&self) -> Result<CacheConnection, String> {
+        let taken_at = Instant::now();
+        let con = self
+            .pool
             .get()
-            .map_err(|e| format!("Failed to get connection: {}", e))
+            .map_err(|e| format!("Failed to get connection: {}", e))?;
+        Ok(CacheConnection { con, taken_at })
     }
 
     pub fn delete_blockchain(&self, blockchain: Uuid) -> Result<(), String> {
@@ -68,38 +131,313 @@ impl Cache {
         Ok(())
     }
 
-    pub fn set_accounts(&self, blockchain: Uuid, accounts: Vec<DbAccount>) -> Result<(), String> {
+    /// Returns the owners the accounts moved away from.
+    pub fn set_accounts(
+        &self,
+        blockchain: Uuid,
+        accounts: &[DbAccount],
+    ) -> Result<Vec<String>, String> {
         let mut con = self.get_connection()?;
         let con = &mut *con;
 
-        // Prepare key-value pairs for MSET
-        let mut key_value_pairs = Vec::new();
+        // Stores the accounts and keeps the usage counters in step, the
+        // previous data length of every account is tracked in a hash so
+        // overwrites only count the difference. Owners are tracked the same
+        // way, cached program accounts of the previous and new one are dropped
+        // and their generations bumped
+        let lua_script = r#"
+            local usage = KEYS[1]
+            local sizes = KEYS[2]
+            local owners = KEYS[3]
+            local generations = KEYS[4]
+            local programs = ARGV[1]
+            local created = 0
+            local data_delta = 0
+            local cache_delta = 0
+            local moved = {}
+            for i = 5, #KEYS do
+                local offset = 1 + (i - 5) * 4
+                local address = ARGV[offset + 1]
+                local value = ARGV[offset + 2]
+                local data_len = tonumber(ARGV[offset + 3])
+                local owner = ARGV[offset + 4]
+                local previous = redis.call('STRLEN', KEYS[i])
+                if previous == 0 then
+                    created = created + 1
+                end
+                local previous_data = tonumber(redis.call('HGET', sizes, address) or '0')
+                redis.call('SET', KEYS[i], value)
+                redis.call('HSET', sizes, address, data_len)
+                data_delta = data_delta + data_len - previous_data
+                cache_delta = cache_delta + #value - previous
+                local previous_owner = redis.call('HGET', owners, address)
+                if previous_owner and previous_owner ~= owner then
+                    redis.call('DEL', programs .. previous_owner)
+                    redis.call('HINCRBY', generations, previous_owner, 1)
+                    table.insert(moved, previous_owner)
+                end
+                redis.call('DEL', programs .. owner)
+                redis.call('HINCRBY', generations, owner, 1)
+                redis.call('HSET', owners, address, owner)
+            end
+            redis.call('HINCRBY', usage, 'accounts', created)
+            redis.call('HINCRBY', usage, 'account_data_bytes', data_delta)
+            redis.call('HINCRBY', usage, 'cache_bytes', cache_delta)
+            return moved
+        "#;
+
+        let mut keys = vec![
+            Self::usage_key(blockchain),
+            format!("blockchain:{}:account_sizes", blockchain),
+            Self::account_owners_key(blockchain),
+            Self::program_generations_key(blockchain),
+        ];
+        let mut args = vec![Self::program_accounts_key(blockchain, "")];
         for account in accounts {
-            let key = format!(
+            keys.push(format!(
                 "blockchain:{}:account:{}",
                 blockchain.to_string(),
                 account.address,
-            );
+            ));
             let serialized_account = serde_json::to_string(&account)
                 .map_err(|e| format!("Failed to serialize account: {}", e))?;
-            key_value_pairs.push((key, serialized_account));
+            args.push(account.address.clone());
+            args.push(serialized_account);
+            args.push(account.data.len().to_string());
+            args.push(account.owner.clone());
         }
 
-        // Flatten the key-value pairs into a single vector for MSET
-        let flattened: Vec<String> = key_value_pairs
-            .into_iter()
-            .flat_map(|(key, value)| vec![key, value])
-            .collect();
+        redis::cmd("EVAL")
+            .arg(lua_script)
+            .arg(keys.len())
+            .arg(keys)
+            .arg(args)
+            .query(con)
+            .map_err(|e| format!("Failed to set accounts: {}", e))
+    }
 
-        // Use MSET to set all accounts in one request
-        let _: () = redis::cmd("MSET")
-            .arg(flattened)
+    pub fn delete_account(&self, blockchain: Uuid, address: &str) -> Result<bool, String> {
+        let mut con = self.get_connection()?;
+        let con = &mut *con;
+
+        // Mirrors the bookkeeping of set_accounts so usage stays accurate
+        let lua_script = r#"
+            local previous = redis.call('STRLEN', KEYS[3])
+            if previous == 0 then
+                return 0
+            end
+            local previous_data = tonumber(redis.call('HGET', KEYS[2], ARGV[1]) or '0')
+            local owner = redis.call('HGET', KEYS[4], ARGV[1])
+            if owner then
+                redis.call('DEL', ARGV[2] .. owner)
+                redis.call('HINCRBY', KEYS[5], owner, 1)
+            end
+            redis.call('DEL', KEYS[3])
+            redis.call('HDEL', KEYS[2], ARGV[1])
+            redis.call('HDEL', KEYS[4], ARGV[1])
+            redis.call('HINCRBY', KEYS[1], 'accounts', -1)
+            redis.call('HINCRBY', KEYS[1], 'account_data_bytes', -previous_data)
+            redis.call('HINCRBY', KEYS[1], 'cache_bytes', -previous)
+            return 1
+        "#;
+
+        let deleted: i64 = redis::cmd("EVAL")
+            .arg(lua_script)
+            .arg(5)
+            .arg(Self::usage_key(blockchain))
+            .arg(format!("blockchain:{}:account_sizes", blockchain))
+            .arg(format!("blockchain:{}:account:{}", blockchain, address))
+            .arg(Self::account_owners_key(blockchain))
+            .arg(Self::program_generations_key(blockchain))
+            .arg(address)
+            .arg(Self::program_accounts_key(blockchain, ""))
             .query(con)
-            .map_err(|e| format!("Failed to execute MSET: {}", e))?;
+            .map_err(|e| format!("Failed to delete account: {}", e))?;
+
+        Ok(deleted == 1)
+    }
 
+    /// `delete_account` if the cached account has neither lamports nor data, checked
+    /// in the same script. Returns whether it was deleted, `None` when it was kept.
+    pub fn delete_empty_account(
+        &self,
+        blockchain: Uuid,
+        address: &str,
+    ) -> Result<Option<bool>, String> {
+        let mut con = self.get_connection()?;
+        let con = &mut *con;
+
+        // delete_account's script, behind the check
+        let lua_script = r#"
+            local value = redis.call('GET', KEYS[3])
+            if not value then
+                return 0
+            end
+            local previous_data = tonumber(redis.call('HGET', KEYS[2], ARGV[1]) or '0')
+            if previous_data ~= 0 or tonumber(cjson.decode(value).lamports) ~= 0 then
+                return -1
+            end
+            local owner = redis.call('HGET', KEYS[4], ARGV[1])
+            if owner then
+                redis.call('DEL', ARGV[2] .. owner)
+                redis.call('HINCRBY', KEYS[5], owner, 1)
+            end
+            redis.call('DEL', KEYS[3])
+            redis.call('HDEL', KEYS[2], ARGV[1])
+            redis.call('HDEL', KEYS[4], ARGV[1])
+            redis.call('HINCRBY', KEYS[1], 'accounts', -1)
+            redis.call('HINCRBY', KEYS[1], 'cache_bytes', -#value)
+            return 1
+        "#;
+
+        let deleted: i64 = redis::cmd("EVAL")
+            .arg(lua_script)
+            .arg(5)
+            .arg(Self::usage_key(blockchain))
+            .arg(format!("blockchain:{}:account_sizes", blockchain))
+            .arg(format!("blockchain:{}:account:{}", blockchain, address))
+            .arg(Self::account_owners_key(blockchain))
+            .arg(Self::program_generations_key(blockchain))
+            .arg(address)
+            .arg(Self::program_accounts_key(blockchain, ""))
+            .query(con)
+            .map_err(|e| format!("Failed to delete account: {}", e))?;
+
+        Ok(match deleted {
+            -1 => None,
+            deleted => Some(deleted == 1),
+        })
+    }
+
+    fn usage_key(blockchain: Uuid) -> String {
+        format!("blockchain:{}:usage", blockchain)
+    }
+
+    fn account_owners_key(blockchain: Uuid) -> String {
+        format!("blockchain:{}:account_owners", blockchain)
+    }
+
+    /// Hash of the program's cached getProgramAccounts results, by request key.
+    /// They expire together.
+    fn program_accounts_key(blockchain: Uuid, program: &str) -> String {
+        format!("blockchain:{}:program_accounts:{}", blockchain, program)
+    }
+
+    /// Hash of how many times each program's cached results were dropped, by
+    /// program. A result is only cached if the count didn't move while it was read.
+    fn program_generations_key(blockchain: Uuid) -> String {
+        format!("blockchain:{}:program_accounts_generations", blockchain)
+    }
+
+    /// The cached result, if any, and the program's generation.
+    pub fn get_program_accounts_result(
+        &self,
+        blockchain: Uuid,
+        program: &str,
+        key: &str,
+    ) -> Result<(Option<String>, u64), String> {
+        if self.program_accounts_ttl.is_none() {
+            return Ok((None, 0));
+        }
+        let mut con = self.get_connection()?;
+        let con = &mut *con;
+        let (result, generation): (Option<String>, Option<u64>) = redis::pipe()
+            .atomic()
+            .hget(Self::program_accounts_key(blockchain, program), key)
+            .hget(Self::program_generations_key(blockchain), program)
+            .query(con)
+            .map_err(|e| format!("Failed to get program accounts: {}", e))?;
+        Ok((result, generation.unwrap_or_default()))
+    }
+
+    /// Caches the result unless the program's generation moved past `generation`.
+    pub fn set_program_accounts_result(
+        &self,
+        blockchain: Uuid,
+        program: &str,
+        key: &str,
+        result: &str,
+        generation: u64,
+    ) -> Result<(), String> {
+        let Some(ttl) = self.program_accounts_ttl else {
+            return Ok(());
+        };
+        let mut con = self.get_connection()?;
+        let con = &mut *con;
+
+        let lua_script = r#"
+            local current = tonumber(redis.call('HGET', KEYS[2], ARGV[1]) or '0')
+            if current ~= tonumber(ARGV[2]) then
+                return 0
+            end
+            redis.call('HSET', KEYS[1], ARGV[3], ARGV[4])
+            redis.call('PEXPIRE', KEYS[1], ARGV[5])
+            return 1
+        "#;
+
+        let _: i64 = redis::cmd("EVAL")
+            .arg(lua_script)
+            .arg(2)
+            .arg(Self::program_accounts_key(blockchain, program))
+            .arg(Self::program_generations_key(blockchain))
+            .arg(program)
+            .arg(generation)
+            .arg(key)
+            .arg(result)
+            .arg(ttl.as_millis() as u64)
+            .query(con)
+            .map_err(|e| format!("Failed to set program accounts: {}", e))?;
         Ok(())
     }
 
+    /// Drops the cached getProgramAccounts results of the programs and bumps
+    /// their generations.
+    pub fn invalidate_program_accounts(
+        &self,
+        blockchain: Uuid,
+        programs: &[&str],
+    ) -> Result<(), String> {
+        if programs.is_empty() {
+            return Ok(());
+        }
+        let mut con = self.get_connection()?;
+        let con = &mut *con;
+        let generations = Self::program_generations_key(blockchain);
+        let mut pipe = redis::pipe();
+        pipe.atomic();
+        for program in programs {
+            pipe.del(Self::program_accounts_key(blockchain, program))
+                .ignore()
+                .hincr(&generations, *program, 1)
+                .ignore();
+        }
+        let _: () = pipe
+            .query(con)
+            .map_err(|e| format!("Failed to invalidate program accounts: {}", e))?;
+        Ok(())
+    }
+
+    /// Round trip to Redis, waiting at most `timeout` for a connection.
+    pub fn ping(&self, timeout: Duration) -> Result<(), String> {
+        let mut con = self
+            .pool
+            .get_timeout(timeout)
+            .map_err(|e| format!("Failed to get connection: {}", e))?;
+        redis::cmd("PING")
+            .query::<String>(&mut *con)
+            .map(|_| ())
+            .map_err(|e| format!("Failed to ping: {}", e))
+    }
+
+    pub fn get_usage(&self, blockchain: Uuid) -> Result<StorageUsage, String> {
+        let mut con = self.get_connection()?;
+        let con = &mut *con;
+        let counters: HashMap<String, i64> = con
+            .hgetall(Self::usage_key(blockchain))
+            .map_err(|e| format!("Failed to get usage: {}", e))?;
+        Ok(StorageUsage::from_counters(counters))
+    }
+
     pub fn get_account(
         &self,
         blockchain: Uuid,
@@ -157,6 +495,62 @@ impl Cache {
         Ok(accounts)
     }
 
+    /// Reads every account of a blockchain, scanning the keyspace in batches.
+    pub fn get_all_accounts(&self, blockchain: Uuid) -> Result<Vec<DbAccount>, String> {
+        let mut accounts = Vec::new();
+        self.scan_accounts(blockchain, |batch| {
+            accounts.extend(batch);
+            Ok(())
+        })?;
+        Ok(accounts)
+    }
+
+    /// Hands every account of a blockchain to `each`, one scanned batch at a time.
+    pub fn scan_accounts(
+        &self,
+        blockchain: Uuid,
+        mut each: impl FnMut(Vec<DbAccount>) -> Result<(), String>,
+    ) -> Result<(), String> {
+        let mut con = self.get_connection()?;
+        let con = &mut *con;
+        let pattern = format!("blockchain:{}:account:*", blockchain);
+        let mut cursor: u64 = 0;
+
+        loop {
+            let (next_cursor, keys): (u64, Vec<String>) = redis::cmd("SCAN")
+                .arg(cursor)
+                .arg("MATCH")
+                .arg(&pattern)
+                .arg("COUNT")
+                .arg(1000)
+                .query(con)
+                .map_err(|e| format!("Failed to scan keys: {}", e))?;
+
+            if !keys.is_empty() {
+                let raw_jsons: Vec<Option<String>> = redis::cmd("MGET")
+                    .arg(keys)
+                    .query(con)
+                    .map_err(|e| format!("Failed to execute MGET: {}", e))?;
+                let accounts = raw_jsons
+                    .into_iter()
+                    .flatten()
+                    .map(|json| {
+                        serde_json::from_str::<DbAccount>(&json)
+                            .map_err(|e| format!("Failed to deserialize: {}", e))
+                    })
+                    .collect::<Result<Vec<DbAccount>, String>>()?;
+                each(accounts)?;
+            }
+
+            if next_cursor == 0 {
+                break;
+            }
+            cursor = next_cursor;
+        }
+
+        Ok(())
+    }
+
     pub fn set_block(&self, blockchain: Uuid, block: DbBlock) -> Result<(), String> {
         let mut con = self.get_connection()?;
         let con = &mut *con;
@@ -176,22 +570,35 @@ impl Cache {
             .map_err(|e| format!("Failed to serialize block: {}", e))?;
 
         // Use the block's height or timestamp as the score
-        let score = block.block_height.to_u64().unwrap() as f64; // Or use block.timestamp as f64
+        let score = numeric::saturating_u64(&block.block_height) as f64; // Or use block.timestamp as f64
 
-        // Add the block to the sorted set
-        let _: () = redis::cmd("ZADD")
+        // Adds the block to the sorted set and stores it individually. Usage
+        // counts each copy that's new, so storing a block again is free
+        let lua_script = r#"
+            local block = ARGV[2]
+            local cache_delta = 0
+            if redis.call('ZADD', KEYS[1], ARGV[1], block) == 1 then
+                cache_delta = cache_delta + #block
+            end
+            local previous = redis.call('STRLEN', KEYS[2])
+            redis.call('SET', KEYS[2], block)
+            cache_delta = cache_delta + #block - previous
+            if previous == 0 then
+                redis.call('HINCRBY', KEYS[3], 'blocks', 1)
+            end
+            redis.call('HINCRBY', KEYS[3], 'cache_bytes', cache_delta)
+            return cache_delta
+        "#;
+        let _: i64 = redis::cmd("EVAL")
+            .arg(lua_script)
+            .arg(3)
             .arg(&sorted_set_key)
-            .arg(score)
-            .arg(serialized_block.clone())
-            .query(con)
-            .map_err(|e| format!("Failed to add block to sorted set: {}", e))?;
-
-        // Store the block individually
-        let _: () = redis::cmd("SET")
             .arg(&block_key)
+            .arg(Self::usage_key(blockchain))
+            .arg(score)
             .arg(serialized_block)
             .query(con)
-            .map_err(|e| format!("Failed to store individual block: {}", e))?;
+            .map_err(|e| format!("Failed to store block: {}", e))?;
 
         Ok(())
     }
@@ -273,10 +680,13 @@ impl Cache {
         blocks
     }
 
+    /// Caches the transaction and adds it to the feed in one pipeline, which also
+    /// publishes `event` as (channel, payload) when given.
     pub fn set_transaction(
         &self,
         blockchain: Uuid,
-        transaction: DbTransactionObject,
+        transaction: &DbTransactionObject,
+        event: Option<(String, String)>,
     ) -> Result<(), String> {
         let mut con = self.get_connection()?;
         let con = &mut *con;
@@ -285,11 +695,41 @@ impl Cache {
             blockchain.to_string(),
             transaction.transaction.signature,
         );
-        let serialized_transaction = serde_json::to_string(&transaction)
+        let serialized_transaction = serde_json::to_string(transaction)
             .map_err(|e| format!("Failed to deserialize: {}", e))?;
-        let _: () = con
-            .set(key, serialized_transaction)
-            .map_err(|e| format!("Failed to scan keys: {}", e))?;
+        let serialized_summary =
+            serde_json::to_string(&TransactionSummary::from_object(transaction))
+                .map_err(|e| format!("Failed to serialize summary: {}", e))?;
+        // Storing a transaction again, e.g. a replay, only counts the difference
+        let lua_script = r#"
+            local previous = redis.call('STRLEN', KEYS[1])
+            redis.call('SET', KEYS[1], ARGV[1])
+            if previous == 0 then
+                redis.call('HINCRBY', KEYS[2], 'transactions', 1)
+            end
+            redis.call('HINCRBY', KEYS[2], 'cache_bytes', #ARGV[1] - previous)
+            return previous
+        "#;
+        let feed_key = Self::transaction_feed_key(blockchain);
+        let mut pipe = redis::pipe();
+        pipe.atomic()
+            .cmd("EVAL")
+            .arg(lua_script)
+            .arg(2)
+            .arg(key)
+            .arg(Self::usage_key(blockchain))
+            .arg(serialized_transaction)
+            .ignore()
+            .lpush(&feed_key, serialized_summary)
+            .ignore()
+            .ltrim(&feed_key, 0, TRANSACTION_FEED_SIZE as isize - 1)
+            .ignore();
+        if let Some((channel, payload)) = event {
+            pipe.publish(channel, payload).ignore();
+        }
+        let _: () = pipe
+            .query(con)
+            .map_err(|e| format!("Failed to set transaction: {}", e))?;
         Ok(())
     }
 
@@ -318,6 +758,37 @@ impl Cache {
         Ok(transaction)
     }
 
+    fn transaction_feed_key(blockchain: Uuid) -> String {
+        format!("blockchain:{}:transaction_feed", blockchain)
+    }
+
+    /// Newest first, at most `TRANSACTION_FEED_SIZE` are kept.
+    pub fn get_transaction_feed(
+        &self,
+        blockchain: Uuid,
+        limit: usize,
+    ) -> Result<Vec<TransactionSummary>, String> {
+        if limit == 0 {
+            return Ok(vec![]);
+        }
+        let mut con = self.get_connection()?;
+        let con = &mut *con;
+        let raw_json: Vec<String> = con
+            .lrange(
+                Self::transaction_feed_key(blockchain),
+                0,
+                limit as isize - 1,
+            )
+            .map_err(|e| format!("Failed to fetch transaction feed: {}", e))?;
+        raw_json
+            .into_iter()
+            .map(|json| {
+                serde_json::from_str::<TransactionSummary>(&json)
+                    .map_err(|e| format!("Failed to deserialize transaction summary: {}", e))
+            })
+            .collect()
+    }
+
     pub fn get_all_blockchain_values(&self, limit: usize) -> Result<Vec<Uuid>, String> {
         let mut con = self.get_connection()?;
         let con = &mut *con;
@@ -0,0 +1,119 @@
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tokio::sync::mpsc;
+use uuid::Uuid;
+
+use super::cache::Cache;
+
+/// Events distributed between engine instances over Redis pub/sub. Unlike the
+/// Kafka geyser stream these only carry identifiers, subscribers re-read the
+/// full objects from storage.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum BusEvent {
+    Account {
+        address: String,
+    },
+    Transaction {
+        signature: String,
+        accounts: Vec<String>,
+    },
+    Block {
+        slot: u64,
+    },
+}
+
+#[derive(Clone)]
+pub struct EventBus {
+    cache: Cache,
+    client: redis::Client,
+}
+
+impl EventBus {
+    pub fn new(cache: Cache, url: &str) -> Self {
+        let client = redis::Client::open(url).expect("Failed to create event bus client");
+        EventBus { cache, client }
+    }
+
+    fn channel(blockchain: Uuid) -> String {
+        format!("events:{}", blockchain)
+    }
+
+    /// Channel and payload of an event, for publishing it in a cache pipeline.
+    pub fn message(blockchain: Uuid, event: &BusEvent) -> Result<(String, String), String> {
+        let payload = serde_json::to_string(event)
+            .map_err(|e| format!("Failed to serialize event: {}", e))?;
+        Ok((Self::channel(blockchain), payload))
+    }
+
+    pub fn publish(&self, blockchain: Uuid, event: &BusEvent) {
+        let (channel, payload) = match Self::message(blockchain, event) {
+            Ok(message) => message,
+            Err(e) => {
+                println!("{}", e);
+                return;
+            }
+        };
+        let mut con = match self.cache.get_connection() {
+            Ok(con) => con,
+            Err(e) => {
+                println!("Failed to publish event: {}", e);
+                return;
+            }
+        };
+        let res: Result<i64, _> = redis::cmd("PUBLISH")
+            .arg(channel)
+            .arg(payload)
+            .query(&mut *con);
+        if let Err(e) = res {
+            println!("Failed to publish event: {:?}", e);
+        }
+    }
+
+    /// Subscribes to the events of a blockchain. The subscription runs on a
+    /// dedicated connection and stops once the receiver is dropped.
+    pub fn subscribe(&self, blockchain: Uuid) -> Result<mpsc::Receiver<BusEvent>, String> {
+        let mut con = self
+            .client
+            .get_connection()
+            .map_err(|e| format!("Failed to get connection: {}", e))?;
+        let (tx, rx) = mpsc::channel(100);
+        let channel = Self::channel(blockchain);
+
+        std::thread::spawn(move || {
+            let mut pubsub = con.as_pubsub();
+            if let Err(e) = pubsub.subscribe(&channel) {
+                println!("Failed to subscribe to {}: {:?}", channel, e);
+                return;
+            }
+            // Wake up periodically so the thread notices dropped receivers
+            if let Err(e) = pubsub.set_read_timeout(Some(Duration::from_secs(1))) {
+                println!("Failed to set read timeout: {:?}", e);
+                return;
+            }
+            while !tx.is_closed() {
+                let msg = match pubsub.get_message() {
+                    Ok(msg) => msg,
+                    Err(e) if e.is_timeout() => continue,
+                    Err(e) => {
+                        println!("Event bus subscription failed: {:?}", e);
+                        return;
+                    }
+                };
+                let payload: String = match msg.get_payload() {
+                    Ok(payload) => payload,
+                    Err(_) => continue,
+                };
+                let event = match serde_json::from_str::<BusEvent>(&payload) {
+                    Ok(event) => event,
+                    Err(_) => continue,
+                };
+                if tx.blocking_send(event).is_err() {
+                    return;
+                }
+            }
+        });
+
+        Ok(rx)
+    }
+}
@@ -0,0 +1,67 @@
+use diesel::{sql_types::Text, QueryableByName};
+use serde::Serialize;
+
+/// Tables the engine reads and writes, all must exist before it serves traffic.
+pub const REQUIRED_TABLES: [&str; 20] = [
+    "accounts",
+    "blocks",
+    "blockchains",
+    "transactions",
+    "transaction_account_keys",
+    "transaction_instructions",
+    "transaction_log_messages",
+    "transaction_meta",
+    "transaction_signatures",
+    "teams",
+    "api_keys",
+    "transaction_token_balances",
+    "blockchain_configs",
+    "blockchain_config_accounts",
+    "program_idls",
+    "webhooks",
+    "audit_logs",
+    "pending_transactions",
+    "top_ups",
+    "blockchain_sysvars",
+];
+
+/// Result of one readiness check.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct ComponentStatus {
+    pub name: &'static str,
+    pub ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl ComponentStatus {
+    pub fn new(name: &'static str, res: Result<(), String>) -> Self {
+        match res {
+            Ok(_) => ComponentStatus {
+                name,
+                ok: true,
+                error: None,
+            },
+            Err(e) => ComponentStatus {
+                name,
+                ok: false,
+                error: Some(e),
+            },
+        }
+    }
+}
+
+#[derive(QueryableByName)]
+pub struct ExistingTable {
+    #[diesel(sql_type = Text)]
+    pub table_name: String,
+}
+
+/// Required tables missing from the existing ones, in schema order.
+pub fn missing_tables(existing: &[String]) -> Vec<&'static str> {
+    REQUIRED_TABLES
+        .iter()
+        .filter(|table| !existing.iter().any(|existing| existing == *table))
+        .copied()
+        .collect()
+}
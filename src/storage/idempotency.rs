@@ -0,0 +1,91 @@
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use uuid::Uuid;
+
+use super::cache::Cache;
+
+/// How long a retry returns the blockchain created for an `Idempotency-Key`.
+pub const IDEMPOTENCY_KEY_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+/// How long a reservation blocks retries when its request dies before completing it.
+pub const IDEMPOTENCY_PENDING_TTL: Duration = Duration::from_secs(60);
+pub const MAX_IDEMPOTENCY_KEY_LEN: usize = 255;
+
+/// What an `Idempotency-Key` was first used for, `blockchain` is set once created.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct IdempotencyRecord {
+    /// The parts of the request a retry has to repeat.
+    pub fingerprint: String,
+    pub blockchain: Option<Uuid>,
+}
+
+/// Idempotency keys in Redis, per team.
+#[derive(Clone)]
+pub struct IdempotencyStore {
+    cache: Cache,
+}
+
+impl IdempotencyStore {
+    pub fn new(cache: Cache) -> Self {
+        IdempotencyStore { cache }
+    }
+
+    fn key(team_id: Uuid, key: &str) -> String {
+        format!("team:{}:idempotency:{}", team_id, key)
+    }
+
+    pub fn reserve(
+        &self,
+        team_id: Uuid,
+        key: &str,
+        fingerprint: &str,
+    ) -> Result<Option<IdempotencyRecord>, String> {
+        let record = IdempotencyRecord {
+            fingerprint: fingerprint.to_string(),
+            blockchain: None,
+        };
+        let payload = serde_json::to_string(&record).map_err(|e| e.to_string())?;
+        let mut con = self.cache.get_connection()?;
+        let lua_script = r#"
+            if redis.call('SET', KEYS[1], ARGV[1], 'NX', 'PX', ARGV[2]) then
+                return false
+            end
+            return redis.call('GET', KEYS[1])
+        "#;
+        let existing: Option<String> = redis::cmd("EVAL")
+            .arg(lua_script)
+            .arg(1)
+            .arg(Self::key(team_id, key))
+            .arg(payload)
+            .arg(IDEMPOTENCY_PENDING_TTL.as_millis() as u64)
+            .query(&mut *con)
+            .map_err(|e| format!("Failed to reserve idempotency key: {}", e))?;
+        existing
+            .map(|existing| serde_json::from_str(&existing).map_err(|e| e.to_string()))
+            .transpose()
+    }
+
+    pub fn complete(
+        &self,
+        team_id: Uuid,
+        key: &str,
+        record: &IdempotencyRecord,
+    ) -> Result<(), String> {
+        let payload = serde_json::to_string(record).map_err(|e| e.to_string())?;
+        let mut con = self.cache.get_connection()?;
+        redis::cmd("SET")
+            .arg(Self::key(team_id, key))
+            .arg(payload)
+            .arg("PX")
+            .arg(IDEMPOTENCY_KEY_TTL.as_millis() as u64)
+            .query::<()>(&mut *con)
+            .map_err(|e| format!("Failed to complete idempotency key: {}", e))
+    }
+
+    pub fn release(&self, team_id: Uuid, key: &str) -> Result<(), String> {
+        let mut con = self.cache.get_connection()?;
+        redis::cmd("DEL")
+            .arg(Self::key(team_id, key))
+            .query::<()>(&mut *con)
+            .map_err(|e| format!("Failed to release idempotency key: {}", e))
+    }
+}
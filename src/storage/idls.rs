@@ -0,0 +1,13 @@
+use diesel::prelude::*;
+use uuid::Uuid;
+
+#[derive(Queryable, Selectable, Insertable, AsChangeset, Clone)]
+#[diesel(table_name = crate::schema::program_idls)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct DbProgramIdl {
+    pub id: Uuid,
+    pub created_at: chrono::NaiveDateTime,
+    pub blockchain: Uuid,
+    pub program_id: String,
+    pub idl: String, // IDL JSON as uploaded
+}
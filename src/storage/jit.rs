@@ -0,0 +1,70 @@
+use std::time::Instant;
+
+use chrono::Utc;
+use solana_sdk::{account::Account, pubkey::Pubkey};
+use uuid::Uuid;
+
+use super::rpc::Rpc;
+use crate::metrics::{self, JitFetch};
+
+/// Records reads of a JIT blockchain's accounts that were already stored.
+pub fn record_hits(id: Uuid, hits: usize) {
+    metrics::record_jit(id, hits, vec![], Default::default());
+}
+
+/// Fetches the accounts a JIT blockchain is missing from upstream, in order.
+/// `hits` is how many of the requested accounts were already stored. Every
+/// fetch is logged and recorded for the blockchain's JIT report.
+pub async fn fetch_missing(
+    rpc: &Rpc,
+    id: Uuid,
+    hits: usize,
+    missing: &[Pubkey],
+) -> Result<Vec<Option<Account>>, String> {
+    if missing.is_empty() {
+        record_hits(id, hits);
+        return Ok(vec![]);
+    }
+    let started = Instant::now();
+    let accounts = match rpc.get_accounts(missing).await {
+        Ok(accounts) => accounts,
+        Err(e) => {
+            metrics::record_jit_error(id, started.elapsed());
+            println!(
+                "JIT fetch of {} accounts for {} failed after {}ms: {}",
+                missing.len(),
+                id,
+                started.elapsed().as_millis(),
+                e
+            );
+            return Err(e);
+        }
+    };
+    let elapsed = started.elapsed();
+
+    let fetched_at = Utc::now().naive_utc();
+    let fetches: Vec<JitFetch> = missing
+        .iter()
+        .zip(&accounts)
+        .map(|(pubkey, account)| JitFetch {
+            pubkey: pubkey.to_string(),
+            found: account.is_some(),
+            owner: account.as_ref().map(|account| account.owner.to_string()),
+            bytes: account.as_ref().map_or(0, |account| account.data.len()),
+            latency_ms: elapsed.as_millis() as u64,
+            fetched_at,
+        })
+        .collect();
+    for fetch in &fetches {
+        println!(
+            "JIT {} {} {} in {}ms, {} bytes",
+            id,
+            fetch.pubkey,
+            if fetch.found { "cloned" } else { "not found" },
+            fetch.latency_ms,
+            fetch.bytes
+        );
+    }
+    metrics::record_jit(id, hits, fetches, elapsed);
+    Ok(accounts)
+}
@@ -0,0 +1,182 @@
+use base64::prelude::*;
+use serde::{Deserialize, Serialize};
+use solana_sdk::transaction::VersionedTransaction;
+use std::time::Duration;
+use uuid::Uuid;
+
+use super::cache::Cache;
+
+// Forwarded work nobody picks up, e.g. of a deleted blockchain, expires eventually
+const FORWARDED_TTL_SECS: i64 = 3600;
+
+/// Work an instance without a blockchain's lease hands to the one holding it.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum Forwarded {
+    /// Base64 bincode transaction, as sent.
+    Transaction { transaction: String, jit: bool },
+    /// A blockhash was read, so the holder should produce a block.
+    Block,
+}
+
+impl Forwarded {
+    pub fn transaction(tx: &VersionedTransaction, jit: bool) -> Result<Self, String> {
+        let bytes = bincode::serialize(tx).map_err(|e| e.to_string())?;
+        Ok(Forwarded::Transaction {
+            transaction: BASE64_STANDARD.encode(bytes),
+            jit,
+        })
+    }
+
+    pub fn decode_transaction(transaction: &str) -> Result<VersionedTransaction, String> {
+        let bytes = BASE64_STANDARD
+            .decode(transaction)
+            .map_err(|e| e.to_string())?;
+        bincode::deserialize(&bytes).map_err(|e| e.to_string())
+    }
+}
+
+/// Blockchain leases and forwarded work in Redis. Keys live under the blockchain's
+/// prefix, so deleting it drops them too.
+#[derive(Clone)]
+pub struct LeaseStore {
+    cache: Cache,
+}
+
+impl LeaseStore {
+    pub fn new(cache: Cache) -> Self {
+        LeaseStore { cache }
+    }
+
+    fn lease_key(blockchain: Uuid) -> String {
+        format!("blockchain:{}:lease", blockchain)
+    }
+
+    fn forwarded_key(blockchain: Uuid) -> String {
+        format!("blockchain:{}:forwarded", blockchain)
+    }
+
+    pub fn acquire(&self, blockchain: Uuid, holder: Uuid, ttl: Duration) -> Result<Uuid, String> {
+        let mut con = self.cache.get_connection()?;
+        let lua_script = r#"
+            local holder = redis.call('GET', KEYS[1])
+            if not holder or holder == ARGV[1] then
+                redis.call('SET', KEYS[1], ARGV[1], 'PX', ARGV[2])
+                return ARGV[1]
+            end
+            return holder
+        "#;
+        let holder: String = redis::cmd("EVAL")
+            .arg(lua_script)
+            .arg(1)
+            .arg(Self::lease_key(blockchain))
+            .arg(holder.to_string())
+            .arg(ttl.as_millis().max(1) as u64)
+            .query(&mut *con)
+            .map_err(|e| format!("Failed to acquire lease: {}", e))?;
+        Uuid::parse_str(&holder).map_err(|e| format!("Invalid lease holder: {}", e))
+    }
+
+    pub fn release(&self, blockchain: Uuid, holder: Uuid) -> Result<bool, String> {
+        let mut con = self.cache.get_connection()?;
+        // Work forwarded since the holder last looked keeps the lease held
+        let lua_script = r#"
+            if redis.call('GET', KEYS[1]) == ARGV[1] and redis.call('LLEN', KEYS[2]) == 0 then
+                redis.call('DEL', KEYS[1])
+                return 1
+            end
+            return 0
+        "#;
+        let released: i32 = redis::cmd("EVAL")
+            .arg(lua_script)
+            .arg(2)
+            .arg(Self::lease_key(blockchain))
+            .arg(Self::forwarded_key(blockchain))
+            .arg(holder.to_string())
+            .query(&mut *con)
+            .map_err(|e| format!("Failed to release lease: {}", e))?;
+        Ok(released == 1)
+    }
+
+    pub fn forward(&self, blockchain: Uuid, work: &Forwarded) -> Result<(), String> {
+        let payload = serde_json::to_string(work).map_err(|e| e.to_string())?;
+        let mut con = self.cache.get_connection()?;
+        let key = Self::forwarded_key(blockchain);
+        redis::pipe()
+            .cmd("RPUSH")
+            .arg(&key)
+            .arg(payload)
+            .ignore()
+            .cmd("EXPIRE")
+            .arg(&key)
+            .arg(FORWARDED_TTL_SECS)
+            .ignore()
+            .query::<()>(&mut *con)
+            .map_err(|e| format!("Failed to forward: {}", e))
+    }
+
+    /// Pushes `work` back on the head of the list, keeping its order.
+    pub fn give_back(&self, blockchain: Uuid, work: &[Forwarded]) -> Result<(), String> {
+        if work.is_empty() {
+            return Ok(());
+        }
+        let payloads = work
+            .iter()
+            .rev()
+            .map(serde_json::to_string)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| e.to_string())?;
+        let mut con = self.cache.get_connection()?;
+        let key = Self::forwarded_key(blockchain);
+        redis::pipe()
+            .cmd("LPUSH")
+            .arg(&key)
+            .arg(payloads)
+            .ignore()
+            .cmd("EXPIRE")
+            .arg(&key)
+            .arg(FORWARDED_TTL_SECS)
+            .ignore()
+            .query::<()>(&mut *con)
+            .map_err(|e| format!("Failed to give back forwarded work: {}", e))
+    }
+
+    pub fn take(
+        &self,
+        blockchains: &[Uuid],
+        limit: usize,
+    ) -> Result<Vec<(Uuid, Forwarded)>, String> {
+        if blockchains.is_empty() || limit == 0 {
+            return Ok(vec![]);
+        }
+        let mut con = self.cache.get_connection()?;
+        let lua_script = r#"
+            local taken = {}
+            for i = 1, #KEYS do
+                taken[i] = redis.call('LRANGE', KEYS[i], 0, ARGV[1] - 1)
+                redis.call('LTRIM', KEYS[i], ARGV[1], -1)
+            end
+            return taken
+        "#;
+        let mut cmd = redis::cmd("EVAL");
+        cmd.arg(lua_script).arg(blockchains.len());
+        for blockchain in blockchains {
+            cmd.arg(Self::forwarded_key(*blockchain));
+        }
+        let taken: Vec<Vec<String>> = cmd
+            .arg(limit)
+            .query(&mut *con)
+            .map_err(|e| format!("Failed to take forwarded work: {}", e))?;
+
+        let mut work = vec![];
+        for (blockchain, payloads) in blockchains.iter().zip(taken) {
+            for payload in payloads {
+                match serde_json::from_str::<Forwarded>(&payload) {
+                    Ok(forwarded) => work.push((*blockchain, forwarded)),
+                    Err(e) => println!("Dropping invalid forwarded work: {}", e),
+                }
+            }
+        }
+        Ok(work)
+    }
+}
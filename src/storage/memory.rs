@@ -0,0 +1,1766 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Arc, RwLock},
+    time::{Duration, Instant},
+};
+
+use actix_web::rt;
+use bigdecimal::BigDecimal;
+use chrono::{NaiveDate, Utc};
+use solana_sdk::{
+    account::Account, hash::Hash, pubkey::Pubkey, signature::Signature,
+    transaction::TransactionError, transaction::VersionedTransaction,
+};
+use tokio::sync::{broadcast, mpsc};
+use uuid::Uuid;
+
+use super::{
+    accounts::{is_empty_account, nonce_authority, AccountListFilter, AccountSummary},
+    audit::{AuditLogFilter, DbAuditLog},
+    blocks::{BlockchainFilter, DBBlockchainConfig, DbBlock, DbBlockchain, DbBlockchainUpdate},
+    events::BusEvent,
+    health::ComponentStatus,
+    idempotency::{IdempotencyRecord, IDEMPOTENCY_KEY_TTL, IDEMPOTENCY_PENDING_TTL},
+    jit,
+    leases::Forwarded,
+    numeric,
+    pubsub::{GeyserEvent, PubSubHeartbeat},
+    rpc::Rpc,
+    teams::{ApiKey, Team, TeamQuotas},
+    top_ups::DbTopUp,
+    transactions::{
+        DbPendingTransaction, DbTransaction, DbTransactionLogMessage, DbTransactionObject,
+        TokenBalanceChange, TransactionFilter, TransactionSummary,
+    },
+    usage::StorageUsage,
+    webhooks::DbWebhook,
+    Storage, LOCAL_EVENTS_CAPACITY,
+};
+use crate::engine::{
+    blocks::{Block, Blockchain},
+    sysvars::SysvarOverrides,
+    transactions::{TransactionMeta, TransactionMetadata},
+};
+
+#[derive(Clone)]
+struct StoredAccount {
+    account: Account,
+    label: Option<String>,
+}
+
+#[derive(Default)]
+struct State {
+    teams: HashMap<Uuid, Team>,
+    api_keys: Vec<ApiKey>,
+    blockchains: HashMap<Uuid, DbBlockchain>,
+    accounts: HashMap<Uuid, HashMap<Pubkey, StoredAccount>>,
+    configs: Vec<DBBlockchainConfig>,
+    config_accounts: HashMap<Uuid, HashMap<Pubkey, Account>>,
+    // In insertion order, which is also height order
+    blocks: HashMap<Uuid, Vec<DbBlock>>,
+    // In insertion order, oldest first
+    transactions: HashMap<Uuid, Vec<DbTransactionObject>>,
+    idls: HashMap<(Uuid, Pubkey), String>,
+    sysvars: HashMap<Uuid, SysvarOverrides>,
+    // getProgramAccounts results by blockchain and program, then request key
+    program_accounts: HashMap<(Uuid, Pubkey), HashMap<String, String>>,
+    // Bumped whenever a program's results are dropped
+    program_generations: HashMap<(Uuid, Pubkey), u64>,
+    webhooks: Vec<DbWebhook>,
+    top_ups: Vec<DbTopUp>,
+    audit_logs: Vec<DbAuditLog>,
+    // Holder and expiry of each lease
+    leases: HashMap<Uuid, (Uuid, Instant)>,
+    forwarded: HashMap<Uuid, VecDeque<Forwarded>>,
+    // In insertion order, oldest first
+    pending_transactions: Vec<DbPendingTransaction>,
+    // Record and expiry, by team and key
+    idempotency_keys: HashMap<(Uuid, String), (IdempotencyRecord, Instant)>,
+    // Transactions counted against the daily quota, by team and UTC day
+    daily_transactions: HashMap<(Uuid, NaiveDate), u32>,
+}
+
+impl State {
+    fn remove_blockchain(&mut self, id: Uuid) {
+        self.blockchains.remove(&id);
+        self.accounts.remove(&id);
+        self.blocks.remove(&id);
+        self.transactions.remove(&id);
+        self.idls.retain(|(blockchain, _), _| *blockchain != id);
+        self.sysvars.remove(&id);
+        self.program_accounts
+            .retain(|(blockchain, _), _| *blockchain != id);
+        self.program_generations
+            .retain(|(blockchain, _), _| *blockchain != id);
+        self.webhooks.retain(|webhook| webhook.blockchain != id);
+        self.top_ups.retain(|top_up| top_up.blockchain != id);
+        self.leases.remove(&id);
+        self.forwarded.remove(&id);
+        self.pending_transactions.retain(|tx| tx.blockchain != id);
+    }
+
+    /// Drops the cached program accounts of the account's previous and new owner.
+    fn forget_program_accounts(&mut self, id: Uuid, previous: Option<Pubkey>, owner: &Pubkey) {
+        if let Some(previous) = previous {
+            self.invalidate_program_accounts(id, previous);
+        }
+        self.invalidate_program_accounts(id, *owner);
+    }
+
+    fn invalidate_program_accounts(&mut self, id: Uuid, program: Pubkey) {
+        self.program_accounts.remove(&(id, program));
+        *self.program_generations.entry((id, program)).or_default() += 1;
+    }
+}
+
+/// Storage kept entirely in process memory, for running the engine without Postgres,
+/// Redis or Kafka. Nothing survives the process, and accounts are only fetched from
+/// mainnet when an upstream RPC is given.
+#[derive(Clone)]
+pub struct MemoryStorage {
+    state: Arc<RwLock<State>>,
+    rpc: Option<Rpc>,
+    local_events: broadcast::Sender<(Uuid, BusEvent)>,
+}
+
+impl Default for MemoryStorage {
+    fn default() -> Self {
+        MemoryStorage {
+            state: Arc::default(),
+            rpc: None,
+            local_events: broadcast::channel(LOCAL_EVENTS_CAPACITY).0,
+        }
+    }
+}
+
+impl MemoryStorage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Fetches missing accounts of JIT blockchains from `rpc_url`.
+    pub fn with_rpc(mut self, rpc_url: &str) -> Self {
+        self.rpc = Some(Rpc::new(rpc_url.to_string()));
+        self
+    }
+
+    /// Adds a team, so API keys can be resolved to it.
+    pub fn create_team(&self, team: Team) {
+        self.state.write().unwrap().teams.insert(team.id, team);
+    }
+
+    fn publish_event(&self, id: Uuid, event: BusEvent) {
+        // Errors only mean nobody is listening
+        let _ = self.local_events.send((id, event));
+    }
+
+    fn remote_accounts(&self) -> Result<&Rpc, String> {
+        self.rpc
+            .as_ref()
+            .ok_or_else(|| "No upstream RPC configured".to_string())
+    }
+
+    fn account_matches(
+        &self,
+        id: Uuid,
+        matches: impl Fn(&Account) -> bool,
+    ) -> Vec<(Pubkey, Account)> {
+        let state = self.state.read().unwrap();
+        state
+            .accounts
+            .get(&id)
+            .into_iter()
+            .flatten()
+            .filter(|(_, stored)| matches(&stored.account))
+            .map(|(address, stored)| (*address, stored.account.clone()))
+            .collect()
+    }
+
+    fn transactions_matching(
+        &self,
+        id: Uuid,
+        matches: impl Fn(&DbTransactionObject) -> bool,
+    ) -> Vec<DbTransactionObject> {
+        let state = self.state.read().unwrap();
+        state
+            .transactions
+            .get(&id)
+            .into_iter()
+            .flatten()
+            .filter(|tx| matches(tx))
+            .cloned()
+            .collect()
+    }
+}
+
+fn references(tx: &DbTransactionObject, address: &str) -> bool {
+    tx.account_keys.iter().any(|key| key.account == address)
+}
+
+impl Storage for MemoryStorage {
+    fn get_team_from_api_key(&self, api_key: Uuid) -> Result<Team, String> {
+        let (_, team) = self.get_api_key_team(api_key)?;
+        Ok(team)
+    }
+
+    fn get_team(&self, id: Uuid) -> Result<Option<Team>, String> {
+        Ok(self.state.read().unwrap().teams.get(&id).cloned())
+    }
+
+    fn set_team_quotas(&self, id: Uuid, quotas: &TeamQuotas) -> Result<Option<Team>, String> {
+        let mut state = self.state.write().unwrap();
+        Ok(state.teams.get_mut(&id).map(|team| {
+            team.max_blockchains = quotas.max_blockchains;
+            team.max_accounts = quotas.max_accounts;
+            team.max_transactions_per_day = quotas.max_transactions_per_day;
+            team.clone()
+        }))
+    }
+
+    fn set_team_auto_pause(&self, id: Uuid, hours: Option<i32>) -> Result<Option<Team>, String> {
+        let mut state = self.state.write().unwrap();
+        Ok(state.teams.get_mut(&id).map(|team| {
+            team.auto_pause_hours = hours;
+            team.clone()
+        }))
+    }
+
+    fn set_team_rpc_write_guard(&self, id: Uuid, enabled: bool) -> Result<Option<Team>, String> {
+        let mut state = self.state.write().unwrap();
+        Ok(state.teams.get_mut(&id).map(|team| {
+            team.rpc_write_guard = enabled;
+            team.clone()
+        }))
+    }
+
+    fn get_api_key_team(&self, api_key: Uuid) -> Result<(ApiKey, Team), String> {
+        let state = self.state.read().unwrap();
+        let key = state
+            .api_keys
+            .iter()
+            .find(|key| key.id == api_key && key.revoked_at.is_none())
+            .ok_or("API key not found")?;
+        let team = state.teams.get(&key.team_id).ok_or("Team not found")?;
+        Ok((key.clone(), team.clone()))
+    }
+
+    fn create_api_key(&self, api_key: &ApiKey) -> Result<(), String> {
+        self.state.write().unwrap().api_keys.push(api_key.clone());
+        Ok(())
+    }
+
+    fn get_api_keys(&self, team_id: Uuid) -> Result<Vec<ApiKey>, String> {
+        let state = self.state.read().unwrap();
+        Ok(state
+            .api_keys
+            .iter()
+            .filter(|key| key.team_id == team_id)
+            .cloned()
+            .collect())
+    }
+
+    fn set_api_key_label(
+        &self,
+        team_id: Uuid,
+        api_key: Uuid,
+        label: String,
+    ) -> Result<bool, String> {
+        let mut state = self.state.write().unwrap();
+        match state
+            .api_keys
+            .iter_mut()
+            .find(|key| key.id == api_key && key.team_id == team_id)
+        {
+            Some(key) => {
+                key.label = label;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    fn revoke_api_key(&self, team_id: Uuid, api_key: Uuid) -> Result<bool, String> {
+        let mut state = self.state.write().unwrap();
+        match state
+            .api_keys
+            .iter_mut()
+            .find(|key| key.id == api_key && key.team_id == team_id && key.revoked_at.is_none())
+        {
+            Some(key) => {
+                key.revoked_at = Some(Utc::now().naive_utc());
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    fn get_account(&self, id: Uuid, address: &Pubkey) -> Result<Option<Account>, String> {
+        let state = self.state.read().unwrap();
+        Ok(state
+            .accounts
+            .get(&id)
+            .and_then(|accounts| accounts.get(address))
+            .map(|stored| stored.account.clone()))
+    }
+
+    async fn get_remote_accounts(
+        &self,
+        addresses: &[Pubkey],
+    ) -> Result<Vec<Option<Account>>, String> {
+        self.remote_accounts()?.get_accounts(addresses).await
+    }
+
+    async fn get_account_jit(
+        &self,
+        id: Uuid,
+        address: &Pubkey,
+        jit: bool,
+    ) -> Result<Option<Account>, String> {
+        let account = self.get_account(id, address)?;
+        match (&account, &self.rpc) {
+            (None, Some(rpc)) if jit => {
+                let remote = jit::fetch_missing(rpc, id, 0, &[*address])
+                    .await?
+                    .pop()
+                    .flatten();
+                if let Some(remote) = &remote {
+                    self.set_account(id, address, remote.clone(), None)?;
+                }
+                Ok(remote)
+            }
+            (Some(_), Some(_)) if jit => {
+                jit::record_hits(id, 1);
+                Ok(account)
+            }
+            _ => Ok(account),
+        }
+    }
+
+    fn get_accounts(
+        &self,
+        id: Uuid,
+        addresses: &Vec<&Pubkey>,
+    ) -> Result<Vec<Option<Account>>, String> {
+        let state = self.state.read().unwrap();
+        let accounts = state.accounts.get(&id);
+        Ok(addresses
+            .iter()
+            .map(|address| {
+                accounts
+                    .and_then(|accounts| accounts.get(*address))
+                    .map(|stored| stored.account.clone())
+            })
+            .collect())
+    }
+
+    async fn get_accounts_jit(
+        &self,
+        id: Uuid,
+        addresses: &Vec<&Pubkey>,
+        jit: bool,
+    ) -> Result<Vec<Option<Account>>, String> {
+        let mut accounts = self.get_accounts(id, addresses)?;
+        let rpc = match &self.rpc {
+            Some(rpc) if jit => rpc,
+            _ => return Ok(accounts),
+        };
+        let missing: Vec<usize> = (0..accounts.len())
+            .filter(|idx| accounts[*idx].is_none())
+            .collect();
+        let hits = accounts.len() - missing.len();
+        if missing.is_empty() {
+            jit::record_hits(id, hits);
+            return Ok(accounts);
+        }
+        let missing_addresses: Vec<Pubkey> = missing.iter().map(|idx| *addresses[*idx]).collect();
+        let remote = jit::fetch_missing(rpc, id, hits, &missing_addresses).await?;
+        let mut to_save = vec![];
+        for (idx, account) in missing.into_iter().zip(remote) {
+            if let Some(account) = account {
+                to_save.push((*addresses[idx], account.clone()));
+                accounts[idx] = Some(account);
+            }
+        }
+        if !to_save.is_empty() {
+            self.set_accounts(id, to_save)?;
+        }
+        Ok(accounts)
+    }
+
+    fn get_largest_accounts(&self, id: Uuid, limit: usize) -> Result<Vec<(Pubkey, u64)>, String> {
+        let mut accounts: Vec<(Pubkey, u64)> = self
+            .account_matches(id, |_| true)
+            .into_iter()
+            .map(|(address, account)| (address, account.lamports))
+            .collect();
+        accounts.sort_by_key(|(_, lamports)| std::cmp::Reverse(*lamports));
+        accounts.truncate(limit);
+        Ok(accounts)
+    }
+
+    fn list_accounts(
+        &self,
+        id: Uuid,
+        filter: &AccountListFilter,
+    ) -> Result<(Vec<AccountSummary>, i64), String> {
+        let state = self.state.read().unwrap();
+        let label = filter.label.as_ref().map(|label| label.to_lowercase());
+        let mut accounts: Vec<AccountSummary> = state
+            .accounts
+            .get(&id)
+            .into_iter()
+            .flatten()
+            .filter(|(_, stored)| {
+                filter
+                    .owner
+                    .is_none_or(|owner| stored.account.owner == owner)
+                    && filter
+                        .executable
+                        .is_none_or(|executable| stored.account.executable == executable)
+                    && label.as_ref().is_none_or(|label| {
+                        stored
+                            .label
+                            .as_ref()
+                            .is_some_and(|l| l.to_lowercase().contains(label))
+                    })
+            })
+            .map(|(address, stored)| AccountSummary {
+                address: address.to_string(),
+                lamports: stored.account.lamports.into(),
+                owner: stored.account.owner.to_string(),
+                executable: stored.account.executable,
+                rent_epoch: stored.account.rent_epoch.into(),
+                label: stored.label.clone(),
+                data_len: stored.account.data.len() as i32,
+            })
+            .collect();
+        accounts.sort_by(|a, b| a.address.cmp(&b.address));
+        let total = accounts.len() as i64;
+        let accounts = accounts
+            .into_iter()
+            .skip(filter.offset.max(0) as usize)
+            .take(filter.limit.max(0) as usize)
+            .collect();
+        Ok((accounts, total))
+    }
+
+    fn set_account(
+        &self,
+        id: Uuid,
+        address: &Pubkey,
+        account: Account,
+        label: Option<String>,
+    ) -> Result<(), String> {
+        {
+            let mut state = self.state.write().unwrap();
+            let owner = account.owner;
+            let previous = state
+                .accounts
+                .entry(id)
+                .or_default()
+                .insert(*address, StoredAccount { account, label });
+            state.forget_program_accounts(id, previous.map(|stored| stored.account.owner), &owner);
+        }
+        self.publish_event(
+            id,
+            BusEvent::Account {
+                address: address.to_string(),
+            },
+        );
+        Ok(())
+    }
+
+    fn set_account_lamports(
+        &self,
+        id: Uuid,
+        address: &Pubkey,
+        lamports: u64,
+    ) -> Result<(), String> {
+        let updated = {
+            let mut state = self.state.write().unwrap();
+            let owner = match state
+                .accounts
+                .get_mut(&id)
+                .and_then(|accounts| accounts.get_mut(address))
+            {
+                Some(stored) => {
+                    stored.account.lamports = lamports;
+                    Some(stored.account.owner)
+                }
+                None => None,
+            };
+            if let Some(owner) = &owner {
+                state.forget_program_accounts(id, None, owner);
+            }
+            owner.is_some()
+        };
+        if updated {
+            self.publish_event(
+                id,
+                BusEvent::Account {
+                    address: address.to_string(),
+                },
+            );
+        }
+        Ok(())
+    }
+
+    fn set_accounts(&self, id: Uuid, accounts: Vec<(Pubkey, Account)>) -> Result<(), String> {
+        let addresses: Vec<Pubkey> = accounts.iter().map(|(address, _)| *address).collect();
+        {
+            let mut state = self.state.write().unwrap();
+            for (address, account) in accounts {
+                let owner = account.owner;
+                let stored = state.accounts.entry(id).or_default();
+                // Writes from transactions keep the label the account was loaded with
+                let previous = stored.remove(&address);
+                let label = previous
+                    .as_ref()
+                    .and_then(|previous| previous.label.clone());
+                stored.insert(address, StoredAccount { account, label });
+                state.forget_program_accounts(
+                    id,
+                    previous.map(|stored| stored.account.owner),
+                    &owner,
+                );
+            }
+        }
+        for address in addresses {
+            self.publish_event(
+                id,
+                BusEvent::Account {
+                    address: address.to_string(),
+                },
+            );
+        }
+        Ok(())
+    }
+
+    fn set_account_label(
+        &self,
+        id: Uuid,
+        address: &Pubkey,
+        label: Option<String>,
+    ) -> Result<bool, String> {
+        let mut state = self.state.write().unwrap();
+        match state
+            .accounts
+            .get_mut(&id)
+            .and_then(|accounts| accounts.get_mut(address))
+        {
+            Some(stored) => {
+                stored.label = label;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    fn get_labeled_accounts(&self, id: Uuid, labels: &[String]) -> Result<Vec<Pubkey>, String> {
+        let state = self.state.read().unwrap();
+        Ok(state
+            .accounts
+            .get(&id)
+            .into_iter()
+            .flatten()
+            .filter(|(_, stored)| stored.label.as_ref().is_some_and(|l| labels.contains(l)))
+            .map(|(address, _)| *address)
+            .collect())
+    }
+
+    fn delete_account(&self, id: Uuid, address: &Pubkey) -> Result<bool, String> {
+        let deleted = {
+            let mut state = self.state.write().unwrap();
+            let previous = state
+                .accounts
+                .get_mut(&id)
+                .and_then(|accounts| accounts.remove(address));
+            if let Some(previous) = &previous {
+                let owner = previous.account.owner;
+                state.invalidate_program_accounts(id, owner);
+            }
+            previous.is_some()
+        };
+        if deleted {
+            self.publish_event(
+                id,
+                BusEvent::Account {
+                    address: address.to_string(),
+                },
+            );
+        }
+        Ok(deleted)
+    }
+
+    fn delete_accounts(&self, id: Uuid, addresses: &[Pubkey]) -> Result<usize, String> {
+        let mut deleted = 0;
+        for address in addresses {
+            if self.delete_account(id, address)? {
+                deleted += 1;
+            }
+        }
+        Ok(deleted)
+    }
+
+    fn delete_empty_accounts(&self, id: Uuid, addresses: &[Pubkey]) -> Result<usize, String> {
+        let deleted: Vec<Pubkey> = {
+            let mut state = self.state.write().unwrap();
+            let Some(accounts) = state.accounts.get_mut(&id) else {
+                return Ok(0);
+            };
+            let mut owners = vec![];
+            for address in addresses {
+                if accounts
+                    .get(address)
+                    .is_some_and(|stored| is_empty_account(&stored.account))
+                {
+                    let previous = accounts.remove(address).unwrap();
+                    owners.push((*address, previous.account.owner));
+                }
+            }
+            for (_, owner) in owners.iter() {
+                state.invalidate_program_accounts(id, *owner);
+            }
+            owners.into_iter().map(|(address, _)| address).collect()
+        };
+        for address in deleted.iter() {
+            self.publish_event(
+                id,
+                BusEvent::Account {
+                    address: address.to_string(),
+                },
+            );
+        }
+        Ok(deleted.len())
+    }
+
+    fn get_empty_accounts(&self, limit: i64) -> Result<Vec<(Uuid, Pubkey)>, String> {
+        let state = self.state.read().unwrap();
+        Ok(state
+            .accounts
+            .iter()
+            .filter(|(id, _)| state.blockchains.get(id).is_some_and(|b| !b.jit))
+            .flat_map(|(id, accounts)| {
+                accounts
+                    .iter()
+                    .filter(|(_, stored)| is_empty_account(&stored.account))
+                    .map(|(address, _)| (*id, *address))
+            })
+            .take(limit.max(0) as usize)
+            .collect())
+    }
+
+    fn snapshot_accounts(&self, id: Uuid) -> Result<Vec<(Pubkey, Account)>, String> {
+        Ok(self.account_matches(id, |_| true))
+    }
+
+    fn stream_accounts(
+        &self,
+        id: Uuid,
+        each: &mut dyn FnMut(Pubkey, Account) -> Result<(), String>,
+    ) -> Result<(), String> {
+        // Copied first so a slow consumer doesn't hold the lock
+        for (address, account) in self.account_matches(id, |_| true) {
+            each(address, account)?;
+        }
+        Ok(())
+    }
+
+    fn get_token_accounts_by_owner(
+        &self,
+        id: Uuid,
+        owner: &Pubkey,
+        token_program: &Pubkey,
+    ) -> Result<Vec<(Pubkey, Account)>, String> {
+        // Same match as the Postgres query, the owner anywhere in the data
+        let owner = owner.to_bytes();
+        Ok(self.account_matches(id, |account| {
+            account.owner == *token_program
+                && account
+                    .data
+                    .windows(owner.len())
+                    .any(|window| window == owner)
+        }))
+    }
+
+    fn get_program_accounts(
+        &self,
+        id: Uuid,
+        program_id: &Pubkey,
+    ) -> Result<Vec<(Pubkey, Account)>, String> {
+        Ok(self.account_matches(id, |account| account.owner == *program_id))
+    }
+
+    fn get_cached_program_accounts(
+        &self,
+        id: Uuid,
+        program_id: &Pubkey,
+        key: &str,
+    ) -> Result<(Option<String>, u64), String> {
+        let state = self.state.read().unwrap();
+        let result = state
+            .program_accounts
+            .get(&(id, *program_id))
+            .and_then(|results| results.get(key))
+            .cloned();
+        let generation = state
+            .program_generations
+            .get(&(id, *program_id))
+            .copied()
+            .unwrap_or_default();
+        Ok((result, generation))
+    }
+
+    fn cache_program_accounts(
+        &self,
+        id: Uuid,
+        program_id: &Pubkey,
+        key: &str,
+        result: &str,
+        generation: u64,
+    ) -> Result<(), String> {
+        let mut state = self.state.write().unwrap();
+        let current = state
+            .program_generations
+            .get(&(id, *program_id))
+            .copied()
+            .unwrap_or_default();
+        if current != generation {
+            return Ok(());
+        }
+        state
+            .program_accounts
+            .entry((id, *program_id))
+            .or_default()
+            .insert(key.to_string(), result.to_string());
+        Ok(())
+    }
+
+    fn get_nonce_accounts(
+        &self,
+        id: Uuid,
+        authority: &Pubkey,
+    ) -> Result<Vec<(Pubkey, Account)>, String> {
+        Ok(self.account_matches(id, |account| nonce_authority(account) == Some(*authority)))
+    }
+
+    fn get_config_accounts(&self, config_id: Uuid) -> Result<Vec<(Pubkey, Account)>, String> {
+        let state = self.state.read().unwrap();
+        Ok(state
+            .config_accounts
+            .get(&config_id)
+            .into_iter()
+            .flatten()
+            .map(|(address, account)| (*address, account.clone()))
+            .collect())
+    }
+
+    fn get_config_account(
+        &self,
+        config_id: Uuid,
+        pubkey: &Pubkey,
+    ) -> Result<Option<Account>, String> {
+        let state = self.state.read().unwrap();
+        Ok(state
+            .config_accounts
+            .get(&config_id)
+            .and_then(|accounts| accounts.get(pubkey))
+            .cloned())
+    }
+
+    fn set_config_account(
+        &self,
+        config_id: Uuid,
+        address: &Pubkey,
+        account: Account,
+    ) -> Result<(), String> {
+        self.set_config_accounts(config_id, vec![(*address, account)])
+    }
+
+    fn set_config_accounts(
+        &self,
+        config_id: Uuid,
+        accounts: Vec<(Pubkey, Account)>,
+    ) -> Result<(), String> {
+        self.state
+            .write()
+            .unwrap()
+            .config_accounts
+            .entry(config_id)
+            .or_default()
+            .extend(accounts);
+        Ok(())
+    }
+
+    fn delete_config_accounts(
+        &self,
+        config_id: Uuid,
+        addresses: &[Pubkey],
+    ) -> Result<usize, String> {
+        let mut state = self.state.write().unwrap();
+        let Some(accounts) = state.config_accounts.get_mut(&config_id) else {
+            return Ok(0);
+        };
+        Ok(addresses
+            .iter()
+            .filter(|address| accounts.remove(address).is_some())
+            .count())
+    }
+
+    fn create_config(
+        &self,
+        team_id: Option<Uuid>,
+        label: String,
+        bundles: Vec<String>,
+    ) -> Result<DBBlockchainConfig, String> {
+        let config = DBBlockchainConfig {
+            id: Uuid::new_v4(),
+            created_at: Utc::now().naive_utc(),
+            label,
+            team_id,
+            bundles,
+        };
+        self.state.write().unwrap().configs.push(config.clone());
+        Ok(config)
+    }
+
+    fn get_config(&self, config_id: Uuid) -> Result<Option<DBBlockchainConfig>, String> {
+        let state = self.state.read().unwrap();
+        Ok(state
+            .configs
+            .iter()
+            .find(|config| config.id == config_id)
+            .cloned())
+    }
+
+    fn get_configs(&self, team_id: Uuid) -> Result<Vec<DBBlockchainConfig>, String> {
+        let state = self.state.read().unwrap();
+        Ok(state
+            .configs
+            .iter()
+            .filter(|config| config.is_visible_to(team_id))
+            .cloned()
+            .collect())
+    }
+
+    fn delete_config(&self, config_id: Uuid) -> Result<(), String> {
+        let mut state = self.state.write().unwrap();
+        state.config_accounts.remove(&config_id);
+        state.configs.retain(|config| config.id != config_id);
+        Ok(())
+    }
+
+    fn set_block(&self, id: Uuid, block: &Block) -> Result<(), String> {
+        {
+            let mut state = self.state.write().unwrap();
+            let blocks = state.blocks.entry(id).or_default();
+            let db_block = DbBlock::from_block(block, id);
+            if blocks.iter().any(|b| b.blockhash == db_block.blockhash) {
+                return Ok(());
+            }
+            blocks.push(db_block);
+        }
+        self.publish_event(
+            id,
+            BusEvent::Block {
+                slot: block.block_height,
+            },
+        );
+        Ok(())
+    }
+
+    fn get_block(&self, id: Uuid, blockhash: &Hash) -> Result<Block, String> {
+        let state = self.state.read().unwrap();
+        state
+            .blocks
+            .get(&id)
+            .into_iter()
+            .flatten()
+            .find(|block| block.blockhash == blockhash.to_bytes())
+            .ok_or_else(|| "Block not found".to_string())
+            .and_then(|block| Ok(block.clone().into_block()?.0))
+    }
+
+    fn get_recent_blocks(&self, id: Uuid, limit: usize) -> Result<Vec<Block>, String> {
+        let state = self.state.read().unwrap();
+        Ok(state
+            .blocks
+            .get(&id)
+            .into_iter()
+            .flat_map(|blocks| blocks.iter().rev())
+            .take(limit)
+            .map(|block| block.clone().into_block().map(|(block, _)| block))
+            .collect::<Result<_, _>>()?)
+    }
+
+    fn get_block_by_height(&self, id: Uuid, height: u64) -> Result<Option<Block>, String> {
+        let state = self.state.read().unwrap();
+        Ok(state
+            .blocks
+            .get(&id)
+            .into_iter()
+            .flatten()
+            .find(|block| block.block_height == height.into())
+            .map(|block| block.clone().into_block().map(|(block, _)| block))
+            .transpose()?)
+    }
+
+    fn get_block_created_at(&self, id: Uuid, height: u64) -> Result<chrono::DateTime<Utc>, String> {
+        let state = self.state.read().unwrap();
+        state
+            .blocks
+            .get(&id)
+            .into_iter()
+            .flatten()
+            .find(|block| block.block_height == height.into())
+            .map(|block| block.created_at.and_utc())
+            .ok_or_else(|| "Block not found".to_string())
+    }
+
+    fn get_latest_block(&self, id: Uuid) -> Result<Block, String> {
+        let state = self.state.read().unwrap();
+        state
+            .blocks
+            .get(&id)
+            .and_then(|blocks| blocks.last())
+            .ok_or_else(|| "Blockchain has no blocks".to_string())
+            .and_then(|block| Ok(block.clone().into_block()?.0))
+    }
+
+    fn get_slot_history(
+        &self,
+        id: Uuid,
+        from_slot: u64,
+        to_slot: u64,
+        limit: i64,
+        max_events: usize,
+    ) -> Result<Vec<GeyserEvent>, String> {
+        let state = self.state.read().unwrap();
+        let transactions = state.transactions.get(&id);
+        let slots: Vec<_> = state
+            .blocks
+            .get(&id)
+            .into_iter()
+            .flatten()
+            .filter(|block| (from_slot..to_slot).contains(&numeric::saturating_u64(&block.slot)))
+            .take(limit.max(0) as usize)
+            .map(|block| {
+                let slot_transactions: Vec<_> = transactions
+                    .into_iter()
+                    .flatten()
+                    .filter(|tx| tx.transaction.slot == block.slot)
+                    .cloned()
+                    .collect();
+                (block, slot_transactions)
+            })
+            .collect();
+        let page = GeyserEvent::slots_in_page(
+            slots.iter().map(|(_, transactions)| transactions.len()),
+            max_events,
+        );
+        Ok(slots
+            .into_iter()
+            .take(page)
+            .flat_map(|(block, transactions)| GeyserEvent::from_slot(block.clone(), transactions))
+            .collect())
+    }
+
+    fn replay_geyser_events(&self, _events: &[GeyserEvent]) -> Result<(), String> {
+        Err("Kafka is disabled".to_string())
+    }
+
+    fn publish_heartbeats(&self, _heartbeats: &[PubSubHeartbeat]) {}
+
+    fn reserve_idempotency_key(
+        &self,
+        team_id: Uuid,
+        key: &str,
+        fingerprint: &str,
+    ) -> Result<Option<IdempotencyRecord>, String> {
+        let mut state = self.state.write().unwrap();
+        let now = Instant::now();
+        let key = (team_id, key.to_string());
+        if let Some((record, expires)) = state.idempotency_keys.get(&key) {
+            if *expires > now {
+                return Ok(Some(record.clone()));
+            }
+        }
+        let record = IdempotencyRecord {
+            fingerprint: fingerprint.to_string(),
+            blockchain: None,
+        };
+        state
+            .idempotency_keys
+            .insert(key, (record, now + IDEMPOTENCY_PENDING_TTL));
+        Ok(None)
+    }
+
+    fn complete_idempotency_key(
+        &self,
+        team_id: Uuid,
+        key: &str,
+        record: &IdempotencyRecord,
+    ) -> Result<(), String> {
+        self.state.write().unwrap().idempotency_keys.insert(
+            (team_id, key.to_string()),
+            (record.clone(), Instant::now() + IDEMPOTENCY_KEY_TTL),
+        );
+        Ok(())
+    }
+
+    fn release_idempotency_key(&self, team_id: Uuid, key: &str) -> Result<(), String> {
+        self.state
+            .write()
+            .unwrap()
+            .idempotency_keys
+            .remove(&(team_id, key.to_string()));
+        Ok(())
+    }
+
+    fn take_daily_transaction(&self, team_id: Uuid, limit: u32) -> Result<bool, String> {
+        let mut state = self.state.write().unwrap();
+        let today = Utc::now().date_naive();
+        state.daily_transactions.retain(|(_, day), _| *day >= today);
+        let count = state
+            .daily_transactions
+            .entry((team_id, today))
+            .or_default();
+        if *count >= limit {
+            return Ok(false);
+        }
+        *count += 1;
+        Ok(true)
+    }
+
+    fn get_blockchain(&self, id: Uuid) -> Result<Blockchain, String> {
+        let state = self.state.read().unwrap();
+        state
+            .blockchains
+            .get(&id)
+            .map(|blockchain| blockchain.clone().to_blockchain())
+            .ok_or_else(|| "Blockchain not found".to_string())
+    }
+
+    fn get_expired_blockchains(&self) -> Result<Vec<Blockchain>, String> {
+        let now = Utc::now().naive_utc();
+        let state = self.state.read().unwrap();
+        Ok(state
+            .blockchains
+            .values()
+            .filter(|blockchain| blockchain.expiry.is_some_and(|expiry| expiry < now))
+            .map(|blockchain| blockchain.clone().to_blockchain())
+            .collect())
+    }
+
+    fn get_blockchains(&self, team_id: Uuid) -> Result<Vec<Blockchain>, String> {
+        let state = self.state.read().unwrap();
+        Ok(state
+            .blockchains
+            .values()
+            .filter(|blockchain| blockchain.team_id == team_id && !blockchain.pooled)
+            .map(|blockchain| blockchain.clone().to_blockchain())
+            .collect())
+    }
+
+    fn get_all_blockchains(
+        &self,
+        filter: &BlockchainFilter,
+    ) -> Result<(Vec<Blockchain>, i64), String> {
+        let state = self.state.read().unwrap();
+        let mut blockchains: Vec<&DbBlockchain> = state
+            .blockchains
+            .values()
+            .filter(|b| {
+                !b.pooled
+                    && filter.team_id.is_none_or(|team_id| b.team_id == team_id)
+                    && filter
+                        .label
+                        .as_ref()
+                        .is_none_or(|label| b.label.as_ref() == Some(label))
+                    && filter
+                        .created_after
+                        .is_none_or(|created_after| b.created_at > created_after)
+                    && filter.expiring_before.is_none_or(|expiring_before| {
+                        b.expiry.is_some_and(|expiry| expiry < expiring_before)
+                    })
+            })
+            .collect();
+        blockchains.sort_by_key(|b| std::cmp::Reverse(b.created_at));
+        let total = blockchains.len() as i64;
+        let limit = filter
+            .limit
+            .map_or(usize::MAX, |limit| limit.max(0) as usize);
+        Ok((
+            blockchains
+                .into_iter()
+                .skip(filter.offset.max(0) as usize)
+                .take(limit)
+                .map(|b| b.clone().to_blockchain())
+                .collect(),
+            total,
+        ))
+    }
+
+    fn claim_pooled_blockchain(
+        &self,
+        team_id: Uuid,
+        config: Option<Uuid>,
+        label: Option<String>,
+        expiry: Option<chrono::NaiveDateTime>,
+    ) -> Result<Option<Blockchain>, String> {
+        let mut state = self.state.write().unwrap();
+        let claimed = state
+            .blockchains
+            .values_mut()
+            .filter(|b| b.pooled && b.team_id == team_id && b.config == config)
+            .min_by_key(|b| b.created_at);
+        Ok(claimed.map(|blockchain| {
+            blockchain.pooled = false;
+            blockchain.label = label;
+            blockchain.expiry = expiry;
+            blockchain.created_at = Utc::now().naive_utc();
+            blockchain.clone().to_blockchain()
+        }))
+    }
+
+    fn count_pooled_blockchains(&self) -> Result<Vec<(Uuid, Option<Uuid>, i64)>, String> {
+        let state = self.state.read().unwrap();
+        let mut counts: HashMap<(Uuid, Option<Uuid>), i64> = HashMap::new();
+        for blockchain in state.blockchains.values().filter(|b| b.pooled) {
+            *counts
+                .entry((blockchain.team_id, blockchain.config))
+                .or_default() += 1;
+        }
+        Ok(counts
+            .into_iter()
+            .map(|((team_id, config), count)| (team_id, config, count))
+            .collect())
+    }
+
+    fn delete_pooled_blockchains(&self, config: Uuid) -> Result<Vec<Uuid>, String> {
+        let mut state = self.state.write().unwrap();
+        let ids: Vec<Uuid> = state
+            .blockchains
+            .values()
+            .filter(|b| b.pooled && b.config == Some(config))
+            .map(|b| b.id)
+            .collect();
+        for id in &ids {
+            state.remove_blockchain(*id);
+        }
+        Ok(ids)
+    }
+
+    fn transfer_blockchain(&self, id: Uuid, team_id: Uuid) -> Result<Blockchain, String> {
+        let mut state = self.state.write().unwrap();
+        let blockchain = state
+            .blockchains
+            .get_mut(&id)
+            .ok_or("Blockchain not found")?;
+        blockchain.team_id = team_id;
+        let blockchain = blockchain.clone().to_blockchain();
+        state
+            .webhooks
+            .retain(|webhook| webhook.blockchain != id || webhook.team_id == team_id);
+        Ok(blockchain)
+    }
+
+    fn get_blockchains_by_config(
+        &self,
+        team_id: Uuid,
+        config_id: Uuid,
+    ) -> Result<Vec<Blockchain>, String> {
+        let state = self.state.read().unwrap();
+        Ok(state
+            .blockchains
+            .values()
+            .filter(|b| b.team_id == team_id && b.config == Some(config_id) && !b.pooled)
+            .map(|b| b.clone().to_blockchain())
+            .collect())
+    }
+
+    fn delete_blockchain(&self, id: Uuid) -> Result<(), String> {
+        self.state.write().unwrap().remove_blockchain(id);
+        Ok(())
+    }
+
+    fn set_blockchain(&self, blockchain: &Blockchain) -> Result<Uuid, String> {
+        let db_blockchain = DbBlockchain {
+            id: blockchain.id,
+            created_at: blockchain.created_at,
+            airdrop_keypair: blockchain.airdrop_keypair.to_bytes().to_vec(),
+            team_id: blockchain.team_id,
+            label: blockchain.label.clone(),
+            expiry: blockchain.expiry,
+            jit: blockchain.jit,
+            config: blockchain.config,
+            pooled: blockchain.pooled,
+            log_bytes_limit: blockchain.log_bytes_limit,
+            read_only: blockchain.read_only,
+            sponsor_fees: blockchain.sponsor_fees,
+            parity_check: blockchain.parity_check,
+            accounts_status: blockchain.accounts_status.as_str().to_string(),
+        };
+        let mut state = self.state.write().unwrap();
+        if state.blockchains.contains_key(&blockchain.id) {
+            return Err("Blockchain already exists".to_string());
+        }
+        state.blockchains.insert(blockchain.id, db_blockchain);
+        Ok(blockchain.id)
+    }
+
+    fn update_blockchain(
+        &self,
+        id: Uuid,
+        update: &DbBlockchainUpdate,
+    ) -> Result<Blockchain, String> {
+        let mut state = self.state.write().unwrap();
+        let blockchain = state
+            .blockchains
+            .get_mut(&id)
+            .ok_or("Blockchain not found")?;
+        if let Some(label) = &update.label {
+            blockchain.label = Some(label.clone());
+        }
+        if let Some(expiry) = update.expiry {
+            blockchain.expiry = Some(expiry);
+        }
+        if let Some(limit) = update.log_bytes_limit {
+            blockchain.log_bytes_limit = limit;
+        }
+        if let Some(read_only) = update.read_only {
+            blockchain.read_only = read_only;
+        }
+        if let Some(sponsor_fees) = update.sponsor_fees {
+            blockchain.sponsor_fees = sponsor_fees;
+        }
+        if let Some(parity_check) = update.parity_check {
+            blockchain.parity_check = parity_check;
+        }
+        if let Some(accounts_status) = update.accounts_status {
+            blockchain.accounts_status = accounts_status.as_str().to_string();
+        }
+        Ok(blockchain.clone().to_blockchain())
+    }
+
+    fn save_transaction(&self, id: Uuid, tx: &TransactionMetadata) -> Result<(), String> {
+        let tx_object = DbTransactionObject::from_transaction(id, tx);
+        let accounts = tx_object
+            .account_keys
+            .iter()
+            .map(|k| k.account.clone())
+            .collect();
+        let signature = tx.signature.to_string();
+        let mut state = self.state.write().unwrap();
+        state
+            .pending_transactions
+            .retain(|pending| pending.blockchain != id || pending.signature != signature);
+        state.transactions.entry(id).or_default().push(tx_object);
+        drop(state);
+        self.publish_event(
+            id,
+            BusEvent::Transaction {
+                signature: tx.signature.to_string(),
+                accounts,
+            },
+        );
+        Ok(())
+    }
+
+    fn get_transaction(
+        &self,
+        id: Uuid,
+        signature: &Signature,
+    ) -> Result<
+        Option<(
+            VersionedTransaction,
+            u64,
+            TransactionMeta,
+            Option<TransactionError>,
+            chrono::NaiveDateTime,
+        )>,
+        String,
+    > {
+        let signature = signature.to_string();
+        let state = self.state.read().unwrap();
+        Ok(state
+            .transactions
+            .get(&id)
+            .into_iter()
+            .flatten()
+            .find(|tx| tx.transaction.signature == signature)
+            .map(|tx| tx.clone().into_transaction()))
+    }
+
+    fn get_transactions_for_address(
+        &self,
+        id: Uuid,
+        address: &Pubkey,
+        limit: Option<usize>,
+    ) -> Result<Vec<DbTransaction>, String> {
+        let address = address.to_string();
+        Ok(self
+            .transactions_matching(id, |tx| references(tx, &address))
+            .into_iter()
+            .take(limit.unwrap_or(1000))
+            .map(|tx| tx.transaction)
+            .collect())
+    }
+
+    fn get_transactions_for_address_created_at(
+        &self,
+        id: Uuid,
+        address: &Pubkey,
+        start: chrono::NaiveDateTime,
+        end: chrono::NaiveDateTime,
+    ) -> Result<Vec<DbTransaction>, String> {
+        let address = address.to_string();
+        Ok(self
+            .transactions_matching(id, |tx| {
+                let created_at = tx.transaction.created_at;
+                references(tx, &address) && created_at >= start && created_at <= end
+            })
+            .into_iter()
+            .map(|tx| tx.transaction)
+            .collect())
+    }
+
+    fn get_program_logs(
+        &self,
+        id: Uuid,
+        program_id: &Pubkey,
+        start: chrono::NaiveDateTime,
+        end: chrono::NaiveDateTime,
+        limit: i64,
+    ) -> Result<Vec<DbTransactionLogMessage>, String> {
+        let program_id = program_id.to_string();
+        Ok(self
+            .transactions_matching(id, |tx| {
+                tx.transaction.created_at >= start && tx.transaction.created_at <= end
+            })
+            .into_iter()
+            .flat_map(|tx| tx.log_messages)
+            .filter(|log| log.program_id.as_ref() == Some(&program_id))
+            .take(limit.max(0) as usize)
+            .collect())
+    }
+
+    fn get_token_balance_changes(
+        &self,
+        id: Uuid,
+        owner: &Pubkey,
+        before: Option<&Signature>,
+        limit: i64,
+    ) -> Result<Vec<TokenBalanceChange>, String> {
+        let owner = owner.to_string();
+        let before = match before {
+            Some(before) => {
+                let before = before.to_string();
+                match self
+                    .transactions_matching(id, |tx| tx.transaction.signature == before)
+                    .first()
+                {
+                    Some(tx) => Some(tx.transaction.created_at),
+                    None => return Ok(vec![]),
+                }
+            }
+            None => None,
+        };
+        let mut matches = self.transactions_matching(id, |tx| {
+            tx.token_balances.iter().any(|b| b.owner == owner)
+                && before.is_none_or(|before| tx.transaction.created_at < before)
+        });
+        matches.reverse();
+        Ok(matches
+            .iter()
+            .take(limit.max(0) as usize)
+            .flat_map(|tx| {
+                TokenBalanceChange::from_balances(
+                    &tx.transaction,
+                    &tx.account_keys,
+                    &tx.token_balances,
+                    &owner,
+                )
+            })
+            .collect())
+    }
+
+    fn get_transaction_count(&self, id: Uuid) -> Result<u64, String> {
+        let state = self.state.read().unwrap();
+        Ok(state
+            .transactions
+            .get(&id)
+            .map_or(0, |txs| txs.len() as u64))
+    }
+
+    fn get_slot_transaction_versions(&self, id: Uuid, slot: u64) -> Result<Vec<String>, String> {
+        let state = self.state.read().unwrap();
+        let mut versions: Vec<String> = state
+            .transactions
+            .get(&id)
+            .into_iter()
+            .flatten()
+            .filter(|tx| tx.transaction.slot == slot.into())
+            .map(|tx| tx.transaction.version.clone())
+            .collect();
+        versions.sort();
+        versions.dedup();
+        Ok(versions)
+    }
+
+    fn get_recent_transactions(
+        &self,
+        id: Uuid,
+        limit: usize,
+    ) -> Result<Vec<TransactionSummary>, String> {
+        let state = self.state.read().unwrap();
+        Ok(state
+            .transactions
+            .get(&id)
+            .into_iter()
+            .flat_map(|txs| txs.iter().rev())
+            .take(limit)
+            .map(TransactionSummary::from_object)
+            .collect())
+    }
+
+    fn search_transactions(
+        &self,
+        id: Uuid,
+        filter: &TransactionFilter,
+    ) -> Result<(Vec<DbTransactionObject>, i64), String> {
+        let mut matches = self.transactions_matching(id, |tx| {
+            filter
+                .address
+                .as_ref()
+                .is_none_or(|address| references(tx, address))
+                && filter.program_id.as_ref().is_none_or(|program_id| {
+                    tx.instructions
+                        .iter()
+                        .any(|ix| &ix.program_id == program_id)
+                })
+                && filter
+                    .failed
+                    .is_none_or(|failed| tx.meta.err.is_some() == failed)
+                && filter
+                    .start
+                    .is_none_or(|start| tx.transaction.created_at >= start)
+                && filter
+                    .end
+                    .is_none_or(|end| tx.transaction.created_at <= end)
+                && filter
+                    .signature_prefix
+                    .as_ref()
+                    .is_none_or(|prefix| tx.transaction.signature.starts_with(prefix))
+                && filter
+                    .fee_payer
+                    .as_ref()
+                    .is_none_or(|fee_payer| tx.transaction.fee_payer.as_ref() == Some(fee_payer))
+                && filter
+                    .is_vote
+                    .is_none_or(|is_vote| tx.transaction.is_vote == is_vote)
+                && filter.min_compute_unit_price.is_none_or(|min| {
+                    tx.transaction
+                        .compute_unit_price
+                        .as_ref()
+                        .is_some_and(|price| *price >= BigDecimal::from(min))
+                })
+        });
+        matches.reverse();
+        let total = matches.len() as i64;
+        Ok((
+            matches
+                .into_iter()
+                .skip(filter.offset.max(0) as usize)
+                .take(filter.limit.max(0) as usize)
+                .collect(),
+            total,
+        ))
+    }
+
+    fn set_program_idl(&self, id: Uuid, program_id: &Pubkey, idl: String) -> Result<(), String> {
+        self.state
+            .write()
+            .unwrap()
+            .idls
+            .insert((id, *program_id), idl);
+        Ok(())
+    }
+
+    fn get_program_idl(&self, id: Uuid, program_id: &Pubkey) -> Result<Option<String>, String> {
+        let state = self.state.read().unwrap();
+        Ok(state.idls.get(&(id, *program_id)).cloned())
+    }
+
+    fn set_sysvar_overrides(&self, id: Uuid, overrides: &SysvarOverrides) -> Result<(), String> {
+        self.state
+            .write()
+            .unwrap()
+            .sysvars
+            .insert(id, overrides.clone());
+        Ok(())
+    }
+
+    fn get_sysvar_overrides(&self, id: Uuid) -> Result<Option<SysvarOverrides>, String> {
+        let state = self.state.read().unwrap();
+        Ok(state.sysvars.get(&id).cloned())
+    }
+
+    fn save_pending_transaction(&self, tx: &DbPendingTransaction) -> Result<(), String> {
+        let mut state = self.state.write().unwrap();
+        let exists = state.pending_transactions.iter().any(|pending| {
+            pending.blockchain == tx.blockchain && pending.signature == tx.signature
+        });
+        if !exists {
+            state.pending_transactions.push(tx.clone());
+        }
+        Ok(())
+    }
+
+    fn delete_pending_transaction(&self, id: Uuid, signature: &str) -> Result<(), String> {
+        self.state
+            .write()
+            .unwrap()
+            .pending_transactions
+            .retain(|pending| pending.blockchain != id || pending.signature != signature);
+        Ok(())
+    }
+
+    fn get_pending_transactions(
+        &self,
+        instance: Uuid,
+    ) -> Result<Vec<DbPendingTransaction>, String> {
+        let state = self.state.read().unwrap();
+        Ok(state
+            .pending_transactions
+            .iter()
+            .filter(|pending| pending.instance == Some(instance))
+            .cloned()
+            .collect())
+    }
+
+    fn get_storage_usage(&self, id: Uuid) -> Result<StorageUsage, String> {
+        let state = self.state.read().unwrap();
+        let accounts = state.accounts.get(&id);
+        Ok(StorageUsage {
+            accounts: accounts.map_or(0, |accounts| accounts.len() as u64),
+            account_data_bytes: accounts.map_or(0, |accounts| {
+                accounts
+                    .values()
+                    .map(|stored| stored.account.data.len() as u64)
+                    .sum()
+            }),
+            transactions: state.transactions.get(&id).map_or(0, |t| t.len() as u64),
+            blocks: state.blocks.get(&id).map_or(0, |b| b.len() as u64),
+            cache_bytes: 0,
+        })
+    }
+
+    fn subscribe_events(&self, id: Uuid) -> Result<Option<mpsc::Receiver<BusEvent>>, String> {
+        let mut events = self.local_events.subscribe();
+        let (tx, rx) = mpsc::channel(LOCAL_EVENTS_CAPACITY);
+        rt::spawn(async move {
+            loop {
+                match events.recv().await {
+                    Ok((blockchain, event)) if blockchain == id => {
+                        if tx.send(event).await.is_err() {
+                            break;
+                        }
+                    }
+                    Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => {}
+                    Err(broadcast::error::RecvError::Closed) => break,
+                }
+            }
+        });
+        Ok(Some(rx))
+    }
+
+    fn subscribe_local_events(&self) -> broadcast::Receiver<(Uuid, BusEvent)> {
+        self.local_events.subscribe()
+    }
+
+    fn readiness(&self, _timeout: Duration) -> Vec<ComponentStatus> {
+        vec![ComponentStatus::new("memory", Ok(()))]
+    }
+
+    async fn flush(&self, _timeout: Duration) {}
+
+    fn acquire_lease(&self, id: Uuid, holder: Uuid, ttl: Duration) -> Result<Uuid, String> {
+        let mut state = self.state.write().unwrap();
+        let now = Instant::now();
+        match state.leases.get(&id) {
+            Some((current, expires)) if *current != holder && *expires > now => Ok(*current),
+            _ => {
+                state.leases.insert(id, (holder, now + ttl));
+                Ok(holder)
+            }
+        }
+    }
+
+    fn release_lease(&self, id: Uuid, holder: Uuid) -> Result<bool, String> {
+        let mut state = self.state.write().unwrap();
+        let held = matches!(state.leases.get(&id), Some((current, _)) if *current == holder);
+        if !held
+            || state
+                .forwarded
+                .get(&id)
+                .is_some_and(|work| !work.is_empty())
+        {
+            return Ok(false);
+        }
+        state.leases.remove(&id);
+        Ok(true)
+    }
+
+    fn forward(&self, id: Uuid, work: &Forwarded) -> Result<(), String> {
+        let mut state = self.state.write().unwrap();
+        state
+            .forwarded
+            .entry(id)
+            .or_default()
+            .push_back(work.clone());
+        Ok(())
+    }
+
+    fn take_forwarded(&self, ids: &[Uuid], limit: usize) -> Result<Vec<(Uuid, Forwarded)>, String> {
+        let mut state = self.state.write().unwrap();
+        let mut taken = vec![];
+        for id in ids {
+            if let Some(work) = state.forwarded.get_mut(id) {
+                let count = work.len().min(limit);
+                taken.extend(work.drain(..count).map(|work| (*id, work)));
+            }
+        }
+        Ok(taken)
+    }
+
+    fn return_forwarded(&self, id: Uuid, work: &[Forwarded]) -> Result<(), String> {
+        let mut state = self.state.write().unwrap();
+        let forwarded = state.forwarded.entry(id).or_default();
+        for work in work.iter().rev() {
+            forwarded.push_front(work.clone());
+        }
+        Ok(())
+    }
+
+    fn create_webhook(&self, webhook: &DbWebhook) -> Result<(), String> {
+        self.state.write().unwrap().webhooks.push(webhook.clone());
+        Ok(())
+    }
+
+    fn get_webhooks(&self, blockchain: Uuid) -> Result<Vec<DbWebhook>, String> {
+        let state = self.state.read().unwrap();
+        Ok(state
+            .webhooks
+            .iter()
+            .filter(|webhook| webhook.blockchain == blockchain)
+            .cloned()
+            .collect())
+    }
+
+    fn get_webhooks_for_event(&self, event: &str) -> Result<Vec<DbWebhook>, String> {
+        let state = self.state.read().unwrap();
+        Ok(state
+            .webhooks
+            .iter()
+            .filter(|webhook| webhook.wants(event))
+            .cloned()
+            .collect())
+    }
+
+    fn delete_webhook(&self, blockchain: Uuid, id: Uuid) -> Result<bool, String> {
+        let mut state = self.state.write().unwrap();
+        let before = state.webhooks.len();
+        state
+            .webhooks
+            .retain(|webhook| webhook.id != id || webhook.blockchain != blockchain);
+        Ok(state.webhooks.len() < before)
+    }
+
+    fn create_top_up(&self, top_up: &DbTopUp) -> Result<(), String> {
+        self.state.write().unwrap().top_ups.push(top_up.clone());
+        Ok(())
+    }
+
+    fn get_top_ups(&self, blockchain: Uuid) -> Result<Vec<DbTopUp>, String> {
+        let state = self.state.read().unwrap();
+        Ok(state
+            .top_ups
+            .iter()
+            .filter(|top_up| top_up.blockchain == blockchain)
+            .cloned()
+            .collect())
+    }
+
+    fn get_all_top_ups(&self) -> Result<Vec<DbTopUp>, String> {
+        Ok(self.state.read().unwrap().top_ups.clone())
+    }
+
+    fn claim_top_up(
+        &self,
+        id: Uuid,
+        last_run: Option<chrono::NaiveDateTime>,
+        now: chrono::NaiveDateTime,
+    ) -> Result<bool, String> {
+        let mut state = self.state.write().unwrap();
+        match state
+            .top_ups
+            .iter_mut()
+            .find(|top_up| top_up.id == id && top_up.last_run == last_run)
+        {
+            Some(top_up) => {
+                top_up.last_run = Some(now);
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    fn delete_top_up(&self, blockchain: Uuid, id: Uuid) -> Result<bool, String> {
+        let mut state = self.state.write().unwrap();
+        let before = state.top_ups.len();
+        state
+            .top_ups
+            .retain(|top_up| top_up.id != id || top_up.blockchain != blockchain);
+        Ok(state.top_ups.len() < before)
+    }
+
+    fn create_audit_log(&self, log: &DbAuditLog) -> Result<(), String> {
+        self.state.write().unwrap().audit_logs.push(log.clone());
+        Ok(())
+    }
+
+    fn get_audit_logs(
+        &self,
+        team_id: Uuid,
+        filter: &AuditLogFilter,
+    ) -> Result<(Vec<DbAuditLog>, i64), String> {
+        let state = self.state.read().unwrap();
+        let logs: Vec<&DbAuditLog> = state
+            .audit_logs
+            .iter()
+            .rev()
+            .filter(|log| {
+                log.team_id == team_id
+                    && filter
+                        .blockchain
+                        .is_none_or(|blockchain| log.blockchain == Some(blockchain))
+                    && filter
+                        .action
+                        .as_ref()
+                        .is_none_or(|action| &log.action == action)
+                    && (filter.actions.is_empty() || filter.actions.contains(&log.action))
+                    && filter.since.is_none_or(|since| log.created_at >= since)
+            })
+            .collect();
+        let total = logs.len() as i64;
+        Ok((
+            logs.into_iter()
+                .skip(filter.offset.max(0) as usize)
+                .take(filter.limit.max(0) as usize)
+                .cloned()
+                .collect(),
+            total,
+        ))
+    }
+}
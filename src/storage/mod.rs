@@ -1,48 +1,128 @@
-use accounts::{DbAccount, DbConfigAccount};
-use actix_web::rt;
-use bigdecimal::{BigDecimal, ToPrimitive};
-use blocks::{DbBlock, DbBlockchain};
+use account_writes::{AccountWrites, WriteTicket};
+use accounts::{
+    nonce_authority, AccountListFilter, AccountSummary, DbAccount, DbConfigAccount,
+    NONCE_AUTHORITY_OFFSET,
+};
+use audit::{AuditLogFilter, DbAuditLog};
+use bigdecimal::BigDecimal;
+use blocks::{BlockchainFilter, DBBlockchainConfig, DbBlock, DbBlockchain, DbBlockchainUpdate};
 use cache::Cache;
 use chrono::Utc;
 use diesel::dsl::sql;
 use diesel::pg::PgConnection;
 use diesel::prelude::*;
 use diesel::r2d2::{self, ConnectionManager};
-use diesel::sql_types::{Bool, Text};
+use diesel::sql_types::{Bool, Integer, Text};
 use diesel::upsert::excluded;
+use events::{BusEvent, EventBus};
+use health::{ComponentStatus, ExistingTable, REQUIRED_TABLES};
 use hex::encode;
-use pubsub::Pubsub;
+use idempotency::{IdempotencyRecord, IdempotencyStore};
+use idls::DbProgramIdl;
+use leases::{Forwarded, LeaseStore};
+use numeric::StorageError;
+use pending::PendingWrites;
+use pubsub::{GeyserEvent, PubSubHeartbeat, Pubsub};
 use rpc::Rpc;
+use std::collections::HashMap;
 use std::str::FromStr;
+use std::time::Duration;
+use sysvars::DbBlockchainSysvars;
 
-use solana_sdk::instruction::Instruction;
+use actix_web::rt::time;
+use quotas::QuotaStore;
 use solana_sdk::transaction::TransactionError;
 use solana_sdk::{
-    account::Account, hash::Hash, pubkey::Pubkey, signature::Signature, transaction::Transaction,
+    account::Account, hash::Hash, nonce::State as NonceState, pubkey::Pubkey, signature::Signature,
+    system_program, transaction::VersionedTransaction,
 };
-use teams::Team;
+use teams::{ApiKey, Team, TeamQuotas};
+use tokio::sync::{broadcast, mpsc};
+use top_ups::DbTopUp;
 use transactions::{
-    DBTransactionTokenBalance, DbTransaction, DbTransactionAccountKey, DbTransactionInstruction,
-    DbTransactionLogMessage, DbTransactionMeta, DbTransactionObject, DbTransactionSignature,
+    DBTransactionTokenBalance, DbPendingTransaction, DbTransaction, DbTransactionAccountKey,
+    DbTransactionInstruction, DbTransactionLogMessage, DbTransactionMeta, DbTransactionObject,
+    DbTransactionSignature, TokenBalanceChange, TransactionFilter, TransactionSummary,
 };
+use usage::StorageUsage;
 use uuid::Uuid;
+use webhooks::DbWebhook;
 
+pub mod account_writes;
 pub mod accounts;
+pub mod audit;
 pub mod blocks;
 pub mod cache;
+pub mod events;
+pub mod health;
+pub mod idempotency;
+pub mod idls;
+pub mod jit;
+pub mod leases;
+pub mod memory;
+pub mod numeric;
+pub mod pending;
 pub mod pubsub;
+pub mod quotas;
 pub mod rpc;
+pub mod snapshot;
+pub mod sysvars;
 pub mod teams;
+pub mod top_ups;
+pub mod trace;
 pub mod transactions;
+pub mod usage;
+pub mod webhooks;
 
 use crate::engine::blocks::Blockchain;
+use crate::engine::sysvars::SysvarOverrides;
 use crate::engine::transactions::TransactionMeta;
 use crate::engine::{blocks::Block, transactions::TransactionMetadata};
+use crate::settings::PoolSettings;
 
-pub trait Storage {
+pub trait Storage: Send + Sync {
     fn get_team_from_api_key(&self, api_key: Uuid) -> Result<Team, String>;
+    fn get_team(&self, id: Uuid) -> Result<Option<Team>, String>;
+    /// Replaces a team's quotas, returns None if the team doesn't exist.
+    fn set_team_quotas(&self, id: Uuid, quotas: &TeamQuotas) -> Result<Option<Team>, String>;
+    fn set_team_auto_pause(&self, id: Uuid, hours: Option<i32>) -> Result<Option<Team>, String>;
+    fn set_team_rpc_write_guard(&self, id: Uuid, enabled: bool) -> Result<Option<Team>, String>;
+    /// Resolves an unrevoked API key together with its team.
+    fn get_api_key_team(&self, api_key: Uuid) -> Result<(ApiKey, Team), String>;
+    fn create_api_key(&self, api_key: &ApiKey) -> Result<(), String>;
+    fn get_api_keys(&self, team_id: Uuid) -> Result<Vec<ApiKey>, String>;
+    fn set_api_key_label(
+        &self,
+        team_id: Uuid,
+        api_key: Uuid,
+        label: String,
+    ) -> Result<bool, String>;
+    fn revoke_api_key(&self, team_id: Uuid, api_key: Uuid) -> Result<bool, String>;
+    /// Claims an `Idempotency-Key` for a request, or returns what it was first used for.
+    fn reserve_idempotency_key(
+        &self,
+        team_id: Uuid,
+        key: &str,
+        fingerprint: &str,
+    ) -> Result<Option<IdempotencyRecord>, String>;
+    fn complete_idempotency_key(
+        &self,
+        team_id: Uuid,
+        key: &str,
+        record: &IdempotencyRecord,
+    ) -> Result<(), String>;
+    /// Frees a reserved key whose request failed, so a retry runs again.
+    fn release_idempotency_key(&self, team_id: Uuid, key: &str) -> Result<(), String>;
+    /// Counts a transaction against the team's quota for the current UTC day, false
+    /// once `limit` were counted.
+    fn take_daily_transaction(&self, team_id: Uuid, limit: u32) -> Result<bool, String>;
 
     fn get_account(&self, id: Uuid, address: &Pubkey) -> Result<Option<Account>, String>;
+    /// Fetches accounts from the upstream RPC, bypassing the blockchain's own state.
+    fn get_remote_accounts(
+        &self,
+        addresses: &[Pubkey],
+    ) -> impl std::future::Future<Output = Result<Vec<Option<Account>>, String>> + Send;
     fn get_account_jit(
         &self,
         id: Uuid,
@@ -61,6 +141,11 @@ pub trait Storage {
         jit: bool,
     ) -> impl std::future::Future<Output = Result<Vec<Option<Account>>, String>> + Send;
     fn get_largest_accounts(&self, id: Uuid, limit: usize) -> Result<Vec<(Pubkey, u64)>, String>;
+    fn list_accounts(
+        &self,
+        id: Uuid,
+        filter: &AccountListFilter,
+    ) -> Result<(Vec<AccountSummary>, i64), String>;
     fn set_account(
         &self,
         id: Uuid,
@@ -71,6 +156,33 @@ pub trait Storage {
     fn set_account_lamports(&self, id: Uuid, address: &Pubkey, lamports: u64)
         -> Result<(), String>;
     fn set_accounts(&self, id: Uuid, accounts: Vec<(Pubkey, Account)>) -> Result<(), String>;
+    /// Sets or, with `None`, clears an account's label. Returns false if the account doesn't exist.
+    fn set_account_label(
+        &self,
+        id: Uuid,
+        address: &Pubkey,
+        label: Option<String>,
+    ) -> Result<bool, String>;
+    /// Addresses of the accounts labeled exactly one of `labels`.
+    fn get_labeled_accounts(&self, id: Uuid, labels: &[String]) -> Result<Vec<Pubkey>, String>;
+    /// Removes an account from the cache and the database, returns false if it didn't exist.
+    fn delete_account(&self, id: Uuid, address: &Pubkey) -> Result<bool, String>;
+    /// `delete_account` for several accounts, returns how many existed.
+    fn delete_accounts(&self, id: Uuid, addresses: &[Pubkey]) -> Result<usize, String>;
+    /// `delete_accounts` for the ones still without lamports and data, checked in
+    /// the same step as each delete so an account funded since it was found is kept.
+    fn delete_empty_accounts(&self, id: Uuid, addresses: &[Pubkey]) -> Result<usize, String>;
+    /// Up to `limit` zero-lamport accounts without data, by blockchain. JIT blockchains
+    /// are left out since a deleted account would be fetched upstream again.
+    fn get_empty_accounts(&self, limit: i64) -> Result<Vec<(Uuid, Pubkey)>, String>;
+    /// Point-in-time copy of every account of a blockchain.
+    fn snapshot_accounts(&self, id: Uuid) -> Result<Vec<(Pubkey, Account)>, String>;
+    /// `snapshot_accounts` handed to `each` one at a time, without loading them all.
+    fn stream_accounts(
+        &self,
+        id: Uuid,
+        each: &mut dyn FnMut(Pubkey, Account) -> Result<(), String>,
+    ) -> Result<(), String>;
     fn get_token_accounts_by_owner(
         &self,
         id: Uuid,
@@ -82,6 +194,31 @@ pub trait Storage {
         id: Uuid,
         program_id: &Pubkey,
     ) -> Result<Vec<(Pubkey, Account)>, String>;
+    /// A getProgramAccounts result cached under `key`, until an account the
+    /// program owns, or owned before, is written or deleted. Also returns the
+    /// program's generation, which each of those bumps.
+    fn get_cached_program_accounts(
+        &self,
+        id: Uuid,
+        program_id: &Pubkey,
+        key: &str,
+    ) -> Result<(Option<String>, u64), String>;
+    /// Caches a result computed after reading `generation`, unless the program's
+    /// results were dropped since, so a slow read can't cache a stale result.
+    fn cache_program_accounts(
+        &self,
+        id: Uuid,
+        program_id: &Pubkey,
+        key: &str,
+        result: &str,
+        generation: u64,
+    ) -> Result<(), String>;
+    /// Initialized system nonce accounts whose authority is `authority`.
+    fn get_nonce_accounts(
+        &self,
+        id: Uuid,
+        authority: &Pubkey,
+    ) -> Result<Vec<(Pubkey, Account)>, String>;
     fn get_config_accounts(&self, config_id: Uuid) -> Result<Vec<(Pubkey, Account)>, String>;
     fn get_config_account(
         &self,
@@ -94,6 +231,25 @@ pub trait Storage {
         address: &Pubkey,
         account: Account,
     ) -> Result<(), String>;
+    fn set_config_accounts(
+        &self,
+        config_id: Uuid,
+        accounts: Vec<(Pubkey, Account)>,
+    ) -> Result<(), String>;
+    fn delete_config_accounts(
+        &self,
+        config_id: Uuid,
+        addresses: &[Pubkey],
+    ) -> Result<usize, String>;
+    fn create_config(
+        &self,
+        team_id: Option<Uuid>,
+        label: String,
+        bundles: Vec<String>,
+    ) -> Result<DBBlockchainConfig, String>;
+    fn get_config(&self, config_id: Uuid) -> Result<Option<DBBlockchainConfig>, String>;
+    fn get_configs(&self, team_id: Uuid) -> Result<Vec<DBBlockchainConfig>, String>;
+    fn delete_config(&self, config_id: Uuid) -> Result<(), String>;
 
     fn set_block(&self, id: Uuid, block: &Block) -> Result<(), String>;
     fn get_block(&self, id: Uuid, blockhash: &Hash) -> Result<Block, String>;
@@ -101,12 +257,58 @@ pub trait Storage {
     fn get_block_by_height(&self, id: Uuid, height: u64) -> Result<Option<Block>, String>;
     fn get_block_created_at(&self, id: Uuid, height: u64) -> Result<chrono::DateTime<Utc>, String>;
     fn get_latest_block(&self, id: Uuid) -> Result<Block, String>;
+    /// Geyser events of up to `limit` slots from `from_slot` until `to_slot`
+    /// (exclusive), oldest first: each slot's block, then its transactions. Slots
+    /// past `max_events` events are left for the next page, see
+    /// `GeyserEvent::slots_in_page`.
+    fn get_slot_history(
+        &self,
+        id: Uuid,
+        from_slot: u64,
+        to_slot: u64,
+        limit: i64,
+        max_events: usize,
+    ) -> Result<Vec<GeyserEvent>, String>;
+    /// Publishes replayed events to the geyser topic, fails when Kafka is disabled.
+    fn replay_geyser_events(&self, events: &[GeyserEvent]) -> Result<(), String>;
+    fn publish_heartbeats(&self, heartbeats: &[PubSubHeartbeat]);
 
     fn get_blockchain(&self, id: Uuid) -> Result<Blockchain, String>;
     fn get_expired_blockchains(&self) -> Result<Vec<Blockchain>, String>;
     fn get_blockchains(&self, team_id: Uuid) -> Result<Vec<Blockchain>, String>;
+    /// Blockchains matching the filter across teams, newest first, with the total count.
+    fn get_all_blockchains(
+        &self,
+        filter: &BlockchainFilter,
+    ) -> Result<(Vec<Blockchain>, i64), String>;
+    /// Hands a warm pool blockchain of the team and config over to the caller, `None` when
+    /// the pool is empty. Safe to race from several instances.
+    fn claim_pooled_blockchain(
+        &self,
+        team_id: Uuid,
+        config: Option<Uuid>,
+        label: Option<String>,
+        expiry: Option<chrono::NaiveDateTime>,
+    ) -> Result<Option<Blockchain>, String>;
+    /// Warm pool blockchains per team and config.
+    fn count_pooled_blockchains(&self) -> Result<Vec<(Uuid, Option<Uuid>, i64)>, String>;
+    /// Deletes the warm pool blockchains built from a config, so they're rebuilt with its
+    /// current accounts. Returns their ids.
+    fn delete_pooled_blockchains(&self, config: Uuid) -> Result<Vec<Uuid>, String>;
+    /// Moves a blockchain to another team, dropping webhooks registered by the old one.
+    fn transfer_blockchain(&self, id: Uuid, team_id: Uuid) -> Result<Blockchain, String>;
+    fn get_blockchains_by_config(
+        &self,
+        team_id: Uuid,
+        config_id: Uuid,
+    ) -> Result<Vec<Blockchain>, String>;
     fn delete_blockchain(&self, id: Uuid) -> Result<(), String>;
     fn set_blockchain(&self, blockchain: &Blockchain) -> Result<Uuid, String>;
+    fn update_blockchain(
+        &self,
+        id: Uuid,
+        update: &DbBlockchainUpdate,
+    ) -> Result<Blockchain, String>;
     fn save_transaction(&self, id: Uuid, tx: &TransactionMetadata) -> Result<(), String>;
     fn get_transaction(
         &self,
@@ -114,7 +316,7 @@ pub trait Storage {
         signature: &Signature,
     ) -> Result<
         Option<(
-            Transaction,
+            VersionedTransaction,
             u64,
             TransactionMeta,
             Option<TransactionError>,
@@ -135,33 +337,243 @@ pub trait Storage {
         start: chrono::NaiveDateTime,
         end: chrono::NaiveDateTime,
     ) -> Result<Vec<DbTransaction>, String>;
+    /// Log lines emitted by the program in transactions saved between `start` and
+    /// `end`, oldest first and in log order within a transaction.
+    fn get_program_logs(
+        &self,
+        id: Uuid,
+        program_id: &Pubkey,
+        start: chrono::NaiveDateTime,
+        end: chrono::NaiveDateTime,
+        limit: i64,
+    ) -> Result<Vec<DbTransactionLogMessage>, String>;
+    /// Balance changes of the owner's token accounts, newest first, over the `limit`
+    /// latest transactions that touched them before the `before` signature.
+    fn get_token_balance_changes(
+        &self,
+        id: Uuid,
+        owner: &Pubkey,
+        before: Option<&Signature>,
+        limit: i64,
+    ) -> Result<Vec<TokenBalanceChange>, String>;
     fn get_transaction_count(&self, id: Uuid) -> Result<u64, String>;
+    /// Distinct versions of the transactions saved in a slot, e.g. "legacy" or "v0".
+    fn get_slot_transaction_versions(&self, id: Uuid, slot: u64) -> Result<Vec<String>, String>;
+    /// Newest transactions first, served from the cache.
+    fn get_recent_transactions(
+        &self,
+        id: Uuid,
+        limit: usize,
+    ) -> Result<Vec<TransactionSummary>, String>;
+    /// Transactions matching the filter, newest first with their meta, account keys,
+    /// instructions, logs, signatures and token balances, plus the total match count.
+    fn search_transactions(
+        &self,
+        id: Uuid,
+        filter: &TransactionFilter,
+    ) -> Result<(Vec<DbTransactionObject>, i64), String>;
+    fn set_program_idl(&self, id: Uuid, program_id: &Pubkey, idl: String) -> Result<(), String>;
+    fn get_program_idl(&self, id: Uuid, program_id: &Pubkey) -> Result<Option<String>, String>;
+    fn set_sysvar_overrides(&self, id: Uuid, overrides: &SysvarOverrides) -> Result<(), String>;
+    fn get_sysvar_overrides(&self, id: Uuid) -> Result<Option<SysvarOverrides>, String>;
+    /// Records a transaction accepted into the in-process queue, `save_transaction`
+    /// removes it again once the transaction is written.
+    fn save_pending_transaction(&self, tx: &DbPendingTransaction) -> Result<(), String>;
+    fn delete_pending_transaction(&self, id: Uuid, signature: &str) -> Result<(), String>;
+    /// Transactions queued by `instance` that were never saved, oldest first.
+    fn get_pending_transactions(&self, instance: Uuid)
+        -> Result<Vec<DbPendingTransaction>, String>;
+    fn get_storage_usage(&self, id: Uuid) -> Result<StorageUsage, String>;
+
+    /// Returns a stream of the blockchain's account/transaction events when an
+    /// event bus is configured, callers fall back to polling otherwise.
+    fn subscribe_events(&self, id: Uuid) -> Result<Option<mpsc::Receiver<BusEvent>>, String>;
+    /// Events of every blockchain written through this storage instance.
+    fn subscribe_local_events(&self) -> broadcast::Receiver<(Uuid, BusEvent)>;
+    /// Checks Postgres (including the schema), Redis and Kafka, each bounded by `timeout`.
+    fn readiness(&self, timeout: Duration) -> Vec<ComponentStatus>;
+    /// Waits for write-behind database writes and flushes the Kafka producer.
+    fn flush(&self, timeout: Duration) -> impl std::future::Future<Output = ()> + Send;
+
+    /// Takes or renews the lease on running a blockchain's queue and producing its
+    /// blocks, returns the instance holding it afterwards.
+    fn acquire_lease(&self, id: Uuid, holder: Uuid, ttl: Duration) -> Result<Uuid, String>;
+    /// Gives the lease up, unless work was forwarded to the holder meanwhile.
+    fn release_lease(&self, id: Uuid, holder: Uuid) -> Result<bool, String>;
+    /// Queues work for whichever instance holds the blockchain's lease.
+    fn forward(&self, id: Uuid, work: &Forwarded) -> Result<(), String>;
+    /// Removes up to `limit` forwarded items per blockchain, oldest first.
+    fn take_forwarded(&self, ids: &[Uuid], limit: usize) -> Result<Vec<(Uuid, Forwarded)>, String>;
+    /// Puts taken items that weren't run back ahead of the blockchain's other work.
+    fn return_forwarded(&self, id: Uuid, work: &[Forwarded]) -> Result<(), String>;
+
+    fn create_webhook(&self, webhook: &DbWebhook) -> Result<(), String>;
+    fn get_webhooks(&self, blockchain: Uuid) -> Result<Vec<DbWebhook>, String>;
+    fn get_webhooks_for_event(&self, event: &str) -> Result<Vec<DbWebhook>, String>;
+    fn delete_webhook(&self, blockchain: Uuid, id: Uuid) -> Result<bool, String>;
+
+    fn create_top_up(&self, top_up: &DbTopUp) -> Result<(), String>;
+    fn get_top_ups(&self, blockchain: Uuid) -> Result<Vec<DbTopUp>, String>;
+    /// Every blockchain's top-ups, for the job running them.
+    fn get_all_top_ups(&self) -> Result<Vec<DbTopUp>, String>;
+    /// Records a run if the last one is still `last_run`, so instances running the
+    /// job at once don't both top up. Returns whether this caller got the run.
+    fn claim_top_up(
+        &self,
+        id: Uuid,
+        last_run: Option<chrono::NaiveDateTime>,
+        now: chrono::NaiveDateTime,
+    ) -> Result<bool, String>;
+    fn delete_top_up(&self, blockchain: Uuid, id: Uuid) -> Result<bool, String>;
+
+    fn create_audit_log(&self, log: &DbAuditLog) -> Result<(), String>;
+    fn get_audit_logs(
+        &self,
+        team_id: Uuid,
+        filter: &AuditLogFilter,
+    ) -> Result<(Vec<DbAuditLog>, i64), String>;
 }
 
 type PgPool = r2d2::Pool<ConnectionManager<PgConnection>>;
 
+const LOCAL_EVENTS_CAPACITY: usize = 4096;
+/// How long a replayed page may take to reach the geyser topic.
+const GEYSER_REPLAY_TIMEOUT: Duration = Duration::from_secs(30);
+
 #[derive(Clone)]
 pub struct PgStorage {
     pool: PgPool,
     cache: Cache,
     rpc: Rpc,
     pubsub: Pubsub,
+    events: Option<EventBus>,
+    leases: LeaseStore,
+    idempotency: IdempotencyStore,
+    quotas: QuotaStore,
+    account_writes: AccountWrites,
+    local_events: broadcast::Sender<(Uuid, BusEvent)>,
+    pending_writes: PendingWrites,
+}
+
+fn upsert_accounts(conn: &mut PgConnection, accounts: &[DbAccount]) -> QueryResult<usize> {
+    diesel::insert_into(crate::schema::accounts::table)
+        .values(accounts)
+        .on_conflict((
+            crate::schema::accounts::address,
+            crate::schema::accounts::blockchain,
+        ))
+        .do_update()
+        .set((
+            crate::schema::accounts::lamports.eq(excluded(crate::schema::accounts::lamports)),
+            crate::schema::accounts::data.eq(excluded(crate::schema::accounts::data)),
+            crate::schema::accounts::owner.eq(excluded(crate::schema::accounts::owner)),
+            crate::schema::accounts::executable.eq(excluded(crate::schema::accounts::executable)),
+            crate::schema::accounts::rent_epoch.eq(excluded(crate::schema::accounts::rent_epoch)),
+        ))
+        .execute(conn)
+}
+
+/// `upsert_accounts` that also overwrites the label, which it leaves alone.
+fn upsert_labeled_accounts(conn: &mut PgConnection, accounts: &[DbAccount]) -> QueryResult<usize> {
+    diesel::insert_into(crate::schema::accounts::table)
+        .values(accounts)
+        .on_conflict((
+            crate::schema::accounts::address,
+            crate::schema::accounts::blockchain,
+        ))
+        .do_update()
+        .set((
+            crate::schema::accounts::lamports.eq(excluded(crate::schema::accounts::lamports)),
+            crate::schema::accounts::data.eq(excluded(crate::schema::accounts::data)),
+            crate::schema::accounts::owner.eq(excluded(crate::schema::accounts::owner)),
+            crate::schema::accounts::executable.eq(excluded(crate::schema::accounts::executable)),
+            crate::schema::accounts::rent_epoch.eq(excluded(crate::schema::accounts::rent_epoch)),
+            crate::schema::accounts::label.eq(excluded(crate::schema::accounts::label)),
+        ))
+        .execute(conn)
+}
+
+/// Sets up new pooled connections: their statement timeout, if any, and the
+/// query timing of request traces.
+#[derive(Debug)]
+struct ConnectionSetup {
+    statement_timeout: Option<Duration>,
+}
+
+impl r2d2::CustomizeConnection<PgConnection, r2d2::Error> for ConnectionSetup {
+    fn on_acquire(&self, conn: &mut PgConnection) -> Result<(), r2d2::Error> {
+        if let Some(timeout) = self.statement_timeout {
+            diesel::sql_query(format!("SET statement_timeout = {}", timeout.as_millis()))
+                .execute(conn)
+                .map_err(r2d2::Error::QueryError)?;
+        }
+        conn.set_instrumentation(trace::QueryTimer::default());
+        Ok(())
+    }
 }
 
 impl PgStorage {
     pub fn new(database_url: &str, cache_url: &str, rpc_url: &str, pubsub_url: &str) -> Self {
+        Self::with_settings(
+            database_url,
+            cache_url,
+            rpc_url,
+            pubsub_url,
+            &PoolSettings::default(),
+        )
+    }
+
+    pub fn with_settings(
+        database_url: &str,
+        cache_url: &str,
+        rpc_url: &str,
+        pubsub_url: &str,
+        settings: &PoolSettings,
+    ) -> Self {
         let manager = ConnectionManager::<PgConnection>::new(database_url);
-        let pool = match r2d2::Pool::builder().build(manager) {
+        let builder = r2d2::Pool::builder()
+            .max_size(settings.database_max_size)
+            .min_idle(settings.database_min_idle)
+            .connection_timeout(settings.database_connect_timeout)
+            .idle_timeout(settings.database_idle_timeout)
+            .max_lifetime(settings.database_max_lifetime)
+            .connection_customizer(Box::new(ConnectionSetup {
+                statement_timeout: settings.database_statement_timeout,
+            }));
+        let pool = match builder.build(manager) {
             Ok(pool) => pool,
             Err(e) => panic!("Failed to create pool: {}", e),
         };
 
+        let cache = Cache::with_settings(cache_url, settings);
         PgStorage {
             pool,
-            cache: Cache::new(cache_url),
+            leases: LeaseStore::new(cache.clone()),
+            idempotency: IdempotencyStore::new(cache.clone()),
+            quotas: QuotaStore::new(cache.clone()),
+            account_writes: AccountWrites::new(settings.database_write_window),
+            cache,
             rpc: Rpc::new(rpc_url.to_string()),
             pubsub: Pubsub::new(pubsub_url),
+            events: None,
+            local_events: broadcast::channel(LOCAL_EVENTS_CAPACITY).0,
+            pending_writes: PendingWrites::default(),
+        }
+    }
+
+    /// Distributes account/transaction events to every engine instance over
+    /// Redis pub/sub, so websocket subscriptions work without Kafka.
+    pub fn with_event_bus(mut self, url: &str) -> Self {
+        self.events = Some(EventBus::new(self.cache.clone(), url));
+        self
+    }
+
+    fn publish_event(&self, id: Uuid, event: BusEvent) {
+        if let Some(events) = &self.events {
+            events.publish(id, &event);
         }
+        // Errors only mean nobody is listening
+        let _ = self.local_events.send((id, event));
     }
 
     fn get_connection(
@@ -169,21 +581,209 @@ impl PgStorage {
     ) -> Result<r2d2::PooledConnection<ConnectionManager<PgConnection>>, String> {
         self.pool.get().map_err(|e| e.to_string())
     }
+
+    /// Upserts account rows in the background, together with the ones written
+    /// during the blockchain's write window when there is one. `previous_owners`
+    /// are the owners the accounts moved away from, as `Cache::set_accounts` found.
+    fn write_accounts(&self, id: Uuid, db_accounts: Vec<DbAccount>, previous_owners: Vec<String>) {
+        let self_clone = self.clone();
+        let window = match self.account_writes.window() {
+            Some(window) => window,
+            None => {
+                let ticket = self.account_writes.ticket();
+                self.pending_writes.spawn(async move {
+                    self_clone.upsert_live_accounts(id, &ticket, db_accounts, previous_owners);
+                });
+                return;
+            }
+        };
+        // Otherwise the open window's flush writes them
+        if !self.account_writes.add(id, db_accounts, previous_owners) {
+            return;
+        }
+        self.pending_writes.spawn(async move {
+            time::sleep(window).await;
+            let ticket = self_clone.account_writes.ticket();
+            let (db_accounts, previous_owners) = self_clone.account_writes.take(id);
+            if db_accounts.is_empty() {
+                return;
+            }
+            self_clone.upsert_live_accounts(id, &ticket, db_accounts, previous_owners);
+        });
+    }
+
+    /// Upserts rows, except the ones of accounts deleted since `ticket` was taken.
+    fn upsert_live_accounts(
+        &self,
+        id: Uuid,
+        ticket: &WriteTicket,
+        db_accounts: Vec<DbAccount>,
+        previous_owners: Vec<String>,
+    ) {
+        let db_accounts = self.account_writes.in_order(id, || {
+            let db_accounts = self.account_writes.live(id, ticket, db_accounts);
+            let mut conn = self.get_connection().unwrap();
+            conn.transaction(|conn| upsert_accounts(conn, &db_accounts))
+                .unwrap();
+            db_accounts
+        });
+        // Results of the previous owners still list the accounts that moved away
+        self.invalidate_program_accounts(
+            id,
+            db_accounts
+                .iter()
+                .map(|account| account.owner.as_str())
+                .chain(previous_owners.iter().map(String::as_str)),
+        );
+    }
+
+    /// Writing accounts to Redis dropped their programs' cached results, but those
+    /// are read from Postgres, so results cached before the rows landed are
+    /// dropped again.
+    fn invalidate_program_accounts<'a>(&self, id: Uuid, owners: impl IntoIterator<Item = &'a str>) {
+        let mut owners: Vec<&str> = owners.into_iter().collect();
+        owners.sort_unstable();
+        owners.dedup();
+        if let Err(e) = self.cache.invalidate_program_accounts(id, &owners) {
+            println!("Failed to invalidate program accounts of {}: {}", id, e);
+        }
+    }
 }
 
 impl Storage for PgStorage {
     fn get_team_from_api_key(&self, api_key: Uuid) -> Result<Team, String> {
+        let (_, team) = self.get_api_key_team(api_key)?;
+        Ok(team)
+    }
+
+    fn get_team(&self, id: Uuid) -> Result<Option<Team>, String> {
+        let mut conn = self.get_connection()?;
+        crate::schema::teams::table
+            .filter(crate::schema::teams::id.eq(id))
+            .first::<Team>(&mut conn)
+            .optional()
+            .map_err(|e| e.to_string())
+    }
+
+    fn set_team_quotas(&self, id: Uuid, quotas: &TeamQuotas) -> Result<Option<Team>, String> {
+        let mut conn = self.get_connection()?;
+        diesel::update(crate::schema::teams::table.filter(crate::schema::teams::id.eq(id)))
+            .set(quotas)
+            .get_result::<Team>(&mut conn)
+            .optional()
+            .map_err(|e| e.to_string())
+    }
+
+    fn set_team_auto_pause(&self, id: Uuid, hours: Option<i32>) -> Result<Option<Team>, String> {
+        let mut conn = self.get_connection()?;
+        diesel::update(crate::schema::teams::table.filter(crate::schema::teams::id.eq(id)))
+            .set(crate::schema::teams::auto_pause_hours.eq(hours))
+            .get_result::<Team>(&mut conn)
+            .optional()
+            .map_err(|e| e.to_string())
+    }
+
+    fn set_team_rpc_write_guard(&self, id: Uuid, enabled: bool) -> Result<Option<Team>, String> {
         let mut conn = self.get_connection()?;
-        let team = crate::schema::api_keys::table
+        diesel::update(crate::schema::teams::table.filter(crate::schema::teams::id.eq(id)))
+            .set(crate::schema::teams::rpc_write_guard.eq(enabled))
+            .get_result::<Team>(&mut conn)
+            .optional()
+            .map_err(|e| e.to_string())
+    }
+
+    fn get_api_key_team(&self, api_key: Uuid) -> Result<(ApiKey, Team), String> {
+        let mut conn = self.get_connection()?;
+        crate::schema::api_keys::table
             .filter(crate::schema::api_keys::id.eq(api_key))
+            .filter(crate::schema::api_keys::revoked_at.is_null())
             .inner_join(
                 crate::schema::teams::table
                     .on(crate::schema::api_keys::team_id.eq(crate::schema::teams::id)),
             )
-            .select(crate::schema::teams::all_columns)
-            .first::<Team>(&mut conn)
+            .select((
+                crate::schema::api_keys::all_columns,
+                crate::schema::teams::all_columns,
+            ))
+            .first::<(ApiKey, Team)>(&mut conn)
+            .map_err(|e| e.to_string())
+    }
+
+    fn create_api_key(&self, api_key: &ApiKey) -> Result<(), String> {
+        let mut conn = self.get_connection()?;
+        diesel::insert_into(crate::schema::api_keys::table)
+            .values(api_key)
+            .execute(&mut conn)
             .map_err(|e| e.to_string())?;
-        Ok(team)
+        Ok(())
+    }
+
+    fn get_api_keys(&self, team_id: Uuid) -> Result<Vec<ApiKey>, String> {
+        let mut conn = self.get_connection()?;
+        crate::schema::api_keys::table
+            .filter(crate::schema::api_keys::team_id.eq(team_id))
+            .order(crate::schema::api_keys::created_at.asc())
+            .load::<ApiKey>(&mut conn)
+            .map_err(|e| e.to_string())
+    }
+
+    fn set_api_key_label(
+        &self,
+        team_id: Uuid,
+        api_key: Uuid,
+        label: String,
+    ) -> Result<bool, String> {
+        let mut conn = self.get_connection()?;
+        let updated = diesel::update(
+            crate::schema::api_keys::table
+                .filter(crate::schema::api_keys::id.eq(api_key))
+                .filter(crate::schema::api_keys::team_id.eq(team_id)),
+        )
+        .set(crate::schema::api_keys::label.eq(label))
+        .execute(&mut conn)
+        .map_err(|e| e.to_string())?;
+        Ok(updated > 0)
+    }
+
+    fn revoke_api_key(&self, team_id: Uuid, api_key: Uuid) -> Result<bool, String> {
+        let mut conn = self.get_connection()?;
+        let revoked = diesel::update(
+            crate::schema::api_keys::table
+                .filter(crate::schema::api_keys::id.eq(api_key))
+                .filter(crate::schema::api_keys::team_id.eq(team_id))
+                .filter(crate::schema::api_keys::revoked_at.is_null()),
+        )
+        .set(crate::schema::api_keys::revoked_at.eq(Utc::now().naive_utc()))
+        .execute(&mut conn)
+        .map_err(|e| e.to_string())?;
+        Ok(revoked > 0)
+    }
+
+    fn reserve_idempotency_key(
+        &self,
+        team_id: Uuid,
+        key: &str,
+        fingerprint: &str,
+    ) -> Result<Option<IdempotencyRecord>, String> {
+        self.idempotency.reserve(team_id, key, fingerprint)
+    }
+
+    fn complete_idempotency_key(
+        &self,
+        team_id: Uuid,
+        key: &str,
+        record: &IdempotencyRecord,
+    ) -> Result<(), String> {
+        self.idempotency.complete(team_id, key, record)
+    }
+
+    fn release_idempotency_key(&self, team_id: Uuid, key: &str) -> Result<(), String> {
+        self.idempotency.release(team_id, key)
+    }
+
+    fn take_daily_transaction(&self, team_id: Uuid, limit: u32) -> Result<bool, String> {
+        self.quotas
+            .take_transaction(team_id, Utc::now().date_naive(), limit)
     }
 
     fn get_blockchain(&self, id: Uuid) -> Result<Blockchain, String> {
@@ -206,6 +806,138 @@ impl Storage for PgStorage {
         let mut conn = self.get_connection()?;
         let blockchains = crate::schema::blockchains::table
             .filter(crate::schema::blockchains::team_id.eq(team_id))
+            .filter(crate::schema::blockchains::pooled.eq(false))
+            .load::<DbBlockchain>(&mut conn)
+            .map_err(|e| e.to_string())?;
+        Ok(blockchains.into_iter().map(|b| b.to_blockchain()).collect())
+    }
+    fn get_all_blockchains(
+        &self,
+        filter: &BlockchainFilter,
+    ) -> Result<(Vec<Blockchain>, i64), String> {
+        let mut conn = self.get_connection()?;
+        let filtered = || {
+            let mut query = crate::schema::blockchains::table
+                .filter(crate::schema::blockchains::pooled.eq(false))
+                .into_boxed();
+            if let Some(team_id) = filter.team_id {
+                query = query.filter(crate::schema::blockchains::team_id.eq(team_id));
+            }
+            if let Some(label) = &filter.label {
+                query = query.filter(crate::schema::blockchains::label.eq(label));
+            }
+            if let Some(created_after) = filter.created_after {
+                query = query.filter(crate::schema::blockchains::created_at.gt(created_after));
+            }
+            if let Some(expiring_before) = filter.expiring_before {
+                query = query.filter(crate::schema::blockchains::expiry.lt(expiring_before));
+            }
+            query
+        };
+
+        let total: i64 = filtered()
+            .count()
+            .get_result(&mut conn)
+            .map_err(|e| e.to_string())?;
+        let mut query = filtered()
+            .order(crate::schema::blockchains::created_at.desc())
+            .offset(filter.offset);
+        if let Some(limit) = filter.limit {
+            query = query.limit(limit);
+        }
+        let blockchains = query
+            .load::<DbBlockchain>(&mut conn)
+            .map_err(|e| e.to_string())?;
+        Ok((
+            blockchains.into_iter().map(|b| b.to_blockchain()).collect(),
+            total,
+        ))
+    }
+    fn claim_pooled_blockchain(
+        &self,
+        team_id: Uuid,
+        config: Option<Uuid>,
+        label: Option<String>,
+        expiry: Option<chrono::NaiveDateTime>,
+    ) -> Result<Option<Blockchain>, String> {
+        let mut conn = self.get_connection()?;
+        // SKIP LOCKED lets concurrent claims each take a different blockchain
+        let blockchain = diesel::sql_query(
+            "UPDATE blockchains SET pooled = false, label = $3, expiry = $4, created_at = $5 \
+             WHERE id = (SELECT id FROM blockchains WHERE pooled AND team_id = $1 \
+             AND config IS NOT DISTINCT FROM $2 ORDER BY created_at LIMIT 1 \
+             FOR UPDATE SKIP LOCKED) RETURNING *",
+        )
+        .bind::<diesel::sql_types::Uuid, _>(team_id)
+        .bind::<diesel::sql_types::Nullable<diesel::sql_types::Uuid>, _>(config)
+        .bind::<diesel::sql_types::Nullable<Text>, _>(label)
+        .bind::<diesel::sql_types::Nullable<diesel::sql_types::Timestamp>, _>(expiry)
+        .bind::<diesel::sql_types::Timestamp, _>(Utc::now().naive_utc())
+        .get_result::<DbBlockchain>(&mut conn)
+        .optional()
+        .map_err(|e| e.to_string())?;
+        Ok(blockchain.map(|b| b.to_blockchain()))
+    }
+    fn count_pooled_blockchains(&self) -> Result<Vec<(Uuid, Option<Uuid>, i64)>, String> {
+        let mut conn = self.get_connection()?;
+        crate::schema::blockchains::table
+            .filter(crate::schema::blockchains::pooled.eq(true))
+            .group_by((
+                crate::schema::blockchains::team_id,
+                crate::schema::blockchains::config,
+            ))
+            .select((
+                crate::schema::blockchains::team_id,
+                crate::schema::blockchains::config,
+                diesel::dsl::count_star(),
+            ))
+            .load::<(Uuid, Option<Uuid>, i64)>(&mut conn)
+            .map_err(|e| e.to_string())
+    }
+    fn delete_pooled_blockchains(&self, config: Uuid) -> Result<Vec<Uuid>, String> {
+        let mut conn = self.get_connection()?;
+        let ids = diesel::delete(
+            crate::schema::blockchains::table
+                .filter(crate::schema::blockchains::pooled.eq(true))
+                .filter(crate::schema::blockchains::config.eq(config)),
+        )
+        .returning(crate::schema::blockchains::id)
+        .get_results::<Uuid>(&mut conn)
+        .map_err(|e| e.to_string())?;
+        for id in &ids {
+            self.cache.delete_blockchain(*id)?;
+        }
+        Ok(ids)
+    }
+    fn transfer_blockchain(&self, id: Uuid, team_id: Uuid) -> Result<Blockchain, String> {
+        let mut conn = self.get_connection()?;
+        let blockchain = conn
+            .transaction::<_, diesel::result::Error, _>(|conn| {
+                diesel::delete(
+                    crate::schema::webhooks::table
+                        .filter(crate::schema::webhooks::blockchain.eq(id))
+                        .filter(crate::schema::webhooks::team_id.ne(team_id)),
+                )
+                .execute(conn)?;
+                diesel::update(
+                    crate::schema::blockchains::table.filter(crate::schema::blockchains::id.eq(id)),
+                )
+                .set(crate::schema::blockchains::team_id.eq(team_id))
+                .get_result::<DbBlockchain>(conn)
+            })
+            .map_err(|e| e.to_string())?;
+        Ok(blockchain.to_blockchain())
+    }
+    fn get_blockchains_by_config(
+        &self,
+        team_id: Uuid,
+        config_id: Uuid,
+    ) -> Result<Vec<Blockchain>, String> {
+        let mut conn = self.get_connection()?;
+        let blockchains = crate::schema::blockchains::table
+            .filter(crate::schema::blockchains::team_id.eq(team_id))
+            .filter(crate::schema::blockchains::config.eq(config_id))
+            .filter(crate::schema::blockchains::pooled.eq(false))
             .load::<DbBlockchain>(&mut conn)
             .map_err(|e| e.to_string())?;
         Ok(blockchains.into_iter().map(|b| b.to_blockchain()).collect())
@@ -221,6 +953,13 @@ impl Storage for PgStorage {
             label: blockchain.label.clone(),
             expiry: blockchain.expiry,
             jit: blockchain.jit,
+            config: blockchain.config,
+            pooled: blockchain.pooled,
+            log_bytes_limit: blockchain.log_bytes_limit,
+            read_only: blockchain.read_only,
+            sponsor_fees: blockchain.sponsor_fees,
+            parity_check: blockchain.parity_check,
+            accounts_status: blockchain.accounts_status.as_str().to_string(),
         };
         diesel::insert_into(crate::schema::blockchains::table)
             .values(&db_blockchain)
@@ -229,7 +968,23 @@ impl Storage for PgStorage {
         Ok(blockchain.id)
     }
 
+    fn update_blockchain(
+        &self,
+        id: Uuid,
+        update: &DbBlockchainUpdate,
+    ) -> Result<Blockchain, String> {
+        let mut conn = self.get_connection()?;
+        let blockchain = diesel::update(
+            crate::schema::blockchains::table.filter(crate::schema::blockchains::id.eq(id)),
+        )
+        .set(update)
+        .get_result::<DbBlockchain>(&mut conn)
+        .map_err(|e| e.to_string())?;
+        Ok(blockchain.to_blockchain())
+    }
+
     fn delete_blockchain(&self, id: Uuid) -> Result<(), String> {
+        self.account_writes.forget_blockchain(id);
         self.cache.delete_blockchain(id)?;
         let mut conn = self.get_connection()?;
         diesel::delete(
@@ -242,7 +997,14 @@ impl Storage for PgStorage {
 
     fn get_account(&self, id: Uuid, address: &Pubkey) -> Result<Option<Account>, String> {
         let account = self.cache.get_account(id, &address.to_string())?;
-        Ok(account.map(|a| a.into_account()))
+        Ok(account.map(|a| a.into_account()).transpose()?)
+    }
+
+    async fn get_remote_accounts(
+        &self,
+        addresses: &[Pubkey],
+    ) -> Result<Vec<Option<Account>>, String> {
+        self.rpc.get_accounts(addresses).await
     }
 
     async fn get_account_jit(
@@ -253,14 +1015,20 @@ impl Storage for PgStorage {
     ) -> Result<Option<Account>, String> {
         let account = self.cache.get_account(id, &address.to_string())?;
         if account.is_none() && jit {
-            let mainnet_account = self.rpc.get_account(address).await?;
-            if mainnet_account.is_some() {
-                self.set_account(id, address, mainnet_account.clone().unwrap(), None)?;
+            let mainnet_account = jit::fetch_missing(&self.rpc, id, 0, &[*address])
+                .await?
+                .pop()
+                .flatten();
+            if let Some(mainnet_account) = &mainnet_account {
+                self.set_account(id, address, mainnet_account.clone(), None)?;
             }
             return Ok(mainnet_account);
         }
+        if jit {
+            jit::record_hits(id, 1);
+        }
 
-        Ok(account.map(|a| a.into_account()))
+        Ok(account.map(|a| a.into_account()).transpose()?)
     }
 
     fn get_accounts(
@@ -277,9 +1045,9 @@ impl Storage for PgStorage {
         )?;
 
         Ok(accounts
-            .iter()
-            .map(|a| a.as_ref().map(|a| a.clone().into_account()))
-            .collect())
+            .into_iter()
+            .map(|a| a.map(|a| a.into_account()).transpose())
+            .collect::<Result<_, _>>()?)
     }
 
     async fn get_accounts_jit(
@@ -295,42 +1063,87 @@ impl Storage for PgStorage {
                 .map(|a| a.to_string())
                 .collect::<Vec<String>>(),
         )?;
+        let none_idxs = accounts
+            .iter()
+            .enumerate()
+            .filter(|(_, a)| a.is_none())
+            .map(|(idx, _)| idx)
+            .collect::<Vec<usize>>();
         if jit {
-            let none_accounts = accounts
+            let none_accounts = none_idxs
                 .iter()
-                .enumerate()
-                .filter(|(_, a)| a.is_none())
-                .map(|(idx, _)| addresses[idx].to_owned())
+                .map(|idx| addresses[*idx].to_owned())
                 .collect::<Vec<Pubkey>>();
 
-            let none_idxs = accounts
-                .iter()
-                .enumerate()
-                .filter(|(_, a)| a.is_none())
-                .map(|(idx, _)| idx)
-                .collect::<Vec<usize>>();
-
-            let mainnet_accounts = self.rpc.get_accounts(&none_accounts).await?;
+            let hits = addresses.len() - none_accounts.len();
+            let mainnet_accounts = jit::fetch_missing(&self.rpc, id, hits, &none_accounts).await?;
             let mut accounts_to_save = vec![];
-            for (i, account) in mainnet_accounts.iter().enumerate() {
-                let idx = none_idxs[i];
+            for (idx, account) in none_idxs.into_iter().zip(mainnet_accounts) {
                 if let Some(account) = account {
-                    accounts_to_save.push((addresses[idx].to_owned(), account.clone()));
-                    accounts.insert(
-                        none_idxs[idx],
-                        Some(DbAccount::from_account(addresses[idx], &account, None, id)),
-                    );
+                    accounts[idx] =
+                        Some(DbAccount::from_account(addresses[idx], &account, None, id));
+                    accounts_to_save.push((addresses[idx].to_owned(), account));
                 }
             }
-            if accounts_to_save.len() > 0 {
+            if !accounts_to_save.is_empty() {
                 self.set_accounts(id, accounts_to_save)?;
             }
         }
 
         Ok(accounts
-            .iter()
-            .map(|a| a.as_ref().map(|a| a.clone().into_account()))
-            .collect())
+            .into_iter()
+            .map(|a| a.map(|a| a.into_account()).transpose())
+            .collect::<Result<_, _>>()?)
+    }
+    fn list_accounts(
+        &self,
+        id: Uuid,
+        filter: &AccountListFilter,
+    ) -> Result<(Vec<AccountSummary>, i64), String> {
+        let mut conn = self.get_connection()?;
+        let filtered = || {
+            let mut query = crate::schema::accounts::table
+                .filter(crate::schema::accounts::blockchain.eq(id))
+                .into_boxed();
+            if let Some(owner) = filter.owner {
+                query = query.filter(crate::schema::accounts::owner.eq(owner.to_string()));
+            }
+            if let Some(executable) = filter.executable {
+                query = query.filter(crate::schema::accounts::executable.eq(executable));
+            }
+            if let Some(label) = &filter.label {
+                let pattern = format!(
+                    "%{}%",
+                    label
+                        .replace('\\', "\\\\")
+                        .replace('%', "\\%")
+                        .replace('_', "\\_")
+                );
+                query = query.filter(crate::schema::accounts::label.ilike(pattern));
+            }
+            query
+        };
+
+        let total: i64 = filtered()
+            .count()
+            .get_result(&mut conn)
+            .map_err(|e| e.to_string())?;
+        let accounts = filtered()
+            .select((
+                crate::schema::accounts::address,
+                crate::schema::accounts::lamports,
+                crate::schema::accounts::owner,
+                crate::schema::accounts::executable,
+                crate::schema::accounts::rent_epoch,
+                crate::schema::accounts::label,
+                sql::<Integer>("octet_length(data)"),
+            ))
+            .order(crate::schema::accounts::address.asc())
+            .limit(filter.limit)
+            .offset(filter.offset)
+            .load::<AccountSummary>(&mut conn)
+            .map_err(|e| e.to_string())?;
+        Ok((accounts, total))
     }
     fn get_largest_accounts(&self, id: Uuid, limit: usize) -> Result<Vec<(Pubkey, u64)>, String> {
         let mut conn = self.get_connection()?;
@@ -343,12 +1156,12 @@ impl Storage for PgStorage {
         Ok(accounts
             .iter()
             .map(|a| {
-                (
+                Ok((
                     Pubkey::from_str(&a.address).unwrap(),
-                    a.lamports.to_u64().unwrap(),
-                )
+                    numeric::to_u64("lamports", &a.lamports)?,
+                ))
             })
-            .collect())
+            .collect::<Result<_, StorageError>>()?)
     }
 
     fn set_account_lamports(
@@ -357,15 +1170,24 @@ impl Storage for PgStorage {
         address: &Pubkey,
         lamports: u64,
     ) -> Result<(), String> {
+        self.account_writes
+            .set_lamports(id, &address.to_string(), lamports);
         let account = self.cache.get_account(id, &address.to_string())?;
+        let owner = account.as_ref().map(|account| account.owner.clone());
         if let Some(mut account) = account {
             account.lamports = lamports.into();
-            self.cache.set_accounts(id, vec![account])?;
+            self.cache.set_accounts(id, &[account])?;
+            self.publish_event(
+                id,
+                BusEvent::Account {
+                    address: address.to_string(),
+                },
+            );
         }
 
         let self_clone = self.clone();
         let address_clone = address.clone();
-        rt::spawn(async move {
+        self.pending_writes.spawn(async move {
             let mut conn = self_clone.get_connection().unwrap();
             diesel::update(
                 crate::schema::accounts::table
@@ -376,90 +1198,258 @@ impl Storage for PgStorage {
             .execute(&mut conn)
             .map_err(|e| e.to_string())
             .unwrap();
+            if let Some(owner) = &owner {
+                self_clone.invalidate_program_accounts(id, [owner.as_str()]);
+            }
         });
         Ok(())
     }
 
-    fn set_account(
+    fn set_account_label(
         &self,
         id: Uuid,
         address: &Pubkey,
-        account: Account,
         label: Option<String>,
-    ) -> Result<(), String> {
-        let db_account = DbAccount::from_account(&address.clone(), &account, label.clone(), id);
-        self.cache.set_accounts(id, vec![db_account.clone()])?;
-        self.pubsub.publish_account_update(db_account.clone());
+    ) -> Result<bool, String> {
+        let Some(mut account) = self.cache.get_account(id, &address.to_string())? else {
+            return Ok(false);
+        };
+        account.label = label;
+        self.cache
+            .set_accounts(id, std::slice::from_ref(&account))?;
+        self.pubsub.publish_account_update(&account);
 
+        // The whole cached row, since the account may still be queued in its write
+        // window and have no row to update yet
         let self_clone = self.clone();
-        let address_clone = address.clone();
-        rt::spawn(async move {
-            let mut conn = self_clone.get_connection().unwrap();
-            let db_account = DbAccount::from_account(&address_clone, &account, label, id);
-            diesel::insert_into(crate::schema::accounts::table)
-                .values(&db_account)
-                .on_conflict((
-                    crate::schema::accounts::address,
-                    crate::schema::accounts::blockchain,
-                ))
-                .do_update()
-                .set((
-                    crate::schema::accounts::lamports
-                        .eq(excluded(crate::schema::accounts::lamports)),
-                    crate::schema::accounts::data.eq(excluded(crate::schema::accounts::data)),
-                    crate::schema::accounts::owner.eq(excluded(crate::schema::accounts::owner)),
-                    crate::schema::accounts::executable
-                        .eq(excluded(crate::schema::accounts::executable)),
-                    crate::schema::accounts::rent_epoch
-                        .eq(excluded(crate::schema::accounts::rent_epoch)),
-                ))
-                .execute(&mut conn)
-                .map_err(|e| e.to_string())
-                .unwrap();
+        let ticket = self.account_writes.ticket();
+        self.pending_writes.spawn(async move {
+            self_clone.account_writes.in_order(id, || {
+                let rows = self_clone.account_writes.live(id, &ticket, vec![account]);
+                if rows.is_empty() {
+                    return;
+                }
+                let mut conn = self_clone.get_connection().unwrap();
+                upsert_labeled_accounts(&mut conn, &rows).unwrap();
+            });
         });
+        Ok(true)
+    }
+
+    fn get_labeled_accounts(&self, id: Uuid, labels: &[String]) -> Result<Vec<Pubkey>, String> {
+        if labels.is_empty() {
+            return Ok(Vec::new());
+        }
+        let mut conn = self.get_connection()?;
+        let addresses: Vec<String> = crate::schema::accounts::table
+            .filter(crate::schema::accounts::blockchain.eq(id))
+            .filter(crate::schema::accounts::label.eq_any(labels))
+            .select(crate::schema::accounts::address)
+            .load(&mut conn)
+            .map_err(|e| e.to_string())?;
+        addresses
+            .iter()
+            .map(|address| Pubkey::from_str(address).map_err(|e| e.to_string()))
+            .collect()
+    }
+
+    fn delete_account(&self, id: Uuid, address: &Pubkey) -> Result<bool, String> {
+        self.account_writes.forget(id, &address.to_string());
+        let cached = self.cache.delete_account(id, &address.to_string())?;
+        let deleted = self.account_writes.in_order(id, || {
+            let mut conn = self.get_connection()?;
+            diesel::delete(
+                crate::schema::accounts::table
+                    .filter(crate::schema::accounts::address.eq(address.to_string()))
+                    .filter(crate::schema::accounts::blockchain.eq(id)),
+            )
+            .execute(&mut conn)
+            .map_err(|e| e.to_string())
+        })?;
+        if cached {
+            self.pubsub
+                .publish_account_deletion(id, &address.to_string());
+            self.publish_event(
+                id,
+                BusEvent::Account {
+                    address: address.to_string(),
+                },
+            );
+        }
+        Ok(cached || deleted > 0)
+    }
+
+    fn delete_accounts(&self, id: Uuid, addresses: &[Pubkey]) -> Result<usize, String> {
+        if addresses.is_empty() {
+            return Ok(0);
+        }
+        let addresses: Vec<String> = addresses.iter().map(|a| a.to_string()).collect();
+        let mut cached = vec![];
+        for address in addresses.iter() {
+            self.account_writes.forget(id, address);
+            if self.cache.delete_account(id, address)? {
+                cached.push(address);
+            }
+        }
+        let deleted = self.account_writes.in_order(id, || {
+            let mut conn = self.get_connection()?;
+            diesel::delete(
+                crate::schema::accounts::table
+                    .filter(crate::schema::accounts::address.eq_any(&addresses))
+                    .filter(crate::schema::accounts::blockchain.eq(id)),
+            )
+            .execute(&mut conn)
+            .map_err(|e| e.to_string())
+        })?;
+        for address in cached.iter() {
+            self.pubsub.publish_account_deletion(id, address);
+            self.publish_event(
+                id,
+                BusEvent::Account {
+                    address: address.to_string(),
+                },
+            );
+        }
+        Ok(cached.len().max(deleted))
+    }
+
+    fn delete_empty_accounts(&self, id: Uuid, addresses: &[Pubkey]) -> Result<usize, String> {
+        let mut cached = vec![];
+        let mut empty = vec![];
+        for address in addresses.iter().map(|a| a.to_string()) {
+            match self.cache.delete_empty_account(id, &address)? {
+                Some(true) => cached.push(address.clone()),
+                Some(false) => {}
+                // Funded since it was found
+                None => continue,
+            }
+            empty.push(address);
+        }
+        if empty.is_empty() {
+            return Ok(0);
+        }
+        // Queued rows aren't forgotten like `delete_accounts` does, they may fund
+        // the account again. An empty one written back is swept next time.
+        let deleted = self.account_writes.in_order(id, || {
+            let mut conn = self.get_connection()?;
+            diesel::delete(
+                crate::schema::accounts::table
+                    .filter(crate::schema::accounts::address.eq_any(&empty))
+                    .filter(crate::schema::accounts::blockchain.eq(id))
+                    .filter(crate::schema::accounts::lamports.eq(BigDecimal::from(0)))
+                    .filter(sql::<Bool>("octet_length(data) = 0")),
+            )
+            .execute(&mut conn)
+            .map_err(|e| e.to_string())
+        })?;
+        for address in cached.iter() {
+            self.pubsub.publish_account_deletion(id, address);
+            self.publish_event(
+                id,
+                BusEvent::Account {
+                    address: address.to_string(),
+                },
+            );
+        }
+        Ok(cached.len().max(deleted))
+    }
+
+    fn get_empty_accounts(&self, limit: i64) -> Result<Vec<(Uuid, Pubkey)>, String> {
+        let mut conn = self.get_connection()?;
+        let non_jit = crate::schema::blockchains::table
+            .filter(crate::schema::blockchains::jit.eq(false))
+            .select(crate::schema::blockchains::id);
+        let accounts: Vec<(Uuid, String)> = crate::schema::accounts::table
+            .filter(crate::schema::accounts::lamports.eq(BigDecimal::from(0)))
+            .filter(sql::<Bool>("octet_length(data) = 0"))
+            .filter(crate::schema::accounts::blockchain.eq_any(non_jit))
+            .select((
+                crate::schema::accounts::blockchain,
+                crate::schema::accounts::address,
+            ))
+            .limit(limit)
+            .load(&mut conn)
+            .map_err(|e| e.to_string())?;
+        accounts
+            .into_iter()
+            .map(|(id, address)| {
+                Pubkey::from_str(&address)
+                    .map(|address| (id, address))
+                    .map_err(|e| e.to_string())
+            })
+            .collect()
+    }
+
+    fn set_account(
+        &self,
+        id: Uuid,
+        address: &Pubkey,
+        account: Account,
+        label: Option<String>,
+    ) -> Result<(), String> {
+        let db_account = DbAccount::new(address, account, label, id);
+        let previous_owners = self
+            .cache
+            .set_accounts(id, std::slice::from_ref(&db_account))?;
+        self.pubsub.publish_account_update(&db_account);
+        self.publish_event(
+            id,
+            BusEvent::Account {
+                address: address.to_string(),
+            },
+        );
+
+        self.write_accounts(id, vec![db_account], previous_owners);
         Ok(())
     }
 
     fn set_accounts(&self, id: Uuid, accounts: Vec<(Pubkey, Account)>) -> Result<(), String> {
+        // The rows own the account data, the cache and pubsub only borrow them before
+        // they're moved into the write
         let db_accounts: Vec<DbAccount> = accounts
-            .iter()
-            .map(|(address, account)| DbAccount::from_account(address, account, None, id))
+            .into_iter()
+            .map(|(address, account)| DbAccount::new(&address, account, None, id))
             .collect();
-        self.cache.set_accounts(id, db_accounts.clone())?;
-        self.pubsub.publish_accounts_update(db_accounts.clone());
+        let previous_owners = self.cache.set_accounts(id, &db_accounts)?;
+        self.pubsub.publish_accounts_update(&db_accounts);
+        for account in db_accounts.iter() {
+            self.publish_event(
+                id,
+                BusEvent::Account {
+                    address: account.address.clone(),
+                },
+            );
+        }
 
-        let self_clone = self.clone();
-        rt::spawn(async move {
-            let mut conn = self_clone.get_connection().unwrap();
-            let db_accounts: Vec<DbAccount> = accounts
-                .iter()
-                .map(|(address, account)| DbAccount::from_account(address, account, None, id))
-                .collect();
-            conn.transaction::<_, diesel::result::Error, _>(|conn| {
-                diesel::insert_into(crate::schema::accounts::table)
-                    .values(db_accounts)
-                    .on_conflict((
-                        crate::schema::accounts::address,
-                        crate::schema::accounts::blockchain,
-                    ))
-                    .do_update()
-                    .set((
-                        crate::schema::accounts::lamports
-                            .eq(excluded(crate::schema::accounts::lamports)),
-                        crate::schema::accounts::data.eq(excluded(crate::schema::accounts::data)),
-                        crate::schema::accounts::owner.eq(excluded(crate::schema::accounts::owner)),
-                        crate::schema::accounts::executable
-                            .eq(excluded(crate::schema::accounts::executable)),
-                        crate::schema::accounts::rent_epoch
-                            .eq(excluded(crate::schema::accounts::rent_epoch)),
-                    ))
-                    .execute(conn)
-            })
-            .unwrap();
-        });
+        self.write_accounts(id, db_accounts, previous_owners);
         Ok(())
     }
 
+    fn snapshot_accounts(&self, id: Uuid) -> Result<Vec<(Pubkey, Account)>, String> {
+        let accounts = self.cache.get_all_accounts(id)?;
+        accounts
+            .into_iter()
+            .map(|a| {
+                let address = Pubkey::from_str(&a.address).map_err(|e| e.to_string())?;
+                Ok((address, a.into_account()?))
+            })
+            .collect()
+    }
+
+    fn stream_accounts(
+        &self,
+        id: Uuid,
+        each: &mut dyn FnMut(Pubkey, Account) -> Result<(), String>,
+    ) -> Result<(), String> {
+        self.cache.scan_accounts(id, |accounts| {
+            for a in accounts {
+                let address = Pubkey::from_str(&a.address).map_err(|e| e.to_string())?;
+                each(address, a.into_account()?)?;
+            }
+            Ok(())
+        })
+    }
+
     fn get_token_accounts_by_owner(
         &self,
         id: Uuid,
@@ -483,12 +1473,12 @@ impl Storage for PgStorage {
         Ok(accounts
             .iter()
             .map(|a| {
-                (
+                Ok((
                     Pubkey::from_str(&a.address).unwrap(),
-                    a.clone().into_account(),
-                )
+                    a.clone().into_account()?,
+                ))
             })
-            .collect())
+            .collect::<Result<_, StorageError>>()?)
     }
     fn get_program_accounts(
         &self,
@@ -504,11 +1494,61 @@ impl Storage for PgStorage {
         Ok(accounts
             .iter()
             .map(|a| {
-                (
+                Ok((
                     Pubkey::from_str(&a.address).unwrap(),
-                    a.clone().into_account(),
-                )
+                    a.clone().into_account()?,
+                ))
             })
+            .collect::<Result<_, StorageError>>()?)
+    }
+    fn get_cached_program_accounts(
+        &self,
+        id: Uuid,
+        program_id: &Pubkey,
+        key: &str,
+    ) -> Result<(Option<String>, u64), String> {
+        self.cache
+            .get_program_accounts_result(id, &program_id.to_string(), key)
+    }
+    fn cache_program_accounts(
+        &self,
+        id: Uuid,
+        program_id: &Pubkey,
+        key: &str,
+        result: &str,
+        generation: u64,
+    ) -> Result<(), String> {
+        self.cache
+            .set_program_accounts_result(id, &program_id.to_string(), key, result, generation)
+    }
+    fn get_nonce_accounts(
+        &self,
+        id: Uuid,
+        authority: &Pubkey,
+    ) -> Result<Vec<(Pubkey, Account)>, String> {
+        let mut conn = self.get_connection()?;
+        let accounts = crate::schema::accounts::table
+            .filter(crate::schema::accounts::owner.eq(system_program::id().to_string()))
+            .filter(
+                sql::<Bool>("length(data) = ")
+                    .bind::<Integer, _>(NonceState::size() as i32)
+                    .sql(" AND substring(data from ")
+                    .bind::<Integer, _>(NONCE_AUTHORITY_OFFSET as i32 + 1)
+                    .sql(" for 32) = decode(")
+                    .bind::<Text, _>(encode(authority.to_bytes()))
+                    .sql(", 'hex')"),
+            )
+            .filter(crate::schema::accounts::blockchain.eq(id))
+            .load::<DbAccount>(&mut conn)
+            .map_err(|e| e.to_string())?;
+        // The bytes matched, the state also has to be initialized
+        let accounts = accounts
+            .into_iter()
+            .map(|a| Ok((Pubkey::from_str(&a.address).unwrap(), a.into_account()?)))
+            .collect::<Result<Vec<_>, StorageError>>()?;
+        Ok(accounts
+            .into_iter()
+            .filter(|(_, account)| nonce_authority(account) == Some(*authority))
             .collect())
     }
     fn get_config_accounts(&self, config_id: Uuid) -> Result<Vec<(Pubkey, Account)>, String> {
@@ -520,12 +1560,12 @@ impl Storage for PgStorage {
         Ok(accounts
             .iter()
             .map(|a| {
-                (
+                Ok((
                     Pubkey::from_str(&a.address).unwrap(),
-                    a.clone().into_account(),
-                )
+                    a.clone().into_account()?,
+                ))
             })
-            .collect())
+            .collect::<Result<_, StorageError>>()?)
     }
     fn get_config_account(
         &self,
@@ -539,7 +1579,7 @@ impl Storage for PgStorage {
             .first::<DbConfigAccount>(&mut conn)
             .optional()
             .map_err(|e| e.to_string())?;
-        Ok(account.map(|a| a.into_account()))
+        Ok(account.map(|a| a.into_account()).transpose()?)
     }
     fn set_config_account(
         &self,
@@ -576,13 +1616,132 @@ impl Storage for PgStorage {
         Ok(())
     }
 
+    fn set_config_accounts(
+        &self,
+        config_id: Uuid,
+        accounts: Vec<(Pubkey, Account)>,
+    ) -> Result<(), String> {
+        let mut conn = self.get_connection()?;
+        let db_accounts: Vec<DbConfigAccount> = accounts
+            .iter()
+            .map(|(address, account)| {
+                DbConfigAccount::from_account(address, account, None, config_id)
+            })
+            .collect();
+        diesel::insert_into(crate::schema::blockchain_config_accounts::table)
+            .values(db_accounts)
+            .on_conflict((
+                crate::schema::blockchain_config_accounts::address,
+                crate::schema::blockchain_config_accounts::config,
+            ))
+            .do_update()
+            .set((
+                crate::schema::blockchain_config_accounts::lamports.eq(excluded(
+                    crate::schema::blockchain_config_accounts::lamports,
+                )),
+                crate::schema::blockchain_config_accounts::data
+                    .eq(excluded(crate::schema::blockchain_config_accounts::data)),
+                crate::schema::blockchain_config_accounts::owner
+                    .eq(excluded(crate::schema::blockchain_config_accounts::owner)),
+                crate::schema::blockchain_config_accounts::executable.eq(excluded(
+                    crate::schema::blockchain_config_accounts::executable,
+                )),
+                crate::schema::blockchain_config_accounts::rent_epoch.eq(excluded(
+                    crate::schema::blockchain_config_accounts::rent_epoch,
+                )),
+            ))
+            .execute(&mut conn)
+            .map_err(|e| e.to_string())?;
+        Ok(())
+    }
+    fn delete_config_accounts(
+        &self,
+        config_id: Uuid,
+        addresses: &[Pubkey],
+    ) -> Result<usize, String> {
+        let mut conn = self.get_connection()?;
+        diesel::delete(
+            crate::schema::blockchain_config_accounts::table
+                .filter(crate::schema::blockchain_config_accounts::config.eq(config_id))
+                .filter(
+                    crate::schema::blockchain_config_accounts::address
+                        .eq_any(addresses.iter().map(|a| a.to_string())),
+                ),
+        )
+        .execute(&mut conn)
+        .map_err(|e| e.to_string())
+    }
+    fn create_config(
+        &self,
+        team_id: Option<Uuid>,
+        label: String,
+        bundles: Vec<String>,
+    ) -> Result<DBBlockchainConfig, String> {
+        let mut conn = self.get_connection()?;
+        let config = DBBlockchainConfig {
+            id: Uuid::new_v4(),
+            created_at: Utc::now().naive_utc(),
+            label,
+            team_id,
+            bundles,
+        };
+        diesel::insert_into(crate::schema::blockchain_configs::table)
+            .values(&config)
+            .execute(&mut conn)
+            .map_err(|e| e.to_string())?;
+        Ok(config)
+    }
+    fn get_config(&self, config_id: Uuid) -> Result<Option<DBBlockchainConfig>, String> {
+        let mut conn = self.get_connection()?;
+        crate::schema::blockchain_configs::table
+            .filter(crate::schema::blockchain_configs::id.eq(config_id))
+            .first::<DBBlockchainConfig>(&mut conn)
+            .optional()
+            .map_err(|e| e.to_string())
+    }
+    fn get_configs(&self, team_id: Uuid) -> Result<Vec<DBBlockchainConfig>, String> {
+        let mut conn = self.get_connection()?;
+        crate::schema::blockchain_configs::table
+            .filter(
+                crate::schema::blockchain_configs::team_id
+                    .eq(team_id)
+                    .or(crate::schema::blockchain_configs::team_id.is_null()),
+            )
+            .order(crate::schema::blockchain_configs::created_at.asc())
+            .load::<DBBlockchainConfig>(&mut conn)
+            .map_err(|e| e.to_string())
+    }
+    fn delete_config(&self, config_id: Uuid) -> Result<(), String> {
+        let mut conn = self.get_connection()?;
+        conn.transaction::<_, diesel::result::Error, _>(|conn| {
+            diesel::delete(
+                crate::schema::blockchain_config_accounts::table
+                    .filter(crate::schema::blockchain_config_accounts::config.eq(config_id)),
+            )
+            .execute(conn)?;
+            diesel::delete(
+                crate::schema::blockchain_configs::table
+                    .filter(crate::schema::blockchain_configs::id.eq(config_id)),
+            )
+            .execute(conn)?;
+            Ok(())
+        })
+        .map_err(|e| e.to_string())
+    }
+
     fn set_block(&self, id: Uuid, block: &Block) -> Result<(), String> {
         let self_clone = self.clone();
         let db_block = DbBlock::from_block(block, id);
         self.cache.set_block(id, db_block.clone())?;
         self.pubsub.publish_block(db_block.clone());
+        self.publish_event(
+            id,
+            BusEvent::Block {
+                slot: block.block_height,
+            },
+        );
 
-        rt::spawn(async move {
+        self.pending_writes.spawn(async move {
             let mut conn = self_clone.get_connection().unwrap();
             diesel::insert_into(crate::schema::blocks::table)
                 .values(db_block)
@@ -596,7 +1755,7 @@ impl Storage for PgStorage {
     fn get_block(&self, id: Uuid, blockhash: &Hash) -> Result<Block, String> {
         let block = self.cache.get_block(id, &blockhash.to_bytes())?;
         match block {
-            Some(block) => Ok(block.into_block().0),
+            Some(block) => Ok(block.into_block()?.0),
             None => {
                 let mut conn = self.get_connection()?;
                 let block: DbBlock = crate::schema::blocks::table
@@ -604,7 +1763,7 @@ impl Storage for PgStorage {
                     .filter(crate::schema::blocks::blockchain.eq(id))
                     .first(&mut conn)
                     .map_err(|e| e.to_string())?;
-                Ok(block.into_block().0)
+                Ok(block.into_block()?.0)
             }
         }
     }
@@ -612,7 +1771,10 @@ impl Storage for PgStorage {
     fn get_recent_blocks(&self, id: Uuid, limit: usize) -> Result<Vec<Block>, String> {
         let blocks = self.cache.get_recent_blocks(id, limit);
         match blocks {
-            Ok(blocks) => Ok(blocks.into_iter().map(|b| b.into_block().0).collect()),
+            Ok(blocks) => Ok(blocks
+                .into_iter()
+                .map(|b| b.into_block().map(|(block, _)| block))
+                .collect::<Result<_, _>>()?),
             Err(e) => Err(e),
         }
     }
@@ -627,7 +1789,7 @@ impl Storage for PgStorage {
             .optional()
             .map_err(|e| e.to_string())?;
         match block {
-            Some(block) => Ok(Some(block.into_block().0)),
+            Some(block) => Ok(Some(block.into_block()?.0)),
             None => Ok(None),
         }
     }
@@ -644,80 +1806,151 @@ impl Storage for PgStorage {
 
     fn get_latest_block(&self, id: Uuid) -> Result<Block, String> {
         let block = self.cache.get_latest_block(id)?;
-        Ok(block.into_block().0)
+        Ok(block.into_block()?.0)
     }
 
-    fn save_transaction(&self, id: Uuid, tx: &TransactionMetadata) -> Result<(), String> {
+    fn get_slot_history(
+        &self,
+        id: Uuid,
+        from_slot: u64,
+        to_slot: u64,
+        limit: i64,
+        max_events: usize,
+    ) -> Result<Vec<GeyserEvent>, String> {
+        use crate::schema::{blocks, transactions};
+
         let mut conn = self.get_connection()?;
-        let db_tx = DbTransaction::from_transaction(id, &tx);
-        let db_meta = DbTransactionMeta::from_transaction(tx);
-        let db_accounts = DbTransactionAccountKey::from_transaction(tx);
-        let db_ix = DbTransactionInstruction::from_transaction(tx);
-        let db_log = DbTransactionLogMessage::from_transaction(tx);
-        let db_signature = DbTransactionSignature::from_transaction(tx);
-        let mut token_balances: Vec<DBTransactionTokenBalance> = Vec::new();
-        if let Some(pre_balances) = &tx.pre_token_balances {
-            for pre_balance in pre_balances {
-                token_balances.push(DBTransactionTokenBalance::from_token_balance(
-                    pre_balance,
-                    &tx.signature.to_string(),
-                    true,
-                ));
-            }
-        }
-        if let Some(post_balances) = &tx.post_token_balances {
-            for post_balance in post_balances {
-                token_balances.push(DBTransactionTokenBalance::from_token_balance(
-                    post_balance,
-                    &tx.signature.to_string(),
-                    false,
-                ));
-            }
+        let mut db_blocks: Vec<DbBlock> = blocks::table
+            .filter(blocks::blockchain.eq(id))
+            .filter(blocks::slot.ge(BigDecimal::from(from_slot)))
+            .filter(blocks::slot.lt(BigDecimal::from(to_slot)))
+            .order(blocks::slot.asc())
+            .limit(limit)
+            .load(&mut conn)
+            .map_err(|e| e.to_string())?;
+        let (Some(first), Some(last)) = (db_blocks.first(), db_blocks.last()) else {
+            return Ok(vec![]);
+        };
+        // Counted first, so only the transactions of the slots that fit are loaded
+        let counts: HashMap<BigDecimal, i64> = transactions::table
+            .filter(transactions::blockchain.eq(id))
+            .filter(transactions::slot.ge(first.slot.clone()))
+            .filter(transactions::slot.le(last.slot.clone()))
+            .group_by(transactions::slot)
+            .select((transactions::slot, diesel::dsl::count_star()))
+            .load::<(BigDecimal, i64)>(&mut conn)
+            .map_err(|e| e.to_string())?
+            .into_iter()
+            .collect();
+        let slots = GeyserEvent::slots_in_page(
+            db_blocks
+                .iter()
+                .map(|block| counts.get(&block.slot).copied().unwrap_or(0) as usize),
+            max_events,
+        );
+        db_blocks.truncate(slots);
+        let (first, last) = (&db_blocks[0], &db_blocks[slots - 1]);
+        let db_transactions: Vec<DbTransaction> = transactions::table
+            .filter(transactions::blockchain.eq(id))
+            .filter(transactions::slot.ge(first.slot.clone()))
+            .filter(transactions::slot.le(last.slot.clone()))
+            .order(transactions::created_at.asc())
+            .load(&mut conn)
+            .map_err(|e| e.to_string())?;
+        let mut by_slot: HashMap<u64, Vec<DbTransactionObject>> = HashMap::new();
+        for object in transaction_objects(&mut conn, db_transactions)? {
+            by_slot
+                .entry(numeric::saturating_u64(&object.transaction.slot))
+                .or_default()
+                .push(object);
         }
-        let tx_object = DbTransactionObject {
-            transaction: db_tx.clone(),
-            meta: db_meta.clone(),
-            account_keys: db_accounts.clone(),
-            instructions: db_ix.clone(),
-            log_messages: db_log.clone(),
-            signatures: db_signature.clone(),
-            token_balances: token_balances.clone(),
+
+        Ok(db_blocks
+            .into_iter()
+            .flat_map(|block| {
+                let transactions = by_slot
+                    .remove(&numeric::saturating_u64(&block.slot))
+                    .unwrap_or_default();
+                GeyserEvent::from_slot(block, transactions)
+            })
+            .collect())
+    }
+
+    fn replay_geyser_events(&self, events: &[GeyserEvent]) -> Result<(), String> {
+        self.pubsub.publish_replay(events, GEYSER_REPLAY_TIMEOUT)
+    }
+
+    fn publish_heartbeats(&self, heartbeats: &[PubSubHeartbeat]) {
+        self.pubsub.publish_heartbeats(heartbeats);
+    }
+
+    fn save_transaction(&self, id: Uuid, tx: &TransactionMetadata) -> Result<(), String> {
+        let mut conn = self.get_connection()?;
+        let tx_object = DbTransactionObject::from_transaction(id, tx);
+        let event = BusEvent::Transaction {
+            signature: tx.signature.to_string(),
+            accounts: tx_object
+                .account_keys
+                .iter()
+                .map(|k| k.account.clone())
+                .collect(),
+        };
+        // The event bus shares the cache's Redis, so the event goes out in the same
+        // pipeline as the cache writes
+        let message = match &self.events {
+            Some(_) => Some(EventBus::message(id, &event)?),
+            None => None,
         };
-        self.cache.set_transaction(id, tx_object.clone())?;
+        self.cache.set_transaction(id, &tx_object, message)?;
         self.pubsub.publish_transaction(tx_object.clone());
+        // Errors only mean nobody is listening
+        let _ = self.local_events.send((id, event));
 
-        rt::spawn(async move {
-            diesel::insert_into(crate::schema::transactions::table)
-                .values(db_tx)
-                .execute(&mut conn)
-                .map_err(|e| e.to_string())
-                .unwrap();
-            diesel::insert_into(crate::schema::transaction_meta::table)
-                .values(db_meta)
-                .execute(&mut conn)
-                .unwrap();
-            diesel::insert_into(crate::schema::transaction_account_keys::table)
-                .values(db_accounts)
-                .execute(&mut conn)
-                .unwrap();
-            diesel::insert_into(crate::schema::transaction_instructions::table)
-                .values(db_ix)
-                .execute(&mut conn)
-                .unwrap();
-            diesel::insert_into(crate::schema::transaction_log_messages::table)
-                .values(db_log)
-                .execute(&mut conn)
-                .unwrap();
-            diesel::insert_into(crate::schema::transaction_signatures::table)
-                .values(db_signature)
-                .execute(&mut conn)
-                .unwrap();
-            if token_balances.len() > 0 {
-                diesel::insert_into(crate::schema::transaction_token_balances::table)
-                    .values(token_balances)
-                    .execute(&mut conn)
-                    .unwrap();
-            };
+        let signature = tx.signature.to_string();
+        let DbTransactionObject {
+            transaction: db_tx,
+            meta: db_meta,
+            account_keys: db_accounts,
+            instructions: db_ix,
+            log_messages: db_log,
+            signatures: db_signature,
+            token_balances,
+        } = tx_object;
+        self.pending_writes.spawn(async move {
+            // One database transaction, so the rows are committed once rather than
+            // once per table, and never partially
+            conn.transaction::<_, diesel::result::Error, _>(|conn| {
+                diesel::insert_into(crate::schema::transactions::table)
+                    .values(db_tx)
+                    .execute(conn)?;
+                diesel::insert_into(crate::schema::transaction_meta::table)
+                    .values(db_meta)
+                    .execute(conn)?;
+                diesel::insert_into(crate::schema::transaction_account_keys::table)
+                    .values(db_accounts)
+                    .execute(conn)?;
+                diesel::insert_into(crate::schema::transaction_instructions::table)
+                    .values(db_ix)
+                    .execute(conn)?;
+                diesel::insert_into(crate::schema::transaction_log_messages::table)
+                    .values(db_log)
+                    .execute(conn)?;
+                diesel::insert_into(crate::schema::transaction_signatures::table)
+                    .values(db_signature)
+                    .execute(conn)?;
+                if !token_balances.is_empty() {
+                    diesel::insert_into(crate::schema::transaction_token_balances::table)
+                        .values(token_balances)
+                        .execute(conn)?;
+                }
+                Ok(())
+            })
+            .map_err(|e| e.to_string())
+            .unwrap();
+            // Only once written, so a crash before this resumes it
+            if let Err(e) = delete_pending_transaction(&mut conn, id, &signature) {
+                println!("Failed to delete pending transaction: {}", e);
+            }
         });
 
         Ok(())
@@ -729,7 +1962,7 @@ impl Storage for PgStorage {
         signature: &Signature,
     ) -> Result<
         Option<(
-            Transaction,
+            VersionedTransaction,
             u64,
             TransactionMeta,
             Option<TransactionError>,
@@ -738,49 +1971,7 @@ impl Storage for PgStorage {
         String,
     > {
         let tx = self.cache.get_transaction(id, &signature.to_string())?;
-        match tx {
-            Some(tx) => {
-                // let db
-                // let (db_tx, account_keys, instructions, logs, metas, signatures, token_balances) =
-                //     transaction_map.into_iter().next().unwrap().1;
-
-                let instructions = tx
-                    .instructions
-                    .iter()
-                    .map(|i| i.to_instruction(tx.account_keys.clone()))
-                    .collect::<Vec<Instruction>>();
-
-                let transaction = Transaction {
-                    signatures: tx
-                        .signatures
-                        .into_iter()
-                        .map(|s| Signature::from_str(&s.signature).unwrap())
-                        .collect(),
-                    message: solana_sdk::message::Message::new(&instructions, None),
-                };
-
-                let metadata = tx.meta.to_metadata(tx.log_messages, tx.token_balances);
-
-                Ok(Some((
-                    transaction,
-                    tx.transaction.slot.to_u64().unwrap(),
-                    metadata,
-                    match tx.meta.to_owned().err {
-                        Some(e) => {
-                            let deserialized_error: Result<TransactionError, _> =
-                                serde_json::from_str(&e);
-                            match deserialized_error {
-                                Ok(e) => Some(e),
-                                Err(_) => Some(TransactionError::InvalidAccountIndex),
-                            }
-                        }
-                        None => None,
-                    },
-                    tx.transaction.created_at,
-                )))
-            }
-            None => Ok(None),
-        }
+        Ok(tx.map(|tx| tx.into_transaction()))
     }
 
     fn get_transactions_for_address(
@@ -832,6 +2023,109 @@ impl Storage for PgStorage {
         Ok(transactions)
     }
 
+    fn get_program_logs(
+        &self,
+        id: Uuid,
+        program_id: &Pubkey,
+        start: chrono::NaiveDateTime,
+        end: chrono::NaiveDateTime,
+        limit: i64,
+    ) -> Result<Vec<DbTransactionLogMessage>, String> {
+        use crate::schema::{transaction_log_messages, transactions};
+
+        let mut conn = self.get_connection()?;
+        transaction_log_messages::table
+            .inner_join(
+                transactions::table
+                    .on(transactions::signature.eq(transaction_log_messages::transaction_signature)),
+            )
+            .filter(transactions::blockchain.eq(id))
+            .filter(transaction_log_messages::program_id.eq(program_id.to_string()))
+            .filter(transactions::created_at.ge(start))
+            .filter(transactions::created_at.le(end))
+            .order((
+                transactions::created_at.asc(),
+                transaction_log_messages::transaction_signature.asc(),
+                transaction_log_messages::index.asc(),
+            ))
+            .limit(limit)
+            .select(transaction_log_messages::all_columns)
+            .load(&mut conn)
+            .map_err(|e| e.to_string())
+    }
+
+    fn get_token_balance_changes(
+        &self,
+        id: Uuid,
+        owner: &Pubkey,
+        before: Option<&Signature>,
+        limit: i64,
+    ) -> Result<Vec<TokenBalanceChange>, String> {
+        use crate::schema::{transaction_account_keys, transaction_token_balances, transactions};
+
+        let owner = owner.to_string();
+        let mut conn = self.get_connection()?;
+        let mut query = transactions::table
+            .filter(transactions::blockchain.eq(id))
+            .filter(
+                transactions::signature.eq_any(
+                    transaction_token_balances::table
+                        .filter(transaction_token_balances::owner.eq(owner.clone()))
+                        .select(transaction_token_balances::transaction_signature),
+                ),
+            )
+            .into_boxed();
+        if let Some(before) = before {
+            let created_at: Option<chrono::NaiveDateTime> = transactions::table
+                .filter(transactions::blockchain.eq(id))
+                .filter(transactions::signature.eq(before.to_string()))
+                .select(transactions::created_at)
+                .first(&mut conn)
+                .optional()
+                .map_err(|e| e.to_string())?;
+            match created_at {
+                Some(created_at) => query = query.filter(transactions::created_at.lt(created_at)),
+                None => return Ok(vec![]),
+            }
+        }
+        let db_transactions: Vec<DbTransaction> = query
+            .order(transactions::created_at.desc())
+            .limit(limit)
+            .load(&mut conn)
+            .map_err(|e| e.to_string())?;
+        let signatures: Vec<String> = db_transactions
+            .iter()
+            .map(|tx| tx.signature.clone())
+            .collect();
+
+        let mut account_keys = group_by_signature(
+            transaction_account_keys::table
+                .filter(transaction_account_keys::transaction_signature.eq_any(&signatures))
+                .load::<DbTransactionAccountKey>(&mut conn)
+                .map_err(|e| e.to_string())?,
+            |key| &key.transaction_signature,
+        );
+        let mut balances = group_by_signature(
+            transaction_token_balances::table
+                .filter(transaction_token_balances::transaction_signature.eq_any(&signatures))
+                .filter(transaction_token_balances::owner.eq(owner.clone()))
+                .load::<DBTransactionTokenBalance>(&mut conn)
+                .map_err(|e| e.to_string())?,
+            |balance| &balance.transaction_signature,
+        );
+        Ok(db_transactions
+            .iter()
+            .flat_map(|tx| {
+                TokenBalanceChange::from_balances(
+                    tx,
+                    &account_keys.remove(&tx.signature).unwrap_or_default(),
+                    &balances.remove(&tx.signature).unwrap_or_default(),
+                    &owner,
+                )
+            })
+            .collect())
+    }
+
     fn get_transaction_count(&self, id: Uuid) -> Result<u64, String> {
         let mut conn = self.get_connection()?;
         let count: i64 = crate::schema::transactions::table
@@ -841,4 +2135,544 @@ impl Storage for PgStorage {
             .map_err(|e| e.to_string())?;
         Ok(count as u64)
     }
+
+    fn get_slot_transaction_versions(&self, id: Uuid, slot: u64) -> Result<Vec<String>, String> {
+        let mut conn = self.get_connection()?;
+        crate::schema::transactions::table
+            .filter(crate::schema::transactions::blockchain.eq(id))
+            .filter(crate::schema::transactions::slot.eq(BigDecimal::from(slot)))
+            .select(crate::schema::transactions::version)
+            .distinct()
+            .load(&mut conn)
+            .map_err(|e| e.to_string())
+    }
+
+    fn get_recent_transactions(
+        &self,
+        id: Uuid,
+        limit: usize,
+    ) -> Result<Vec<TransactionSummary>, String> {
+        self.cache.get_transaction_feed(id, limit)
+    }
+
+    fn search_transactions(
+        &self,
+        id: Uuid,
+        filter: &TransactionFilter,
+    ) -> Result<(Vec<DbTransactionObject>, i64), String> {
+        use crate::schema::{
+            transaction_account_keys, transaction_instructions, transaction_meta, transactions,
+        };
+
+        let mut conn = self.get_connection()?;
+        let filtered = || {
+            let mut query = transactions::table
+                .filter(transactions::blockchain.eq(id))
+                .into_boxed();
+            if let Some(address) = &filter.address {
+                query = query.filter(
+                    transactions::signature.eq_any(
+                        transaction_account_keys::table
+                            .filter(transaction_account_keys::account.eq(address.clone()))
+                            .select(transaction_account_keys::transaction_signature),
+                    ),
+                );
+            }
+            if let Some(program_id) = &filter.program_id {
+                query = query.filter(
+                    transactions::signature.eq_any(
+                        transaction_instructions::table
+                            .filter(transaction_instructions::program_id.eq(program_id.clone()))
+                            .select(transaction_instructions::transaction_signature),
+                    ),
+                );
+            }
+            match filter.failed {
+                Some(true) => {
+                    query = query.filter(
+                        transactions::signature.eq_any(
+                            transaction_meta::table
+                                .filter(transaction_meta::err.is_not_null())
+                                .select(transaction_meta::transaction_signature),
+                        ),
+                    )
+                }
+                Some(false) => {
+                    query = query.filter(
+                        transactions::signature.eq_any(
+                            transaction_meta::table
+                                .filter(transaction_meta::err.is_null())
+                                .select(transaction_meta::transaction_signature),
+                        ),
+                    )
+                }
+                None => {}
+            }
+            if let Some(start) = filter.start {
+                query = query.filter(transactions::created_at.ge(start));
+            }
+            if let Some(end) = filter.end {
+                query = query.filter(transactions::created_at.le(end));
+            }
+            if let Some(prefix) = &filter.signature_prefix {
+                query = query.filter(transactions::signature.like(format!("{}%", prefix)));
+            }
+            if let Some(fee_payer) = &filter.fee_payer {
+                query = query.filter(transactions::fee_payer.eq(fee_payer.clone()));
+            }
+            if let Some(is_vote) = filter.is_vote {
+                query = query.filter(transactions::is_vote.eq(is_vote));
+            }
+            if let Some(price) = filter.min_compute_unit_price {
+                query = query.filter(transactions::compute_unit_price.ge(BigDecimal::from(price)));
+            }
+            query
+        };
+
+        let total: i64 = filtered()
+            .count()
+            .get_result(&mut conn)
+            .map_err(|e| e.to_string())?;
+        let db_transactions: Vec<DbTransaction> = filtered()
+            .order(transactions::created_at.desc())
+            .limit(filter.limit)
+            .offset(filter.offset)
+            .load(&mut conn)
+            .map_err(|e| e.to_string())?;
+        let objects = transaction_objects(&mut conn, db_transactions)?;
+        Ok((objects, total))
+    }
+
+    fn set_program_idl(&self, id: Uuid, program_id: &Pubkey, idl: String) -> Result<(), String> {
+        let mut conn = self.get_connection()?;
+        let program_idl = DbProgramIdl {
+            id: Uuid::new_v4(),
+            created_at: Utc::now().naive_utc(),
+            blockchain: id,
+            program_id: program_id.to_string(),
+            idl,
+        };
+        diesel::insert_into(crate::schema::program_idls::table)
+            .values(&program_idl)
+            .on_conflict((
+                crate::schema::program_idls::blockchain,
+                crate::schema::program_idls::program_id,
+            ))
+            .do_update()
+            .set(crate::schema::program_idls::idl.eq(excluded(crate::schema::program_idls::idl)))
+            .execute(&mut conn)
+            .map_err(|e| e.to_string())?;
+        Ok(())
+    }
+
+    fn get_program_idl(&self, id: Uuid, program_id: &Pubkey) -> Result<Option<String>, String> {
+        let mut conn = self.get_connection()?;
+        crate::schema::program_idls::table
+            .filter(crate::schema::program_idls::blockchain.eq(id))
+            .filter(crate::schema::program_idls::program_id.eq(program_id.to_string()))
+            .select(crate::schema::program_idls::idl)
+            .first::<String>(&mut conn)
+            .optional()
+            .map_err(|e| e.to_string())
+    }
+
+    fn set_sysvar_overrides(&self, id: Uuid, overrides: &SysvarOverrides) -> Result<(), String> {
+        let mut conn = self.get_connection()?;
+        let sysvars = DbBlockchainSysvars {
+            blockchain: id,
+            updated_at: Utc::now().naive_utc(),
+            overrides: serde_json::to_string(overrides).map_err(|e| e.to_string())?,
+        };
+        diesel::insert_into(crate::schema::blockchain_sysvars::table)
+            .values(&sysvars)
+            .on_conflict(crate::schema::blockchain_sysvars::blockchain)
+            .do_update()
+            .set(&sysvars)
+            .execute(&mut conn)
+            .map_err(|e| e.to_string())?;
+        Ok(())
+    }
+
+    fn get_sysvar_overrides(&self, id: Uuid) -> Result<Option<SysvarOverrides>, String> {
+        let mut conn = self.get_connection()?;
+        crate::schema::blockchain_sysvars::table
+            .filter(crate::schema::blockchain_sysvars::blockchain.eq(id))
+            .select(crate::schema::blockchain_sysvars::overrides)
+            .first::<String>(&mut conn)
+            .optional()
+            .map_err(|e| e.to_string())?
+            .map(|overrides| serde_json::from_str(&overrides).map_err(|e| e.to_string()))
+            .transpose()
+    }
+
+    fn save_pending_transaction(&self, tx: &DbPendingTransaction) -> Result<(), String> {
+        let mut conn = self.get_connection()?;
+        diesel::insert_into(crate::schema::pending_transactions::table)
+            .values(tx)
+            .on_conflict_do_nothing()
+            .execute(&mut conn)
+            .map_err(|e| e.to_string())?;
+        Ok(())
+    }
+
+    fn delete_pending_transaction(&self, id: Uuid, signature: &str) -> Result<(), String> {
+        let mut conn = self.get_connection()?;
+        delete_pending_transaction(&mut conn, id, signature)
+    }
+
+    fn get_pending_transactions(
+        &self,
+        instance: Uuid,
+    ) -> Result<Vec<DbPendingTransaction>, String> {
+        use crate::schema::pending_transactions::dsl;
+        let mut conn = self.get_connection()?;
+        dsl::pending_transactions
+            .select(DbPendingTransaction::as_select())
+            .filter(dsl::instance.eq(instance))
+            .order(dsl::created_at.asc())
+            .load(&mut conn)
+            .map_err(|e| e.to_string())
+    }
+
+    fn get_storage_usage(&self, id: Uuid) -> Result<StorageUsage, String> {
+        self.cache.get_usage(id)
+    }
+
+    fn subscribe_events(&self, id: Uuid) -> Result<Option<mpsc::Receiver<BusEvent>>, String> {
+        match &self.events {
+            Some(events) => Ok(Some(events.subscribe(id)?)),
+            None => Ok(None),
+        }
+    }
+
+    fn subscribe_local_events(&self) -> broadcast::Receiver<(Uuid, BusEvent)> {
+        self.local_events.subscribe()
+    }
+
+    fn readiness(&self, timeout: Duration) -> Vec<ComponentStatus> {
+        let database = self
+            .pool
+            .get_timeout(timeout)
+            .map_err(|e| format!("Failed to get connection: {}", e))
+            .and_then(|mut conn| {
+                diesel::sql_query(
+                    "SELECT table_name::text AS table_name FROM information_schema.tables \
+                     WHERE table_schema = current_schema() AND table_name = ANY($1)",
+                )
+                .bind::<diesel::sql_types::Array<Text>, _>(
+                    REQUIRED_TABLES
+                        .iter()
+                        .map(|t| t.to_string())
+                        .collect::<Vec<_>>(),
+                )
+                .load::<ExistingTable>(&mut conn)
+                .map_err(|e| e.to_string())
+            });
+        let schema = match &database {
+            Ok(tables) => {
+                let existing: Vec<String> = tables.iter().map(|t| t.table_name.clone()).collect();
+                let missing = health::missing_tables(&existing);
+                match missing.is_empty() {
+                    true => Ok(()),
+                    false => Err(format!("Missing tables: {}", missing.join(", "))),
+                }
+            }
+            Err(_) => Err("Database unavailable".to_string()),
+        };
+
+        let mut statuses = vec![
+            ComponentStatus::new("postgres", database.map(|_| ())),
+            ComponentStatus::new("schema", schema),
+            ComponentStatus::new("redis", self.cache.ping(timeout)),
+        ];
+        if let Some(kafka) = self.pubsub.check(timeout) {
+            statuses.push(ComponentStatus::new("kafka", kafka));
+        }
+        statuses
+    }
+
+    async fn flush(&self, timeout: Duration) {
+        if tokio::time::timeout(timeout, self.pending_writes.wait())
+            .await
+            .is_err()
+        {
+            println!(
+                "Gave up on {} pending database writes",
+                self.pending_writes.len()
+            );
+        }
+        self.pubsub.flush(timeout);
+    }
+
+    fn acquire_lease(&self, id: Uuid, holder: Uuid, ttl: Duration) -> Result<Uuid, String> {
+        self.leases.acquire(id, holder, ttl)
+    }
+
+    fn release_lease(&self, id: Uuid, holder: Uuid) -> Result<bool, String> {
+        self.leases.release(id, holder)
+    }
+
+    fn forward(&self, id: Uuid, work: &Forwarded) -> Result<(), String> {
+        self.leases.forward(id, work)
+    }
+
+    fn take_forwarded(&self, ids: &[Uuid], limit: usize) -> Result<Vec<(Uuid, Forwarded)>, String> {
+        self.leases.take(ids, limit)
+    }
+
+    fn return_forwarded(&self, id: Uuid, work: &[Forwarded]) -> Result<(), String> {
+        self.leases.give_back(id, work)
+    }
+
+    fn create_webhook(&self, webhook: &DbWebhook) -> Result<(), String> {
+        let mut conn = self.get_connection()?;
+        diesel::insert_into(crate::schema::webhooks::table)
+            .values(webhook)
+            .execute(&mut conn)
+            .map_err(|e| e.to_string())?;
+        Ok(())
+    }
+
+    fn get_webhooks(&self, blockchain: Uuid) -> Result<Vec<DbWebhook>, String> {
+        let mut conn = self.get_connection()?;
+        crate::schema::webhooks::table
+            .filter(crate::schema::webhooks::blockchain.eq(blockchain))
+            .order(crate::schema::webhooks::created_at.asc())
+            .load::<DbWebhook>(&mut conn)
+            .map_err(|e| e.to_string())
+    }
+
+    fn get_webhooks_for_event(&self, event: &str) -> Result<Vec<DbWebhook>, String> {
+        let mut conn = self.get_connection()?;
+        crate::schema::webhooks::table
+            .filter(crate::schema::webhooks::events.contains(vec![event.to_string()]))
+            .load::<DbWebhook>(&mut conn)
+            .map_err(|e| e.to_string())
+    }
+
+    fn delete_webhook(&self, blockchain: Uuid, id: Uuid) -> Result<bool, String> {
+        let mut conn = self.get_connection()?;
+        let deleted = diesel::delete(
+            crate::schema::webhooks::table
+                .filter(crate::schema::webhooks::id.eq(id))
+                .filter(crate::schema::webhooks::blockchain.eq(blockchain)),
+        )
+        .execute(&mut conn)
+        .map_err(|e| e.to_string())?;
+        Ok(deleted > 0)
+    }
+
+    fn create_top_up(&self, top_up: &DbTopUp) -> Result<(), String> {
+        let mut conn = self.get_connection()?;
+        diesel::insert_into(crate::schema::top_ups::table)
+            .values(top_up)
+            .execute(&mut conn)
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+    }
+
+    fn get_top_ups(&self, blockchain: Uuid) -> Result<Vec<DbTopUp>, String> {
+        let mut conn = self.get_connection()?;
+        crate::schema::top_ups::table
+            .filter(crate::schema::top_ups::blockchain.eq(blockchain))
+            .order(crate::schema::top_ups::created_at.asc())
+            .load(&mut conn)
+            .map_err(|e| e.to_string())
+    }
+
+    fn get_all_top_ups(&self) -> Result<Vec<DbTopUp>, String> {
+        let mut conn = self.get_connection()?;
+        crate::schema::top_ups::table
+            .load(&mut conn)
+            .map_err(|e| e.to_string())
+    }
+
+    fn claim_top_up(
+        &self,
+        id: Uuid,
+        last_run: Option<chrono::NaiveDateTime>,
+        now: chrono::NaiveDateTime,
+    ) -> Result<bool, String> {
+        let mut conn = self.get_connection()?;
+        let claimed = diesel::update(
+            crate::schema::top_ups::table
+                .filter(crate::schema::top_ups::id.eq(id))
+                .filter(crate::schema::top_ups::last_run.is_not_distinct_from(last_run)),
+        )
+        .set(crate::schema::top_ups::last_run.eq(now))
+        .execute(&mut conn)
+        .map_err(|e| e.to_string())?;
+        Ok(claimed > 0)
+    }
+
+    fn delete_top_up(&self, blockchain: Uuid, id: Uuid) -> Result<bool, String> {
+        let mut conn = self.get_connection()?;
+        let deleted = diesel::delete(
+            crate::schema::top_ups::table
+                .filter(crate::schema::top_ups::id.eq(id))
+                .filter(crate::schema::top_ups::blockchain.eq(blockchain)),
+        )
+        .execute(&mut conn)
+        .map_err(|e| e.to_string())?;
+        Ok(deleted > 0)
+    }
+
+    fn create_audit_log(&self, log: &DbAuditLog) -> Result<(), String> {
+        let mut conn = self.get_connection()?;
+        diesel::insert_into(crate::schema::audit_logs::table)
+            .values(log)
+            .execute(&mut conn)
+            .map_err(|e| e.to_string())?;
+        Ok(())
+    }
+
+    fn get_audit_logs(
+        &self,
+        team_id: Uuid,
+        filter: &AuditLogFilter,
+    ) -> Result<(Vec<DbAuditLog>, i64), String> {
+        let mut conn = self.get_connection()?;
+        let filtered = || {
+            let mut query = crate::schema::audit_logs::table
+                .filter(crate::schema::audit_logs::team_id.eq(team_id))
+                .into_boxed();
+            if let Some(blockchain) = filter.blockchain {
+                query = query.filter(crate::schema::audit_logs::blockchain.eq(blockchain));
+            }
+            if let Some(action) = &filter.action {
+                query = query.filter(crate::schema::audit_logs::action.eq(action.clone()));
+            }
+            if !filter.actions.is_empty() {
+                query = query.filter(crate::schema::audit_logs::action.eq_any(&filter.actions));
+            }
+            if let Some(since) = filter.since {
+                query = query.filter(crate::schema::audit_logs::created_at.ge(since));
+            }
+            query
+        };
+
+        let total: i64 = filtered()
+            .count()
+            .get_result(&mut conn)
+            .map_err(|e| e.to_string())?;
+        let logs = filtered()
+            .order(crate::schema::audit_logs::created_at.desc())
+            .limit(filter.limit)
+            .offset(filter.offset)
+            .load::<DbAuditLog>(&mut conn)
+            .map_err(|e| e.to_string())?;
+        Ok((logs, total))
+    }
+}
+
+fn delete_pending_transaction(
+    conn: &mut PgConnection,
+    id: Uuid,
+    signature: &str,
+) -> Result<(), String> {
+    use crate::schema::pending_transactions::dsl;
+    diesel::delete(
+        dsl::pending_transactions
+            .filter(dsl::blockchain.eq(id))
+            .filter(dsl::signature.eq(signature)),
+    )
+    .execute(conn)
+    .map(|_| ())
+    .map_err(|e| e.to_string())
+}
+
+/// Loads the rows belonging to each transaction, skipping transactions whose
+/// meta isn't written yet.
+fn transaction_objects(
+    conn: &mut PgConnection,
+    db_transactions: Vec<DbTransaction>,
+) -> Result<Vec<DbTransactionObject>, String> {
+    use crate::schema::{
+        transaction_account_keys, transaction_instructions, transaction_log_messages,
+        transaction_meta, transaction_signatures, transaction_token_balances,
+    };
+
+    let signatures: Vec<String> = db_transactions
+        .iter()
+        .map(|tx| tx.signature.clone())
+        .collect();
+
+    // One query per table for the whole page, grouped by signature below
+    let mut metas: HashMap<String, DbTransactionMeta> = transaction_meta::table
+        .filter(transaction_meta::transaction_signature.eq_any(&signatures))
+        .load::<DbTransactionMeta>(conn)
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .map(|meta| (meta.transaction_signature.clone(), meta))
+        .collect();
+    let mut account_keys = group_by_signature(
+        transaction_account_keys::table
+            .filter(transaction_account_keys::transaction_signature.eq_any(&signatures))
+            .order(transaction_account_keys::index.asc())
+            .load::<DbTransactionAccountKey>(conn)
+            .map_err(|e| e.to_string())?,
+        |key| &key.transaction_signature,
+    );
+    let mut instructions = group_by_signature(
+        transaction_instructions::table
+            .filter(transaction_instructions::transaction_signature.eq_any(&signatures))
+            .order(transaction_instructions::created_at.asc())
+            .load::<DbTransactionInstruction>(conn)
+            .map_err(|e| e.to_string())?,
+        |ix| &ix.transaction_signature,
+    );
+    let mut log_messages = group_by_signature(
+        transaction_log_messages::table
+            .filter(transaction_log_messages::transaction_signature.eq_any(&signatures))
+            .order(transaction_log_messages::index.asc())
+            .load::<DbTransactionLogMessage>(conn)
+            .map_err(|e| e.to_string())?,
+        |log| &log.transaction_signature,
+    );
+    let mut tx_signatures = group_by_signature(
+        transaction_signatures::table
+            .filter(transaction_signatures::transaction_signature.eq_any(&signatures))
+            .load::<DbTransactionSignature>(conn)
+            .map_err(|e| e.to_string())?,
+        |signature| &signature.transaction_signature,
+    );
+    let mut token_balances = group_by_signature(
+        transaction_token_balances::table
+            .filter(transaction_token_balances::transaction_signature.eq_any(&signatures))
+            .load::<DBTransactionTokenBalance>(conn)
+            .map_err(|e| e.to_string())?,
+        |balance| &balance.transaction_signature,
+    );
+
+    Ok(db_transactions
+        .into_iter()
+        .filter_map(|transaction| {
+            // Still being written
+            let meta = metas.remove(&transaction.signature)?;
+            let signature = transaction.signature.clone();
+            Some(DbTransactionObject {
+                transaction,
+                meta,
+                account_keys: account_keys.remove(&signature).unwrap_or_default(),
+                instructions: instructions.remove(&signature).unwrap_or_default(),
+                log_messages: log_messages.remove(&signature).unwrap_or_default(),
+                signatures: tx_signatures.remove(&signature).unwrap_or_default(),
+                token_balances: token_balances.remove(&signature).unwrap_or_default(),
+            })
+        })
+        .collect())
+}
+
+fn group_by_signature<R>(
+    rows: Vec<R>,
+    signature: impl Fn(&R) -> &String,
+) -> HashMap<String, Vec<R>> {
+    let mut grouped: HashMap<String, Vec<R>> = HashMap::new();
+    for row in rows {
+        grouped
+            .entry(signature(&row).clone())
+            .or_default()
+            .push(row);
+    }
+    grouped
 }
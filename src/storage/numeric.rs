@@ -0,0 +1,50 @@
+use bigdecimal::{BigDecimal, Signed, ToPrimitive};
+use std::fmt;
+
+/// A stored value that can't be read back as the type the engine expects.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum StorageError {
+    /// A `Numeric` column holding a negative, fractional or out of range value.
+    InvalidNumeric { column: &'static str, value: String },
+}
+
+impl fmt::Display for StorageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StorageError::InvalidNumeric { column, value } => {
+                write!(f, "Invalid {} value {}", column, value)
+            }
+        }
+    }
+}
+
+impl std::error::Error for StorageError {}
+
+impl From<StorageError> for String {
+    fn from(e: StorageError) -> Self {
+        e.to_string()
+    }
+}
+
+/// Reads a `Numeric` column that has to hold a u64 exactly, like lamports or a slot.
+pub fn to_u64(column: &'static str, value: &BigDecimal) -> Result<u64, StorageError> {
+    value
+        .is_integer()
+        .then(|| value.to_u64())
+        .flatten()
+        .ok_or_else(|| StorageError::InvalidNumeric {
+            column,
+            value: value.to_string(),
+        })
+}
+
+/// Reads a `Numeric` column clamped to the u64 range, dropping any fraction.
+///
+/// Used for values where the bound is the meaningful part, like the rent-exempt
+/// rent_epoch sentinels, and for display-only fields.
+pub fn saturating_u64(value: &BigDecimal) -> u64 {
+    match value.is_negative() {
+        true => 0,
+        false => value.to_u64().unwrap_or(u64::MAX),
+    }
+}
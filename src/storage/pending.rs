@@ -0,0 +1,61 @@
+use actix_web::rt;
+use std::{
+    future::Future,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+use tokio::sync::Notify;
+
+use crate::reporting;
+
+/// Tracks write-behind tasks so shutdown can wait for them to reach Postgres.
+#[derive(Clone, Default)]
+pub struct PendingWrites {
+    count: Arc<AtomicUsize>,
+    idle: Arc<Notify>,
+}
+
+/// Decrements on drop, so a panicking write still counts as finished.
+struct PendingGuard(PendingWrites);
+
+impl Drop for PendingGuard {
+    fn drop(&mut self) {
+        if self.0.count.fetch_sub(1, Ordering::AcqRel) == 1 {
+            self.0.idle.notify_waiters();
+        }
+    }
+}
+
+impl PendingWrites {
+    pub fn spawn<F: Future<Output = ()> + 'static>(&self, write: F) {
+        self.count.fetch_add(1, Ordering::AcqRel);
+        let guard = PendingGuard(self.clone());
+        // Panicking writes are reported with the context of whatever queued them
+        let write = reporting::scope(reporting::current_context(), write);
+        rt::spawn(async move {
+            write.await;
+            drop(guard);
+        });
+    }
+
+    pub fn len(&self) -> usize {
+        self.count.load(Ordering::Acquire)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Resolves once no writes are in flight.
+    pub async fn wait(&self) {
+        loop {
+            let idle = self.idle.notified();
+            if self.is_empty() {
+                return;
+            }
+            idle.await;
+        }
+    }
+}
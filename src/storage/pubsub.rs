@@ -1,21 +1,45 @@
+//! Publishing to the Kafka `geyser` topic. Every message's key names its JSON
+//! payload:
+//!
+//! - `account`: `PubSubAccount`, an account's state after a write.
+//! - `account_deletion`: `{"address", "blockchain"}`, an account was removed.
+//! - `block`: `PubSubBlock`, a new block.
+//! - `transaction`: `PubSubTransactionObject`, an executed transaction. Its
+//!   top-level `addresses` and `owners` list every account key and the programs
+//!   owning them, so consumers can filter without reading the instructions.
+//! - `heartbeat`: `PubSubHeartbeat`, sent periodically for each blockchain an
+//!   instance serves, with or without activity.
+//!
+//! Replays from `/admin/blockchains/{id}/geyser/replay` send `block` and
+//! `transaction` messages again, with the same payloads.
+
 use actix_web::rt;
 use bigdecimal::ToPrimitive;
 use rdkafka::admin::{AdminClient, NewTopic};
 use rdkafka::config::ClientConfig;
-use rdkafka::producer::{BaseProducer, BaseRecord};
+use rdkafka::error::{KafkaError, RDKafkaErrorCode};
+use rdkafka::producer::{BaseProducer, BaseRecord, Producer};
 use serde::{Deserialize, Serialize};
 use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
 use uuid::Uuid;
 
-use super::{accounts::DbAccount, blocks::DbBlock, transactions::DbTransactionObject};
+use super::{accounts::DbAccount, blocks::DbBlock, numeric, transactions::DbTransactionObject};
 
 #[derive(Clone)]
 pub struct Pubsub {
-    producer: Arc<Mutex<BaseProducer>>,
+    producer: Option<Arc<Mutex<BaseProducer>>>,
 }
 
 impl Pubsub {
+    /// Creates the Kafka producer. An empty url disables publishing, for
+    /// deployments that rely on the internal event bus instead.
     pub fn new(url: &str) -> Self {
+        if url.is_empty() {
+            return Pubsub { producer: None };
+        }
+
         let producer = Arc::new(Mutex::new(
             ClientConfig::new()
                 .set("bootstrap.servers", url)
@@ -40,12 +64,78 @@ impl Pubsub {
                 .expect("Failed to create topics");
         });
 
-        Pubsub { producer }
+        Pubsub {
+            producer: Some(producer),
+        }
+    }
+
+    /// Fetches cluster metadata to check the brokers are reachable, `None`
+    /// when Kafka is disabled.
+    pub fn check(&self, timeout: Duration) -> Option<Result<(), String>> {
+        let producer = self.producer.as_ref()?;
+        let producer = producer.lock().unwrap();
+        Some(
+            producer
+                .client()
+                .fetch_metadata(None, timeout)
+                .map(|_| ())
+                .map_err(|e| e.to_string()),
+        )
     }
 
-    pub fn publish_account_update(&self, account: DbAccount) {
-        let producer = self.producer.lock().unwrap();
-        let payload = serde_json::to_string(&PubSubAccount::from_db_account(account)).unwrap();
+    /// Blocks until queued messages are delivered or the timeout passes.
+    pub fn flush(&self, timeout: Duration) {
+        let Some(producer) = &self.producer else {
+            return;
+        };
+        if let Err(e) = producer.lock().unwrap().flush(timeout) {
+            println!("Failed to flush Kafka producer: {:?}", e);
+        }
+    }
+
+    /// Sends replayed events and waits until they are delivered, so a replayed
+    /// page is only reported done once consumers can read it. The producer is
+    /// only locked while sending, live events keep going out meanwhile.
+    pub fn publish_replay(&self, events: &[GeyserEvent], timeout: Duration) -> Result<(), String> {
+        let Some(producer) = &self.producer else {
+            return Err("Kafka is disabled".to_string());
+        };
+        let deadline = Instant::now() + timeout;
+        for event in events {
+            let payload = event.payload()?;
+            let mut record = BaseRecord::to("geyser")
+                .payload(payload.as_str())
+                .key(event.key());
+            loop {
+                match producer.lock().unwrap().send(record) {
+                    Ok(()) => break,
+                    Err((KafkaError::MessageProduction(RDKafkaErrorCode::QueueFull), returned)) => {
+                        record = returned;
+                    }
+                    Err((e, _)) => return Err(e.to_string()),
+                }
+                if Instant::now() >= deadline {
+                    return Err("Timed out queueing geyser events".to_string());
+                }
+                producer.lock().unwrap().poll(Duration::from_millis(10));
+            }
+        }
+        while producer.lock().unwrap().in_flight_count() > 0 {
+            if Instant::now() >= deadline {
+                return Err("Timed out delivering geyser events".to_string());
+            }
+            producer.lock().unwrap().poll(Duration::ZERO);
+            thread::sleep(Duration::from_millis(10));
+        }
+        Ok(())
+    }
+
+    pub fn publish_account_update(&self, account: &DbAccount) {
+        let Some(producer) = &self.producer else {
+            return;
+        };
+        let producer = producer.lock().unwrap();
+        let payload = serde_json::to_string(&PubSubAccountRef::from_db_account(account)).unwrap();
         if let Err(e) = producer.send(
             BaseRecord::to("geyser")
                 .payload(payload.as_str())
@@ -55,10 +145,14 @@ impl Pubsub {
         }
     }
 
-    pub fn publish_accounts_update(&self, accounts: Vec<DbAccount>) {
-        let producer = self.producer.lock().unwrap();
+    pub fn publish_accounts_update(&self, accounts: &[DbAccount]) {
+        let Some(producer) = &self.producer else {
+            return;
+        };
+        let producer = producer.lock().unwrap();
         for account in accounts {
-            let payload = serde_json::to_string(&PubSubAccount::from_db_account(account)).unwrap();
+            let payload =
+                serde_json::to_string(&PubSubAccountRef::from_db_account(account)).unwrap();
             if let Err(e) = producer.send(
                 BaseRecord::to("geyser")
                     .payload(payload.as_str())
@@ -69,8 +163,30 @@ impl Pubsub {
         }
     }
 
+    pub fn publish_account_deletion(&self, blockchain: Uuid, address: &str) {
+        let Some(producer) = &self.producer else {
+            return;
+        };
+        let producer = producer.lock().unwrap();
+        let payload = serde_json::to_string(&PubSubAccountDeletion {
+            address,
+            blockchain,
+        })
+        .unwrap();
+        if let Err(e) = producer.send(
+            BaseRecord::to("geyser")
+                .payload(payload.as_str())
+                .key("account_deletion"),
+        ) {
+            println!("Failed to send message to Kafka: {:?}", e);
+        }
+    }
+
     pub fn publish_transaction(&self, transaction: DbTransactionObject) {
-        let producer = self.producer.lock().unwrap();
+        let Some(producer) = &self.producer else {
+            return;
+        };
+        let producer = producer.lock().unwrap();
         let payload = serde_json::to_string(&PubSubTransactionObject::from_db_transaction_object(
             transaction,
         ))
@@ -84,8 +200,28 @@ impl Pubsub {
         }
     }
 
+    pub fn publish_heartbeats(&self, heartbeats: &[PubSubHeartbeat]) {
+        let Some(producer) = &self.producer else {
+            return;
+        };
+        let producer = producer.lock().unwrap();
+        for heartbeat in heartbeats {
+            let payload = serde_json::to_string(heartbeat).unwrap();
+            if let Err(e) = producer.send(
+                BaseRecord::to("geyser")
+                    .payload(payload.as_str())
+                    .key("heartbeat"),
+            ) {
+                println!("Failed to send message to Kafka: {:?}", e);
+            }
+        }
+    }
+
     pub fn publish_block(&self, block: DbBlock) {
-        let producer = self.producer.lock().unwrap();
+        let Some(producer) = &self.producer else {
+            return;
+        };
+        let producer = producer.lock().unwrap();
         let payload = serde_json::to_string(&PubSubBlock::from_db_block(block)).unwrap();
         if let Err(e) = producer.send(
             BaseRecord::to("geyser")
@@ -97,6 +233,64 @@ impl Pubsub {
     }
 }
 
+/// A message of the geyser topic, as replayed for consumers that joined late.
+/// Serializes as its Kafka key and payload.
+#[derive(Clone, Debug, Serialize)]
+#[serde(tag = "key", content = "payload", rename_all = "snake_case")]
+pub enum GeyserEvent {
+    Block(PubSubBlock),
+    Transaction(Box<PubSubTransactionObject>),
+}
+
+impl GeyserEvent {
+    /// A slot's block followed by its transactions, the order they were
+    /// published in live.
+    pub fn from_slot(block: DbBlock, transactions: Vec<DbTransactionObject>) -> Vec<Self> {
+        let mut events = Vec::with_capacity(transactions.len() + 1);
+        events.push(GeyserEvent::Block(PubSubBlock::from_db_block(block)));
+        events.extend(transactions.into_iter().map(|transaction| {
+            GeyserEvent::Transaction(Box::new(
+                PubSubTransactionObject::from_db_transaction_object(transaction),
+            ))
+        }));
+        events
+    }
+
+    /// How many of a page's slots, oldest first by their transaction counts, fit in
+    /// `max_events` events. Slots aren't split, so the first one always fits.
+    pub fn slots_in_page(
+        transactions: impl IntoIterator<Item = usize>,
+        max_events: usize,
+    ) -> usize {
+        let mut events = 0;
+        let mut slots = 0;
+        for count in transactions {
+            events += count + 1;
+            if slots > 0 && events > max_events {
+                break;
+            }
+            slots += 1;
+        }
+        slots
+    }
+
+    pub fn key(&self) -> &'static str {
+        match self {
+            GeyserEvent::Block(_) => "block",
+            GeyserEvent::Transaction(_) => "transaction",
+        }
+    }
+
+    /// The message payload, the same as the live one.
+    pub fn payload(&self) -> Result<String, String> {
+        match self {
+            GeyserEvent::Block(block) => serde_json::to_string(block),
+            GeyserEvent::Transaction(transaction) => serde_json::to_string(transaction),
+        }
+        .map_err(|e| e.to_string())
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct PubSubAccount {
     pub id: Uuid,
@@ -115,17 +309,54 @@ impl PubSubAccount {
         PubSubAccount {
             id: db_account.id,
             address: db_account.address,
-            lamports: db_account.lamports.to_u128().unwrap(),
+            lamports: numeric::saturating_u64(&db_account.lamports).into(),
             data: db_account.data,
             owner: db_account.owner,
             executable: db_account.executable,
-            rent_epoch: db_account.rent_epoch.to_u128().unwrap(),
+            rent_epoch: numeric::saturating_u64(&db_account.rent_epoch).into(),
             label: db_account.label,
             blockchain: db_account.blockchain,
         }
     }
 }
 
+/// Serializes the same as `PubSubAccount`, borrowing the data instead of copying it.
+#[derive(Serialize)]
+struct PubSubAccountRef<'a> {
+    id: Uuid,
+    address: &'a str,
+    lamports: u128,
+    data: &'a [u8],
+    owner: &'a str,
+    executable: bool,
+    rent_epoch: u128,
+    label: Option<&'a str>,
+    blockchain: Uuid,
+}
+
+impl<'a> PubSubAccountRef<'a> {
+    fn from_db_account(db_account: &'a DbAccount) -> Self {
+        PubSubAccountRef {
+            id: db_account.id,
+            address: &db_account.address,
+            lamports: numeric::saturating_u64(&db_account.lamports).into(),
+            data: &db_account.data,
+            owner: &db_account.owner,
+            executable: db_account.executable,
+            rent_epoch: numeric::saturating_u64(&db_account.rent_epoch).into(),
+            label: db_account.label.as_deref(),
+            blockchain: db_account.blockchain,
+        }
+    }
+}
+
+/// Published when an account is deleted rather than updated.
+#[derive(Serialize)]
+struct PubSubAccountDeletion<'a> {
+    address: &'a str,
+    blockchain: Uuid,
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct PubSubBlock {
     pub id: Uuid,
@@ -144,16 +375,32 @@ impl PubSubBlock {
             blockchain: db_block.blockchain,
             blockhash: db_block.blockhash,
             previous_blockhash: db_block.previous_blockhash,
-            parent_slot: db_block.parent_slot.to_u128().unwrap(),
-            block_height: db_block.block_height.to_u128().unwrap(),
-            slot: db_block.slot.to_u128().unwrap(),
+            parent_slot: numeric::saturating_u64(&db_block.parent_slot).into(),
+            block_height: numeric::saturating_u64(&db_block.block_height).into(),
+            slot: numeric::saturating_u64(&db_block.slot).into(),
         }
     }
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+/// Lets consumers tell an idle blockchain from a stopped engine, and measure
+/// their lag against the blockchain's slot.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PubSubHeartbeat {
+    pub blockchain: Uuid,
+    pub slot: u128,
+    pub block_height: u128,
+    /// Unix time of the latest block, in seconds.
+    pub block_time: u64,
+    /// When the heartbeat was sent, in unix milliseconds.
+    pub timestamp: i64,
+}
 
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct PubSubTransactionObject {
+    /// Every account key of the transaction, in order.
+    pub addresses: Vec<String>,
+    /// Programs owning the transaction's accounts, each listed once.
+    pub owners: Vec<String>,
     pub transaction: PubSubTransaction,
     pub meta: PubSubTransactionMeta,
     pub account_keys: Vec<PubSubTransactionAccountKey>,
@@ -171,6 +418,9 @@ pub struct PubSubTransaction {
     pub recent_blockhash: Vec<u8>,
     pub slot: u128,
     pub blockchain: Uuid,
+    pub compute_unit_price: Option<u64>,
+    pub fee_payer: Option<String>,
+    pub is_vote: bool,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -192,6 +442,7 @@ pub struct PubSubTransactionAccountKey {
     pub signer: bool,
     pub writable: bool,
     pub index: i16,
+    pub owner: Option<String>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -211,6 +462,7 @@ pub struct PubSubTransactionLogMessage {
     pub transaction_signature: String,
     pub log: String,
     pub index: i16,
+    pub program_id: Option<String>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -235,14 +487,36 @@ pub struct PubSubTransactionTokenBalance {
 
 impl PubSubTransactionObject {
     pub fn from_db_transaction_object(db_transaction_object: DbTransactionObject) -> Self {
+        let mut owners: Vec<String> = vec![];
+        for owner in db_transaction_object
+            .account_keys
+            .iter()
+            .filter_map(|key| key.owner.as_ref())
+        {
+            if !owners.contains(owner) {
+                owners.push(owner.clone());
+            }
+        }
         PubSubTransactionObject {
+            addresses: db_transaction_object
+                .account_keys
+                .iter()
+                .map(|key| key.account.clone())
+                .collect(),
+            owners,
             transaction: PubSubTransaction {
                 id: db_transaction_object.transaction.id,
                 signature: db_transaction_object.transaction.signature,
                 version: db_transaction_object.transaction.version,
                 recent_blockhash: db_transaction_object.transaction.recent_blockhash,
-                slot: db_transaction_object.transaction.slot.to_u128().unwrap(),
+                slot: numeric::saturating_u64(&db_transaction_object.transaction.slot).into(),
                 blockchain: db_transaction_object.transaction.blockchain,
+                compute_unit_price: db_transaction_object
+                    .transaction
+                    .compute_unit_price
+                    .and_then(|price| price.to_u64()),
+                fee_payer: db_transaction_object.transaction.fee_payer,
+                is_vote: db_transaction_object.transaction.is_vote,
             },
             meta: PubSubTransactionMeta {
                 id: db_transaction_object.meta.id,
@@ -277,6 +551,7 @@ impl PubSubTransactionObject {
                     signer: x.signer,
                     writable: x.writable,
                     index: x.index,
+                    owner: x.owner.clone(),
                 })
                 .collect(),
             instructions: db_transaction_object
@@ -300,6 +575,7 @@ impl PubSubTransactionObject {
                     transaction_signature: x.transaction_signature.clone(),
                     log: x.log.clone(),
                     index: x.index,
+                    program_id: x.program_id.clone(),
                 })
                 .collect(),
             signatures: db_transaction_object
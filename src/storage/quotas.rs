@@ -0,0 +1,51 @@
+use chrono::NaiveDate;
+use uuid::Uuid;
+
+use super::cache::Cache;
+
+// Counters outlive their day briefly, so late requests across midnight still count
+const DAILY_COUNTER_TTL_SECS: i64 = 2 * 24 * 60 * 60;
+
+/// Daily quota counters in Redis, per team.
+#[derive(Clone)]
+pub struct QuotaStore {
+    cache: Cache,
+}
+
+impl QuotaStore {
+    pub fn new(cache: Cache) -> Self {
+        QuotaStore { cache }
+    }
+
+    fn transactions_key(team_id: Uuid, day: NaiveDate) -> String {
+        format!("team:{}:transactions:{}", team_id, day)
+    }
+
+    /// Counts a transaction against the day's quota, false once `limit` were counted.
+    pub fn take_transaction(
+        &self,
+        team_id: Uuid,
+        day: NaiveDate,
+        limit: u32,
+    ) -> Result<bool, String> {
+        let mut con = self.cache.get_connection()?;
+        let lua_script = r#"
+            local count = tonumber(redis.call('GET', KEYS[1]) or '0')
+            if count >= tonumber(ARGV[1]) then
+                return 0
+            end
+            redis.call('INCR', KEYS[1])
+            redis.call('EXPIRE', KEYS[1], ARGV[2])
+            return 1
+        "#;
+        let taken: i32 = redis::cmd("EVAL")
+            .arg(lua_script)
+            .arg(1)
+            .arg(Self::transactions_key(team_id, day))
+            .arg(limit)
+            .arg(DAILY_COUNTER_TTL_SECS)
+            .query(&mut *con)
+            .map_err(|e| format!("Failed to count transaction quota: {}", e))?;
+        Ok(taken == 1)
+    }
+}
@@ -1,8 +1,16 @@
-use std::sync::Arc;
+use std::{sync::Arc, time::Instant};
 
+use futures::{stream, StreamExt as _, TryStreamExt as _};
 use solana_rpc_client::nonblocking::rpc_client::RpcClient;
 use solana_sdk::{account::Account, commitment_config::CommitmentConfig, pubkey::Pubkey};
 
+use super::trace::{self, Operation};
+
+/// Most keys RPC nodes take in one getMultipleAccounts.
+pub const MAX_ACCOUNTS_PER_REQUEST: usize = 100;
+// Chunks of a large key list fetched at once
+const MAX_CONCURRENT_REQUESTS: usize = 4;
+
 #[derive(Clone)]
 pub struct Rpc {
     client: Arc<RpcClient>,
@@ -15,20 +23,43 @@ impl Rpc {
     }
 
     pub async fn get_account(&self, pubkey: &Pubkey) -> Result<Option<Account>, String> {
+        let started = Instant::now();
         let account = self
             .client
             .get_account_with_commitment(pubkey, CommitmentConfig::confirmed())
-            .await
-            .map_err(|e| e.to_string())?;
-        Ok(account.value)
+            .await;
+        trace::record(Operation::Upstream, 1, started.elapsed());
+        Ok(account.map_err(|e| e.to_string())?.value)
     }
 
+    /// Fetches the accounts in chunks the node accepts, a few at a time, in order.
     pub async fn get_accounts(&self, pubkeys: &[Pubkey]) -> Result<Vec<Option<Account>>, String> {
-        let accounts = self
-            .client
-            .get_multiple_accounts_with_commitment(pubkeys, CommitmentConfig::confirmed())
-            .await
-            .map_err(|e| e.to_string())?;
-        Ok(accounts.value)
+        // Owned chunks, the futures have to be Send for any lifetime
+        let chunks: Vec<Vec<Pubkey>> = pubkeys
+            .chunks(MAX_ACCOUNTS_PER_REQUEST)
+            .map(|chunk| chunk.to_vec())
+            .collect();
+        let requests = chunks.len() as u32;
+        let started = Instant::now();
+        let chunks: Result<Vec<Vec<Option<Account>>>, String> = stream::iter(chunks)
+            .map(|chunk| {
+                let client = self.client.clone();
+                async move {
+                    client
+                        .get_multiple_accounts_with_commitment(
+                            &chunk,
+                            CommitmentConfig::confirmed(),
+                        )
+                        .await
+                        .map(|accounts| accounts.value)
+                        .map_err(|e| e.to_string())
+                }
+            })
+            .buffered(MAX_CONCURRENT_REQUESTS)
+            .try_collect()
+            .await;
+        // Timed as a whole, the requests overlap
+        trace::record(Operation::Upstream, requests, started.elapsed());
+        Ok(chunks?.into_iter().flatten().collect())
     }
 }
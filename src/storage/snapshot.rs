@@ -0,0 +1,265 @@
+use base64::prelude::*;
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use serde::{
+    de::{self, DeserializeSeed, IgnoredAny, MapAccess, SeqAccess, Visitor},
+    Deserialize, Deserializer, Serialize,
+};
+use solana_sdk::{account::Account, pubkey::Pubkey};
+use std::{
+    fmt,
+    io::{Read, Write},
+    str::FromStr,
+};
+
+pub const SNAPSHOT_VERSION: u32 = 1;
+/// Most JSON an uploaded snapshot may inflate to, against gzip bombs. Uploads are
+/// read with `Snapshot::read_accounts`, so it bounds the time spent reading one
+/// rather than the memory.
+pub const MAX_SNAPSHOT_BYTES: u64 = 2 * 1024 * 1024 * 1024;
+
+/// Portable archive of a blockchain's accounts, stored as gzipped JSON.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct Snapshot {
+    pub version: u32,
+    pub created_at: chrono::NaiveDateTime,
+    pub slot: u64,
+    pub accounts: Vec<SnapshotAccount>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct SnapshotAccount {
+    pub address: String,
+    pub lamports: u64,
+    pub data: String, // base64
+    pub owner: String,
+    pub executable: bool,
+    pub rent_epoch: u64,
+}
+
+impl Snapshot {
+    pub fn new(slot: u64, accounts: Vec<(Pubkey, Account)>) -> Self {
+        Snapshot {
+            version: SNAPSHOT_VERSION,
+            created_at: chrono::Utc::now().naive_utc(),
+            slot,
+            accounts: accounts
+                .iter()
+                .map(|(address, account)| SnapshotAccount::new(address, account))
+                .collect(),
+        }
+    }
+
+    pub fn into_accounts(self) -> Result<Vec<(Pubkey, Account)>, String> {
+        self.accounts
+            .into_iter()
+            .map(SnapshotAccount::into_account)
+            .collect()
+    }
+
+    pub fn write_to<W: Write>(&self, writer: W) -> Result<(), String> {
+        let mut encoder = GzEncoder::new(writer, Compression::default());
+        serde_json::to_writer(&mut encoder, self).map_err(|e| e.to_string())?;
+        let mut writer = encoder.finish().map_err(|e| e.to_string())?;
+        writer.flush().map_err(|e| e.to_string())
+    }
+
+    /// Fails once the archive inflates past `max_bytes`.
+    pub fn read_from<R: Read>(reader: R, max_bytes: u64) -> Result<Self, String> {
+        let mut json = GzDecoder::new(reader).take(max_bytes + 1);
+        let snapshot = serde_json::from_reader::<_, Snapshot>(&mut json);
+        if json.limit() == 0 {
+            return Err("Snapshot is too large".to_string());
+        }
+        let snapshot = snapshot.map_err(|e| format!("Invalid snapshot: {}", e))?;
+        if snapshot.version != SNAPSHOT_VERSION {
+            return Err(format!("Unsupported snapshot version {}", snapshot.version));
+        }
+        Ok(snapshot)
+    }
+
+    /// `read_from` that hands the accounts to `each` one at a time instead of
+    /// collecting them, so an archive's accounts never have to be in memory
+    /// together. Returns the snapshot's slot.
+    pub fn read_accounts<R: Read>(
+        reader: R,
+        max_bytes: u64,
+        each: &mut dyn FnMut(Pubkey, Account) -> Result<(), String>,
+    ) -> Result<u64, String> {
+        let mut json = GzDecoder::new(reader).take(max_bytes + 1);
+        let slot = {
+            let mut deserializer = serde_json::Deserializer::from_reader(&mut json);
+            SnapshotSeed { each }
+                .deserialize(&mut deserializer)
+                .and_then(|slot| deserializer.end().map(|_| slot))
+        };
+        if json.limit() == 0 {
+            return Err("Snapshot is too large".to_string());
+        }
+        slot.map_err(|e| format!("Invalid snapshot: {}", e))
+    }
+}
+
+impl SnapshotAccount {
+    pub fn new(address: &Pubkey, account: &Account) -> Self {
+        SnapshotAccount {
+            address: address.to_string(),
+            lamports: account.lamports,
+            data: BASE64_STANDARD.encode(&account.data),
+            owner: account.owner.to_string(),
+            executable: account.executable,
+            rent_epoch: account.rent_epoch,
+        }
+    }
+
+    pub fn into_account(self) -> Result<(Pubkey, Account), String> {
+        let address = Pubkey::from_str(&self.address)
+            .map_err(|_| format!("Invalid address {}", self.address))?;
+        let owner = Pubkey::from_str(&self.owner)
+            .map_err(|_| format!("Invalid owner of {}", self.address))?;
+        let data = BASE64_STANDARD
+            .decode(&self.data)
+            .map_err(|_| format!("Invalid base64 data of {}", self.address))?;
+        Ok((
+            address,
+            Account {
+                lamports: self.lamports,
+                data,
+                owner,
+                executable: self.executable,
+                rent_epoch: self.rent_epoch,
+            },
+        ))
+    }
+}
+
+/// Reads a `Snapshot` document, passing its accounts on as they're read.
+struct SnapshotSeed<'a> {
+    each: &'a mut dyn FnMut(Pubkey, Account) -> Result<(), String>,
+}
+
+impl<'de> DeserializeSeed<'de> for SnapshotSeed<'_> {
+    type Value = u64;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<u64, D::Error> {
+        deserializer.deserialize_map(self)
+    }
+}
+
+impl<'de> Visitor<'de> for SnapshotSeed<'_> {
+    type Value = u64;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a snapshot")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<u64, A::Error> {
+        let (mut version, mut created_at, mut slot, mut accounts) = (false, false, None, false);
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                "version" => {
+                    let found: u32 = map.next_value()?;
+                    if found != SNAPSHOT_VERSION {
+                        return Err(de::Error::custom(format!(
+                            "unsupported snapshot version {}",
+                            found
+                        )));
+                    }
+                    version = true;
+                }
+                "created_at" => {
+                    map.next_value::<chrono::NaiveDateTime>()?;
+                    created_at = true;
+                }
+                "slot" => slot = Some(map.next_value()?),
+                "accounts" => {
+                    map.next_value_seed(AccountsSeed {
+                        each: &mut *self.each,
+                    })?;
+                    accounts = true;
+                }
+                _ => {
+                    map.next_value::<IgnoredAny>()?;
+                }
+            }
+        }
+        for (field, found) in [
+            ("version", version),
+            ("created_at", created_at),
+            ("accounts", accounts),
+        ] {
+            if !found {
+                return Err(de::Error::missing_field(field));
+            }
+        }
+        slot.ok_or_else(|| de::Error::missing_field("slot"))
+    }
+}
+
+struct AccountsSeed<'a> {
+    each: &'a mut dyn FnMut(Pubkey, Account) -> Result<(), String>,
+}
+
+impl<'de> DeserializeSeed<'de> for AccountsSeed<'_> {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de> Visitor<'de> for AccountsSeed<'_> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a list of accounts")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<(), A::Error> {
+        while let Some(account) = seq.next_element::<SnapshotAccount>()? {
+            let (address, account) = account.into_account().map_err(de::Error::custom)?;
+            (self.each)(address, account).map_err(de::Error::custom)?;
+        }
+        Ok(())
+    }
+}
+
+/// Writes the same archive as `Snapshot::write_to` one account at a time, so a
+/// blockchain's accounts never have to be in memory together.
+pub struct SnapshotWriter<W: Write> {
+    encoder: GzEncoder<W>,
+    accounts: usize,
+}
+
+impl<W: Write> SnapshotWriter<W> {
+    pub fn new(writer: W, slot: u64) -> Result<Self, String> {
+        let mut encoder = GzEncoder::new(writer, Compression::default());
+        let created_at =
+            serde_json::to_string(&chrono::Utc::now().naive_utc()).map_err(|e| e.to_string())?;
+        write!(
+            encoder,
+            r#"{{"version":{},"created_at":{},"slot":{},"accounts":["#,
+            SNAPSHOT_VERSION, created_at, slot
+        )
+        .map_err(|e| e.to_string())?;
+        Ok(SnapshotWriter {
+            encoder,
+            accounts: 0,
+        })
+    }
+
+    pub fn write_account(&mut self, address: &Pubkey, account: &Account) -> Result<(), String> {
+        if self.accounts > 0 {
+            self.encoder.write_all(b",").map_err(|e| e.to_string())?;
+        }
+        serde_json::to_writer(&mut self.encoder, &SnapshotAccount::new(address, account))
+            .map_err(|e| e.to_string())?;
+        self.accounts += 1;
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> Result<(), String> {
+        self.encoder.write_all(b"]}").map_err(|e| e.to_string())?;
+        let mut writer = self.encoder.finish().map_err(|e| e.to_string())?;
+        writer.flush().map_err(|e| e.to_string())
+    }
+}
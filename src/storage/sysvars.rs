@@ -0,0 +1,11 @@
+use diesel::prelude::*;
+use uuid::Uuid;
+
+#[derive(Queryable, Selectable, Insertable, AsChangeset, Clone)]
+#[diesel(table_name = crate::schema::blockchain_sysvars)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct DbBlockchainSysvars {
+    pub blockchain: Uuid,
+    pub updated_at: chrono::NaiveDateTime,
+    pub overrides: String, // SysvarOverrides JSON
+}
@@ -1,4 +1,5 @@
 use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
 
 use uuid::Uuid;
 
@@ -10,6 +11,62 @@ pub struct Team {
     pub created_at: chrono::NaiveDateTime,
     pub name: String,
     pub default_expiry: Option<i32>, // Default expiry in seconds
+    // Quotas, unset means unlimited, except blockchains: see `blockchain_limit`
+    pub max_blockchains: Option<i32>,
+    pub max_accounts: Option<i32>, // Per blockchain
+    pub max_transactions_per_day: Option<i32>,
+    // Idle blockchains are paused after this long, unset never pauses them
+    pub auto_pause_hours: Option<i32>,
+    // Mutating RPC calls with an API key of another team are rejected
+    pub rpc_write_guard: bool,
+}
+
+/// Blockchains a team may hold when it has no quota, teams whose blockchains
+/// expire by default aren't capped.
+pub const DEFAULT_MAX_BLOCKCHAINS: i32 = 10;
+
+impl Team {
+    /// Blockchains the team may hold, `None` when unlimited.
+    pub fn blockchain_limit(&self) -> Option<i32> {
+        match (self.max_blockchains, self.default_expiry) {
+            (Some(max_blockchains), _) => Some(max_blockchains),
+            (None, None) => Some(DEFAULT_MAX_BLOCKCHAINS),
+            (None, Some(_)) => None,
+        }
+    }
+
+    pub fn quotas(&self) -> TeamQuotas {
+        TeamQuotas {
+            max_blockchains: self.max_blockchains,
+            max_accounts: self.max_accounts,
+            max_transactions_per_day: self.max_transactions_per_day,
+        }
+    }
+}
+
+/// A team's quotas, set by admins. Transactions per day count UTC days.
+#[derive(AsChangeset, Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[diesel(table_name = crate::schema::teams)]
+#[diesel(treat_none_as_null = true)]
+pub struct TeamQuotas {
+    pub max_blockchains: Option<i32>,
+    pub max_accounts: Option<i32>,
+    pub max_transactions_per_day: Option<i32>,
+}
+
+impl TeamQuotas {
+    pub fn validate(&self) -> Result<(), String> {
+        for (name, quota) in [
+            ("max_blockchains", self.max_blockchains),
+            ("max_accounts", self.max_accounts),
+            ("max_transactions_per_day", self.max_transactions_per_day),
+        ] {
+            if quota.is_some_and(|quota| quota < 0) {
+                return Err(format!("{} cannot be negative", name));
+            }
+        }
+        Ok(())
+    }
 }
 
 #[derive(Queryable, QueryableByName, Selectable, Insertable, AsChangeset, Clone)]
@@ -19,4 +76,14 @@ pub struct ApiKey {
     pub id: Uuid,
     pub created_at: chrono::NaiveDateTime,
     pub team_id: Uuid,
+    pub label: String,
+    pub scope: String,
+    pub revoked_at: Option<chrono::NaiveDateTime>,
+    pub role: String, // see auth::Role
 }
+
+/// Management and RPC access.
+pub const SCOPE_FULL: &str = "full";
+/// Only read requests (GET) are allowed.
+pub const SCOPE_READ_ONLY: &str = "read_only";
+pub const API_KEY_SCOPES: [&str; 2] = [SCOPE_FULL, SCOPE_READ_ONLY];
@@ -0,0 +1,44 @@
+use super::numeric;
+use bigdecimal::BigDecimal;
+use diesel::prelude::*;
+use solana_sdk::pubkey::Pubkey;
+use std::str::FromStr;
+use uuid::Uuid;
+
+#[derive(Queryable, Selectable, Insertable, Clone, Debug)]
+#[diesel(table_name = crate::schema::top_ups)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct DbTopUp {
+    pub id: Uuid,
+    pub created_at: chrono::NaiveDateTime,
+    pub blockchain: Uuid,
+    pub address: String,      // Owner of the token account when mint is set
+    pub mint: Option<String>, // Lamports when unset
+    pub target: BigDecimal,   // Lamports or base token units
+    pub interval_seconds: i64,
+    pub last_run: Option<chrono::NaiveDateTime>,
+}
+
+impl DbTopUp {
+    pub fn is_due(&self, now: chrono::NaiveDateTime) -> bool {
+        match self.last_run {
+            Some(last_run) => now - last_run >= chrono::Duration::seconds(self.interval_seconds),
+            None => true,
+        }
+    }
+
+    pub fn address(&self) -> Result<Pubkey, String> {
+        Pubkey::from_str(&self.address).map_err(|e| e.to_string())
+    }
+
+    pub fn mint(&self) -> Result<Option<Pubkey>, String> {
+        self.mint
+            .as_deref()
+            .map(|mint| Pubkey::from_str(mint).map_err(|e| e.to_string()))
+            .transpose()
+    }
+
+    pub fn target(&self) -> Result<u64, String> {
+        Ok(numeric::to_u64("target", &self.target)?)
+    }
+}
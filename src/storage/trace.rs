@@ -0,0 +1,83 @@
+use diesel::connection::{Instrumentation, InstrumentationEvent};
+use serde::Serialize;
+use std::{
+    future::Future,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+tokio::task_local! {
+    static LOG: Arc<Mutex<OperationLog>>;
+}
+
+/// Storage work done while serving one request, to tell slow storage apart
+/// from slow execution.
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OperationLog {
+    /// Uses of a Redis connection, each a command, pipeline or script.
+    pub redis_ops: u32,
+    pub postgres_queries: u32,
+    /// Requests to the upstream RPC node.
+    pub upstream_fetches: u32,
+    /// Time spent in the operations above, in milliseconds.
+    pub storage_time_ms: f64,
+    /// Time spent on the whole request, in milliseconds.
+    pub total_time_ms: f64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Operation {
+    Redis,
+    Postgres,
+    Upstream,
+}
+
+/// Runs `future`, collecting the storage operations it does on its own task.
+/// Work it spawns, like write-behind database writes, isn't included.
+pub async fn traced<F: Future>(future: F) -> (F::Output, OperationLog) {
+    let log = Arc::new(Mutex::new(OperationLog::default()));
+    let started = Instant::now();
+    let output = LOG.scope(log.clone(), future).await;
+    let mut log = log.lock().unwrap().clone();
+    log.total_time_ms = millis(started.elapsed());
+    (output, log)
+}
+
+/// Adds `count` operations that took `elapsed` together to the enclosing
+/// `traced` call, does nothing outside one.
+pub fn record(operation: Operation, count: u32, elapsed: Duration) {
+    let _ = LOG.try_with(|log| {
+        let mut log = log.lock().unwrap();
+        match operation {
+            Operation::Redis => log.redis_ops += count,
+            Operation::Postgres => log.postgres_queries += count,
+            Operation::Upstream => log.upstream_fetches += count,
+        }
+        log.storage_time_ms += millis(elapsed);
+    });
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+/// Records the queries of a Postgres connection, installed on every pooled one.
+#[derive(Default)]
+pub struct QueryTimer {
+    started: Option<Instant>,
+}
+
+impl Instrumentation for QueryTimer {
+    fn on_connection_event(&mut self, event: InstrumentationEvent<'_>) {
+        match event {
+            InstrumentationEvent::StartQuery { .. } => self.started = Some(Instant::now()),
+            InstrumentationEvent::FinishQuery { .. } => {
+                if let Some(started) = self.started.take() {
+                    record(Operation::Postgres, 1, started.elapsed());
+                }
+            }
+            _ => {}
+        }
+    }
+}
@@ -1,6 +1,7 @@
 use crate::engine::transactions::TransactionMeta;
 use crate::engine::transactions::TransactionMetadata;
 use crate::engine::transactions::TransactionTokenBalance;
+use crate::storage::numeric;
 use bigdecimal::BigDecimal;
 use bigdecimal::ToPrimitive;
 use diesel::prelude::*;
@@ -9,11 +10,72 @@ use solana_account_decoder::parse_token::UiTokenAmount;
 use solana_sdk::pubkey::Pubkey;
 use solana_sdk::{
     account::ReadableAccount,
-    transaction::{Legacy, TransactionVersion},
+    borsh1::try_from_slice_unchecked,
+    compute_budget::{self, ComputeBudgetInstruction},
+    instruction::Instruction,
+    message::{v0::LoadedAddresses, Message, SanitizedMessage, VersionedMessage},
+    signature::Signature,
+    transaction::{
+        Legacy, SanitizedTransaction, TransactionError, TransactionVersion, VersionedTransaction,
+    },
 };
 use std::str::FromStr;
 use uuid::Uuid;
 
+/// Narrows a transaction search, `None` fields match everything.
+#[derive(Clone, Debug, Default)]
+pub struct TransactionFilter {
+    /// Any account the transaction referenced.
+    pub address: Option<String>,
+    /// Program invoked by a top level or inner instruction.
+    pub program_id: Option<String>,
+    pub failed: Option<bool>,
+    pub start: Option<chrono::NaiveDateTime>,
+    pub end: Option<chrono::NaiveDateTime>,
+    pub signature_prefix: Option<String>,
+    pub fee_payer: Option<String>,
+    pub is_vote: Option<bool>,
+    /// Micro-lamports per compute unit, transactions without a price don't match.
+    pub min_compute_unit_price: Option<u64>,
+    pub limit: i64,
+    pub offset: i64,
+}
+
+/// Recent transactions kept per blockchain for explorer feeds.
+pub const TRANSACTION_FEED_SIZE: usize = 100;
+
+/// Fields an explorer lists a transaction with, without its instructions or logs.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TransactionSummary {
+    pub signature: String,
+    pub slot: u64,
+    pub created_at: chrono::NaiveDateTime,
+    pub fee_payer: Option<String>,
+    pub fee: u64,
+    pub compute_units_consumed: u64,
+    pub err: Option<String>,
+    pub instruction_count: usize,
+}
+
+impl TransactionSummary {
+    pub fn from_object(tx: &DbTransactionObject) -> Self {
+        TransactionSummary {
+            signature: tx.transaction.signature.clone(),
+            slot: numeric::saturating_u64(&tx.transaction.slot),
+            created_at: tx.transaction.created_at,
+            fee_payer: tx
+                .account_keys
+                .iter()
+                .find(|key| key.index == 0)
+                .map(|key| key.account.clone()),
+            fee: numeric::saturating_u64(&tx.meta.fee),
+            compute_units_consumed: numeric::saturating_u64(&tx.meta.compute_units_consumed),
+            err: tx.meta.err.clone(),
+            instruction_count: tx.instructions.iter().filter(|ix| !ix.inner).count(),
+        }
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 
 pub struct DbTransactionObject {
@@ -26,6 +88,95 @@ pub struct DbTransactionObject {
     pub token_balances: Vec<DBTransactionTokenBalance>,
 }
 
+impl DbTransactionObject {
+    pub fn from_transaction(blockchain: Uuid, tx: &TransactionMetadata) -> Self {
+        let signature = tx.signature.to_string();
+        let pre_balances = tx.pre_token_balances.iter().flatten();
+        let post_balances = tx.post_token_balances.iter().flatten();
+        let token_balances = pre_balances
+            .map(|balance| DBTransactionTokenBalance::from_token_balance(balance, &signature, true))
+            .chain(post_balances.map(|balance| {
+                DBTransactionTokenBalance::from_token_balance(balance, &signature, false)
+            }))
+            .collect();
+        DbTransactionObject {
+            transaction: DbTransaction::from_transaction(blockchain, tx),
+            meta: DbTransactionMeta::from_transaction(tx),
+            account_keys: DbTransactionAccountKey::from_transaction(tx),
+            instructions: DbTransactionInstruction::from_transaction(tx),
+            log_messages: DbTransactionLogMessage::from_transaction(tx),
+            signatures: DbTransactionSignature::from_transaction(tx),
+            token_balances,
+        }
+    }
+
+    /// The transaction as it was sent, with its slot, meta, error and creation time.
+    pub fn into_transaction(
+        self,
+    ) -> (
+        VersionedTransaction,
+        u64,
+        TransactionMeta,
+        Option<TransactionError>,
+        chrono::NaiveDateTime,
+    ) {
+        let signatures = self
+            .signatures
+            .into_iter()
+            .map(|s| Signature::from_str(&s.signature).unwrap())
+            .collect();
+        let message = self
+            .transaction
+            .message
+            .as_ref()
+            .and_then(|message| bincode::deserialize::<VersionedMessage>(message).ok())
+            .unwrap_or_else(|| {
+                // Saved before the message was kept, rebuilt from its instructions, so
+                // the account order and header may differ from what was signed
+                let instructions = self
+                    .instructions
+                    .iter()
+                    .map(|i| i.to_instruction(self.account_keys.clone()))
+                    .collect::<Vec<Instruction>>();
+                VersionedMessage::Legacy(Message::new(&instructions, None))
+            });
+        let transaction = VersionedTransaction {
+            signatures,
+            message,
+        };
+
+        let err = self.meta.err.as_ref().map(|e| {
+            serde_json::from_str::<TransactionError>(e)
+                .unwrap_or(TransactionError::InvalidAccountIndex)
+        });
+        let mut metadata = self
+            .meta
+            .to_metadata(self.log_messages, self.token_balances);
+        metadata.block_time = self.transaction.block_time;
+        metadata.version = string_to_version(&self.transaction.version);
+        let mut lookup_keys: Vec<&DbTransactionAccountKey> =
+            self.account_keys.iter().filter(|key| key.lookup).collect();
+        lookup_keys.sort_by_key(|key| key.index);
+        for key in lookup_keys {
+            let Ok(address) = Pubkey::from_str(&key.account) else {
+                continue;
+            };
+            match key.writable {
+                true => metadata.loaded_addresses.writable.push(address),
+                false => metadata.loaded_addresses.readonly.push(address),
+            }
+        }
+
+        (
+            transaction,
+            numeric::saturating_u64(&self.transaction.slot),
+            metadata,
+            err,
+            self.transaction.created_at,
+        )
+    }
+}
+
 #[derive(
     Queryable,
     QueryableByName,
@@ -47,6 +198,19 @@ pub struct DbTransaction {
     pub recent_blockhash: Vec<u8>,
     pub slot: BigDecimal,
     pub blockchain: Uuid,
+    /// Unix timestamp of the slot's block, unset for transactions saved before it was recorded.
+    #[serde(default)]
+    pub block_time: Option<i64>,
+    /// The message as signed, serialized, unset for transactions saved before it was kept.
+    #[serde(default)]
+    pub message: Option<Vec<u8>>,
+    /// Micro-lamports per compute unit the transaction set, unset when it didn't.
+    #[serde(default)]
+    pub compute_unit_price: Option<BigDecimal>,
+    #[serde(default)]
+    pub fee_payer: Option<String>,
+    #[serde(default)]
+    pub is_vote: bool,
 }
 
 impl DbTransaction {
@@ -59,10 +223,68 @@ impl DbTransaction {
             recent_blockhash: meta.tx.message().recent_blockhash().to_bytes().to_vec(),
             slot: meta.current_block.block_height.into(),
             blockchain,
+            block_time: Some(meta.current_block.block_time as i64),
+            message: Some(meta.tx.to_versioned_transaction().message.serialize()),
+            compute_unit_price: compute_unit_price(&meta.tx).map(BigDecimal::from),
+            fee_payer: Some(meta.tx.message().fee_payer().to_string()),
+            is_vote: meta.tx.is_simple_vote_transaction(),
         }
     }
 }
 
+/// The price set by the transaction's last `SetComputeUnitPrice`, as the runtime reads it.
+pub fn compute_unit_price(tx: &SanitizedTransaction) -> Option<u64> {
+    tx.message()
+        .program_instructions_iter()
+        .filter(|(program_id, _)| **program_id == compute_budget::id())
+        .filter_map(|(_, ix)| try_from_slice_unchecked(&ix.data).ok())
+        .filter_map(|ix| match ix {
+            ComputeBudgetInstruction::SetComputeUnitPrice(price) => Some(price),
+            _ => None,
+        })
+        .last()
+}
+
+/// A transaction accepted into the in-process queue and not saved yet, so it can be
+/// processed again after a crash.
+#[derive(Queryable, Selectable, Insertable, Clone, Debug, PartialEq)]
+#[diesel(table_name = crate::schema::pending_transactions)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct DbPendingTransaction {
+    pub blockchain: Uuid,
+    pub signature: String,
+    pub created_at: chrono::NaiveDateTime,
+    pub instance: Option<Uuid>, // `cluster.instance_id` of the engine that queued it
+    pub transaction: Vec<u8>,   // bincode, as sent
+    pub jit: bool,
+}
+
+impl DbPendingTransaction {
+    pub fn new(
+        blockchain: Uuid,
+        instance: Option<Uuid>,
+        tx: &VersionedTransaction,
+        jit: bool,
+    ) -> Result<Self, String> {
+        Ok(DbPendingTransaction {
+            blockchain,
+            signature: tx
+                .signatures
+                .first()
+                .ok_or("Transaction must include signatures".to_string())?
+                .to_string(),
+            created_at: chrono::Utc::now().naive_utc(),
+            instance,
+            transaction: bincode::serialize(tx).map_err(|e| e.to_string())?,
+            jit,
+        })
+    }
+
+    pub fn decode(&self) -> Result<VersionedTransaction, String> {
+        bincode::deserialize(&self.transaction).map_err(|e| e.to_string())
+    }
+}
+
 pub fn version_to_string(version: &TransactionVersion) -> String {
     match version {
         TransactionVersion::Legacy(_) => "legacy".to_string(),
@@ -103,10 +325,21 @@ pub struct DbTransactionAccountKey {
     pub signer: bool,
     pub writable: bool,
     pub index: i16,
+    /// Loaded from an address lookup table rather than listed in the message.
+    #[serde(default)]
+    pub lookup: bool,
+    /// Program owning the account after the transaction, or before it when the
+    /// transaction closed the account. `None` if it never existed.
+    #[serde(default)]
+    pub owner: Option<String>,
 }
 
 impl DbTransactionAccountKey {
     pub fn from_transaction(meta: &TransactionMetadata) -> Vec<Self> {
+        let static_keys = match meta.tx.message() {
+            SanitizedMessage::V0(message) => message.message.account_keys.len(),
+            message => message.account_keys().len(),
+        };
         meta.tx
             .message()
             .account_keys()
@@ -120,11 +353,21 @@ impl DbTransactionAccountKey {
                 signer: meta.tx.message().is_signer(i),
                 writable: meta.tx.message().is_writable(i),
                 index: i as i16,
+                lookup: i >= static_keys,
+                owner: account_owner(meta, account),
             })
             .collect()
     }
 }
 
+fn account_owner(meta: &TransactionMetadata, pubkey: &Pubkey) -> Option<String> {
+    [&meta.post_accounts, &meta.pre_accounts]
+        .into_iter()
+        .filter_map(|accounts| accounts.iter().find(|(key, _)| key == pubkey))
+        .find(|(_, account)| account.lamports() > 0)
+        .map(|(_, account)| account.owner().to_string())
+}
+
 #[derive(
     Queryable,
     QueryableByName,
@@ -221,24 +464,56 @@ pub struct DbTransactionLogMessage {
     pub transaction_signature: String,
     pub log: String,
     pub index: i16,
+    /// Program executing when the line was logged, unset for lines outside any
+    /// invocation and for transactions saved before it was recorded.
+    #[serde(default)]
+    pub program_id: Option<String>,
 }
 
 impl DbTransactionLogMessage {
     pub fn from_transaction(meta: &TransactionMetadata) -> Vec<Self> {
         meta.logs
             .iter()
+            .zip(log_program_ids(&meta.logs))
             .enumerate()
-            .map(|(i, log)| DbTransactionLogMessage {
+            .map(|(i, (log, program_id))| DbTransactionLogMessage {
                 id: Uuid::new_v4(),
                 created_at: chrono::Utc::now().naive_utc(),
                 transaction_signature: meta.tx.signature().to_string(),
                 log: log.to_string(),
                 index: i as i16,
+                program_id,
             })
             .collect()
     }
 }
 
+/// The program each log line was emitted by, following the runtime's
+/// `Program <id> invoke [n]` and `Program <id> success|failed` lines.
+pub fn log_program_ids(logs: &[String]) -> Vec<Option<String>> {
+    let mut stack: Vec<String> = vec![];
+    logs.iter()
+        .map(|log| {
+            let mut words = log.split(' ');
+            let (Some("Program"), Some(program_id), Some(event)) =
+                (words.next(), words.next(), words.next())
+            else {
+                return stack.last().cloned();
+            };
+            if event == "invoke" && Pubkey::from_str(program_id).is_ok() {
+                stack.push(program_id.to_string());
+                return stack.last().cloned();
+            }
+            let current = stack.last().cloned();
+            if (event == "success" || event == "failed:") && current.as_deref() == Some(program_id)
+            {
+                stack.pop();
+            }
+            current
+        })
+        .collect()
+}
+
 #[derive(
     Queryable,
     QueryableByName,
@@ -261,6 +536,7 @@ pub struct DbTransactionMeta {
     pub fee: BigDecimal,
     pub pre_balances: Vec<i64>,
     pub post_balances: Vec<i64>,
+    pub sponsored_fee: Option<BigDecimal>,
 }
 
 impl DbTransactionMeta {
@@ -282,6 +558,7 @@ impl DbTransactionMeta {
                 .iter()
                 .map(|(_, a)| a.lamports() as i64)
                 .collect(),
+            sponsored_fee: (meta.sponsored_fee > 0).then(|| meta.sponsored_fee.into()),
         }
     }
 
@@ -301,10 +578,13 @@ impl DbTransactionMeta {
 
         TransactionMeta {
             err: self.err.clone(),
-            fee: self.fee.to_u64().unwrap(),
+            fee: numeric::saturating_u64(&self.fee),
             log_messages: logs.iter().map(|l| l.log.clone()).collect(),
             inner_instructions: Default::default(),
-            compute_units_consumed: self.compute_units_consumed.to_u64().unwrap(),
+            compute_units_consumed: numeric::saturating_u64(&self.compute_units_consumed),
+            loaded_addresses: LoadedAddresses::default(),
+            block_time: None,
+            version: TransactionVersion::LEGACY,
             pre_balances: self
                 .pre_balances
                 .iter()
@@ -359,6 +639,7 @@ impl DbTransactionMeta {
             ),
             rewards: vec![],
             status: status,
+            sponsored_fee: self.sponsored_fee.as_ref().map(numeric::saturating_u64),
         }
     }
 }
@@ -430,6 +711,76 @@ pub struct DBTransactionTokenBalance {
     pub pre_transaction: bool,
 }
 
+/// How much one of an owner's token accounts changed in a transaction.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenBalanceChange {
+    pub signature: String,
+    pub slot: u64,
+    pub block_time: Option<i64>,
+    pub account: String,
+    pub mint: String,
+    pub program_id: String,
+    pub decimals: u8,
+    /// Raw amounts, the account didn't exist before or after when zero.
+    pub pre_amount: String,
+    pub post_amount: String,
+    /// `post_amount - pre_amount`, negative when tokens left the account.
+    pub change: String,
+}
+
+impl TokenBalanceChange {
+    /// Changes of the owner's token accounts in a transaction, from its saved pre and
+    /// post balances. Accounts whose balance didn't move are left out.
+    pub fn from_balances(
+        tx: &DbTransaction,
+        account_keys: &[DbTransactionAccountKey],
+        balances: &[DBTransactionTokenBalance],
+        owner: &str,
+    ) -> Vec<Self> {
+        let mut indexes: Vec<i16> = balances
+            .iter()
+            .filter(|b| b.owner == owner)
+            .map(|b| b.account_index)
+            .collect();
+        indexes.sort();
+        indexes.dedup();
+        indexes
+            .into_iter()
+            .filter_map(|index| {
+                let find = |pre: bool| {
+                    balances.iter().find(|b| {
+                        b.account_index == index && b.pre_transaction == pre && b.owner == owner
+                    })
+                };
+                let (pre, post) = (find(true), find(false));
+                let balance = post.or(pre)?;
+                let pre_amount = pre.map(|b| b.amount.clone()).unwrap_or_default();
+                let post_amount = post.map(|b| b.amount.clone()).unwrap_or_default();
+                if pre_amount == post_amount {
+                    return None;
+                }
+                let account = account_keys
+                    .iter()
+                    .find(|key| key.index == index)
+                    .map(|key| key.account.clone())?;
+                Some(TokenBalanceChange {
+                    signature: tx.signature.clone(),
+                    slot: numeric::saturating_u64(&tx.slot),
+                    block_time: tx.block_time,
+                    account,
+                    mint: balance.mint.clone(),
+                    program_id: balance.program_id.clone(),
+                    decimals: balance.decimals as u8,
+                    change: (&post_amount - &pre_amount).to_string(),
+                    pre_amount: pre_amount.to_string(),
+                    post_amount: post_amount.to_string(),
+                })
+            })
+            .collect()
+    }
+}
+
 impl DBTransactionTokenBalance {
     pub fn from_token_balance(meta: &TransactionTokenBalance, tx_sig: &str, pre_tx: bool) -> Self {
         DBTransactionTokenBalance {
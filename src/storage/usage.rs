@@ -0,0 +1,26 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Storage footprint of a blockchain, maintained incrementally by the cache
+/// on every write.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct StorageUsage {
+    pub accounts: u64,
+    pub account_data_bytes: u64,
+    pub transactions: u64,
+    pub blocks: u64,
+    pub cache_bytes: u64,
+}
+
+impl StorageUsage {
+    pub fn from_counters(counters: HashMap<String, i64>) -> Self {
+        let get = |key: &str| counters.get(key).map(|v| (*v).max(0) as u64).unwrap_or(0);
+        StorageUsage {
+            accounts: get("accounts"),
+            account_data_bytes: get("account_data_bytes"),
+            transactions: get("transactions"),
+            blocks: get("blocks"),
+            cache_bytes: get("cache_bytes"),
+        }
+    }
+}
@@ -0,0 +1,39 @@
+use diesel::prelude::*;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+#[derive(Queryable, Selectable, Insertable, AsChangeset, Clone, Debug)]
+#[diesel(table_name = crate::schema::webhooks)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct DbWebhook {
+    pub id: Uuid,
+    pub created_at: chrono::NaiveDateTime,
+    pub team_id: Uuid,
+    pub blockchain: Uuid,
+    pub url: String,
+    pub secret: String,        // HMAC key for the X-Mirror-Signature header
+    pub events: Vec<String>,   // transaction, account, expiring
+    pub accounts: Vec<String>, // Only deliver events touching these, empty for all
+    pub labels: Vec<String>,   // Or accounts with these labels
+}
+
+impl DbWebhook {
+    pub fn wants(&self, event: &str) -> bool {
+        self.events.iter().any(|e| e == event)
+    }
+
+    /// `labeled` holds the label of each address currently carrying one.
+    pub fn matches_accounts<'a>(
+        &self,
+        mut addresses: impl Iterator<Item = &'a String>,
+        labeled: &HashMap<String, String>,
+    ) -> bool {
+        (self.accounts.is_empty() && self.labels.is_empty())
+            || addresses.any(|address| {
+                self.accounts.contains(address)
+                    || labeled
+                        .get(address)
+                        .is_some_and(|label| self.labels.contains(label))
+            })
+    }
+}
@@ -0,0 +1,31 @@
+use actix_web::rt::{self, time};
+use std::{sync::Arc, time::Duration};
+
+use crate::{
+    engine::{SvmEngine, SVM},
+    reporting,
+    storage::Storage,
+};
+
+/// Periodically deletes zero-lamport accounts without data that weren't deleted by
+/// the transaction draining them. Each account is checked again in the same step
+/// that deletes it, so one funded after it was found is kept.
+pub fn start<T: Storage + Clone + 'static>(svm: Arc<SvmEngine<T>>, interval: Duration) {
+    rt::spawn(async move {
+        let mut interval = time::interval(interval);
+        loop {
+            interval.tick().await;
+            match svm.sweep_empty_accounts() {
+                Ok(0) => {}
+                Ok(deleted) => println!("Deleted {} empty accounts", deleted),
+                Err(e) => {
+                    println!("Failed to sweep empty accounts: {}", e);
+                    reporting::capture_error(
+                        &format!("Failed to sweep empty accounts: {}", e),
+                        &[],
+                    );
+                }
+            }
+        }
+    });
+}
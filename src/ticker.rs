@@ -0,0 +1,23 @@
+use actix_web::rt::{self, time};
+use std::{sync::Arc, time::Duration};
+
+use crate::{
+    engine::{SvmEngine, SVM},
+    reporting,
+    storage::Storage,
+};
+
+/// Produces blocks for the blockchains clients read a blockhash from, at most
+/// one per blockchain each interval. Reading a blockhash never writes a block.
+pub fn start<T: Storage + Clone + 'static>(svm: Arc<SvmEngine<T>>, interval: Duration) {
+    rt::spawn(async move {
+        let mut interval = time::interval(interval);
+        loop {
+            interval.tick().await;
+            if let Err(e) = svm.produce_blocks() {
+                println!("Failed to produce blocks: {}", e);
+                reporting::capture_error(&format!("Failed to produce blocks: {}", e), &[]);
+            }
+        }
+    });
+}
@@ -0,0 +1,27 @@
+use actix_web::rt::{self, time};
+use std::{sync::Arc, time::Duration};
+
+use crate::{
+    engine::{SvmEngine, SVM},
+    reporting,
+    storage::Storage,
+};
+
+/// Periodically tops up balances per the blockchains' top-ups. Every instance runs
+/// it, each due top-up is claimed by one of them.
+pub fn start<T: Storage + Clone + 'static>(svm: Arc<SvmEngine<T>>, interval: Duration) {
+    rt::spawn(async move {
+        let mut interval = time::interval(interval);
+        loop {
+            interval.tick().await;
+            match svm.run_top_ups().await {
+                Ok(0) => {}
+                Ok(topped_up) => println!("Topped up {} balances", topped_up),
+                Err(e) => {
+                    println!("Failed to run top-ups: {}", e);
+                    reporting::capture_error(&format!("Failed to run top-ups: {}", e), &[]);
+                }
+            }
+        }
+    });
+}
@@ -0,0 +1,115 @@
+use actix_web::rt::{self, time};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+    time::Duration,
+};
+use tokio::sync::mpsc;
+use uuid::Uuid;
+
+use crate::{
+    engine::{SvmEngine, SVM},
+    reporting,
+    storage::Storage,
+};
+
+type PoolKey = (Uuid, Option<Uuid>);
+
+/// Keeps a few blockchains provisioned ahead of time per team and config, so
+/// `POST /blockchains` can hand one out without writing its accounts. A team's
+/// pool is created after its first blockchain and refilled after every claim.
+#[derive(Clone, Default)]
+pub struct WarmPool {
+    refills: Option<mpsc::UnboundedSender<PoolKey>>,
+}
+
+impl WarmPool {
+    /// Starts refilling pools up to `size` blockchains, pools are also topped up every
+    /// `interval`. Each instance runs it, so a pool may briefly hold more than `size`.
+    pub fn start<T: Storage + Clone + 'static>(
+        svm: Arc<SvmEngine<T>>,
+        size: usize,
+        interval: Duration,
+    ) -> Self {
+        let (refills, mut requests) = mpsc::unbounded_channel();
+        rt::spawn(async move {
+            let mut keys = HashSet::new();
+            let mut interval = time::interval(interval);
+            loop {
+                tokio::select! {
+                    _ = interval.tick() => {}
+                    key = requests.recv() => match key {
+                        Some(key) => {
+                            keys.insert(key);
+                        }
+                        None => return,
+                    },
+                }
+                // Provisioning writes to storage, which blocks
+                let svm = svm.clone();
+                keys = match rt::task::spawn_blocking(move || {
+                    refill(&svm, &mut keys, size);
+                    keys
+                })
+                .await
+                {
+                    Ok(keys) => keys,
+                    Err(e) => {
+                        println!("Warm pool refill panicked: {}", e);
+                        // Pools still holding blockchains are counted again next time
+                        HashSet::new()
+                    }
+                };
+            }
+        });
+        WarmPool {
+            refills: Some(refills),
+        }
+    }
+
+    /// Asks for the pool of a team and config to be topped up, does nothing when the
+    /// warm pool is disabled.
+    pub fn refill(&self, team_id: Uuid, config: Option<Uuid>) {
+        if let Some(refills) = &self.refills {
+            let _ = refills.send((team_id, config));
+        }
+    }
+}
+
+fn refill<T: Storage + Clone + 'static>(
+    svm: &SvmEngine<T>,
+    keys: &mut HashSet<PoolKey>,
+    size: usize,
+) {
+    let pooled = match svm.storage.count_pooled_blockchains() {
+        Ok(pooled) => pooled,
+        Err(e) => {
+            println!("Failed to count warm pool blockchains: {}", e);
+            reporting::capture_error(
+                &format!("Failed to count warm pool blockchains: {}", e),
+                &[],
+            );
+            return;
+        }
+    };
+    // Pools left by earlier runs keep being refilled after a restart
+    let counts: HashMap<PoolKey, i64> = pooled
+        .into_iter()
+        .map(|(team_id, config, count)| ((team_id, config), count))
+        .collect();
+    keys.extend(counts.keys().copied());
+
+    for (team_id, config) in keys.iter().copied() {
+        let count = counts.get(&(team_id, config)).copied().unwrap_or(0) as usize;
+        if count >= size {
+            continue;
+        }
+        if let Err(e) = svm.fill_warm_pool(team_id, config, size - count) {
+            println!("Failed to fill warm pool of team {}: {}", team_id, e);
+            reporting::capture_error(
+                &format!("Failed to fill warm pool: {}", e),
+                &[("team", team_id.to_string())],
+            );
+        }
+    }
+}
@@ -0,0 +1,315 @@
+use actix_web::rt::{self, time};
+use hmac::{Hmac, Mac, NewMac};
+use hyper::client::connect::dns::Name;
+use serde_json::{json, Value};
+use sha2::Sha256;
+use std::{
+    collections::{HashMap, HashSet},
+    net::{IpAddr, SocketAddr},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tokio::sync::broadcast::error::RecvError;
+use uuid::Uuid;
+
+use crate::{
+    reporting,
+    storage::{events::BusEvent, webhooks::DbWebhook, Storage},
+};
+
+pub const TRANSACTION_EVENT: &str = "transaction";
+pub const ACCOUNT_EVENT: &str = "account";
+pub const EXPIRING_EVENT: &str = "expiring";
+pub const WEBHOOK_EVENTS: [&str; 3] = [TRANSACTION_EVENT, ACCOUNT_EVENT, EXPIRING_EVENT];
+
+const MAX_ATTEMPTS: u32 = 4;
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+// Webhook and account label changes take up to this long to reach the dispatcher
+const WEBHOOK_CACHE_TTL: Duration = Duration::from_secs(30);
+const EXPIRY_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+const EXPIRING_WINDOW: chrono::Duration = chrono::Duration::minutes(15);
+
+/// Hex encoded HMAC-SHA256 of the body, sent as `X-Mirror-Signature: sha256=<hex>`.
+pub fn sign(secret: &str, body: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_varkey(secret.as_bytes()).expect("HMAC accepts any key");
+    mac.update(body);
+    hex::encode(mac.finalize().into_bytes())
+}
+
+/// Checks a url before it's registered: http(s) only, and every address its host
+/// resolves to has to be public, so webhooks can't reach internal services.
+pub async fn validate_url(url: &str) -> Result<(), String> {
+    let url = reqwest::Url::parse(url).map_err(|_| "Invalid webhook url".to_string())?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err("Webhook url must use http or https".to_string());
+    }
+    let host = url
+        .host_str()
+        .ok_or("Invalid webhook url".to_string())?
+        .trim_start_matches('[')
+        .trim_end_matches(']');
+    let port = url.port_or_known_default().unwrap_or(443);
+    let addresses: Vec<IpAddr> = tokio::net::lookup_host((host, port))
+        .await
+        .map_err(|_| format!("Failed to resolve {}", host))?
+        .map(|address| address.ip())
+        .collect();
+    if addresses.is_empty() {
+        return Err(format!("Failed to resolve {}", host));
+    }
+    if !addresses.into_iter().all(is_public) {
+        return Err("Webhook url must point to a public address".to_string());
+    }
+    Ok(())
+}
+
+/// False for loopback, private, link-local and other addresses that aren't
+/// reachable over the internet.
+pub fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [first, second, ..] = ip.octets();
+            !(ip.is_loopback()
+                || ip.is_private()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || ip.is_multicast()
+                || ip.is_documentation()
+                || first == 0
+                // Carrier-grade NAT, 100.64.0.0/10
+                || (first == 100 && second & 0xc0 == 64))
+        }
+        IpAddr::V6(ip) => {
+            if let Some(ip) = ip.to_ipv4_mapped() {
+                return is_public(IpAddr::V4(ip));
+            }
+            let first = ip.segments()[0];
+            !(ip.is_loopback()
+                || ip.is_unspecified()
+                || ip.is_multicast()
+                // Unique local, fc00::/7
+                || first & 0xfe00 == 0xfc00
+                // Link-local, fe80::/10
+                || first & 0xffc0 == 0xfe80)
+        }
+    }
+}
+
+/// Resolves hosts when webhooks are sent, skipping addresses that aren't public:
+/// a host can point elsewhere since its url was validated.
+struct PublicResolver;
+
+impl reqwest::dns::Resolve for PublicResolver {
+    fn resolve(&self, name: Name) -> reqwest::dns::Resolving {
+        Box::pin(async move {
+            let addresses: Vec<SocketAddr> = tokio::net::lookup_host((name.as_str(), 0))
+                .await?
+                .filter(|address| is_public(address.ip()))
+                .collect();
+            if addresses.is_empty() {
+                return Err(format!("{} has no public address", name.as_str()).into());
+            }
+            Ok(Box::new(addresses.into_iter()) as reqwest::dns::Addrs)
+        })
+    }
+}
+
+/// Client webhooks are sent with. It doesn't follow redirects, which could lead
+/// to internal services.
+pub fn client() -> reqwest::Client {
+    reqwest::Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .redirect(reqwest::redirect::Policy::none())
+        .dns_resolver(Arc::new(PublicResolver))
+        .build()
+        .expect("Failed to create webhook client")
+}
+
+/// Delivers the events written through `storage`, plus expiry warnings, to the
+/// registered webhooks.
+pub fn start<T: Storage + Clone + 'static>(storage: T) {
+    let client = client();
+
+    let mut events = storage.subscribe_local_events();
+    let event_storage = storage.clone();
+    let event_client = client.clone();
+    rt::spawn(async move {
+        let mut webhooks: HashMap<Uuid, CachedWebhooks> = HashMap::new();
+        loop {
+            let (blockchain, event) = match events.recv().await {
+                Ok(event) => event,
+                Err(RecvError::Lagged(skipped)) => {
+                    println!("Webhook dispatcher skipped {} events", skipped);
+                    continue;
+                }
+                Err(RecvError::Closed) => return,
+            };
+            let (kind, data) = match &event {
+                BusEvent::Transaction {
+                    signature,
+                    accounts,
+                } => (
+                    TRANSACTION_EVENT,
+                    json!({ "signature": signature, "accounts": accounts }),
+                ),
+                BusEvent::Account { address } => (ACCOUNT_EVENT, json!({ "address": address })),
+                BusEvent::Block { .. } => continue,
+            };
+
+            let stale = webhooks
+                .get(&blockchain)
+                .is_none_or(|cached| cached.fetched_at.elapsed() >= WEBHOOK_CACHE_TTL);
+            if stale {
+                match load_webhooks(&event_storage, blockchain) {
+                    Ok(cached) => {
+                        webhooks.insert(blockchain, cached);
+                    }
+                    Err(e) => {
+                        println!("Failed to load webhooks of {}: {}", blockchain, e);
+                        reporting::capture_error(
+                            &format!("Failed to load webhooks: {}", e),
+                            &[("blockchain", blockchain.to_string())],
+                        );
+                        continue;
+                    }
+                }
+            }
+            let CachedWebhooks {
+                webhooks: blockchain_webhooks,
+                labeled,
+                ..
+            } = &webhooks[&blockchain];
+
+            for webhook in blockchain_webhooks {
+                let matches = match &event {
+                    BusEvent::Transaction { accounts, .. } => {
+                        webhook.matches_accounts(accounts.iter(), labeled)
+                    }
+                    BusEvent::Account { address } => {
+                        webhook.matches_accounts(std::iter::once(address), labeled)
+                    }
+                    BusEvent::Block { .. } => false,
+                };
+                if webhook.wants(kind) && matches {
+                    deliver(event_client.clone(), webhook.clone(), kind, data.clone());
+                }
+            }
+        }
+    });
+
+    rt::spawn(async move {
+        let mut interval = time::interval(EXPIRY_CHECK_INTERVAL);
+        // Keyed by expiry as well, so extending a blockchain re-arms the warning
+        let mut notified: HashSet<(Uuid, chrono::NaiveDateTime)> = HashSet::new();
+        loop {
+            interval.tick().await;
+            let now = chrono::Utc::now().naive_utc();
+            notified.retain(|(_, expiry)| *expiry > now);
+
+            let webhooks = match storage.get_webhooks_for_event(EXPIRING_EVENT) {
+                Ok(webhooks) => webhooks,
+                Err(e) => {
+                    println!("Failed to load expiry webhooks: {}", e);
+                    reporting::capture_error(
+                        &format!("Failed to load expiry webhooks: {}", e),
+                        &[],
+                    );
+                    continue;
+                }
+            };
+            for webhook in webhooks {
+                let expiry = match storage.get_blockchain(webhook.blockchain) {
+                    Ok(blockchain) => match blockchain.expiry {
+                        Some(expiry) => expiry,
+                        None => continue,
+                    },
+                    Err(_) => continue,
+                };
+                if expiry <= now || expiry - now > EXPIRING_WINDOW {
+                    continue;
+                }
+                if notified.insert((webhook.id, expiry)) {
+                    deliver(
+                        client.clone(),
+                        webhook,
+                        EXPIRING_EVENT,
+                        json!({ "expiry": expiry }),
+                    );
+                }
+            }
+        }
+    });
+}
+
+/// The blockchain's webhooks and the label of each address carrying one they filter on.
+/// A blockchain's webhooks, with the labels of the addresses labeled as they ask for.
+struct CachedWebhooks {
+    fetched_at: Instant,
+    webhooks: Vec<DbWebhook>,
+    // Label by address
+    labeled: HashMap<String, String>,
+}
+
+fn load_webhooks<T: Storage>(storage: &T, blockchain: Uuid) -> Result<CachedWebhooks, String> {
+    let webhooks = storage.get_webhooks(blockchain)?;
+    let mut labels: Vec<String> = webhooks
+        .iter()
+        .flat_map(|webhook| webhook.labels.iter().cloned())
+        .collect();
+    labels.sort();
+    labels.dedup();
+    let mut labeled = HashMap::new();
+    for label in labels {
+        for address in storage.get_labeled_accounts(blockchain, std::slice::from_ref(&label))? {
+            labeled.insert(address.to_string(), label.clone());
+        }
+    }
+    Ok(CachedWebhooks {
+        fetched_at: Instant::now(),
+        webhooks,
+        labeled,
+    })
+}
+
+fn deliver(client: reqwest::Client, webhook: DbWebhook, kind: &'static str, data: Value) {
+    rt::spawn(async move {
+        let delivery = Uuid::new_v4();
+        let body = json!({
+            "id": delivery,
+            "type": kind,
+            "blockchain": webhook.blockchain,
+            "created_at": chrono::Utc::now().naive_utc(),
+            "data": data,
+        })
+        .to_string();
+        let signature = sign(&webhook.secret, body.as_bytes());
+
+        let mut backoff = Duration::from_secs(1);
+        for attempt in 1..=MAX_ATTEMPTS {
+            let res = client
+                .post(&webhook.url)
+                .header("content-type", "application/json")
+                .header("X-Mirror-Event", kind)
+                .header("X-Mirror-Delivery", delivery.to_string())
+                .header("X-Mirror-Signature", format!("sha256={}", signature))
+                .body(body.clone())
+                .send()
+                .await;
+            match res {
+                Ok(res) if res.status().is_success() => return,
+                Ok(res) => println!(
+                    "Webhook {} attempt {} failed with status {}",
+                    webhook.id,
+                    attempt,
+                    res.status()
+                ),
+                Err(e) => println!("Webhook {} attempt {} failed: {}", webhook.id, attempt, e),
+            }
+            if attempt < MAX_ATTEMPTS {
+                time::sleep(backoff).await;
+                backoff *= 2;
+            }
+        }
+    });
+}
@@ -0,0 +1,301 @@
+use actix_web::rt::{task, time};
+use base64::prelude::*;
+use mockchain_engine::{embedded::EmbeddedEngine, engine::SVM, storage::audit};
+use serde_json::{json, Value};
+use solana_sdk::{
+    bpf_loader_upgradeable::{self, UpgradeableLoaderState},
+    hash::Hash,
+    instruction::{AccountMeta, Instruction},
+    rent::Rent,
+    signature::Keypair,
+    signer::Signer,
+    system_instruction,
+    transaction::Transaction,
+};
+use std::{process::Command, str::FromStr, time::Duration};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpStream,
+};
+
+use crate::common::processed;
+
+const MEMO: &[u8] = include_bytes!("../../src/engine/spl/programs/spl_memo-3.0.0.so");
+// What the solana CLI writes per transaction
+const WRITE_CHUNK: usize = 900;
+
+/// Sends the transaction, waits for it to be processed and checks that it succeeded.
+async fn send(
+    engine: &EmbeddedEngine,
+    instructions: &[Instruction],
+    signers: &[&Keypair],
+) -> Value {
+    let blockhash = engine.rpc("getLatestBlockhash", json!([])).await.unwrap();
+    let blockhash = Hash::from_str(blockhash["value"]["blockhash"].as_str().unwrap()).unwrap();
+    let tx = Transaction::new_signed_with_payer(
+        instructions,
+        Some(&signers[0].pubkey()),
+        signers,
+        blockhash,
+    );
+    let tx = BASE64_STANDARD.encode(bincode::serialize(&tx).unwrap());
+    let signature = engine
+        .rpc("sendTransaction", json!([tx, {"encoding": "base64"}]))
+        .await
+        .unwrap();
+    assert!(
+        processed(
+            engine.svm(),
+            engine.blockchain(),
+            signature.as_str().unwrap()
+        )
+        .await,
+        "{} was not processed",
+        signature
+    );
+    let statuses = engine
+        .rpc("getSignatureStatuses", json!([[signature]]))
+        .await
+        .unwrap();
+    assert_eq!(
+        statuses["value"][0]["err"],
+        Value::Null,
+        "{} failed",
+        signature
+    );
+    signature
+}
+
+async fn deploy(engine: &EmbeddedEngine, payer: &Keypair, program: &Keypair, elf: &[u8]) {
+    let buffer = Keypair::new();
+    let rent = Rent::default();
+    let buffer_lamports = rent.minimum_balance(UpgradeableLoaderState::size_of_buffer(elf.len()));
+    let create = bpf_loader_upgradeable::create_buffer(
+        &payer.pubkey(),
+        &buffer.pubkey(),
+        &payer.pubkey(),
+        buffer_lamports,
+        elf.len(),
+    )
+    .unwrap();
+    send(engine, &create, &[payer, &buffer]).await;
+    for (idx, chunk) in elf.chunks(WRITE_CHUNK).enumerate() {
+        let write = bpf_loader_upgradeable::write(
+            &buffer.pubkey(),
+            &payer.pubkey(),
+            (idx * WRITE_CHUNK) as u32,
+            chunk.to_vec(),
+        );
+        send(engine, &[write], &[payer]).await;
+    }
+    let program_lamports = rent.minimum_balance(UpgradeableLoaderState::size_of_program());
+    #[allow(deprecated)]
+    let deploy = bpf_loader_upgradeable::deploy_with_max_program_len(
+        &payer.pubkey(),
+        &program.pubkey(),
+        &buffer.pubkey(),
+        &payer.pubkey(),
+        program_lamports,
+        elf.len() * 2,
+    )
+    .unwrap();
+    send(engine, &deploy, &[payer, program]).await;
+}
+
+#[actix_web::test]
+async fn test_deploy_over_rpc() {
+    let engine = EmbeddedEngine::new().unwrap();
+    let payer = engine.payer();
+    let program = Keypair::new();
+    deploy(&engine, &payer, &program, MEMO).await;
+
+    let account = engine
+        .rpc(
+            "getAccountInfo",
+            json!([program.pubkey().to_string(), {"encoding": "base64"}]),
+        )
+        .await
+        .unwrap();
+    assert_eq!(account["value"]["executable"], json!(true));
+
+    // On the timeline however many buffer writes came before
+    let timeline = engine
+        .svm()
+        .timeline(engine.blockchain(), None, 10)
+        .unwrap();
+    let deploys: Vec<_> = timeline
+        .iter()
+        .filter(|event| event.kind == audit::PROGRAM_DEPLOY)
+        .collect();
+    assert_eq!(deploys.len(), 1);
+    assert_eq!(
+        deploys[0].details["program_id"],
+        json!(program.pubkey().to_string())
+    );
+    assert_eq!(deploys[0].source, "transaction");
+
+    let memo = Instruction::new_with_bytes(
+        program.pubkey(),
+        b"deployed",
+        vec![AccountMeta::new_readonly(payer.pubkey(), true)],
+    );
+    send(&engine, &[memo], &[&payer]).await;
+}
+
+#[actix_web::test]
+async fn test_blockhash_validity() {
+    let engine = EmbeddedEngine::new().unwrap();
+    let res = engine.rpc("getLatestBlockhash", json!([])).await.unwrap();
+    let height = engine.rpc("getBlockHeight", json!([])).await.unwrap();
+    let last_valid = res["value"]["lastValidBlockHeight"].as_u64().unwrap();
+    assert!(last_valid > height.as_u64().unwrap());
+
+    let nodes = engine.rpc("getClusterNodes", json!([])).await.unwrap();
+    assert_eq!(nodes.as_array().unwrap().len(), 1);
+    assert!(nodes[0]["pubkey"].is_string());
+}
+
+/// Just enough of a websocket client for one subscription: text frames only.
+struct WsClient {
+    stream: TcpStream,
+}
+
+impl WsClient {
+    async fn connect(url: &str) -> Self {
+        let url = reqwest::Url::parse(url).unwrap();
+        let host = url.host_str().unwrap();
+        let port = url.port().unwrap();
+        let mut stream = TcpStream::connect((host, port)).await.unwrap();
+        let request = format!(
+            "GET {} HTTP/1.1\r\nHost: {}:{}\r\nUpgrade: websocket\r\nConnection: Upgrade\r\n\
+             Sec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\nSec-WebSocket-Version: 13\r\n\r\n",
+            url.path(),
+            host,
+            port
+        );
+        stream.write_all(request.as_bytes()).await.unwrap();
+        let mut response = Vec::new();
+        while !response.ends_with(b"\r\n\r\n") {
+            response.push(stream.read_u8().await.unwrap());
+        }
+        assert!(response.starts_with(b"HTTP/1.1 101"));
+        WsClient { stream }
+    }
+
+    async fn send(&mut self, message: Value) {
+        let payload = message.to_string().into_bytes();
+        let mut frame = vec![0x81];
+        match payload.len() {
+            len @ 0..=125 => frame.push(0x80 | len as u8),
+            len => {
+                frame.push(0x80 | 126);
+                frame.extend_from_slice(&(len as u16).to_be_bytes());
+            }
+        }
+        let mask = [1u8, 2, 3, 4];
+        frame.extend_from_slice(&mask);
+        frame.extend(payload.iter().enumerate().map(|(i, b)| b ^ mask[i % 4]));
+        self.stream.write_all(&frame).await.unwrap();
+    }
+
+    async fn receive(&mut self) -> Value {
+        loop {
+            let mut header = [0u8; 2];
+            self.stream.read_exact(&mut header).await.unwrap();
+            let len = match header[1] & 0x7f {
+                126 => self.stream.read_u16().await.unwrap() as usize,
+                127 => self.stream.read_u64().await.unwrap() as usize,
+                len => len as usize,
+            };
+            let mut payload = vec![0u8; len];
+            self.stream.read_exact(&mut payload).await.unwrap();
+            if header[0] & 0x0f == 0x1 {
+                return serde_json::from_slice(&payload).unwrap();
+            }
+        }
+    }
+}
+
+#[actix_web::test]
+async fn test_signature_subscribe() {
+    let mut engine = EmbeddedEngine::new().unwrap();
+    engine.listen().unwrap();
+    let payer = engine.payer();
+
+    let blockhash = engine.rpc("getLatestBlockhash", json!([])).await.unwrap();
+    let blockhash = Hash::from_str(blockhash["value"]["blockhash"].as_str().unwrap()).unwrap();
+    let tx = Transaction::new_signed_with_payer(
+        &[system_instruction::transfer(
+            &payer.pubkey(),
+            &Keypair::new().pubkey(),
+            1_000_000,
+        )],
+        Some(&payer.pubkey()),
+        &[&payer],
+        blockhash,
+    );
+
+    // Subscribe before sending, like confirmTransaction does
+    let mut ws = WsClient::connect(&engine.ws_url().unwrap()).await;
+    ws.send(json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "signatureSubscribe",
+        "params": [tx.signatures[0].to_string(), {"commitment": "confirmed"}],
+    }))
+    .await;
+    let subscribed = ws.receive().await;
+    assert!(subscribed["result"].is_u64());
+
+    let encoded = BASE64_STANDARD.encode(bincode::serialize(&tx).unwrap());
+    engine
+        .rpc("sendTransaction", json!([encoded, {"encoding": "base64"}]))
+        .await
+        .unwrap();
+    let notification = time::timeout(Duration::from_secs(5), ws.receive())
+        .await
+        .expect("No signature notification");
+    assert_eq!(notification["method"], json!("signatureNotification"));
+    assert_eq!(
+        notification["params"]["result"]["value"]["err"],
+        Value::Null
+    );
+    assert!(notification["params"]["result"]["context"]["slot"].is_u64());
+    engine.stop().await;
+}
+
+/// Runs `anchor test` for the workspace in ANCHOR_WORKSPACE against the engine. Needs
+/// the anchor CLI, the solana CLI and node on the path, so it only runs when asked:
+///
+/// ANCHOR_WORKSPACE=path/to/workspace cargo test anchor::test_anchor_workspace -- --ignored
+#[actix_web::test]
+#[ignore]
+async fn test_anchor_workspace() {
+    let workspace = std::env::var("ANCHOR_WORKSPACE").expect("ANCHOR_WORKSPACE is not set");
+    let mut engine = EmbeddedEngine::new().unwrap();
+    engine.listen().unwrap();
+    let url = engine.rpc_url().unwrap();
+
+    // The wallet anchor deploys and pays with
+    let wallet = std::env::temp_dir().join(format!("{}.json", engine.blockchain()));
+    let payer = engine.payer();
+    std::fs::write(&wallet, json!(payer.to_bytes().to_vec()).to_string()).unwrap();
+
+    // Off the runtime, which keeps serving the engine meanwhile
+    let wallet_path = wallet.clone();
+    let status = task::spawn_blocking(move || {
+        Command::new("anchor")
+            .args(["test", "--skip-local-validator", "--provider.cluster"])
+            .arg(&url)
+            .arg("--provider.wallet")
+            .arg(&wallet_path)
+            .current_dir(&workspace)
+            .status()
+    })
+    .await
+    .unwrap()
+    .unwrap();
+    std::fs::remove_file(&wallet).ok();
+    assert!(status.success(), "anchor test failed");
+    engine.stop().await;
+}
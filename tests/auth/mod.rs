@@ -0,0 +1,330 @@
+use actix_web::{http::StatusCode, test::TestRequest};
+use base64::prelude::*;
+use hmac::{Hmac, Mac, NewMac};
+use mockchain_engine::{
+    auth::{
+        api_key_role, check_blockchain_write, check_rpc_write,
+        jwt::{self, JwkSet},
+        Role,
+    },
+    engine::{SvmEngine, SVM},
+    rpc::rpc::RpcMethod,
+    settings::JwtSettings,
+    storage::{
+        blocks::DbBlockchainUpdate,
+        memory::MemoryStorage,
+        teams::{ApiKey, Team, SCOPE_FULL, SCOPE_READ_ONLY},
+        Storage,
+    },
+};
+use ring::{
+    rand::SystemRandom,
+    signature::{EcdsaKeyPair, KeyPair, ECDSA_P256_SHA256_FIXED_SIGNING},
+};
+use serde_json::json;
+use sha2::Sha256;
+use std::str::FromStr;
+use uuid::Uuid;
+
+fn api_key(scope: &str, role: &str) -> ApiKey {
+    ApiKey {
+        id: Uuid::new_v4(),
+        created_at: chrono::Utc::now().naive_utc(),
+        team_id: Uuid::new_v4(),
+        label: "test".to_string(),
+        scope: scope.to_string(),
+        revoked_at: None,
+        role: role.to_string(),
+    }
+}
+
+#[test]
+fn test_role_order() {
+    assert!(Role::Owner > Role::Developer);
+    assert!(Role::Developer > Role::ReadOnly);
+    assert_eq!(Role::from_str("developer"), Ok(Role::Developer));
+    assert!(Role::from_str("admin").is_err());
+}
+
+#[test]
+fn test_api_key_role() {
+    assert_eq!(api_key_role(&api_key(SCOPE_FULL, "owner")), Role::Owner);
+    // Read-only scope caps the role
+    assert_eq!(
+        api_key_role(&api_key(SCOPE_READ_ONLY, "owner")),
+        Role::ReadOnly
+    );
+    assert_eq!(
+        api_key_role(&api_key(SCOPE_FULL, "unknown")),
+        Role::ReadOnly
+    );
+}
+
+fn team(rpc_write_guard: bool) -> Team {
+    Team {
+        id: Uuid::new_v4(),
+        created_at: chrono::Utc::now().naive_utc(),
+        name: "guard".to_string(),
+        default_expiry: None,
+        max_blockchains: None,
+        max_accounts: None,
+        max_transactions_per_day: None,
+        auto_pause_hours: None,
+        rpc_write_guard,
+    }
+}
+
+#[test]
+fn test_rpc_write_guard() {
+    assert!(RpcMethod::SendTransaction.is_mutating());
+    assert!(RpcMethod::RequestAirdrop.is_mutating());
+    assert!(!RpcMethod::GetBalance.is_mutating());
+
+    let storage = MemoryStorage::new();
+    let (owner, other, open) = (team(true), team(false), team(false));
+    for team in [&owner, &other, &open] {
+        storage.create_team(team.clone());
+    }
+    let mut owner_key = api_key(SCOPE_FULL, "developer");
+    owner_key.team_id = owner.id;
+    let mut other_key = api_key(SCOPE_FULL, "developer");
+    other_key.team_id = other.id;
+    storage.create_api_key(&owner_key).unwrap();
+    storage.create_api_key(&other_key).unwrap();
+    let svm = SvmEngine::new(storage);
+    let guarded = svm
+        .create_blockchain(owner.id, None, None, None, None, false)
+        .unwrap();
+    let unguarded = svm
+        .create_blockchain(open.id, None, None, None, None, false)
+        .unwrap();
+
+    let with_key = |key: &str| {
+        TestRequest::post()
+            .insert_header(("api_key", key))
+            .to_http_request()
+    };
+    let anonymous = TestRequest::post().to_http_request();
+    let owner_req = with_key(&owner_key.id.to_string());
+    let other_req = with_key(&other_key.id.to_string());
+
+    assert!(check_rpc_write(&svm.storage, &anonymous, guarded).is_ok());
+    assert!(check_rpc_write(&svm.storage, &owner_req, guarded).is_ok());
+    assert_eq!(
+        check_rpc_write(&svm.storage, &other_req, guarded),
+        Err("API key does not own this blockchain".to_string())
+    );
+    assert!(check_rpc_write(&svm.storage, &with_key("invalid"), guarded).is_err());
+    // Teams without the guard accept any caller
+    assert!(check_rpc_write(&svm.storage, &other_req, unguarded).is_ok());
+}
+
+#[test]
+fn test_read_only_blockchain() {
+    let storage = MemoryStorage::new();
+    let (owner, other) = (team(false), team(false));
+    for team in [&owner, &other] {
+        storage.create_team(team.clone());
+    }
+    let mut owner_key = api_key(SCOPE_FULL, "developer");
+    owner_key.team_id = owner.id;
+    let mut other_key = api_key(SCOPE_FULL, "developer");
+    other_key.team_id = other.id;
+    storage.create_api_key(&owner_key).unwrap();
+    storage.create_api_key(&other_key).unwrap();
+    let svm = SvmEngine::new(storage);
+    let id = svm
+        .create_blockchain(owner.id, None, None, None, None, false)
+        .unwrap();
+
+    let with_key = |key: &str| {
+        TestRequest::post()
+            .insert_header(("api_key", key))
+            .to_http_request()
+    };
+    let anonymous = TestRequest::post().to_http_request();
+    let owner_req = with_key(&owner_key.id.to_string());
+    let other_req = with_key(&other_key.id.to_string());
+    assert!(check_rpc_write(&svm.storage, &anonymous, id).is_ok());
+
+    let update = DbBlockchainUpdate {
+        read_only: Some(true),
+        ..Default::default()
+    };
+    assert!(
+        svm.storage
+            .update_blockchain(id, &update)
+            .unwrap()
+            .read_only
+    );
+    assert_eq!(
+        check_rpc_write(&svm.storage, &anonymous, id),
+        Err("Blockchain is read only without an API key".to_string())
+    );
+    assert!(check_rpc_write(&svm.storage, &owner_req, id).is_ok());
+    // Read only applies the write guard whatever the team's setting
+    assert_eq!(
+        check_rpc_write(&svm.storage, &other_req, id),
+        Err("API key does not own this blockchain".to_string())
+    );
+    // Unknown blockchains are reported by the RPC call itself
+    assert!(check_rpc_write(&svm.storage, &anonymous, Uuid::new_v4()).is_ok());
+}
+
+#[test]
+fn test_blockchain_write() {
+    let storage = MemoryStorage::new();
+    let (owner, other) = (team(false), team(false));
+    for team in [&owner, &other] {
+        storage.create_team(team.clone());
+    }
+    let mut owner_key = api_key(SCOPE_FULL, "developer");
+    owner_key.team_id = owner.id;
+    let mut viewer_key = api_key(SCOPE_FULL, "read_only");
+    viewer_key.team_id = owner.id;
+    let mut other_key = api_key(SCOPE_FULL, "owner");
+    other_key.team_id = other.id;
+    for key in [&owner_key, &viewer_key, &other_key] {
+        storage.create_api_key(key).unwrap();
+    }
+    let svm = SvmEngine::new(storage);
+    let id = svm
+        .create_blockchain(owner.id, None, None, None, None, false)
+        .unwrap();
+    let update = DbBlockchainUpdate {
+        read_only: Some(true),
+        ..Default::default()
+    };
+    svm.storage.update_blockchain(id, &update).unwrap();
+    let blockchain = svm.storage.get_blockchain(id).unwrap();
+
+    let with_key = |key: &ApiKey| {
+        TestRequest::put()
+            .insert_header(("api_key", key.id.to_string()))
+            .to_http_request()
+    };
+    let status = |http_req| {
+        check_blockchain_write(&svm.storage, &http_req, &blockchain)
+            .map(|api_key| api_key.map(|api_key| api_key.id))
+            .map_err(|(status, _)| status)
+    };
+    assert_eq!(status(with_key(&owner_key)), Ok(Some(owner_key.id)));
+    // Unlike RPC writes, credentials are always needed
+    assert_eq!(
+        status(TestRequest::put().to_http_request()),
+        Err(StatusCode::UNAUTHORIZED)
+    );
+    assert_eq!(status(with_key(&viewer_key)), Err(StatusCode::UNAUTHORIZED));
+    assert_eq!(status(with_key(&other_key)), Err(StatusCode::FORBIDDEN));
+}
+
+fn jwt_settings(secret: Option<&str>) -> JwtSettings {
+    JwtSettings {
+        issuer: Some("https://auth.example.com/".to_string()),
+        audience: Some("engine".to_string()),
+        secret: secret.map(str::to_string),
+        ..Default::default()
+    }
+}
+
+fn encode(value: &serde_json::Value) -> String {
+    BASE64_URL_SAFE_NO_PAD.encode(value.to_string())
+}
+
+fn claims(team_id: Uuid, exp: u64) -> serde_json::Value {
+    json!({
+        "iss": "https://auth.example.com/",
+        "aud": ["engine", "dashboard"],
+        "exp": exp,
+        "team_id": team_id.to_string(),
+        "role": "owner",
+    })
+}
+
+fn hs256_token(secret: &str, claims: &serde_json::Value) -> String {
+    let signed = format!(
+        "{}.{}",
+        encode(&json!({ "alg": "HS256", "typ": "JWT" })),
+        encode(claims)
+    );
+    let mut mac = Hmac::<Sha256>::new_varkey(secret.as_bytes()).unwrap();
+    mac.update(signed.as_bytes());
+    let signature = BASE64_URL_SAFE_NO_PAD.encode(mac.finalize().into_bytes());
+    format!("{}.{}", signed, signature)
+}
+
+#[test]
+fn test_jwt_hs256() {
+    let settings = jwt_settings(Some("secret"));
+    let team_id = Uuid::new_v4();
+    let token = hs256_token("secret", &claims(team_id, 2_000));
+
+    let verified = jwt::verify(&token, &settings, &JwkSet::default(), 1_000).unwrap();
+    assert_eq!(
+        jwt::team_and_role(&verified, &settings),
+        Ok((team_id, Role::Owner))
+    );
+
+    // Expired past the leeway
+    assert!(jwt::verify(&token, &settings, &JwkSet::default(), 2_061).is_err());
+    let forged = hs256_token("other", &claims(team_id, 2_000));
+    assert!(jwt::verify(&forged, &settings, &JwkSet::default(), 1_000).is_err());
+    let mut wrong_audience = claims(team_id, 2_000);
+    wrong_audience["aud"] = json!("other");
+    let token = hs256_token("secret", &wrong_audience);
+    assert!(jwt::verify(&token, &settings, &JwkSet::default(), 1_000).is_err());
+}
+
+#[test]
+fn test_jwt_rejects_hs256_without_secret() {
+    // A token signed with a public key as HMAC secret must not pass as RS256/ES256
+    let settings = jwt_settings(None);
+    let token = hs256_token("public key", &claims(Uuid::new_v4(), 2_000));
+    assert!(jwt::verify(&token, &settings, &JwkSet::default(), 1_000).is_err());
+}
+
+#[test]
+fn test_jwt_es256() {
+    let rng = SystemRandom::new();
+    let pkcs8 = EcdsaKeyPair::generate_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, &rng).unwrap();
+    let key_pair =
+        EcdsaKeyPair::from_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, pkcs8.as_ref(), &rng).unwrap();
+    let point = key_pair.public_key().as_ref();
+    let jwks = JwkSet::from_json(
+        &json!({
+            "keys": [{
+                "kty": "EC",
+                "crv": "P-256",
+                "kid": "key-1",
+                "x": BASE64_URL_SAFE_NO_PAD.encode(&point[1..33]),
+                "y": BASE64_URL_SAFE_NO_PAD.encode(&point[33..]),
+            }]
+        })
+        .to_string(),
+    )
+    .unwrap();
+
+    let team_id = Uuid::new_v4();
+    let mut token_claims = claims(team_id, 2_000);
+    token_claims.as_object_mut().unwrap().remove("role");
+    let signed = format!(
+        "{}.{}",
+        encode(&json!({ "alg": "ES256", "kid": "key-1" })),
+        encode(&token_claims)
+    );
+    let signature = key_pair.sign(&rng, signed.as_bytes()).unwrap();
+    let token = format!(
+        "{}.{}",
+        signed,
+        BASE64_URL_SAFE_NO_PAD.encode(signature.as_ref())
+    );
+
+    let settings = jwt_settings(None);
+    let verified = jwt::verify(&token, &settings, &jwks, 1_000).unwrap();
+    // No role claim means read only
+    assert_eq!(
+        jwt::team_and_role(&verified, &settings),
+        Ok((team_id, Role::ReadOnly))
+    );
+    assert!(jwt::verify(&token, &settings, &JwkSet::default(), 1_000).is_err());
+}
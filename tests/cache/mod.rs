@@ -28,7 +28,7 @@ fn test_set_account() {
 
     println!("ID: {}", id.to_string());
 
-    storage.set_accounts(id, vec![account.clone()]).unwrap();
+    storage.set_accounts(id, &[account.clone()]).unwrap();
 
     let stored_account = storage.get_account(id, &pubkey.to_string()).unwrap();
 
@@ -67,3 +67,101 @@ fn test_set_blocks() {
 
     storage.delete_blockchain(id).unwrap();
 }
+
+#[test]
+fn test_usage() {
+    dotenv().ok();
+    let database_url = env::var("CACHE_URL").expect("CACHE_URL must be set");
+    let storage = Cache::new(&database_url);
+
+    let id = uuid::Uuid::new_v4();
+    let pubkey = solana_sdk::pubkey::new_rand().to_string();
+    let mut account = DbAccount {
+        lamports: BigDecimal::from(100),
+        data: vec![1, 2, 3],
+        owner: solana_sdk::pubkey::new_rand().to_string(),
+        executable: false,
+        rent_epoch: BigDecimal::from(1234),
+        id: Uuid::new_v4(),
+        created_at: chrono::Utc::now().naive_utc(),
+        address: pubkey.clone(),
+        label: None,
+        blockchain: id,
+    };
+
+    storage.set_accounts(id, &[account.clone()]).unwrap();
+    account.data = vec![1, 2, 3, 4, 5];
+    storage.set_accounts(id, &[account]).unwrap();
+
+    let usage = storage.get_usage(id).unwrap();
+    assert_eq!(usage.accounts, 1);
+    assert_eq!(usage.account_data_bytes, 5);
+    assert!(usage.cache_bytes > 0);
+
+    storage.delete_blockchain(id).unwrap();
+}
+
+#[test]
+fn test_block_usage() {
+    dotenv().ok();
+    let database_url = env::var("CACHE_URL").expect("CACHE_URL must be set");
+    let storage = Cache::new(&database_url);
+
+    let id = uuid::Uuid::new_v4();
+    let block = DbBlock {
+        id: Uuid::new_v4(),
+        created_at: chrono::Utc::now().naive_utc(),
+        blockchain: id,
+        blockhash: vec![1, 2, 3],
+        previous_blockhash: vec![4, 5, 6],
+        parent_slot: BigDecimal::from(1),
+        block_height: BigDecimal::from(2),
+        slot: BigDecimal::from(2),
+    };
+    let block_bytes = serde_json::to_string(&block).unwrap().len() as u64;
+
+    // Storing a block again doesn't count it twice
+    storage.set_block(id, block.clone()).unwrap();
+    storage.set_block(id, block).unwrap();
+
+    let usage = storage.get_usage(id).unwrap();
+    assert_eq!(usage.blocks, 1);
+    // Once in the sorted set and once on its own
+    assert_eq!(usage.cache_bytes, block_bytes * 2);
+
+    storage.delete_blockchain(id).unwrap();
+}
+
+#[test]
+fn test_delete_account() {
+    dotenv().ok();
+    let database_url = env::var("CACHE_URL").expect("CACHE_URL must be set");
+    let storage = Cache::new(&database_url);
+
+    let id = uuid::Uuid::new_v4();
+    let pubkey = solana_sdk::pubkey::new_rand().to_string();
+    let account = DbAccount {
+        lamports: BigDecimal::from(100),
+        data: vec![1, 2, 3],
+        owner: solana_sdk::pubkey::new_rand().to_string(),
+        executable: false,
+        rent_epoch: BigDecimal::from(1234),
+        id: Uuid::new_v4(),
+        created_at: chrono::Utc::now().naive_utc(),
+        address: pubkey.clone(),
+        label: None,
+        blockchain: id,
+    };
+
+    storage.set_accounts(id, &[account]).unwrap();
+    assert!(storage.delete_account(id, &pubkey).unwrap());
+    assert!(!storage.delete_account(id, &pubkey).unwrap());
+    assert!(storage.get_account(id, &pubkey).unwrap().is_none());
+
+    let usage = storage.get_usage(id).unwrap();
+    assert_eq!(usage.accounts, 0);
+    assert_eq!(usage.account_data_bytes, 0);
+    assert_eq!(usage.cache_bytes, 0);
+
+    storage.delete_blockchain(id).unwrap();
+}
@@ -0,0 +1,76 @@
+use actix_web::{rt, web, App, HttpResponse, HttpServer};
+use mockchain_engine::client::{blockchain_id, Client};
+use serde_json::json;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+#[test]
+fn test_blockchain_id() {
+    let id = Uuid::new_v4();
+    assert_eq!(blockchain_id(&id.to_string()), Ok(id));
+    assert_eq!(
+        blockchain_id(&format!("http://localhost:8899/rpc/{}", id)),
+        Ok(id)
+    );
+    assert_eq!(
+        blockchain_id(&format!("http://localhost:8899/rpc/{}/", id)),
+        Ok(id)
+    );
+    assert!(blockchain_id("http://localhost:8899/rpc/").is_err());
+}
+
+#[actix_web::test]
+async fn test_error_message() {
+    let server = HttpServer::new(|| {
+        App::new().route(
+            "/blockchains",
+            web::get().to(|| async {
+                HttpResponse::Unauthorized().json(json!({ "message": "Invalid API key" }))
+            }),
+        )
+    })
+    .workers(1)
+    .disable_signals()
+    .bind(("127.0.0.1", 0))
+    .unwrap();
+    let addr = server.addrs()[0];
+    let server = server.run();
+    let handle = server.handle();
+    rt::spawn(server);
+
+    let client = Client::new(&format!("http://{}/", addr), None);
+    let err = client.list_blockchains(None).await.unwrap_err();
+    assert_eq!(err, "401 Unauthorized: Invalid API key");
+    handle.stop(true).await;
+}
+
+#[actix_web::test]
+async fn test_export_accounts() {
+    let server = HttpServer::new(|| {
+        App::new().route(
+            "/accounts/{id}/export",
+            web::get().to(|query: web::Query<HashMap<String, String>>| async move {
+                HttpResponse::Ok().json(json!([{ "pubkey": query.get("owner") }]))
+            }),
+        )
+    })
+    .workers(1)
+    .disable_signals()
+    .bind(("127.0.0.1", 0))
+    .unwrap();
+    let addr = server.addrs()[0];
+    let server = server.run();
+    let handle = server.handle();
+    rt::spawn(server);
+
+    let client = Client::new(&format!("http://{}", addr), None);
+    let owner = Pubkey::new_unique();
+    let exported = client
+        .export_accounts(Uuid::new_v4(), Some(&owner))
+        .await
+        .unwrap();
+    let exported: serde_json::Value = serde_json::from_slice(&exported).unwrap();
+    assert_eq!(exported, json!([{ "pubkey": owner.to_string() }]));
+    handle.stop(true).await;
+}
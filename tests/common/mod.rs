@@ -0,0 +1,74 @@
+//! Fixtures shared across test modules.
+
+use actix_web::rt::time;
+use mockchain_engine::{
+    engine::{SvmEngine, SVM},
+    storage::{memory::MemoryStorage, Storage},
+};
+use solana_sdk::{
+    hash::Hash, native_token::LAMPORTS_PER_SOL, pubkey::Pubkey, signature::Signature,
+    signer::Signer, system_instruction, transaction::Transaction,
+    transaction::VersionedTransaction,
+};
+use std::time::Duration;
+use uuid::Uuid;
+
+/// An engine on memory storage with a fresh blockchain.
+pub fn engine() -> (SvmEngine<MemoryStorage>, Uuid) {
+    let svm = SvmEngine::new(MemoryStorage::new());
+    let id = svm
+        .create_blockchain(Uuid::new_v4(), None, None, None, None, false)
+        .unwrap();
+    (svm, id)
+}
+
+/// Sends `lamports` from the blockchain's airdrop keypair to `recipient`, signed
+/// with `blockhash` or else the latest one.
+pub fn transfer_to(
+    svm: &SvmEngine<MemoryStorage>,
+    id: Uuid,
+    recipient: &Pubkey,
+    lamports: u64,
+    blockhash: Option<Hash>,
+) -> VersionedTransaction {
+    let payer = svm.storage.get_blockchain(id).unwrap().airdrop_keypair;
+    let blockhash = blockhash.unwrap_or_else(|| svm.latest_blockhash(id).unwrap().blockhash);
+    VersionedTransaction::from(Transaction::new_signed_with_payer(
+        &[system_instruction::transfer(
+            &payer.pubkey(),
+            recipient,
+            lamports,
+        )],
+        Some(&payer.pubkey()),
+        &[&payer],
+        blockhash,
+    ))
+}
+
+/// Sends one SOL to a new address.
+pub fn transfer(svm: &SvmEngine<MemoryStorage>, id: Uuid) -> VersionedTransaction {
+    transfer_to(svm, id, &Pubkey::new_unique(), LAMPORTS_PER_SOL, None)
+}
+
+/// Waits up to two seconds for `done` to hold.
+pub async fn eventually(mut done: impl FnMut() -> bool) -> bool {
+    for _ in 0..100 {
+        if done() {
+            return true;
+        }
+        time::sleep(Duration::from_millis(20)).await;
+    }
+    false
+}
+
+/// Waits up to two seconds for the transaction to be saved.
+pub async fn processed(svm: &SvmEngine<MemoryStorage>, id: Uuid, signature: &str) -> bool {
+    let signature: Signature = signature.parse().unwrap();
+    eventually(|| {
+        svm.storage
+            .get_transaction(id, &signature)
+            .unwrap()
+            .is_some()
+    })
+    .await
+}
@@ -0,0 +1,282 @@
+//! Differential tests: the same RPC calls against the engine and a solana-test-validator,
+//! comparing the shape of the responses. Values differ between the two, so only keys and
+//! JSON types are compared, with `VOLATILE` keys skipped altogether.
+//!
+//! The differential test needs a validator, so it only runs when asked:
+//!
+//! solana-test-validator --reset &
+//! SOLANA_TEST_VALIDATOR_URL=http://127.0.0.1:8899 cargo test compat -- --ignored
+
+use actix_web::rt::time;
+use base64::prelude::*;
+use mockchain_engine::embedded::EmbeddedEngine;
+use serde_json::{json, Value};
+use solana_rpc_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{
+    commitment_config::CommitmentConfig,
+    message::Message,
+    native_token::LAMPORTS_PER_SOL,
+    pubkey::Pubkey,
+    signature::{Keypair, Signature},
+    signer::Signer,
+    system_instruction, system_program,
+    transaction::Transaction,
+};
+use std::time::Duration;
+
+/// Keys whose type legitimately changes from call to call, e.g. null once finalized.
+const VOLATILE: [&str; 1] = ["confirmations"];
+
+/// JSON type of a value, for messages.
+fn kind(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "bool",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+/// Differences in shape between two responses, one per line, by JSON path.
+fn shape_diff(path: &str, engine: &Value, validator: &Value, diffs: &mut Vec<String>) {
+    match (engine, validator) {
+        (Value::Object(engine), Value::Object(validator)) => {
+            for (key, value) in validator {
+                if VOLATILE.contains(&key.as_str()) {
+                    continue;
+                }
+                let path = format!("{}.{}", path, key);
+                match engine.get(key) {
+                    Some(engine) => shape_diff(&path, engine, value, diffs),
+                    None => diffs.push(format!("{}: missing from the engine", path)),
+                }
+            }
+            for key in engine.keys() {
+                if !validator.contains_key(key) && !VOLATILE.contains(&key.as_str()) {
+                    diffs.push(format!("{}.{}: not returned by the validator", path, key));
+                }
+            }
+        }
+        // Lengths depend on the data, element shapes don't
+        (Value::Array(engine), Value::Array(validator)) => {
+            for (idx, (engine, validator)) in engine.iter().zip(validator).enumerate() {
+                shape_diff(&format!("{}[{}]", path, idx), engine, validator, diffs);
+            }
+        }
+        (engine, validator) if kind(engine) != kind(validator) => diffs.push(format!(
+            "{}: {} from the engine, {} from the validator",
+            path,
+            kind(engine),
+            kind(validator)
+        )),
+        _ => {}
+    }
+}
+
+/// One side of the comparison, with the state the calls refer to.
+struct Side {
+    url: String,
+    http: reqwest::Client,
+    payer: Pubkey,
+    recipient: Pubkey,
+    signature: Signature,
+    /// Base64 transfer message, for getFeeForMessage
+    message: String,
+    /// Base64 signed transfer, for simulateTransaction
+    transaction: String,
+}
+
+impl Side {
+    /// Funds the payer if needed and lands a transfer, so there is history to query.
+    async fn setup(url: String, payer: Keypair, airdrop: bool) -> Self {
+        let client = RpcClient::new_with_commitment(url.clone(), CommitmentConfig::confirmed());
+        if airdrop {
+            let signature = client
+                .request_airdrop(&payer.pubkey(), 10 * LAMPORTS_PER_SOL)
+                .await
+                .unwrap();
+            wait(&client, &signature).await;
+        }
+        let recipient = Pubkey::new_unique();
+        let transfer =
+            |lamports| system_instruction::transfer(&payer.pubkey(), &recipient, lamports);
+        let blockhash = client.get_latest_blockhash().await.unwrap();
+        let tx = Transaction::new_signed_with_payer(
+            &[transfer(LAMPORTS_PER_SOL)],
+            Some(&payer.pubkey()),
+            &[&payer],
+            blockhash,
+        );
+        let signature = client.send_transaction(&tx).await.unwrap();
+        wait(&client, &signature).await;
+
+        let message = Message::new_with_blockhash(
+            &[transfer(LAMPORTS_PER_SOL / 2)],
+            Some(&payer.pubkey()),
+            &blockhash,
+        );
+        let simulated = Transaction::new(&[&payer], message.clone(), blockhash);
+        Side {
+            url,
+            http: reqwest::Client::new(),
+            payer: payer.pubkey(),
+            recipient,
+            signature,
+            message: BASE64_STANDARD.encode(message.serialize()),
+            transaction: BASE64_STANDARD.encode(bincode::serialize(&simulated).unwrap()),
+        }
+    }
+
+    /// The whole JSON-RPC response, so errors are compared too.
+    async fn call(&self, method: &str, params: &Value) -> Value {
+        let res = self
+            .http
+            .post(&self.url)
+            .json(&json!({"jsonrpc": "2.0", "id": 1, "method": method, "params": params}))
+            .send()
+            .await
+            .unwrap();
+        let mut body: Value = res.json().await.unwrap();
+        if let Some(body) = body.as_object_mut() {
+            body.remove("id");
+        }
+        body
+    }
+
+    /// The calls to compare, with this side's accounts and transactions.
+    fn calls(&self) -> Vec<(&'static str, Value)> {
+        let payer = self.payer.to_string();
+        let recipient = self.recipient.to_string();
+        let signature = self.signature.to_string();
+        let confirmed = json!({"commitment": "confirmed"});
+        let mut calls = vec![
+            ("getAccountInfo", json!([payer, {"encoding": "base64"}])),
+            (
+                "getAccountInfo",
+                json!([recipient, {"encoding": "jsonParsed"}]),
+            ),
+            (
+                "getAccountInfo",
+                json!([system_program::id().to_string(), {"encoding": "base64"}]),
+            ),
+            ("getAccountInfo", json!([Pubkey::new_unique().to_string()])),
+            (
+                "getMultipleAccounts",
+                json!([[payer, recipient], {"encoding": "base64"}]),
+            ),
+            ("getBalance", json!([payer])),
+            ("getSignatureStatuses", json!([[signature]])),
+            (
+                "getSignatureStatuses",
+                json!([[signature], {"searchTransactionHistory": true}]),
+            ),
+            ("getSignaturesForAddress", json!([recipient, confirmed])),
+            ("getLatestBlockhash", json!([])),
+            ("getFeeForMessage", json!([self.message])),
+            (
+                "simulateTransaction",
+                json!([self.transaction, {"encoding": "base64"}]),
+            ),
+            ("getMinimumBalanceForRentExemption", json!([165])),
+            ("getBlockHeight", json!([])),
+            ("getSlot", json!([])),
+            ("getEpochInfo", json!([])),
+            ("getEpochSchedule", json!([])),
+            ("getGenesisHash", json!([])),
+            ("getIdentity", json!([])),
+            ("getClusterNodes", json!([])),
+            ("getHealth", json!([])),
+            ("getVersion", json!([])),
+        ];
+        for encoding in ["json", "jsonParsed", "base64"] {
+            calls.push((
+                "getTransaction",
+                json!([signature, {
+                    "encoding": encoding,
+                    "commitment": "confirmed",
+                    "maxSupportedTransactionVersion": 0,
+                }]),
+            ));
+        }
+        calls
+    }
+}
+
+/// Waits for a signature to be confirmed.
+async fn wait(client: &RpcClient, signature: &Signature) {
+    for _ in 0..300 {
+        if let Ok(Some(res)) = client.get_signature_status(signature).await {
+            res.unwrap();
+            return;
+        }
+        time::sleep(Duration::from_millis(100)).await;
+    }
+    panic!("{} was not confirmed", signature);
+}
+
+#[actix_web::test]
+#[ignore]
+async fn test_against_validator() {
+    let validator_url = std::env::var("SOLANA_TEST_VALIDATOR_URL")
+        .unwrap_or_else(|_| "http://127.0.0.1:8899".to_string());
+    let mut engine = EmbeddedEngine::new().unwrap();
+    engine.listen().unwrap();
+    let engine_side = Side::setup(engine.rpc_url().unwrap(), engine.payer(), false).await;
+    let validator_side = Side::setup(validator_url, Keypair::new(), true).await;
+
+    let mut report = vec![];
+    for ((method, engine_params), (_, validator_params)) in
+        engine_side.calls().iter().zip(validator_side.calls())
+    {
+        let engine_res = engine_side.call(method, engine_params).await;
+        let validator_res = validator_side.call(method, &validator_params).await;
+        let mut diffs = vec![];
+        shape_diff("", &engine_res, &validator_res, &mut diffs);
+        if !diffs.is_empty() {
+            report.push(format!(
+                "{} {}\n  {}",
+                method,
+                validator_params,
+                diffs.join("\n  ")
+            ));
+        }
+    }
+    engine.stop().await;
+    assert!(report.is_empty(), "\n{}", report.join("\n"));
+}
+
+#[test]
+fn test_shape_diff() {
+    let engine = json!({
+        "result": {
+            "context": {"slot": 1},
+            "value": [{"lamports": 1, "data": ["", "base64"], "space": 0}],
+            "confirmations": null,
+        }
+    });
+    let validator = json!({
+        "result": {
+            "context": {"slot": 2, "apiVersion": "2.1.13"},
+            "value": [
+                {"lamports": 5, "data": ["AA==", "base64"], "space": "0"},
+                {"lamports": 6, "data": ["", "base64"], "space": 0},
+            ],
+            "confirmations": 10,
+        }
+    });
+    let mut diffs = vec![];
+    shape_diff("", &engine, &validator, &mut diffs);
+    assert_eq!(
+        diffs,
+        vec![
+            ".result.context.apiVersion: missing from the engine",
+            ".result.value[0].space: number from the engine, string from the validator",
+        ]
+    );
+
+    let mut diffs = vec![];
+    shape_diff("", &validator, &validator, &mut diffs);
+    assert!(diffs.is_empty());
+}
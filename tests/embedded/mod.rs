@@ -0,0 +1,54 @@
+use mockchain_engine::embedded::EmbeddedEngine;
+use serde_json::{json, Value};
+use solana_sdk::{native_token::LAMPORTS_PER_SOL, pubkey::Pubkey, signer::Signer};
+
+#[actix_web::test]
+async fn test_payer_is_funded() {
+    let engine = EmbeddedEngine::new().unwrap();
+    let res = engine
+        .rpc("getBalance", json!([engine.payer().pubkey().to_string()]))
+        .await
+        .unwrap();
+    assert_eq!(res["value"], json!(1_000_000 * LAMPORTS_PER_SOL));
+}
+
+#[actix_web::test]
+async fn test_airdrop() {
+    let engine = EmbeddedEngine::new().unwrap();
+    let recipient = Pubkey::new_unique();
+    engine.airdrop(&recipient, LAMPORTS_PER_SOL).await.unwrap();
+    let res = engine
+        .rpc("getBalance", json!([recipient.to_string()]))
+        .await
+        .unwrap();
+    assert_eq!(res["value"], json!(LAMPORTS_PER_SOL));
+}
+
+#[actix_web::test]
+async fn test_unknown_method() {
+    let engine = EmbeddedEngine::new().unwrap();
+    assert!(engine.rpc("notAMethod", json!([])).await.is_err());
+}
+
+#[actix_web::test]
+async fn test_listen() {
+    let mut engine = EmbeddedEngine::new().unwrap();
+    assert!(engine.rpc_url().is_none());
+    engine.listen().unwrap();
+    let url = engine.rpc_url().unwrap();
+    assert!(engine.ws_url().unwrap().starts_with("ws://127.0.0.1:"));
+
+    let res: Value = reqwest::Client::new()
+        .post(&url)
+        .json(&json!({"jsonrpc": "2.0", "id": 1, "method": "getLatestBlockhash"}))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert!(res["result"]["value"]["blockhash"].is_string());
+
+    engine.stop().await;
+    assert!(engine.rpc_url().is_none());
+}
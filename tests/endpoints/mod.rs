@@ -0,0 +1,72 @@
+use base64::prelude::*;
+use mockchain_engine::endpoints::AccountImport;
+use serde_json::json;
+use solana_sdk::{pubkey::Pubkey, system_program};
+
+fn line(address: &Pubkey, data: &[u8]) -> String {
+    json!({
+        "address": address.to_string(),
+        "lamports": 1_000_000,
+        "data": BASE64_STANDARD.encode(data),
+        "owner": system_program::id().to_string(),
+        "rent_epoch": 0,
+        "executable": false,
+    })
+    .to_string()
+}
+
+#[test]
+fn test_import_lines_split_across_chunks() {
+    let (first, second, third) = (
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+    );
+    let body = format!(
+        "{}\n\n{{\"address\": 1}}\n{}\n{}",
+        line(&first, &[1; 300]),
+        line(&second, &[2; 10]),
+        line(&third, &[]),
+    );
+
+    let mut import = AccountImport::default();
+    for chunk in body.as_bytes().chunks(7) {
+        import.push_chunk(chunk).unwrap();
+    }
+    // Without a trailing newline the last line waits for the end of the body
+    assert_eq!(import.lines, 4);
+    import.finish();
+    assert_eq!(import.lines, 5);
+
+    let addresses: Vec<Pubkey> = import.batch.iter().map(|(address, _)| *address).collect();
+    assert_eq!(addresses, vec![first, second, third]);
+    assert_eq!(import.batch[0].1.data, vec![1; 300]);
+    // Blank lines are skipped, invalid ones reported by line number
+    assert_eq!(import.failed, 1);
+    assert_eq!(import.errors[0]["line"], 3);
+}
+
+#[test]
+fn test_import_rejects_long_lines() {
+    let mut import = AccountImport::default();
+    let chunk = vec![b' '; 1024 * 1024];
+    let mut result = Ok(());
+    for _ in 0..=16 {
+        result = import.push_chunk(&chunk);
+        if result.is_err() {
+            break;
+        }
+    }
+    assert_eq!(
+        result,
+        Err(format!("Line 1 is longer than {} bytes", 16 * 1024 * 1024))
+    );
+
+    // A newline anywhere in the line ends it
+    let mut import = AccountImport::default();
+    for _ in 0..16 {
+        import.push_chunk(&chunk).unwrap();
+    }
+    import.push_chunk(b"\n").unwrap();
+    assert_eq!(import.lines, 1);
+}
@@ -0,0 +1,113 @@
+use mockchain_engine::{
+    engine::{SvmEngine, SVM},
+    storage::{memory::MemoryStorage, teams::Team, Storage},
+};
+use solana_sdk::{account::Account, pubkey::Pubkey, signature::Signature};
+use uuid::Uuid;
+
+use crate::common::{processed, transfer_to};
+
+#[actix_web::test]
+async fn test_account_quota() {
+    let storage = MemoryStorage::new();
+    let team = Team {
+        id: Uuid::new_v4(),
+        created_at: chrono::Utc::now().naive_utc(),
+        name: "quota".to_string(),
+        default_expiry: None,
+        max_blockchains: None,
+        max_accounts: None,
+        max_transactions_per_day: None,
+        auto_pause_hours: None,
+        rpc_write_guard: false,
+    };
+    storage.create_team(team.clone());
+    let svm = SvmEngine::new(storage);
+    let id = svm
+        .create_blockchain(team.id, None, None, None, None, false)
+        .unwrap();
+    // Room for one more account
+    let existing = svm.storage.get_storage_usage(id).unwrap().accounts as i32;
+    let mut quotas = team.quotas();
+    quotas.max_accounts = Some(existing + 1);
+    svm.storage.set_team_quotas(team.id, &quotas).unwrap();
+
+    let (first, second) = (Pubkey::new_unique(), Pubkey::new_unique());
+    svm.airdrop(id, &first, 1_000_000).await.unwrap();
+    let err = svm.airdrop(id, &second, 1_000_000).await.unwrap_err();
+    assert!(err.contains("accounts per blockchain"), "{}", err);
+    // Existing accounts are only overwritten
+    svm.airdrop(id, &first, 1_000_000).await.unwrap();
+    assert!(svm.mint_tokens(id, None, 6, &first, 1_000).await.is_err());
+
+    // Transactions are rejected too, the queue moves on to the next one
+    let created = svm
+        .send_transaction(id, transfer_to(&svm, id, &second, 1_000_000, None), false)
+        .unwrap();
+    let overwritten = svm
+        .send_transaction(id, transfer_to(&svm, id, &first, 1_000_000, None), false)
+        .unwrap();
+    assert!(processed(&svm, id, &overwritten).await);
+    let created: Signature = created.parse().unwrap();
+    assert!(svm.storage.get_transaction(id, &created).unwrap().is_none());
+    assert_eq!(svm.storage.get_account(id, &second).unwrap(), None);
+    assert_eq!(
+        svm.storage
+            .get_account(id, &first)
+            .unwrap()
+            .unwrap()
+            .lamports,
+        3_000_000
+    );
+}
+
+#[test]
+fn test_account_quota_of_new_blockchains() {
+    let storage = MemoryStorage::new();
+    let team = Team {
+        id: Uuid::new_v4(),
+        created_at: chrono::Utc::now().naive_utc(),
+        name: "quota".to_string(),
+        default_expiry: None,
+        max_blockchains: None,
+        max_accounts: None,
+        max_transactions_per_day: None,
+        auto_pause_hours: None,
+        rpc_write_guard: false,
+    };
+    storage.create_team(team.clone());
+    let svm = SvmEngine::new(storage);
+    let source = svm
+        .create_blockchain(team.id, None, None, None, None, false)
+        .unwrap();
+    let source_accounts = svm.storage.snapshot_accounts(source).unwrap().len();
+
+    // Lowered below what the source already has
+    let mut quotas = team.quotas();
+    quotas.max_accounts = Some(source_accounts as i32 - 1);
+    svm.storage.set_team_quotas(team.id, &quotas).unwrap();
+    let err = svm
+        .clone_blockchain(source, team.id, None, None)
+        .unwrap_err();
+    assert!(err.contains("accounts per blockchain"), "{}", err);
+
+    // A snapshot's accounts and the airdrop account
+    quotas.max_accounts = Some(3);
+    svm.storage.set_team_quotas(team.id, &quotas).unwrap();
+    let create = |count: usize| {
+        svm.create_blockchain_from_accounts(team.id, None, None, count, &mut |each| {
+            for _ in 0..count {
+                each(Pubkey::new_unique(), Account::new(1, 0, &Pubkey::default()))?;
+            }
+            Ok(())
+        })
+    };
+    let err = create(3).unwrap_err();
+    assert!(err.contains("accounts per blockchain"), "{}", err);
+    let id = create(2).unwrap();
+    assert_eq!(svm.storage.snapshot_accounts(id).unwrap().len(), 3);
+
+    quotas.max_accounts = Some(source_accounts as i32);
+    svm.storage.set_team_quotas(team.id, &quotas).unwrap();
+    svm.clone_blockchain(source, team.id, None, None).unwrap();
+}
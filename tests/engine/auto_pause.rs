@@ -0,0 +1,75 @@
+use mockchain_engine::{
+    engine::{SvmEngine, SVM},
+    storage::{memory::MemoryStorage, teams::Team},
+};
+use uuid::Uuid;
+
+use crate::common::{processed, transfer};
+
+/// An engine with a blockchain of a team pausing after `auto_pause_hours`.
+fn engine(auto_pause_hours: Option<i32>) -> (SvmEngine<MemoryStorage>, Uuid) {
+    let storage = MemoryStorage::new();
+    let team = Team {
+        id: Uuid::new_v4(),
+        created_at: chrono::Utc::now().naive_utc(),
+        name: "auto-pause".to_string(),
+        default_expiry: None,
+        max_blockchains: None,
+        max_accounts: None,
+        max_transactions_per_day: None,
+        auto_pause_hours,
+        rpc_write_guard: false,
+    };
+    storage.create_team(team.clone());
+    let svm = SvmEngine::new(storage);
+    let id = svm
+        .create_blockchain(team.id, None, None, None, None, false)
+        .unwrap();
+    (svm, id)
+}
+
+async fn send(svm: &SvmEngine<MemoryStorage>, id: Uuid) {
+    svm.touch(id);
+    let signature = svm.send_transaction(id, transfer(svm, id), false).unwrap();
+    assert!(
+        processed(svm, id, &signature).await,
+        "Transaction {} wasn't processed",
+        signature
+    );
+}
+
+#[actix_web::test]
+async fn test_idle_blockchain_paused_and_resumed() {
+    let (svm, id) = engine(Some(0));
+    send(&svm, id).await;
+    assert_eq!(svm.engine_stats().queues.len(), 1);
+
+    assert_eq!(svm.pause_idle_blockchains().unwrap(), 1);
+    assert!(svm.is_paused(id));
+    let stats = svm.engine_stats();
+    assert!(stats.queues.is_empty());
+    assert_eq!(stats.paused_blockchains, 1);
+    assert_eq!(svm.pause_idle_blockchains().unwrap(), 0);
+
+    // The next request resumes it with a new queue
+    send(&svm, id).await;
+    assert!(!svm.is_paused(id));
+    assert_eq!(svm.engine_stats().queues.len(), 1);
+}
+
+#[actix_web::test]
+async fn test_blockchain_without_policy_not_paused() {
+    let (svm, id) = engine(None);
+    send(&svm, id).await;
+    assert_eq!(svm.pause_idle_blockchains().unwrap(), 0);
+    assert!(!svm.is_paused(id));
+}
+
+#[actix_web::test]
+async fn test_deleted_blockchain_forgotten() {
+    let (svm, id) = engine(Some(0));
+    svm.touch(id);
+    svm.delete_blockchain(id).unwrap();
+    assert_eq!(svm.pause_idle_blockchains().unwrap(), 0);
+    assert_eq!(svm.engine_stats().paused_blockchains, 0);
+}
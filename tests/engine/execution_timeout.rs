@@ -0,0 +1,68 @@
+use mockchain_engine::{
+    engine::{SvmEngine, SVM},
+    storage::{memory::MemoryStorage, Storage},
+};
+use solana_sdk::{
+    instruction::InstructionError, pubkey::Pubkey, signature::Signature,
+    transaction::TransactionError,
+};
+use std::time::Duration;
+use uuid::Uuid;
+
+use crate::common::{processed, transfer_to};
+
+#[actix_web::test]
+async fn test_execution_timeout() {
+    // Any execution takes longer than this
+    let svm =
+        SvmEngine::new(MemoryStorage::new()).with_execution_timeout(Some(Duration::from_nanos(1)));
+    let id = svm
+        .create_blockchain(Uuid::new_v4(), None, None, None, None, false)
+        .unwrap();
+    let recipient = Pubkey::new_unique();
+    let timed_out =
+        TransactionError::InstructionError(0, InstructionError::ProgramFailedToComplete);
+
+    let simulated = svm
+        .simulate_transaction(
+            id,
+            transfer_to(&svm, id, &recipient, 1_000_000, None),
+            false,
+        )
+        .await
+        .unwrap();
+    assert_eq!(simulated.err, Some(timed_out));
+    assert_eq!(
+        simulated.logs.last().unwrap(),
+        "Transaction exceeded the 0 ms execution timeout"
+    );
+
+    let signature = svm
+        .send_transaction(
+            id,
+            transfer_to(&svm, id, &recipient, 1_000_000, None),
+            false,
+        )
+        .unwrap();
+    assert!(processed(&svm, id, &signature).await);
+    let signature: Signature = signature.parse().unwrap();
+    let (_, meta, _) = svm.get_transaction(id, &signature).unwrap().unwrap();
+    assert!(meta.err.is_some());
+    assert_eq!(
+        meta.log_messages.last().unwrap(),
+        "Transaction exceeded the 0 ms execution timeout"
+    );
+    // Its writes were dropped
+    assert!(svm.storage.get_account(id, &recipient).unwrap().is_none());
+
+    let svm = svm.with_execution_timeout(None);
+    let simulated = svm
+        .simulate_transaction(
+            id,
+            transfer_to(&svm, id, &recipient, 1_000_000, None),
+            false,
+        )
+        .await
+        .unwrap();
+    assert_eq!(simulated.err, None);
+}
@@ -0,0 +1,91 @@
+use mockchain_engine::{
+    engine::{SvmEngine, SVM},
+    storage::{blocks::DbBlockchainUpdate, memory::MemoryStorage, Storage},
+};
+use solana_sdk::{
+    pubkey::Pubkey,
+    signature::{Keypair, Signature},
+    signer::Signer,
+    system_instruction,
+    transaction::{Transaction, VersionedTransaction},
+};
+use uuid::Uuid;
+
+use crate::common::{engine, processed};
+
+fn lamports(svm: &SvmEngine<MemoryStorage>, id: Uuid, address: &Pubkey) -> u64 {
+    svm.storage
+        .get_account(id, address)
+        .unwrap()
+        .map_or(0, |account| account.lamports)
+}
+
+/// Sends nothing, so only the fee is owed.
+fn empty_transfer(
+    svm: &SvmEngine<MemoryStorage>,
+    id: Uuid,
+    payer: &Keypair,
+) -> VersionedTransaction {
+    let blockhash = svm.latest_blockhash(id).unwrap().blockhash;
+    VersionedTransaction::from(Transaction::new_signed_with_payer(
+        &[system_instruction::transfer(
+            &payer.pubkey(),
+            &Pubkey::new_unique(),
+            0,
+        )],
+        Some(&payer.pubkey()),
+        &[payer],
+        blockhash,
+    ))
+}
+
+#[actix_web::test]
+async fn test_fee_sponsorship() {
+    let (svm, id) = engine();
+    let sponsor = svm.storage.get_blockchain(id).unwrap().airdrop_keypair;
+    let payer = Keypair::new();
+    let tx = empty_transfer(&svm, id, &payer);
+
+    // Unfunded payers can't pay their fee by default
+    assert!(svm
+        .simulate_transaction(id, tx.clone(), false)
+        .await
+        .is_err());
+
+    let update = DbBlockchainUpdate {
+        sponsor_fees: Some(true),
+        ..Default::default()
+    };
+    assert!(
+        svm.storage
+            .update_blockchain(id, &update)
+            .unwrap()
+            .sponsor_fees
+    );
+    let simulated = svm
+        .simulate_transaction(id, tx.clone(), false)
+        .await
+        .unwrap();
+    assert_eq!(simulated.err, None);
+    assert_eq!(simulated.sponsored_fee, 5000);
+
+    let sponsor_balance = lamports(&svm, id, &sponsor.pubkey());
+    let signature = svm.send_transaction(id, tx, false).unwrap();
+    assert!(processed(&svm, id, &signature).await);
+    let signature: Signature = signature.parse().unwrap();
+    assert_eq!(lamports(&svm, id, &payer.pubkey()), 0);
+    assert_eq!(
+        lamports(&svm, id, &sponsor.pubkey()),
+        sponsor_balance - 5000
+    );
+    let (_, meta, _) = svm.get_transaction(id, &signature).unwrap().unwrap();
+    assert_eq!(meta.err, None);
+    assert_eq!(meta.sponsored_fee, Some(5000));
+
+    // Payers with enough lamports pay for themselves. The drained payer was deleted,
+    // so it's funded again rather than updated
+    svm.airdrop(id, &payer.pubkey(), 1_000_000).await.unwrap();
+    let tx = empty_transfer(&svm, id, &payer);
+    let simulated = svm.simulate_transaction(id, tx, false).await.unwrap();
+    assert_eq!(simulated.sponsored_fee, 0);
+}
@@ -0,0 +1,60 @@
+use mockchain_engine::{
+    engine::{blocks::AccountsStatus, SvmEngine, SVM},
+    storage::{memory::MemoryStorage, Storage},
+};
+use solana_sdk::{bpf_loader_upgradeable, signer::Signer, sysvar};
+use uuid::Uuid;
+
+use crate::common::{engine, eventually};
+
+#[actix_web::test]
+async fn test_deferred_genesis_accounts() {
+    let svm = SvmEngine::new(MemoryStorage::new());
+    let id = svm
+        .create_blockchain(Uuid::new_v4(), None, None, None, None, true)
+        .unwrap();
+    // The blockchain and its genesis block exist right away
+    let blockchain = svm.storage.get_blockchain(id).unwrap();
+    assert_eq!(blockchain.accounts_status, AccountsStatus::Initializing);
+    assert_eq!(svm.storage.get_latest_block(id).unwrap().block_height, 0);
+    assert!(svm
+        .storage
+        .get_account(id, &spl_token::id())
+        .unwrap()
+        .is_none());
+
+    assert!(
+        eventually(|| {
+            svm.storage.get_blockchain(id).unwrap().accounts_status != AccountsStatus::Initializing
+        })
+        .await
+    );
+    assert_eq!(
+        svm.storage.get_blockchain(id).unwrap().accounts_status,
+        AccountsStatus::Ready
+    );
+    for address in [
+        blockchain.airdrop_keypair.pubkey(),
+        spl_token::id(),
+        sysvar::clock::id(),
+        bpf_loader_upgradeable::id(),
+    ] {
+        assert!(
+            svm.storage.get_account(id, &address).unwrap().is_some(),
+            "{} missing",
+            address
+        );
+    }
+}
+
+#[actix_web::test]
+async fn test_genesis_accounts_ready_on_creation() {
+    let (svm, id) = engine();
+    let blockchain = svm.storage.get_blockchain(id).unwrap();
+    assert_eq!(blockchain.accounts_status, AccountsStatus::Ready);
+    assert!(svm
+        .storage
+        .get_account(id, &spl_token::id())
+        .unwrap()
+        .is_some());
+}
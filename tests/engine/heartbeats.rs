@@ -0,0 +1,59 @@
+use mockchain_engine::{
+    engine::{SvmEngine, SVM},
+    storage::memory::MemoryStorage,
+};
+use std::time::Duration;
+use uuid::Uuid;
+
+#[actix_web::test]
+async fn test_heartbeats_of_served_blockchains() {
+    let svm = SvmEngine::new(MemoryStorage::new());
+    let served = svm
+        .create_blockchain(Uuid::new_v4(), None, None, None, None, false)
+        .unwrap();
+    // Created here, but never served since
+    svm.create_blockchain(Uuid::new_v4(), None, None, None, None, false)
+        .unwrap();
+    assert!(svm.heartbeats().is_empty());
+
+    // Idle blockchains keep their heartbeat, at the same slot
+    svm.touch(served);
+    let heartbeats = svm.heartbeats();
+    assert_eq!(heartbeats.len(), 1);
+    assert_eq!(heartbeats[0].blockchain, served);
+    let slot = svm.current_block(served).unwrap().block_height;
+    assert_eq!(heartbeats[0].slot, slot as u128);
+    assert_eq!(svm.heartbeats()[0].slot, slot as u128);
+
+    svm.latest_blockhash(served).unwrap();
+    svm.produce_blocks().unwrap();
+    let heartbeat = svm.heartbeats().remove(0);
+    assert_eq!(heartbeat.slot, slot as u128 + 1);
+    assert_eq!(heartbeat.block_height, heartbeat.slot);
+    assert!(heartbeat.timestamp >= heartbeats[0].timestamp);
+    assert_eq!(svm.publish_heartbeats(), 1);
+
+    svm.delete_blockchain(served).unwrap();
+    assert!(svm.heartbeats().is_empty());
+}
+
+#[actix_web::test]
+async fn test_heartbeats_of_held_blockchains() {
+    let storage = MemoryStorage::new();
+    let ttl = Duration::from_secs(10);
+    let first = SvmEngine::new(storage.clone()).with_leases(Uuid::new_v4(), ttl);
+    let second = SvmEngine::new(storage).with_leases(Uuid::new_v4(), ttl);
+    let id = first
+        .create_blockchain(Uuid::new_v4(), None, None, None, None, false)
+        .unwrap();
+    first.latest_blockhash(id).unwrap();
+    first.produce_blocks().unwrap();
+
+    // Only the holder sends them, even when both instances serve the blockchain
+    first.touch(id);
+    second.touch(id);
+    let heartbeats = first.heartbeats();
+    assert_eq!(heartbeats.len(), 1);
+    assert_eq!(heartbeats[0].blockchain, id);
+    assert!(second.heartbeats().is_empty());
+}
@@ -0,0 +1,29 @@
+use mockchain_engine::engine::idl::{decode_idl_account, encode_idl_account, idl_address};
+use solana_sdk::pubkey::Pubkey;
+use std::str::FromStr;
+
+#[test]
+fn test_idl_account_roundtrip() {
+    let authority = solana_sdk::pubkey::new_rand();
+    let idl = br#"{"version":"0.1.0","name":"counter","instructions":[]}"#;
+
+    let data = encode_idl_account(&authority, idl).unwrap();
+    let (decoded_authority, decoded_idl) = decode_idl_account(&data).unwrap();
+
+    assert_eq!(decoded_authority, authority);
+    assert_eq!(decoded_idl, idl.to_vec());
+}
+
+#[test]
+fn test_idl_address() {
+    // Where `anchor idl init` puts the IDL of Jupiter's v6 program
+    let program_id = Pubkey::from_str("JUP6LkbZbjS1jKKwapdHNy74zcZ3tLUZoi5QNyVTaV4").unwrap();
+    let expected = Pubkey::from_str("C88XWfp26heEmDkmfSzeXP7Fd7GQJ2j9dDTUsyiZbUTa").unwrap();
+
+    assert_eq!(idl_address(&program_id).unwrap(), expected);
+}
+
+#[test]
+fn test_decode_rejects_other_accounts() {
+    assert!(decode_idl_account(&[0u8; 64]).is_err());
+}
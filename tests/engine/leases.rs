@@ -0,0 +1,122 @@
+use actix_web::rt::time;
+use mockchain_engine::{
+    engine::{SvmEngine, SVM},
+    storage::{leases::Forwarded, memory::MemoryStorage, Storage},
+};
+use std::time::Duration;
+use uuid::Uuid;
+
+use crate::common::{processed, transfer};
+
+/// Two instances sharing storage, the first one owning a fresh blockchain.
+fn cluster(ttl: Duration) -> (SvmEngine<MemoryStorage>, SvmEngine<MemoryStorage>, Uuid) {
+    let storage = MemoryStorage::new();
+    let first = SvmEngine::new(storage.clone()).with_leases(Uuid::new_v4(), ttl);
+    let second = SvmEngine::new(storage).with_leases(Uuid::new_v4(), ttl);
+    let id = first
+        .create_blockchain(Uuid::new_v4(), None, None, None, None, false)
+        .unwrap();
+    (first, second, id)
+}
+
+#[actix_web::test]
+async fn test_transactions_forwarded_to_holder() {
+    let (first, second, id) = cluster(Duration::from_secs(10));
+    let signature = first
+        .send_transaction(id, transfer(&first, id), false)
+        .unwrap();
+    assert!(processed(&first, id, &signature).await);
+    assert_eq!(first.leases().unwrap().held(), vec![id]);
+
+    // The second instance hands its transactions to the holder
+    let signature = second
+        .send_transaction(id, transfer(&second, id), false)
+        .unwrap();
+    assert!(second.leases().unwrap().held().is_empty());
+    assert_eq!(second.run_forwarded().await.unwrap(), 0);
+    assert_eq!(first.run_forwarded().await.unwrap(), 1);
+    assert!(processed(&first, id, &signature).await);
+}
+
+#[actix_web::test]
+async fn test_blocks_produced_by_holder() {
+    let (first, second, id) = cluster(Duration::from_secs(10));
+    first.latest_blockhash(id).unwrap();
+    assert_eq!(first.produce_blocks().unwrap(), 1);
+    let height = first.current_block(id).unwrap().block_height;
+
+    second.latest_blockhash(id).unwrap();
+    assert_eq!(second.produce_blocks().unwrap(), 0);
+    assert_eq!(first.current_block(id).unwrap().block_height, height);
+
+    assert_eq!(first.run_forwarded().await.unwrap(), 1);
+    assert_eq!(first.produce_blocks().unwrap(), 1);
+    assert_eq!(first.current_block(id).unwrap().block_height, height + 1);
+}
+
+#[actix_web::test]
+async fn test_failover() {
+    let ttl = Duration::from_millis(200);
+    let (first, second, id) = cluster(ttl);
+    first.latest_blockhash(id).unwrap();
+    first.produce_blocks().unwrap();
+
+    // The holder stops renewing, work forwarded meanwhile isn't lost
+    let signature = second
+        .send_transaction(id, transfer(&second, id), false)
+        .unwrap();
+    drop(first);
+    time::sleep(ttl).await;
+    second.latest_blockhash(id).unwrap();
+    assert_eq!(second.produce_blocks().unwrap(), 1);
+    assert_eq!(second.leases().unwrap().held(), vec![id]);
+    assert_eq!(second.run_forwarded().await.unwrap(), 1);
+    assert!(processed(&second, id, &signature).await);
+}
+
+#[actix_web::test]
+async fn test_idle_leases_released() {
+    let ttl = Duration::from_millis(100);
+    let (first, second, id) = cluster(ttl);
+    first.latest_blockhash(id).unwrap();
+    first.produce_blocks().unwrap();
+    assert_eq!(first.renew_leases(), 1);
+
+    // Forwarded work keeps the lease held
+    second.latest_blockhash(id).unwrap();
+    second.produce_blocks().unwrap();
+    time::sleep(ttl).await;
+    assert_eq!(first.renew_leases(), 1);
+
+    first.run_forwarded().await.unwrap();
+    first.produce_blocks().unwrap();
+    time::sleep(ttl).await;
+    assert_eq!(first.renew_leases(), 0);
+    assert_eq!(
+        second.storage.acquire_lease(id, Uuid::nil(), ttl).unwrap(),
+        Uuid::nil()
+    );
+}
+
+#[actix_web::test]
+async fn test_forwarded_work_given_back() {
+    let (first, _, id) = cluster(Duration::from_secs(10));
+    let work =
+        |svm: &SvmEngine<MemoryStorage>| Forwarded::transaction(&transfer(svm, id), false).unwrap();
+    let (a, b, c) = (work(&first), work(&first), Forwarded::Block);
+    first.storage.forward(id, &a).unwrap();
+    first.storage.forward(id, &b).unwrap();
+
+    // Work that couldn't run goes back ahead of work forwarded since
+    let taken = first.storage.take_forwarded(&[id], 10).unwrap();
+    assert_eq!(taken, vec![(id, a.clone()), (id, b.clone())]);
+    first.storage.forward(id, &c).unwrap();
+    first
+        .storage
+        .return_forwarded(id, &[a.clone(), b.clone()])
+        .unwrap();
+    assert_eq!(
+        first.storage.take_forwarded(&[id], 10).unwrap(),
+        vec![(id, a), (id, b), (id, c)]
+    );
+}
@@ -0,0 +1,53 @@
+use mockchain_engine::engine::loader::upgradeable_program_accounts;
+use solana_sdk::{
+    account_utils::StateMut,
+    bpf_loader_upgradeable::{self, UpgradeableLoaderState},
+    rent::Rent,
+};
+
+#[test]
+fn test_upgradeable_program_accounts() {
+    let program_id = solana_sdk::pubkey::new_rand();
+    let authority = solana_sdk::pubkey::new_rand();
+    let elf = vec![7u8; 100];
+
+    let [(program_address, program), (programdata_address, programdata)] =
+        upgradeable_program_accounts(&program_id, &elf, Some(authority), &Rent::default()).unwrap();
+
+    assert_eq!(program_address, program_id);
+    assert!(program.executable);
+    assert_eq!(program.owner, bpf_loader_upgradeable::id());
+    assert_eq!(
+        program.state(),
+        Ok(UpgradeableLoaderState::Program {
+            programdata_address
+        })
+    );
+
+    assert!(!programdata.executable);
+    assert_eq!(
+        programdata.state(),
+        Ok(UpgradeableLoaderState::ProgramData {
+            slot: 0,
+            upgrade_authority_address: Some(authority),
+        })
+    );
+    assert_eq!(
+        &programdata.data[UpgradeableLoaderState::size_of_programdata_metadata()..],
+        elf.as_slice()
+    );
+}
+
+#[test]
+fn test_immutable_program_keeps_elf_offset() {
+    let program_id = solana_sdk::pubkey::new_rand();
+    let elf = vec![7u8; 100];
+
+    let [_, (_, programdata)] =
+        upgradeable_program_accounts(&program_id, &elf, None, &Rent::default()).unwrap();
+
+    assert_eq!(
+        programdata.data.len(),
+        UpgradeableLoaderState::size_of_programdata_metadata() + elf.len()
+    );
+}
@@ -0,0 +1,63 @@
+use mockchain_engine::{
+    engine::{SvmEngine, SVM},
+    storage::{blocks::DbBlockchainUpdate, memory::MemoryStorage, Storage},
+};
+use solana_sdk::{
+    pubkey::Pubkey, signer::Signer, system_instruction, transaction::Transaction,
+    transaction::VersionedTransaction,
+};
+use uuid::Uuid;
+
+use crate::common::engine;
+
+async fn simulated_logs(svm: &SvmEngine<MemoryStorage>, id: Uuid) -> Vec<String> {
+    let payer = svm.storage.get_blockchain(id).unwrap().airdrop_keypair;
+    let recipient = Pubkey::new_unique();
+    let blockhash = svm.latest_blockhash(id).unwrap().blockhash;
+    let tx = Transaction::new_signed_with_payer(
+        &[
+            system_instruction::transfer(&payer.pubkey(), &recipient, 1_000_000),
+            system_instruction::transfer(&payer.pubkey(), &recipient, 1_000_000),
+        ],
+        Some(&payer.pubkey()),
+        &[&payer],
+        blockhash,
+    );
+    svm.simulate_transaction(id, VersionedTransaction::from(tx), false)
+        .await
+        .unwrap()
+        .logs
+}
+
+fn set_log_bytes_limit(svm: &SvmEngine<MemoryStorage>, id: Uuid, limit: Option<i32>) {
+    let update = DbBlockchainUpdate {
+        log_bytes_limit: Some(limit),
+        ..Default::default()
+    };
+    let blockchain = svm.storage.update_blockchain(id, &update).unwrap();
+    assert_eq!(blockchain.log_bytes_limit, limit);
+}
+
+#[actix_web::test]
+async fn test_log_bytes_limit() {
+    let (svm, id) = engine();
+
+    // Solana's limit keeps both transfers' logs
+    let logs = simulated_logs(&svm, id).await;
+    assert_eq!(logs.len(), 4);
+    assert!(!logs.contains(&"Log truncated".to_string()));
+
+    // The first transfer's 99 bytes fit, the second's don't
+    set_log_bytes_limit(&svm, id, Some(100));
+    let logs = simulated_logs(&svm, id).await;
+    assert_eq!(logs.len(), 3);
+    assert_eq!(logs.last().unwrap(), "Log truncated");
+
+    set_log_bytes_limit(&svm, id, Some(0));
+    assert_eq!(simulated_logs(&svm, id).await.len(), 4);
+
+    // Cleared, Solana's limit applies again
+    set_log_bytes_limit(&svm, id, Some(100));
+    set_log_bytes_limit(&svm, id, None);
+    assert_eq!(simulated_logs(&svm, id).await.len(), 4);
+}
@@ -0,0 +1,24 @@
+pub mod account_quota;
+pub mod auto_pause;
+pub mod execution_timeout;
+pub mod fee_sponsorship;
+pub mod genesis;
+pub mod heartbeats;
+pub mod idl;
+pub mod leases;
+pub mod loader;
+pub mod log_limit;
+pub mod parity;
+pub mod pending;
+pub mod preflight;
+pub mod programs;
+pub mod queue;
+pub mod simulations;
+pub mod status_cache;
+pub mod subscriptions;
+pub mod sweep;
+pub mod sysvars;
+pub mod timeline;
+pub mod token_balances;
+pub mod top_ups;
+pub mod write_locks;
@@ -0,0 +1,123 @@
+use mockchain_engine::{
+    engine::{parity::ExecutionOutcome, SVM},
+    storage::{blocks::DbBlockchainUpdate, Storage},
+};
+use solana_sdk::{
+    account::{AccountSharedData, WritableAccount},
+    pubkey::Pubkey,
+    signature::Signature,
+    signer::Signer,
+    system_program,
+    transaction::{Transaction, VersionedTransaction},
+};
+
+use crate::common::{engine, processed, transfer};
+
+#[test]
+fn test_divergences() {
+    let (payer, token_account) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let accounts = vec![
+        (
+            payer,
+            AccountSharedData::new(1_000, 0, &system_program::id()),
+        ),
+        (
+            token_account,
+            AccountSharedData::new(2_039_280, 165, &spl_token::id()),
+        ),
+    ];
+    let logs = vec![
+        "Program log: one".to_string(),
+        "Program log: two".to_string(),
+    ];
+    let executed = ExecutionOutcome::new(4_645, logs.clone(), &accounts);
+    assert!(executed
+        .divergences(&ExecutionOutcome::new(4_645, logs, &accounts))
+        .is_empty());
+
+    let mut changed = accounts.clone();
+    changed[1].1.set_lamports(0);
+    let simulated = ExecutionOutcome::new(4_700, vec!["Program log: one".to_string()], &changed);
+    assert_eq!(
+        executed.divergences(&simulated),
+        vec![
+            "executed with 4645 compute units, simulated with 4700".to_string(),
+            "log line 1 executed as Some(\"Program log: two\"), simulated as None".to_string(),
+            format!(
+                "account {} executed as {}, simulated as {}",
+                token_account, executed.accounts[1].1, simulated.accounts[1].1
+            ),
+        ]
+    );
+
+    let simulated = ExecutionOutcome::new(4_645, executed.logs.clone(), &accounts[..1]);
+    assert_eq!(
+        executed.divergences(&simulated),
+        vec!["executed with 2 accounts, simulated with 1".to_string()]
+    );
+}
+
+#[actix_web::test]
+async fn test_parity_check() {
+    let (svm, id) = engine();
+    assert!(!svm.storage.get_blockchain(id).unwrap().parity_check);
+    let signature = svm.send_transaction(id, transfer(&svm, id), false).unwrap();
+    assert!(processed(&svm, id, &signature).await);
+    assert_eq!(svm.engine_stats().parity_checks, 0);
+    let update = DbBlockchainUpdate {
+        parity_check: Some(true),
+        ..Default::default()
+    };
+    assert!(
+        svm.storage
+            .update_blockchain(id, &update)
+            .unwrap()
+            .parity_check
+    );
+
+    let payer = svm.storage.get_blockchain(id).unwrap().airdrop_keypair;
+    let (mint, source) = svm
+        .mint_tokens(id, None, 6, &payer.pubkey(), 1_000)
+        .await
+        .unwrap();
+    let (_, destination) = svm
+        .mint_tokens(id, Some(mint), 6, &Pubkey::new_unique(), 0)
+        .await
+        .unwrap();
+    let blockhash = svm.latest_blockhash(id).unwrap().blockhash;
+    let tx = VersionedTransaction::from(Transaction::new_signed_with_payer(
+        &[spl_token::instruction::transfer(
+            &spl_token::id(),
+            &source,
+            &destination,
+            &payer.pubkey(),
+            &[],
+            250,
+        )
+        .unwrap()],
+        Some(&payer.pubkey()),
+        &[&payer],
+        blockhash,
+    ));
+    // Executed with the programs its preflight compiled, simulated without them
+    let simulated = svm
+        .simulate_transaction(id, tx.clone(), false)
+        .await
+        .unwrap();
+    assert_eq!(simulated.err, None);
+
+    let signature = svm.send_transaction(id, tx, false).unwrap();
+    assert!(processed(&svm, id, &signature).await);
+    let signature: Signature = signature.parse().unwrap();
+    let (_, meta, _) = svm.get_transaction(id, &signature).unwrap().unwrap();
+    assert_eq!(meta.err, None);
+    assert_eq!(
+        meta.compute_units_consumed,
+        simulated.compute_units_consumed
+    );
+    assert_eq!(meta.log_messages, simulated.logs);
+    // The check compared the execution with its own simulation and agreed
+    let stats = svm.engine_stats();
+    assert_eq!(stats.parity_checks, 1);
+    assert_eq!(stats.parity_divergences, 0);
+}
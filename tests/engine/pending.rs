@@ -0,0 +1,111 @@
+use mockchain_engine::{
+    engine::{SvmEngine, SVM},
+    storage::{memory::MemoryStorage, transactions::DbPendingTransaction, Storage},
+};
+use solana_sdk::{
+    hash::Hash, native_token::LAMPORTS_PER_SOL, pubkey::Pubkey, transaction::VersionedTransaction,
+};
+use uuid::Uuid;
+
+use crate::common::{eventually, processed, transfer_to};
+
+fn transfer(svm: &SvmEngine<MemoryStorage>, id: Uuid, blockhash: Hash) -> VersionedTransaction {
+    transfer_to(
+        svm,
+        id,
+        &Pubkey::new_unique(),
+        LAMPORTS_PER_SOL,
+        Some(blockhash),
+    )
+}
+
+#[actix_web::test]
+async fn test_pending_until_saved() {
+    let storage = MemoryStorage::new();
+    let instance = Uuid::new_v4();
+    let svm = SvmEngine::new(storage.clone()).with_persist_queue(Some(instance));
+    let id = svm
+        .create_blockchain(Uuid::new_v4(), None, None, None, None, false)
+        .unwrap();
+    let blockhash = svm.latest_blockhash(id).unwrap().blockhash;
+    let settled = || {
+        storage
+            .get_pending_transactions(instance)
+            .unwrap()
+            .is_empty()
+    };
+
+    let signature = svm
+        .send_transaction(id, transfer(&svm, id, blockhash), false)
+        .unwrap();
+    assert!(processed(&svm, id, &signature).await);
+    assert!(eventually(settled).await);
+
+    // Failed transactions aren't retried either
+    svm.send_transaction(id, transfer(&svm, id, Hash::new_unique()), false)
+        .unwrap();
+    assert!(eventually(settled).await);
+}
+
+#[actix_web::test]
+async fn test_resume_after_crash() {
+    let storage = MemoryStorage::new();
+    let instance = Uuid::new_v4();
+    let svm = SvmEngine::new(storage.clone()).with_persist_queue(Some(instance));
+    let id = svm
+        .create_blockchain(Uuid::new_v4(), None, None, None, None, false)
+        .unwrap();
+    let blockhash = svm.latest_blockhash(id).unwrap().blockhash;
+    let settled = || {
+        storage
+            .get_pending_transactions(instance)
+            .unwrap()
+            .is_empty()
+    };
+
+    // Accepted by an engine that died before processing them
+    let first = transfer(&svm, id, blockhash);
+    let second = transfer(&svm, id, blockhash);
+    let other = transfer(&svm, id, blockhash);
+    let unscoped = transfer(&svm, id, blockhash);
+    for (tx, instance) in [
+        (&first, Some(instance)),
+        (&second, Some(instance)),
+        (&other, Some(Uuid::new_v4())),
+        (&unscoped, None),
+    ] {
+        storage
+            .save_pending_transaction(&DbPendingTransaction::new(id, instance, tx, false).unwrap())
+            .unwrap();
+    }
+
+    // Without an instance there's nothing this engine can call its own
+    let anonymous = SvmEngine::new(storage.clone()).with_persist_queue(None);
+    assert_eq!(anonymous.resume_pending_transactions().await.unwrap(), 0);
+
+    let restarted = SvmEngine::new(storage.clone()).with_persist_queue(Some(instance));
+    assert_eq!(restarted.resume_pending_transactions().await.unwrap(), 2);
+    assert!(eventually(settled).await);
+    for tx in [&first, &second] {
+        let saved = storage.get_transaction(id, &tx.signatures[0]).unwrap();
+        assert!(saved.is_some());
+    }
+    // Queued by another instance, which resumes it itself, or by none, which
+    // every instance would resume
+    for tx in [&other, &unscoped] {
+        let saved = storage.get_transaction(id, &tx.signatures[0]).unwrap();
+        assert!(saved.is_none());
+    }
+
+    // Saved before the crash, only the pending row is cleaned up
+    storage
+        .save_pending_transaction(
+            &DbPendingTransaction::new(id, Some(instance), &first, false).unwrap(),
+        )
+        .unwrap();
+    assert_eq!(restarted.resume_pending_transactions().await.unwrap(), 0);
+    assert!(storage
+        .get_pending_transactions(instance)
+        .unwrap()
+        .is_empty());
+}
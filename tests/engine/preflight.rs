@@ -0,0 +1,55 @@
+use mockchain_engine::engine::{expiring::ExpiringMap, SVM};
+use solana_sdk::{native_token::LAMPORTS_PER_SOL, pubkey::Pubkey};
+use std::time::Duration;
+
+use crate::common::{engine, processed, transfer_to};
+
+#[actix_web::test]
+async fn test_preflight_taken_when_sent() {
+    let (svm, id) = engine();
+    let tx = transfer_to(&svm, id, &Pubkey::new_unique(), LAMPORTS_PER_SOL, None);
+    let simulated = svm.simulate_transaction(id, tx.clone(), false).await;
+    assert!(simulated.unwrap().err.is_none());
+    assert_eq!(svm.engine_stats().cached_preflights, 1);
+
+    let signature = svm.send_transaction(id, tx, false).unwrap();
+    assert!(processed(&svm, id, &signature).await);
+    assert_eq!(svm.engine_stats().cached_preflights, 0);
+}
+
+#[actix_web::test]
+async fn test_failed_preflight_not_cached() {
+    let (svm, id) = engine();
+    let tx = transfer_to(&svm, id, &Pubkey::new_unique(), u64::MAX, None);
+    let simulated = svm.simulate_transaction(id, tx, false).await;
+    assert!(simulated.unwrap().err.is_some());
+    assert_eq!(svm.engine_stats().cached_preflights, 0);
+}
+
+#[test]
+fn test_preflights_bounded_by_bytes() {
+    let mut map =
+        ExpiringMap::with_max_weight(Duration::from_secs(60), 10, 100, |bytes: &Vec<u8>| {
+            bytes.len()
+        });
+    map.insert(1, vec![0; 60]);
+    map.insert(2, vec![0; 30]);
+    assert_eq!(map.weight(), 90);
+
+    // The oldest entries make room
+    map.insert(3, vec![0; 40]);
+    assert_eq!(map.get(&1), None);
+    assert_eq!(map.weight(), 70);
+
+    // Replaced entries only count once
+    map.insert(2, vec![0; 10]);
+    assert_eq!(map.weight(), 50);
+
+    // Too heavy to ever fit
+    map.insert(4, vec![0; 101]);
+    assert_eq!(map.get(&4), None);
+    assert_eq!(map.weight(), 50);
+
+    assert_eq!(map.remove(&3).map(|bytes| bytes.len()), Some(40));
+    assert_eq!(map.weight(), 10);
+}
@@ -0,0 +1,84 @@
+use mockchain_engine::{
+    engine::{spl::bundle_programs, SvmEngine, SVM},
+    storage::{memory::MemoryStorage, Storage},
+};
+use solana_sdk::{bpf_loader, pubkey, pubkey::Pubkey};
+use std::fs;
+use uuid::Uuid;
+
+const TOKEN_PROGRAM_ID: Pubkey = pubkey!("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA");
+const MEMO_PROGRAM_ID: Pubkey = pubkey!("MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcHr");
+const MEMO: &[u8] = include_bytes!("../../src/engine/spl/programs/spl_memo-3.0.0.so");
+
+fn program_data(svm: &SvmEngine<MemoryStorage>, id: Uuid, program_id: &Pubkey) -> Vec<u8> {
+    let account = svm.storage.get_account(id, program_id).unwrap().unwrap();
+    assert!(account.executable);
+    assert_eq!(account.owner, bpf_loader::id());
+    account.data
+}
+
+#[actix_web::test]
+async fn test_programs_directory() {
+    let directory = std::env::temp_dir().join(format!("programs-{}", Uuid::new_v4()));
+    fs::create_dir(&directory).unwrap();
+    let extra = Pubkey::new_unique();
+    fs::write(directory.join(format!("{}.so", TOKEN_PROGRAM_ID)), MEMO).unwrap();
+    fs::write(directory.join(format!("{}.so", extra)), MEMO).unwrap();
+    fs::write(directory.join("memo.so"), b"not a program").unwrap();
+
+    let embedded = SvmEngine::new(MemoryStorage::new());
+    let id = embedded
+        .create_blockchain(Uuid::new_v4(), None, None, None, None, false)
+        .unwrap();
+    assert_ne!(program_data(&embedded, id, &TOKEN_PROGRAM_ID), MEMO);
+    assert!(embedded.storage.get_account(id, &extra).unwrap().is_none());
+    assert!(embedded
+        .set_genesis_programs(id, &[(MEMO_PROGRAM_ID, "memo.so".to_string())])
+        .is_err());
+
+    let svm = SvmEngine::new(MemoryStorage::new()).with_programs_directory(Some(directory.clone()));
+    let id = svm
+        .create_blockchain(Uuid::new_v4(), None, None, None, None, false)
+        .unwrap();
+    assert_eq!(program_data(&svm, id, &TOKEN_PROGRAM_ID), MEMO);
+    assert_eq!(program_data(&svm, id, &extra), MEMO);
+
+    // Per-blockchain builds are picked by file name, within the directory
+    for file in ["", "..", "../memo.so", "missing.so"] {
+        assert!(svm
+            .set_genesis_programs(id, &[(MEMO_PROGRAM_ID, file.to_string())])
+            .is_err());
+    }
+    svm.set_genesis_programs(id, &[(MEMO_PROGRAM_ID, "memo.so".to_string())])
+        .unwrap();
+    assert_eq!(program_data(&svm, id, &MEMO_PROGRAM_ID), b"not a program");
+
+    fs::remove_dir_all(directory).unwrap();
+}
+
+#[actix_web::test]
+async fn test_metaplex_bundle() {
+    let programs = bundle_programs("metaplex").unwrap();
+    assert!(programs.contains(&pubkey!("CndyV3LdqHUfDLmE5naZjVN8rBZz4tqhdefbAnjHG3JR")));
+    assert!(programs.contains(&pubkey!("BGUMAp9Gq7iTEuizy4pqaxsTyUCBK68MDfK752saRPUY")));
+    assert!(bundle_programs("raydium").is_err());
+
+    let svm = SvmEngine::new(MemoryStorage::new());
+    let config = svm
+        .storage
+        .create_config(None, "nft".to_string(), vec!["metaplex".to_string()])
+        .unwrap();
+    let stored = svm.storage.get_config(config.id).unwrap().unwrap();
+    assert_eq!(stored.bundles, vec!["metaplex"]);
+
+    let id = svm
+        .create_blockchain(Uuid::new_v4(), None, None, None, Some(config.id), false)
+        .unwrap();
+    assert!(svm.clone_bundles(id, &[]).await.unwrap().is_empty());
+    assert!(svm
+        .clone_bundles(id, &["raydium".to_string()])
+        .await
+        .is_err());
+    // Bundles are cloned from mainnet, which this storage has no RPC for
+    assert!(svm.clone_bundles(id, &stored.bundles).await.is_err());
+}
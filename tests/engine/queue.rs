@@ -0,0 +1,103 @@
+//! The Kafka round trip needs brokers, so it only runs when asked:
+//!
+//! KAFKA_URL=localhost:9092 cargo test engine::queue -- --ignored
+
+use actix_web::rt::time;
+use mockchain_engine::{
+    engine::{
+        engine::QUEUE_FULL,
+        queue::{KafkaQueue, QueuedTransaction},
+        SvmEngine, SVM,
+    },
+    settings::QueueSettings,
+    storage::{memory::MemoryStorage, Storage},
+};
+use std::{sync::Arc, time::Duration};
+use uuid::Uuid;
+
+use crate::common::{engine, eventually, transfer};
+
+#[test]
+fn test_queued_transaction_roundtrip() {
+    let (svm, id) = engine();
+    let tx = transfer(&svm, id);
+    let queued = QueuedTransaction::new(id, &tx, true).unwrap();
+    let json = serde_json::to_string(&queued).unwrap();
+    let decoded: QueuedTransaction = serde_json::from_str(&json).unwrap();
+    assert_eq!(decoded, queued);
+    assert_eq!(decoded.decode().unwrap(), tx);
+}
+
+#[actix_web::test]
+async fn test_queue_backpressure_and_idle_workers() {
+    let svm =
+        SvmEngine::new(MemoryStorage::new()).with_queue_limits(1, Some(Duration::from_millis(50)));
+    let id = svm
+        .create_blockchain(Uuid::new_v4(), None, None, None, None, false)
+        .unwrap();
+
+    // The worker only runs once this test yields, so the second send finds it full
+    let first = svm.send_transaction(id, transfer(&svm, id), false).unwrap();
+    assert_eq!(
+        svm.send_transaction(id, transfer(&svm, id), false),
+        Err(QUEUE_FULL.to_string())
+    );
+    let stats = svm.engine_stats();
+    assert_eq!(stats.in_flight_transactions, 1);
+    assert_eq!(stats.queues.len(), 1);
+    assert_eq!(stats.queues[0].capacity, 1);
+    assert_eq!(stats.queues[0].rejected, 1);
+
+    assert!(eventually(|| svm.engine_stats().in_flight_transactions == 0).await);
+    assert!(svm
+        .storage
+        .get_transaction(id, &first.parse().unwrap())
+        .unwrap()
+        .is_some());
+
+    // Idle workers stop, the next send starts another
+    time::sleep(Duration::from_millis(200)).await;
+    assert!(svm.engine_stats().queues.is_empty());
+    let second = svm.send_transaction(id, transfer(&svm, id), false).unwrap();
+    assert!(eventually(|| svm.engine_stats().in_flight_transactions == 0).await);
+    assert!(svm
+        .storage
+        .get_transaction(id, &second.parse().unwrap())
+        .unwrap()
+        .is_some());
+}
+
+#[actix_web::test]
+#[ignore]
+async fn test_kafka_queue() {
+    let brokers = std::env::var("KAFKA_URL").unwrap_or_else(|_| "localhost:9092".to_string());
+    let settings = QueueSettings {
+        topic: format!("transactions-{}", Uuid::new_v4()),
+        group_id: format!("mirror-engine-{}", Uuid::new_v4()),
+        partitions: 4,
+        consumers: 2,
+        ..QueueSettings::default()
+    };
+    let queue = KafkaQueue::new(&brokers, &settings).unwrap();
+    let svm = Arc::new(SvmEngine::new(MemoryStorage::new()).with_kafka_queue(queue.clone()));
+    queue.start(svm.clone());
+    let id = svm
+        .create_blockchain(Uuid::new_v4(), None, None, None, None, false)
+        .unwrap();
+
+    let signature = svm.send_transaction(id, transfer(&svm, id), false).unwrap();
+    let signature = signature.parse().unwrap();
+    for _ in 0..300 {
+        if svm
+            .storage
+            .get_transaction(id, &signature)
+            .unwrap()
+            .is_some()
+        {
+            svm.drain(Duration::from_secs(5)).await;
+            return;
+        }
+        time::sleep(Duration::from_millis(100)).await;
+    }
+    panic!("Queued transaction was not processed");
+}
@@ -0,0 +1,47 @@
+use actix_web::rt;
+use mockchain_engine::engine::simulations::{SimulationPools, SIMULATIONS_BUSY};
+use std::sync::Arc;
+use tokio::sync::Semaphore;
+use uuid::Uuid;
+
+use crate::common::eventually;
+
+#[actix_web::test]
+async fn test_simulations_bounded_per_blockchain() {
+    let pools = Arc::new(SimulationPools::new(1, 1));
+    let release = Arc::new(Semaphore::new(0));
+    let busy = Uuid::new_v4();
+
+    // One simulation runs and one waits for the worker
+    let handles: Vec<_> = (0..2)
+        .map(|i| {
+            let (pools, release) = (pools.clone(), release.clone());
+            rt::spawn(async move {
+                let simulation = async { release.acquire().await.unwrap().forget() };
+                pools
+                    .run(busy, async {
+                        simulation.await;
+                        i
+                    })
+                    .await
+            })
+        })
+        .collect();
+    assert!(eventually(|| pools.admitted(busy) == 2).await);
+
+    assert_eq!(
+        pools.run(busy, async {}).await,
+        Err(SIMULATIONS_BUSY.to_string())
+    );
+    // Other blockchains aren't held up
+    assert_eq!(pools.run(Uuid::new_v4(), async { 7 }).await, Ok(7));
+
+    release.add_permits(2);
+    let mut results = Vec::new();
+    for handle in handles {
+        results.push(handle.await.unwrap().unwrap());
+    }
+    assert_eq!(results, vec![0, 1]);
+    assert_eq!(pools.admitted(busy), 0);
+    assert_eq!(pools.run(busy, async { 3 }).await, Ok(3));
+}
@@ -0,0 +1,130 @@
+use mockchain_engine::engine::{
+    commitment_slot, expiring::ExpiringMap, tx_confirmation_status, FINALIZATION_DEPTH, SVM,
+};
+use solana_banks_interface::TransactionConfirmationStatus;
+use solana_sdk::{pubkey::Pubkey, signature::Signature};
+use std::{thread, time::Duration};
+
+use crate::common::{engine, processed, transfer_to};
+
+#[actix_web::test]
+async fn test_signature_status_cache() {
+    let (svm, id) = engine();
+    // Past genesis, so the transaction doesn't land in an already finalized slot
+    for _ in 0..FINALIZATION_DEPTH {
+        svm.latest_blockhash(id).unwrap();
+        svm.produce_blocks().unwrap();
+    }
+    let tx = transfer_to(&svm, id, &Pubkey::new_unique(), 1_000_000, None);
+
+    // Unknown signatures aren't cached, they may still be processed
+    assert!(svm
+        .get_signature_status(id, &Signature::new_unique())
+        .unwrap()
+        .is_none());
+    assert_eq!(svm.engine_stats().cached_statuses, 0);
+
+    let signature = svm.send_transaction(id, tx, false).unwrap();
+    assert!(processed(&svm, id, &signature).await);
+    let signature: Signature = signature.parse().unwrap();
+    let status = svm.get_signature_status(id, &signature).unwrap().unwrap();
+    assert_eq!(status.err, None);
+    assert_eq!(
+        status.confirmation_status,
+        Some(TransactionConfirmationStatus::Processed)
+    );
+    assert_eq!(svm.engine_stats().cached_statuses, 1);
+
+    // Polling an unfinalized status keeps blocks coming until it's finalized
+    svm.produce_blocks().unwrap();
+    assert_eq!(
+        svm.get_signature_status(id, &signature)
+            .unwrap()
+            .unwrap()
+            .confirmation_status,
+        Some(TransactionConfirmationStatus::Confirmed)
+    );
+    for _ in 1..FINALIZATION_DEPTH {
+        svm.produce_blocks().unwrap();
+        svm.get_signature_status(id, &signature).unwrap();
+    }
+    let latest = svm.get_latest_block(id).unwrap().block_height;
+    assert_eq!(
+        tx_confirmation_status(status.slot, latest),
+        TransactionConfirmationStatus::Finalized
+    );
+    assert_eq!(
+        svm.get_signature_status(id, &signature)
+            .unwrap()
+            .unwrap()
+            .confirmation_status,
+        Some(TransactionConfirmationStatus::Finalized)
+    );
+    assert!(status.slot <= commitment_slot(latest, &TransactionConfirmationStatus::Finalized));
+
+    let (_, _, stored) = svm.get_transaction(id, &signature).unwrap().unwrap();
+    assert_eq!(stored.slot, status.slot);
+    assert_eq!(
+        svm.get_signature_status(id, &signature)
+            .unwrap()
+            .unwrap()
+            .slot,
+        status.slot
+    );
+
+    svm.delete_blockchain(id).unwrap();
+    assert_eq!(svm.engine_stats().cached_statuses, 0);
+}
+
+#[actix_web::test]
+async fn test_finalized_without_block_production() {
+    let (svm, id) = engine();
+    let svm = svm.with_block_production(false);
+    let tx = transfer_to(&svm, id, &Pubkey::new_unique(), 1_000_000, None);
+    let signature = svm.send_transaction(id, tx, false).unwrap();
+    assert!(processed(&svm, id, &signature).await);
+    let signature: Signature = signature.parse().unwrap();
+
+    // Nothing produces blocks, confirmation loops would wait forever otherwise
+    let (slot, statuses) = svm
+        .get_signature_statuses(id, &[signature, Signature::new_unique()])
+        .unwrap();
+    assert_eq!(slot, svm.get_latest_block(id).unwrap().block_height);
+    assert_eq!(statuses.len(), 2);
+    assert_eq!(
+        statuses[0].as_ref().unwrap().confirmation_status,
+        Some(TransactionConfirmationStatus::Finalized)
+    );
+    assert!(statuses[1].is_none());
+}
+
+#[test]
+fn test_expiring_map_evicts_oldest() {
+    let mut map = ExpiringMap::new(Duration::from_secs(60), 3);
+    map.insert("a", 1);
+    map.insert("b", 2);
+    map.insert("c", 3);
+    // Full, the oldest entry makes room
+    map.insert("b", 4);
+    assert_eq!(map.len(), 2);
+    assert_eq!(map.get(&"a"), None);
+    assert_eq!(map.get(&"b"), Some(&4));
+
+    // Inserted again, "b" is newer than "c"
+    map.insert("d", 5);
+    map.insert("e", 6);
+    assert_eq!(map.len(), 3);
+    assert_eq!(map.get(&"c"), None);
+    assert_eq!(map.get(&"b"), Some(&4));
+    assert_eq!(map.remove(&"d"), Some(5));
+    map.retain(|key, _| *key != "b");
+    assert_eq!(map.len(), 1);
+
+    let mut map = ExpiringMap::new(Duration::from_millis(20), 3);
+    map.insert("a", 1);
+    thread::sleep(Duration::from_millis(30));
+    assert_eq!(map.get(&"a"), None);
+    // Expired entries go first, whatever the capacity
+    map.insert("b", 2);
+    assert_eq!(map.len(), 1);
+}
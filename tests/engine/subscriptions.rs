@@ -0,0 +1,142 @@
+use actix_web::rt::time;
+use mockchain_engine::{
+    engine::{
+        subscriptions::{LogsFilter, Subscriptions},
+        transactions::is_vote_transaction,
+        SVM,
+    },
+    storage::Storage,
+};
+use solana_sdk::{
+    hash::Hash,
+    pubkey::Pubkey,
+    signature::Signature,
+    signer::Signer,
+    system_instruction,
+    transaction::{Transaction, VersionedTransaction},
+    vote::{instruction as vote_instruction, state::Vote},
+};
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+use crate::common::engine;
+
+#[test]
+fn test_subscriptions() {
+    let subscriptions = Subscriptions::default();
+    subscriptions.add(1);
+    subscriptions.add(1);
+    subscriptions.add(2);
+    assert_eq!(subscriptions.len(), 3);
+
+    // Another connection using the same id keeps its subscription
+    subscriptions.remove(1).unwrap();
+    assert!(subscriptions.contains(1));
+    subscriptions.remove(1).unwrap();
+    assert!(!subscriptions.contains(1));
+    assert!(subscriptions.remove(1).is_err());
+    assert!(subscriptions.contains(2));
+}
+
+#[test]
+fn test_concurrent_subscriptions() {
+    let subscriptions = Subscriptions::default();
+    let threads: Vec<_> = (0..8)
+        .map(|thread| {
+            let subscriptions = subscriptions.clone();
+            std::thread::spawn(move || {
+                for id in 0..1000 {
+                    subscriptions.add(thread * 1000 + id);
+                    assert!(subscriptions.contains(thread * 1000 + id));
+                    subscriptions.remove(thread * 1000 + id).unwrap();
+                }
+            })
+        })
+        .collect();
+    for thread in threads {
+        thread.join().unwrap();
+    }
+    assert!(subscriptions.is_empty());
+}
+
+#[test]
+fn test_registration() {
+    let subscriptions = Subscriptions::default();
+    let registration = subscriptions.register(1);
+    assert!(subscriptions.contains(1));
+    drop(registration);
+    assert!(subscriptions.is_empty());
+
+    // Unsubscribing first leaves nothing for the drop to remove
+    let registration = subscriptions.register(2);
+    subscriptions.remove(2).unwrap();
+    drop(registration);
+    assert!(subscriptions.is_empty());
+}
+
+#[test]
+fn test_logs_filter() {
+    let pubkey = Pubkey::new_unique();
+    let accounts = vec![pubkey.to_string()];
+    assert!(LogsFilter::All.matches(&accounts, false));
+    assert!(!LogsFilter::All.matches(&accounts, true));
+    assert!(LogsFilter::AllWithVotes.matches(&accounts, true));
+    assert!(LogsFilter::Mentions(pubkey).matches(&accounts, true));
+    assert!(!LogsFilter::Mentions(Pubkey::new_unique()).mentioned(&accounts));
+}
+
+async fn next_signature<A, B, C>(
+    receiver: &mut mpsc::Receiver<Option<(Signature, A, B, C)>>,
+) -> Signature {
+    let (signature, _, _, _) = time::timeout(Duration::from_secs(5), receiver.recv())
+        .await
+        .unwrap()
+        .unwrap()
+        .unwrap();
+    signature
+}
+
+#[actix_web::test]
+async fn test_logs_subscribe_votes() {
+    let (svm, id) = engine();
+    let payer = svm.storage.get_blockchain(id).unwrap().airdrop_keypair;
+    let mut all = svm.logs_subscribe(id, 1, &LogsFilter::All).unwrap();
+    let mut with_votes = svm
+        .logs_subscribe(id, 2, &LogsFilter::AllWithVotes)
+        .unwrap();
+
+    let blockhash = svm.latest_blockhash(id).unwrap().blockhash;
+    let vote = VersionedTransaction::from(Transaction::new_signed_with_payer(
+        &[vote_instruction::vote(
+            &Pubkey::new_unique(),
+            &payer.pubkey(),
+            Vote::new(vec![0], Hash::default()),
+        )],
+        Some(&payer.pubkey()),
+        &[&payer],
+        blockhash,
+    ));
+    let transfer = VersionedTransaction::from(Transaction::new_signed_with_payer(
+        &[system_instruction::transfer(
+            &payer.pubkey(),
+            &Pubkey::new_unique(),
+            1_000_000,
+        )],
+        Some(&payer.pubkey()),
+        &[&payer],
+        blockhash,
+    ));
+    assert!(is_vote_transaction(&vote));
+    assert!(!is_vote_transaction(&transfer));
+    // Sent one after the other so they're notified in order
+    svm.send_transaction(id, vote.clone(), false).unwrap();
+    time::sleep(Duration::from_millis(200)).await;
+    svm.send_transaction(id, transfer.clone(), false).unwrap();
+
+    assert_eq!(next_signature(&mut all).await, transfer.signatures[0]);
+    assert_eq!(next_signature(&mut with_votes).await, vote.signatures[0]);
+    assert_eq!(
+        next_signature(&mut with_votes).await,
+        transfer.signatures[0]
+    );
+}
@@ -0,0 +1,148 @@
+use mockchain_engine::{
+    engine::{
+        blocks::{AccountsStatus, Blockchain},
+        SVM,
+    },
+    storage::Storage,
+};
+use solana_sdk::{
+    account::Account,
+    pubkey::Pubkey,
+    signature::{Keypair, Signature},
+    signer::Signer,
+    system_instruction, system_program,
+    transaction::{Transaction, VersionedTransaction},
+};
+use uuid::Uuid;
+
+use crate::common::{engine, processed};
+
+fn account(lamports: u64, data: Vec<u8>) -> Account {
+    Account {
+        lamports,
+        data,
+        owner: system_program::id(),
+        executable: false,
+        rent_epoch: u64::MAX,
+    }
+}
+
+#[actix_web::test]
+async fn test_drained_account_deleted() {
+    let (svm, id) = engine();
+    let payer = Keypair::new();
+    let recipient = Pubkey::new_unique();
+    svm.airdrop(id, &payer.pubkey(), 1_000_000).await.unwrap();
+
+    // Sends everything left after the fee
+    let blockhash = svm.latest_blockhash(id).unwrap().blockhash;
+    let tx = VersionedTransaction::from(Transaction::new_signed_with_payer(
+        &[system_instruction::transfer(
+            &payer.pubkey(),
+            &recipient,
+            1_000_000 - 5000,
+        )],
+        Some(&payer.pubkey()),
+        &[&payer],
+        blockhash,
+    ));
+    let signature = svm.send_transaction(id, tx, false).unwrap();
+    assert!(processed(&svm, id, &signature).await);
+    let signature: Signature = signature.parse().unwrap();
+    let status = svm.get_signature_status(id, &signature).unwrap().unwrap();
+    assert_eq!(status.err, None);
+
+    assert_eq!(svm.storage.get_account(id, &payer.pubkey()).unwrap(), None);
+    assert_eq!(
+        svm.storage
+            .get_account(id, &recipient)
+            .unwrap()
+            .map(|account| account.lamports),
+        Some(1_000_000 - 5000)
+    );
+}
+
+#[test]
+fn test_sweep_empty_accounts() {
+    let (svm, id) = engine();
+    let jit_id = svm
+        .storage
+        .set_blockchain(&Blockchain {
+            id: Uuid::new_v4(),
+            created_at: chrono::Utc::now().naive_utc(),
+            airdrop_keypair: Keypair::new(),
+            team_id: Uuid::new_v4(),
+            label: None,
+            expiry: None,
+            jit: true,
+            config: None,
+            pooled: false,
+            log_bytes_limit: None,
+            read_only: false,
+            sponsor_fees: false,
+            parity_check: false,
+            accounts_status: AccountsStatus::Ready,
+        })
+        .unwrap();
+
+    let (empty, funded, with_data) = (
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+    );
+    let accounts = vec![
+        (empty, account(0, vec![])),
+        (funded, account(1, vec![])),
+        (with_data, account(0, vec![1, 2, 3])),
+    ];
+    svm.storage.set_accounts(id, accounts.clone()).unwrap();
+    svm.storage.set_accounts(jit_id, accounts).unwrap();
+
+    assert_eq!(
+        svm.storage.get_empty_accounts(100).unwrap(),
+        vec![(id, empty)]
+    );
+    assert_eq!(svm.sweep_empty_accounts().unwrap(), 1);
+    assert_eq!(svm.storage.get_account(id, &empty).unwrap(), None);
+    assert!(svm.storage.get_account(id, &funded).unwrap().is_some());
+    assert!(svm.storage.get_account(id, &with_data).unwrap().is_some());
+    // Deleted on a JIT blockchain, the account would be fetched upstream again
+    assert!(svm.storage.get_account(jit_id, &empty).unwrap().is_some());
+
+    assert_eq!(svm.sweep_empty_accounts().unwrap(), 0);
+}
+
+#[test]
+fn test_sweep_keeps_account_funded_meanwhile() {
+    let (svm, id) = engine();
+    let (empty, funded) = (Pubkey::new_unique(), Pubkey::new_unique());
+    svm.storage
+        .set_accounts(
+            id,
+            vec![(empty, account(0, vec![])), (funded, account(0, vec![]))],
+        )
+        .unwrap();
+    let mut found = svm.storage.get_empty_accounts(100).unwrap();
+    found.sort();
+    let mut expected = vec![(id, empty), (id, funded)];
+    expected.sort();
+    assert_eq!(found, expected);
+
+    // A transaction funds it after the sweep found it, before the delete
+    svm.storage
+        .set_accounts(id, vec![(funded, account(1_000, vec![]))])
+        .unwrap();
+    let addresses: Vec<Pubkey> = found.into_iter().map(|(_, address)| address).collect();
+    assert_eq!(
+        svm.storage.delete_empty_accounts(id, &addresses).unwrap(),
+        1
+    );
+    assert_eq!(svm.storage.get_account(id, &empty).unwrap(), None);
+    assert_eq!(
+        svm.storage
+            .get_account(id, &funded)
+            .unwrap()
+            .map(|account| account.lamports),
+        Some(1_000)
+    );
+}
@@ -0,0 +1,216 @@
+use mockchain_engine::{
+    engine::{
+        sysvars::{ClockOverride, EpochScheduleOverride, SysvarOverrides},
+        SvmEngine, SVM,
+    },
+    storage::{memory::MemoryStorage, Storage},
+};
+use solana_sdk::{
+    account::from_account,
+    clock::Clock,
+    epoch_schedule::EpochSchedule,
+    pubkey::Pubkey,
+    slot_hashes::SlotHashes,
+    sysvar::{self, rent::Rent},
+};
+use uuid::Uuid;
+
+use crate::common;
+
+/// An engine with a blockchain a few blocks past genesis.
+fn engine() -> (SvmEngine<MemoryStorage>, Uuid) {
+    let (svm, id) = common::engine();
+    for _ in 0..3 {
+        svm.latest_blockhash(id).unwrap();
+        svm.produce_blocks().unwrap();
+    }
+    (svm, id)
+}
+
+#[actix_web::test]
+async fn test_clock_follows_blocks() {
+    let (svm, id) = engine();
+    let latest = svm.get_latest_block(id).unwrap();
+    assert!(latest.block_height > 0);
+
+    let account = svm
+        .get_account(id, &sysvar::clock::ID, false)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(account.owner, sysvar::id());
+    let clock: Clock = from_account(&account).unwrap();
+    assert_eq!(clock.slot, latest.block_height);
+    assert_eq!(clock.unix_timestamp, latest.block_time as i64);
+
+    // The stored copy written at genesis isn't served
+    let stored = svm
+        .storage
+        .get_account(id, &sysvar::clock::ID)
+        .unwrap()
+        .unwrap();
+    assert_eq!(from_account::<Clock, _>(&stored).unwrap().slot, 0);
+}
+
+#[actix_web::test]
+async fn test_multiple_accounts_mix_sysvars() {
+    let (svm, id) = engine();
+    let payer = svm.get_identity(id).unwrap();
+    let missing = Pubkey::new_unique();
+    let accounts = svm
+        .get_multiple_accounts(
+            id,
+            &vec![
+                &sysvar::slot_hashes::ID,
+                &payer,
+                &missing,
+                &sysvar::rent::ID,
+            ],
+            false,
+        )
+        .await
+        .unwrap();
+
+    let slot_hashes: SlotHashes = from_account(accounts[0].as_ref().unwrap()).unwrap();
+    let latest = svm.get_latest_block(id).unwrap();
+    assert_eq!(
+        slot_hashes.get(&latest.block_height),
+        Some(&latest.blockhash)
+    );
+    assert!(accounts[1].as_ref().unwrap().lamports > 0);
+    assert!(accounts[2].is_none());
+    let rent: Rent = from_account(accounts[3].as_ref().unwrap()).unwrap();
+    assert_eq!(rent, Rent::default());
+}
+
+async fn read_clock(svm: &SvmEngine<MemoryStorage>, id: Uuid) -> Clock {
+    let account = svm
+        .get_account(id, &sysvar::clock::ID, false)
+        .await
+        .unwrap()
+        .unwrap();
+    from_account(&account).unwrap()
+}
+
+#[actix_web::test]
+async fn test_overrides_are_per_blockchain() {
+    let (svm, id) = engine();
+    let other = svm
+        .create_blockchain(Uuid::new_v4(), None, None, None, None, false)
+        .unwrap();
+    assert_eq!(
+        svm.get_sysvar_overrides(id).unwrap(),
+        SysvarOverrides::default()
+    );
+
+    let overrides = SysvarOverrides {
+        clock: ClockOverride {
+            unix_timestamp: Some(1_900_000_000),
+            ..Default::default()
+        },
+        epoch_schedule: Some(EpochScheduleOverride {
+            slots_per_epoch: 32,
+            leader_schedule_slot_offset: None,
+            warmup: false,
+        }),
+    };
+    svm.set_sysvar_overrides(id, overrides.clone()).unwrap();
+    assert_eq!(svm.get_sysvar_overrides(id).unwrap(), overrides);
+
+    let latest = svm.get_latest_block(id).unwrap();
+    let clock = read_clock(&svm, id).await;
+    assert_eq!(clock.slot, latest.block_height);
+    assert_eq!(clock.unix_timestamp, 1_900_000_000);
+    assert_eq!(clock.epoch, latest.block_height / 32);
+    let account = svm
+        .get_account(id, &sysvar::epoch_schedule::ID, false)
+        .await
+        .unwrap()
+        .unwrap();
+    let schedule: EpochSchedule = from_account(&account).unwrap();
+    assert_eq!(schedule, EpochSchedule::custom(32, 32, false));
+
+    // An explicit epoch wins over the one from the schedule
+    svm.set_sysvar_overrides(
+        id,
+        SysvarOverrides {
+            clock: ClockOverride {
+                epoch: Some(7),
+                ..Default::default()
+            },
+            ..overrides
+        },
+    )
+    .unwrap();
+    assert_eq!(read_clock(&svm, id).await.epoch, 7);
+
+    // The other blockchain keeps the defaults
+    let other_latest = svm.get_latest_block(other).unwrap();
+    let other_clock = read_clock(&svm, other).await;
+    assert_eq!(other_clock.unix_timestamp, other_latest.block_time as i64);
+    assert_eq!(other_clock.epoch, 0);
+    let account = svm
+        .get_account(other, &sysvar::epoch_schedule::ID, false)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        from_account::<EpochSchedule, _>(&account).unwrap(),
+        EpochSchedule::default()
+    );
+}
+
+#[actix_web::test]
+async fn test_overrides_are_persisted() {
+    let (svm, id) = engine();
+    let overrides = SysvarOverrides {
+        clock: ClockOverride {
+            epoch: Some(3),
+            leader_schedule_epoch: Some(4),
+            epoch_start_timestamp: Some(1_700_000_000),
+            unix_timestamp: None,
+        },
+        epoch_schedule: None,
+    };
+    svm.set_sysvar_overrides(id, overrides.clone()).unwrap();
+    assert_eq!(
+        svm.storage.get_sysvar_overrides(id).unwrap(),
+        Some(overrides.clone())
+    );
+
+    // Another engine over the same storage loads them when reading the chain
+    let restarted = SvmEngine::new(svm.storage.clone());
+    let clock = read_clock(&restarted, id).await;
+    assert_eq!(clock.epoch, 3);
+    assert_eq!(clock.leader_schedule_epoch, 4);
+    assert_eq!(clock.epoch_start_timestamp, 1_700_000_000);
+
+    // Clones keep them, deleting the blockchain drops them
+    let clone = svm
+        .clone_blockchain(id, Uuid::new_v4(), None, None)
+        .unwrap();
+    assert_eq!(svm.get_sysvar_overrides(clone).unwrap(), overrides);
+    svm.delete_blockchain(id).unwrap();
+    assert_eq!(svm.storage.get_sysvar_overrides(id).unwrap(), None);
+}
+
+#[actix_web::test]
+async fn test_invalid_epoch_schedule() {
+    let (svm, id) = engine();
+    let overrides = SysvarOverrides {
+        epoch_schedule: Some(EpochScheduleOverride {
+            slots_per_epoch: 8,
+            leader_schedule_slot_offset: None,
+            warmup: false,
+        }),
+        ..Default::default()
+    };
+    assert!(svm.set_sysvar_overrides(id, overrides).is_err());
+    assert_eq!(
+        svm.get_sysvar_overrides(id).unwrap(),
+        SysvarOverrides::default()
+    );
+
+    // Unknown fields aren't silently dropped
+    assert!(serde_json::from_str::<SysvarOverrides>(r#"{"clock": {"slot": 5}}"#).is_err());
+}
@@ -0,0 +1,122 @@
+use chrono::{Duration, Utc};
+use mockchain_engine::{
+    engine::{timeline, SvmEngine, SVM},
+    storage::{
+        audit::{self, DbAuditLog},
+        memory::MemoryStorage,
+        Storage,
+    },
+};
+use serde_json::json;
+use solana_sdk::{
+    bpf_loader_upgradeable,
+    instruction::{AccountMeta, Instruction},
+    loader_upgradeable_instruction::UpgradeableLoaderInstruction,
+    pubkey::Pubkey,
+    reserved_account_keys::ReservedAccountKeys,
+    transaction::{SanitizedTransaction, Transaction},
+};
+use uuid::Uuid;
+
+fn loader_transaction(
+    instruction: &UpgradeableLoaderInstruction,
+    keys: &[Pubkey],
+) -> SanitizedTransaction {
+    let instruction = Instruction::new_with_bincode(
+        bpf_loader_upgradeable::id(),
+        instruction,
+        keys.iter()
+            .map(|key| AccountMeta::new(*key, false))
+            .collect(),
+    );
+    let tx = Transaction::new_with_payer(&[instruction], Some(&Pubkey::new_unique()));
+    SanitizedTransaction::try_from_legacy_transaction(tx, &ReservedAccountKeys::empty_key_set())
+        .unwrap()
+}
+
+#[test]
+fn test_program_deployments() {
+    let keys = (0..4).map(|_| Pubkey::new_unique()).collect::<Vec<_>>();
+
+    let deploy = timeline::program_deployments(&loader_transaction(
+        &UpgradeableLoaderInstruction::DeployWithMaxDataLen { max_data_len: 100 },
+        &keys,
+    ));
+    assert_eq!(deploy, vec![(audit::PROGRAM_DEPLOY, keys[2])]);
+
+    let upgrade = timeline::program_deployments(&loader_transaction(
+        &UpgradeableLoaderInstruction::Upgrade,
+        &keys,
+    ));
+    assert_eq!(upgrade, vec![(audit::PROGRAM_UPGRADE, keys[1])]);
+
+    // Buffer writes aren't significant on their own
+    assert!(timeline::program_deployments(&loader_transaction(
+        &UpgradeableLoaderInstruction::Write {
+            offset: 0,
+            bytes: vec![1, 2, 3],
+        },
+        &keys,
+    ))
+    .is_empty());
+}
+
+#[test]
+fn test_timeline() {
+    let svm = SvmEngine::new(MemoryStorage::new());
+    let team_id = Uuid::new_v4();
+    let expiry = (Utc::now() + Duration::hours(1)).naive_utc();
+    let id = svm
+        .create_blockchain(team_id, None, None, Some(expiry), None, false)
+        .unwrap();
+    let created_at = svm.storage.get_blockchain(id).unwrap().created_at;
+
+    let log = |action: &str, blockchain: Uuid, minutes: i64| {
+        let mut log = DbAuditLog::new(team_id, None, action, Some(blockchain), json!({}));
+        log.created_at = created_at + Duration::minutes(minutes);
+        svm.storage.create_audit_log(&log).unwrap();
+    };
+    log(audit::PROGRAM_LOAD, id, 1);
+    log(audit::AIRDROP, id, 2);
+    // Not significant, or for another blockchain
+    log(audit::WEBHOOK_CREATE, id, 3);
+    log(audit::ACCOUNT_LOAD, Uuid::new_v4(), 4);
+
+    let events = svm.timeline(id, None, 100).unwrap();
+    let kinds = events.iter().map(|e| e.kind.as_str()).collect::<Vec<_>>();
+    assert_eq!(
+        kinds,
+        vec![
+            timeline::BLOCKCHAIN_EXPIRY,
+            audit::AIRDROP,
+            audit::PROGRAM_LOAD,
+            audit::BLOCKCHAIN_CREATE,
+        ]
+    );
+    // The blockchain was created without an audit entry
+    assert_eq!(events[3].source, "blockchain");
+    assert_eq!(events[3].timestamp, created_at);
+
+    let events = svm.timeline(id, None, 2).unwrap();
+    assert_eq!(events.len(), 2);
+    assert_eq!(events[1].kind, audit::AIRDROP);
+
+    let since = created_at + Duration::seconds(90);
+    let kinds = svm
+        .timeline(id, Some(since), 100)
+        .unwrap()
+        .into_iter()
+        .map(|e| e.kind)
+        .collect::<Vec<_>>();
+    assert_eq!(kinds, vec![timeline::BLOCKCHAIN_EXPIRY, audit::AIRDROP]);
+
+    // A recorded creation replaces the one read from the blockchain
+    log(audit::BLOCKCHAIN_CREATE, id, 0);
+    let events = svm.timeline(id, None, 100).unwrap();
+    let created = events
+        .iter()
+        .filter(|e| e.kind == audit::BLOCKCHAIN_CREATE)
+        .collect::<Vec<_>>();
+    assert_eq!(created.len(), 1);
+    assert_eq!(created[0].source, "audit");
+}
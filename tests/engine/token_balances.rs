@@ -0,0 +1,115 @@
+use mockchain_engine::{
+    engine::{
+        spl::{associated_token_address, ASSOCIATED_TOKEN_PROGRAM_ID},
+        transactions::TransactionTokenBalance,
+        SVM,
+    },
+    storage::Storage,
+};
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    signature::Signature,
+    signer::Signer,
+    system_program,
+    transaction::{Transaction, VersionedTransaction},
+};
+
+use crate::common::{engine, processed};
+
+fn amounts(balances: &[TransactionTokenBalance], keys: &[Pubkey]) -> Vec<(Pubkey, String)> {
+    let mut amounts: Vec<(Pubkey, String)> = balances
+        .iter()
+        .map(|balance| {
+            assert_eq!(balance.ui_token_amount.decimals, 6);
+            (
+                keys[balance.account_index as usize],
+                balance.ui_token_amount.amount.clone(),
+            )
+        })
+        .collect();
+    amounts.sort();
+    amounts
+}
+
+#[actix_web::test]
+async fn test_token_balances_of_inner_and_read_only_accounts() {
+    let (svm, id) = engine();
+    let payer = svm.storage.get_blockchain(id).unwrap().airdrop_keypair;
+    let (mint, source) = svm
+        .mint_tokens(id, None, 6, &payer.pubkey(), 1_000)
+        .await
+        .unwrap();
+    let (_, bystander) = svm
+        .mint_tokens(id, Some(mint), 6, &Pubkey::new_unique(), 50)
+        .await
+        .unwrap();
+
+    // Like a router, the associated token program initializes the destination
+    // through the token program, so its balance only exists after a CPI
+    let recipient = Pubkey::new_unique();
+    let destination = associated_token_address(&recipient, &mint, &spl_token::id());
+    let create = Instruction {
+        program_id: ASSOCIATED_TOKEN_PROGRAM_ID,
+        accounts: vec![
+            AccountMeta::new(payer.pubkey(), true),
+            AccountMeta::new(destination, false),
+            AccountMeta::new_readonly(recipient, false),
+            AccountMeta::new_readonly(mint, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ],
+        data: vec![1],
+    };
+    let mut transfer = spl_token::instruction::transfer(
+        &spl_token::id(),
+        &source,
+        &destination,
+        &payer.pubkey(),
+        &[],
+        400,
+    )
+    .unwrap();
+    // Passed along read-only and left untouched, as routes often do
+    transfer
+        .accounts
+        .push(AccountMeta::new_readonly(bystander, false));
+
+    let blockhash = svm.latest_blockhash(id).unwrap().blockhash;
+    let tx = Transaction::new_signed_with_payer(
+        &[create, transfer],
+        Some(&payer.pubkey()),
+        &[&payer],
+        blockhash,
+    );
+    let keys = tx.message.account_keys.clone();
+    let signature = svm
+        .send_transaction(id, VersionedTransaction::from(tx), false)
+        .unwrap();
+    assert!(processed(&svm, id, &signature).await);
+    let signature: Signature = signature.parse().unwrap();
+    let (_, _, meta, err, _) = svm
+        .storage
+        .get_transaction(id, &signature)
+        .unwrap()
+        .unwrap();
+    assert_eq!(err, None, "{:?}", meta.log_messages);
+
+    assert_eq!(
+        amounts(&meta.pre_token_balances.unwrap(), &keys),
+        amounts_of(&[(source, "1000"), (bystander, "50")])
+    );
+    assert_eq!(
+        amounts(&meta.post_token_balances.unwrap(), &keys),
+        amounts_of(&[(source, "600"), (destination, "400"), (bystander, "50")])
+    );
+}
+
+fn amounts_of(amounts: &[(Pubkey, &str)]) -> Vec<(Pubkey, String)> {
+    let mut amounts: Vec<(Pubkey, String)> = amounts
+        .iter()
+        .map(|(pubkey, amount)| (*pubkey, amount.to_string()))
+        .collect();
+    amounts.sort();
+    amounts
+}
@@ -0,0 +1,62 @@
+use mockchain_engine::{
+    engine::SVM,
+    storage::{top_ups::DbTopUp, Storage},
+};
+use solana_sdk::{native_token::LAMPORTS_PER_SOL, program_pack::Pack, pubkey::Pubkey};
+use spl_token::state::Account as TokenAccount;
+use uuid::Uuid;
+
+use crate::common::engine;
+
+fn top_up(id: Uuid, address: &Pubkey, mint: Option<Pubkey>, target: u64) -> DbTopUp {
+    DbTopUp {
+        id: Uuid::new_v4(),
+        created_at: chrono::Utc::now().naive_utc(),
+        blockchain: id,
+        address: address.to_string(),
+        mint: mint.map(|mint| mint.to_string()),
+        target: target.into(),
+        interval_seconds: 60,
+        last_run: None,
+    }
+}
+
+#[actix_web::test]
+async fn test_top_ups() {
+    let (svm, id) = engine();
+    let bot = Pubkey::new_unique();
+    let (mint, token_address) = svm.mint_tokens(id, None, 6, &bot, 10).await.unwrap();
+
+    svm.storage
+        .create_top_up(&top_up(id, &bot, None, 5 * LAMPORTS_PER_SOL))
+        .unwrap();
+    svm.storage
+        .create_top_up(&top_up(id, &bot, Some(mint), 100))
+        .unwrap();
+    assert_eq!(svm.run_top_ups().await.unwrap(), 2);
+
+    let balance = svm.get_account(id, &bot, false).await.unwrap().unwrap();
+    assert_eq!(balance.lamports, 5 * LAMPORTS_PER_SOL);
+    let token_account = svm
+        .get_account(id, &token_address, false)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        TokenAccount::unpack(&token_account.data).unwrap().amount,
+        100
+    );
+
+    // Not due again until the interval passes
+    svm.airdrop(id, &bot, 1).await.unwrap();
+    assert_eq!(svm.run_top_ups().await.unwrap(), 0);
+
+    // Another instance claimed the run first
+    let stale = svm.storage.get_top_ups(id).unwrap().remove(0);
+    assert!(!svm
+        .storage
+        .claim_top_up(stale.id, None, chrono::Utc::now().naive_utc())
+        .unwrap());
+    // Already at the target, so a run adds nothing
+    assert_eq!(svm.top_up(&stale).await.unwrap(), 0);
+}
@@ -0,0 +1,81 @@
+use mockchain_engine::{
+    engine::{
+        write_locks::{WriteLocks, MAX_WRITE_LOCKED_ACCOUNTS},
+        SVM,
+    },
+    storage::Storage,
+};
+use solana_sdk::{native_token::LAMPORTS_PER_SOL, pubkey::Pubkey, signer::Signer};
+use uuid::Uuid;
+
+use crate::common::{engine, processed, transfer_to};
+
+#[test]
+fn test_write_lock_report() {
+    let write_locks = WriteLocks::default();
+    let id = Uuid::new_v4();
+    let (hot, cold) = (Pubkey::new_unique(), Pubkey::new_unique());
+    write_locks.record(id, 1, [hot, cold]);
+    write_locks.record(id, 1, [hot]);
+    write_locks.record(id, 2, [hot]);
+    write_locks.record(Uuid::new_v4(), 1, [cold]);
+
+    let report = write_locks.report(id, 10);
+    assert_eq!(report.len(), 2);
+    assert_eq!(report[0].0, hot);
+    assert_eq!(report[0].1.write_locks, 3);
+    // Only the second lock of slot 1 contended
+    assert_eq!(report[0].1.conflicts, 1);
+    assert_eq!(report[1].0, cold);
+    assert_eq!(report[1].1.write_locks, 1);
+    assert_eq!(report[1].1.conflicts, 0);
+    assert_eq!(write_locks.report(id, 1).len(), 1);
+
+    write_locks.record(
+        id,
+        3,
+        (0..MAX_WRITE_LOCKED_ACCOUNTS).map(|_| Pubkey::new_unique()),
+    );
+    assert_eq!(
+        write_locks.report(id, usize::MAX).len(),
+        MAX_WRITE_LOCKED_ACCOUNTS
+    );
+    // Tracked accounts still count once full
+    write_locks.record(id, 3, [hot]);
+    assert_eq!(write_locks.report(id, 1)[0].1.write_locks, 4);
+
+    write_locks.forget(id);
+    assert!(write_locks.report(id, 10).is_empty());
+}
+
+#[actix_web::test]
+async fn test_executed_write_locks() {
+    let (svm, id) = engine();
+    let payer = svm.storage.get_blockchain(id).unwrap().airdrop_keypair;
+    let blockhash = svm.latest_blockhash(id).unwrap().blockhash;
+    let mut signatures = vec![];
+    for _ in 0..2 {
+        let tx = transfer_to(
+            &svm,
+            id,
+            &Pubkey::new_unique(),
+            LAMPORTS_PER_SOL,
+            Some(blockhash),
+        );
+        signatures.push(svm.send_transaction(id, tx, false).unwrap());
+    }
+    for signature in &signatures {
+        assert!(processed(&svm, id, signature).await);
+    }
+
+    let report = svm.write_lock_report(id, 10);
+    // The payer, then each recipient once
+    assert_eq!(report.len(), 3);
+    assert_eq!(report[0].0, payer.pubkey());
+    assert_eq!(report[0].1.write_locks, 2);
+    assert_eq!(report[0].1.conflicts, 1);
+    assert!(report[1..].iter().all(|(_, stats)| stats.write_locks == 1));
+
+    svm.delete_blockchain(id).unwrap();
+    assert!(svm.write_lock_report(id, 10).is_empty());
+}
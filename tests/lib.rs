@@ -1,3 +1,18 @@
+pub mod anchor;
+pub mod auth;
 pub mod cache;
+pub mod client;
+pub mod common;
+pub mod compat;
+pub mod embedded;
+pub mod endpoints;
+pub mod engine;
+pub mod metrics;
+pub mod openapi;
+pub mod reporting;
 pub mod rpc;
+pub mod settings;
+pub mod solana_cli;
 pub mod storage;
+pub mod warm_pool;
+pub mod webhooks;
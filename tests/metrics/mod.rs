@@ -0,0 +1,126 @@
+use std::time::Duration;
+
+use mockchain_engine::metrics::{
+    forget_blockchain, jit_stats, record_jit, record_jit_error, record_rpc, record_stage, render,
+    rpc_stats, stage_stats, JitFetch, JIT_RECENT_FETCHES,
+};
+use uuid::Uuid;
+
+#[test]
+fn test_record_rpc() {
+    let blockchain = Uuid::new_v4();
+    record_rpc(
+        blockchain,
+        "http",
+        "GetBalance",
+        Duration::from_millis(3),
+        false,
+        10,
+        100,
+    );
+    record_rpc(
+        blockchain,
+        "http",
+        "GetBalance",
+        Duration::from_secs(20),
+        true,
+        10,
+        50,
+    );
+
+    let stats = rpc_stats(blockchain, "http", "GetBalance").unwrap();
+    assert_eq!(stats.latency.count(), 2);
+    assert_eq!(stats.errors, 1);
+    assert_eq!(stats.request_bytes, 20);
+    assert_eq!(stats.response_bytes, 150);
+
+    let labels = format!(
+        "blockchain=\"{}\",transport=\"http\",method=\"GetBalance\"",
+        blockchain
+    );
+    let rendered = render();
+    assert!(rendered.contains(&format!(
+        "rpc_request_duration_seconds_bucket{{{},le=\"0.005\"}} 1",
+        labels
+    )));
+    assert!(rendered.contains(&format!(
+        "rpc_request_duration_seconds_bucket{{{},le=\"+Inf\"}} 2",
+        labels
+    )));
+    assert!(rendered.contains(&format!("rpc_errors_total{{{}}} 1", labels)));
+
+    forget_blockchain(blockchain);
+    assert!(rpc_stats(blockchain, "http", "GetBalance").is_none());
+}
+
+#[test]
+fn test_record_stage() {
+    let blockchain = Uuid::new_v4();
+    record_stage(blockchain, "queued", Duration::from_millis(40));
+    record_stage(blockchain, "execute", Duration::from_millis(10));
+    record_stage(blockchain, "execute", Duration::from_millis(30));
+
+    let stages = stage_stats(blockchain);
+    assert_eq!(
+        stages
+            .iter()
+            .map(|(stage, histogram)| (*stage, histogram.count()))
+            .collect::<Vec<_>>(),
+        vec![("execute", 2), ("queued", 1)]
+    );
+    assert_eq!(stages[0].1.sum(), Duration::from_millis(40));
+    assert!(render().contains(&format!(
+        "pipeline_stage_duration_seconds_count{{blockchain=\"{}\",stage=\"execute\"}} 2",
+        blockchain
+    )));
+
+    forget_blockchain(blockchain);
+    assert!(stage_stats(blockchain).is_empty());
+}
+
+#[test]
+fn test_record_jit() {
+    let blockchain = Uuid::new_v4();
+    let fetch = |index: usize, found: bool| JitFetch {
+        pubkey: index.to_string(),
+        found,
+        owner: None,
+        bytes: if found { 10 } else { 0 },
+        latency_ms: 5,
+        fetched_at: chrono::Utc::now().naive_utc(),
+    };
+    record_jit(blockchain, 3, vec![], Duration::ZERO);
+    record_jit(
+        blockchain,
+        1,
+        vec![fetch(0, true), fetch(1, false)],
+        Duration::from_millis(5),
+    );
+    record_jit_error(blockchain, Duration::from_secs(1));
+
+    let stats = jit_stats(blockchain).unwrap();
+    assert_eq!((stats.hits, stats.misses, stats.found), (4, 2, 1));
+    assert_eq!((stats.bytes, stats.errors), (10, 1));
+    // Reads without upstream fetches aren't timed
+    assert_eq!(stats.upstream_latency.count(), 2);
+    let labels = format!("blockchain=\"{}\"", blockchain);
+    let rendered = render();
+    assert!(rendered.contains(&format!("jit_account_hits_total{{{}}} 4", labels)));
+    assert!(rendered.contains(&format!("jit_stored_bytes_total{{{}}} 10", labels)));
+    assert!(rendered.contains(&format!(
+        "jit_upstream_duration_seconds_count{{{}}} 2",
+        labels
+    )));
+
+    // Only the newest fetches are kept
+    let fetches = (0..JIT_RECENT_FETCHES + 5)
+        .map(|index| fetch(index, false))
+        .collect();
+    record_jit(blockchain, 0, fetches, Duration::from_millis(1));
+    let recent = jit_stats(blockchain).unwrap().recent;
+    assert_eq!(recent.len(), JIT_RECENT_FETCHES);
+    assert_eq!(recent.front().unwrap().pubkey, "5");
+
+    forget_blockchain(blockchain);
+    assert!(jit_stats(blockchain).is_none());
+}
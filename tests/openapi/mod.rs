@@ -0,0 +1,107 @@
+use mockchain_engine::openapi::{spec, OPERATIONS};
+use serde_json::Value;
+use std::collections::{BTreeSet, HashMap};
+
+const ENDPOINTS: &str = include_str!("../../src/endpoints.rs");
+const MAIN: &str = include_str!("../../src/main.rs");
+const METHODS: [&str; 5] = ["get", "post", "put", "patch", "delete"];
+
+/// (method, path) of every route main.rs serves, read from the handlers' attributes.
+fn served_routes() -> BTreeSet<(String, String)> {
+    let mut handlers = HashMap::new();
+    let mut route = None;
+    for line in ENDPOINTS.lines() {
+        let line = line.trim();
+        for method in METHODS {
+            if let Some(rest) = line.strip_prefix(&format!("#[{}(\"", method)) {
+                let path = rest.split('"').next().unwrap();
+                route = Some((method.to_string(), path.to_string()));
+            }
+        }
+        if let Some(rest) = line.strip_prefix("pub async fn ") {
+            let name = rest.split(['(', '<']).next().unwrap();
+            if let Some(route) = route.take() {
+                handlers.insert(name.to_string(), route);
+            }
+        }
+    }
+
+    let mut routes = BTreeSet::new();
+    let mut resource = None;
+    for line in MAIN.lines() {
+        let line = line.trim();
+        if let Some(rest) = line.strip_prefix("web::resource(\"") {
+            resource = Some(rest.split('"').next().unwrap().to_string());
+        }
+        if let (Some(path), Some(rest)) = (&resource, line.strip_prefix(".route(web::")) {
+            let method = rest.split('(').next().unwrap();
+            routes.insert((method.to_string(), path.clone()));
+        }
+        // `.service(` alone opens a resource, read above
+        if let Some(name) = line
+            .strip_prefix(".service(")
+            .filter(|rest| !rest.is_empty())
+        {
+            let name = name.trim_end_matches(')');
+            let route = handlers
+                .get(name)
+                .unwrap_or_else(|| panic!("No route attribute for {}", name));
+            routes.insert(route.clone());
+        }
+    }
+    routes
+}
+
+#[test]
+fn test_spec_covers_served_routes() {
+    let documented: BTreeSet<(String, String)> = OPERATIONS
+        .iter()
+        .map(|op| (op.method.to_string(), op.path.to_string()))
+        .collect();
+    assert_eq!(documented.len(), OPERATIONS.len(), "Duplicate operations");
+    assert_eq!(documented, served_routes());
+}
+
+fn refs(value: &Value, found: &mut Vec<String>) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map {
+                match (key.as_str(), value) {
+                    ("$ref", Value::String(reference)) => found.push(reference.clone()),
+                    _ => refs(value, found),
+                }
+            }
+        }
+        Value::Array(values) => values.iter().for_each(|value| refs(value, found)),
+        _ => {}
+    }
+}
+
+#[test]
+fn test_spec_is_consistent() {
+    let spec = spec();
+    assert_eq!(spec["openapi"], "3.0.3");
+
+    let mut found = vec![];
+    refs(&spec, &mut found);
+    assert!(!found.is_empty());
+    for reference in found {
+        let name = reference
+            .strip_prefix("#/components/schemas/")
+            .unwrap_or_else(|| panic!("Unexpected reference {}", reference));
+        assert!(
+            spec["components"]["schemas"][name].is_object(),
+            "Missing schema {}",
+            name
+        );
+    }
+
+    let webhook = &spec["paths"]["/webhooks/{id}/{webhook_id}"]["delete"];
+    let params: Vec<&str> = webhook["parameters"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|param| param["name"].as_str().unwrap())
+        .collect();
+    assert_eq!(params, vec!["id", "webhook_id"]);
+}
@@ -0,0 +1,36 @@
+use std::str::FromStr;
+
+use mockchain_engine::reporting::{current_context, event, scope, Dsn};
+
+#[test]
+fn test_parse_dsn() {
+    let dsn = Dsn::from_str("https://abc123@o42.ingest.sentry.io/1234").unwrap();
+    assert_eq!(
+        dsn.store_url(),
+        "https://o42.ingest.sentry.io/api/1234/store/"
+    );
+    assert!(dsn.auth_header().ends_with("sentry_key=abc123"));
+
+    let dsn = Dsn::from_str("http://key@localhost:9000/sentry/7").unwrap();
+    assert_eq!(dsn.store_url(), "http://localhost:9000/sentry/api/7/store/");
+
+    assert!(Dsn::from_str("https://o42.ingest.sentry.io/1234").is_err());
+    assert!(Dsn::from_str("https://key@o42.ingest.sentry.io/").is_err());
+    assert!(Dsn::from_str("not a dsn").is_err());
+}
+
+#[actix_web::test]
+async fn test_scope_context() {
+    assert!(current_context().is_empty());
+    let context = scope(vec![("blockchain", "abc".to_string())], async {
+        current_context()
+    })
+    .await;
+    assert_eq!(context, vec![("blockchain", "abc".to_string())]);
+
+    let event = event("error", "boom", "test", &context, None);
+    assert_eq!(event["level"], "error");
+    assert_eq!(event["message"]["formatted"], "boom");
+    assert_eq!(event["tags"]["blockchain"], "abc");
+    assert_eq!(event["environment"], "test");
+}
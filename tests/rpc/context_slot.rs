@@ -0,0 +1,51 @@
+use mockchain_engine::{
+    engine::{SvmEngine, SVM},
+    rpc::rpc::{handle_request, RpcMethod},
+    storage::memory::MemoryStorage,
+};
+use serde_json::{json, Value};
+use solana_sdk::pubkey::Pubkey;
+use uuid::Uuid;
+
+use super::request;
+use crate::common::engine;
+
+async fn context_slots(id: Uuid, svm: &SvmEngine<MemoryStorage>) -> Vec<Value> {
+    let mut slots = vec![];
+    for (method, params) in [
+        (
+            RpcMethod::GetMultipleAccounts,
+            json!([[Pubkey::new_unique().to_string()]]),
+        ),
+        (RpcMethod::GetBlockProduction, json!([])),
+        (RpcMethod::GetStakeMinimumDelegation, json!([])),
+        (RpcMethod::GetSupply, json!([])),
+        (RpcMethod::GetLargestAccounts, json!([])),
+    ] {
+        let res = handle_request(id, request(method, params), svm).await;
+        assert!(res.error.is_none(), "{:?}: {:?}", method, res.error);
+        let result = res.result.unwrap();
+        assert_eq!(result["context"]["apiVersion"], "2.1.13");
+        slots.push(result["context"]["slot"].clone());
+    }
+    slots
+}
+
+#[actix_web::test]
+async fn test_context_slot_is_current_slot() {
+    let (svm, id) = engine();
+
+    let slot = svm.get_latest_block(id).unwrap().block_height;
+    for context_slot in context_slots(id, &svm).await {
+        assert_eq!(context_slot, json!(slot));
+    }
+
+    // Contexts follow the chain as it produces blocks
+    svm.latest_blockhash(id).unwrap();
+    svm.produce_blocks().unwrap();
+    let next_slot = svm.get_latest_block(id).unwrap().block_height;
+    assert!(next_slot > slot);
+    for context_slot in context_slots(id, &svm).await {
+        assert_eq!(context_slot, json!(next_slot));
+    }
+}
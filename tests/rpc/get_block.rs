@@ -0,0 +1,24 @@
+use mockchain_engine::rpc::rpc::{handle_request, RpcMethod};
+use serde_json::json;
+
+use super::{get_transaction::transfer, request};
+use crate::common::engine;
+
+#[actix_web::test]
+async fn test_max_supported_transaction_version() {
+    let (svm, id) = engine();
+    transfer(&svm, id, true).await;
+
+    let res = handle_request(id, request(RpcMethod::GetBlock, json!([0])), &svm).await;
+    assert_eq!(res.error.unwrap()["code"], -32015);
+    let res = handle_request(
+        id,
+        request(
+            RpcMethod::GetBlock,
+            json!([0, {"maxSupportedTransactionVersion": 0}]),
+        ),
+        &svm,
+    )
+    .await;
+    assert!(res.error.is_none(), "{:?}", res.error);
+}
@@ -0,0 +1,174 @@
+use mockchain_engine::{
+    engine::{SvmEngine, SVM},
+    rpc::{
+        get_program_accounts::cache_key,
+        rpc::{handle_request, RpcMethod},
+    },
+    storage::{memory::MemoryStorage, Storage},
+};
+use serde_json::{json, Value};
+use solana_sdk::{account::Account, pubkey::Pubkey};
+use uuid::Uuid;
+
+use super::request;
+use crate::common::engine;
+
+fn account(owner: &Pubkey, data_len: usize) -> Account {
+    Account::new(1_000_000, data_len, owner)
+}
+
+async fn program_accounts(
+    svm: &SvmEngine<MemoryStorage>,
+    id: Uuid,
+    program: &Pubkey,
+    config: &Value,
+) -> Vec<String> {
+    let res = handle_request(
+        id,
+        request(
+            RpcMethod::GetProgramAccounts,
+            json!([program.to_string(), config]),
+        ),
+        svm,
+    )
+    .await;
+    assert!(res.error.is_none(), "{:?}", res.error);
+    let mut addresses: Vec<String> = res
+        .result
+        .unwrap()
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|account| account["pubkey"].as_str().unwrap().to_string())
+        .collect();
+    addresses.sort();
+    addresses
+}
+
+fn sorted(addresses: &[Pubkey]) -> Vec<String> {
+    let mut addresses: Vec<String> = addresses.iter().map(|a| a.to_string()).collect();
+    addresses.sort();
+    addresses
+}
+
+#[actix_web::test]
+async fn test_program_accounts_cache() {
+    let (svm, id) = engine();
+    let (program, other_program) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let (config_a, config_b, other) = (
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+    );
+    svm.storage
+        .set_accounts(
+            id,
+            vec![
+                (config_a, account(&program, 8)),
+                (config_b, account(&program, 16)),
+                (other, account(&other_program, 8)),
+            ],
+        )
+        .unwrap();
+
+    let everything = json!({"encoding": "base64"});
+    let small = json!({"encoding": "base64", "filters": [{"dataSize": 8}]});
+    assert_eq!(
+        program_accounts(&svm, id, &program, &everything).await,
+        sorted(&[config_a, config_b])
+    );
+    assert_eq!(
+        program_accounts(&svm, id, &program, &small).await,
+        sorted(&[config_a])
+    );
+    let cached = |config: &Value| {
+        svm.storage
+            .get_cached_program_accounts(id, &program, &cache_key(Some(config)))
+            .unwrap()
+            .0
+    };
+    assert!(cached(&everything).is_some());
+    assert!(cached(&small).is_some());
+
+    // Writes to other programs' accounts keep the results
+    svm.storage
+        .set_account(id, &other, account(&other_program, 32), None)
+        .unwrap();
+    assert!(cached(&everything).is_some());
+
+    // A write to one of the program's accounts drops every result of the program
+    svm.storage
+        .set_account(id, &config_b, account(&program, 8), None)
+        .unwrap();
+    assert!(cached(&everything).is_none());
+    assert!(cached(&small).is_none());
+    assert_eq!(
+        program_accounts(&svm, id, &program, &small).await,
+        sorted(&[config_a, config_b])
+    );
+
+    // So does an account created for the program, or leaving it
+    let config_c = Pubkey::new_unique();
+    svm.storage
+        .set_accounts(id, vec![(config_c, account(&program, 8))])
+        .unwrap();
+    assert_eq!(
+        program_accounts(&svm, id, &program, &small).await,
+        sorted(&[config_a, config_b, config_c])
+    );
+    svm.storage
+        .set_accounts(id, vec![(config_c, account(&other_program, 8))])
+        .unwrap();
+    assert_eq!(
+        program_accounts(&svm, id, &program, &small).await,
+        sorted(&[config_a, config_b])
+    );
+
+    // Lamports changes and deletions too
+    program_accounts(&svm, id, &program, &everything).await;
+    svm.storage.set_account_lamports(id, &config_a, 42).unwrap();
+    assert!(cached(&everything).is_none());
+    program_accounts(&svm, id, &program, &everything).await;
+    svm.storage.delete_account(id, &config_a).unwrap();
+    assert_eq!(
+        program_accounts(&svm, id, &program, &everything).await,
+        sorted(&[config_b])
+    );
+
+    svm.delete_blockchain(id).unwrap();
+    assert!(cached(&everything).is_none());
+}
+
+#[actix_web::test]
+async fn test_stale_program_accounts_not_cached() {
+    let (svm, id) = engine();
+    let program = Pubkey::new_unique();
+    let key = cache_key(None);
+
+    // A result read before a write to one of the program's accounts is stale
+    let (_, generation) = svm
+        .storage
+        .get_cached_program_accounts(id, &program, &key)
+        .unwrap();
+    svm.storage
+        .set_account(id, &Pubkey::new_unique(), account(&program, 8), None)
+        .unwrap();
+    svm.storage
+        .cache_program_accounts(id, &program, &key, "[]", generation)
+        .unwrap();
+    let (cached, current) = svm
+        .storage
+        .get_cached_program_accounts(id, &program, &key)
+        .unwrap();
+    assert!(cached.is_none());
+    assert!(current > generation);
+
+    svm.storage
+        .cache_program_accounts(id, &program, &key, "[]", current)
+        .unwrap();
+    let (cached, _) = svm
+        .storage
+        .get_cached_program_accounts(id, &program, &key)
+        .unwrap();
+    assert_eq!(cached.as_deref(), Some("[]"));
+}
@@ -0,0 +1,94 @@
+use mockchain_engine::{
+    engine::SVM,
+    rpc::rpc::{handle_request, RpcMethod},
+    storage::Storage,
+};
+use serde_json::json;
+use solana_sdk::{
+    account::Account,
+    address_lookup_table::{
+        self,
+        state::{AddressLookupTable, LookupTableMeta},
+        AddressLookupTableAccount,
+    },
+    message::{v0, VersionedMessage},
+    pubkey::Pubkey,
+    signature::Signature,
+    signer::Signer,
+    system_instruction,
+    transaction::VersionedTransaction,
+};
+use std::borrow::Cow;
+
+use super::request;
+use crate::common::{engine, processed};
+
+#[actix_web::test]
+async fn test_lookup_table_addresses() {
+    let (svm, id) = engine();
+    let payer = svm.storage.get_blockchain(id).unwrap().airdrop_keypair;
+    let recipient = Pubkey::new_unique();
+    let table_address = Pubkey::new_unique();
+    let table = AddressLookupTable {
+        // Extended before the current slot, so the address is usable right away
+        meta: LookupTableMeta {
+            last_extended_slot_start_index: 1,
+            ..LookupTableMeta::default()
+        },
+        addresses: Cow::Owned(vec![recipient]),
+    };
+    let data = table.serialize_for_tests().unwrap();
+    let table_account = Account {
+        lamports: svm.minimum_balance_for_rent_exemption(data.len()),
+        data,
+        owner: address_lookup_table::program::id(),
+        executable: false,
+        rent_epoch: 0,
+    };
+    svm.storage
+        .set_account(id, &table_address, table_account, None)
+        .unwrap();
+
+    let blockhash = svm.latest_blockhash(id).unwrap().blockhash;
+    let message = v0::Message::try_compile(
+        &payer.pubkey(),
+        &[system_instruction::transfer(
+            &payer.pubkey(),
+            &recipient,
+            1_000_000,
+        )],
+        &[AddressLookupTableAccount {
+            key: table_address,
+            addresses: vec![recipient],
+        }],
+        blockhash,
+    )
+    .unwrap();
+    assert!(!message.account_keys.contains(&recipient));
+    let tx = VersionedTransaction::try_new(VersionedMessage::V0(message), &[&payer]).unwrap();
+    let signature = svm.send_transaction(id, tx, false).unwrap();
+    assert!(processed(&svm, id, &signature).await);
+    let signature: Signature = signature.parse().unwrap();
+
+    let (_, _, meta, err, _) = svm
+        .storage
+        .get_transaction(id, &signature)
+        .unwrap()
+        .unwrap();
+    assert_eq!(err, None);
+    assert_eq!(meta.loaded_addresses.writable, vec![recipient]);
+    assert!(meta.loaded_addresses.readonly.is_empty());
+
+    let req = request(
+        RpcMethod::GetSignaturesForAddress,
+        json!([recipient.to_string()]),
+    );
+    let res = handle_request(id, req, &svm).await.result.unwrap();
+    let signatures: Vec<&str> = res
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|entry| entry["signature"].as_str().unwrap())
+        .collect();
+    assert_eq!(signatures, vec![signature.to_string()]);
+}
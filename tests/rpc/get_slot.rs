@@ -0,0 +1,53 @@
+use mockchain_engine::{
+    engine::{SvmEngine, FINALIZATION_DEPTH, SVM},
+    rpc::rpc::{handle_request, RpcMethod},
+    storage::memory::MemoryStorage,
+};
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use super::request;
+use crate::common::engine;
+
+async fn slot(id: Uuid, svm: &SvmEngine<MemoryStorage>, params: Value) -> u64 {
+    let res = handle_request(id, request(RpcMethod::GetSlot, params), svm).await;
+    assert!(res.error.is_none(), "{:?}", res.error);
+    res.result.unwrap().as_u64().unwrap()
+}
+
+#[actix_web::test]
+async fn test_get_slot_by_commitment() {
+    let (svm, id) = engine();
+
+    // A young chain has nothing finalized past genesis
+    let processed = slot(id, &svm, json!([{"commitment": "processed"}])).await;
+    assert!(processed < FINALIZATION_DEPTH);
+    assert_eq!(slot(id, &svm, json!([])).await, 0);
+
+    for _ in 0..FINALIZATION_DEPTH + 8 {
+        svm.latest_blockhash(id).unwrap();
+        svm.produce_blocks().unwrap();
+    }
+    let latest = svm.get_latest_block(id).unwrap().block_height;
+    assert!(latest >= FINALIZATION_DEPTH + 8);
+    assert_eq!(
+        slot(id, &svm, json!([{"commitment": "processed"}])).await,
+        latest
+    );
+    assert_eq!(
+        slot(id, &svm, json!([{"commitment": "confirmed"}])).await,
+        latest - 1
+    );
+    let finalized = slot(id, &svm, json!([{"commitment": "finalized"}])).await;
+    assert_eq!(finalized, latest - FINALIZATION_DEPTH);
+    // Finalized is the default
+    assert_eq!(slot(id, &svm, json!([])).await, finalized);
+
+    let res = handle_request(
+        id,
+        request(RpcMethod::GetSlot, json!([{"commitment": "max"}])),
+        &svm,
+    )
+    .await;
+    assert_eq!(res.error.unwrap()["code"], -32602);
+}
@@ -0,0 +1,124 @@
+use base64::prelude::*;
+use mockchain_engine::{
+    engine::{SvmEngine, SVM},
+    rpc::rpc::{handle_request, RpcMethod},
+    storage::{memory::MemoryStorage, Storage},
+};
+use serde_json::{json, Value};
+use solana_sdk::{
+    message::{v0, VersionedMessage},
+    native_token::LAMPORTS_PER_SOL,
+    pubkey::Pubkey,
+    signature::Signature,
+    signer::Signer,
+    system_instruction,
+    transaction::VersionedTransaction,
+};
+use uuid::Uuid;
+
+use super::request;
+use crate::common::{engine, processed};
+
+/// Sends a transfer, as a v0 transaction or a legacy one, and waits for it to be saved.
+pub async fn transfer(svm: &SvmEngine<MemoryStorage>, id: Uuid, v0: bool) -> Signature {
+    let tx = match v0 {
+        true => {
+            let payer = svm.storage.get_blockchain(id).unwrap().airdrop_keypair;
+            let blockhash = svm.latest_blockhash(id).unwrap().blockhash;
+            let ix = system_instruction::transfer(
+                &payer.pubkey(),
+                &Pubkey::new_unique(),
+                LAMPORTS_PER_SOL,
+            );
+            let message = v0::Message::try_compile(&payer.pubkey(), &[ix], &[], blockhash).unwrap();
+            VersionedTransaction::try_new(VersionedMessage::V0(message), &[&payer]).unwrap()
+        }
+        false => crate::common::transfer(svm, id),
+    };
+    let signature = svm.send_transaction(id, tx, false).unwrap();
+    assert!(
+        processed(svm, id, &signature).await,
+        "Transaction {} wasn't processed",
+        signature
+    );
+    signature.parse().unwrap()
+}
+
+async fn get_transaction(
+    svm: &SvmEngine<MemoryStorage>,
+    id: Uuid,
+    params: Value,
+) -> Result<Value, Value> {
+    let res = handle_request(id, request(RpcMethod::GetTransaction, params), svm).await;
+    match res.error {
+        Some(error) => Err(error),
+        None => Ok(res.result.unwrap()),
+    }
+}
+
+#[actix_web::test]
+async fn test_block_time() {
+    let (svm, id) = engine();
+    svm.latest_blockhash(id).unwrap();
+    svm.produce_blocks().unwrap();
+    let signature = transfer(&svm, id, false).await;
+    // Later blocks don't move the transaction
+    svm.produce_blocks().unwrap();
+
+    let result = get_transaction(
+        &svm,
+        id,
+        json!([signature.to_string(), {"encoding": "json"}]),
+    )
+    .await
+    .unwrap();
+    let slot = result["slot"].as_u64().unwrap();
+    let block = svm.get_block(id, &slot).unwrap().unwrap();
+    assert_eq!(slot, 1);
+    assert_eq!(result["blockTime"], json!(block.block_time));
+    assert!(block.block_time > 0);
+}
+
+#[actix_web::test]
+async fn test_max_supported_transaction_version() {
+    let (svm, id) = engine();
+    let legacy = transfer(&svm, id, false).await.to_string();
+    let v0 = transfer(&svm, id, true).await.to_string();
+
+    // Legacy clients read legacy transactions, without a version
+    let result = get_transaction(&svm, id, json!([legacy, {"encoding": "json"}]))
+        .await
+        .unwrap();
+    assert!(result.get("version").is_none());
+    let error = get_transaction(&svm, id, json!([v0, {"encoding": "json"}]))
+        .await
+        .unwrap_err();
+    assert_eq!(error["code"], -32015);
+
+    let config = json!({"encoding": "json", "maxSupportedTransactionVersion": 0});
+    let result = get_transaction(&svm, id, json!([legacy, config]))
+        .await
+        .unwrap();
+    assert_eq!(result["version"], "legacy");
+    let result = get_transaction(&svm, id, json!([v0, config]))
+        .await
+        .unwrap();
+    assert_eq!(result["version"], 0);
+}
+
+#[actix_web::test]
+async fn test_original_message() {
+    let (svm, id) = engine();
+    for v0 in [false, true] {
+        let signature = transfer(&svm, id, v0).await;
+        let config = json!({"encoding": "base64", "maxSupportedTransactionVersion": 0});
+        let result = get_transaction(&svm, id, json!([signature.to_string(), config]))
+            .await
+            .unwrap();
+        let encoded = result["transaction"][0].as_str().unwrap();
+        let tx: VersionedTransaction =
+            bincode::deserialize(&BASE64_STANDARD.decode(encoded).unwrap()).unwrap();
+        assert_eq!(tx.signatures[0], signature);
+        assert!(tx.verify_with_results().into_iter().all(|ok| ok));
+    }
+}
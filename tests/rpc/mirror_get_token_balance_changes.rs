@@ -0,0 +1,120 @@
+use mockchain_engine::{
+    engine::SVM,
+    rpc::rpc::{handle_request, RpcMethod},
+    storage::Storage,
+};
+use serde_json::json;
+use solana_sdk::{
+    pubkey::Pubkey, signer::Signer, transaction::Transaction, transaction::VersionedTransaction,
+};
+
+use super::request;
+use crate::common::{engine, processed};
+
+#[actix_web::test]
+async fn test_token_balance_changes() {
+    let (svm, id) = engine();
+    let payer = svm.storage.get_blockchain(id).unwrap().airdrop_keypair;
+    let recipient = Pubkey::new_unique();
+    let (mint, source) = svm
+        .mint_tokens(id, None, 6, &payer.pubkey(), 1_000)
+        .await
+        .unwrap();
+    let (_, destination) = svm
+        .mint_tokens(id, Some(mint), 6, &recipient, 0)
+        .await
+        .unwrap();
+
+    let mut signatures = vec![];
+    for amount in [300, 200] {
+        let blockhash = svm.latest_blockhash(id).unwrap().blockhash;
+        svm.produce_blocks().unwrap();
+        let tx = Transaction::new_signed_with_payer(
+            &[spl_token::instruction::transfer(
+                &spl_token::id(),
+                &source,
+                &destination,
+                &payer.pubkey(),
+                &[],
+                amount,
+            )
+            .unwrap()],
+            Some(&payer.pubkey()),
+            &[&payer],
+            blockhash,
+        );
+        let signature = svm
+            .send_transaction(id, VersionedTransaction::from(tx), false)
+            .unwrap();
+        assert!(processed(&svm, id, &signature).await);
+        signatures.push(signature);
+    }
+
+    let res = handle_request(
+        id,
+        request(
+            RpcMethod::MirrorGetTokenBalanceChanges,
+            json!([payer.pubkey().to_string()]),
+        ),
+        &svm,
+    )
+    .await;
+    assert!(res.error.is_none(), "{:?}", res.error);
+    let changes = res.result.unwrap();
+    assert_eq!(
+        changes,
+        json!([
+            {
+                "signature": signatures[1],
+                "slot": changes[0]["slot"],
+                "blockTime": changes[0]["blockTime"],
+                "account": source.to_string(),
+                "mint": mint.to_string(),
+                "programId": spl_token::id().to_string(),
+                "decimals": 6,
+                "preAmount": "700",
+                "postAmount": "500",
+                "change": "-200",
+            },
+            {
+                "signature": signatures[0],
+                "slot": changes[1]["slot"],
+                "blockTime": changes[1]["blockTime"],
+                "account": source.to_string(),
+                "mint": mint.to_string(),
+                "programId": spl_token::id().to_string(),
+                "decimals": 6,
+                "preAmount": "1000",
+                "postAmount": "700",
+                "change": "-300",
+            },
+        ])
+    );
+
+    // The recipient sees the other side, paged with `before`
+    let res = handle_request(
+        id,
+        request(
+            RpcMethod::MirrorGetTokenBalanceChanges,
+            json!([recipient.to_string(), {"limit": 1, "before": signatures[1]}]),
+        ),
+        &svm,
+    )
+    .await;
+    let changes = res.result.unwrap();
+    assert_eq!(changes.as_array().unwrap().len(), 1);
+    assert_eq!(changes[0]["signature"], signatures[0]);
+    assert_eq!(changes[0]["account"], destination.to_string());
+    assert_eq!(changes[0]["change"], "300");
+
+    let res = handle_request(
+        id,
+        request(
+            RpcMethod::MirrorGetTokenBalanceChanges,
+            json!([recipient.to_string(), {"limit": 0}]),
+        ),
+        &svm,
+    )
+    .await;
+    assert_eq!(res.error.unwrap()["code"], -32602);
+}
@@ -0,0 +1,84 @@
+use mockchain_engine::{
+    engine::SVM,
+    rpc::rpc::{handle_request, RpcMethod},
+    storage::Storage,
+};
+use serde_json::json;
+use solana_sdk::{
+    native_token::LAMPORTS_PER_SOL, pubkey::Pubkey, signature::Keypair, signer::Signer,
+    system_instruction, transaction::Transaction, transaction::VersionedTransaction,
+};
+
+use super::request;
+use crate::common::{engine, processed};
+
+#[actix_web::test]
+async fn test_list_nonce_accounts() {
+    let (svm, id) = engine();
+    let payer = svm.storage.get_blockchain(id).unwrap().airdrop_keypair;
+    let authority = Pubkey::new_unique();
+    let nonce = Keypair::new();
+    let blockhash = svm.latest_blockhash(id).unwrap().blockhash;
+    let tx = Transaction::new_signed_with_payer(
+        &system_instruction::create_nonce_account(
+            &payer.pubkey(),
+            &nonce.pubkey(),
+            &authority,
+            LAMPORTS_PER_SOL,
+        ),
+        Some(&payer.pubkey()),
+        &[&payer, &nonce],
+        blockhash,
+    );
+    let signature = svm
+        .send_transaction(id, VersionedTransaction::from(tx), false)
+        .unwrap();
+    assert!(processed(&svm, id, &signature).await);
+
+    // jsonParsed decodes the nonce state
+    let res = handle_request(
+        id,
+        request(
+            RpcMethod::GetAccountInfo,
+            json!([nonce.pubkey().to_string(), {"encoding": "jsonParsed"}]),
+        ),
+        &svm,
+    )
+    .await;
+    assert!(res.error.is_none(), "{:?}", res.error);
+    let result = res.result.unwrap();
+    let parsed = &result["value"]["data"]["parsed"];
+    assert_eq!(parsed["type"], "initialized");
+    assert_eq!(parsed["info"]["authority"], authority.to_string());
+    assert!(parsed["info"]["blockhash"].is_string());
+    assert!(parsed["info"]["feeCalculator"]["lamportsPerSignature"].is_string());
+
+    let res = handle_request(
+        id,
+        request(
+            RpcMethod::MirrorListNonceAccounts,
+            json!([authority.to_string()]),
+        ),
+        &svm,
+    )
+    .await;
+    assert!(res.error.is_none(), "{:?}", res.error);
+    let value = &res.result.unwrap()["value"];
+    assert_eq!(value.as_array().unwrap().len(), 1);
+    assert_eq!(value[0]["pubkey"], nonce.pubkey().to_string());
+    assert_eq!(
+        value[0]["account"]["data"]["parsed"]["info"]["authority"],
+        authority.to_string()
+    );
+
+    let res = handle_request(
+        id,
+        request(
+            RpcMethod::MirrorListNonceAccounts,
+            json!([payer.pubkey().to_string()]),
+        ),
+        &svm,
+    )
+    .await;
+    assert_eq!(res.result.unwrap()["value"], json!([]));
+}
@@ -0,0 +1,80 @@
+use mockchain_engine::{
+    engine::{SvmEngine, SVM},
+    rpc::rpc::{handle_request, RpcMethod},
+    storage::{memory::MemoryStorage, Storage},
+};
+use serde_json::json;
+use solana_sdk::{program_option::COption, program_pack::Pack, pubkey::Pubkey};
+use spl_token::state::Mint;
+use uuid::Uuid;
+
+use super::request;
+use crate::common::engine;
+
+fn mint_state(svm: &SvmEngine<MemoryStorage>, id: Uuid, mint: &Pubkey) -> Mint {
+    let account = svm.storage.get_account(id, mint).unwrap().unwrap();
+    Mint::unpack(&account.data).unwrap()
+}
+
+#[actix_web::test]
+async fn test_set_mint_authority() {
+    let (svm, id) = engine();
+    let (mint, _) = svm
+        .mint_tokens(id, None, 6, &Pubkey::new_unique(), 100)
+        .await
+        .unwrap();
+    let identity = svm.get_identity(id).unwrap();
+    let (authority, freezer) = (Pubkey::new_unique(), Pubkey::new_unique());
+
+    let res = handle_request(
+        id,
+        request(
+            RpcMethod::MirrorSetMintAuthority,
+            json!([mint.to_string(), {"freezeAuthority": freezer.to_string()}]),
+        ),
+        &svm,
+    )
+    .await;
+    assert!(res.error.is_none(), "{:?}", res.error);
+    let state = mint_state(&svm, id, &mint);
+    // Authorities that aren't given are left as they were
+    assert_eq!(state.mint_authority, COption::Some(identity));
+    assert_eq!(state.freeze_authority, COption::Some(freezer));
+    assert_eq!(state.supply, 100);
+
+    let res = handle_request(
+        id,
+        request(
+            RpcMethod::MirrorSetMintAuthority,
+            json!([mint.to_string(), {"mintAuthority": authority.to_string()}]),
+        ),
+        &svm,
+    )
+    .await;
+    assert!(res.error.is_none(), "{:?}", res.error);
+    assert_eq!(
+        mint_state(&svm, id, &mint).mint_authority,
+        COption::Some(authority)
+    );
+
+    let res = handle_request(
+        id,
+        request(
+            RpcMethod::MirrorSetMintAuthority,
+            json!([mint.to_string(), {}]),
+        ),
+        &svm,
+    )
+    .await;
+    assert_eq!(res.error.unwrap()["message"], "No authority to set");
+    let res = handle_request(
+        id,
+        request(
+            RpcMethod::MirrorSetMintAuthority,
+            json!([identity.to_string(), {"mintAuthority": authority.to_string()}]),
+        ),
+        &svm,
+    )
+    .await;
+    assert!(res.error.is_some());
+}
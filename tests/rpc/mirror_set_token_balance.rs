@@ -0,0 +1,142 @@
+use mockchain_engine::{
+    engine::{SvmEngine, SVM},
+    rpc::rpc::{handle_request, RpcMethod},
+    storage::{memory::MemoryStorage, Storage},
+};
+use serde_json::json;
+use solana_sdk::{account::Account, program_pack::Pack, pubkey::Pubkey};
+use spl_token::state::{Account as TokenAccount, Mint};
+use spl_token_2022::{
+    extension::{
+        immutable_owner::ImmutableOwner, BaseStateWithExtensions, ExtensionType,
+        StateWithExtensions,
+    },
+    state::Account as Token2022Account,
+};
+use uuid::Uuid;
+
+use super::request;
+use crate::common::engine;
+
+fn supply(svm: &SvmEngine<MemoryStorage>, id: Uuid, mint: &Pubkey) -> u64 {
+    let account = svm.storage.get_account(id, mint).unwrap().unwrap();
+    Mint::unpack(&account.data).unwrap().supply
+}
+
+#[actix_web::test]
+async fn test_set_token_balance() {
+    let (svm, id) = engine();
+    let (holder, owner) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let (mint, _) = svm.mint_tokens(id, None, 6, &holder, 100).await.unwrap();
+    assert!(RpcMethod::MirrorSetTokenBalance.is_mutating());
+
+    // Creates the owner's token account
+    let res = handle_request(
+        id,
+        request(
+            RpcMethod::MirrorSetTokenBalance,
+            json!([owner.to_string(), mint.to_string(), 50]),
+        ),
+        &svm,
+    )
+    .await;
+    assert!(res.error.is_none(), "{:?}", res.error);
+    let value = &res.result.unwrap()["value"];
+    let token_address: Pubkey = value["tokenAccount"].as_str().unwrap().parse().unwrap();
+    assert_eq!(value["amount"], "50");
+    let token_account = svm
+        .storage
+        .get_account(id, &token_address)
+        .unwrap()
+        .unwrap();
+    let state = TokenAccount::unpack(&token_account.data).unwrap();
+    assert_eq!((state.mint, state.owner, state.amount), (mint, owner, 50));
+    assert_eq!(supply(&svm, id, &mint), 150);
+
+    // Lowers an existing balance, amounts can be strings
+    let res = handle_request(
+        id,
+        request(
+            RpcMethod::MirrorSetTokenBalance,
+            json!([owner.to_string(), mint.to_string(), "10"]),
+        ),
+        &svm,
+    )
+    .await;
+    assert!(res.error.is_none(), "{:?}", res.error);
+    assert_eq!(supply(&svm, id, &mint), 110);
+
+    let res = handle_request(
+        id,
+        request(
+            RpcMethod::MirrorSetTokenBalance,
+            json!([owner.to_string(), Pubkey::new_unique().to_string(), 1]),
+        ),
+        &svm,
+    )
+    .await;
+    assert!(res.error.unwrap()["message"]
+        .as_str()
+        .unwrap()
+        .starts_with("Mint"));
+    let res = handle_request(
+        id,
+        request(
+            RpcMethod::MirrorSetTokenBalance,
+            json!([owner.to_string(), mint.to_string(), -1]),
+        ),
+        &svm,
+    )
+    .await;
+    assert_eq!(res.error.unwrap()["code"], -32602);
+}
+
+#[actix_web::test]
+async fn test_set_token_balance_token_2022() {
+    let (svm, id) = engine();
+    let (mint, owner) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let mut data = vec![0; Mint::LEN];
+    Mint::pack(
+        Mint {
+            decimals: 6,
+            is_initialized: true,
+            ..Mint::default()
+        },
+        &mut data,
+    )
+    .unwrap();
+    let mint_account = Account {
+        lamports: svm.minimum_balance_for_rent_exemption(Mint::LEN),
+        data,
+        owner: spl_token_2022::id(),
+        executable: false,
+        rent_epoch: 0,
+    };
+    svm.storage
+        .set_account(id, &mint, mint_account, None)
+        .unwrap();
+
+    let token_address = svm.set_token_balance(id, &mint, &owner, 50).await.unwrap();
+
+    // Like the associated token program creates them
+    let token_account = svm
+        .storage
+        .get_account(id, &token_address)
+        .unwrap()
+        .unwrap();
+    assert_eq!(token_account.owner, spl_token_2022::id());
+    assert_eq!(
+        token_account.lamports,
+        svm.minimum_balance_for_rent_exemption(token_account.data.len())
+    );
+    let state = StateWithExtensions::<Token2022Account>::unpack(&token_account.data).unwrap();
+    assert_eq!(
+        (state.base.mint, state.base.owner, state.base.amount),
+        (mint, owner, 50)
+    );
+    assert_eq!(
+        state.get_extension_types().unwrap(),
+        vec![ExtensionType::ImmutableOwner]
+    );
+    assert!(state.get_extension::<ImmutableOwner>().is_ok());
+}
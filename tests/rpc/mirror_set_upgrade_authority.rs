@@ -0,0 +1,64 @@
+use mockchain_engine::{
+    engine::SVM,
+    rpc::rpc::{handle_request, RpcMethod},
+    storage::Storage,
+};
+use serde_json::json;
+use solana_sdk::{bpf_loader_upgradeable::UpgradeableLoaderState, pubkey::Pubkey};
+
+use super::request;
+use crate::common::engine;
+
+#[actix_web::test]
+async fn test_set_upgrade_authority() {
+    let (svm, id) = engine();
+    let (program_id, authority) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let elf = vec![7; 64];
+    let accounts = svm
+        .add_upgradeable_program(program_id, &elf, Some(Pubkey::new_unique()))
+        .unwrap();
+    let programdata_address = accounts[1].0;
+    svm.storage.set_accounts(id, accounts).unwrap();
+
+    let res = handle_request(
+        id,
+        request(
+            RpcMethod::MirrorSetUpgradeAuthority,
+            json!([program_id.to_string(), authority.to_string()]),
+        ),
+        &svm,
+    )
+    .await;
+    assert!(res.error.is_none(), "{:?}", res.error);
+    assert_eq!(
+        res.result.unwrap()["value"]["programData"],
+        programdata_address.to_string()
+    );
+    let programdata = svm
+        .storage
+        .get_account(id, &programdata_address)
+        .unwrap()
+        .unwrap();
+    match bincode::deserialize(&programdata.data).unwrap() {
+        UpgradeableLoaderState::ProgramData {
+            upgrade_authority_address,
+            ..
+        } => assert_eq!(upgrade_authority_address, Some(authority)),
+        state => panic!("Unexpected state {:?}", state),
+    }
+    // The ELF is left as it was
+    let metadata_len = UpgradeableLoaderState::size_of_programdata_metadata();
+    assert_eq!(programdata.data[metadata_len..], elf[..]);
+
+    // Only upgradeable programs have an upgrade authority
+    let res = handle_request(
+        id,
+        request(
+            RpcMethod::MirrorSetUpgradeAuthority,
+            json!([authority.to_string(), authority.to_string()]),
+        ),
+        &svm,
+    )
+    .await;
+    assert!(res.error.is_some());
+}
@@ -0,0 +1,118 @@
+use base64::prelude::*;
+use mockchain_engine::{
+    engine::{SvmEngine, SVM},
+    rpc::rpc::{handle_request, RpcMethod},
+    storage::{memory::MemoryStorage, Storage},
+};
+use serde_json::{json, Value};
+use solana_sdk::{
+    native_token::LAMPORTS_PER_SOL, pubkey::Pubkey, signer::Signer, system_instruction,
+    system_program, transaction::Transaction,
+};
+use uuid::Uuid;
+
+use super::request;
+use crate::common::engine;
+
+async fn simulate_with_trace(
+    svm: &SvmEngine<MemoryStorage>,
+    id: Uuid,
+    amounts: &[u64],
+    config: Value,
+) -> (Pubkey, Vec<Pubkey>, Value) {
+    let payer = svm.storage.get_blockchain(id).unwrap().airdrop_keypair;
+    let recipients: Vec<Pubkey> = amounts.iter().map(|_| Pubkey::new_unique()).collect();
+    let instructions: Vec<_> = recipients
+        .iter()
+        .zip(amounts)
+        .map(|(recipient, amount)| {
+            system_instruction::transfer(&payer.pubkey(), recipient, *amount)
+        })
+        .collect();
+    let blockhash = svm.latest_blockhash(id).unwrap().blockhash;
+    let tx = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&payer.pubkey()),
+        &[&payer],
+        blockhash,
+    );
+    let res = handle_request(
+        id,
+        request(
+            RpcMethod::MirrorSimulateWithTrace,
+            json!([
+                BASE64_STANDARD.encode(bincode::serialize(&tx).unwrap()),
+                config,
+            ]),
+        ),
+        svm,
+    )
+    .await;
+    assert!(res.error.is_none(), "{:?}", res.error);
+    (
+        payer.pubkey(),
+        recipients,
+        res.result.unwrap()["value"].clone(),
+    )
+}
+
+#[actix_web::test]
+async fn test_simulate_with_trace() {
+    let (svm, id) = engine();
+
+    let (payer, recipients, value) = simulate_with_trace(
+        &svm,
+        id,
+        &[LAMPORTS_PER_SOL, 2 * LAMPORTS_PER_SOL],
+        json!({"encoding": "base64", "accountData": true}),
+    )
+    .await;
+    assert!(value["err"].is_null());
+    let trace = value["trace"].as_array().unwrap();
+    assert_eq!(trace.len(), 2);
+    let mut units = 0;
+    for (index, (instruction, recipient)) in trace.iter().zip(&recipients).enumerate() {
+        assert_eq!(instruction["index"], index);
+        assert_eq!(instruction["programId"], system_program::id().to_string());
+        // Each instruction starts where the previous one left off
+        assert_eq!(instruction["computeUnitsBefore"], units);
+        units = instruction["computeUnitsAfter"].as_u64().unwrap();
+        assert!(instruction["computeUnitsConsumed"].as_u64().unwrap() > 0);
+        assert!(instruction["err"].is_null());
+        assert!(!instruction["logs"].as_array().unwrap().is_empty());
+
+        let writes = instruction["accountWrites"].as_array().unwrap();
+        let written: Vec<&str> = writes
+            .iter()
+            .map(|write| write["pubkey"].as_str().unwrap())
+            .collect();
+        assert_eq!(written.len(), 2);
+        assert!(written.contains(&payer.to_string().as_str()));
+        let write = writes
+            .iter()
+            .find(|write| write["pubkey"] == recipient.to_string())
+            .unwrap();
+        assert_eq!(write["lamports"]["before"], 0);
+        assert_eq!(
+            write["lamports"]["after"],
+            (index as u64 + 1) * LAMPORTS_PER_SOL
+        );
+        assert_eq!(write["data"], json!(["", "base64"]));
+    }
+    assert_eq!(value["unitsConsumed"], units);
+
+    // The failing instruction is traced without writes, later ones never run
+    let (_, _, value) = simulate_with_trace(
+        &svm,
+        id,
+        &[LAMPORTS_PER_SOL, u64::MAX, LAMPORTS_PER_SOL],
+        json!({"encoding": "base64"}),
+    )
+    .await;
+    assert!(!value["err"].is_null());
+    let trace = value["trace"].as_array().unwrap();
+    assert_eq!(trace.len(), 2);
+    assert!(trace[0]["accountWrites"][0].get("data").is_none());
+    assert!(!trace[1]["err"].is_null());
+    assert!(trace[1]["accountWrites"].as_array().unwrap().is_empty());
+}
@@ -1,9 +1,34 @@
+pub mod context_slot;
 pub mod get_account_info;
 pub mod get_balance;
+pub mod get_block;
 pub mod get_health;
 pub mod get_latest_blockhash;
 pub mod get_minimum_balance_for_rent_exemption;
+pub mod get_program_accounts;
+pub mod get_signatures_for_address;
+pub mod get_slot;
+pub mod get_transaction;
 pub mod get_version;
 pub mod is_blockhash_valid;
+pub mod mirror_get_token_balance_changes;
+pub mod mirror_list_nonce_accounts;
+pub mod mirror_set_mint_authority;
+pub mod mirror_set_token_balance;
+pub mod mirror_set_upgrade_authority;
+pub mod mirror_simulate_with_trace;
 pub mod request_airdrop;
 pub mod send_transaction;
+
+use mockchain_engine::rpc::rpc::{RpcMethod, RpcRequest};
+use serde_json::Value;
+
+/// A JSON-RPC call of `method` with `params`.
+pub fn request(method: RpcMethod, params: Value) -> RpcRequest {
+    RpcRequest {
+        jsonrpc: "2.0".to_string(),
+        id: Value::Number(1.into()),
+        method,
+        params: Some(params),
+    }
+}
@@ -1 +1,68 @@
-//TODO: need to write tests
+use base64::prelude::*;
+use mockchain_engine::{
+    engine::{SvmEngine, SVM},
+    rpc::rpc::{handle_request, RpcMethod, RpcRequest},
+    storage::{memory::MemoryStorage, teams::Team, Storage},
+};
+use serde_json::json;
+use solana_sdk::{
+    native_token::LAMPORTS_PER_SOL, pubkey::Pubkey, signer::Signer, system_instruction,
+    transaction::Transaction,
+};
+use uuid::Uuid;
+
+use super::request;
+
+fn send_request(svm: &SvmEngine<MemoryStorage>, id: Uuid) -> RpcRequest {
+    let payer = svm.storage.get_blockchain(id).unwrap().airdrop_keypair;
+    let blockhash = svm.latest_blockhash(id).unwrap().blockhash;
+    let tx = Transaction::new_signed_with_payer(
+        &[system_instruction::transfer(
+            &payer.pubkey(),
+            &Pubkey::new_unique(),
+            LAMPORTS_PER_SOL,
+        )],
+        Some(&payer.pubkey()),
+        &[&payer],
+        blockhash,
+    );
+    request(
+        RpcMethod::SendTransaction,
+        json!([
+            BASE64_STANDARD.encode(bincode::serialize(&tx).unwrap()),
+            {"encoding": "base64", "skipPreflight": true},
+        ]),
+    )
+}
+
+#[actix_web::test]
+async fn test_daily_transaction_quota() {
+    let storage = MemoryStorage::new();
+    let team = Team {
+        id: Uuid::new_v4(),
+        created_at: chrono::Utc::now().naive_utc(),
+        name: "quota".to_string(),
+        default_expiry: None,
+        max_blockchains: None,
+        max_accounts: None,
+        max_transactions_per_day: Some(1),
+        auto_pause_hours: None,
+        rpc_write_guard: false,
+    };
+    storage.create_team(team.clone());
+    let svm = SvmEngine::new(storage);
+    let id = svm
+        .create_blockchain(team.id, None, None, None, None, false)
+        .unwrap();
+
+    let res = handle_request(id, send_request(&svm, id), &svm).await;
+    assert!(res.error.is_none(), "{:?}", res.error);
+
+    let res = handle_request(id, send_request(&svm, id), &svm).await;
+    let error = res.error.unwrap();
+    assert_eq!(error["code"], -32602);
+    assert!(error["message"]
+        .as_str()
+        .unwrap()
+        .contains("1 transactions per day"));
+}
@@ -0,0 +1,172 @@
+use mockchain_engine::settings::{
+    ClusterSettings, EventBusKind, LimitSettings, PoolSettings, ProgramSettings, QueueKind,
+    QueueSettings, Settings,
+};
+use std::time::Duration;
+
+#[test]
+fn test_example_config() {
+    let settings = Settings::from_toml(include_str!("../../config.example.toml")).unwrap();
+    settings.validate().unwrap();
+    assert_eq!(settings.event_bus, EventBusKind::Kafka);
+    assert_eq!(settings.server.bind, vec!["0.0.0.0:8899", "[::]:9001"]);
+    assert_eq!(settings.pool, PoolSettings::default());
+    assert_eq!(settings.jobs.expiry_interval, Some(Duration::from_secs(60)));
+    assert_eq!(
+        settings.jobs.block_interval,
+        Some(Duration::from_millis(400))
+    );
+    assert_eq!(
+        settings.jobs.auto_pause_interval,
+        Some(Duration::from_secs(300))
+    );
+    assert_eq!(settings.jobs.top_up_interval, Some(Duration::from_secs(10)));
+    assert_eq!(settings.jobs.sweep_interval, Some(Duration::from_secs(300)));
+    assert_eq!(
+        settings.jobs.heartbeat_interval,
+        Some(Duration::from_secs(10))
+    );
+    assert_eq!(settings.limits, LimitSettings::default());
+    assert!(settings.features.webhooks);
+    assert!(!settings.features.anchor_compat);
+    assert!(!settings.features.persist_queue);
+    assert_eq!(settings.cluster, ClusterSettings::default());
+    assert_eq!(settings.queue, QueueSettings::default());
+    assert_eq!(settings.programs, ProgramSettings::default());
+}
+
+#[test]
+fn test_partial_config() {
+    let settings = Settings::from_toml(
+        r#"
+        env = "dev"
+        event_bus = "redis"
+
+        [pool]
+        database_max_size = 32
+        database_statement_timeout_ms = 5000
+        database_write_window_ms = 0
+        cache_read_timeout_ms = 0
+        program_accounts_cache_ttl_ms = 0
+
+        [jobs]
+        expiry_interval_ms = 0
+        block_interval_ms = 0
+        auto_pause_interval_ms = 0
+        top_up_interval_ms = 0
+        sweep_interval_ms = 0
+        heartbeat_interval_ms = 0
+        "#,
+    )
+    .unwrap();
+    assert!(settings.is_dev());
+    assert_eq!(settings.event_bus, EventBusKind::Redis);
+    assert_eq!(settings.pool.database_max_size, 32);
+    assert_eq!(
+        settings.pool.database_statement_timeout,
+        Some(Duration::from_secs(5))
+    );
+    assert_eq!(settings.pool.database_write_window, None);
+    assert_eq!(settings.pool.cache_read_timeout, None);
+    assert_eq!(settings.pool.program_accounts_cache_ttl, None);
+    assert_eq!(
+        settings.pool.cache_max_size,
+        PoolSettings::default().cache_max_size
+    );
+    assert_eq!(settings.jobs.expiry_interval, None);
+    assert_eq!(settings.jobs.block_interval, None);
+    assert_eq!(settings.jobs.auto_pause_interval, None);
+    assert_eq!(settings.jobs.top_up_interval, None);
+    assert_eq!(settings.jobs.sweep_interval, None);
+    assert_eq!(settings.jobs.heartbeat_interval, None);
+    assert_eq!(
+        settings.blockchain_url(uuid::Uuid::nil()),
+        "http://localhost:8899/rpc/00000000-0000-0000-0000-000000000000"
+    );
+    // Required URLs come from the environment when the file leaves them out
+    assert!(settings.validate().is_err());
+}
+
+#[test]
+fn test_unknown_keys_rejected() {
+    assert!(Settings::from_toml("databse_url = \"postgres://\"").is_err());
+    assert!(Settings::from_toml("[pool]\ndatabase_connect_timeout = 10").is_err());
+    assert!(Settings::from_toml("event_bus = \"nats\"").is_err());
+}
+
+#[test]
+fn test_invalid_reporting_dsn() {
+    let mut settings = Settings::from_toml(include_str!("../../config.example.toml")).unwrap();
+    settings.reporting.dsn = Some("https://o42.ingest.sentry.io/1234".to_string());
+    assert!(settings.validate().is_err());
+    settings.reporting.dsn = Some("https://key@o42.ingest.sentry.io/1234".to_string());
+    settings.validate().unwrap();
+}
+
+#[test]
+fn test_invalid_log_sample_rate() {
+    let mut settings = Settings::from_toml(include_str!("../../config.example.toml")).unwrap();
+    settings.logging.sample_rate = 1.5;
+    assert!(settings.validate().is_err());
+    settings.logging.sample_rate = 0.25;
+    settings.validate().unwrap();
+}
+
+#[test]
+fn test_invalid_lease_ttl() {
+    let mut settings = Settings::from_toml(include_str!("../../config.example.toml")).unwrap();
+    settings.cluster.leases = true;
+    settings.validate().unwrap();
+    settings.cluster.lease_ttl = settings.cluster.forward_interval;
+    assert!(settings.validate().is_err());
+}
+
+#[test]
+fn test_persist_queue_requires_instance_id() {
+    let mut settings = Settings::from_toml(include_str!("../../config.example.toml")).unwrap();
+    settings.features.persist_queue = true;
+    assert!(settings.validate().is_err());
+    settings.cluster.instance_id = Some(uuid::Uuid::new_v4());
+    settings.validate().unwrap();
+}
+
+#[test]
+fn test_kafka_queue_requires_brokers() {
+    let mut settings = Settings::from_toml(include_str!("../../config.example.toml")).unwrap();
+    settings.queue.kind = QueueKind::Kafka;
+    assert!(settings.validate().is_err());
+    settings.pubsub_url = "localhost:9092".to_string();
+    settings.validate().unwrap();
+    assert!(Settings::from_toml("[queue]\nkind = \"nats\"").is_err());
+}
+
+#[test]
+fn test_queue_capacity() {
+    let settings = Settings::from_toml("[queue]\ncapacity = 10\nidle_timeout_ms = 0").unwrap();
+    assert_eq!(settings.queue.capacity, 10);
+    assert_eq!(settings.queue.idle_timeout, None);
+    let mut settings = Settings::from_toml(include_str!("../../config.example.toml")).unwrap();
+    settings.queue.capacity = 0;
+    assert!(settings.validate().is_err());
+}
+
+#[test]
+fn test_simulation_workers() {
+    let settings =
+        Settings::from_toml("[limits]\nsimulation_workers = 2\nsimulation_queue_depth = 0")
+            .unwrap();
+    assert_eq!(settings.limits.simulation_workers, 2);
+    assert_eq!(settings.limits.simulation_queue_depth, 0);
+    let mut settings = Settings::from_toml(include_str!("../../config.example.toml")).unwrap();
+    settings.limits.simulation_workers = 0;
+    assert!(settings.validate().is_err());
+}
+
+#[test]
+fn test_missing_programs_directory() {
+    let mut settings = Settings::from_toml(include_str!("../../config.example.toml")).unwrap();
+    settings.programs.directory = Some("/nonexistent/programs".into());
+    assert!(settings.validate().is_err());
+    settings.programs.directory = Some(std::env::temp_dir());
+    settings.validate().unwrap();
+}
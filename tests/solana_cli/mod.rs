@@ -0,0 +1,258 @@
+//! The RPC calls the solana CLI makes, through the same client it uses. The CLI itself
+//! is exercised by scripts/solana-cli.sh.
+
+use actix_web::rt::time;
+use mockchain_engine::embedded::EmbeddedEngine;
+use solana_rpc_client::nonblocking::rpc_client::RpcClient;
+use solana_rpc_client_api::{
+    client_error::ErrorKind,
+    config::{
+        RpcSendTransactionConfig, RpcSimulateTransactionAccountsConfig,
+        RpcSimulateTransactionConfig,
+    },
+    request::{RpcError, RpcResponseErrorData},
+};
+use solana_sdk::{
+    bpf_loader_upgradeable::{self, UpgradeableLoaderState},
+    hash::Hash,
+    instruction::{AccountMeta, Instruction},
+    message::Message,
+    native_token::LAMPORTS_PER_SOL,
+    signature::{Keypair, Signature},
+    signer::Signer,
+    system_instruction,
+    transaction::Transaction,
+};
+use std::time::Duration;
+
+const MEMO: &[u8] = include_bytes!("../../src/engine/spl/programs/spl_memo-3.0.0.so");
+// What the solana CLI writes per transaction
+const WRITE_CHUNK: usize = 900;
+
+fn client(engine: &mut EmbeddedEngine) -> RpcClient {
+    engine.listen().unwrap();
+    RpcClient::new(engine.rpc_url().unwrap())
+}
+
+/// Waits for every signature the way `solana program deploy` does, polling statuses.
+async fn confirm(client: &RpcClient, signatures: &[Signature]) {
+    for _ in 0..250 {
+        let statuses = client.get_signature_statuses(signatures).await.unwrap();
+        if statuses.value.iter().all(Option::is_some) {
+            for status in statuses.value.into_iter().flatten() {
+                assert_eq!(status.err, None);
+            }
+            return;
+        }
+        time::sleep(Duration::from_millis(20)).await;
+    }
+    panic!("Transactions were not processed");
+}
+
+#[actix_web::test]
+async fn test_airdrop_and_transfer() {
+    let mut engine = EmbeddedEngine::new().unwrap();
+    let client = client(&mut engine);
+    let sender = Keypair::new();
+    let recipient = Keypair::new();
+
+    // solana airdrop
+    let signature = client
+        .request_airdrop(&sender.pubkey(), 2 * LAMPORTS_PER_SOL)
+        .await
+        .unwrap();
+    client.poll_for_signature(&signature).await.unwrap();
+    assert_eq!(
+        client.get_balance(&sender.pubkey()).await.unwrap(),
+        2 * LAMPORTS_PER_SOL
+    );
+
+    // solana transfer checks the fee before sending
+    let blockhash = client.get_latest_blockhash().await.unwrap();
+    let message = Message::new_with_blockhash(
+        &[system_instruction::transfer(
+            &sender.pubkey(),
+            &recipient.pubkey(),
+            LAMPORTS_PER_SOL,
+        )],
+        Some(&sender.pubkey()),
+        &blockhash,
+    );
+    let fee = client.get_fee_for_message(&message).await.unwrap();
+    assert_eq!(fee, 5000);
+    let tx = Transaction::new(&[&sender], message, blockhash);
+    client.send_and_confirm_transaction(&tx).await.unwrap();
+    assert_eq!(
+        client.get_balance(&recipient.pubkey()).await.unwrap(),
+        LAMPORTS_PER_SOL
+    );
+    assert_eq!(
+        client.get_balance(&sender.pubkey()).await.unwrap(),
+        LAMPORTS_PER_SOL - fee
+    );
+    engine.stop().await;
+}
+
+#[actix_web::test]
+async fn test_simulation() {
+    let mut engine = EmbeddedEngine::new().unwrap();
+    let client = client(&mut engine);
+    let payer = engine.payer();
+    let recipient = Keypair::new();
+
+    // The CLI estimates compute units on unsigned messages with a default blockhash
+    let tx = Transaction::new_unsigned(Message::new_with_blockhash(
+        &[system_instruction::transfer(
+            &payer.pubkey(),
+            &recipient.pubkey(),
+            LAMPORTS_PER_SOL,
+        )],
+        Some(&payer.pubkey()),
+        &Hash::default(),
+    ));
+    let res = client
+        .simulate_transaction_with_config(
+            &tx,
+            RpcSimulateTransactionConfig {
+                sig_verify: false,
+                replace_recent_blockhash: true,
+                accounts: Some(RpcSimulateTransactionAccountsConfig {
+                    encoding: None,
+                    addresses: vec![recipient.pubkey().to_string()],
+                }),
+                ..RpcSimulateTransactionConfig::default()
+            },
+        )
+        .await
+        .unwrap()
+        .value;
+    assert_eq!(res.err, None);
+    assert!(res.units_consumed.unwrap() > 0);
+    assert!(res.replacement_blockhash.is_some());
+    let accounts = res.accounts.unwrap();
+    assert_eq!(accounts[0].as_ref().unwrap().lamports, LAMPORTS_PER_SOL);
+
+    // Failures are results, with their logs
+    let blockhash = client.get_latest_blockhash().await.unwrap();
+    let tx = Transaction::new_signed_with_payer(
+        &[system_instruction::transfer(
+            &payer.pubkey(),
+            &recipient.pubkey(),
+            u64::MAX / 2,
+        )],
+        Some(&payer.pubkey()),
+        &[&payer],
+        blockhash,
+    );
+    let res = client.simulate_transaction(&tx).await.unwrap().value;
+    assert!(res.err.is_some());
+    assert!(!res.logs.unwrap().is_empty());
+
+    // And fail preflight the way the CLI reports
+    let err = client.send_transaction(&tx).await.unwrap_err();
+    match err.kind() {
+        ErrorKind::RpcError(RpcError::RpcResponseError {
+            data: RpcResponseErrorData::SendTransactionPreflightFailure(res),
+            ..
+        }) => assert!(res.err.is_some()),
+        _ => panic!("Unexpected error {:?}", err),
+    }
+    engine.stop().await;
+}
+
+#[actix_web::test]
+async fn test_program_deploy() {
+    let mut engine = EmbeddedEngine::new().unwrap();
+    let client = client(&mut engine);
+    let payer = engine.payer();
+    let buffer = Keypair::new();
+    let program = Keypair::new();
+
+    // solana program deploy, sending writes in parallel without preflight
+    let buffer_lamports = client
+        .get_minimum_balance_for_rent_exemption(UpgradeableLoaderState::size_of_buffer(MEMO.len()))
+        .await
+        .unwrap();
+    let create = bpf_loader_upgradeable::create_buffer(
+        &payer.pubkey(),
+        &buffer.pubkey(),
+        &payer.pubkey(),
+        buffer_lamports,
+        MEMO.len(),
+    )
+    .unwrap();
+    let blockhash = client.get_latest_blockhash().await.unwrap();
+    let tx = Transaction::new_signed_with_payer(
+        &create,
+        Some(&payer.pubkey()),
+        &[&payer, &buffer],
+        blockhash,
+    );
+    client.send_and_confirm_transaction(&tx).await.unwrap();
+
+    let mut writes = vec![];
+    for (idx, chunk) in MEMO.chunks(WRITE_CHUNK).enumerate() {
+        let write = bpf_loader_upgradeable::write(
+            &buffer.pubkey(),
+            &payer.pubkey(),
+            (idx * WRITE_CHUNK) as u32,
+            chunk.to_vec(),
+        );
+        let tx = Transaction::new_signed_with_payer(
+            &[write],
+            Some(&payer.pubkey()),
+            &[&payer],
+            blockhash,
+        );
+        let signature = client
+            .send_transaction_with_config(
+                &tx,
+                RpcSendTransactionConfig {
+                    skip_preflight: true,
+                    ..RpcSendTransactionConfig::default()
+                },
+            )
+            .await
+            .unwrap();
+        writes.push(signature);
+    }
+    confirm(&client, &writes).await;
+
+    let program_lamports = client
+        .get_minimum_balance_for_rent_exemption(UpgradeableLoaderState::size_of_program())
+        .await
+        .unwrap();
+    #[allow(deprecated)]
+    let deploy = bpf_loader_upgradeable::deploy_with_max_program_len(
+        &payer.pubkey(),
+        &program.pubkey(),
+        &buffer.pubkey(),
+        &payer.pubkey(),
+        program_lamports,
+        MEMO.len() * 2,
+    )
+    .unwrap();
+    let tx = Transaction::new_signed_with_payer(
+        &deploy,
+        Some(&payer.pubkey()),
+        &[&payer, &program],
+        blockhash,
+    );
+    client.send_and_confirm_transaction(&tx).await.unwrap();
+
+    // solana account
+    let account = client.get_account(&program.pubkey()).await.unwrap();
+    assert!(account.executable);
+    assert_eq!(account.owner, bpf_loader_upgradeable::id());
+
+    let memo = Instruction::new_with_bytes(
+        program.pubkey(),
+        b"deployed",
+        vec![AccountMeta::new_readonly(payer.pubkey(), true)],
+    );
+    let blockhash = client.get_latest_blockhash().await.unwrap();
+    let tx =
+        Transaction::new_signed_with_payer(&[memo], Some(&payer.pubkey()), &[&payer], blockhash);
+    client.send_and_confirm_transaction(&tx).await.unwrap();
+    engine.stop().await;
+}
@@ -0,0 +1,104 @@
+use bigdecimal::BigDecimal;
+use mockchain_engine::storage::{account_writes::AccountWrites, accounts::DbAccount};
+use solana_sdk::{account::Account, pubkey::Pubkey};
+use std::time::Duration;
+use uuid::Uuid;
+
+fn row(id: Uuid, address: &Pubkey, lamports: u64, label: Option<&str>) -> DbAccount {
+    let account = Account {
+        lamports,
+        ..Account::default()
+    };
+    DbAccount::new(address, account, label.map(str::to_string), id)
+}
+
+#[test]
+fn test_writes_coalesced_per_window() {
+    let writes = AccountWrites::new(Some(Duration::from_millis(20)));
+    let (id, other) = (Uuid::new_v4(), Uuid::new_v4());
+    let (first, second) = (Pubkey::new_unique(), Pubkey::new_unique());
+
+    assert!(writes.add(
+        id,
+        vec![row(id, &first, 1, Some("payer")), row(id, &second, 1, None)],
+        vec![]
+    ));
+    // Later transactions join the open window, their rows replace earlier ones
+    assert!(!writes.add(
+        id,
+        vec![row(id, &first, 2, None)],
+        vec!["program".to_string()]
+    ));
+    assert!(writes.add(other, vec![row(other, &first, 5, None)], vec![]));
+    assert_eq!(writes.len(), 3);
+
+    let (mut rows, previous_owners) = writes.take(id);
+    assert_eq!(previous_owners, vec!["program".to_string()]);
+    rows.sort_by_key(|row| row.lamports.clone());
+    assert_eq!(rows.len(), 2);
+    assert_eq!(rows[0].address, second.to_string());
+    assert_eq!(rows[1].address, first.to_string());
+    assert_eq!(rows[1].lamports, BigDecimal::from(2));
+    assert_eq!(rows[1].label.as_deref(), Some("payer"));
+
+    // Taking closes the window
+    assert!(writes.take(id).0.is_empty());
+    assert!(writes.add(id, vec![row(id, &first, 3, None)], vec![]));
+    assert_eq!(writes.len(), 2);
+}
+
+#[test]
+fn test_direct_writes_applied_to_queued_rows() {
+    let writes = AccountWrites::new(Some(Duration::from_millis(20)));
+    let id = Uuid::new_v4();
+    let (updated, deleted) = (Pubkey::new_unique(), Pubkey::new_unique());
+    writes.add(
+        id,
+        vec![row(id, &updated, 1, None), row(id, &deleted, 1, None)],
+        vec![],
+    );
+
+    writes.set_lamports(id, &updated.to_string(), 7);
+    writes.forget(id, &deleted.to_string());
+    let (rows, _) = writes.take(id);
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0].lamports, BigDecimal::from(7));
+
+    writes.add(id, vec![row(id, &updated, 1, None)], vec![]);
+    writes.forget_blockchain(id);
+    assert!(writes.is_empty());
+    assert!(writes.take(id).0.is_empty());
+}
+
+#[test]
+fn test_deletes_win_over_ticketed_rows() {
+    let writes = AccountWrites::new(None);
+    let id = Uuid::new_v4();
+    let (kept, deleted) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let rows = || vec![row(id, &kept, 1, None), row(id, &deleted, 1, None)];
+
+    // Rows already on their way to Postgres when the account is deleted
+    let ticket = writes.ticket();
+    writes.forget(id, &deleted.to_string());
+    let live = writes.in_order(id, || writes.live(id, &ticket, rows()));
+    assert_eq!(live.len(), 1);
+    assert_eq!(live[0].address, kept.to_string());
+    // Other blockchains are unaffected
+    let other = Uuid::new_v4();
+    assert_eq!(
+        writes
+            .live(other, &ticket, vec![row(other, &deleted, 1, None)])
+            .len(),
+        1
+    );
+    drop(ticket);
+
+    // Writing the account again after the delete recreates it
+    let ticket = writes.ticket();
+    assert_eq!(writes.live(id, &ticket, rows()).len(), 2);
+}
+
+#[test]
+fn test_no_window() {
+    assert_eq!(AccountWrites::new(None).window(), None);
+}
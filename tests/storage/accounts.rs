@@ -46,3 +46,31 @@ fn test_set_account() {
 
     assert_eq!(stored_account, Some(account));
 }
+
+#[actix_web::test]
+async fn test_label_account_right_after_set_account() {
+    dotenv().ok();
+    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set");
+    let cache_url = env::var("CACHE_URL").expect("CACHE_URL must be set");
+    let rpc_url = env::var("RPC_URL").expect("RPC_URL must be set");
+    let pubsub_url = env::var("PUBSUB_URL").expect("PUBSUB_URL must be set");
+    let storage = PgStorage::new(&database_url, &cache_url, &rpc_url, &pubsub_url);
+
+    let id = uuid::Uuid::parse_str("110200f4-1a05-4a3f-b4f9-6bc38ff19cdf").unwrap();
+    let address = solana_sdk::pubkey::new_rand();
+    let label = format!("label-{}", uuid::Uuid::new_v4());
+
+    // Labeled while the account is still queued in its write window
+    storage
+        .set_account(id, &address, solana_sdk::account::Account::default(), None)
+        .unwrap();
+    assert!(storage
+        .set_account_label(id, &address, Some(label.clone()))
+        .unwrap());
+    storage.flush(std::time::Duration::from_secs(5)).await;
+
+    assert_eq!(
+        storage.get_labeled_accounts(id, &[label]).unwrap(),
+        vec![address]
+    );
+}
@@ -0,0 +1,14 @@
+use mockchain_engine::storage::events::{BusEvent, EventBus};
+use uuid::Uuid;
+
+#[test]
+fn test_event_message() {
+    let id = Uuid::new_v4();
+    let event = BusEvent::Transaction {
+        signature: "signature".to_string(),
+        accounts: vec!["account".to_string()],
+    };
+    let (channel, payload) = EventBus::message(id, &event).unwrap();
+    assert_eq!(channel, format!("events:{}", id));
+    assert_eq!(serde_json::from_str::<BusEvent>(&payload).unwrap(), event);
+}
@@ -0,0 +1,178 @@
+use mockchain_engine::{
+    engine::SVM,
+    storage::{pubsub::GeyserEvent, Storage},
+};
+use serde_json::{json, Value};
+use solana_sdk::{
+    pubkey::Pubkey,
+    signer::Signer,
+    system_program,
+    transaction::{Transaction, VersionedTransaction},
+};
+use std::collections::HashSet;
+
+use crate::{
+    common::{engine, processed},
+    rpc::get_transaction::transfer,
+};
+
+fn described(events: &[GeyserEvent]) -> Vec<(String, u64)> {
+    events
+        .iter()
+        .map(|event| {
+            let message = serde_json::to_value(event).unwrap();
+            assert_eq!(message["key"], event.key());
+            let payload: Value = serde_json::from_str(&event.payload().unwrap()).unwrap();
+            assert_eq!(message["payload"], payload);
+            let slot = match event {
+                GeyserEvent::Block(block) => block.slot,
+                GeyserEvent::Transaction(tx) => tx.transaction.slot,
+            };
+            (event.key().to_string(), slot as u64)
+        })
+        .collect()
+}
+
+#[actix_web::test]
+async fn test_slot_history() {
+    let (svm, id) = engine();
+    svm.latest_blockhash(id).unwrap();
+    svm.produce_blocks().unwrap();
+    let first = transfer(&svm, id, false).await;
+    let second = transfer(&svm, id, true).await;
+    svm.produce_blocks().unwrap();
+    let third = transfer(&svm, id, false).await;
+
+    // Slot 2 is still being produced, so callers stop before it
+    let latest = svm.storage.get_latest_block(id).unwrap().block_height;
+    assert_eq!(latest, 2);
+    let events = svm
+        .storage
+        .get_slot_history(id, 0, latest, 10, 1_000)
+        .unwrap();
+    assert_eq!(
+        described(&events),
+        vec![
+            ("block".to_string(), 0),
+            ("block".to_string(), 1),
+            ("transaction".to_string(), 1),
+            ("transaction".to_string(), 1),
+        ]
+    );
+    let signatures: Vec<String> = events
+        .iter()
+        .filter_map(|event| match event {
+            GeyserEvent::Transaction(tx) => Some(tx.transaction.signature.clone()),
+            GeyserEvent::Block(_) => None,
+        })
+        .collect();
+    assert_eq!(signatures, vec![first.to_string(), second.to_string()]);
+
+    // Pages by slot
+    let page = svm
+        .storage
+        .get_slot_history(id, 1, latest, 1, 1_000)
+        .unwrap();
+    assert_eq!(described(&page), described(&events[1..]));
+    let rest = svm
+        .storage
+        .get_slot_history(id, 2, latest + 1, 10, 1_000)
+        .unwrap();
+    assert_eq!(
+        described(&rest),
+        vec![("block".to_string(), 2), ("transaction".to_string(), 2)]
+    );
+    assert!(matches!(
+        &rest[1],
+        GeyserEvent::Transaction(tx) if tx.transaction.signature == third.to_string()
+    ));
+
+    // Busy slots end a page early, though never before its first slot
+    let page = svm
+        .storage
+        .get_slot_history(id, 0, latest + 1, 10, 3)
+        .unwrap();
+    assert_eq!(described(&page), described(&events[..1]));
+    let page = svm
+        .storage
+        .get_slot_history(id, 1, latest + 1, 10, 1)
+        .unwrap();
+    assert_eq!(described(&page), described(&events[1..]));
+
+    // Without Kafka there is nowhere to publish to
+    assert!(svm.storage.replay_geyser_events(&events).is_err());
+}
+
+#[actix_web::test]
+async fn test_transaction_addresses_and_owners() {
+    let (svm, id) = engine();
+    let payer = svm.storage.get_blockchain(id).unwrap().airdrop_keypair;
+    let (mint, source) = svm
+        .mint_tokens(id, None, 6, &payer.pubkey(), 1_000)
+        .await
+        .unwrap();
+    let (_, destination) = svm
+        .mint_tokens(id, Some(mint), 6, &Pubkey::new_unique(), 0)
+        .await
+        .unwrap();
+    svm.produce_blocks().unwrap();
+    let blockhash = svm.latest_blockhash(id).unwrap().blockhash;
+    let tx = Transaction::new_signed_with_payer(
+        &[spl_token::instruction::transfer(
+            &spl_token::id(),
+            &source,
+            &destination,
+            &payer.pubkey(),
+            &[],
+            250,
+        )
+        .unwrap()],
+        Some(&payer.pubkey()),
+        &[&payer],
+        blockhash,
+    );
+    let keys: Vec<String> = tx
+        .message
+        .account_keys
+        .iter()
+        .map(|key| key.to_string())
+        .collect();
+    let signature = svm
+        .send_transaction(id, VersionedTransaction::from(tx), false)
+        .unwrap();
+    assert!(processed(&svm, id, &signature).await);
+    svm.produce_blocks().unwrap();
+
+    let latest = svm.storage.get_latest_block(id).unwrap().block_height;
+    let events = svm
+        .storage
+        .get_slot_history(id, 0, latest, 100, 1_000)
+        .unwrap();
+    let tx = events
+        .iter()
+        .find_map(|event| match event {
+            GeyserEvent::Transaction(tx) if tx.transaction.signature == signature => Some(tx),
+            _ => None,
+        })
+        .unwrap();
+    assert_eq!(tx.addresses, keys);
+    assert!(tx.owners.contains(&system_program::id().to_string()));
+    assert!(tx.owners.contains(&spl_token::id().to_string()));
+    let distinct: HashSet<&String> = tx.owners.iter().collect();
+    assert_eq!(distinct.len(), tx.owners.len());
+    let owner_of = |address: &Pubkey| {
+        tx.account_keys
+            .iter()
+            .find(|key| key.account == address.to_string())
+            .and_then(|key| key.owner.clone())
+    };
+    assert_eq!(owner_of(&source), Some(spl_token::id().to_string()));
+    assert_eq!(
+        owner_of(&payer.pubkey()),
+        Some(system_program::id().to_string())
+    );
+
+    let message = serde_json::to_value(GeyserEvent::Transaction(tx.clone())).unwrap();
+    assert_eq!(message["payload"]["addresses"], json!(keys));
+    assert_eq!(message["payload"]["owners"], json!(tx.owners));
+}
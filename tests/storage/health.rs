@@ -0,0 +1,20 @@
+use mockchain_engine::storage::health::{missing_tables, ComponentStatus, REQUIRED_TABLES};
+
+#[test]
+fn test_missing_tables() {
+    let mut existing: Vec<String> = REQUIRED_TABLES.iter().map(|t| t.to_string()).collect();
+    assert!(missing_tables(&existing).is_empty());
+
+    existing.retain(|table| table != "audit_logs" && table != "webhooks");
+    assert_eq!(missing_tables(&existing), vec!["webhooks", "audit_logs"]);
+}
+
+#[test]
+fn test_component_status_json() {
+    let ok = serde_json::to_value(ComponentStatus::new("redis", Ok(()))).unwrap();
+    assert_eq!(ok, serde_json::json!({ "name": "redis", "ok": true }));
+
+    let failed = ComponentStatus::new("postgres", Err("timed out".to_string()));
+    assert!(!failed.ok);
+    assert_eq!(failed.error.as_deref(), Some("timed out"));
+}
@@ -0,0 +1,59 @@
+use mockchain_engine::storage::{idempotency::IdempotencyRecord, memory::MemoryStorage, Storage};
+use uuid::Uuid;
+
+#[test]
+fn test_idempotency_key_replay() {
+    let storage = MemoryStorage::new();
+    let team = Uuid::new_v4();
+    assert_eq!(
+        storage
+            .reserve_idempotency_key(team, "ci-run-1", "{}")
+            .unwrap(),
+        None
+    );
+
+    // A retry while the first request is still creating sees it pending
+    let pending = storage
+        .reserve_idempotency_key(team, "ci-run-1", "{}")
+        .unwrap()
+        .unwrap();
+    assert_eq!(pending.blockchain, None);
+
+    let record = IdempotencyRecord {
+        fingerprint: "{}".to_string(),
+        blockchain: Some(Uuid::new_v4()),
+    };
+    storage
+        .complete_idempotency_key(team, "ci-run-1", &record)
+        .unwrap();
+    assert_eq!(
+        storage
+            .reserve_idempotency_key(team, "ci-run-1", "{}")
+            .unwrap(),
+        Some(record)
+    );
+
+    // Keys are per team
+    assert_eq!(
+        storage
+            .reserve_idempotency_key(Uuid::new_v4(), "ci-run-1", "{}")
+            .unwrap(),
+        None
+    );
+}
+
+#[test]
+fn test_idempotency_key_released() {
+    let storage = MemoryStorage::new();
+    let team = Uuid::new_v4();
+    storage
+        .reserve_idempotency_key(team, "ci-run-1", "{\"label\":\"a\"}")
+        .unwrap();
+    storage.release_idempotency_key(team, "ci-run-1").unwrap();
+    assert_eq!(
+        storage
+            .reserve_idempotency_key(team, "ci-run-1", "{\"label\":\"b\"}")
+            .unwrap(),
+        None
+    );
+}
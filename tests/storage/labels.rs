@@ -0,0 +1,58 @@
+use mockchain_engine::storage::{accounts::AccountListFilter, memory::MemoryStorage, Storage};
+use solana_sdk::{account::Account, pubkey::Pubkey, system_program};
+use uuid::Uuid;
+
+#[test]
+fn test_set_and_clear_account_labels() {
+    let storage = MemoryStorage::new();
+    let id = Uuid::new_v4();
+    let alice = Pubkey::new_unique();
+    let mint = Pubkey::new_unique();
+    let account = Account::new(1_000_000, 0, &system_program::id());
+    storage
+        .set_accounts(id, vec![(alice, account.clone()), (mint, account.clone())])
+        .unwrap();
+
+    assert!(storage
+        .set_account_label(id, &alice, Some("alice-wallet".to_string()))
+        .unwrap());
+    assert!(storage
+        .set_account_label(id, &mint, Some("usdc-mint".to_string()))
+        .unwrap());
+    assert!(!storage
+        .set_account_label(id, &Pubkey::new_unique(), Some("missing".to_string()))
+        .unwrap());
+
+    let filter = AccountListFilter {
+        owner: None,
+        executable: None,
+        label: Some("alice".to_string()),
+        limit: 10,
+        offset: 0,
+    };
+    let (accounts, total) = storage.list_accounts(id, &filter).unwrap();
+    assert_eq!(total, 1);
+    assert_eq!(accounts[0].address, alice.to_string());
+    assert_eq!(accounts[0].label.as_deref(), Some("alice-wallet"));
+    assert_eq!(
+        storage
+            .get_labeled_accounts(id, &["usdc-mint".to_string()])
+            .unwrap(),
+        vec![mint]
+    );
+
+    // Transactions rewriting the account keep its label
+    storage.set_accounts(id, vec![(alice, account)]).unwrap();
+    assert_eq!(
+        storage
+            .get_labeled_accounts(id, &["alice-wallet".to_string()])
+            .unwrap(),
+        vec![alice]
+    );
+
+    assert!(storage.set_account_label(id, &alice, None).unwrap());
+    assert!(storage
+        .get_labeled_accounts(id, &["alice-wallet".to_string()])
+        .unwrap()
+        .is_empty());
+}
@@ -1,2 +1,15 @@
+pub mod account_writes;
 pub mod accounts;
+pub mod events;
+pub mod geyser;
+pub mod health;
+pub mod idempotency;
+pub mod labels;
+pub mod numeric;
+pub mod pending;
+pub mod quotas;
+pub mod rpc;
+pub mod snapshot;
 pub mod teams;
+pub mod trace;
+pub mod transactions;
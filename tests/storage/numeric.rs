@@ -0,0 +1,83 @@
+use bigdecimal::BigDecimal;
+use mockchain_engine::storage::{
+    accounts::DbAccount,
+    numeric::{self, StorageError},
+};
+use solana_sdk::{account::Account, pubkey::Pubkey};
+use std::str::FromStr;
+use uuid::Uuid;
+
+#[test]
+fn test_to_u64() {
+    assert_eq!(numeric::to_u64("slot", &BigDecimal::from(42)), Ok(42));
+    assert_eq!(
+        numeric::to_u64("lamports", &BigDecimal::from(u64::MAX)),
+        Ok(u64::MAX)
+    );
+    // Postgres can hand back a scaled value for a whole number
+    assert_eq!(
+        numeric::to_u64("slot", &BigDecimal::from_str("100.00").unwrap()),
+        Ok(100)
+    );
+
+    for value in ["-1", "1.5", "18446744073709551616"] {
+        assert_eq!(
+            numeric::to_u64("lamports", &BigDecimal::from_str(value).unwrap()),
+            Err(StorageError::InvalidNumeric {
+                column: "lamports",
+                value: value.to_string(),
+            })
+        );
+    }
+
+    let err: String = numeric::to_u64("slot", &BigDecimal::from(-3))
+        .unwrap_err()
+        .into();
+    assert_eq!(err, "Invalid slot value -3");
+}
+
+#[test]
+fn test_saturating_u64() {
+    assert_eq!(
+        numeric::saturating_u64(&BigDecimal::from(100_000_000_000u64)),
+        100_000_000_000
+    );
+    assert_eq!(numeric::saturating_u64(&BigDecimal::from(-1)), 0);
+    assert_eq!(
+        numeric::saturating_u64(&BigDecimal::from_str("1e30").unwrap()),
+        u64::MAX
+    );
+    assert_eq!(
+        numeric::saturating_u64(&BigDecimal::from_str("7.9").unwrap()),
+        7
+    );
+}
+
+#[test]
+fn test_into_account() {
+    let address = Pubkey::new_unique();
+    let account = Account {
+        lamports: 1_000_000,
+        data: vec![1, 2, 3],
+        owner: Pubkey::new_unique(),
+        executable: false,
+        rent_epoch: u64::MAX,
+    };
+    let db_account = DbAccount::from_account(&address, &account, None, Uuid::new_v4());
+    assert_eq!(db_account.clone().into_account(), Ok(account.clone()));
+
+    // Out of range rent epochs are clamped rather than rejected
+    let mut oversized = db_account.clone();
+    oversized.rent_epoch = BigDecimal::from_str("1e30").unwrap();
+    assert_eq!(oversized.into_account().unwrap().rent_epoch, u64::MAX);
+
+    let mut negative = db_account;
+    negative.lamports = BigDecimal::from(-5);
+    assert!(matches!(
+        negative.into_account(),
+        Err(StorageError::InvalidNumeric {
+            column: "lamports",
+            ..
+        })
+    ));
+}
@@ -0,0 +1,20 @@
+use actix_web::rt::time;
+use mockchain_engine::storage::pending::PendingWrites;
+use std::time::Duration;
+
+#[actix_web::test]
+async fn test_pending_writes_wait() {
+    let pending = PendingWrites::default();
+    pending.wait().await;
+
+    pending.spawn(async {
+        time::sleep(Duration::from_millis(50)).await;
+    });
+    pending.spawn(async {});
+    assert!(!pending.is_empty());
+
+    time::timeout(Duration::from_secs(1), pending.wait())
+        .await
+        .unwrap();
+    assert!(pending.is_empty());
+}
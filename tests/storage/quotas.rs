@@ -0,0 +1,81 @@
+use mockchain_engine::storage::{
+    memory::MemoryStorage,
+    teams::{Team, TeamQuotas, DEFAULT_MAX_BLOCKCHAINS},
+    Storage,
+};
+use uuid::Uuid;
+
+fn team() -> Team {
+    Team {
+        id: Uuid::new_v4(),
+        created_at: chrono::Utc::now().naive_utc(),
+        name: "quota".to_string(),
+        default_expiry: None,
+        max_blockchains: Some(10),
+        max_accounts: None,
+        max_transactions_per_day: None,
+        auto_pause_hours: None,
+        rpc_write_guard: false,
+    }
+}
+
+#[test]
+fn test_set_team_quotas() {
+    let storage = MemoryStorage::new();
+    let team = team();
+    storage.create_team(team.clone());
+
+    let quotas = TeamQuotas {
+        max_blockchains: None,
+        max_accounts: Some(1000),
+        max_transactions_per_day: Some(50_000),
+    };
+    let updated = storage.set_team_quotas(team.id, &quotas).unwrap().unwrap();
+    assert_eq!(updated.quotas(), quotas);
+    assert_eq!(storage.get_team(team.id).unwrap().unwrap().quotas(), quotas);
+    assert!(storage
+        .set_team_quotas(Uuid::new_v4(), &quotas)
+        .unwrap()
+        .is_none());
+}
+
+#[test]
+fn test_blockchain_limit() {
+    let mut team = team();
+    assert_eq!(team.blockchain_limit(), Some(10));
+    team.max_blockchains = Some(3);
+    team.default_expiry = Some(3600);
+    assert_eq!(team.blockchain_limit(), Some(3));
+
+    // Teams without a quota keep the default cap, unless their blockchains expire
+    team.max_blockchains = None;
+    assert_eq!(team.blockchain_limit(), None);
+    team.default_expiry = None;
+    assert_eq!(team.blockchain_limit(), Some(DEFAULT_MAX_BLOCKCHAINS));
+}
+
+#[test]
+fn test_invalid_team_quotas() {
+    let quotas = TeamQuotas {
+        max_accounts: Some(-1),
+        ..Default::default()
+    };
+    assert_eq!(
+        quotas.validate(),
+        Err("max_accounts cannot be negative".to_string())
+    );
+    assert!(TeamQuotas::default().validate().is_ok());
+}
+
+#[test]
+fn test_take_daily_transaction() {
+    let storage = MemoryStorage::new();
+    let team_id = Uuid::new_v4();
+    assert!(storage.take_daily_transaction(team_id, 2).unwrap());
+    assert!(storage.take_daily_transaction(team_id, 2).unwrap());
+    assert!(!storage.take_daily_transaction(team_id, 2).unwrap());
+
+    // Counted per team
+    assert!(storage.take_daily_transaction(Uuid::new_v4(), 2).unwrap());
+    assert!(!storage.take_daily_transaction(team_id, 0).unwrap());
+}
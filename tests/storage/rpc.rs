@@ -0,0 +1,235 @@
+use actix_web::{rt, web, App, HttpResponse, HttpServer};
+use mockchain_engine::{
+    engine::{SvmEngine, SVM},
+    metrics::{jit_stats, render},
+    storage::{
+        memory::MemoryStorage,
+        rpc::{Rpc, MAX_ACCOUNTS_PER_REQUEST},
+        Storage,
+    },
+};
+use serde_json::{json, Value};
+use solana_sdk::{account::Account, pubkey::Pubkey, sysvar};
+use std::sync::{Arc, Mutex};
+use uuid::Uuid;
+
+/// Keys carrying their index, the upstream has the ones not divisible by 3.
+fn key(index: u64) -> Pubkey {
+    let mut bytes = [0u8; 32];
+    bytes[..8].copy_from_slice(&index.to_le_bytes());
+    Pubkey::new_from_array(bytes)
+}
+
+fn index(key: &str) -> u64 {
+    let bytes = key.parse::<Pubkey>().unwrap().to_bytes();
+    u64::from_le_bytes(bytes[..8].try_into().unwrap())
+}
+
+/// An upstream answering getMultipleAccounts with the key's index as lamports,
+/// recording how many keys each request had.
+async fn upstream() -> (String, Arc<Mutex<Vec<usize>>>, actix_web::dev::ServerHandle) {
+    let requests = Arc::new(Mutex::new(vec![]));
+    let recorded = requests.clone();
+    let server = HttpServer::new(move || {
+        let requests = requests.clone();
+        App::new().default_service(web::post().to(move |body: web::Json<Value>| {
+            let requests = requests.clone();
+            async move {
+                let keys = body["params"][0].as_array().unwrap().clone();
+                requests.lock().unwrap().push(keys.len());
+                let value: Vec<Value> = keys
+                    .iter()
+                    .map(|key| match index(key.as_str().unwrap()) {
+                        i if i % 3 == 0 => Value::Null,
+                        i => json!({
+                            "lamports": i,
+                            "data": ["", "base64"],
+                            "owner": Pubkey::default().to_string(),
+                            "executable": false,
+                            "rentEpoch": 0,
+                            "space": 0,
+                        }),
+                    })
+                    .collect();
+                HttpResponse::Ok().json(json!({
+                    "jsonrpc": "2.0",
+                    "id": body["id"],
+                    "result": { "context": { "slot": 1 }, "value": value },
+                }))
+            }
+        }))
+    })
+    .workers(1)
+    .disable_signals()
+    .bind(("127.0.0.1", 0))
+    .unwrap();
+    let addr = server.addrs()[0];
+    let server = server.run();
+    let handle = server.handle();
+    rt::spawn(server);
+    (format!("http://{}", addr), recorded, handle)
+}
+
+#[actix_web::test]
+async fn test_get_accounts_chunked_in_order() {
+    let (url, requests, handle) = upstream().await;
+    let keys: Vec<Pubkey> = (0..250).map(key).collect();
+    let accounts = Rpc::new(url).get_accounts(&keys).await.unwrap();
+
+    assert_eq!(accounts.len(), keys.len());
+    for (i, account) in accounts.iter().enumerate() {
+        match i % 3 {
+            0 => assert!(account.is_none()),
+            _ => assert_eq!(account.as_ref().unwrap().lamports, i as u64),
+        }
+    }
+    let mut sizes = requests.lock().unwrap().clone();
+    sizes.sort();
+    assert_eq!(
+        sizes,
+        vec![50, MAX_ACCOUNTS_PER_REQUEST, MAX_ACCOUNTS_PER_REQUEST]
+    );
+    handle.stop(true).await;
+}
+
+#[actix_web::test]
+async fn test_jit_fills_missing_accounts_in_place() {
+    let (url, requests, handle) = upstream().await;
+    let storage = MemoryStorage::new().with_rpc(&url);
+    let id = Uuid::new_v4();
+    // Held locally, so only the others go upstream
+    let local = key(1);
+    storage
+        .set_accounts(
+            id,
+            vec![(
+                local,
+                Account {
+                    lamports: 1_000_000,
+                    ..Account::default()
+                },
+            )],
+        )
+        .unwrap();
+
+    let keys: Vec<Pubkey> = (0..6).map(key).collect();
+    let addresses: Vec<&Pubkey> = keys.iter().collect();
+    let accounts = storage
+        .get_accounts_jit(id, &addresses, true)
+        .await
+        .unwrap();
+    let lamports: Vec<Option<u64>> = accounts
+        .iter()
+        .map(|account| account.as_ref().map(|account| account.lamports))
+        .collect();
+    assert_eq!(
+        lamports,
+        vec![None, Some(1_000_000), Some(2), None, Some(4), Some(5)]
+    );
+    assert_eq!(*requests.lock().unwrap(), vec![5]);
+
+    // Saved, so a second read doesn't go upstream
+    storage
+        .get_accounts_jit(id, &addresses[4..].to_vec(), true)
+        .await
+        .unwrap();
+    assert_eq!(*requests.lock().unwrap(), vec![5]);
+    handle.stop(true).await;
+}
+
+#[actix_web::test]
+async fn test_fetch_remote_accounts() {
+    let (url, requests, handle) = upstream().await;
+    let svm = SvmEngine::new(MemoryStorage::new().with_rpc(&url));
+
+    let accounts = svm.fetch_remote_accounts(&[key(1), key(2)]).await.unwrap();
+    let lamports: Vec<(Pubkey, u64)> = accounts
+        .iter()
+        .map(|(address, account)| (*address, account.lamports))
+        .collect();
+    assert_eq!(lamports, vec![(key(1), 1), (key(2), 2)]);
+    assert_eq!(*requests.lock().unwrap(), vec![2]);
+
+    let err = svm
+        .fetch_remote_accounts(&[key(1), key(3)])
+        .await
+        .unwrap_err();
+    assert_eq!(err, format!("Account {} not found", key(3)));
+    // Sysvars come from the engine, never upstream
+    assert!(svm
+        .fetch_remote_accounts(&[sysvar::clock::id()])
+        .await
+        .is_err());
+    assert_eq!(requests.lock().unwrap().len(), 2);
+    handle.stop(true).await;
+}
+
+#[actix_web::test]
+async fn test_jit_fetches_are_recorded() {
+    let (url, _, handle) = upstream().await;
+    let storage = MemoryStorage::new().with_rpc(&url);
+    let id = Uuid::new_v4();
+    storage
+        .set_accounts(id, vec![(key(1), Account::default())])
+        .unwrap();
+
+    let keys: Vec<Pubkey> = (0..6).map(key).collect();
+    let addresses: Vec<&Pubkey> = keys.iter().collect();
+    storage
+        .get_accounts_jit(id, &addresses, true)
+        .await
+        .unwrap();
+    let stats = jit_stats(id).unwrap();
+    assert_eq!((stats.hits, stats.misses, stats.found), (1, 5, 3));
+    assert_eq!(stats.upstream_latency.count(), 1);
+    let fetched: Vec<(String, bool)> = stats
+        .recent
+        .iter()
+        .map(|fetch| (fetch.pubkey.clone(), fetch.found))
+        .collect();
+    assert_eq!(
+        fetched,
+        vec![
+            (key(0).to_string(), false),
+            (key(2).to_string(), true),
+            (key(3).to_string(), false),
+            (key(4).to_string(), true),
+            (key(5).to_string(), true),
+        ]
+    );
+
+    // Stored accounts are hits, single reads go upstream too
+    storage
+        .get_account_jit(id, &key(2), true)
+        .await
+        .unwrap()
+        .unwrap();
+    storage
+        .get_account_jit(id, &key(7), true)
+        .await
+        .unwrap()
+        .unwrap();
+    let stats = jit_stats(id).unwrap();
+    assert_eq!((stats.hits, stats.misses, stats.found), (2, 6, 4));
+    assert_eq!(stats.recent.back().unwrap().pubkey, key(7).to_string());
+    assert!(render().contains(&format!(
+        "jit_account_misses_total{{blockchain=\"{}\"}} 6",
+        id
+    )));
+
+    // Reads of blockchains without JIT aren't counted
+    storage.get_account_jit(id, &key(8), false).await.unwrap();
+    assert_eq!(jit_stats(id).unwrap().misses, 6);
+    handle.stop(true).await;
+}
+
+#[actix_web::test]
+async fn test_jit_upstream_errors_are_recorded() {
+    // Nothing listens on the discard port
+    let storage = MemoryStorage::new().with_rpc("http://127.0.0.1:9");
+    let id = Uuid::new_v4();
+    assert!(storage.get_account_jit(id, &key(1), true).await.is_err());
+    let stats = jit_stats(id).unwrap();
+    assert_eq!((stats.errors, stats.misses), (1, 0));
+    assert!(stats.recent.is_empty());
+}
@@ -0,0 +1,168 @@
+use flate2::{write::GzEncoder, Compression};
+use mockchain_engine::{
+    engine::SVM,
+    storage::{
+        snapshot::{Snapshot, SnapshotWriter, MAX_SNAPSHOT_BYTES},
+        Storage,
+    },
+};
+use solana_sdk::{account::Account, pubkey::Pubkey};
+use std::io::Write;
+
+use crate::common::engine;
+
+fn accounts() -> Vec<(Pubkey, Account)> {
+    vec![
+        (
+            Pubkey::new_unique(),
+            Account {
+                lamports: 1_000_000,
+                data: vec![1, 2, 3, 4],
+                owner: Pubkey::new_unique(),
+                executable: false,
+                rent_epoch: u64::MAX,
+            },
+        ),
+        (
+            Pubkey::new_unique(),
+            Account {
+                lamports: 1,
+                data: vec![],
+                owner: solana_sdk::system_program::id(),
+                executable: true,
+                rent_epoch: 0,
+            },
+        ),
+    ]
+}
+
+#[test]
+fn test_snapshot_roundtrip() {
+    let accounts = accounts();
+    let snapshot = Snapshot::new(42, accounts.clone());
+
+    let mut archive = Vec::new();
+    snapshot.write_to(&mut archive).unwrap();
+    let restored = Snapshot::read_from(archive.as_slice(), MAX_SNAPSHOT_BYTES).unwrap();
+
+    assert_eq!(restored.slot, 42);
+    assert_eq!(restored.into_accounts().unwrap(), accounts);
+}
+
+#[test]
+fn test_snapshot_read_accounts() {
+    let accounts = accounts();
+    let mut archive = Vec::new();
+    Snapshot::new(42, accounts.clone())
+        .write_to(&mut archive)
+        .unwrap();
+
+    let mut read = Vec::new();
+    let slot = Snapshot::read_accounts(
+        archive.as_slice(),
+        MAX_SNAPSHOT_BYTES,
+        &mut |address, account| {
+            read.push((address, account));
+            Ok(())
+        },
+    )
+    .unwrap();
+    assert_eq!(slot, 42);
+    assert_eq!(read, accounts);
+
+    // Errors of the callback stop the read
+    let err = Snapshot::read_accounts(archive.as_slice(), MAX_SNAPSHOT_BYTES, &mut |_, _| {
+        Err("full".to_string())
+    })
+    .unwrap_err();
+    assert!(err.contains("full"), "{}", err);
+
+    let mut other_version = Vec::new();
+    let mut snapshot = Snapshot::new(42, accounts);
+    snapshot.version += 1;
+    snapshot.write_to(&mut other_version).unwrap();
+    let err = Snapshot::read_accounts(other_version.as_slice(), MAX_SNAPSHOT_BYTES, &mut |_, _| {
+        Ok(())
+    })
+    .unwrap_err();
+    assert!(err.contains("unsupported snapshot version"), "{}", err);
+    assert!(
+        Snapshot::read_accounts(&b"not a snapshot"[..], MAX_SNAPSHOT_BYTES, &mut |_, _| Ok(
+            ()
+        ))
+        .is_err()
+    );
+}
+
+#[test]
+fn test_snapshot_rejects_garbage() {
+    assert!(Snapshot::read_from(&b"not a snapshot"[..], MAX_SNAPSHOT_BYTES).is_err());
+}
+
+#[test]
+fn test_snapshot_inflation_limit() {
+    // A tiny archive of a huge document
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::best());
+    encoder.write_all(&vec![b' '; 1024 * 1024]).unwrap();
+    let archive = encoder.finish().unwrap();
+    assert!(archive.len() < 8 * 1024);
+
+    assert_eq!(
+        Snapshot::read_from(archive.as_slice(), 64 * 1024).unwrap_err(),
+        "Snapshot is too large"
+    );
+    assert_eq!(
+        Snapshot::read_accounts(archive.as_slice(), 64 * 1024, &mut |_, _| Ok(())).unwrap_err(),
+        "Snapshot is too large"
+    );
+
+    let mut archive = Vec::new();
+    Snapshot::new(42, accounts())
+        .write_to(&mut archive)
+        .unwrap();
+    assert!(Snapshot::read_from(archive.as_slice(), 64 * 1024).is_ok());
+}
+
+#[test]
+fn test_snapshot_writer_streams_accounts() {
+    let (svm, id) = engine();
+    let accounts = accounts();
+    svm.storage.set_accounts(id, accounts.clone()).unwrap();
+
+    let mut archive = Vec::new();
+    let mut writer = SnapshotWriter::new(&mut archive, 42).unwrap();
+    svm.storage
+        .stream_accounts(id, &mut |address, account| {
+            writer.write_account(&address, &account)
+        })
+        .unwrap();
+    writer.finish().unwrap();
+
+    let restored = Snapshot::read_from(archive.as_slice(), MAX_SNAPSHOT_BYTES).unwrap();
+    assert_eq!(restored.slot, 42);
+    let mut expected = svm.storage.snapshot_accounts(id).unwrap();
+    let mut restored = restored.into_accounts().unwrap();
+    expected.sort_by_key(|(address, _)| *address);
+    restored.sort_by_key(|(address, _)| *address);
+    assert_eq!(restored, expected);
+    for account in &accounts {
+        assert!(restored.contains(account));
+    }
+}
+
+#[test]
+fn test_failed_restore_deletes_blockchain() {
+    let (svm, id) = engine();
+    let team_id = svm.storage.get_blockchain(id).unwrap().team_id;
+    let before = svm.get_blockchains(team_id).unwrap().len();
+
+    let err = svm
+        .create_blockchain_from_accounts(team_id, None, None, 2, &mut |each| {
+            let (address, account) = accounts().remove(0);
+            each(address, account)?;
+            Err("Snapshot ended early".to_string())
+        })
+        .unwrap_err();
+    assert_eq!(err, "Snapshot ended early");
+    assert_eq!(svm.get_blockchains(team_id).unwrap().len(), before);
+}
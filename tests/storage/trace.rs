@@ -0,0 +1,58 @@
+use mockchain_engine::{
+    rpc::rpc::{handle_request, RpcMethod},
+    storage::trace::{self, Operation, OperationLog},
+};
+use serde_json::json;
+use std::time::Duration;
+
+use crate::{common::engine, rpc::request};
+
+#[actix_web::test]
+async fn test_traced_collects_operations() {
+    let ((), log) = trace::traced(async {
+        trace::record(Operation::Redis, 2, Duration::from_millis(3));
+        trace::record(Operation::Postgres, 1, Duration::from_millis(5));
+        trace::record(Operation::Upstream, 4, Duration::from_millis(7));
+    })
+    .await;
+    assert_eq!(log.redis_ops, 2);
+    assert_eq!(log.postgres_queries, 1);
+    assert_eq!(log.upstream_fetches, 4);
+    assert!((log.storage_time_ms - 15.0).abs() < 1e-6);
+    assert!(log.total_time_ms >= 0.0);
+
+    let message = serde_json::to_value(&log).unwrap();
+    assert_eq!(message["redisOps"], 2);
+    assert_eq!(message["postgresQueries"], 1);
+    assert_eq!(message["upstreamFetches"], 4);
+
+    // Outside a trace nothing is collected
+    trace::record(Operation::Redis, 1, Duration::from_millis(1));
+    let ((), log) = trace::traced(async {}).await;
+    assert_eq!(
+        log,
+        OperationLog {
+            total_time_ms: log.total_time_ms,
+            ..Default::default()
+        }
+    );
+}
+
+#[actix_web::test]
+async fn test_mirror_debug_in_response() {
+    let (svm, id) = engine();
+    let req = request(RpcMethod::GetSlot, json!([]));
+
+    let res = handle_request(id, req.clone(), &svm).await;
+    assert!(serde_json::to_value(&res)
+        .unwrap()
+        .get("mirrorDebug")
+        .is_none());
+
+    let (mut res, log) = trace::traced(handle_request(id, req, &svm)).await;
+    res.mirror_debug = Some(log);
+    let message = serde_json::to_value(&res).unwrap();
+    assert_eq!(message["mirrorDebug"]["redisOps"], 0);
+    assert_eq!(message["mirrorDebug"]["postgresQueries"], 0);
+    assert!(message["result"].is_u64());
+}
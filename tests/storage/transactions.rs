@@ -0,0 +1,124 @@
+use mockchain_engine::{
+    engine::SVM,
+    storage::{
+        transactions::{log_program_ids, TransactionFilter},
+        Storage,
+    },
+};
+use solana_sdk::{
+    compute_budget::ComputeBudgetInstruction, native_token::LAMPORTS_PER_SOL, pubkey::Pubkey,
+    signer::Signer, system_instruction, system_program, transaction::Transaction,
+    transaction::VersionedTransaction,
+};
+
+use crate::common::{engine, processed, transfer};
+
+#[actix_web::test]
+async fn test_search_by_fee_payer_and_compute_unit_price() {
+    let (svm, id) = engine();
+    let payer = svm.storage.get_blockchain(id).unwrap().airdrop_keypair;
+    let blockhash = svm.latest_blockhash(id).unwrap().blockhash;
+    let tx = Transaction::new_signed_with_payer(
+        &[
+            ComputeBudgetInstruction::set_compute_unit_price(5_000),
+            system_instruction::transfer(&payer.pubkey(), &Pubkey::new_unique(), LAMPORTS_PER_SOL),
+        ],
+        Some(&payer.pubkey()),
+        &[&payer],
+        blockhash,
+    );
+    let signature = svm
+        .send_transaction(id, VersionedTransaction::from(tx), false)
+        .unwrap();
+    assert!(processed(&svm, id, &signature).await);
+
+    let search = |filter: TransactionFilter| {
+        svm.storage
+            .search_transactions(
+                id,
+                &TransactionFilter {
+                    limit: 10,
+                    ..filter
+                },
+            )
+            .unwrap()
+    };
+    let (transactions, total) = search(TransactionFilter {
+        fee_payer: Some(payer.pubkey().to_string()),
+        is_vote: Some(false),
+        min_compute_unit_price: Some(5_000),
+        ..Default::default()
+    });
+    assert_eq!(total, 1);
+    let transaction = &transactions[0].transaction;
+    assert_eq!(transaction.signature, signature);
+    assert_eq!(transaction.fee_payer, Some(payer.pubkey().to_string()));
+    assert_eq!(transaction.compute_unit_price, Some(5_000.into()));
+    assert!(!transaction.is_vote);
+
+    let (_, total) = search(TransactionFilter {
+        min_compute_unit_price: Some(5_001),
+        ..Default::default()
+    });
+    assert_eq!(total, 0);
+    let (_, total) = search(TransactionFilter {
+        fee_payer: Some(Pubkey::new_unique().to_string()),
+        ..Default::default()
+    });
+    assert_eq!(total, 0);
+    let (_, total) = search(TransactionFilter {
+        is_vote: Some(true),
+        ..Default::default()
+    });
+    assert_eq!(total, 0);
+}
+
+#[test]
+fn test_log_program_ids() {
+    let outer = Pubkey::new_unique().to_string();
+    let inner = system_program::id().to_string();
+    let logs: Vec<String> = [
+        format!("Program {} invoke [1]", outer),
+        "Program log: Instruction: Deposit".to_string(),
+        format!("Program {} invoke [2]", inner),
+        format!("Program {} success", inner),
+        format!("Program {} consumed 2000 of 200000 compute units", outer),
+        format!("Program {} failed: custom program error: 0x1", outer),
+        "Log truncated".to_string(),
+    ]
+    .to_vec();
+    assert_eq!(
+        log_program_ids(&logs),
+        vec![
+            Some(outer.clone()),
+            Some(outer.clone()),
+            Some(inner.clone()),
+            Some(inner),
+            Some(outer.clone()),
+            Some(outer),
+            None,
+        ]
+    );
+}
+
+#[actix_web::test]
+async fn test_program_logs() {
+    let (svm, id) = engine();
+    let start = chrono::Utc::now().naive_utc();
+    let signature = svm.send_transaction(id, transfer(&svm, id), false).unwrap();
+    assert!(processed(&svm, id, &signature).await);
+    let end = chrono::Utc::now().naive_utc();
+
+    let logs = svm
+        .get_program_logs(id, &system_program::id(), start, end, 100)
+        .unwrap();
+    assert!(!logs.is_empty());
+    assert!(logs
+        .iter()
+        .all(|log| log.transaction_signature == signature));
+    assert!(logs[0].log.ends_with("invoke [1]"));
+    let logs = svm
+        .get_program_logs(id, &Pubkey::new_unique(), start, end, 100)
+        .unwrap();
+    assert!(logs.is_empty());
+}
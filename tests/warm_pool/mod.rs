@@ -0,0 +1,55 @@
+use mockchain_engine::{
+    engine::{SvmEngine, SVM},
+    storage::{memory::MemoryStorage, Storage},
+    warm_pool::WarmPool,
+};
+use solana_sdk::signer::Signer;
+use std::{sync::Arc, time::Duration};
+use uuid::Uuid;
+
+use crate::common::eventually;
+
+fn pooled(svm: &SvmEngine<MemoryStorage>, team_id: Uuid) -> i64 {
+    svm.storage
+        .count_pooled_blockchains()
+        .unwrap()
+        .into_iter()
+        .filter(|(team, config, _)| *team == team_id && config.is_none())
+        .map(|(_, _, count)| count)
+        .sum()
+}
+
+#[actix_web::test]
+async fn test_refill_after_claim() {
+    let svm = Arc::new(SvmEngine::new(MemoryStorage::new()));
+    let pool = WarmPool::start(svm.clone(), 2, Duration::from_secs(60));
+    let team_id = Uuid::new_v4();
+
+    pool.refill(team_id, None);
+    assert!(eventually(|| pooled(&svm, team_id) == 2).await);
+
+    let claimed = svm
+        .storage
+        .claim_pooled_blockchain(team_id, None, Some("claimed".to_string()), None)
+        .unwrap()
+        .unwrap();
+    assert!(!claimed.pooled);
+    assert_eq!(claimed.label, Some("claimed".to_string()));
+    // Provisioned with its accounts, ready to use
+    assert!(svm
+        .storage
+        .get_account(claimed.id, &claimed.airdrop_keypair.pubkey())
+        .unwrap()
+        .is_some());
+    assert_eq!(pooled(&svm, team_id), 1);
+
+    pool.refill(team_id, None);
+    assert!(eventually(|| pooled(&svm, team_id) == 2).await);
+
+    // Other teams' pools are their own
+    let other = svm
+        .storage
+        .claim_pooled_blockchain(Uuid::new_v4(), None, None, None)
+        .unwrap();
+    assert!(other.is_none());
+}
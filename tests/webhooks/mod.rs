@@ -0,0 +1,140 @@
+use mockchain_engine::{
+    storage::webhooks::DbWebhook,
+    webhooks::{client, is_public, sign, validate_url},
+};
+use std::{
+    collections::HashMap,
+    io::{Read, Write},
+    net::TcpListener,
+    thread,
+};
+use uuid::Uuid;
+
+#[test]
+fn test_sign() {
+    // RFC 4231 test case 2
+    assert_eq!(
+        sign("Jefe", b"what do ya want for nothing?"),
+        "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+    );
+}
+
+fn webhook(accounts: &[&str], labels: &[&str]) -> DbWebhook {
+    DbWebhook {
+        id: Uuid::new_v4(),
+        created_at: chrono::Utc::now().naive_utc(),
+        team_id: Uuid::new_v4(),
+        blockchain: Uuid::new_v4(),
+        url: "https://example.com/hook".to_string(),
+        secret: String::new(),
+        events: vec!["account".to_string()],
+        accounts: accounts.iter().map(|a| a.to_string()).collect(),
+        labels: labels.iter().map(|l| l.to_string()).collect(),
+    }
+}
+
+#[test]
+fn test_matches_labeled_accounts() {
+    let (alice, bob, carol) = ("alice".to_string(), "bob".to_string(), "carol".to_string());
+    let labeled = HashMap::from([
+        (alice.clone(), "alice-wallet".to_string()),
+        (bob.clone(), "bob-wallet".to_string()),
+    ]);
+
+    // Only addresses carrying the webhook's own labels match
+    let alice_webhook = webhook(&[], &["alice-wallet"]);
+    assert!(alice_webhook.matches_accounts(std::iter::once(&alice), &labeled));
+    assert!(!alice_webhook.matches_accounts(std::iter::once(&bob), &labeled));
+    let bob_webhook = webhook(&[], &["bob-wallet"]);
+    assert!(bob_webhook.matches_accounts(std::iter::once(&bob), &labeled));
+    assert!(!bob_webhook.matches_accounts(std::iter::once(&alice), &labeled));
+
+    // Filtering on accounts alone, labels of other webhooks don't match
+    let carol_webhook = webhook(&["carol"], &[]);
+    assert!(carol_webhook.matches_accounts(std::iter::once(&carol), &labeled));
+    assert!(!carol_webhook.matches_accounts([&alice, &bob].into_iter(), &labeled));
+
+    let unfiltered = webhook(&[], &[]);
+    assert!(unfiltered.matches_accounts(std::iter::once(&bob), &HashMap::new()));
+}
+
+#[test]
+fn test_is_public() {
+    for ip in ["1.1.1.1", "8.8.8.8", "2606:4700:4700::1111"] {
+        assert!(is_public(ip.parse().unwrap()), "{}", ip);
+    }
+    for ip in [
+        "127.0.0.1",
+        "10.1.2.3",
+        "172.16.0.1",
+        "192.168.1.1",
+        "169.254.169.254",
+        "100.64.0.1",
+        "0.0.0.0",
+        "::1",
+        "::",
+        "fd00::1",
+        "fe80::1",
+        "::ffff:127.0.0.1",
+    ] {
+        assert!(!is_public(ip.parse().unwrap()), "{}", ip);
+    }
+}
+
+#[actix_web::test]
+async fn test_validate_url() {
+    assert!(validate_url("https://1.1.1.1/hook").await.is_ok());
+
+    assert!(validate_url("not a url").await.is_err());
+    assert_eq!(
+        validate_url("ftp://1.1.1.1/hook").await.unwrap_err(),
+        "Webhook url must use http or https"
+    );
+    // Checked after resolving the host
+    for url in [
+        "http://localhost:8080/hook",
+        "http://127.0.0.1/hook",
+        "http://[::1]/hook",
+        "http://169.254.169.254/latest/meta-data",
+        "https://192.168.0.10/hook",
+    ] {
+        assert_eq!(
+            validate_url(url).await.unwrap_err(),
+            "Webhook url must point to a public address",
+            "{}",
+            url
+        );
+    }
+}
+
+#[actix_web::test]
+async fn test_client() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let server = thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut request = [0; 1024];
+        let _ = stream.read(&mut request).unwrap();
+        stream
+            .write_all(
+                b"HTTP/1.1 302 Found\r\nLocation: http://localhost/\r\nContent-Length: 0\r\n\r\n",
+            )
+            .unwrap();
+    });
+    let client = client();
+
+    // Hosts resolving to internal addresses aren't reached, even once registered
+    assert!(client
+        .get(format!("http://localhost:{}/hook", port))
+        .send()
+        .await
+        .is_err());
+    // Redirects are handed back instead of followed
+    let response = client
+        .get(format!("http://127.0.0.1:{}/hook", port))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 302);
+    server.join().unwrap();
+}